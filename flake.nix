@@ -46,6 +46,15 @@
       url = "github:nix-community/disko";
       inputs.nixpkgs.follows = "nixpkgs";
     };
+
+    # Lanzaboote for Secure Boot (hosts opt in via modules/boot/secure-boot.nix)
+    lanzaboote = {
+      url = "github:nix-community/lanzaboote/v0.4.2";
+      inputs.nixpkgs.follows = "nixpkgs";
+    };
+
+    # Impermanence for hosts with an ephemeral root (modules/impermanence.nix)
+    impermanence.url = "github:nix-community/impermanence";
   };
 
   outputs = { self, nixpkgs, home-manager, noctalia, dots-hyprland, rounded-polygon-qmljs, disko, quickshell, ... }@inputs:
@@ -81,14 +90,28 @@
       # sharedModules removed - external modules now imported conditionally in home.nix
     };
 
+    # nixpkgs and Home Manager per update channel. A host picks one with
+    # `channel = "stable";` (edited with `forge channels`, which also adds the
+    # nixpkgs-stable and home-manager-stable inputs while a host uses them)
+    channels = {
+      unstable = { inherit nixpkgs home-manager; };
+    } // nixpkgs.lib.optionalAttrs (inputs ? nixpkgs-stable) {
+      stable = {
+        nixpkgs = inputs.nixpkgs-stable;
+        home-manager = inputs.home-manager-stable;
+      };
+    };
+
     # Helper to create NixOS configurations with shell specialisations
-    mkNixosSystem = { hostname, username ? "john", extraModules ? [] }:
-      nixpkgs.lib.nixosSystem {
+    mkNixosSystem = { hostname, username ? "john", channel ? "unstable", extraModules ? [] }:
+      channels.${channel}.nixpkgs.lib.nixosSystem {
         inherit system;
         specialArgs = { inherit inputs plymouth-cybex forge username; };
         modules = [
           # Apply overlay for patched xdg-desktop-portal-gtk
           { nixpkgs.overlays = [ gtkPortalOverlay ]; }
+          # Commit the system was built from (nixos-version --json, forge report-state)
+          { system.configurationRevision = self.rev or self.dirtyRev or null; }
           # Disko for declarative disk partitioning
           disko.nixosModules.disko
           ./modules/disko/${hostname}.nix
@@ -99,7 +122,7 @@
           ./modules/desktop-environments.nix
 
           # Home Manager
-          home-manager.nixosModules.home-manager
+          channels.${channel}.home-manager.nixosModules.home-manager
           (mkHomeManagerConfig { inherit hostname username; })
 
           # Shell specialisations (boot menu entries)
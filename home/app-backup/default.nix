@@ -1161,15 +1161,18 @@ let
 
       # Parse arguments
       PUSH=false
+      PASSPHRASE_FILE=""
       while [[ $# -gt 0 ]]; do
         case $1 in
           --push|-p) PUSH=true; shift ;;
+          --passphrase-file) PASSPHRASE_FILE="$2"; shift 2 ;;
           --help|-h)
             echo "Usage: keys-backup [OPTIONS]"
             echo ""
             echo "Options:"
-            echo "  --push, -p     Push encrypted archive to GitHub after backup"
-            echo "  --help, -h     Show this help"
+            echo "  --push, -p              Push encrypted archive to GitHub after backup"
+            echo "  --passphrase-file FILE  Read the passphrase from FILE instead of prompting"
+            echo "  --help, -h              Show this help"
             exit 0
             ;;
           *) log_error "Unknown option: $1" ;;
@@ -1217,6 +1220,20 @@ let
         log_error "No keys found to backup. Run 'keys-setup' first."
       fi
 
+      # Add ~/.ssh/config and known_hosts if enabled (includeSshConfig)
+      if [[ "''${KEYS_INCLUDE_SSH_CONFIG:-false}" == "true" ]]; then
+        for name in config known_hosts; do
+          SSH_FILE="$HOME/.ssh/$name"
+          if [[ -L "$SSH_FILE" ]]; then
+            # Managed by Home Manager (a store symlink); the config recreates it
+            log_info "Skipping ~/.ssh/$name (symlink, managed by Nix)"
+          elif [[ -f "$SSH_FILE" ]]; then
+            cp "$SSH_FILE" "$STAGING_DIR/ssh-$name"
+            log_info "Added ~/.ssh/$name to backup"
+          fi
+        done
+      fi
+
       log_info "Creating keys archive..."
       ARCHIVE="$TEMP_DIR/keys.tar.gz"
       tar --create --gzip --file="$ARCHIVE" \
@@ -1227,7 +1244,9 @@ let
 
       # Get passphrase for encryption
       PASSPHRASE=""
-      if [[ -n "''${KEYS_PASSPHRASE_1PASSWORD:-}" ]]; then
+      if [[ -n "$PASSPHRASE_FILE" ]]; then
+        PASSPHRASE=$(<"$PASSPHRASE_FILE")
+      elif [[ -n "''${KEYS_PASSPHRASE_1PASSWORD:-}" ]]; then
         log_info "Retrieving keys passphrase from 1Password..."
         PASSPHRASE=$(op read "$KEYS_PASSPHRASE_1PASSWORD")
       else
@@ -1298,7 +1317,7 @@ let
   # Keys restore script - restores keys from passphrase-encrypted archive
   keys-restore = pkgs.writeShellApplication {
     name = "keys-restore";
-    runtimeInputs = with pkgs; [ coreutils gnutar gzip age git git-lfs openssh ];
+    runtimeInputs = with pkgs; [ coreutils gnutar gzip age git git-lfs openssh gnugrep gnused gawk ];
     text = ''
       set -euo pipefail
 
@@ -1325,17 +1344,20 @@ let
       # Parse arguments
       PULL=false
       FORCE=false
+      PASSPHRASE_FILE=""
       while [[ $# -gt 0 ]]; do
         case $1 in
           --pull|-p) PULL=true; shift ;;
           --force|-f) FORCE=true; shift ;;
+          --passphrase-file) PASSPHRASE_FILE="$2"; shift 2 ;;
           --help|-h)
             echo "Usage: keys-restore [OPTIONS]"
             echo ""
             echo "Options:"
-            echo "  --pull, -p     Pull latest from GitHub before restoring"
-            echo "  --force, -f    Force overwrite of existing keys"
-            echo "  --help, -h     Show this help"
+            echo "  --pull, -p              Pull latest from GitHub before restoring"
+            echo "  --force, -f             Force overwrite of existing keys"
+            echo "  --passphrase-file FILE  Read the passphrase from FILE instead of prompting"
+            echo "  --help, -h              Show this help"
             exit 0
             ;;
           *) log_error "Unknown option: $1" ;;
@@ -1368,32 +1390,55 @@ let
         log_error "Keys backup not found: $AGE_FILE"
       fi
 
-      # Get passphrase for decryption
-      PASSPHRASE=""
-      if [[ -n "''${KEYS_PASSPHRASE_1PASSWORD:-}" ]]; then
-        log_info "Retrieving keys passphrase from 1Password..."
-        PASSPHRASE=$(op read "$KEYS_PASSPHRASE_1PASSWORD")
-      else
-        read -rsp "Enter passphrase for keys backup: " PASSPHRASE
-        echo ""
-      fi
-
       # Create secure temp directory
       TEMP_DIR=$(mktemp -d)
       chmod 700 "$TEMP_DIR"
       trap 'rm -rf "$TEMP_DIR"' EXIT INT TERM
 
-      # Decrypt
-      log_info "Decrypting keys archive..."
+      # Decrypt, asking again (up to MAX_ATTEMPTS times) when the passphrase
+      # was typed here. A wrong passphrase exits with WRONG_PASSPHRASE_EXIT
+      # and a single error line, which forge uses to ask for it again.
+      MAX_ATTEMPTS=3
+      WRONG_PASSPHRASE_EXIT=3
       TAR_FILE="$TEMP_DIR/keys.tar.gz"
-      echo "$PASSPHRASE" | age --decrypt --passphrase --output "$TAR_FILE" "$AGE_FILE" || {
-        log_error "Failed to decrypt keys backup (wrong passphrase?)"
-      }
+      AGE_ERR="$TEMP_DIR/age.err"
+      ARCHIVE_SIZE=$(du -h "$AGE_FILE" | cut -f1)
+      ATTEMPT=1
+      while true; do
+        PASSPHRASE=""
+        PROMPTED=false
+        if [[ -n "$PASSPHRASE_FILE" ]]; then
+          PASSPHRASE=$(<"$PASSPHRASE_FILE")
+        elif [[ -n "''${KEYS_PASSPHRASE_1PASSWORD:-}" ]]; then
+          log_info "Retrieving keys passphrase from 1Password..."
+          PASSPHRASE=$(op read "$KEYS_PASSPHRASE_1PASSWORD")
+        else
+          read -rsp "Enter passphrase for keys backup: " PASSPHRASE
+          echo ""
+          PROMPTED=true
+        fi
 
+        log_info "[1/3] Decrypting keys archive ($ARCHIVE_SIZE)..."
+        if echo "$PASSPHRASE" | age --decrypt --passphrase --output "$TAR_FILE" "$AGE_FILE" 2>"$AGE_ERR"; then
+          break
+        fi
+        if ! grep -qiE "incorrect passphrase|no identity matched" "$AGE_ERR"; then
+          log_error "Failed to decrypt keys backup: $(head -n1 "$AGE_ERR")"
+        fi
+        if [[ "$PROMPTED" != "true" || $ATTEMPT -ge $MAX_ATTEMPTS ]]; then
+          echo -e "''${RED}[ERROR]''${NC} Wrong passphrase for keys archive"
+          exit "$WRONG_PASSPHRASE_EXIT"
+        fi
+        log_warn "Wrong passphrase, $((MAX_ATTEMPTS - ATTEMPT)) attempt(s) left"
+        ATTEMPT=$((ATTEMPT + 1))
+      done
+
+      log_info "[2/3] Extracting keys archive..."
       EXTRACT_DIR="$TEMP_DIR/keys-extract"
       mkdir -p "$EXTRACT_DIR"
-      tar --extract --gzip --file="$TAR_FILE" --directory="$EXTRACT_DIR"
+      tar --extract --gzip --verbose --file="$TAR_FILE" --directory="$EXTRACT_DIR" | sed 's/^/  /'
 
+      log_info "[3/3] Restoring keys..."
       COUNT=0
 
       # Restore Age key
@@ -1434,6 +1479,33 @@ let
         fi
       fi
 
+      # Restore ~/.ssh/config and known_hosts (present when includeSshConfig was on)
+      for name in config known_hosts; do
+        [[ -f "$EXTRACT_DIR/ssh-$name" ]] || continue
+        SSH_FILE="$HOME/.ssh/$name"
+        if [[ -L "$SSH_FILE" ]]; then
+          log_warn "~/.ssh/$name is managed by Nix (symlink) - skipping"
+        elif [[ "$name" == "known_hosts" && -f "$SSH_FILE" && "$FORCE" != "true" ]]; then
+          # Often already started by the git pull above; keep its entries too
+          awk '!seen[$0]++' "$SSH_FILE" "$EXTRACT_DIR/ssh-$name" > "$TEMP_DIR/known_hosts"
+          cat "$TEMP_DIR/known_hosts" > "$SSH_FILE"
+          log_success "Merged backed up entries into ~/.ssh/known_hosts"
+        elif [[ -f "$SSH_FILE" && "$FORCE" != "true" ]]; then
+          log_warn "~/.ssh/$name already exists - skipping (use --force to overwrite)"
+        else
+          mkdir -p "$HOME/.ssh"
+          chmod 700 "$HOME/.ssh"
+          cp "$EXTRACT_DIR/ssh-$name" "$SSH_FILE"
+          # ssh refuses a config others can write; known_hosts is public
+          if [[ "$name" == "config" ]]; then
+            chmod 600 "$SSH_FILE"
+          else
+            chmod 644 "$SSH_FILE"
+          fi
+          log_success "Restored ~/.ssh/$name"
+        fi
+      done
+
       # Cleanup
       shred -u "$TAR_FILE" 2>/dev/null || rm -f "$TAR_FILE"
       rm -rf "$EXTRACT_DIR"
@@ -1450,7 +1522,7 @@ let
   # Keys status script - shows status of local keys
   keys-status = pkgs.writeShellApplication {
     name = "keys-status";
-    runtimeInputs = with pkgs; [ coreutils ];
+    runtimeInputs = with pkgs; [ coreutils gnugrep gnused libfido2 openssh ];
     text = ''
       set -euo pipefail
 
@@ -1519,6 +1591,115 @@ let
         echo -e "  1Password: ''${YELLOW}✗''${NC} Not configured"
       fi
 
+      if [[ "''${KEYS_INCLUDE_SSH_CONFIG:-false}" == "true" ]]; then
+        echo -e "  Backup includes: ~/.ssh/config, ~/.ssh/known_hosts"
+      fi
+
+      echo ""
+
+      # Hardware-backed (FIDO) SSH keys: sk-ssh-ed25519 / sk-ecdsa-sha2-nistp256
+      # Flags byte of an sk private key file; "encrypted" if it has a passphrase
+      sk_flags() {
+        local -a b
+        local pos=15
+        mapfile -t b < <(sed '1d;$d' "$1" | base64 -d 2>/dev/null \
+          | od -An -v -tx1 | tr -s ' ' '\n' | sed '/^$/d')
+        (( ''${#b[@]} > pos )) || return 0
+        u32() { echo $((16#''${b[$1]}''${b[$1+1]}''${b[$1+2]}''${b[$1+3]})); }
+        skip() { pos=$((pos + 4 + $(u32 "$pos"))); }
+        # "openssh-key-v1\0", cipher name ("none" unless encrypted), kdf, kdf options
+        if [[ "''${b[*]:pos+4:4}" != "6e 6f 6e 65" ]]; then
+          echo "encrypted"
+          return 0
+        fi
+        skip; skip; skip
+        # One public key, the private section's length, and its two check words
+        pos=$((pos + 4)); skip; pos=$((pos + 12))
+        # Key type, then its public part: "sk-s..." (ed25519) or "sk-e..." (ecdsa)
+        local kind="''${b[*]:pos+4:4}"
+        skip
+        if [[ "$kind" == "73 6b 2d 73" ]]; then skip; else skip; skip; fi
+        # Application ("ssh:"), then the flags
+        skip
+        (( pos < ''${#b[@]} )) && echo $((16#''${b[pos]}))
+        return 0
+      }
+
+      echo -e "''${BLUE}Hardware Keys:''${NC}"
+      TOKENS=$(fido2-token -L 2>/dev/null || true)
+      SK_FOUND=false
+      for pub in "$HOME"/.ssh/*.pub; do
+        [[ -f "$pub" ]] || continue
+        case "$(cut -d' ' -f1 "$pub")" in
+          sk-ssh-ed25519@openssh.com) KIND="ed25519-sk" ;;
+          sk-ecdsa-sha2-nistp256@openssh.com) KIND="ecdsa-sk" ;;
+          *) continue ;;
+        esac
+        SK_FOUND=true
+        KEY="''${pub%.pub}"
+        if [[ ! -f "$KEY" ]]; then
+          echo -e "  $KIND: ''${YELLOW}✗''${NC} $pub (no key file)"
+          continue
+        fi
+        FLAGS=$(sk_flags "$KEY")
+        case "$FLAGS" in
+          "") DETAIL="" ;;
+          encrypted) DETAIL=", resident unknown (key file has a passphrase)" ;;
+          *)
+            if (( FLAGS & 0x20 )); then DETAIL=", resident"; else DETAIL=", not resident"; fi
+            if (( FLAGS & 0x04 )); then DETAIL="$DETAIL, PIN required"; fi
+            ;;
+        esac
+        echo -e "  $KIND: ''${GREEN}✓''${NC} $KEY$DETAIL"
+      done
+      if [[ "$SK_FOUND" != "true" ]]; then
+        echo -e "  None"
+      elif [[ -n "$TOKENS" ]]; then
+        echo -e "  Token: ''${GREEN}✓''${NC} $(head -n1 <<<"$TOKENS")"
+      else
+        log_warn "No security key connected; the keys above can't sign until it is plugged in"
+        if compgen -G "$HOME/.ssh/*_rk*" >/dev/null; then
+          echo "  Resident keys can be fetched again from the token with: ssh-keygen -K"
+        fi
+      fi
+
+      echo ""
+
+      # GitHub accounts the keys are registered to, from `ssh -T` probes
+      echo -e "''${BLUE}GitHub Accounts:''${NC}"
+      PROBED=false
+      for pub in "$HOME"/.ssh/*.pub; do
+        KEY="''${pub%.pub}"
+        [[ -f "$pub" && -f "$KEY" ]] || continue
+        case "$(cut -d' ' -f1 "$pub")" in
+          sk-*)
+            if [[ -z "$TOKENS" ]]; then
+              echo -e "  $(basename "$KEY"): ''${YELLOW}-''${NC} skipped (security key not connected)"
+              continue
+            fi
+            log_info "Touch your security key to probe $(basename "$KEY")"
+            ;;
+        esac
+        PROBED=true
+        # Only this key (no agent, no ~/.ssh/config identities); GitHub answers
+        # "Hi <user>! You've successfully authenticated" and exits 1
+        PROBE=$(timeout 30 ssh -T -F /dev/null -i "$KEY" \
+          -o IdentitiesOnly=yes -o IdentityAgent=none -o BatchMode=yes \
+          -o ConnectTimeout=5 -o StrictHostKeyChecking=accept-new \
+          git@github.com 2>&1 || true)
+        ACCOUNT=$(sed -n 's/^Hi \([^!]*\)!.*/\1/p' <<<"$PROBE" | head -n1)
+        if [[ -n "$ACCOUNT" ]]; then
+          echo -e "  $(basename "$KEY"): ''${GREEN}✓''${NC} $ACCOUNT"
+        elif grep -q "Permission denied" <<<"$PROBE"; then
+          echo -e "  $(basename "$KEY"): ''${YELLOW}✗''${NC} Not registered"
+        else
+          echo -e "  $(basename "$KEY"): ''${YELLOW}?''${NC} Could not reach github.com"
+        fi
+      done
+      if [[ "$PROBED" != "true" ]]; then
+        echo -e "  No keys to probe"
+      fi
+
       echo ""
 
       # Check backup
@@ -1536,6 +1717,403 @@ let
     '';
   };
 
+  # Register the SSH key with GitHub, then check the repos can be reached with it
+  keys-github = pkgs.writeShellApplication {
+    name = "keys-github";
+    runtimeInputs = with pkgs; [ coreutils curl gh git gnused jq openssh ];
+    text = ''
+      set -euo pipefail
+
+      # Colors
+      RED='\033[0;31m'
+      GREEN='\033[0;32m'
+      YELLOW='\033[1;33m'
+      BLUE='\033[0;34m'
+      NC='\033[0m'
+
+      log_info() { echo -e "''${BLUE}[INFO]''${NC} $1"; }
+      log_success() { echo -e "''${GREEN}[SUCCESS]''${NC} $1"; }
+      log_warn() { echo -e "''${YELLOW}[WARN]''${NC} $1"; }
+      log_error() { echo -e "''${RED}[ERROR]''${NC} $1"; exit 1; }
+
+      usage() {
+        echo "Usage: keys-github [--deploy] [--title TITLE] [--revoke]"
+        echo ""
+        echo "Register the SSH public key with GitHub and check the private repos"
+        echo "can be reached with it."
+        echo ""
+        echo "  --deploy       Add it as a deploy key (with write access) to the"
+        echo "                 app-backup repo only, instead of to your account"
+        echo "  --title TITLE  Key title on GitHub (default: '<hostname> (forge)')"
+        echo "  --revoke       Delete the keys titled TITLE from your account and the"
+        echo "                 app-backup repo's deploy keys instead (forge host archive)"
+        echo ""
+        echo "Signs in through the gh CLI (gh auth login), or GITHUB_TOKEN/GH_TOKEN"
+        echo "with the admin:public_key scope (repo admin for --deploy)."
+      }
+
+      DEPLOY=false
+      REVOKE=false
+      TITLE="$(uname -n) (forge)"
+      while [[ $# -gt 0 ]]; do
+        case "$1" in
+          --deploy) DEPLOY=true; shift ;;
+          --revoke) REVOKE=true; shift ;;
+          --title) TITLE="''${2:?--title needs a value}"; shift 2 ;;
+          -h|--help) usage; exit 0 ;;
+          *) usage; exit 1 ;;
+        esac
+      done
+
+      # Load configuration
+      CONFIG_FILE="$HOME/.config/app-backup/config"
+      if [[ ! -f "$CONFIG_FILE" ]]; then
+        log_error "Config file not found. Run: nixos-rebuild switch"
+      fi
+      # shellcheck source=/dev/null
+      source "$CONFIG_FILE"
+
+      # Revoking goes by title, the key itself may be on a machine that is gone
+      if [[ "$REVOKE" != "true" ]]; then
+        if [[ -z "''${SSH_KEY_PATH:-}" ]]; then
+          log_error "SSH_KEY_PATH not configured (programs.app-backup.sshKeyPath)"
+        fi
+        KEY="''${SSH_KEY_PATH/#\~/$HOME}"
+        PUB="$KEY.pub"
+        if [[ ! -f "$PUB" ]]; then
+          [[ -f "$KEY" ]] || log_error "No SSH key at $KEY. Run: forge keys setup"
+          ssh-keygen -y -f "$KEY" > "$PUB"
+        fi
+        # Type and key only; GitHub drops the comment
+        PUBKEY=$(cut -d' ' -f1,2 "$PUB")
+      fi
+
+      # owner/name of a GitHub remote (git@github.com:owner/name.git, https://...)
+      repo_slug() {
+        sed -E -n 's#^(git@github\.com:|ssh://git@github\.com/|https://github\.com/)([^/]+/[^/]+)$#\2#p' \
+          <<<"$1" | sed 's/\.git$//'
+      }
+
+      # GitHub access: a token from the environment, or the gh CLI's
+      TOKEN="''${GITHUB_TOKEN:-''${GH_TOKEN:-}}"
+      if [[ -z "$TOKEN" ]] && gh auth status &>/dev/null; then
+        TOKEN=$(gh auth token)
+      fi
+      if [[ -z "$TOKEN" ]]; then
+        log_error "Not signed in to GitHub. Run 'gh auth login' or set GITHUB_TOKEN."
+      fi
+
+      RESPONSE=$(mktemp)
+      trap 'rm -f "$RESPONSE"' EXIT
+
+      # api METHOD PATH [JSON]: the body goes to $RESPONSE, the HTTP status to stdout
+      api() {
+        local args=(-sS -o "$RESPONSE" -w '%{http_code}' -X "$1"
+          -H "Authorization: Bearer $TOKEN"
+          -H "Accept: application/vnd.github+json"
+          "https://api.github.com$2")
+        if [[ $# -gt 2 ]]; then
+          args+=(--data "$3")
+        fi
+        # -w still prints 000 when curl can't connect
+        curl "''${args[@]}" || true
+      }
+
+      BACKUP_SLUG=$(repo_slug "''${APP_BACKUP_REPO:-}")
+      if [[ "$REVOKE" == "true" ]]; then
+        ENDPOINTS=("/user/keys")
+        [[ -n "$BACKUP_SLUG" ]] && ENDPOINTS+=("/repos/$BACKUP_SLUG/keys")
+        REVOKED=0
+        FAILED=0
+        for endpoint in "''${ENDPOINTS[@]}"; do
+          STATUS=$(api GET "$endpoint?per_page=100")
+          if [[ "$STATUS" != "200" ]]; then
+            log_warn "Could not list $endpoint ($STATUS); check the token's scopes"
+            FAILED=1
+            continue
+          fi
+          for id in $(jq -r --arg t "$TITLE" '.[] | select(.title == $t) | .id' "$RESPONSE"); do
+            STATUS=$(api DELETE "$endpoint/$id")
+            if [[ "$STATUS" == "204" ]]; then
+              log_success "Revoked \"$TITLE\" ($endpoint/$id)"
+              REVOKED=$((REVOKED + 1))
+            else
+              log_warn "GitHub refused to delete $endpoint/$id ($STATUS)"
+              FAILED=1
+            fi
+          done
+        done
+        if [[ $FAILED -ne 0 ]]; then
+          log_error "Some keys titled \"$TITLE\" may still be registered"
+        fi
+        if [[ $REVOKED -eq 0 ]]; then
+          log_info "No GitHub key titled \"$TITLE\""
+        fi
+        exit 0
+      fi
+
+      if [[ "$DEPLOY" == "true" ]]; then
+        [[ -n "$BACKUP_SLUG" ]] || log_error "APP_BACKUP_REPO is not a GitHub repo: ''${APP_BACKUP_REPO:-}"
+        ENDPOINT="/repos/$BACKUP_SLUG/keys"
+        KIND="deploy key of $BACKUP_SLUG"
+        BODY=$(jq -nc --arg t "$TITLE" --arg k "$PUBKEY" '{title: $t, key: $k, read_only: false}')
+      else
+        ENDPOINT="/user/keys"
+        KIND="key of your GitHub account"
+        BODY=$(jq -nc --arg t "$TITLE" --arg k "$PUBKEY" '{title: $t, key: $k}')
+      fi
+
+      log_info "Registering $PUB as $KIND..."
+      STATUS=$(api GET "$ENDPOINT")
+      if [[ "$STATUS" == "200" ]] && jq -e --arg k "$PUBKEY" \
+        'any(.[]; (.key | split(" ")[0:2] | join(" ")) == $k)' "$RESPONSE" >/dev/null; then
+        log_success "Already registered"
+      else
+        STATUS=$(api POST "$ENDPOINT" "$BODY")
+        case "$STATUS" in
+          201) log_success "Registered as \"$TITLE\"" ;;
+          # e.g. "key is already in use" (on another account or repo)
+          422) log_warn "GitHub refused the key: $(jq -r '.errors[0].message // .message' "$RESPONSE")" ;;
+          401|403|404)
+            log_error "GitHub denied the request ($STATUS); check the token's scopes" ;;
+          *) log_error "GitHub API request failed ($STATUS): $(jq -r '.message // empty' "$RESPONSE" 2>/dev/null)" ;;
+        esac
+      fi
+
+      echo ""
+      log_info "Checking access with $KEY..."
+      SSH_OPTS=(-i "$KEY" -o IdentitiesOnly=yes -o BatchMode=yes -o ConnectTimeout=10
+        -o StrictHostKeyChecking=accept-new)
+      SSH_CMD="ssh $(printf '%q ' "''${SSH_OPTS[@]}")"
+      if [[ "$DEPLOY" != "true" ]]; then
+        ACCOUNT=$(ssh "''${SSH_OPTS[@]}" -T git@github.com 2>&1 \
+          | sed -n 's/^Hi \([^!]*\)!.*/\1/p' || true)
+        if [[ -n "$ACCOUNT" ]]; then
+          echo -e "  GitHub account: ''${GREEN}✓''${NC} $ACCOUNT"
+        fi
+      fi
+      REPOS=()
+      [[ -n "$BACKUP_SLUG" ]] && REPOS+=("git@github.com:$BACKUP_SLUG.git")
+      # A deploy key only opens the app-backup repo
+      CONFIG_SLUG=$(repo_slug "$(git -C "$HOME/nixos-config" remote get-url origin 2>/dev/null || true)")
+      if [[ "$DEPLOY" != "true" && -n "$CONFIG_SLUG" ]]; then
+        REPOS+=("git@github.com:$CONFIG_SLUG.git")
+      fi
+      FAILED=0
+      for repo in "''${REPOS[@]}"; do
+        if GIT_SSH_COMMAND="$SSH_CMD" timeout 60 git ls-remote --heads "$repo" >/dev/null 2>&1; then
+          echo -e "  ''${GREEN}✓''${NC} $repo"
+        else
+          echo -e "  ''${RED}✗''${NC} $repo"
+          FAILED=1
+        fi
+      done
+
+      echo ""
+      if [[ $FAILED -ne 0 ]]; then
+        log_error "Some repos can't be reached with this key (a key with a passphrase needs ssh-agent)"
+      fi
+      log_success "GitHub access verified"
+    '';
+  };
+
+  # First-run setup: create the private backup repo, point the config at it,
+  # then push the first backup
+  app-backup-init = pkgs.writeShellApplication {
+    name = "app-backup-init";
+    runtimeInputs = with pkgs; [ coreutils curl gh git gnused jq ];
+    text = ''
+      set -euo pipefail
+
+      # Colors
+      RED='\033[0;31m'
+      GREEN='\033[0;32m'
+      YELLOW='\033[1;33m'
+      BLUE='\033[0;34m'
+      NC='\033[0m'
+
+      log_info() { echo -e "''${BLUE}[INFO]''${NC} $1"; }
+      log_success() { echo -e "''${GREEN}[SUCCESS]''${NC} $1"; }
+      log_warn() { echo -e "''${YELLOW}[WARN]''${NC} $1"; }
+      log_error() { echo -e "''${RED}[ERROR]''${NC} $1"; exit 1; }
+
+      usage() {
+        echo "Usage: app-backup-init [--repo OWNER/NAME|URL] [--no-backup]"
+        echo ""
+        echo "Create the private GitHub repo for app profile backups if it doesn't"
+        echo "exist, clone it, and push the first backup."
+        echo ""
+        echo "  --repo REPO    Repo to use instead of programs.app-backup.repoUrl;"
+        echo "                 it is written to home/home.nix and the config file"
+        echo "  --no-backup    Stop after setting up the repo"
+        echo ""
+        echo "Creating the repo needs the gh CLI (gh auth login), or GITHUB_TOKEN/GH_TOKEN"
+        echo "with the repo scope."
+      }
+
+      REPO_ARG=""
+      BACKUP=true
+      while [[ $# -gt 0 ]]; do
+        case "$1" in
+          --repo) REPO_ARG="''${2:?--repo needs a value}"; shift 2 ;;
+          --no-backup) BACKUP=false; shift ;;
+          -h|--help) usage; exit 0 ;;
+          *) usage; exit 1 ;;
+        esac
+      done
+
+      # Load configuration
+      CONFIG_FILE="$HOME/.config/app-backup/config"
+      if [[ ! -f "$CONFIG_FILE" ]]; then
+        log_error "Config file not found. Run: nixos-rebuild switch"
+      fi
+      # shellcheck source=/dev/null
+      source "$CONFIG_FILE"
+      : "''${LOCAL_REPO_PATH:=$HOME/.local/share/app-backup}"
+      LOCAL_REPO_PATH="''${LOCAL_REPO_PATH/#\~/$HOME}"
+
+      # owner/name of a GitHub remote (git@github.com:owner/name.git, https://...)
+      repo_slug() {
+        sed -E -n 's#^(git@github\.com:|ssh://git@github\.com/|https://github\.com/)([^/]+/[^/]+)$#\2#p' \
+          <<<"$1" | sed 's/\.git$//'
+      }
+
+      REPO_URL="''${APP_BACKUP_REPO:-}"
+      if [[ -n "$REPO_ARG" ]]; then
+        if [[ "$REPO_ARG" =~ ^[A-Za-z0-9_.-]+/[A-Za-z0-9_.-]+$ ]]; then
+          REPO_URL="git@github.com:$REPO_ARG.git"
+        else
+          REPO_URL="$REPO_ARG"
+        fi
+      fi
+      SLUG=$(repo_slug "$REPO_URL")
+      [[ -n "$SLUG" ]] || log_error "Not a GitHub repo: ''${REPO_URL:-(none)}"
+      OWNER="''${SLUG%%/*}"
+      NAME="''${SLUG#*/}"
+
+      log_info "App Backup Repo Setup"
+      echo ""
+
+      # 1. Repo on GitHub
+      log_info "[1/4] Checking $SLUG on GitHub..."
+      if timeout 60 git ls-remote "$REPO_URL" >/dev/null 2>&1; then
+        log_success "Repo exists and is reachable"
+      else
+        TOKEN="''${GITHUB_TOKEN:-''${GH_TOKEN:-}}"
+        if [[ -z "$TOKEN" ]] && gh auth status &>/dev/null; then
+          TOKEN=$(gh auth token)
+        fi
+        if [[ -z "$TOKEN" ]]; then
+          log_error "Can't reach $REPO_URL and not signed in to GitHub to create it. Run 'gh auth login' or set GITHUB_TOKEN."
+        fi
+
+        RESPONSE=$(mktemp)
+        trap 'rm -f "$RESPONSE"' EXIT
+
+        # api METHOD PATH [JSON]: the body goes to $RESPONSE, the HTTP status to stdout
+        api() {
+          local args=(-sS -o "$RESPONSE" -w '%{http_code}' -X "$1"
+            -H "Authorization: Bearer $TOKEN"
+            -H "Accept: application/vnd.github+json"
+            "https://api.github.com$2")
+          if [[ $# -gt 2 ]]; then
+            args+=(--data "$3")
+          fi
+          # -w still prints 000 when curl can't connect
+          curl "''${args[@]}" || true
+        }
+
+        STATUS=$(api GET "/repos/$SLUG")
+        if [[ "$STATUS" == "200" ]]; then
+          [[ "$(jq -r '.private' "$RESPONSE")" == "true" ]] \
+            || log_error "$SLUG exists but is public; backups must go to a private repo"
+          log_error "$SLUG exists but this machine can't reach it over SSH. Run: forge keys github"
+        fi
+
+        STATUS=$(api GET /user)
+        [[ "$STATUS" == "200" ]] || log_error "GitHub denied the request ($STATUS); check the token"
+        LOGIN=$(jq -r '.login' "$RESPONSE")
+        if [[ "$OWNER" == "$LOGIN" ]]; then
+          ENDPOINT="/user/repos"
+        else
+          ENDPOINT="/orgs/$OWNER/repos"
+        fi
+        BODY=$(jq -nc --arg n "$NAME" \
+          '{name: $n, private: true, description: "Encrypted app profile backups (app-backup)"}')
+        STATUS=$(api POST "$ENDPOINT" "$BODY")
+        case "$STATUS" in
+          201) log_success "Created private repo $SLUG" ;;
+          401|403|404)
+            log_error "GitHub denied creating $SLUG ($STATUS); check the token's scopes" ;;
+          *) log_error "Creating $SLUG failed ($STATUS): $(jq -r '.errors[0].message // .message // empty' "$RESPONSE" 2>/dev/null)" ;;
+        esac
+      fi
+
+      # 2. Local clone with origin pointing at the repo
+      echo ""
+      log_info "[2/4] Setting up $LOCAL_REPO_PATH..."
+      if [[ -d "$LOCAL_REPO_PATH/.git" ]]; then
+        if git -C "$LOCAL_REPO_PATH" remote get-url origin &>/dev/null; then
+          git -C "$LOCAL_REPO_PATH" remote set-url origin "$REPO_URL"
+        else
+          git -C "$LOCAL_REPO_PATH" remote add origin "$REPO_URL"
+        fi
+        log_success "Remote origin set to $REPO_URL"
+      else
+        mkdir -p "$(dirname "$LOCAL_REPO_PATH")"
+        git clone "$REPO_URL" "$LOCAL_REPO_PATH" 2>&1
+        log_success "Cloned $REPO_URL"
+      fi
+      cd "$LOCAL_REPO_PATH"
+      # A freshly created repo has no commits yet
+      if ! git rev-parse --verify -q HEAD >/dev/null; then
+        git symbolic-ref HEAD refs/heads/main
+        printf '%s\n' "# App profile backups" "" \
+          "Age-encrypted browser/Termius profiles and keys, written by app-backup." \
+          "Restore with \`forge apps restore\`." > README.md
+        git add README.md
+        git commit -q -m "Initialize app profile backups"
+        git push -u origin main
+        log_success "Pushed initial commit"
+      fi
+
+      # 3. Config: home.nix for the next rebuild, the generated file for now
+      echo ""
+      log_info "[3/4] Updating configuration..."
+      if [[ "$REPO_URL" == "''${APP_BACKUP_REPO:-}" ]]; then
+        log_success "Config already points at $REPO_URL"
+      else
+        HOME_NIX="$HOME/nixos-config/home/home.nix"
+        if [[ -f "$HOME_NIX" ]] && grep -q 'programs.app-backup = {' "$HOME_NIX"; then
+          if grep -q '^ *repoUrl = ' "$HOME_NIX"; then
+            sed -i "s#^\( *\)repoUrl = .*#\1repoUrl = \"$REPO_URL\";#" "$HOME_NIX"
+          else
+            sed -i "s#^\( *\)programs.app-backup = {#&\n\1  repoUrl = \"$REPO_URL\";#" "$HOME_NIX"
+          fi
+          log_success "Set programs.app-backup.repoUrl in $HOME_NIX (commit it and rebuild)"
+        else
+          log_warn "Set programs.app-backup.repoUrl = \"$REPO_URL\"; in your Home Manager config"
+        fi
+        # The Home Manager symlink is replaced on the next switch
+        NEW_CONFIG=$(sed "s#^APP_BACKUP_REPO=.*#APP_BACKUP_REPO=\"$REPO_URL\"#" "$CONFIG_FILE")
+        rm -f "$CONFIG_FILE"
+        printf '%s\n' "$NEW_CONFIG" > "$CONFIG_FILE"
+        log_success "Wrote $CONFIG_FILE"
+      fi
+
+      # 4. First backup
+      echo ""
+      if [[ "$BACKUP" == "true" ]]; then
+        log_info "[4/4] Running the first backup..."
+        ${app-backup}/bin/app-backup --push
+      else
+        log_info "[4/4] Skipped the first backup (--no-backup)"
+      fi
+
+      echo ""
+      log_success "App backup repo ready: $SLUG"
+    '';
+  };
 in
 {
   options.programs.app-backup = {
@@ -1598,6 +2176,16 @@ in
       example = "~/.ssh/id_ed25519";
     };
 
+    includeSshConfig = mkOption {
+      type = types.bool;
+      default = false;
+      description = ''
+        Also put ~/.ssh/config and ~/.ssh/known_hosts in the keys backup, and
+        restore them (config 600, known_hosts 644) with the keys.
+        Files that are Home Manager symlinks are left out.
+      '';
+    };
+
     keysPassphrase1Password = mkOption {
       type = types.nullOr types.str;
       default = null;
@@ -1638,6 +2226,8 @@ in
       keys-backup
       keys-restore
       keys-status
+      keys-github
+      app-backup-init
       # Backward compatibility
       browser-backup-compat
       browser-restore-compat
@@ -1668,6 +2258,8 @@ in
         SSH_KEY_PATH="${cfg.sshKeyPath}"
       '' + optionalString (cfg.keysPassphrase1Password != null) ''
         KEYS_PASSPHRASE_1PASSWORD="${cfg.keysPassphrase1Password}"
+      '' + optionalString cfg.includeSshConfig ''
+        KEYS_INCLUDE_SSH_CONFIG=true
       '';
       force = true;
     };
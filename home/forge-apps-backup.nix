@@ -0,0 +1,37 @@
+# Scheduled app profile backup
+#
+# Systemd user timer that runs `app-backup --push` once a day, so browser and
+# Termius sessions are in the private backup repo without remembering to do it.
+#
+# Opt-in per host with `forge enable-service apps-backup` (services.json, see
+# modules/forge-services.nix). Needs the age and SSH keys from keys-setup.
+{ config, pkgs, lib, osConfig, ... }:
+
+lib.mkIf (osConfig.forge.services.appsBackup.enable && config.programs.app-backup.enable) {
+  systemd.user.services.forge-apps-backup = {
+    Unit = {
+      Description = "App profile backup";
+      After = [ "network-online.target" ];
+      Wants = [ "network-online.target" ];
+    };
+    Service = {
+      Type = "oneshot";
+      ExecStart = "${config.home.profileDirectory}/bin/app-backup --push";
+    };
+  };
+
+  systemd.user.timers.forge-apps-backup = {
+    Unit = {
+      Description = "Daily app profile backup";
+    };
+    Timer = {
+      OnCalendar = "daily";
+      # Spread out the push a little and catch up after the machine was off
+      RandomizedDelaySec = "30min";
+      Persistent = true;
+    };
+    Install = {
+      WantedBy = [ "timers.target" ];
+    };
+  };
+}
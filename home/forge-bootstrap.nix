@@ -0,0 +1,28 @@
+# Forge first-login bootstrap
+#
+# Opens a terminal running `forge bootstrap` when the graphical session starts,
+# which restores keys and then app profiles. Forge writes
+# ~/.local/share/forge/bootstrap-done once both succeed (or the user picks
+# "Don't ask again"), after which the unit no longer starts.
+#
+# Opt-in per host with `bootstrap.enable = true;` (modules/bootstrap.nix).
+{ config, pkgs, lib, osConfig, forge, ... }:
+
+lib.mkIf osConfig.bootstrap.enable {
+  systemd.user.services.forge-bootstrap = {
+    Unit = {
+      Description = "Forge first-login key and app restore";
+      After = [ "graphical-session.target" "network-online.target" ];
+      Wants = [ "network-online.target" ];
+      PartOf = [ "graphical-session.target" ];
+      ConditionPathExists = "!%h/.local/share/forge/bootstrap-done";
+    };
+    Service = {
+      Type = "simple";
+      ExecStart = "${config.programs.ghostty.package}/bin/ghostty --title=Forge -e ${forge}/bin/forge bootstrap";
+    };
+    Install = {
+      WantedBy = [ "graphical-session.target" ];
+    };
+  };
+}
@@ -6,9 +6,14 @@
 # - Flake input updates (nixpkgs, home-manager, etc.)
 #
 # Sends desktop notifications via libnotify when updates are available.
-{ config, pkgs, lib, forge, ... }:
+# Individual checks can be disabled or given their own timeout/interval in
+# ~/.config/forge/notify.toml (see packages/forge/src/notify/config.rs).
+#
+# On by default; turn it off per host with `forge enable-service notify
+# --disable` (services.json, see modules/forge-services.nix).
+{ config, pkgs, lib, osConfig, forge, ... }:
 
-{
+lib.mkIf osConfig.forge.services.notify.enable {
   # Systemd user service - runs the check
   systemd.user.services.forge-notify = {
     Unit = {
@@ -0,0 +1,39 @@
+# Forge host heartbeat
+#
+# Systemd user timer that runs `forge report-state`, which commits this host's
+# system generation and config revision to hosts/<name>.json on the
+# forge-state branch. `forge fleet status` compares those reports with main
+# to show which machines are behind or have drifted.
+#
+# Opt-in per host with `fleet.reportState.enable = true;` (modules/fleet.nix).
+# Pushing uses the user's git/SSH setup (1Password agent).
+{ config, pkgs, lib, osConfig, forge, ... }:
+
+lib.mkIf osConfig.fleet.reportState.enable {
+  systemd.user.services.forge-report-state = {
+    Unit = {
+      Description = "Forge host state report";
+      After = [ "network-online.target" ];
+      Wants = [ "network-online.target" ];
+    };
+    Service = {
+      Type = "oneshot";
+      ExecStart = "${forge}/bin/forge report-state";
+    };
+  };
+
+  systemd.user.timers.forge-report-state = {
+    Unit = {
+      Description = "Report Forge host state every 6 hours";
+    };
+    Timer = {
+      # Give the network and 1Password agent time after login
+      OnBootSec = "10min";
+      OnUnitActiveSec = "6h";
+      Persistent = true;
+    };
+    Install = {
+      WantedBy = [ "timers.target" ];
+    };
+  };
+}
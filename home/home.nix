@@ -21,6 +21,10 @@ in
     ./1password-secrets.nix  # 1Password SSH agent integration
     ./app-backup  # App profile backup/restore (browsers, Termius, etc.)
     ./forge-notify.nix  # Background update checker
+    ./forge-bootstrap.nix  # First-login keys/apps restore (bootstrap.enable)
+    ./forge-report-state.nix  # Host state heartbeat (fleet.reportState.enable)
+    ./forge-apps-backup.nix  # Daily app profile backup (forge enable-service)
+    ./theming.nix  # GTK/Qt/cursor preset from theming.preset
     # Always deploy Illogical Impulse dotfiles (Quickshell config)
     # Required because Home Manager evaluates with default shell at build time,
     # but specialisations need these files at boot time. See CLAUDE.md.
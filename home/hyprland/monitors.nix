@@ -1,11 +1,16 @@
 # Monitor configuration
 # Host-specific display setup
+# hosts/<hostname>/monitors.conf (captured by forge create-host) takes precedence
 { hostname, lib ? builtins }:
 
 let
   # Check base hostname (handles -illogical suffix)
   isKraken = lib.hasPrefix "kraken" hostname;
-  monitorConfig = if isKraken then ''
+  baseHostname = lib.removeSuffix "-illogical" hostname;
+  capturedConfig = ../../hosts + "/${baseHostname}/monitors.conf";
+  monitorConfig = if builtins.pathExists capturedConfig then
+    builtins.readFile capturedConfig
+  else if isKraken then ''
     # Kraken: 4K display at 165Hz with 1.5x scaling
     monitor = ,3840x2160@165,auto,1.5
     env = GDK_SCALE,1.5
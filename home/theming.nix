@@ -0,0 +1,46 @@
+# Theming presets (GTK/Qt theme, cursor, UI font)
+# Preset is chosen per host via the NixOS option theming.preset (modules/theming.nix)
+{ config, pkgs, lib, osConfig, ... }:
+
+let
+  preset = osConfig.theming.preset;
+
+  presets = {
+    simple = {
+      colorScheme = "prefer-light";
+      gtkTheme = { name = "Adwaita"; package = pkgs.gnome-themes-extra; };
+      qtStyle = "adwaita";
+      cursor = { name = "Adwaita"; package = pkgs.adwaita-icon-theme; size = 32; };
+      font = { name = "Noto Sans"; size = 12; };
+    };
+    dark = {
+      colorScheme = "prefer-dark";
+      gtkTheme = { name = "Adwaita-dark"; package = pkgs.gnome-themes-extra; };
+      qtStyle = "adwaita-dark";
+      cursor = { name = "Bibata-Modern-Classic"; package = pkgs.bibata-cursors; size = 24; };
+      font = { name = "Inter"; size = 11; };
+    };
+  };
+
+  p = presets.${preset};
+in
+lib.mkIf (preset != "default") {
+  gtk = {
+    enable = true;
+    theme = p.gtkTheme;
+    font = p.font;
+  };
+
+  qt = {
+    enable = true;
+    platformTheme.name = "adwaita";
+    style.name = p.qtStyle;
+  };
+
+  home.pointerCursor = p.cursor // {
+    gtk.enable = true;
+    x11.enable = true;
+  };
+
+  dconf.settings."org/gnome/desktop/interface".color-scheme = lib.mkForce p.colorScheme;
+}
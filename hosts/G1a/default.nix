@@ -9,6 +9,9 @@
 
   networking.hostName = "G1a";
 
+  # Steam, GameMode, gamescope (modules/gaming.nix)
+  gaming.enable = true;
+
   # === AMD Strix Halo (RDNA 3.5) GPU Configuration ===
   # Enable official amdgpu initrd support for early KMS and Plymouth
   hardware.amdgpu.initrd.enable = true;
@@ -9,6 +9,9 @@
 
   networking.hostName = "kraken";
 
+  # Steam, GameMode, gamescope (modules/gaming.nix)
+  gaming.enable = true;
+
   # NVIDIA early KMS for Plymouth
   # Override shared config to ensure all required modules are loaded
   # Note: simpledrm is builtin to the kernel, no need to specify it
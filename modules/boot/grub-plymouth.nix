@@ -0,0 +1,19 @@
+# GRUB bootloader and Plymouth boot splash configuration (legacy BIOS)
+# Used by hosts whose firmware cannot boot UEFI. Pairs with modules/disko/bios.nix.
+# The host's disko file sets boot.loader.grub.device to the install disk.
+{ config, pkgs, lib, ... }:
+
+{
+  imports = [ ./plymouth.nix ];
+
+  boot.loader.systemd-boot.enable = false;
+
+  boot.loader.grub = {
+    enable = true;
+    efiSupport = false;
+    configurationLimit = 10;
+    # Catppuccin Mocha background for the text menu
+    backgroundColor = "#1e1e2e";
+    splashImage = null;
+  };
+}
@@ -1,9 +1,8 @@
-# Shared Limine bootloader and Plymouth boot splash configuration
-{ config, pkgs, lib, plymouth-cybex, ... }:
+# Shared Limine bootloader and Plymouth boot splash configuration (UEFI)
+{ config, pkgs, lib, ... }:
 
 {
-  # Use latest kernel for best hardware support
-  boot.kernelPackages = pkgs.linuxPackages_latest;
+  imports = [ ./plymouth.nix ];
 
   # Limine bootloader configuration
   boot.loader.systemd-boot.enable = false;
@@ -28,38 +27,4 @@
       };
     };
   };
-
-  # Boot settings
-  boot.loader.timeout = 3;
-  boot.initrd.systemd.enable = true;
-
-  # Base HID modules for keyboard input during boot
-  # GPU modules should be set per-host (amdgpu for AMD, nvidia for NVIDIA)
-  boot.initrd.kernelModules = lib.mkDefault [
-    "hid-generic"
-    "usbhid"
-  ];
-
-  # Plymouth boot splash
-  boot.plymouth = {
-    enable = true;
-    themePackages = [ plymouth-cybex ];
-    theme = "cybex";
-  };
-
-  # Make Plymouth wait for DRM device (fixes NVIDIA framebuffer takeover issue)
-  boot.initrd.systemd.services.plymouth-start = {
-    wants = [ "systemd-udev-settle.service" ];
-    after = [ "systemd-udev-settle.service" ];
-  };
-
-  # Clean boot display
-  boot.kernelParams = [
-    "quiet"
-    "splash"
-    "rd.udev.log_level=3"
-    "vt.global_cursor_default=0"
-  ];
-  boot.consoleLogLevel = 0;
-  boot.initrd.verbose = false;
 }
@@ -0,0 +1,43 @@
+# Shared boot settings used by both bootloader modules
+# (limine-plymouth.nix for UEFI, grub-plymouth.nix for legacy BIOS)
+{ config, pkgs, lib, plymouth-cybex, ... }:
+
+{
+  # Use latest kernel for best hardware support
+  # Hosts pick their own kernel flavor in hosts/<name>/default.nix
+  boot.kernelPackages = lib.mkDefault pkgs.linuxPackages_latest;
+
+  # Boot settings
+  boot.loader.timeout = 3;
+  boot.initrd.systemd.enable = true;
+
+  # Base HID modules for keyboard input during boot
+  # GPU modules should be set per-host (amdgpu for AMD, nvidia for NVIDIA)
+  boot.initrd.kernelModules = lib.mkDefault [
+    "hid-generic"
+    "usbhid"
+  ];
+
+  # Plymouth boot splash
+  boot.plymouth = {
+    enable = true;
+    themePackages = [ plymouth-cybex ];
+    theme = "cybex";
+  };
+
+  # Make Plymouth wait for DRM device (fixes NVIDIA framebuffer takeover issue)
+  boot.initrd.systemd.services.plymouth-start = {
+    wants = [ "systemd-udev-settle.service" ];
+    after = [ "systemd-udev-settle.service" ];
+  };
+
+  # Clean boot display
+  boot.kernelParams = [
+    "quiet"
+    "splash"
+    "rd.udev.log_level=3"
+    "vt.global_cursor_default=0"
+  ];
+  boot.consoleLogLevel = 0;
+  boot.initrd.verbose = false;
+}
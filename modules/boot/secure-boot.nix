@@ -0,0 +1,20 @@
+# Secure Boot with lanzaboote (UEFI only)
+# Replaces Limine with a signed systemd-boot. The keys live in /var/lib/sbctl;
+# forge install creates them and enrolls them with `sbctl enroll-keys`.
+{ inputs, lib, pkgs, ... }:
+
+{
+  imports = [ inputs.lanzaboote.nixosModules.lanzaboote ];
+
+  # Lanzaboote installs its own systemd-boot
+  boot.loader.limine.enable = lib.mkForce false;
+  boot.loader.systemd-boot.enable = lib.mkForce false;
+
+  boot.lanzaboote = {
+    enable = true;
+    pkiBundle = "/var/lib/sbctl";
+  };
+
+  # sbctl status / verify / enroll-keys
+  environment.systemPackages = [ pkgs.sbctl ];
+}
@@ -0,0 +1,11 @@
+# First-login bootstrap option
+# Set per host with `bootstrap.enable = true;` - the user service lives in
+# home/forge-bootstrap.nix
+{ lib, ... }:
+
+{
+  options.bootstrap.enable = lib.mkEnableOption ''
+    running `forge bootstrap` (keys restore, then apps restore) in a terminal
+    on first login, until it succeeds or is dismissed
+  '';
+}
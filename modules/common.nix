@@ -3,25 +3,19 @@
 
 {
   imports = [
-    ./gaming.nix      # Steam and gaming tools
+    ./gaming.nix      # Steam and gaming tools (gaming.enable per host)
+    ./theming.nix     # Theming presets (theming.preset per host)
+    ./bootstrap.nix   # First-login keys/apps restore (bootstrap.enable per host)
+    ./fleet.nix       # Host state heartbeat (fleet.reportState.enable per host)
+    ./registry.nix    # Flake registry pins from registry.json (forge registry)
+    ./gc.nix          # GC schedule and store optimisation from gc.json (forge gc)
+    ./nix-conf.nix    # Features, max-jobs, cores, sandbox from nix-conf.json (forge nix-conf)
+    ./forge-services.nix  # Notify/auto-update/app backup from services.json (forge enable-service)
   ];
 
-  # Enable flakes
-  nix.settings.experimental-features = [ "nix-command" "flakes" ];
-
   # Increase download buffer size for faster fetches
   nix.settings.download-buffer-size = 256 * 1024 * 1024; # 256 MiB
 
-  # Automatic garbage collection to prevent store bloat
-  nix.gc = {
-    automatic = true;
-    dates = "weekly";
-    options = "--delete-older-than 14d";
-  };
-
-  # Optimize store automatically
-  nix.settings.auto-optimise-store = true;
-
   # Use bash as /bin/sh (instead of busybox ash)
   environment.binsh = "${pkgs.bash}/bin/bash";
 
@@ -57,9 +51,10 @@
   # Disable NetworkManager-wait-online to speed up boot
   systemd.services.NetworkManager-wait-online.enable = lib.mkForce false;
 
-  # Timezone and locale
-  time.timeZone = "Europe/Amsterdam";
-  i18n.defaultLocale = "en_US.UTF-8";
+  # Timezone and locale (defaults; forge install writes the host's own choice
+  # into its default.nix)
+  time.timeZone = lib.mkDefault "Europe/Amsterdam";
+  i18n.defaultLocale = lib.mkDefault "en_US.UTF-8";
   i18n.extraLocaleSettings = {
     LC_ADDRESS = "nl_NL.UTF-8";
     LC_IDENTIFICATION = "nl_NL.UTF-8";
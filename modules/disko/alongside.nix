@@ -0,0 +1,71 @@
+# Disko configuration for installing next to an existing OS (UEFI only)
+# Forge creates two GPT partitions in the largest free region first:
+# - nixos-boot: 2GB EFI partition (FAT32, /boot)
+# - nixos-root: LUKS2 encrypted Btrfs with subvolumes (see subvolumes.nix)
+#   (plain Btrfs when diskLayout.encrypt is off)
+#
+# Existing partitions (Windows, its ESP and recovery) are never touched. Run
+# disko with --mode format,mount: destroy would wipe the whole disk.
+{ config, lib, ... }:
+
+let
+  inherit (config.diskLayout) encrypt subvolumes mountOptions;
+  btrfs = {
+    type = "btrfs";
+    extraArgs = [ "-f" "-L" "nixos" ];
+    subvolumes = lib.mapAttrs (_: mountpoint: {
+      inherit mountpoint mountOptions;
+    }) subvolumes // config.diskLayout.swapSubvolume;
+    inherit (config.diskLayout) postCreateHook;
+  } // lib.optionalAttrs (subvolumes == { }) {
+    # Flat layout: mount the filesystem itself
+    mountpoint = "/";
+    inherit mountOptions;
+  };
+in
+{
+  imports = [ ./subvolumes.nix ];
+
+  disko.devices.disk = {
+    boot = {
+      type = "disk";
+      device = "/dev/disk/by-partlabel/nixos-boot";
+      content = {
+        type = "filesystem";
+        format = "vfat";
+        mountpoint = "/boot";
+        mountOptions = [ "umask=0077" "nofail" "x-systemd.device-timeout=30s" ];
+      };
+    };
+
+    main = {
+      type = "disk";
+      # device is set by the host-specific module (/dev/disk/by-partlabel/nixos-root)
+      content = if encrypt then {
+        type = "luks";
+        name = "cryptroot";
+        # No keyFile or passwordFile = interactive passphrase prompt
+        extraOpenArgs = [
+          "--allow-discards"
+          "--perf-no_read_workqueue"
+          "--perf-no_write_workqueue"
+        ];
+        settings = {
+          allowDiscards = true;
+          bypassWorkqueues = true;
+        };
+        content = btrfs;
+      } else btrfs;
+    };
+  };
+
+  # Forge only makes room for the boot and root partitions
+  assertions = [{
+    assertion = config.diskLayout.swapPartitionSize == null;
+    message = "diskLayout.swapPartitionSize is not supported when installing alongside another OS";
+  }];
+
+  # Ensure /var/log is available early for boot logging
+  fileSystems."/var/log".neededForBoot =
+    lib.mkIf (builtins.elem "/var/log" (lib.attrValues subvolumes)) true;
+}
@@ -0,0 +1,85 @@
+# Disko configuration for legacy BIOS machines
+# Partition layout (MBR / msdos table, GRUB in the MBR):
+# - 2GB boot partition (ext4, /boot, bootable flag) - GRUB can't read LUKS2
+# - Remaining space: LUKS2 encrypted Btrfs with subvolumes (same as default.nix)
+#   (plain Btrfs when diskLayout.encrypt is off)
+#
+# Subvolumes (default, hosts can override diskLayout.subvolumes - see subvolumes.nix):
+# - @         -> /
+# - @home     -> /home
+# - @nix      -> /nix
+# - @var-log  -> /var/log
+{ config, lib, ... }:
+
+let
+  inherit (config.diskLayout) encrypt subvolumes mountOptions;
+  btrfs = {
+    type = "btrfs";
+    extraArgs = [ "-f" "-L" "nixos" ];
+    subvolumes = lib.mapAttrs (_: mountpoint: {
+      inherit mountpoint mountOptions;
+    }) subvolumes // config.diskLayout.swapSubvolume;
+    inherit (config.diskLayout) postCreateHook;
+  } // lib.optionalAttrs (subvolumes == { }) {
+    # Flat layout: mount the filesystem itself
+    mountpoint = "/";
+    inherit mountOptions;
+  };
+in
+{
+  imports = [ ./subvolumes.nix ];
+
+  disko.devices = {
+    disk.main = {
+      type = "disk";
+      # device is set by host-specific module
+      content = {
+        type = "table";
+        format = "msdos";
+        partitions = [
+          {
+            part-type = "primary";
+            start = "1M";
+            end = "2G";
+            bootable = true;
+            content = {
+              type = "filesystem";
+              format = "ext4";
+              mountpoint = "/boot";
+              extraArgs = [ "-L" "boot" ];
+            };
+          }
+          {
+            part-type = "primary";
+            start = "2G";
+            end = "100%";
+            content = if encrypt then {
+              type = "luks";
+              name = "cryptroot";
+              # No keyFile or passwordFile = interactive passphrase prompt
+              extraOpenArgs = [
+                "--allow-discards"
+                "--perf-no_read_workqueue"
+                "--perf-no_write_workqueue"
+              ];
+              settings = {
+                allowDiscards = true;
+                bypassWorkqueues = true;
+              };
+              content = btrfs;
+            } else btrfs;
+          }
+        ];
+      };
+    };
+  };
+
+  assertions = [{
+    assertion = config.diskLayout.swapPartitionSize == null;
+    message = "diskLayout.swapPartitionSize needs a GPT layout; use swapfileSize on BIOS hosts";
+  }];
+
+  # Ensure /var/log is available early for boot logging
+  fileSystems."/var/log".neededForBoot =
+    lib.mkIf (builtins.elem "/var/log" (lib.attrValues subvolumes)) true;
+}
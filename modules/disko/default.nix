@@ -1,18 +1,70 @@
 # Common disko configuration for declarative disk partitioning
 # Partition layout (Omarchy-inspired):
-# - 2GB EFI partition (FAT32, /boot)
+# - 2GB EFI partition (FAT32, /boot; diskLayout.espSize)
 # - Remaining space: LUKS2 encrypted Btrfs with subvolumes
+#   (plain Btrfs when diskLayout.encrypt is off)
+# - With diskLayout.rootSize, the root gets that much and the rest of the disk
+#   is a LUKS2 "crypthome" Btrfs partition mounted at /home
 #
-# Subvolumes:
+# Subvolumes (default, hosts can override diskLayout.subvolumes - see subvolumes.nix):
 # - @         -> /
 # - @home     -> /home
 # - @nix      -> /nix
 # - @var-log  -> /var/log
 #
-# No swap partition - using zram only (configured in common.nix)
-{ lib, ... }:
+# Multi-disk installs use mirror.nix instead, and/or import home-disk.nix next to this.
+#
+# Swap is zram (common.nix) unless the host sets diskLayout.swapfileSize or
+# diskLayout.swapPartitionSize (see subvolumes.nix)
+{ config, lib, ... }:
 
+let
+  inherit (config.diskLayout) encrypt mountOptions rootSize;
+  subvolumes = config.diskLayout.rootSubvolumes;
+  btrfs = {
+    type = "btrfs";
+    extraArgs = [ "-f" "-L" "nixos" ];
+    subvolumes = lib.mapAttrs (_: mountpoint: {
+      inherit mountpoint mountOptions;
+    }) subvolumes // config.diskLayout.swapSubvolume;
+    inherit (config.diskLayout) postCreateHook;
+  } // lib.optionalAttrs (subvolumes == { }) {
+    # Flat layout: mount the filesystem itself
+    mountpoint = "/";
+    inherit mountOptions;
+  };
+  luksOpenArgs = [
+    "--allow-discards"
+    "--perf-no_read_workqueue"
+    "--perf-no_write_workqueue"
+  ];
+  luksSettings = {
+    allowDiscards = true;
+    bypassWorkqueues = true;
+  };
+  homeBtrfs = {
+    type = "btrfs";
+    extraArgs = [ "-f" "-L" "home" ];
+    mountpoint = "/home";
+    inherit mountOptions;
+  };
+  homePartition = lib.optionalAttrs (rootSize != null) {
+    home = {
+      label = if encrypt then "crypthome" else "home";
+      size = "100%";
+      content = if encrypt then {
+        type = "luks";
+        name = "crypthome";
+        extraOpenArgs = luksOpenArgs;
+        settings = luksSettings;
+        content = homeBtrfs;
+      } else homeBtrfs;
+    };
+  };
+in
 {
+  imports = [ ./subvolumes.nix ];
+
   disko.devices = {
     disk.main = {
       type = "disk";
@@ -21,8 +73,8 @@
         type = "gpt";
         partitions = {
           ESP = {
-            label = "ESP";
-            size = "2G";
+            label = config.diskLayout.bootLabel;
+            size = config.diskLayout.espSize;
             type = "EF00";
             content = {
               type = "filesystem";
@@ -32,50 +84,23 @@
             };
           };
           luks = {
-            label = "cryptroot";
-            size = "100%";
-            content = {
+            label = config.diskLayout.rootLabel;
+            size = if rootSize != null then rootSize else "100%";
+            content = if encrypt then {
               type = "luks";
               name = "cryptroot";
               # No keyFile or passwordFile = interactive passphrase prompt
-              extraOpenArgs = [
-                "--allow-discards"
-                "--perf-no_read_workqueue"
-                "--perf-no_write_workqueue"
-              ];
-              settings = {
-                allowDiscards = true;
-                bypassWorkqueues = true;
-              };
-              content = {
-                type = "btrfs";
-                extraArgs = [ "-f" "-L" "nixos" ];
-                subvolumes = {
-                  "@" = {
-                    mountpoint = "/";
-                    mountOptions = [ "compress=zstd" "noatime" ];
-                  };
-                  "@home" = {
-                    mountpoint = "/home";
-                    mountOptions = [ "compress=zstd" "noatime" ];
-                  };
-                  "@nix" = {
-                    mountpoint = "/nix";
-                    mountOptions = [ "compress=zstd" "noatime" ];
-                  };
-                  "@var-log" = {
-                    mountpoint = "/var/log";
-                    mountOptions = [ "compress=zstd" "noatime" ];
-                  };
-                };
-              };
-            };
+              extraOpenArgs = luksOpenArgs;
+              settings = luksSettings;
+              content = btrfs;
+            } else btrfs;
           };
-        };
+        } // config.diskLayout.swapPartition // homePartition;
       };
     };
   };
 
   # Ensure /var/log is available early for boot logging
-  fileSystems."/var/log".neededForBoot = true;
+  fileSystems."/var/log".neededForBoot =
+    lib.mkIf (builtins.elem "/var/log" (lib.attrValues subvolumes)) true;
 }
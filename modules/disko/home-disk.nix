@@ -0,0 +1,49 @@
+# Disko configuration for /home on its own disk
+# Imported next to default.nix or mirror.nix by multi-disk installs:
+# - LUKS2 "crypthome" over the whole disk, Btrfs mounted at /home
+#   (plain Btrfs when diskLayout.encrypt is off)
+#
+# The /home subvolume is left off the root filesystem (diskLayout.separateHome).
+# It is unlocked with the same passphrase as the root at boot.
+{ config, ... }:
+
+let
+  inherit (config.diskLayout) encrypt;
+  btrfs = {
+    type = "btrfs";
+    extraArgs = [ "-f" "-L" "home" ];
+    mountpoint = "/home";
+    inherit (config.diskLayout) mountOptions;
+  };
+in
+{
+  imports = [ ./subvolumes.nix ];
+
+  diskLayout.separateHome = true;
+
+  disko.devices.disk.home = {
+    type = "disk";
+    # device is set by the host-specific module
+    content = {
+      type = "gpt";
+      partitions.luks = {
+        label = if encrypt then "crypthome" else "home";
+        size = "100%";
+        content = if encrypt then {
+          type = "luks";
+          name = "crypthome";
+          extraOpenArgs = [
+            "--allow-discards"
+            "--perf-no_read_workqueue"
+            "--perf-no_write_workqueue"
+          ];
+          settings = {
+            allowDiscards = true;
+            bypassWorkqueues = true;
+          };
+          content = btrfs;
+        } else btrfs;
+      };
+    };
+  };
+}
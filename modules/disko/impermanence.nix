@@ -0,0 +1,68 @@
+# Ephemeral root for impermanence hosts (diskLayout.ephemeralRoot, see subvolumes.nix)
+#
+# - "tmpfs": / is a tmpfs, the btrfs only holds the persistent subvolumes
+# - "snapshot": / is the @ subvolume; disko snapshots it empty as @blank when
+#   the filesystem is created, and the initrd swaps @ for a fresh copy of
+#   @blank at every boot
+#
+# Either way only the subvolumes survive a reboot (@persist, @home, @nix,
+# @var-log in the wizard's layouts). The host's environment.persistence block
+# (modules/impermanence.nix) links the state to keep from /persist.
+{ config, lib, pkgs, ... }:
+
+let
+  inherit (config.diskLayout) ephemeralRoot subvolumes;
+  rootDevice = "/dev/disk/by-label/nixos";
+in
+{
+  config = lib.mkIf (ephemeralRoot != null) (lib.mkMerge [
+    {
+      assertions = [{
+        assertion = builtins.elem "/persist" (lib.attrValues subvolumes);
+        message = "diskLayout.ephemeralRoot needs a subvolume mounted at /persist.";
+      }];
+
+      fileSystems."/persist".neededForBoot = true;
+    }
+
+    (lib.mkIf (ephemeralRoot == "tmpfs") {
+      disko.devices.nodev."/" = {
+        fsType = "tmpfs";
+        mountOptions = [ "defaults" "size=25%" "mode=755" ];
+      };
+    })
+
+    (lib.mkIf (ephemeralRoot == "snapshot") {
+      boot.initrd.systemd.initrdBin = [ pkgs.btrfs-progs ];
+      boot.initrd.systemd.services.rollback-root = {
+        description = "Roll back / to the blank snapshot";
+        wantedBy = [ "initrd.target" ];
+        requires = [ "dev-disk-by\\x2dlabel-nixos.device" ];
+        # Resuming from hibernation needs the old root, so it goes first
+        after = [
+          "dev-disk-by\\x2dlabel-nixos.device"
+          "systemd-cryptsetup@cryptroot.service"
+          "systemd-hibernate-resume.service"
+        ];
+        before = [ "sysroot.mount" ];
+        unitConfig.DefaultDependencies = "no";
+        serviceConfig.Type = "oneshot";
+        script = ''
+          mkdir -p /btrfs
+          mount -t btrfs -o subvol=/ ${rootDevice} /btrfs
+          if [ -e /btrfs/@blank ]; then
+            # Nested subvolumes (machinectl, docker) go first, children before parents
+            btrfs subvolume list -o /btrfs/@ | cut -d' ' -f9- | sort -r | while read -r sub; do
+              btrfs subvolume delete "/btrfs/$sub"
+            done
+            btrfs subvolume delete /btrfs/@
+            btrfs subvolume snapshot /btrfs/@blank /btrfs/@
+          else
+            echo "No @blank snapshot, leaving / as it is"
+          fi
+          umount /btrfs
+        '';
+      };
+    })
+  ]);
+}
@@ -0,0 +1,116 @@
+# Disko configuration for a root filesystem mirrored across two disks (UEFI only)
+# Partition layout:
+# - main:   2GB EFI partition (FAT32, /boot; diskLayout.espSize) + LUKS2 "cryptroot"
+# - mirror: LUKS2 "cryptmirror" over the whole disk
+#
+# One Btrfs filesystem spans both LUKS devices with data and metadata in
+# raid1, so either disk can fail without losing the system. Subvolumes are
+# the same as default.nix (see subvolumes.nix). Only the main disk has an ESP.
+# A swap partition (diskLayout.swapPartitionSize) also goes on the main disk.
+#
+# Disko creates disks in name order: "main" is opened before "mirror" builds
+# the filesystem on top of both. Both devices are unlocked with the same
+# passphrase at boot. With diskLayout.encrypt off, the filesystem spans the
+# two partitions directly.
+{ config, lib, ... }:
+
+let
+  inherit (config.diskLayout) encrypt mountOptions;
+  subvolumes = config.diskLayout.rootSubvolumes;
+  # Second device of the filesystem, created on the main disk
+  mainDevice =
+    if encrypt then "/dev/mapper/cryptroot"
+    else "/dev/disk/by-partlabel/${config.diskLayout.rootLabel}";
+  btrfs = {
+    type = "btrfs";
+    extraArgs = [
+      "-f"
+      "-L" "nixos"
+      "-d" "raid1"
+      "-m" "raid1"
+      mainDevice
+    ];
+    subvolumes = lib.mapAttrs (_: mountpoint: {
+      inherit mountpoint mountOptions;
+    }) subvolumes;
+    inherit (config.diskLayout) postCreateHook;
+  } // lib.optionalAttrs (subvolumes == { }) {
+    # Flat layout: mount the filesystem itself
+    mountpoint = "/";
+    inherit mountOptions;
+  };
+  luksSettings = {
+    extraOpenArgs = [
+      "--allow-discards"
+      "--perf-no_read_workqueue"
+      "--perf-no_write_workqueue"
+    ];
+    settings = {
+      allowDiscards = true;
+      bypassWorkqueues = true;
+    };
+  };
+in
+{
+  imports = [ ./subvolumes.nix ];
+
+  disko.devices.disk = {
+    main = {
+      type = "disk";
+      # device is set by the host-specific module
+      content = {
+        type = "gpt";
+        partitions = {
+          ESP = {
+            label = config.diskLayout.bootLabel;
+            size = config.diskLayout.espSize;
+            type = "EF00";
+            content = {
+              type = "filesystem";
+              format = "vfat";
+              mountpoint = "/boot";
+              mountOptions = [ "umask=0077" "nofail" "x-systemd.device-timeout=30s" ];
+            };
+          };
+          # Filesystem is created from the mirror disk below
+          luks = {
+            label = config.diskLayout.rootLabel;
+            size = "100%";
+          } // lib.optionalAttrs encrypt {
+            content = {
+              type = "luks";
+              name = "cryptroot";
+            } // luksSettings;
+          };
+        } // config.diskLayout.swapPartition;
+      };
+    };
+
+    mirror = {
+      type = "disk";
+      # device is set by the host-specific module
+      content = {
+        type = "gpt";
+        partitions.luks = {
+          label = if encrypt then "cryptmirror" else "mirror";
+          size = "100%";
+          content = if encrypt then {
+            type = "luks";
+            name = "cryptmirror";
+            content = btrfs;
+          } // luksSettings else btrfs;
+        };
+      };
+    };
+  };
+
+  # Btrfs can't put a swapfile on a multi-device filesystem
+  assertions = [{
+    assertion = config.diskLayout.swapfileSize == null;
+    message = "diskLayout.swapfileSize is not supported on a mirrored root";
+  }];
+
+  # Ensure /var/log is available early for boot logging
+  fileSystems."/var/log".neededForBoot =
+    lib.mkIf (builtins.elem "/var/log" (lib.attrValues subvolumes)) true;
+}
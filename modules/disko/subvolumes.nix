@@ -0,0 +1,234 @@
+# Btrfs subvolume scheme shared by default.nix and bios.nix
+#
+# Hosts pick a different scheme in their modules/disko/<host>.nix
+# (forge's create-host wizard writes this):
+#
+#   diskLayout.subvolumes = {
+#     "@" = "/";
+#     "@snapshots" = "/.snapshots";
+#   };
+#
+# An empty set means no subvolumes: the btrfs filesystem itself is mounted at /.
+#
+# home-disk.nix sets diskLayout.separateHome, which drops the /home subvolume
+# from the root filesystem (rootSubvolumes is what the layouts create).
+#
+# Swap beyond zram (common.nix) is also chosen here by the wizard:
+#
+#   diskLayout.swapfileSize = "8G";        # @swap subvolume, single-disk btrfs
+#   diskLayout.swapPartitionSize = "38G";  # LUKS "cryptswap", hibernation, GPT
+#
+# Partition sizes, set by forge install's partition step:
+#
+#   diskLayout.espSize = "1G";             # EFI system partition (UEFI layouts)
+#   diskLayout.rootSize = "200G";          # default.nix only: the rest of the
+#                                          # disk becomes a LUKS "crypthome" /home
+#
+# forge install writes `diskLayout.encrypt = false;` when encryption is turned
+# off; every layout (zfs.nix included) then skips LUKS / ZFS native encryption.
+#
+# Btrfs mount options and GPT partition labels (the wizard's disk options):
+#
+#   diskLayout.compression = "zstd:6";   # null turns compression off
+#   diskLayout.noatime = false;
+#   diskLayout.discard = true;           # discard=async, continuous TRIM
+#   diskLayout.autodefrag = true;        # spinning disks (set when forge detects an HDD)
+#   diskLayout.bootLabel = "BOOT";       # ESP partition label
+#   diskLayout.rootLabel = "nixos";      # root partition label
+#
+# alongside.nix keeps its own nixos-boot / nixos-root labels, which forge
+# install creates the partitions with.
+#
+# Impermanence (the wizard's ephemeral root layouts, see impermanence.nix):
+#
+#   diskLayout.ephemeralRoot = "tmpfs";     # / is a tmpfs
+#   diskLayout.ephemeralRoot = "snapshot";  # @ is reset to @blank at boot
+{ config, lib, ... }:
+
+let
+  cfg = config.diskLayout;
+in
+{
+  imports = [ ./impermanence.nix ];
+
+  options.diskLayout = {
+    encrypt = lib.mkOption {
+      type = lib.types.bool;
+      default = true;
+      description = "Encrypt the disks (LUKS2, or native encryption on ZFS).";
+    };
+
+    subvolumes = lib.mkOption {
+      type = lib.types.attrsOf lib.types.str;
+      default = {
+        "@" = "/";
+        "@home" = "/home";
+        "@nix" = "/nix";
+        "@var-log" = "/var/log";
+      };
+      description = "Btrfs subvolumes on the root filesystem, as name = mountpoint.";
+    };
+
+    separateHome = lib.mkOption {
+      type = lib.types.bool;
+      default = cfg.rootSize != null;
+      defaultText = lib.literalExpression "rootSize != null";
+      description = "/home lives on its own disk (home-disk.nix) or partition (rootSize).";
+    };
+
+    espSize = lib.mkOption {
+      type = lib.types.str;
+      default = "2G";
+      description = "Size of the EFI system partition.";
+    };
+
+    rootSize = lib.mkOption {
+      type = lib.types.nullOr lib.types.str;
+      default = null;
+      example = "200G";
+      description = ''
+        Size of the root partition in default.nix. The rest of the disk becomes
+        a /home partition, encrypted like the root. null: root fills the disk
+        and /home is a subvolume.
+      '';
+    };
+
+    swapfileSize = lib.mkOption {
+      type = lib.types.nullOr lib.types.str;
+      default = null;
+      example = "8G";
+      description = "Size of a swapfile in an @swap subvolume mounted at /swap.";
+    };
+
+    swapPartitionSize = lib.mkOption {
+      type = lib.types.nullOr lib.types.str;
+      default = null;
+      example = "38G";
+      description = ''
+        Size of a swap partition on the main disk, used as the hibernation
+        resume device. Encrypted like the root and unlocked with its passphrase.
+      '';
+    };
+
+    swapSubvolume = lib.mkOption {
+      type = lib.types.attrsOf lib.types.anything;
+      readOnly = true;
+      internal = true;
+      default = lib.optionalAttrs (cfg.swapfileSize != null) {
+        "@swap" = {
+          mountpoint = "/swap";
+          swap.swapfile.size = cfg.swapfileSize;
+        };
+      };
+      description = "Disko subvolume for the swapfile, merged into the btrfs subvolumes.";
+    };
+
+    swapPartition = lib.mkOption {
+      type = lib.types.attrsOf lib.types.anything;
+      readOnly = true;
+      internal = true;
+      default = let
+        swap = {
+          type = "swap";
+          resumeDevice = true;
+        };
+      in lib.optionalAttrs (cfg.swapPartitionSize != null) {
+        swap = {
+          label = if cfg.encrypt then "cryptswap" else "swap";
+          size = cfg.swapPartitionSize;
+          content = if cfg.encrypt then {
+            type = "luks";
+            name = "cryptswap";
+            settings.allowDiscards = true;
+            content = swap;
+          } else swap;
+        };
+      };
+      description = "Disko GPT partition for the swap partition, merged into the main disk.";
+    };
+
+    compression = lib.mkOption {
+      type = lib.types.nullOr lib.types.str;
+      default = "zstd";
+      example = "zstd:6";
+      description = "Btrfs compress= mount option; null turns compression off.";
+    };
+
+    noatime = lib.mkOption {
+      type = lib.types.bool;
+      default = true;
+      description = "Mount btrfs with noatime, so reads don't write access times.";
+    };
+
+    discard = lib.mkOption {
+      type = lib.types.bool;
+      default = false;
+      description = "Mount btrfs with discard=async (continuous TRIM).";
+    };
+
+    autodefrag = lib.mkOption {
+      type = lib.types.bool;
+      default = false;
+      description = "Mount btrfs with autodefrag, worthwhile on spinning disks only.";
+    };
+
+    bootLabel = lib.mkOption {
+      type = lib.types.str;
+      default = "ESP";
+      description = "GPT label of the EFI system partition.";
+    };
+
+    rootLabel = lib.mkOption {
+      type = lib.types.str;
+      default = if cfg.encrypt then "cryptroot" else "root";
+      defaultText = lib.literalExpression ''if encrypt then "cryptroot" else "root"'';
+      description = "GPT label of the root partition (main disk).";
+    };
+
+    mountOptions = lib.mkOption {
+      type = lib.types.listOf lib.types.str;
+      readOnly = true;
+      internal = true;
+      default = lib.optional (cfg.compression != null) "compress=${cfg.compression}"
+        ++ lib.optional cfg.noatime "noatime"
+        ++ lib.optional cfg.discard "discard=async"
+        ++ lib.optional cfg.autodefrag "autodefrag";
+      description = "Btrfs mount options built from compression, noatime, discard and autodefrag.";
+    };
+
+    ephemeralRoot = lib.mkOption {
+      type = lib.types.nullOr (lib.types.enum [ "tmpfs" "snapshot" ]);
+      default = null;
+      description = ''
+        Wipe / at every boot, keeping state in the @persist subvolume: "tmpfs"
+        mounts / as a tmpfs, "snapshot" rolls @ back to an empty @blank
+        snapshot taken when the filesystem was created.
+      '';
+    };
+
+    postCreateHook = lib.mkOption {
+      type = lib.types.lines;
+      readOnly = true;
+      internal = true;
+      default = lib.optionalString (cfg.ephemeralRoot == "snapshot") ''
+        udevadm settle
+        MNTPOINT=$(mktemp -d)
+        mount -o subvol=/ /dev/disk/by-label/nixos "$MNTPOINT"
+        btrfs subvolume snapshot -r "$MNTPOINT/@" "$MNTPOINT/@blank"
+        umount "$MNTPOINT"
+        rmdir "$MNTPOINT"
+      '';
+      description = "Run by disko after creating the root btrfs: takes the @blank snapshot.";
+    };
+
+    rootSubvolumes = lib.mkOption {
+      type = lib.types.attrsOf lib.types.str;
+      readOnly = true;
+      internal = true;
+      default = lib.filterAttrs
+        (_: mountpoint: !(cfg.separateHome && mountpoint == "/home"))
+        cfg.subvolumes;
+      description = "Subvolumes created on the root filesystem.";
+    };
+  };
+}
@@ -0,0 +1,91 @@
+# Disko configuration for a ZFS root (UEFI only)
+# Partition layout:
+# - 2GB EFI partition (FAT32, /boot, diskLayout.espSize)
+# - Remaining space: ZFS pool "rpool" with native encryption (aes-256-gcm),
+#   unencrypted when diskLayout.encrypt is off (see subvolumes.nix)
+#
+# Datasets (legacy mountpoints, mounted through fileSystems):
+# - rpool/root  -> /
+# - rpool/home  -> /home
+# - rpool/nix   -> /nix
+# - rpool/log   -> /var/log
+#
+# The host default.nix must enable ZFS and set a unique networking.hostId
+# (the host wizard generates both). No swap - zram only (common.nix).
+{ config, lib, ... }:
+
+let
+  dataset = mountpoint: {
+    type = "zfs_fs";
+    inherit mountpoint;
+    options.mountpoint = "legacy";
+  };
+in
+{
+  imports = [ ./subvolumes.nix ];
+
+  disko.devices = {
+    disk.main = {
+      type = "disk";
+      # device is set by the host-specific module
+      content = {
+        type = "gpt";
+        partitions = {
+          ESP = {
+            label = config.diskLayout.bootLabel;
+            size = config.diskLayout.espSize;
+            type = "EF00";
+            content = {
+              type = "filesystem";
+              format = "vfat";
+              mountpoint = "/boot";
+              mountOptions = [ "umask=0077" "nofail" "x-systemd.device-timeout=30s" ];
+            };
+          };
+          zfs = {
+            label = "rpool";
+            size = "100%";
+            content = {
+              type = "zfs";
+              pool = "rpool";
+            };
+          };
+        };
+      };
+    };
+
+    zpool.rpool = {
+      type = "zpool";
+      options = {
+        ashift = "12";
+        autotrim = "on";
+      };
+      rootFsOptions = {
+        compression = "zstd";
+        acltype = "posixacl";
+        xattr = "sa";
+        atime = "off";
+        mountpoint = "none";
+        "com.sun:auto-snapshot" = "false";
+      } // lib.optionalAttrs config.diskLayout.encrypt {
+        # The installer writes the passphrase here; it is switched to a
+        # boot-time prompt as soon as the pool exists
+        encryption = "aes-256-gcm";
+        keyformat = "passphrase";
+        keylocation = "file:///tmp/luks-password";
+      };
+      postCreateHook = lib.optionalString config.diskLayout.encrypt ''
+        zfs set keylocation=prompt rpool
+      '';
+      datasets = {
+        root = dataset "/";
+        home = dataset "/home";
+        nix = dataset "/nix";
+        log = dataset "/var/log";
+      };
+    };
+  };
+
+  # Ensure /var/log is available early for boot logging
+  fileSystems."/var/log".neededForBoot = true;
+}
@@ -0,0 +1,11 @@
+# Fleet heartbeat option
+# Set per host with `fleet.reportState.enable = true;` - the timer lives in
+# home/forge-report-state.nix, reports are read by `forge fleet status`
+{ lib, ... }:
+
+{
+  options.fleet.reportState.enable = lib.mkEnableOption ''
+    a timer that runs `forge report-state`, pushing this host's generation and
+    config revision to the forge-state branch of the config repo
+  '';
+}
@@ -0,0 +1,54 @@
+# Forge background services, switched per host
+# Settings live in services.json at the repo root and are managed with
+# `forge enable-service` or the services screen; hosts without an entry use
+# the defaults below. The user timers are in home/forge-notify.nix and
+# home/forge-apps-backup.nix.
+{ config, lib, ... }:
+
+let
+  file = ../services.json;
+  settings = if builtins.pathExists file
+    then builtins.fromJSON (builtins.readFile file)
+    else { };
+  # Keep in sync with Service::default_enabled() in packages/forge/src/system/services.rs
+  host = {
+    notify = true;
+    auto_update = false;
+    apps_backup = false;
+  }
+  // ((settings.hosts or { }).${config.networking.hostName} or { });
+  cfg = config.forge.services;
+in
+{
+  options.forge.services = {
+    notify.enable = lib.mkOption {
+      type = lib.types.bool;
+      default = host.notify;
+      description = "Hourly forge-notify check for config, app profile and flake updates.";
+    };
+    autoUpdate.enable = lib.mkOption {
+      type = lib.types.bool;
+      default = host.auto_update;
+      description = ''
+        Nightly `nixos-rebuild boot` of this host from the main branch on
+        GitHub; the new generation becomes the default at the next reboot.
+      '';
+    };
+    appsBackup.enable = lib.mkOption {
+      type = lib.types.bool;
+      default = host.apps_backup;
+      description = "Daily `app-backup --push` of browser and Termius profiles.";
+    };
+  };
+
+  config = lib.mkIf cfg.autoUpdate.enable {
+    system.autoUpgrade = {
+      enable = true;
+      flake = "github:DigitalPals/nixos-config#${config.networking.hostName}";
+      # Never switch a running desktop underneath the user
+      operation = "boot";
+      dates = "04:00";
+      randomizedDelaySec = "45min";
+    };
+  };
+}
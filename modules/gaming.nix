@@ -1,20 +1,38 @@
 # Gaming configuration - Steam with Proton support
-{ config, pkgs, ... }:
+# Enabled per host with `gaming.enable = true;`
+{ config, pkgs, lib, ... }:
 
+let
+  cfg = config.gaming;
+in
 {
-  # Enable Steam
-  programs.steam = {
-    enable = true;
-    remotePlay.openFirewall = true;
-    dedicatedServer.openFirewall = true;
-  };
+  options.gaming.enable = lib.mkEnableOption "Steam, GameMode and gamescope";
+
+  config = lib.mkIf cfg.enable {
+    # Enable Steam
+    programs.steam = {
+      enable = true;
+      remotePlay.openFirewall = true;
+      dedicatedServer.openFirewall = true;
+      gamescopeSession.enable = true;
+    };
+
+    # GameMode for automatic performance optimization
+    programs.gamemode.enable = true;
 
-  # GameMode for automatic performance optimization
-  programs.gamemode.enable = true;
+    # Gamescope micro-compositor (upscaling, frame limiting)
+    programs.gamescope = {
+      enable = true;
+      capSysNice = true;
+    };
 
-  # Additional gaming packages
-  environment.systemPackages = with pkgs; [
-    protonup-qt    # Manage Proton-GE versions
-    mangohud       # FPS/performance overlay
-  ];
+    # 32-bit graphics libraries for Proton/Wine
+    hardware.graphics.enable32Bit = true;
+
+    # Additional gaming packages
+    environment.systemPackages = with pkgs; [
+      protonup-qt    # Manage Proton-GE versions
+      mangohud       # FPS/performance overlay
+    ];
+  };
 }
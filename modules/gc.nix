@@ -0,0 +1,29 @@
+# Garbage collection and store optimisation for every host
+# Settings live in gc.json at the repo root and are managed with `forge gc`;
+# entries under "hosts" override the defaults for one machine
+{ config, lib, ... }:
+
+let
+  file = ../gc.json;
+  settings = if builtins.pathExists file
+    then builtins.fromJSON (builtins.readFile file)
+    else { };
+  # Keep in sync with GcSettings::default() in packages/forge/src/system/gc.rs
+  gc = {
+    automatic = true;
+    dates = "weekly";
+    delete_older_than = "14d";
+    optimise_store = true;
+  }
+  // (settings.defaults or { })
+  // ((settings.hosts or { }).${config.networking.hostName} or { });
+in
+{
+  nix.gc = {
+    automatic = lib.mkDefault gc.automatic;
+    dates = lib.mkDefault gc.dates;
+    options = lib.mkDefault "--delete-older-than ${gc.delete_older_than}";
+  };
+
+  nix.settings.auto-optimise-store = lib.mkDefault gc.optimise_store;
+}
@@ -0,0 +1,43 @@
+# Impermanence: / is wiped at every boot (diskLayout.ephemeralRoot in the
+# host's disko config, see modules/disko/impermanence.nix)
+#
+# Hosts created with an ephemeral root layout import this next to their
+# environment.persistence."/persist" block, which lists the state to keep.
+# Passwords live in /etc/shadow, which NixOS rewrites on every activation
+# (so it can't be bind-mounted from /persist): it is restored from
+# /persist/etc/shadow before the users are set up and copied back whenever
+# it changes. forge install seeds it right after setting the passwords.
+{ inputs, lib, ... }:
+
+let
+  saveShadow = "install -D -m 0600 /etc/shadow /persist/etc/shadow";
+in
+{
+  imports = [ inputs.impermanence.nixosModules.impermanence ];
+
+  system.activationScripts.restoreShadow = ''
+    if [ -e /persist/etc/shadow ]; then
+      mkdir -p /etc
+      install -m 0600 /persist/etc/shadow /etc/shadow
+    fi
+  '';
+  system.activationScripts.users.deps = [ "restoreShadow" ];
+  system.activationScripts.saveShadow = lib.stringAfter [ "users" ] saveShadow;
+
+  # passwd at runtime
+  systemd.paths.persist-shadow = {
+    wantedBy = [ "multi-user.target" ];
+    pathConfig.PathChanged = "/etc/shadow";
+  };
+  systemd.services.persist-shadow = {
+    description = "Copy /etc/shadow to /persist";
+    serviceConfig.Type = "oneshot";
+    script = saveShadow;
+  };
+
+  # SSH host keys are read from /persist directly, no need to list them
+  services.openssh.hostKeys = [
+    { path = "/persist/etc/ssh/ssh_host_ed25519_key"; type = "ed25519"; }
+    { path = "/persist/etc/ssh/ssh_host_rsa_key"; type = "rsa"; bits = 4096; }
+  ];
+}
@@ -90,6 +90,7 @@ in
     # Disk utilities
     parted
     gptfdisk
+    os-prober    # finds Windows for dual-boot installs
     smartmontools
     nvme-cli
 
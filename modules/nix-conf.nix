@@ -0,0 +1,29 @@
+# nix.conf settings forge relies on, for every host
+# Settings live in nix-conf.json at the repo root and are managed with
+# `forge nix-conf`; entries under "hosts" override the defaults for one machine
+{ config, lib, ... }:
+
+let
+  file = ../nix-conf.json;
+  settings = if builtins.pathExists file
+    then builtins.fromJSON (builtins.readFile file)
+    else { };
+  # Keep in sync with NixConfSettings::default() in packages/forge/src/system/nix_conf.rs
+  nixConf = {
+    experimental_features = [ "nix-command" "flakes" ];
+    max_jobs = "auto";
+    cores = 0;
+    sandbox = true;
+  }
+  // (settings.defaults or { })
+  // ((settings.hosts or { }).${config.networking.hostName} or { });
+in
+{
+  nix.settings = {
+    experimental-features = lib.mkDefault nixConf.experimental_features;
+    max-jobs = lib.mkDefault
+      (if nixConf.max_jobs == "auto" then "auto" else lib.toInt nixConf.max_jobs);
+    cores = lib.mkDefault nixConf.cores;
+    sandbox = lib.mkDefault nixConf.sandbox;
+  };
+}
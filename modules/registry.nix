@@ -0,0 +1,14 @@
+# Flake registry pins shared by every host
+# Entries live in registry.json at the repo root and are managed with
+# `forge registry`; each host picks up changes on its next rebuild
+{ lib, ... }:
+
+let
+  file = ../registry.json;
+  entries = if builtins.pathExists file
+    then builtins.fromJSON (builtins.readFile file)
+    else { };
+in
+{
+  nix.registry = lib.mapAttrs (_: entry: { to = entry.to; }) entries;
+}
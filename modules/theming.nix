@@ -0,0 +1,33 @@
+# Theming preset option (fonts, cursor, GTK/Qt themes)
+# Set per host with `theming.preset = "...";` - applied by home/theming.nix
+{ config, pkgs, lib, ... }:
+
+let
+  preset = config.theming.preset;
+in
+{
+  options.theming.preset = lib.mkOption {
+    type = lib.types.enum [ "default" "simple" "dark" ];
+    default = "default";
+    description = ''
+      Look and feel preset:
+      - default: the shell's own theming (Noctalia/Illogical colours)
+      - simple: light Adwaita, larger fonts and cursor (family machines)
+      - dark: Adwaita dark with Inter and Bibata cursor
+    '';
+  };
+
+  config = lib.mkMerge [
+    (lib.mkIf (preset == "simple") {
+      fonts.packages = [ pkgs.noto-fonts ];
+      fonts.fontconfig.defaultFonts = {
+        sansSerif = [ "Noto Sans" ];
+        serif = [ "Noto Serif" ];
+      };
+    })
+    (lib.mkIf (preset == "dark") {
+      fonts.packages = [ pkgs.inter ];
+      fonts.fontconfig.defaultFonts.sansSerif = [ "Inter" ];
+    })
+  ];
+}
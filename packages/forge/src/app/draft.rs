@@ -0,0 +1,83 @@
+//! Create-host wizard drafts
+//!
+//! Once the hostname is entered, every step of the create-host wizard is
+//! saved to the forge data directory, so quitting (or a crash) doesn't lose
+//! the answers. The next launch offers to resume the draft from the main
+//! menu. Leaving the wizard or generating the host deletes it.
+
+use super::{App, AppMode, CreateHostState};
+use crate::constants::host_draft_path;
+
+/// Saved wizard state, if there is one to resume
+pub fn load_host_draft() -> Option<CreateHostState> {
+    let content = std::fs::read_to_string(host_draft_path()).ok()?;
+    parse_host_draft(&content)
+}
+
+fn parse_host_draft(content: &str) -> Option<CreateHostState> {
+    let state: CreateHostState = serde_json::from_str(content).ok()?;
+    state.draft_config().is_some().then_some(state)
+}
+
+fn save_host_draft(json: &str) {
+    let path = host_draft_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Err(e) = std::fs::write(&path, json) {
+        tracing::warn!("Failed to save host draft: {}", e);
+    }
+}
+
+pub fn clear_host_draft() {
+    let path = host_draft_path();
+    if path.exists() {
+        if let Err(e) = std::fs::remove_file(&path) {
+            tracing::warn!("Failed to remove host draft: {}", e);
+        }
+    }
+}
+
+impl App {
+    /// Save the wizard after a key press, or delete the draft once it was left
+    pub fn sync_host_draft(&mut self) {
+        match &self.mode {
+            AppMode::CreateHost(state) if state.draft_config().is_some() => {
+                let Ok(json) = serde_json::to_string(state) else {
+                    return;
+                };
+                if self.host_draft_json.as_deref() != Some(json.as_str()) {
+                    save_host_draft(&json);
+                    self.host_draft_json = Some(json);
+                }
+            }
+            // Steps before the hostname keep the last draft
+            AppMode::CreateHost(_) => {}
+            _ => {
+                if self.host_draft_json.take().is_some() {
+                    clear_host_draft();
+                }
+            }
+        }
+    }
+
+    /// Forget the draft for good (the host was generated, or the user discarded it)
+    pub(super) fn discard_host_draft(&mut self) {
+        self.host_draft = None;
+        self.host_draft_json = None;
+        clear_host_draft();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_host_draft_only_resumable_steps() {
+        assert!(parse_host_draft("not json").is_none());
+
+        let early = serde_json::to_string(&CreateHostState::DetectingHardware).unwrap();
+        assert!(parse_host_draft(&early).is_none());
+    }
+}
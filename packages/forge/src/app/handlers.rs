@@ -7,8 +7,34 @@ use std::mem;
 use super::state::*;
 use super::App;
 use crate::commands;
-use crate::constants::MAX_INPUT_LENGTH;
-use crate::system::hardware::{CpuInfo, CpuVendor, FormFactor, GpuInfo, GpuVendor};
+use crate::commands::generations::GenerationsOp;
+use crate::commands::registry::RegistryOp;
+use crate::commands::update::{Activation, UpdateOptions};
+use crate::constants::{MAX_INPUT_LENGTH, MAX_KEYS_INPUT_LENGTH};
+use crate::system::bisect::Verdict;
+use crate::system::disk::erase::detect_erase_method;
+use crate::system::disk::health::check_disk_health;
+use crate::system::disk::layout::{
+    host_swap, read_host_disko, supports_size_editing, DiskSwap, PartitionLayout,
+};
+use crate::system::disk::identity::check_disk_drift;
+use crate::system::disk::{
+    detect_storage_kind, generate_host_id, validate_disk_roles, DiskAssignment, DiskInfo, DiskMode,
+    DiskRole, MIN_ALONGSIDE_BYTES,
+};
+use crate::system::hardware::{
+    detect_boot_mode, detect_initrd_modules, detect_memory_gib, detect_secure_boot, detect_tpm2,
+    BootMode, CpuInfo, CpuVendor, FormFactor, GpuInfo, GpuVendor, SecureBootSupport,
+};
+use crate::system::locale::LocaleSettings;
+use crate::system::log_bundle::BundleSources;
+use crate::system::nix_settings;
+use crate::system::preflight::{has_blocking_failures, network_failed};
+use crate::system::gc::{GcConfig, GcField};
+use crate::system::nix_conf::{NixConfConfig, NixConfField};
+use crate::system::parallelism::{save_parallelism, suggest};
+use crate::system::optimise::format_bytes;
+use crate::system::registry::validate_registry_name;
 
 impl App {
     /// Handle keyboard input
@@ -27,16 +53,86 @@ impl App {
             return Ok(());
         }
 
+        // Approval of a flake.nix edit; the command waits for the answer
+        if let Some(review) = &self.edit_review {
+            let last_line = review.diff.lines().count().saturating_sub(1);
+            match key {
+                KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    review.answer(true);
+                    self.edit_review = None;
+                }
+                KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
+                    review.answer(false);
+                    self.edit_review = None;
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.edit_review_scroll = self.edit_review_scroll.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.edit_review_scroll = (self.edit_review_scroll + 1).min(last_line);
+                }
+                KeyCode::PageUp => {
+                    self.edit_review_scroll = self.edit_review_scroll.saturating_sub(20);
+                }
+                KeyCode::PageDown => {
+                    self.edit_review_scroll = (self.edit_review_scroll + 20).min(last_line);
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Switch to a previewed system; the update waits for the answer
+        if let Some(preview) = &self.switch_preview {
+            let last_line = preview.diff.lines().count().saturating_sub(1);
+            match key {
+                KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    preview.answer(true);
+                    self.switch_preview = None;
+                }
+                KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {
+                    preview.answer(false);
+                    self.switch_preview = None;
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.switch_preview_scroll = self.switch_preview_scroll.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.switch_preview_scroll = (self.switch_preview_scroll + 1).min(last_line);
+                }
+                KeyCode::PageUp => {
+                    self.switch_preview_scroll = self.switch_preview_scroll.saturating_sub(20);
+                }
+                KeyCode::PageDown => {
+                    self.switch_preview_scroll = (self.switch_preview_scroll + 20).min(last_line);
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Resume prompt for a create-host wizard left unfinished
+        if let Some(draft) = self.host_draft.take() {
+            match key {
+                KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
+                    self.mode = AppMode::CreateHost(draft);
+                }
+                KeyCode::Char('d') => self.discard_host_draft(),
+                // Keep the file, so the prompt comes back next launch
+                KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => {}
+                _ => self.host_draft = Some(draft),
+            }
+            return Ok(());
+        }
+
         // Handle commit list view
         if self.pending_updates.viewing_commits {
             match key {
-                KeyCode::Up | KeyCode::Char('k') => {
-                    if self.pending_updates.selected_commit > 0 {
-                        self.pending_updates.selected_commit -= 1;
-                        // Scroll up if selection above visible area
-                        if self.pending_updates.selected_commit < self.pending_updates.commit_scroll {
-                            self.pending_updates.commit_scroll = self.pending_updates.selected_commit;
-                        }
+                KeyCode::Up | KeyCode::Char('k') if self.pending_updates.selected_commit > 0 => {
+                    self.pending_updates.selected_commit -= 1;
+                    // Scroll up if selection above visible area
+                    if self.pending_updates.selected_commit < self.pending_updates.commit_scroll {
+                        self.pending_updates.commit_scroll = self.pending_updates.selected_commit;
                     }
                 }
                 KeyCode::Down | KeyCode::Char('j') => {
@@ -70,10 +166,8 @@ impl App {
         // Handle update dialog
         if self.pending_updates.has_updates() {
             match key {
-                KeyCode::Up | KeyCode::Char('k') => {
-                    if self.pending_updates.selected > 0 {
-                        self.pending_updates.selected -= 1;
-                    }
+                KeyCode::Up | KeyCode::Char('k') if self.pending_updates.selected > 0 => {
+                    self.pending_updates.selected -= 1;
                 }
                 KeyCode::Down | KeyCode::Char('j') => {
                     let max = get_update_dialog_option_count(&self.pending_updates) - 1;
@@ -92,9 +186,62 @@ impl App {
             return Ok(());
         }
 
+        // Handle failed build log viewer
+        if self.build_logs.viewing.is_some() {
+            match key {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.build_logs.scroll = self.build_logs.scroll.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => self.build_logs.scroll_down(1),
+                KeyCode::PageUp => {
+                    self.build_logs.scroll = self.build_logs.scroll.saturating_sub(20);
+                }
+                KeyCode::PageDown => self.build_logs.scroll_down(20),
+                KeyCode::Tab => self.build_logs.next_log(),
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('l') => {
+                    self.build_logs.viewing = None;
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+        if key == KeyCode::Char('l') && self.build_log_available() {
+            self.build_logs.open();
+            return Ok(());
+        }
+
+        // Handle flake input changelog viewer
+        if self.changelog.viewing.is_some() {
+            match key {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.changelog.scroll = self.changelog.scroll.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => self.changelog.scroll_down(1),
+                KeyCode::PageUp => {
+                    self.changelog.scroll = self.changelog.scroll.saturating_sub(20);
+                }
+                KeyCode::PageDown => self.changelog.scroll_down(20),
+                KeyCode::Tab | KeyCode::Right => self.changelog.select(true),
+                KeyCode::BackTab | KeyCode::Left => self.changelog.select(false),
+                KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('c') => {
+                    self.changelog.viewing = None;
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+        if key == KeyCode::Char('c') && self.changelog_available() {
+            self.changelog.open();
+            return Ok(());
+        }
+        if key == KeyCode::Char('e') && self.report_available() {
+            self.export_report();
+            return Ok(());
+        }
+
         // Global quit
         if matches!(key, KeyCode::Char('q') | KeyCode::Char('Q'))
-            && matches!(
+            && (matches!(
                 self.mode,
                 AppMode::MainMenu { .. }
                     | AppMode::Apps(AppProfileState::Menu { .. })
@@ -102,9 +249,26 @@ impl App {
                     | AppMode::Apps(AppProfileState::Status { .. })
                     | AppMode::Keys(KeysState::Complete { .. })
                     | AppMode::Update(UpdateState::Complete { .. })
+                    | AppMode::Fleet(FleetState::Complete { .. })
+                    | AppMode::Fleet(FleetState::Status { .. })
+                    | AppMode::Pipeline(PipelineState::Menu { .. })
+                    | AppMode::Pipeline(PipelineState::Complete { .. })
+                    | AppMode::Optimise(OptimiseState::Complete { .. })
+                    | AppMode::Rollback(RollbackState::Select { confirm: None, .. })
+                    | AppMode::Rollback(RollbackState::Complete { .. })
+                    | AppMode::Generations(GenerationsState { confirm: None, .. })
+                    | AppMode::Channels(_)
+                    | AppMode::Services(_)
+                    | AppMode::DevShells(_)
+                    | AppMode::Registry(RegistryState { form: None, .. })
+                    | AppMode::Gc(GcState { input: None, .. })
+                    | AppMode::NixConf(NixConfState { input: None, .. })
+                    | AppMode::Parallelism(_)
                     | AppMode::Install(InstallState::Complete { .. })
                     | AppMode::CreateHost(CreateHostState::Complete { .. })
-            )
+                    | AppMode::Bootstrap(BootstrapState::Prompt { .. })
+                    | AppMode::Bootstrap(BootstrapState::Complete { .. })
+            ) || matches!(&self.mode, AppMode::Bisect(state) if !state.building()))
         {
             self.show_exit_confirm = true;
             return Ok(());
@@ -126,6 +290,12 @@ impl App {
             AppMode::Apps(AppProfileState::Menu { selected }) => {
                 Some(("browser_menu", *selected, None, None))
             }
+            AppMode::Install(InstallState::Preflight { .. }) => {
+                Some(("install_preflight", 0, None, None))
+            }
+            AppMode::Install(InstallState::NetworkSetup { .. }) => {
+                Some(("install_network", 0, None, None))
+            }
             AppMode::Install(InstallState::SelectHost { selected }) => {
                 Some(("install_host", *selected, None, None))
             }
@@ -133,20 +303,54 @@ impl App {
                 host,
                 disks,
                 selected,
+                ..
             }) => Some((
                 "install_disk",
                 *selected,
                 Some(host.clone()),
                 Some(disks.clone()),
             )),
+            AppMode::Install(InstallState::AssignRoles { .. }) => {
+                Some(("install_roles", 0, None, None))
+            }
+            AppMode::Update(UpdateState::PickOverride(_)) => {
+                Some(("update_override", 0, None, None))
+            }
+            AppMode::Update(UpdateState::PickActivation { selected, .. }) => {
+                Some(("update_activation", *selected, None, None))
+            }
+            AppMode::Install(InstallState::ConfirmExistingData { .. }) => {
+                Some(("install_existing_data", 0, None, None))
+            }
+            AppMode::Install(InstallState::EditPartitions { .. }) => {
+                Some(("install_partitions", 0, None, None))
+            }
             AppMode::Install(InstallState::EnterCredentials { host, .. }) => {
                 Some(("install_credentials", 0, Some(host.clone()), None))
             }
+            AppMode::Install(InstallState::SelectLocale { .. }) => {
+                Some(("install_locale", 0, None, None))
+            }
             AppMode::Install(InstallState::Overview { host, disk: _, .. }) => {
                 Some(("install_overview", 0, Some(host.clone()), None))
             }
+            AppMode::Keys(KeysState::Complete {
+                offer_github: true, ..
+            }) if key == KeyCode::Char('g') => Some(("keys_github", 0, None, None)),
+            AppMode::Install(InstallState::Complete { host, .. }) if key == KeyCode::Char('b') => {
+                Some(("install_log_bundle", 0, host.clone(), None))
+            }
+            AppMode::Install(InstallState::Complete { retry: Some(_), .. })
+                if matches!(key, KeyCode::Char('s') | KeyCode::Char('r')) =>
+            {
+                Some(("install_rescue", 0, None, None))
+            }
             AppMode::Install(InstallState::Complete { .. })
             | AppMode::Update(UpdateState::Complete { .. })
+            | AppMode::Fleet(FleetState::Complete { .. })
+            | AppMode::Pipeline(PipelineState::Complete { .. })
+            | AppMode::Optimise(OptimiseState::Complete { .. })
+            | AppMode::Rollback(RollbackState::Complete { .. })
             | AppMode::Apps(AppProfileState::Complete { .. })
             | AppMode::Keys(KeysState::Complete { .. }) => match key {
                 KeyCode::Enter => Some(("complete", 0, None, None)),
@@ -161,6 +365,41 @@ impl App {
                 }
             }
             AppMode::CreateHost(_) => Some(("create_host", 0, None, None)),
+            AppMode::Fleet(FleetState::Status { report }) => match key {
+                KeyCode::Enter => Some(("complete", 0, None, None)),
+                // Don't start a second load while one is in flight
+                KeyCode::Char('r') if report.is_some() => Some(("fleet_refresh", 0, None, None)),
+                _ => None,
+            },
+            AppMode::Pipeline(PipelineState::Menu { selected, .. }) => {
+                Some(("pipeline_menu", *selected, None, None))
+            }
+            AppMode::DevShells(DevShellState {
+                shells, selected, ..
+            }) => match key {
+                KeyCode::Char('r') if shells.is_some() => Some(("devshells_refresh", 0, None, None)),
+                _ => Some(("devshells", *selected, None, None)),
+            },
+            AppMode::Registry(_) => Some(("registry", 0, None, None)),
+            AppMode::Gc(_) => Some(("gc", 0, None, None)),
+            AppMode::NixConf(_) => Some(("nix_conf", 0, None, None)),
+            AppMode::Parallelism(_) => Some(("parallelism", 0, None, None)),
+            AppMode::Rollback(RollbackState::Select { .. }) => Some(("rollback", 0, None, None)),
+            AppMode::Generations(_) => Some(("generations", 0, None, None)),
+            AppMode::Channels(_) => Some(("channels", 0, None, None)),
+            AppMode::Services(_) => Some(("services", 0, None, None)),
+            AppMode::Keys(KeysState::EnterPassphrase { .. }) => {
+                Some(("keys_passphrase", 0, None, None))
+            }
+            AppMode::Bisect(_) => Some(("bisect", 0, None, None)),
+            AppMode::Bootstrap(BootstrapState::Prompt { selected }) => {
+                Some(("bootstrap_prompt", *selected, None, None))
+            }
+            AppMode::Bootstrap(BootstrapState::Complete { .. }) => match key {
+                KeyCode::Enter => Some(("bootstrap_done", 0, None, None)),
+                KeyCode::Up | KeyCode::Down => Some(("scroll", 0, None, None)),
+                _ => None,
+            },
             _ => None,
         };
 
@@ -171,6 +410,12 @@ impl App {
             Some(("browser_menu", selected, _, _)) => {
                 self.handle_app_menu_key(key, selected).await?;
             }
+            Some(("install_preflight", _, _, _)) => {
+                self.handle_install_preflight_key(key).await?;
+            }
+            Some(("install_network", _, _, _)) => {
+                self.handle_network_setup_key(key).await?;
+            }
             Some(("install_host", selected, _, _)) => {
                 self.handle_install_host_key(key, selected).await?;
             }
@@ -178,9 +423,27 @@ impl App {
                 self.handle_install_disk_key(key, &host, &disks, selected)
                     .await?;
             }
+            Some(("install_roles", _, _, _)) => {
+                self.handle_install_roles_key(key).await?;
+            }
+            Some(("update_override", _, _, _)) => {
+                self.handle_update_override_key(key).await?;
+            }
+            Some(("update_activation", selected, _, _)) => {
+                self.handle_update_activation_key(key, selected).await?;
+            }
+            Some(("install_existing_data", _, _, _)) => {
+                self.handle_existing_data_key(key);
+            }
+            Some(("install_partitions", _, _, _)) => {
+                self.handle_partitions_key(key);
+            }
             Some(("install_credentials", _, Some(host), _)) => {
                 self.handle_credentials_key(key, &host).await?;
             }
+            Some(("install_locale", _, _, _)) => {
+                self.handle_locale_key(key);
+            }
             Some(("install_overview", _, Some(host), _)) => {
                 self.handle_overview_key_action(key, &host).await?;
             }
@@ -196,6 +459,76 @@ impl App {
             Some(("create_host", _, _, _)) => {
                 self.handle_create_host_key(key).await?;
             }
+            Some(("fleet_refresh", _, _, _)) => {
+                self.mode = AppMode::Fleet(FleetState::new_status());
+                self.start_initial_command().await?;
+            }
+            Some(("pipeline_menu", selected, _, _)) => {
+                self.handle_pipeline_menu_key(key, selected).await?;
+            }
+            Some(("devshells", selected, _, _)) => {
+                self.handle_devshells_key(key, selected);
+            }
+            Some(("devshells_refresh", _, _, _)) => {
+                self.mode = AppMode::DevShells(DevShellState::new());
+                self.start_initial_command().await?;
+            }
+            Some(("registry", _, _, _)) => {
+                self.handle_registry_key(key).await?;
+            }
+            Some(("gc", _, _, _)) => {
+                self.handle_gc_key(key).await?;
+            }
+            Some(("nix_conf", _, _, _)) => {
+                self.handle_nix_conf_key(key).await?;
+            }
+            Some(("parallelism", _, _, _)) => {
+                self.handle_parallelism_key(key);
+            }
+            Some(("rollback", _, _, _)) => {
+                self.handle_rollback_key(key).await?;
+            }
+            Some(("generations", _, _, _)) => {
+                self.handle_generations_key(key).await?;
+            }
+            Some(("channels", _, _, _)) => {
+                self.handle_channels_key(key).await?;
+            }
+            Some(("services", _, _, _)) => {
+                self.handle_services_key(key).await?;
+            }
+            Some(("keys_passphrase", _, _, _)) => {
+                self.handle_keys_passphrase_key(key).await?;
+            }
+            Some(("keys_github", _, _, _)) => {
+                self.mode = AppMode::Keys(KeysState::new_github(false));
+                self.start_initial_command().await?;
+            }
+            Some(("install_log_bundle", _, host, _)) => {
+                if let Some(tx) = &self.cmd_tx {
+                    let sources = BundleSources {
+                        host,
+                        screen_log: self.screen_log_path.clone(),
+                        recording: self.recording_path.clone(),
+                        build_logs: self.build_logs.saved.iter().map(|l| l.path.clone()).collect(),
+                    };
+                    commands::log_bundle::start_log_bundle(tx.clone(), sources).await?;
+                    self.append_output("Saving install logs...");
+                }
+            }
+            Some(("install_rescue", _, _, _)) => {
+                self.handle_install_rescue_key(key).await?;
+            }
+            Some(("bisect", _, _, _)) => {
+                self.handle_bisect_key(key).await?;
+            }
+            Some(("bootstrap_prompt", selected, _, _)) => {
+                self.handle_bootstrap_prompt_key(key, selected).await?;
+            }
+            Some(("bootstrap_done", _, _, _)) => {
+                // Launched in its own terminal on login, so there is no menu to return to
+                self.should_quit = true;
+            }
             _ => {}
         }
 
@@ -260,6 +593,101 @@ impl App {
             KeyCode::Enter => {
                 self.handle_main_menu_select(current_selected).await?;
             }
+            KeyCode::Char('o') if current_selected == 1 => {
+                // Update with a flake input pointed at a local checkout
+                self.mode = AppMode::Update(UpdateState::PickOverride(OverridePicker::new()));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_update_override_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Update(UpdateState::PickOverride(picker)) = &mut self.mode else {
+            return Ok(());
+        };
+
+        // First pick the input
+        let Some(input) = picker.input.clone() else {
+            let count = picker.inputs.as_ref().map_or(0, Vec::len);
+            match key {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    picker.selected = picker.selected.saturating_sub(1);
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    picker.selected = (picker.selected + 1).min(count.saturating_sub(1));
+                }
+                KeyCode::Enter => {
+                    if let Ok(inputs) = &picker.inputs {
+                        picker.input = inputs.get(picker.selected).cloned();
+                    }
+                }
+                _ => {}
+            }
+            return Ok(());
+        };
+
+        // Then browse to the checkout that replaces it
+        let mut chosen = None;
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                picker.dir_selected = picker.dir_selected.saturating_sub(1);
+                picker.error = None;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                picker.dir_selected =
+                    (picker.dir_selected + 1).min(picker.entries.len().saturating_sub(1));
+                picker.error = None;
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                if let Some(entry) = picker.entries.get(picker.dir_selected) {
+                    let dir = picker.dir.join(&entry.name);
+                    picker.open(dir);
+                }
+            }
+            KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => {
+                if let Some(parent) = picker.dir.parent() {
+                    let parent = parent.to_path_buf();
+                    picker.open(parent);
+                }
+            }
+            KeyCode::Char(' ') => match picker.entries.get(picker.dir_selected) {
+                Some(entry) if entry.is_flake => chosen = Some(picker.dir.join(&entry.name)),
+                Some(entry) => picker.error = Some(format!("{} has no flake.nix", entry.name)),
+                None => {}
+            },
+            _ => {}
+        }
+
+        if let Some(path) = chosen {
+            let overrides = vec![commands::update::flake::InputOverride { input, path }];
+            self.mode = AppMode::Update(UpdateState::with_overrides(overrides));
+            self.start_initial_command().await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_update_activation_key(&mut self, key: KeyCode, selected: usize) -> Result<()> {
+        let last = Activation::ALL.len() - 1;
+        let AppMode::Update(UpdateState::PickActivation { selected: cursor, .. }) = &mut self.mode
+        else {
+            return Ok(());
+        };
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                *cursor = selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                *cursor = (selected + 1).min(last);
+            }
+            KeyCode::Enter => {
+                let options = UpdateOptions {
+                    activation: Activation::ALL[selected.min(last)],
+                    ..Default::default()
+                };
+                self.mode = AppMode::Update(UpdateState::with_options(Vec::new(), options));
+                self.start_initial_command().await?;
+            }
             _ => {}
         }
         Ok(())
@@ -289,21 +717,63 @@ impl App {
                         success: false,
                         output,
                         scroll_offset: None,
+                        host: None,
+                        retry: None,
                     });
                 } else {
-                    self.mode = AppMode::Install(InstallState::SelectHost { selected: 0 });
+                    self.mode = AppMode::Install(InstallState::Preflight { checks: None });
+                    self.start_initial_command().await?;
                 }
             }
             1 => {
-                // Update
-                self.mode = AppMode::Update(UpdateState::new());
-                self.start_initial_command().await?;
+                // Update, after asking how to activate the result
+                self.mode = AppMode::Update(UpdateState::pick_activation());
             }
             2 => {
                 // App profiles
                 self.mode = AppMode::Apps(AppProfileState::Menu { selected: 0 });
             }
             3 => {
+                // Dev shells
+                self.mode = AppMode::DevShells(DevShellState::new());
+                self.start_initial_command().await?;
+            }
+            4 => {
+                // Flake registry
+                self.mode = AppMode::Registry(RegistryState::new());
+                self.start_initial_command().await?;
+            }
+            5 => {
+                // Garbage collection
+                self.mode = AppMode::Gc(GcState::new());
+                self.start_initial_command().await?;
+            }
+            6 => {
+                // Roll back to an earlier generation
+                self.mode = AppMode::Rollback(RollbackState::new());
+                self.start_initial_command().await?;
+            }
+            7 => {
+                // Closure sizes, deleting generations, the boot default
+                self.mode = AppMode::Generations(GenerationsState::new());
+                self.start_initial_command().await?;
+            }
+            8 => {
+                // Which nixpkgs each host follows
+                self.mode = AppMode::Channels(ChannelsState::new());
+                self.start_initial_command().await?;
+            }
+            9 => {
+                // forge-notify, auto-update and the app backup timer
+                self.mode = AppMode::Services(ServicesState::new());
+                self.start_initial_command().await?;
+            }
+            10 => {
+                // experimental-features, max-jobs, cores and sandbox (nix-conf.json)
+                self.mode = AppMode::NixConf(NixConfState::new());
+                self.start_initial_command().await?;
+            }
+            11 => {
                 // Exit
                 self.should_quit = true;
             }
@@ -332,6 +802,26 @@ impl App {
                 scroll_offset,
                 ..
             })
+            | AppMode::Fleet(FleetState::Complete {
+                output,
+                scroll_offset,
+                ..
+            })
+            | AppMode::Pipeline(PipelineState::Complete {
+                output,
+                scroll_offset,
+                ..
+            })
+            | AppMode::Optimise(OptimiseState::Complete {
+                output,
+                scroll_offset,
+                ..
+            })
+            | AppMode::Rollback(RollbackState::Complete {
+                output,
+                scroll_offset,
+                ..
+            })
             | AppMode::Apps(AppProfileState::Complete {
                 output,
                 scroll_offset,
@@ -341,6 +831,11 @@ impl App {
                 output,
                 scroll_offset,
                 ..
+            })
+            | AppMode::Bootstrap(BootstrapState::Complete {
+                output,
+                scroll_offset,
+                ..
             }) => {
                 // Calculate max scroll position (can't scroll past where last line is visible)
                 let max_scroll = output.len().saturating_sub(visible_height);
@@ -397,6 +892,11 @@ impl App {
                     self.start_initial_command().await?;
                 }
                 3 => {
+                    // First-run repo setup
+                    self.mode = AppMode::Apps(AppProfileState::new_init(None, true));
+                    self.start_initial_command().await?;
+                }
+                4 => {
                     // Back
                     self.mode = AppMode::MainMenu { selected: 2 };
                 }
@@ -407,32 +907,24 @@ impl App {
         Ok(())
     }
 
-    async fn handle_install_host_key(&mut self, key: KeyCode, selected: usize) -> Result<()> {
+    async fn handle_pipeline_menu_key(&mut self, key: KeyCode, selected: usize) -> Result<()> {
+        let AppMode::Pipeline(PipelineState::Menu {
+            pipelines,
+            selected: current,
+        }) = &mut self.mode
+        else {
+            return Ok(());
+        };
         match key {
             KeyCode::Up | KeyCode::Char('k') => {
-                if let AppMode::Install(InstallState::SelectHost { selected }) = &mut self.mode {
-                    *selected = selected.saturating_sub(1);
-                }
+                *current = current.saturating_sub(1);
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                if let AppMode::Install(InstallState::SelectHost { selected }) = &mut self.mode {
-                    // +1 for "New host configuration" option
-                    *selected = (*selected + 1).min(self.hosts.len());
-                }
+                *current = (*current + 1).min(pipelines.len().saturating_sub(1));
             }
             KeyCode::Enter => {
-                if selected == 0 {
-                    // "New host configuration" selected
-                    self.mode = AppMode::CreateHost(CreateHostState::new());
-                    self.start_initial_command().await?;
-                } else {
-                    // Existing host selected (index - 1 because of "New host" option)
-                    let host = self.hosts[selected - 1].name.clone();
-                    self.mode = AppMode::Install(InstallState::SelectDisk {
-                        host,
-                        disks: Vec::new(),
-                        selected: 0,
-                    });
+                if let Some(pipeline) = pipelines.get(selected).cloned() {
+                    self.mode = AppMode::Pipeline(PipelineState::new_running(pipeline));
                     self.start_initial_command().await?;
                 }
             }
@@ -441,39 +933,1127 @@ impl App {
         Ok(())
     }
 
-    async fn handle_install_disk_key(
-        &mut self,
-        key: KeyCode,
-        host: &str,
-        disks: &[crate::system::disk::DiskInfo],
-        selected: usize,
-    ) -> Result<()> {
+    fn handle_devshells_key(&mut self, key: KeyCode, selected: usize) {
+        let AppMode::DevShells(state) = &mut self.mode else {
+            return;
+        };
+        let Some(shells) = &state.shells else {
+            return;
+        };
         match key {
             KeyCode::Up | KeyCode::Char('k') => {
-                if let AppMode::Install(InstallState::SelectDisk { selected, .. }) = &mut self.mode
-                {
-                    *selected = selected.saturating_sub(1);
-                }
+                state.selected = selected.saturating_sub(1);
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                if let AppMode::Install(InstallState::SelectDisk { selected, disks, .. }) =
-                    &mut self.mode
-                {
-                    if !disks.is_empty() {
-                        *selected = (*selected + 1).min(disks.len() - 1);
+                state.selected = (selected + 1).min(shells.len().saturating_sub(1));
+            }
+            KeyCode::Enter => {
+                if let Some(shell) = shells.get(selected) {
+                    state.status = Some(match shell.launch() {
+                        Ok(()) => Ok(format!("Opened {}", shell.flake_ref())),
+                        Err(e) => {
+                            tracing::error!("Failed to open dev shell: {}", e);
+                            Err(e.to_string())
+                        }
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_registry_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Registry(state) = &mut self.mode else {
+            return Ok(());
+        };
+
+        if let Some(form) = &mut state.form {
+            match key {
+                KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => {
+                    form.field = match form.field {
+                        RegistryField::Name => RegistryField::Url,
+                        RegistryField::Url => RegistryField::Name,
+                    };
+                    form.error = None;
+                }
+                KeyCode::Char(c) => {
+                    let field = match form.field {
+                        RegistryField::Name => &mut form.name,
+                        RegistryField::Url => &mut form.url,
+                    };
+                    if field.len() < MAX_INPUT_LENGTH {
+                        field.push(c);
+                    }
+                    form.error = None;
+                }
+                KeyCode::Backspace => {
+                    match form.field {
+                        RegistryField::Name => form.name.pop(),
+                        RegistryField::Url => form.url.pop(),
+                    };
+                    form.error = None;
+                }
+                KeyCode::Enter => {
+                    let name = form.name.trim().to_string();
+                    let url = form.url.trim().to_string();
+                    if let Some(err) = validate_registry_name(&name) {
+                        form.error = Some(err);
+                    } else if url.is_empty() {
+                        form.error = Some("Flake reference is required".to_string());
+                    } else {
+                        state.form = None;
+                        self.run_registry_op(RegistryOp::Add { name, url }).await?;
+                    }
+                }
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Ignore keys while a change is being applied
+        let Some(Ok(rows)) = &state.rows else {
+            return Ok(());
+        };
+        let selected_name = state.selected_name().map(str::to_string);
+        let op = match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                state.selected = state.selected.saturating_sub(1);
+                None
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                state.selected = (state.selected + 1).min(rows.len().saturating_sub(1));
+                None
+            }
+            KeyCode::Char('a') => {
+                state.form = Some(RegistryForm::default());
+                None
+            }
+            KeyCode::Char('p') => selected_name.map(RegistryOp::Pin),
+            KeyCode::Char('u') => selected_name.map(RegistryOp::Unpin),
+            KeyCode::Char('d') => selected_name.map(RegistryOp::Remove),
+            KeyCode::Char('r') => {
+                self.mode = AppMode::Registry(RegistryState::new());
+                self.start_initial_command().await?;
+                None
+            }
+            _ => None,
+        };
+        if let Some(op) = op {
+            self.run_registry_op(op).await?;
+        }
+        Ok(())
+    }
+
+    async fn run_registry_op(&mut self, op: RegistryOp) -> Result<()> {
+        if let AppMode::Registry(state) = &mut self.mode {
+            state.rows = None;
+            state.status = None;
+        }
+        if let Some(tx) = &self.cmd_tx {
+            commands::registry::start_registry_op(tx.clone(), Some(op)).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_bisect_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Bisect(state) = &mut self.mode else {
+            return Ok(());
+        };
+        if state.finished() {
+            if key == KeyCode::Enter {
+                self.mode = AppMode::MainMenu { selected: 0 };
+            }
+            return Ok(());
+        }
+        if !state.awaiting_verdict() {
+            return Ok(());
+        }
+        let verdict = match key {
+            KeyCode::Char('g') => Verdict::Good,
+            KeyCode::Char('b') => Verdict::Bad,
+            KeyCode::Char('s') => Verdict::Skip,
+            _ => return Ok(()),
+        };
+        let (Some(Ok(bisection)), Some(index)) = (&mut state.bisection, state.testing.take())
+        else {
+            return Ok(());
+        };
+        bisection.mark(index, verdict);
+        let line = format!(
+            "{} marked {} - {} candidates left",
+            bisection.commits[index].short_sha(),
+            verdict,
+            bisection.candidates().len()
+        );
+        state.output.push_back(line);
+        self.start_initial_command().await
+    }
+
+    async fn handle_keys_passphrase_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Keys(KeysState::EnterPassphrase {
+            operation,
+            force,
+            prompt,
+        }) = &mut self.mode
+        else {
+            return Ok(());
+        };
+        match key {
+            KeyCode::Char(c) => prompt.push(c),
+            KeyCode::Backspace => prompt.pop(),
+            KeyCode::Tab | KeyCode::Up | KeyCode::Down => prompt.toggle_field(),
+            KeyCode::Enter => {
+                if let Some(passphrase) = prompt.submit() {
+                    self.mode = AppMode::Keys(KeysState::Running {
+                        operation: operation.clone(),
+                        output: std::collections::VecDeque::new(),
+                        force: *force,
+                        passphrase: Some(passphrase),
+                        attempts: prompt.attempts,
+                    });
+                    self.start_initial_command().await?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_gc_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Gc(state) = &mut self.mode else {
+            return Ok(());
+        };
+        // Ignore keys while loading or saving
+        let Some(Ok(config)) = &state.config else {
+            if key == KeyCode::Char('r') && state.config.is_some() {
+                self.mode = AppMode::Gc(GcState::new());
+                self.start_initial_command().await?;
+            }
+            return Ok(());
+        };
+        let host = state.host().map(str::to_string);
+        let field = state.field();
+        let mut edited = config.clone();
+
+        if let Some(input) = &mut state.input {
+            match key {
+                KeyCode::Char(c) => {
+                    if input.len() < MAX_INPUT_LENGTH {
+                        input.push(c);
+                    }
+                    state.input_error = None;
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                    state.input_error = None;
+                }
+                KeyCode::Enter => match edited.set_text(host.as_deref(), field, input) {
+                    Ok(()) => {
+                        state.input = None;
+                        self.save_gc_config(edited).await?;
+                    }
+                    Err(err) => state.input_error = Some(err),
+                },
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                state.selected = state.selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                state.selected = (state.selected + 1).min(GcField::ALL.len() - 1);
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab => {
+                state.scope = state.scope.saturating_sub(1);
+            }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => {
+                state.scope = (state.scope + 1).min(state.hosts.len());
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if field.is_toggle() {
+                    edited.toggle(host.as_deref(), field);
+                    self.save_gc_config(edited).await?;
+                } else {
+                    state.input = Some(edited.resolve(host.as_deref()).value(field));
+                    state.input_error = None;
+                }
+            }
+            KeyCode::Char('x') | KeyCode::Delete if edited.is_set(host.as_deref(), field) => {
+                edited.reset(host.as_deref(), field);
+                self.save_gc_config(edited).await?;
+            }
+            KeyCode::Char('r') => {
+                self.mode = AppMode::Gc(GcState::new());
+                self.start_initial_command().await?;
+            }
+            KeyCode::Char('c') => {
+                self.mode = AppMode::Optimise(OptimiseState::with_gc(None));
+                self.start_initial_command().await?;
+            }
+            KeyCode::Char('o') => {
+                self.mode = AppMode::Optimise(OptimiseState::new());
+                self.start_initial_command().await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_rollback_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Rollback(RollbackState::Select {
+            generations,
+            selected,
+            confirm,
+        }) = &mut self.mode
+        else {
+            return Ok(());
+        };
+
+        if let Some(target) = *confirm {
+            match key {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                    self.mode = AppMode::Rollback(RollbackState::new_running(target));
+                    self.start_initial_command().await?;
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') => *confirm = None,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        let Some(Ok(list)) = generations else {
+            if key == KeyCode::Char('r') && generations.is_some() {
+                self.mode = AppMode::Rollback(RollbackState::new());
+                self.start_initial_command().await?;
+            }
+            return Ok(());
+        };
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                *selected = selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                *selected = (*selected + 1).min(list.len().saturating_sub(1));
+            }
+            // Switching to the running generation would only re-activate it
+            KeyCode::Enter => {
+                if let Some(generation) = list.get(*selected).filter(|g| !g.current) {
+                    *confirm = Some(Some(generation.number));
+                }
+            }
+            KeyCode::Char('p') if list.len() > 1 => *confirm = Some(None),
+            KeyCode::Char('r') => {
+                self.mode = AppMode::Rollback(RollbackState::new());
+                self.start_initial_command().await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_generations_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Generations(state) = &mut self.mode else {
+            return Ok(());
+        };
+
+        if let Some(op) = &state.confirm {
+            match key {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                    let op = op.clone();
+                    self.run_generations_op(op).await?;
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') => state.confirm = None,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Ignore keys while a change is being applied
+        let Some(Ok(list)) = &state.generations else {
+            if key == KeyCode::Char('r') && state.generations.is_some() {
+                self.mode = AppMode::Generations(GenerationsState::new());
+                self.start_initial_command().await?;
+            }
+            return Ok(());
+        };
+        let len = list.len();
+        let current = state.selected_generation().filter(|g| g.current).map(|g| g.number);
+        let selected = state.selected_generation().map(|g| g.number);
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                state.selected = state.selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                state.selected = (state.selected + 1).min(len.saturating_sub(1));
+            }
+            // nix-env refuses to delete the generation the profile points at
+            KeyCode::Char(' ') => match (selected, current) {
+                (Some(number), None) => {
+                    if state.marked.contains(&number) {
+                        state.marked.remove(&number);
+                    } else {
+                        state.marked.insert(number);
+                    }
+                }
+                (Some(number), Some(_)) => {
+                    state.status = Some(Err(format!(
+                        "Generation {} is the current one and can't be deleted",
+                        number
+                    )));
+                }
+                _ => {}
+            },
+            KeyCode::Char('d') | KeyCode::Delete => {
+                if !state.marked.is_empty() {
+                    let numbers = state.marked.iter().rev().copied().collect();
+                    state.confirm = Some(GenerationsOp::Delete(numbers));
+                } else if let (Some(number), None) = (selected, current) {
+                    state.confirm = Some(GenerationsOp::Delete(vec![number]));
+                }
+            }
+            KeyCode::Char('b') => {
+                if let (Some(number), None) = (selected, current) {
+                    state.confirm = Some(GenerationsOp::SetBootDefault(number));
+                }
+            }
+            KeyCode::Char('r') => {
+                self.mode = AppMode::Generations(GenerationsState::new());
+                self.start_initial_command().await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn run_generations_op(&mut self, op: GenerationsOp) -> Result<()> {
+        if let AppMode::Generations(state) = &mut self.mode {
+            state.generations = None;
+            state.status = None;
+            state.confirm = None;
+        }
+        if let Some(tx) = &self.cmd_tx {
+            commands::generations::start_generations_op(tx.clone(), Some(op)).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_channels_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Channels(state) = &mut self.mode else {
+            return Ok(());
+        };
+        // Ignore keys while loading or moving a host
+        let Some(Ok(hosts)) = &state.hosts else {
+            if key == KeyCode::Char('r') && state.hosts.is_some() {
+                self.mode = AppMode::Channels(ChannelsState::new());
+                self.start_initial_command().await?;
+            }
+            return Ok(());
+        };
+        let len = hosts.len();
+        let selected = hosts.get(state.selected).cloned();
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                state.selected = state.selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                state.selected = (state.selected + 1).min(len.saturating_sub(1));
+            }
+            KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right => {
+                if let Some(host) = selected {
+                    state.hosts = None;
+                    state.status = None;
+                    let change = (host.host, host.channel.toggled());
+                    if let Some(tx) = &self.cmd_tx {
+                        commands::channels::start_channels_op(tx.clone(), Some(change)).await?;
+                    }
+                }
+            }
+            KeyCode::Char('r') => {
+                self.mode = AppMode::Channels(ChannelsState::new());
+                self.start_initial_command().await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_services_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Services(state) = &mut self.mode else {
+            return Ok(());
+        };
+        // Ignore keys while loading or saving a change
+        let Some(Ok(rows)) = &state.rows else {
+            if key == KeyCode::Char('r') && state.rows.is_some() {
+                self.mode = AppMode::Services(ServicesState::new());
+                self.start_initial_command().await?;
+            }
+            return Ok(());
+        };
+        let len = rows.len();
+        let selected = rows.get(state.selected).cloned();
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                state.selected = state.selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                state.selected = (state.selected + 1).min(len.saturating_sub(1));
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if let Some(row) = selected {
+                    state.rows = None;
+                    state.status = None;
+                    let change = (row.service, !row.configured);
+                    if let Some(tx) = &self.cmd_tx {
+                        commands::services::start_services_op(tx.clone(), Some(change)).await?;
+                    }
+                }
+            }
+            KeyCode::Char('r') => {
+                self.mode = AppMode::Services(ServicesState::new());
+                self.start_initial_command().await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn save_gc_config(&mut self, config: GcConfig) -> Result<()> {
+        if let AppMode::Gc(state) = &mut self.mode {
+            state.config = None;
+            state.status = None;
+        }
+        if let Some(tx) = &self.cmd_tx {
+            commands::gc::start_gc_op(tx.clone(), Some(config)).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_nix_conf_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::NixConf(state) = &mut self.mode else {
+            return Ok(());
+        };
+        // Ignore keys while loading or saving
+        let Some(Ok(config)) = &state.config else {
+            if key == KeyCode::Char('r') && state.config.is_some() {
+                self.mode = AppMode::NixConf(NixConfState::new());
+                self.start_initial_command().await?;
+            }
+            return Ok(());
+        };
+        let host = state.host().map(str::to_string);
+        let field = state.field();
+        let mut edited = config.clone();
+
+        if let Some(input) = &mut state.input {
+            match key {
+                KeyCode::Char(c) => {
+                    if input.len() < MAX_INPUT_LENGTH {
+                        input.push(c);
+                    }
+                    state.input_error = None;
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                    state.input_error = None;
+                }
+                KeyCode::Enter => match edited.set_text(host.as_deref(), field, input) {
+                    Ok(()) => {
+                        state.input = None;
+                        self.save_nix_conf_config(edited).await?;
+                    }
+                    Err(err) => state.input_error = Some(err),
+                },
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                state.selected = state.selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                state.selected = (state.selected + 1).min(NixConfField::ALL.len() - 1);
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab => {
+                state.scope = state.scope.saturating_sub(1);
+            }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => {
+                state.scope = (state.scope + 1).min(state.hosts.len());
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if field.is_toggle() {
+                    edited.toggle(host.as_deref(), field);
+                    self.save_nix_conf_config(edited).await?;
+                } else {
+                    state.input = Some(edited.resolve(host.as_deref()).value(field));
+                    state.input_error = None;
+                }
+            }
+            KeyCode::Char('x') | KeyCode::Delete if edited.is_set(host.as_deref(), field) => {
+                edited.reset(host.as_deref(), field);
+                self.save_nix_conf_config(edited).await?;
+            }
+            KeyCode::Char('r') => {
+                self.mode = AppMode::NixConf(NixConfState::new());
+                self.start_initial_command().await?;
+            }
+            KeyCode::Char('p') => {
+                self.mode = AppMode::Parallelism(ParallelismState::new());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_parallelism_key(&mut self, key: KeyCode) {
+        let AppMode::Parallelism(state) = &mut self.mode else {
+            return;
+        };
+        let threads = state.topology.threads.max(1);
+        // Jobs start at 1; cores at 0, every thread
+        let min = if state.selected == 0 { 1 } else { 0 };
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => state.selected = 0,
+            KeyCode::Down | KeyCode::Char('j') => state.selected = 1,
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('+') => {
+                let field = state.field_mut();
+                *field = Some(field.map_or(min, |v| (v + 1).min(threads)));
+                state.status = None;
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('-') => {
+                // Below the minimum the field is unset and nix.conf decides
+                let field = state.field_mut();
+                *field = field.filter(|v| *v > min).map(|v| v - 1);
+                state.status = None;
+            }
+            KeyCode::Char('x') | KeyCode::Delete => {
+                *state.field_mut() = None;
+                state.status = None;
+            }
+            KeyCode::Char('s') => {
+                state.value = suggest(state.topology, state.memory_gib);
+                state.status = None;
+            }
+            KeyCode::Enter => {
+                state.status = Some(
+                    save_parallelism(&state.host, state.value)
+                        .map(|()| format!("Saved for {} - used from the next rebuild", state.host))
+                        .map_err(|e| format!("{:#}", e)),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    async fn save_nix_conf_config(&mut self, config: NixConfConfig) -> Result<()> {
+        if let AppMode::NixConf(state) = &mut self.mode {
+            state.config = None;
+            state.status = None;
+        }
+        if let Some(tx) = &self.cmd_tx {
+            commands::nix_conf::start_nix_conf_op(tx.clone(), Some(config)).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle_bootstrap_prompt_key(&mut self, key: KeyCode, selected: usize) -> Result<()> {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                if let AppMode::Bootstrap(BootstrapState::Prompt { selected }) = &mut self.mode {
+                    *selected = selected.saturating_sub(1);
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let AppMode::Bootstrap(BootstrapState::Prompt { selected }) = &mut self.mode {
+                    *selected = (*selected + 1).min(BOOTSTRAP_MENU_ITEMS.len() - 1);
+                }
+            }
+            KeyCode::Enter => match selected {
+                0 => {
+                    self.mode = AppMode::Bootstrap(BootstrapState::new_running());
+                    self.start_initial_command().await?;
+                }
+                1 => {
+                    // No stamp written, so the prompt comes back on next login
+                    self.should_quit = true;
+                }
+                2 => {
+                    // If the stamp can't be written the prompt simply returns next login
+                    if let Err(e) = commands::bootstrap::mark_bootstrap_done() {
+                        tracing::warn!("Failed to dismiss bootstrap: {}", e);
+                    }
+                    self.should_quit = true;
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_install_preflight_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Install(InstallState::Preflight { checks: Some(checks) }) = &self.mode else {
+            // Checks still running
+            return Ok(());
+        };
+
+        match key {
+            KeyCode::Enter if !has_blocking_failures(checks) => {
+                self.mode = AppMode::Install(InstallState::SelectHost { selected: 0 });
+                self.start_initial_command().await?;
+            }
+            // Join a wireless network; the checks run again once connected
+            KeyCode::Char('w') | KeyCode::Char('W') if network_failed(checks) => {
+                self.mode = AppMode::Install(open_network_setup(InstallState::Preflight {
+                    checks: None,
+                }));
+                self.start_initial_command().await?;
+            }
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                self.mode = AppMode::Install(InstallState::Preflight { checks: None });
+                self.start_initial_command().await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// `s` (rescue shell) and `r` (retry) on the screen of a failed nixos-install
+    async fn handle_install_rescue_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Install(InstallState::Complete { output, retry, .. }) = &mut self.mode else {
+            return Ok(());
+        };
+        if key == KeyCode::Char('s') {
+            if let Some(InstallState::Running { credentials, .. }) = retry.as_deref() {
+                self.shell_request = Some(commands::install::rescue_shell(&credentials.username));
+            }
+            return Ok(());
+        }
+
+        // Back to the progress screen, with nixos-install running again
+        let Some(mut state) = retry.take() else {
+            return Ok(());
+        };
+        state.rerun_nixos_install(mem::take(output));
+        let InstallState::Running {
+            host,
+            disk,
+            credentials,
+            overview,
+            ..
+        } = state.as_ref()
+        else {
+            return Ok(());
+        };
+        let secure_boot = matches!(
+            overview.as_deref(),
+            Some(InstallState::Overview { secure_boot: true, .. })
+        );
+        let (host, disk, creds) = (host.clone(), disk.path.clone(), credentials.clone());
+        self.mode = AppMode::Install(*state);
+        self.error = None;
+        if let Some(tx) = &self.cmd_tx {
+            commands::install::start_install_retry(
+                tx.clone(),
+                &host,
+                &disk,
+                &creds.primary_user(),
+                &creds.password,
+                &creds.accounts(),
+                creds.encrypt,
+                secure_boot,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Back from the network setup: start the install over from its
+    /// overview, or run the preflight checks again
+    pub(super) async fn leave_network_setup(&mut self, resume: InstallState) -> Result<()> {
+        if matches!(resume, InstallState::Overview { .. }) {
+            return self.start_local_install(resume).await;
+        }
+        self.mode = AppMode::Install(resume);
+        self.start_initial_command().await
+    }
+
+    async fn handle_network_setup_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Install(InstallState::NetworkSetup {
+            resume,
+            networks: Some(networks),
+            selected,
+            passphrase,
+            connecting: connecting @ false,
+            error,
+        }) = &mut self.mode
+        else {
+            // Still scanning or connecting
+            return Ok(());
+        };
+
+        let mut connect = None;
+        if let Some(typed) = passphrase {
+            // The passphrase prompt takes the keys while it is open
+            match key {
+                KeyCode::Char(c) if typed.len() < MAX_INPUT_LENGTH => typed.push(c),
+                KeyCode::Backspace => {
+                    typed.pop();
+                }
+                KeyCode::Enter if !typed.is_empty() => {
+                    connect = networks.get(*selected).cloned().map(|n| (n, typed.clone()));
+                    *passphrase = None;
+                }
+                _ => {}
+            }
+        } else {
+            match key {
+                KeyCode::Up | KeyCode::Char('k') => *selected = selected.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') if *selected + 1 < networks.len() => {
+                    *selected += 1;
+                }
+                KeyCode::Enter => match networks.get(*selected) {
+                    Some(network) if network.secured => {
+                        *passphrase = Some(String::new());
+                        *error = None;
+                    }
+                    Some(network) => connect = Some((network.clone(), String::new())),
+                    None => {}
+                },
+                KeyCode::Char('r') | KeyCode::Char('R') => {
+                    self.mode = AppMode::Install(open_network_setup((**resume).clone()));
+                    return self.start_initial_command().await;
+                }
+                // Connected some other way (a cable): just try again
+                KeyCode::Char('s') | KeyCode::Char('S') => {
+                    let resume = (**resume).clone();
+                    return self.leave_network_setup(resume).await;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some((network, passphrase)) = connect {
+            *connecting = true;
+            *error = None;
+            if let Some(tx) = &self.cmd_tx {
+                commands::wifi::start_wifi_connect(tx.clone(), network, passphrase).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_install_host_key(&mut self, key: KeyCode, selected: usize) -> Result<()> {
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                if let AppMode::Install(InstallState::SelectHost { selected }) = &mut self.mode {
+                    *selected = selected.saturating_sub(1);
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let AppMode::Install(InstallState::SelectHost { selected }) = &mut self.mode {
+                    // +1 for "New host configuration" option
+                    *selected = (*selected + 1).min(self.hosts.len());
+                }
+            }
+            KeyCode::Enter => {
+                if selected == 0 {
+                    // "New host configuration" selected
+                    self.mode = AppMode::CreateHost(CreateHostState::new());
+                    self.start_initial_command().await?;
+                } else {
+                    // Existing host selected (index - 1 because of "New host" option)
+                    let host = self.hosts[selected - 1].name.clone();
+                    self.mode = AppMode::Install(InstallState::SelectDisk {
+                        host,
+                        disks: Vec::new(),
+                        selected: 0,
+                        disk_mode: DiskMode::Wipe,
+                        marked: Vec::new(),
+                        error: None,
+                    });
+                    self.start_initial_command().await?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_install_disk_key(
+        &mut self,
+        key: KeyCode,
+        host: &str,
+        disks: &[crate::system::disk::DiskInfo],
+        selected: usize,
+    ) -> Result<()> {
+        let AppMode::Install(InstallState::SelectDisk {
+            disk_mode,
+            marked,
+            error,
+            ..
+        }) = &mut self.mode
+        else {
+            return Ok(());
+        };
+        let disk_mode = *disk_mode;
+        // A single marked disk is used even if the cursor moved away from it
+        let marked = marked.clone();
+        let selected = match marked.as_slice() {
+            [only] => disks.iter().position(|d| &d.path == only).unwrap_or(selected),
+            _ => selected,
+        };
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                if let AppMode::Install(InstallState::SelectDisk { selected, error, .. }) =
+                    &mut self.mode
+                {
+                    *selected = selected.saturating_sub(1);
+                    *error = None;
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if let AppMode::Install(InstallState::SelectDisk {
+                    selected,
+                    disks,
+                    error,
+                    ..
+                }) = &mut self.mode
+                {
+                    if !disks.is_empty() {
+                        *selected = (*selected + 1).min(disks.len() - 1);
+                    }
+                    *error = None;
+                }
+            }
+            KeyCode::Tab | KeyCode::Char('a') => {
+                if let AppMode::Install(InstallState::SelectDisk { disk_mode, error, .. }) =
+                    &mut self.mode
+                {
+                    *disk_mode = match disk_mode {
+                        DiskMode::Wipe => DiskMode::Alongside,
+                        DiskMode::Alongside => DiskMode::Wipe,
+                    };
+                    *error = None;
+                }
+            }
+            KeyCode::Char(' ') if !disks.is_empty() => {
+                if let AppMode::Install(InstallState::SelectDisk { marked, error, .. }) =
+                    &mut self.mode
+                {
+                    let path = &disks[selected].path;
+                    match marked.iter().position(|p| p == path) {
+                        Some(pos) => {
+                            marked.remove(pos);
+                        }
+                        None => marked.push(path.clone()),
+                    }
+                    *error = None;
+                }
+            }
+            KeyCode::Enter if marked.len() > 1 && disk_mode == DiskMode::Alongside => {
+                *error = Some(
+                    "Install alongside uses a single disk. Unmark the others or switch to \
+                     Erase disk."
+                        .to_string(),
+                );
+            }
+            KeyCode::Enter if marked.len() > 1 => {
+                let disks: Vec<_> = marked
+                    .iter()
+                    .filter_map(|path| disks.iter().find(|d| &d.path == path).cloned())
+                    .collect();
+                // First marked disk holds the root filesystem, the rest default to /home
+                let roles = (0..disks.len())
+                    .map(|i| if i == 0 { DiskRole::Root } else { DiskRole::Home })
+                    .collect();
+                self.mode = AppMode::Install(InstallState::AssignRoles {
+                    host: host.to_string(),
+                    disks,
+                    roles,
+                    selected: 0,
+                    error: None,
+                });
+            }
+            KeyCode::Enter
+                if disk_mode == DiskMode::Alongside
+                    && disks.get(selected).is_some_and(|d| d.free_bytes < MIN_ALONGSIDE_BYTES) =>
+            {
+                *error = Some(format!(
+                    "Needs {} of unallocated space on a GPT disk. Shrink the Windows \
+                     partition first (Disk Management → Shrink Volume).",
+                    format_bytes(MIN_ALONGSIDE_BYTES)
+                ));
+            }
+            KeyCode::Enter
+                if disk_mode == DiskMode::Wipe
+                    && disks.get(selected).is_some_and(|d| !d.existing_data().is_empty()) =>
+            {
+                self.mode = AppMode::Install(InstallState::ConfirmExistingData {
+                    host: host.to_string(),
+                    disk: disks[selected].clone(),
+                    typed: String::new(),
+                    error: None,
+                });
+            }
+            KeyCode::Enter if !disks.is_empty() => {
+                self.mode = disk_chosen(host, disks[selected].clone(), disk_mode);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_existing_data_key(&mut self, key: KeyCode) {
+        let AppMode::Install(InstallState::ConfirmExistingData {
+            host,
+            disk,
+            typed,
+            error,
+        }) = &mut self.mode
+        else {
+            return;
+        };
+        match key {
+            KeyCode::Char(c) => {
+                if typed.len() < MAX_INPUT_LENGTH {
+                    typed.push(c);
+                }
+                *error = None;
+            }
+            KeyCode::Backspace => {
+                typed.pop();
+                *error = None;
+            }
+            KeyCode::Enter if disk.confirms_name(typed) => {
+                self.mode = disk_chosen(host, disk.clone(), DiskMode::Wipe);
+            }
+            KeyCode::Enter => {
+                *error = Some(format!("Type {} to erase this disk", disk.name()));
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_partitions_key(&mut self, key: KeyCode) {
+        let AppMode::Install(InstallState::EditPartitions {
+            host,
+            disk,
+            credentials,
+            inputs,
+            active_field,
+            error,
+        }) = &mut self.mode
+        else {
+            return;
+        };
+        match key {
+            KeyCode::Tab | KeyCode::Down => {
+                *active_field = active_field.next();
+                *error = None;
+            }
+            KeyCode::BackTab | KeyCode::Up => {
+                *active_field = active_field.prev();
+                *error = None;
+            }
+            KeyCode::Left | KeyCode::Right | KeyCode::Char(' ')
+                if *active_field == PartitionField::Swap =>
+            {
+                inputs.swap = inputs.swap.next();
+                *error = None;
+            }
+            KeyCode::Char(_) | KeyCode::Backspace => {
+                let field = match active_field {
+                    PartitionField::Esp => &mut inputs.esp,
+                    PartitionField::Root => &mut inputs.root,
+                    PartitionField::SwapSize => &mut inputs.swap_size,
+                    PartitionField::Swap => return,
+                };
+                match key {
+                    // Whole GiB only
+                    KeyCode::Char(c) if c.is_ascii_digit() && field.len() < 6 => field.push(c),
+                    KeyCode::Backspace => {
+                        field.pop();
                     }
+                    _ => {}
                 }
+                *error = None;
             }
-            KeyCode::Enter => {
-                if !disks.is_empty() {
+            KeyCode::Enter => match inputs.layout(disk.size_bytes) {
+                Ok(layout) => {
+                    credentials.partitions = Some(layout);
                     self.mode = AppMode::Install(InstallState::EnterCredentials {
-                        host: host.to_string(),
-                        disk: disks[selected].clone(),
-                        credentials: InstallCredentials::default(),
+                        host: host.clone(),
+                        disk: disk.clone(),
+                        disk_mode: DiskMode::Wipe,
+                        extra_disks: Vec::new(),
+                        credentials: credentials.clone(),
                         active_field: CredentialField::Username,
                         error: None,
+                        remote: None,
                     });
                 }
+                Err(err) => *error = Some(err.to_string()),
+            },
+            _ => {}
+        }
+    }
+
+    async fn handle_install_roles_key(&mut self, key: KeyCode) -> Result<()> {
+        let AppMode::Install(InstallState::AssignRoles {
+            host,
+            disks,
+            roles,
+            selected,
+            error,
+        }) = &mut self.mode
+        else {
+            return Ok(());
+        };
+        match key {
+            KeyCode::Up | KeyCode::Char('k') => {
+                *selected = selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                *selected = (*selected + 1).min(disks.len().saturating_sub(1));
+            }
+            KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right | KeyCode::Tab => {
+                if let Some(role) = roles.get_mut(*selected) {
+                    *role = role.next();
+                }
+                *error = None;
+            }
+            KeyCode::Enter => {
+                if let Some(err) = validate_disk_roles(roles) {
+                    *error = Some(err);
+                    return Ok(());
+                }
+                let mut extra_disks: Vec<DiskAssignment> = disks
+                    .iter()
+                    .zip(roles.iter())
+                    .map(|(disk, role)| DiskAssignment {
+                        disk: disk.clone(),
+                        role: *role,
+                    })
+                    .collect();
+                let Some(root) = extra_disks.iter().position(|a| a.role == DiskRole::Root) else {
+                    return Ok(());
+                };
+                let root = extra_disks.remove(root);
+                self.mode = AppMode::Install(InstallState::EnterCredentials {
+                    host: host.clone(),
+                    disk: root.disk,
+                    disk_mode: DiskMode::Wipe,
+                    extra_disks,
+                    credentials: InstallCredentials::default(),
+                    active_field: CredentialField::Username,
+                    error: None,
+                    remote: None,
+                });
             }
             _ => {}
         }
@@ -484,39 +2064,69 @@ impl App {
         if let AppMode::Install(InstallState::EnterCredentials {
             host,
             disk,
+            disk_mode,
+            extra_disks,
             credentials,
             active_field,
             error,
+            remote,
         }) = &mut self.mode
         {
             match key {
                 KeyCode::Tab | KeyCode::Down => {
                     // Move to next field
-                    *active_field = match active_field {
-                        CredentialField::Username => CredentialField::Password,
-                        CredentialField::Password => CredentialField::ConfirmPassword,
-                        CredentialField::ConfirmPassword => CredentialField::Username,
-                    };
+                    *active_field = active_field.next(credentials.encrypt);
                     *error = None;
                 }
                 KeyCode::BackTab | KeyCode::Up => {
                     // Move to previous field
-                    *active_field = match active_field {
-                        CredentialField::Username => CredentialField::ConfirmPassword,
-                        CredentialField::Password => CredentialField::Username,
-                        CredentialField::ConfirmPassword => CredentialField::Password,
-                    };
+                    *active_field = active_field.prev(credentials.encrypt);
+                    *error = None;
+                }
+                KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right
+                    if *active_field == CredentialField::Encryption =>
+                {
+                    credentials.encrypt = !credentials.encrypt;
                     *error = None;
                 }
+                KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right
+                    if *active_field == CredentialField::Tpm2Unlock =>
+                {
+                    if remote.is_some() && !credentials.tpm2_unlock {
+                        *error =
+                            Some("TPM2 auto-unlock is only set up on local installs".to_string());
+                    } else if credentials.tpm2_unlock || detect_tpm2() {
+                        credentials.tpm2_unlock = !credentials.tpm2_unlock;
+                        *error = None;
+                    } else {
+                        *error = Some("No TPM 2.0 device found on this machine".to_string());
+                    }
+                }
                 KeyCode::Char(c) => {
                     let field = match active_field {
                         CredentialField::Username => &mut credentials.username,
                         CredentialField::Password => &mut credentials.password,
                         CredentialField::ConfirmPassword => &mut credentials.confirm_password,
+                        CredentialField::ExtraUsers => &mut credentials.extra_users,
+                        CredentialField::SshKeys => &mut credentials.ssh_keys,
+                        CredentialField::Encryption | CredentialField::Tpm2Unlock => {
+                            return Ok(())
+                        }
+                        CredentialField::Passphrase => &mut credentials.passphrase,
+                        CredentialField::ConfirmPassphrase => &mut credentials.confirm_passphrase,
                     };
-                    if field.len() < MAX_INPUT_LENGTH {
-                        // Auto-convert username to lowercase
-                        let c = if *active_field == CredentialField::Username {
+                    let max = if *active_field == CredentialField::SshKeys {
+                        MAX_KEYS_INPUT_LENGTH
+                    } else {
+                        MAX_INPUT_LENGTH
+                    };
+                    if field.len() < max {
+                        // Auto-convert usernames to lowercase
+                        let lowercase = matches!(
+                            active_field,
+                            CredentialField::Username | CredentialField::ExtraUsers
+                        );
+                        let c = if lowercase {
                             c.to_ascii_lowercase()
                         } else {
                             c
@@ -530,24 +2140,49 @@ impl App {
                         CredentialField::Username => &mut credentials.username,
                         CredentialField::Password => &mut credentials.password,
                         CredentialField::ConfirmPassword => &mut credentials.confirm_password,
+                        CredentialField::ExtraUsers => &mut credentials.extra_users,
+                        CredentialField::SshKeys => &mut credentials.ssh_keys,
+                        CredentialField::Encryption | CredentialField::Tpm2Unlock => {
+                            return Ok(())
+                        }
+                        CredentialField::Passphrase => &mut credentials.passphrase,
+                        CredentialField::ConfirmPassphrase => &mut credentials.confirm_passphrase,
                     };
                     field.pop();
                     *error = None;
                 }
                 KeyCode::Enter => {
+                    let passphrase_error = if credentials.encrypt {
+                        validate_passphrase(
+                            &credentials.passphrase,
+                            &credentials.confirm_passphrase,
+                        )
+                    } else {
+                        None
+                    };
                     // Validate and proceed to confirmation
                     if let Some(err) = validate_username(&credentials.username) {
                         *error = Some(err);
                     } else if let Some(err) = validate_password(&credentials.password, &credentials.confirm_password) {
                         *error = Some(err);
+                    } else if let Some(err) = passphrase_error {
+                        *error = Some(err);
+                    } else if let Err(err) = credentials.check_accounts() {
+                        *error = Some(err.to_string());
                     } else {
-                        // All valid, proceed to overview
-                        self.mode = AppMode::Install(InstallState::Overview {
+                        // All valid, proceed to the locale step, prefilled from the ISO
+                        let locale =
+                            credentials.locale.clone().unwrap_or_else(LocaleSettings::detect);
+                        self.mode = AppMode::Install(InstallState::SelectLocale {
                             host: host.clone(),
                             disk: disk.clone(),
+                            disk_mode: *disk_mode,
+                            extra_disks: extra_disks.clone(),
                             credentials: credentials.clone(),
-                            hardware_config: None,
-                            input: String::new(),
+                            remote: remote.clone(),
+                            locale,
+                            active_field: LocaleField::Timezone,
+                            error: None,
                         });
                     }
                 }
@@ -557,73 +2192,341 @@ impl App {
         Ok(())
     }
 
+    fn handle_locale_key(&mut self, key: KeyCode) {
+        let AppMode::Install(InstallState::SelectLocale {
+            host,
+            disk,
+            disk_mode,
+            extra_disks,
+            credentials,
+            remote,
+            locale,
+            active_field,
+            error,
+        }) = &mut self.mode
+        else {
+            return;
+        };
+        match key {
+            KeyCode::Tab | KeyCode::Down => {
+                *active_field = active_field.next();
+                *error = None;
+            }
+            KeyCode::BackTab | KeyCode::Up => {
+                *active_field = active_field.prev();
+                *error = None;
+            }
+            KeyCode::Char(_) | KeyCode::Backspace => {
+                let field = match active_field {
+                    LocaleField::Timezone => &mut locale.timezone,
+                    LocaleField::Locale => &mut locale.locale,
+                    LocaleField::Keymap => &mut locale.keymap,
+                };
+                match key {
+                    KeyCode::Char(c) if field.len() < MAX_INPUT_LENGTH => field.push(c),
+                    KeyCode::Backspace => {
+                        field.pop();
+                    }
+                    _ => {}
+                }
+                *error = None;
+            }
+            KeyCode::Enter => {
+                if let Err(err) = locale.validate() {
+                    *error = Some(err.to_string());
+                    return;
+                }
+                credentials.locale = Some(locale.clone());
+                // Proceed to overview. A remote target's disks and firmware
+                // can't be inspected from here.
+                let local = remote.is_none();
+                let disk_health = std::iter::once(&disk.path)
+                    .chain(extra_disks.iter().map(|a| &a.disk.path))
+                    .filter(|_| local)
+                    .map(|path| (path.clone(), check_disk_health(path)))
+                    .collect();
+                let disk_drift = self
+                    .hosts
+                    .iter()
+                    .filter(|_| local)
+                    .find(|h| h.name == *host)
+                    .and_then(|h| h.metadata.as_ref()?.disk.as_ref())
+                    .and_then(|recorded| check_disk_drift(recorded, &disk.path));
+                let secure_boot_support = if local {
+                    detect_secure_boot()
+                } else {
+                    SecureBootSupport::Unsupported
+                };
+                // Erasing would take the existing OS with it when installing alongside
+                let erase_plan = if local && *disk_mode == DiskMode::Wipe {
+                    std::iter::once(&disk.path)
+                        .chain(extra_disks.iter().map(|a| &a.disk.path))
+                        .map(|path| (path.clone(), detect_erase_method(path)))
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                self.mode = AppMode::Install(InstallState::Overview {
+                    host: host.clone(),
+                    disk: disk.clone(),
+                    disk_mode: *disk_mode,
+                    extra_disks: extra_disks.clone(),
+                    credentials: credentials.clone(),
+                    remote: remote.clone(),
+                    hardware_config: None,
+                    disk_health,
+                    disk_drift,
+                    secure_boot_support,
+                    secure_boot: false,
+                    erase_plan,
+                    secure_erase: false,
+                    erase_confirm: None,
+                    input: String::new(),
+                });
+            }
+            _ => {}
+        }
+    }
+
     async fn handle_overview_key_action(&mut self, key: KeyCode, host: &str) -> Result<()> {
         let (disk, credentials, should_start) = if let AppMode::Install(InstallState::Overview {
             disk,
+            disk_mode,
             credentials,
+            remote,
+            secure_boot_support,
+            secure_boot,
+            erase_plan,
+            secure_erase,
+            erase_confirm,
             input,
             ..
         }) = &mut self.mode
         {
-            match key {
-                KeyCode::Char(c) => {
-                    if input.len() < MAX_INPUT_LENGTH {
-                        input.push(c);
+            let install = || (disk.clone(), remote.clone());
+            // The secure erase confirmation takes the keys while it is open
+            if let Some(typed) = erase_confirm {
+                match key {
+                    KeyCode::Char(c) => {
+                        if typed.len() < MAX_INPUT_LENGTH {
+                            typed.push(c);
+                        }
+                        (None, None, false)
                     }
-                    (None, None, false)
-                }
-                KeyCode::Backspace => {
-                    input.pop();
-                    (None, None, false)
+                    KeyCode::Backspace => {
+                        typed.pop();
+                        (None, None, false)
+                    }
+                    KeyCode::Enter if typed.trim().eq_ignore_ascii_case("erase") => {
+                        *erase_confirm = None;
+                        (Some(install()), Some(credentials.clone()), true)
+                    }
+                    _ => (None, None, false),
                 }
-                KeyCode::Enter => {
-                    if input.trim().eq_ignore_ascii_case("yes") {
-                        (Some(disk.clone()), Some(credentials.clone()), true)
-                    } else {
+            } else {
+                match key {
+                    KeyCode::BackTab => {
+                        if !erase_plan.is_empty() {
+                            *secure_erase = !*secure_erase;
+                        }
+                        (None, None, false)
+                    }
+                    KeyCode::Tab => {
+                        if secure_boot_blocker(*secure_boot_support, *disk_mode).is_none() {
+                            *secure_boot = !*secure_boot;
+                        }
+                        (None, None, false)
+                    }
+                    KeyCode::Char(c) => {
+                        if input.len() < MAX_INPUT_LENGTH {
+                            input.push(c);
+                        }
                         (None, None, false)
                     }
+                    KeyCode::Backspace => {
+                        input.pop();
+                        (None, None, false)
+                    }
+                    KeyCode::Enter if input.trim().eq_ignore_ascii_case("yes") && *secure_erase => {
+                        // Erasing is irreversible even for data recovery; ask once more
+                        *erase_confirm = Some(String::new());
+                        (None, None, false)
+                    }
+                    KeyCode::Enter => {
+                        if input.trim().eq_ignore_ascii_case("yes") {
+                            (Some(install()), Some(credentials.clone()), true)
+                        } else {
+                            (None, None, false)
+                        }
+                    }
+                    _ => (None, None, false),
                 }
-                _ => (None, None, false),
             }
         } else {
             (None, None, false)
         };
 
         if should_start {
-            if let (Some(disk), Some(creds)) = (disk, credentials) {
-                let mut steps = vec![
-                    StepStatus::new("Checking network connectivity"),
-                    StepStatus::new("Enabling Nix flakes"),
-                    StepStatus::new("Cloning configuration repository"),
-                    StepStatus::new("Configuring disk device"),
-                    StepStatus::new("Running disko (partitioning)"),
-                    StepStatus::new("Installing NixOS"),
-                    StepStatus::new("Setting up user account"),
-                ];
-                steps[0].status = StepState::Running;
-
-                self.mode = AppMode::Install(InstallState::Running {
-                    host: host.to_string(),
-                    disk: disk.clone(),
-                    credentials: creds.clone(),
-                    step: 0,
-                    steps,
-                    output: std::collections::VecDeque::new(),
-                });
-                if let Some(tx) = &self.cmd_tx {
-                    commands::install::start_install(
-                        tx.clone(),
-                        host,
-                        &disk.path,
-                        &creds.username,
-                        &creds.password,
-                    ).await?;
+            if let (Some((disk, remote)), Some(creds)) = (disk, credentials) {
+                if let Some(target) = remote {
+                    return self.start_remote_install(host, disk, target, creds).await;
+                }
+                if let AppMode::Install(overview) = self.mode.clone() {
+                    return self.start_local_install(overview).await;
                 }
             }
         }
         Ok(())
     }
 
+    /// Start installing on this machine what `overview` (an `InstallState::Overview`) shows
+    pub(super) async fn start_local_install(&mut self, overview: InstallState) -> Result<()> {
+        let InstallState::Overview {
+            host,
+            disk,
+            disk_mode,
+            extra_disks,
+            credentials: creds,
+            secure_boot,
+            secure_erase,
+            ..
+        } = &overview
+        else {
+            return Ok(());
+        };
+        let (disk_mode, secure_boot, secure_erase) = (*disk_mode, *secure_boot, *secure_erase);
+        let accounts = creds.accounts();
+        let mut steps = vec![
+            StepStatus::new("Checking network connectivity"),
+            StepStatus::new("Enabling Nix flakes"),
+            StepStatus::new("Cloning configuration repository"),
+        ];
+        if !nix_settings::current().is_empty() {
+            steps.push(StepStatus::new("Writing binary cache and proxy settings"));
+        }
+        if !accounts.is_empty() {
+            steps.push(StepStatus::new("Adding authorized keys and accounts"));
+        }
+        if creds.locale.is_some() {
+            steps.push(StepStatus::new("Writing timezone, locale and keymap"));
+        }
+        steps.extend([
+            StepStatus::new("Configuring disk device"),
+            StepStatus::new("Checking drive health"),
+            StepStatus::new("Validating the configuration"),
+        ]);
+        if secure_erase {
+            steps.push(StepStatus::new("Secure erase of target disks"));
+        }
+        steps.push(StepStatus::new("Running disko (partitioning)"));
+        if creds.encrypt && creds.tpm2_unlock {
+            steps.push(StepStatus::new("TPM2 auto-unlock enrollment"));
+        }
+        if secure_boot {
+            steps.push(StepStatus::new("Creating Secure Boot keys"));
+        }
+        // The install configures hibernation for this same swap, so the
+        // step list and the steps it completes agree
+        let swap = host_swap(host, creds.partitions.as_ref());
+        if swap != DiskSwap::None {
+            steps.push(StepStatus::new("Configuring hibernation"));
+        }
+        steps.push(StepStatus::new(NIXOS_INSTALL_STEP));
+        steps.push(StepStatus::new("Setting up user account"));
+        if secure_boot {
+            steps.push(StepStatus::new("Enrolling Secure Boot keys"));
+        }
+        steps[0].status = StepState::Running;
+
+        self.mode = AppMode::Install(InstallState::Running {
+            host: host.to_string(),
+            disk: disk.clone(),
+            credentials: creds.clone(),
+            overview: Some(Box::new(overview.clone())),
+            step: 0,
+            steps,
+            output: std::collections::VecDeque::new(),
+        });
+        if let Some(tx) = &self.cmd_tx {
+            commands::install::start_install(
+                tx.clone(),
+                host,
+                &disk.path,
+                disk_mode,
+                extra_disks,
+                &creds.primary_user(),
+                &creds.password,
+                &accounts,
+                creds.locale.as_ref(),
+                creds.partitions.as_ref(),
+                swap,
+                creds.encrypt.then_some(creds.passphrase.as_str()),
+                creds.encrypt && creds.tpm2_unlock,
+                secure_boot,
+                secure_erase,
+            ).await?;
+        }
+        Ok(())
+    }
+
+    /// Run nixos-anywhere against `target`, with the same progress screen as a local install
+    async fn start_remote_install(
+        &mut self,
+        host: &str,
+        disk: DiskInfo,
+        target: String,
+        creds: InstallCredentials,
+    ) -> Result<()> {
+        let accounts = creds.accounts();
+        let mut steps = vec![
+            StepStatus::new("Enabling Nix flakes"),
+            StepStatus::new("Cloning configuration repository"),
+        ];
+        if !nix_settings::current().is_empty() {
+            steps.push(StepStatus::new("Writing binary cache and proxy settings"));
+        }
+        steps.push(StepStatus::new("Checking SSH access"));
+        if !accounts.is_empty() {
+            steps.push(StepStatus::new("Adding authorized keys and accounts"));
+        }
+        if creds.locale.is_some() {
+            steps.push(StepStatus::new("Writing timezone, locale and keymap"));
+        }
+        steps.extend([
+            StepStatus::new("Configuring disk device"),
+            StepStatus::new("Validating the configuration"),
+            StepStatus::new("Running nixos-anywhere (kexec, disko, install)"),
+            StepStatus::new("Setting up user account"),
+            StepStatus::new("Rebooting target"),
+        ]);
+        steps[0].status = StepState::Running;
+
+        self.mode = AppMode::Install(InstallState::Running {
+            host: host.to_string(),
+            disk: disk.clone(),
+            credentials: creds.clone(),
+            overview: None,
+            step: 0,
+            steps,
+            output: std::collections::VecDeque::new(),
+        });
+        if let Some(tx) = &self.cmd_tx {
+            commands::install::start_remote_install(
+                tx.clone(),
+                &target,
+                host,
+                &disk.path,
+                &creds.primary_user(),
+                &creds.password,
+                &accounts,
+                creds.locale.as_ref(),
+                creds.encrypt.then_some(creds.passphrase.as_str()),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
     /// Handle keyboard input for create host wizard
     async fn handle_create_host_key(&mut self, key: KeyCode) -> Result<()> {
         // For keys that don't transition state, handle them with mutable borrow
@@ -675,81 +2578,282 @@ impl App {
                         KeyCode::Enter => true,
                         _ => false,
                     }
-                } else {
-                    match key {
-                        KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => true,
-                        KeyCode::Char('n') | KeyCode::Char('N') => {
-                            *override_menu = true;
-                            false
+                } else {
+                    match key {
+                        KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => true,
+                        KeyCode::Char('n') | KeyCode::Char('N') => {
+                            *override_menu = true;
+                            false
+                        }
+                        _ => false,
+                    }
+                }
+            }
+            AppMode::CreateHost(CreateHostState::ConfirmFormFactor {
+                override_menu,
+                selected,
+                ..
+            }) => {
+                if *override_menu {
+                    match key {
+                        KeyCode::Up | KeyCode::Char('k') => {
+                            *selected = selected.saturating_sub(1);
+                            false
+                        }
+                        KeyCode::Down | KeyCode::Char('j') => {
+                            *selected = (*selected + 1).min(1);
+                            false
+                        }
+                        KeyCode::Enter => true,
+                        _ => false,
+                    }
+                } else {
+                    match key {
+                        KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => true,
+                        KeyCode::Char('n') | KeyCode::Char('N') => {
+                            *override_menu = true;
+                            false
+                        }
+                        _ => false,
+                    }
+                }
+            }
+            AppMode::CreateHost(CreateHostState::SelectDisk { disks, selected, .. }) => {
+                match key {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        *selected = selected.saturating_sub(1);
+                        false
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        if !disks.is_empty() {
+                            *selected = (*selected + 1).min(disks.len() - 1);
+                        }
+                        false
+                    }
+                    KeyCode::Enter if !disks.is_empty() => true,
+                    _ => false,
+                }
+            }
+            AppMode::CreateHost(CreateHostState::EnterHostname { input, error, .. }) => {
+                match key {
+                    KeyCode::Char(c) => {
+                        if input.len() < MAX_INPUT_LENGTH && (c.is_alphanumeric() || c == '-') {
+                            input.push(c.to_ascii_lowercase());
+                            *error = None;
+                        }
+                        false
+                    }
+                    KeyCode::Backspace => {
+                        input.pop();
+                        *error = None;
+                        false
+                    }
+                    KeyCode::Enter => true,
+                    _ => false,
+                }
+            }
+            AppMode::CreateHost(CreateHostState::SelectFilesystem {
+                selected, error, ..
+            }) => match key {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    *selected = selected.saturating_sub(1);
+                    *error = None;
+                    false
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    *selected = (*selected + 1).min(RootFilesystem::ALL.len() - 1);
+                    *error = None;
+                    false
+                }
+                KeyCode::Enter => true,
+                _ => false,
+            },
+            AppMode::CreateHost(CreateHostState::SelectSwap {
+                selected, error, ..
+            }) => match key {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    *selected = selected.saturating_sub(1);
+                    *error = None;
+                    false
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    *selected = (*selected + 1).min(SwapMode::ALL.len() - 1);
+                    *error = None;
+                    false
+                }
+                KeyCode::Enter => true,
+                _ => false,
+            },
+            AppMode::CreateHost(CreateHostState::SelectLayout {
+                selected,
+                custom,
+                error,
+                ..
+            }) => {
+                // Letters go to the custom scheme input, so only arrow keys navigate
+                let is_custom =
+                    SubvolumeLayout::ALL.get(*selected) == Some(&SubvolumeLayout::Custom);
+                match key {
+                    KeyCode::Up => {
+                        *selected = selected.saturating_sub(1);
+                        *error = None;
+                        false
+                    }
+                    KeyCode::Down => {
+                        *selected = (*selected + 1).min(SubvolumeLayout::ALL.len() - 1);
+                        *error = None;
+                        false
+                    }
+                    KeyCode::Char(c) if is_custom => {
+                        if custom.len() < MAX_INPUT_LENGTH {
+                            custom.push(c);
+                            *error = None;
                         }
-                        _ => false,
+                        false
                     }
+                    KeyCode::Backspace if is_custom => {
+                        custom.pop();
+                        *error = None;
+                        false
+                    }
+                    KeyCode::Enter => true,
+                    _ => false,
                 }
             }
-            AppMode::CreateHost(CreateHostState::ConfirmFormFactor {
-                override_menu,
+            AppMode::CreateHost(CreateHostState::SelectKernel {
                 selected,
+                params,
+                error,
                 ..
             }) => {
-                if *override_menu {
-                    match key {
-                        KeyCode::Up | KeyCode::Char('k') => {
-                            *selected = selected.saturating_sub(1);
-                            false
-                        }
-                        KeyCode::Down | KeyCode::Char('j') => {
-                            *selected = (*selected + 1).min(1);
-                            false
-                        }
-                        KeyCode::Enter => true,
-                        _ => false,
+                // Letters go to the params input, so only arrow keys navigate
+                match key {
+                    KeyCode::Up => {
+                        *selected = selected.saturating_sub(1);
+                        false
                     }
-                } else {
-                    match key {
-                        KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => true,
-                        KeyCode::Char('n') | KeyCode::Char('N') => {
-                            *override_menu = true;
-                            false
+                    KeyCode::Down => {
+                        *selected = (*selected + 1).min(KernelFlavor::ALL.len() - 1);
+                        false
+                    }
+                    KeyCode::Char(c) => {
+                        if params.len() < MAX_INPUT_LENGTH {
+                            params.push(c);
+                            *error = None;
                         }
-                        _ => false,
+                        false
                     }
+                    KeyCode::Backspace => {
+                        params.pop();
+                        *error = None;
+                        false
+                    }
+                    KeyCode::Enter => true,
+                    _ => false,
                 }
             }
-            AppMode::CreateHost(CreateHostState::SelectDisk { disks, selected, .. }) => {
+            AppMode::CreateHost(CreateHostState::SelectModules { config, selected }) => {
                 match key {
                     KeyCode::Up | KeyCode::Char('k') => {
                         *selected = selected.saturating_sub(1);
                         false
                     }
                     KeyCode::Down | KeyCode::Char('j') => {
-                        if !disks.is_empty() {
-                            *selected = (*selected + 1).min(disks.len() - 1);
+                        *selected = (*selected + 1).min(HostModule::ALL.len() - 1);
+                        false
+                    }
+                    KeyCode::Char(' ') => {
+                        if let Some(module) = HostModule::ALL.get(*selected) {
+                            config.toggle_module(*module);
                         }
                         false
                     }
-                    KeyCode::Enter if !disks.is_empty() => true,
+                    KeyCode::Enter => true,
                     _ => false,
                 }
             }
-            AppMode::CreateHost(CreateHostState::EnterHostname { input, error, .. }) => {
+            AppMode::CreateHost(CreateHostState::SelectTheme { selected, .. }) => match key {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    *selected = selected.saturating_sub(1);
+                    false
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    *selected = (*selected + 1).min(ThemePreset::ALL.len() - 1);
+                    false
+                }
+                KeyCode::Enter => true,
+                _ => false,
+            },
+            AppMode::CreateHost(CreateHostState::Review { config, lint }) => match key {
+                KeyCode::Tab => {
+                    *lint = !*lint;
+                    false
+                }
+                // ZFS hosts have no btrfs mount options to tune
+                KeyCode::Char('d') => config.filesystem == RootFilesystem::Btrfs,
+                KeyCode::Enter => true,
+                _ => false,
+            },
+            AppMode::CreateHost(CreateHostState::EditDiskOptions {
+                config,
+                selected,
+                error,
+                ..
+            }) => {
+                let field = DiskOptionField::ALL[*selected];
+                let options = &mut config.disk_options;
                 match key {
-                    KeyCode::Char(c) => {
-                        if input.len() < MAX_INPUT_LENGTH && (c.is_alphanumeric() || c == '-') {
-                            input.push(c.to_ascii_lowercase());
-                            *error = None;
+                    KeyCode::Up | KeyCode::BackTab => {
+                        *selected = selected.saturating_sub(1);
+                        false
+                    }
+                    KeyCode::Down | KeyCode::Tab => {
+                        *selected = (*selected + 1).min(DiskOptionField::ALL.len() - 1);
+                        false
+                    }
+                    KeyCode::Left | KeyCode::Right if field == DiskOptionField::Compression => {
+                        options.step_compression(key == KeyCode::Right);
+                        false
+                    }
+                    KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right if !field.is_text() => {
+                        match field {
+                            DiskOptionField::Noatime => options.noatime = !options.noatime,
+                            DiskOptionField::Discard => options.discard = !options.discard,
+                            DiskOptionField::Autodefrag => {
+                                options.autodefrag = !options.autodefrag
+                            }
+                            _ => {}
                         }
                         false
                     }
-                    KeyCode::Backspace => {
-                        input.pop();
+                    KeyCode::Char(c) if field.is_text() => {
+                        let label = if field == DiskOptionField::BootLabel {
+                            &mut options.boot_label
+                        } else {
+                            &mut options.root_label
+                        };
+                        if label.len() < MAX_INPUT_LENGTH {
+                            label.push(c);
+                        }
                         *error = None;
                         false
                     }
-                    KeyCode::Enter => true,
+                    KeyCode::Backspace if field.is_text() => {
+                        if field == DiskOptionField::BootLabel {
+                            options.boot_label.pop();
+                        } else {
+                            options.root_label.pop();
+                        }
+                        *error = None;
+                        false
+                    }
+                    KeyCode::Enter => {
+                        *error = validate_disk_options(options);
+                        error.is_none()
+                    }
                     _ => false,
                 }
             }
-            AppMode::CreateHost(CreateHostState::Review { .. }) => key == KeyCode::Enter,
             AppMode::CreateHost(CreateHostState::Complete { success, .. }) => {
                 // Auto-proceed on any key for success, Enter for failure
                 *success || key == KeyCode::Enter
@@ -898,18 +3002,175 @@ impl App {
                         error: Some(err),
                     })
                 } else {
-                    AppMode::CreateHost(CreateHostState::Review {
+                    let storage = detect_storage_kind(&disk.path);
+                    AppMode::CreateHost(CreateHostState::SelectFilesystem {
                         config: NewHostConfig {
                             hostname,
                             cpu,
                             gpu,
                             form_factor,
                             disk,
+                            storage,
+                            boot_mode: detect_boot_mode(),
+                            initrd_modules: detect_initrd_modules(),
+                            kernel: KernelFlavor::default(),
+                            kernel_params: Vec::new(),
+                            modules: Vec::new(),
+                            theme: ThemePreset::default(),
+                            filesystem: RootFilesystem::default(),
+                            host_id: generate_host_id(),
+                            subvolume_layout: SubvolumeLayout::default(),
+                            custom_subvolumes: Vec::new(),
+                            swap: SwapMode::default(),
+                            memory_gib: detect_memory_gib(),
+                            disk_options: DiskOptions::for_storage(storage),
                         },
+                        selected: RootFilesystem::default().index(),
+                        error: None,
+                    })
+                }
+            }
+            AppMode::CreateHost(CreateHostState::SelectFilesystem {
+                mut config,
+                selected,
+                ..
+            }) => {
+                let filesystem = RootFilesystem::ALL[selected.min(RootFilesystem::ALL.len() - 1)];
+                if filesystem == RootFilesystem::Zfs && config.boot_mode == BootMode::Bios {
+                    AppMode::CreateHost(CreateHostState::SelectFilesystem {
+                        config,
+                        selected,
+                        error: Some(
+                            "ZFS root needs UEFI, this machine booted in legacy BIOS mode"
+                                .to_string(),
+                        ),
+                    })
+                } else if filesystem == RootFilesystem::Zfs {
+                    // The ZFS module lags behind the newest kernels; start from LTS
+                    if config.filesystem != RootFilesystem::Zfs {
+                        config.kernel = KernelFlavor::Lts;
+                    }
+                    config.filesystem = filesystem;
+                    AppMode::CreateHost(CreateHostState::SelectSwap {
+                        selected: config.swap.index(),
+                        config,
+                        error: None,
+                    })
+                } else {
+                    config.filesystem = filesystem;
+                    AppMode::CreateHost(CreateHostState::SelectLayout {
+                        selected: config.subvolume_layout.index(),
+                        custom: format_subvolumes(&config.custom_subvolumes),
+                        config,
+                        error: None,
+                    })
+                }
+            }
+            AppMode::CreateHost(CreateHostState::SelectLayout {
+                mut config,
+                selected,
+                custom,
+                ..
+            }) => {
+                let layout = SubvolumeLayout::ALL[selected.min(SubvolumeLayout::ALL.len() - 1)];
+                let custom_subvolumes = if layout == SubvolumeLayout::Custom {
+                    validate_subvolumes(&custom)
+                } else {
+                    Ok(Vec::new())
+                };
+                match custom_subvolumes {
+                    Ok(custom_subvolumes) => {
+                        config.subvolume_layout = layout;
+                        config.custom_subvolumes = custom_subvolumes;
+                        AppMode::CreateHost(CreateHostState::SelectSwap {
+                            selected: config.swap.index(),
+                            config,
+                            error: None,
+                        })
+                    }
+                    Err(err) => AppMode::CreateHost(CreateHostState::SelectLayout {
+                        config,
+                        selected,
+                        custom,
+                        error: Some(err),
+                    }),
+                }
+            }
+            AppMode::CreateHost(CreateHostState::SelectSwap {
+                mut config,
+                selected,
+                ..
+            }) => {
+                let swap = SwapMode::ALL[selected.min(SwapMode::ALL.len() - 1)];
+                match validate_swap(swap, &config) {
+                    Some(err) => AppMode::CreateHost(CreateHostState::SelectSwap {
+                        config,
+                        selected,
+                        error: Some(err),
+                    }),
+                    None => {
+                        config.swap = swap;
+                        AppMode::CreateHost(CreateHostState::SelectKernel {
+                            selected: config.kernel.index(),
+                            params: config.kernel_params.join(" "),
+                            config,
+                            error: None,
+                        })
+                    }
+                }
+            }
+            AppMode::CreateHost(CreateHostState::SelectKernel {
+                mut config,
+                selected,
+                params,
+                ..
+            }) => match validate_kernel_params(&params) {
+                Ok(kernel_params) => {
+                    config.kernel = KernelFlavor::ALL[selected.min(KernelFlavor::ALL.len() - 1)];
+                    config.kernel_params = kernel_params;
+                    AppMode::CreateHost(CreateHostState::SelectModules {
+                        config,
+                        selected: 0,
                     })
                 }
+                Err(err) => AppMode::CreateHost(CreateHostState::SelectKernel {
+                    config,
+                    selected,
+                    params,
+                    error: Some(err),
+                }),
+            },
+            AppMode::CreateHost(CreateHostState::SelectModules { config, .. }) => {
+                AppMode::CreateHost(CreateHostState::SelectTheme {
+                    selected: config.theme.index(),
+                    config,
+                })
+            }
+            AppMode::CreateHost(CreateHostState::SelectTheme {
+                mut config,
+                selected,
+            }) => {
+                config.theme = ThemePreset::ALL[selected.min(ThemePreset::ALL.len() - 1)];
+                AppMode::CreateHost(CreateHostState::Review {
+                    config,
+                    lint: false,
+                })
+            }
+            AppMode::CreateHost(CreateHostState::Review { config, lint })
+                if key == KeyCode::Char('d') =>
+            {
+                AppMode::CreateHost(CreateHostState::EditDiskOptions {
+                    original: config.disk_options.clone(),
+                    config,
+                    lint,
+                    selected: 0,
+                    error: None,
+                })
+            }
+            AppMode::CreateHost(CreateHostState::EditDiskOptions { config, lint, .. }) => {
+                AppMode::CreateHost(CreateHostState::Review { config, lint })
             }
-            AppMode::CreateHost(CreateHostState::Review { config }) => {
+            AppMode::CreateHost(CreateHostState::Review { config, lint }) => {
                 let mut steps = if crate::system::is_live_iso_environment() {
                     vec![
                         StepStatus::new("Cloning configuration repository"),
@@ -930,13 +3191,21 @@ impl App {
                         StepStatus::new("Generating host metadata"),
                     ]
                 };
+                if lint {
+                    steps.push(StepStatus::new("Linting generated files"));
+                }
+                steps.push(StepStatus::new("Evaluating the new host"));
+                steps.push(StepStatus::new("Running nix flake check"));
+                steps.push(StepStatus::new("Moving files into place"));
                 steps[0].status = StepState::Running;
 
                 let new_mode = AppMode::CreateHost(CreateHostState::Generating {
                     config,
+                    lint,
                     step: 0,
                     steps,
                     output: std::collections::VecDeque::new(),
+                    error: None,
                 });
 
                 if let Some(tx) = &self.cmd_tx {
@@ -944,15 +3213,18 @@ impl App {
                 }
                 new_mode
             }
-            AppMode::CreateHost(CreateHostState::Complete { config, success }) => {
+            AppMode::CreateHost(CreateHostState::Complete { config, success, .. }) => {
                 if success {
                     // Auto-proceed to install credentials entry
                     AppMode::Install(InstallState::EnterCredentials {
                         host: config.hostname.clone(),
                         disk: config.disk.clone(),
+                        disk_mode: DiskMode::Wipe,
+                        extra_disks: Vec::new(),
                         credentials: InstallCredentials::default(),
                         active_field: CredentialField::Username,
                         error: None,
+                        remote: None,
                     })
                 } else {
                     AppMode::Install(InstallState::SelectHost { selected: 0 })
@@ -969,12 +3241,40 @@ impl App {
     }
 
     pub(super) async fn handle_back(&mut self) -> Result<()> {
+        // Esc on the secure erase confirmation only closes it
+        if let AppMode::Install(InstallState::Overview {
+            erase_confirm: erase_confirm @ Some(_),
+            ..
+        }) = &mut self.mode
+        {
+            *erase_confirm = None;
+            return Ok(());
+        }
+
+        // Esc on the WiFi passphrase prompt only closes it; wait out a connection attempt
+        if let AppMode::Install(InstallState::NetworkSetup {
+            passphrase,
+            connecting,
+            ..
+        }) = &mut self.mode
+        {
+            if *connecting {
+                return Ok(());
+            }
+            if passphrase.take().is_some() {
+                return Ok(());
+            }
+        }
+
         // Take ownership of the mode to avoid cloning
         let old_mode = mem::replace(&mut self.mode, AppMode::MainMenu { selected: 0 });
 
         let needs_disk_refresh = matches!(
             old_mode,
-            AppMode::Install(InstallState::EnterCredentials { .. })
+            AppMode::Install(InstallState::AssignRoles { .. })
+                | AppMode::Install(InstallState::ConfirmExistingData { .. })
+                | AppMode::Install(InstallState::EditPartitions { .. })
+                | AppMode::Install(InstallState::EnterCredentials { .. })
                 | AppMode::Install(InstallState::Overview { .. })
                 | AppMode::CreateHost(CreateHostState::EnterHostname { .. })
         );
@@ -985,33 +3285,191 @@ impl App {
             | AppMode::Apps(AppProfileState::Status { .. }) => {
                 AppMode::Apps(AppProfileState::Menu { selected: 0 })
             }
-            AppMode::Keys(KeysState::Complete { .. }) => AppMode::MainMenu { selected: 2 },
-            AppMode::Install(InstallState::SelectHost { .. }) => {
+            AppMode::Keys(KeysState::EnterPassphrase { .. })
+            | AppMode::Keys(KeysState::Complete { .. }) => AppMode::MainMenu { selected: 2 },
+            AppMode::Install(InstallState::Preflight { .. })
+            | AppMode::Install(InstallState::SelectHost { .. }) => {
                 AppMode::MainMenu { selected: 0 }
             }
             AppMode::Install(InstallState::SelectDisk { .. }) => {
                 AppMode::Install(InstallState::SelectHost { selected: 0 })
             }
-            AppMode::Install(InstallState::EnterCredentials { host, disk, .. }) => {
-                // Go back to disk selection
+            AppMode::Install(InstallState::AssignRoles { host, disks, .. }) => {
+                // Back to disk selection with the same disks still marked
+                AppMode::Install(InstallState::SelectDisk {
+                    host,
+                    marked: disks.iter().map(|d| d.path.clone()).collect(),
+                    disks,
+                    selected: 0,
+                    disk_mode: DiskMode::Wipe,
+                    error: None,
+                })
+            }
+            // A remote install starts here; there is no local disk list to go back to
+            AppMode::Install(InstallState::EnterCredentials {
+                remote: Some(_), ..
+            }) => AppMode::MainMenu { selected: 0 },
+            AppMode::Install(InstallState::EnterCredentials {
+                host,
+                disk,
+                mut credentials,
+                ..
+            }) if credentials.partitions.is_some() => {
+                // Back to the partition step with the sizes that were entered
+                let layout = credentials.partitions.take().unwrap_or_default();
+                AppMode::Install(InstallState::EditPartitions {
+                    host,
+                    disk,
+                    credentials,
+                    inputs: PartitionInputs::from_layout(&layout),
+                    active_field: PartitionField::Esp,
+                    error: None,
+                })
+            }
+            AppMode::Install(InstallState::ConfirmExistingData { host, disk, .. })
+            | AppMode::Install(InstallState::EditPartitions { host, disk, .. }) => {
+                AppMode::Install(InstallState::SelectDisk {
+                    host,
+                    marked: Vec::new(),
+                    disks: vec![disk],
+                    selected: 0,
+                    disk_mode: DiskMode::Wipe,
+                    error: None,
+                })
+            }
+            AppMode::Install(InstallState::EnterCredentials {
+                host,
+                disk,
+                disk_mode,
+                extra_disks,
+                ..
+            }) => {
+                // Go back to disk selection, keeping the chosen disks marked
+                let mut disks = vec![disk];
+                disks.extend(extra_disks.into_iter().map(|a| a.disk));
                 AppMode::Install(InstallState::SelectDisk {
                     host,
-                    disks: vec![disk], // Keep the selected disk
+                    marked: if disks.len() > 1 {
+                        disks.iter().map(|d| d.path.clone()).collect()
+                    } else {
+                        Vec::new()
+                    },
+                    disks,
                     selected: 0,
+                    disk_mode,
+                    error: None,
                 })
             }
-            AppMode::Install(InstallState::Overview { host, disk, credentials, .. }) => {
-                // Go back to credentials entry, keeping the entered credentials
+            AppMode::Install(InstallState::SelectLocale {
+                host,
+                disk,
+                disk_mode,
+                extra_disks,
+                mut credentials,
+                remote,
+                locale,
+                ..
+            }) => {
+                // Go back to credentials entry, keeping what was typed on both screens
+                credentials.locale = Some(locale);
                 AppMode::Install(InstallState::EnterCredentials {
                     host,
                     disk,
+                    disk_mode,
+                    extra_disks,
                     credentials,
                     active_field: CredentialField::Username,
                     error: None,
+                    remote,
+                })
+            }
+            AppMode::Install(InstallState::Overview {
+                host,
+                disk,
+                disk_mode,
+                extra_disks,
+                credentials,
+                remote,
+                ..
+            }) => {
+                // Go back to the locale step, keeping the entered settings
+                let locale = credentials.locale.clone().unwrap_or_else(LocaleSettings::detect);
+                AppMode::Install(InstallState::SelectLocale {
+                    host,
+                    disk,
+                    disk_mode,
+                    extra_disks,
+                    credentials,
+                    remote,
+                    locale,
+                    active_field: LocaleField::Timezone,
+                    error: None,
                 })
             }
+            // Back to the overview, which asks for "yes" again, or the
+            // preflight checks, which run again
+            AppMode::Install(InstallState::NetworkSetup { resume, .. }) => {
+                let mut resume = *resume;
+                if let InstallState::Overview { input, .. } = &mut resume {
+                    input.clear();
+                }
+                AppMode::Install(resume)
+            }
             AppMode::Install(InstallState::Complete { .. }) => AppMode::MainMenu { selected: 0 },
-            AppMode::Update(UpdateState::Complete { .. }) => AppMode::MainMenu { selected: 1 },
+            // Esc in the directory browser returns to the input list
+            AppMode::Update(UpdateState::PickOverride(mut picker)) if picker.input.is_some() => {
+                picker.input = None;
+                picker.error = None;
+                AppMode::Update(UpdateState::PickOverride(picker))
+            }
+            AppMode::Update(UpdateState::PickOverride(_))
+            | AppMode::Update(UpdateState::PickActivation { .. })
+            | AppMode::Update(UpdateState::Complete { .. })
+            | AppMode::Fleet(FleetState::Complete { .. })
+            | AppMode::Fleet(FleetState::Status { .. }) => AppMode::MainMenu { selected: 1 },
+            AppMode::Pipeline(PipelineState::Menu { .. })
+            | AppMode::Pipeline(PipelineState::Complete { .. }) => AppMode::MainMenu { selected: 0 },
+            AppMode::DevShells(_) => AppMode::MainMenu { selected: 3 },
+            // Esc closes the add form first
+            AppMode::Registry(state) if state.form.is_some() => {
+                AppMode::Registry(RegistryState { form: None, ..state })
+            }
+            AppMode::Registry(_) => AppMode::MainMenu { selected: 4 },
+            // Esc cancels a value being typed first
+            AppMode::Gc(state) if state.input.is_some() => AppMode::Gc(GcState {
+                input: None,
+                input_error: None,
+                ..state
+            }),
+            AppMode::Gc(_) | AppMode::Optimise(OptimiseState::Complete { .. }) => {
+                AppMode::MainMenu { selected: 5 }
+            }
+            // Esc answers "no" to a pending switch first
+            AppMode::Rollback(RollbackState::Select {
+                generations,
+                selected,
+                confirm: Some(_),
+            }) => AppMode::Rollback(RollbackState::Select {
+                generations,
+                selected,
+                confirm: None,
+            }),
+            AppMode::Rollback(RollbackState::Select { .. })
+            | AppMode::Rollback(RollbackState::Complete { .. }) => AppMode::MainMenu { selected: 6 },
+            // Esc answers "no" to a pending change first
+            AppMode::Generations(state) if state.confirm.is_some() => {
+                AppMode::Generations(GenerationsState { confirm: None, ..state })
+            }
+            AppMode::Generations(_) => AppMode::MainMenu { selected: 7 },
+            AppMode::Channels(_) => AppMode::MainMenu { selected: 8 },
+            AppMode::Services(_) => AppMode::MainMenu { selected: 9 },
+            // Esc cancels a value being typed first
+            AppMode::NixConf(state) if state.input.is_some() => AppMode::NixConf(NixConfState {
+                input: None,
+                input_error: None,
+                ..state
+            }),
+            AppMode::NixConf(_) | AppMode::Parallelism(_) => AppMode::MainMenu { selected: 10 },
             // CreateHost back navigation - take ownership to avoid clones
             AppMode::CreateHost(CreateHostState::DetectingHardware) => {
                 AppMode::Install(InstallState::SelectHost { selected: 0 })
@@ -1067,7 +3525,67 @@ impl App {
                 disks: Vec::new(),
                 selected: 0,
             }),
-            AppMode::CreateHost(CreateHostState::Review { config }) => {
+            AppMode::CreateHost(CreateHostState::EditDiskOptions {
+                mut config,
+                lint,
+                original,
+                ..
+            }) => {
+                config.disk_options = original;
+                AppMode::CreateHost(CreateHostState::Review { config, lint })
+            }
+            AppMode::CreateHost(CreateHostState::Review { config, .. }) => {
+                AppMode::CreateHost(CreateHostState::SelectTheme {
+                    selected: config.theme.index(),
+                    config,
+                })
+            }
+            AppMode::CreateHost(CreateHostState::SelectTheme { config, .. }) => {
+                AppMode::CreateHost(CreateHostState::SelectModules {
+                    config,
+                    selected: 0,
+                })
+            }
+            AppMode::CreateHost(CreateHostState::SelectModules { config, .. }) => {
+                AppMode::CreateHost(CreateHostState::SelectKernel {
+                    selected: config.kernel.index(),
+                    params: config.kernel_params.join(" "),
+                    config,
+                    error: None,
+                })
+            }
+            AppMode::CreateHost(CreateHostState::SelectKernel { config, .. }) => {
+                AppMode::CreateHost(CreateHostState::SelectSwap {
+                    selected: config.swap.index(),
+                    config,
+                    error: None,
+                })
+            }
+            AppMode::CreateHost(CreateHostState::SelectSwap { config, .. })
+                if config.filesystem == RootFilesystem::Zfs =>
+            {
+                AppMode::CreateHost(CreateHostState::SelectFilesystem {
+                    selected: config.filesystem.index(),
+                    config,
+                    error: None,
+                })
+            }
+            AppMode::CreateHost(CreateHostState::SelectSwap { config, .. }) => {
+                AppMode::CreateHost(CreateHostState::SelectLayout {
+                    selected: config.subvolume_layout.index(),
+                    custom: format_subvolumes(&config.custom_subvolumes),
+                    config,
+                    error: None,
+                })
+            }
+            AppMode::CreateHost(CreateHostState::SelectLayout { config, .. }) => {
+                AppMode::CreateHost(CreateHostState::SelectFilesystem {
+                    selected: config.filesystem.index(),
+                    config,
+                    error: None,
+                })
+            }
+            AppMode::CreateHost(CreateHostState::SelectFilesystem { config, .. }) => {
                 // Destructure to take ownership of all fields
                 let NewHostConfig {
                     hostname,
@@ -1075,6 +3593,7 @@ impl App {
                     gpu,
                     form_factor,
                     disk,
+                    ..
                 } = config;
                 AppMode::CreateHost(CreateHostState::EnterHostname {
                     cpu,
@@ -1088,6 +3607,13 @@ impl App {
             AppMode::CreateHost(CreateHostState::Complete { .. }) => {
                 AppMode::Install(InstallState::SelectHost { selected: 0 })
             }
+            AppMode::Bootstrap(state @ (BootstrapState::Prompt { .. } | BootstrapState::Complete { .. })) => {
+                // Same as "Remind me at next login"
+                self.should_quit = true;
+                AppMode::Bootstrap(state)
+            }
+            // Aborts the bisection; a running rebuild has to finish or be cancelled first
+            AppMode::Bisect(state) if !state.building() => AppMode::MainMenu { selected: 0 },
             other => {
                 // Restore the original mode if no match
                 self.mode = other;
@@ -1095,10 +3621,53 @@ impl App {
             }
         };
 
-        if needs_disk_refresh {
+        let rerun_preflight =
+            matches!(self.mode, AppMode::Install(InstallState::Preflight { checks: None }));
+        if needs_disk_refresh || rerun_preflight {
             self.start_initial_command().await?;
         }
 
         Ok(())
     }
 }
+
+/// The WiFi setup screen, scanning, going back to `resume` when done
+pub(super) fn open_network_setup(resume: InstallState) -> InstallState {
+    InstallState::NetworkSetup {
+        resume: Box::new(resume),
+        networks: None,
+        selected: 0,
+        passphrase: None,
+        connecting: false,
+        error: None,
+    }
+}
+
+/// The step after a single disk was chosen: partition sizes for an erased disk
+/// with the default UEFI btrfs layout, credentials otherwise
+fn disk_chosen(host: &str, disk: DiskInfo, disk_mode: DiskMode) -> AppMode {
+    let host_disko = (disk_mode == DiskMode::Wipe)
+        .then(|| read_host_disko(host))
+        .flatten()
+        .filter(|content| supports_size_editing(content));
+    match host_disko {
+        Some(content) => AppMode::Install(InstallState::EditPartitions {
+            host: host.to_string(),
+            disk,
+            credentials: InstallCredentials::default(),
+            inputs: PartitionInputs::from_layout(&PartitionLayout::from_disko(&content)),
+            active_field: PartitionField::Esp,
+            error: None,
+        }),
+        None => AppMode::Install(InstallState::EnterCredentials {
+            host: host.to_string(),
+            disk,
+            disk_mode,
+            extra_disks: Vec::new(),
+            credentials: InstallCredentials::default(),
+            active_field: CredentialField::Username,
+            error: None,
+            remote: None,
+        }),
+    }
+}
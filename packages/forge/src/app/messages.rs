@@ -5,12 +5,16 @@ use regex::Regex;
 use std::sync::LazyLock;
 
 use super::state::{
-    AppMode, AppProfileState, CommitInfo, CreateHostState, InstallState, KeysState, StepState,
-    StepStatus, UpdateState,
+    AppMode, AppProfileState, BisectState, BootstrapState, Changelog, CommitInfo, CreateHostState,
+    FleetState, InstallState, KeysOp, KeysState, OptimiseState, PassphrasePrompt, PipelineState,
+    RollbackState, StepState, StepStatus, UpdateState,
 };
+use super::handlers::open_network_setup;
+use super::scheduler::{BackgroundTask, TaskResult};
 use super::App;
+use crate::commands::apps::UpdateCheck;
 use crate::commands::errors::ParsedError;
-use crate::commands::CommandMessage;
+use crate::commands::{self, CommandMessage, OutputLine};
 use crate::constants::OUTPUT_BUFFER_SIZE;
 
 /// Regex to match ANSI escape codes.
@@ -23,11 +27,19 @@ fn strip_ansi_codes(s: &str) -> String {
 }
 
 impl App {
-    /// Handle messages from running commands
+    /// Handle a line of output from a running command
+    pub fn handle_output_line(&mut self, line: OutputLine) {
+        self.append_output(&line.text());
+    }
+
+    /// Handle events from running commands
     pub async fn handle_command_message(&mut self, msg: CommandMessage) -> Result<()> {
         match msg {
-            CommandMessage::Stdout(line) | CommandMessage::Stderr(line) => {
-                self.append_output(&line);
+            CommandMessage::Warning(message) => {
+                self.append_output(&format!("Warning: {}", message));
+            }
+            CommandMessage::StepStarted { step } => {
+                self.mark_step_started(&step);
             }
             CommandMessage::StepComplete { step } => {
                 self.mark_step_complete(&step);
@@ -38,34 +50,320 @@ impl App {
             CommandMessage::StepSkipped { step } => {
                 self.mark_step_skipped(&step);
             }
+            CommandMessage::StepProgress { step, progress } => {
+                if let AppMode::Install(InstallState::Running { steps, .. }) = &mut self.mode {
+                    if let Some(s) = steps.iter_mut().find(|s| Self::step_matches(s, &step)) {
+                        s.progress = Some(progress);
+                    }
+                }
+            }
+            CommandMessage::PassphraseRejected => {
+                self.handle_passphrase_rejected();
+            }
+            CommandMessage::NetworkUnavailable => {
+                self.handle_network_unavailable().await?;
+            }
+            CommandMessage::ReviewEdit(review) => {
+                self.append_output(&format!("Review the {} change: {}", review.file, review.reason));
+                self.edit_review = Some(review);
+                self.edit_review_scroll = 0;
+            }
+            CommandMessage::PreviewSwitch(preview) => {
+                self.append_output("Review the new system before switching to it");
+                self.switch_preview = Some(preview);
+                self.switch_preview_scroll = 0;
+            }
             CommandMessage::Done { success } => {
                 self.handle_command_done(success);
+                self.capture_build_logs(success).await?;
+            }
+            CommandMessage::HostsDiscovered(hosts) => {
+                self.hosts = hosts;
+                self.hosts_loading = false;
+                self.hosts_discovered = true;
+                // The list may have shrunk since the cached copy was shown
+                if let AppMode::Install(InstallState::SelectHost { selected }) = &mut self.mode {
+                    *selected = (*selected).min(self.hosts.len());
+                }
+            }
+            CommandMessage::DisksDetected(detected) => {
+                self.disks_loading = false;
+                match &mut self.mode {
+                    AppMode::Install(InstallState::SelectDisk { disks, selected, .. })
+                    | AppMode::CreateHost(CreateHostState::SelectDisk { disks, selected, .. }) => {
+                        *disks = detected;
+                        *selected = (*selected).min(disks.len().saturating_sub(1));
+                    }
+                    _ => {}
+                }
+            }
+            CommandMessage::PreflightComplete(results) => {
+                if let AppMode::Install(InstallState::Preflight { checks }) = &mut self.mode {
+                    *checks = Some(results);
+                }
+            }
+            CommandMessage::FleetStage { host, stage } => {
+                if let AppMode::Fleet(FleetState::Running { stages, .. }) = &mut self.mode {
+                    stages.insert(host, stage);
+                }
+            }
+            CommandMessage::FleetStatus(result) => {
+                if let AppMode::Fleet(FleetState::Status { report }) = &mut self.mode {
+                    *report = Some(result);
+                }
+            }
+            CommandMessage::DevShellsFound(found) => {
+                if let AppMode::DevShells(state) = &mut self.mode {
+                    state.selected = state.selected.min(found.len().saturating_sub(1));
+                    state.shells = Some(found);
+                }
+            }
+            CommandMessage::Registry { rows, message } => {
+                if let AppMode::Registry(state) = &mut self.mode {
+                    if let Ok(rows) = &rows {
+                        state.selected = state.selected.min(rows.len().saturating_sub(1));
+                    }
+                    state.rows = Some(rows);
+                    state.status = message;
+                }
+            }
+            CommandMessage::GarbageCollected { freed } => {
+                if let AppMode::Optimise(OptimiseState::Running { gc: Some(gc), .. }) =
+                    &mut self.mode
+                {
+                    gc.freed = Some(freed);
+                }
+            }
+            CommandMessage::BuildProgress(progress) => {
+                self.build_progress = Some(progress);
+            }
+            CommandMessage::OptimiseProgress(latest) => {
+                if let AppMode::Optimise(OptimiseState::Running { progress, .. }) = &mut self.mode {
+                    *progress = latest;
+                }
+            }
+            CommandMessage::GenerationTable { generations, message } => {
+                if let AppMode::Generations(state) = &mut self.mode {
+                    if let Ok(list) = &generations {
+                        state.selected = state.selected.min(list.len().saturating_sub(1));
+                    }
+                    state.generations = Some(generations);
+                    state.status = message;
+                    state.marked.clear();
+                }
+            }
+            CommandMessage::Generations(result) => {
+                if let AppMode::Rollback(RollbackState::Select {
+                    generations, selected, ..
+                }) = &mut self.mode
+                {
+                    if let Ok(found) = &result {
+                        *selected = (*selected).min(found.len().saturating_sub(1));
+                    }
+                    *generations = Some(result);
+                }
+            }
+            CommandMessage::BisectRange(result) => {
+                if let AppMode::Bisect(state) = &mut self.mode {
+                    state.bisection = Some(result);
+                    self.start_initial_command().await?;
+                }
+            }
+            CommandMessage::BuildLogsSaved(saved) => {
+                for log in &saved {
+                    self.log_to_screen(&format!("Saved build log: {}", log.path.display()));
+                }
+                self.build_logs.saved = saved;
+            }
+            CommandMessage::Changelog {
+                inputs,
+                package_changes,
+            } => {
+                self.changelog = Changelog {
+                    inputs,
+                    package_changes,
+                    ..Default::default()
+                };
+            }
+            CommandMessage::UpdateReport(report) => {
+                if let AppMode::Update(UpdateState::Running {
+                    report: Some(path), ..
+                }) = &self.mode
+                {
+                    let line = match report.write(path) {
+                        Ok(()) => format!("Wrote the update report to {}", path.display()),
+                        Err(e) => format!("Failed to write the update report: {:#}", e),
+                    };
+                    self.append_output(&line);
+                }
+                self.update_report = Some(*report);
+            }
+            CommandMessage::LogBundleSaved(result) => match result {
+                Ok(path) => self.append_output(&format!("Install logs saved to {}", path.display())),
+                Err(e) => self.append_output(&format!("Failed to save install logs: {}", e)),
+            },
+            CommandMessage::WifiNetworks(result) => {
+                if let AppMode::Install(InstallState::NetworkSetup {
+                    networks, selected, error, ..
+                }) = &mut self.mode
+                {
+                    match result {
+                        Ok(found) => {
+                            *selected = (*selected).min(found.len().saturating_sub(1));
+                            *networks = Some(found);
+                        }
+                        Err(e) => {
+                            *networks = Some(Vec::new());
+                            *error = Some(e);
+                        }
+                    }
+                }
+            }
+            CommandMessage::WifiConnected(result) => {
+                if let AppMode::Install(InstallState::NetworkSetup {
+                    resume,
+                    connecting,
+                    error,
+                    ..
+                }) = &mut self.mode
+                {
+                    match result {
+                        // Online: back to the install or the preflight checks
+                        Ok(message) => {
+                            let resume = (**resume).clone();
+                            self.log_to_screen(&message);
+                            self.leave_network_setup(resume).await?;
+                        }
+                        Err(e) => {
+                            *connecting = false;
+                            *error = Some(e);
+                        }
+                    }
+                }
+            }
+            CommandMessage::Channels {
+                hosts,
+                stable,
+                message,
+            } => {
+                if let AppMode::Channels(state) = &mut self.mode {
+                    if let Ok(list) = &hosts {
+                        state.selected = state.selected.min(list.len().saturating_sub(1));
+                    }
+                    state.hosts = Some(hosts);
+                    state.stable = stable;
+                    state.status = message;
+                }
+            }
+            CommandMessage::Services {
+                host,
+                rows,
+                message,
+            } => {
+                if let AppMode::Services(state) = &mut self.mode {
+                    if let Ok(list) = &rows {
+                        state.selected = state.selected.min(list.len().saturating_sub(1));
+                    }
+                    state.host = host;
+                    state.rows = Some(rows);
+                    state.status = message;
+                }
+            }
+            CommandMessage::Gc {
+                config,
+                hosts,
+                message,
+            } => {
+                if let AppMode::Gc(state) = &mut self.mode {
+                    state.scope = state.scope.min(hosts.len());
+                    state.hosts = hosts;
+                    state.config = Some(config);
+                    state.status = message;
+                }
+            }
+            CommandMessage::NixConf {
+                config,
+                hosts,
+                this_host,
+                live,
+                message,
+            } => {
+                if let AppMode::NixConf(state) = &mut self.mode {
+                    state.scope = state.scope.min(hosts.len());
+                    state.hosts = hosts;
+                    state.this_host = this_host;
+                    state.live = Some(live);
+                    state.config = Some(config);
+                    state.status = message;
+                }
             }
             CommandMessage::UpdatesAvailable {
                 nixos_config,
                 app_profiles,
                 commits,
+                flake_inputs,
             } => {
                 self.startup_check_running = false;
-                if (nixos_config || app_profiles) && matches!(self.mode, AppMode::MainMenu { .. }) {
-                    self.pending_updates.nixos_config = nixos_config;
-                    self.pending_updates.app_profiles = app_profiles;
-                    self.pending_updates.commits = commits
-                        .into_iter()
-                        .map(|(hash, message)| CommitInfo { hash, message })
-                        .collect();
-                    self.pending_updates.selected = 0;
-                    self.pending_updates.viewing_commits = false;
-                    self.pending_updates.commit_scroll = 0;
+                self.scheduler.finished(BackgroundTask::UpdateCheck);
+                let check = UpdateCheck {
+                    nixos_config,
+                    app_profiles,
+                    commits,
+                    flake_inputs,
+                };
+                self.show_updates(check, false);
+            }
+            CommandMessage::TaskFinished(result) => {
+                self.scheduler.finished(result.task());
+                match result {
+                    TaskResult::Updates(check) => self.show_updates(check, true),
+                    TaskResult::GitStatus(status) => self.repo_status = status,
                 }
             }
         }
         Ok(())
     }
 
-    fn append_output(&mut self, line: &str) {
+    /// Open the update dialog on the main menu; a refresh only opens it when
+    /// the result differs from the last check, so a dismissed dialog stays closed
+    fn show_updates(&mut self, check: UpdateCheck, refresh: bool) {
+        if refresh && check == self.last_update_check {
+            return;
+        }
+        self.last_update_check = check.clone();
+        let UpdateCheck {
+            nixos_config,
+            app_profiles,
+            commits,
+            flake_inputs,
+        } = check;
+        // Outdated flake inputs are applied by a system update, same as new commits
+        let nixos_config = nixos_config || !flake_inputs.is_empty();
+        if (nixos_config || app_profiles) && matches!(self.mode, AppMode::MainMenu { .. }) {
+            self.pending_updates.nixos_config = nixos_config;
+            self.pending_updates.flake_inputs = flake_inputs;
+            self.pending_updates.app_profiles = app_profiles;
+            self.pending_updates.commits = commits
+                .into_iter()
+                .map(|(hash, message)| CommitInfo { hash, message })
+                .collect();
+            self.pending_updates.selected = 0;
+            self.pending_updates.viewing_commits = false;
+            self.pending_updates.commit_scroll = 0;
+        }
+    }
+
+    pub(super) fn append_output(&mut self, line: &str) {
+        // Messages from nix's internal-json log can span several lines
+        if line.contains('\n') {
+            for part in line.lines() {
+                self.append_output(part);
+            }
+            return;
+        }
         let clean_line = strip_ansi_codes(line);
         self.log_to_screen(&clean_line);
+        self.build_logs.observe(&clean_line);
 
         match &mut self.mode {
             AppMode::Update(UpdateState::Running { output, .. })
@@ -75,7 +373,8 @@ impl App {
                     output.pop_front();
                 }
             }
-            AppMode::Install(InstallState::Running { output, .. }) => {
+            AppMode::Install(InstallState::Running { output, .. })
+            | AppMode::Install(InstallState::Complete { output, .. }) => {
                 output.push_back(clean_line);
                 while output.len() > OUTPUT_BUFFER_SIZE {
                     output.pop_front();
@@ -96,6 +395,17 @@ impl App {
                     output.pop_front();
                 }
             }
+            AppMode::Bootstrap(BootstrapState::Running { output, .. })
+            | AppMode::Fleet(FleetState::Running { output, .. })
+            | AppMode::Pipeline(PipelineState::Running { output, .. })
+            | AppMode::Optimise(OptimiseState::Running { output, .. })
+            | AppMode::Rollback(RollbackState::Running { output, .. })
+            | AppMode::Bisect(BisectState { output, .. }) => {
+                output.push_back(clean_line);
+                while output.len() > OUTPUT_BUFFER_SIZE {
+                    output.pop_front();
+                }
+            }
             AppMode::CreateHost(CreateHostState::Generating { output, .. }) => {
                 output.push_back(clean_line);
                 while output.len() > OUTPUT_BUFFER_SIZE {
@@ -124,6 +434,31 @@ impl App {
         false
     }
 
+    /// Find a step by exact name first, then by fuzzy match.
+    ///
+    /// Fleet steps are host names, where fuzzy matching would confuse e.g.
+    /// `G1` with `G1a`.
+    fn find_step<'a>(steps: &'a mut [StepStatus], step_name: &str) -> Option<&'a mut StepStatus> {
+        let index = steps
+            .iter()
+            .position(|s| s.name == step_name)
+            .or_else(|| steps.iter().position(|s| Self::step_matches(s, step_name)))?;
+        steps.get_mut(index)
+    }
+
+    fn mark_step_started(&mut self, step_name: &str) {
+        match &mut self.mode {
+            AppMode::Fleet(FleetState::Running { steps, .. })
+            | AppMode::Pipeline(PipelineState::Running { steps, .. })
+            | AppMode::Rollback(RollbackState::Running { steps, .. }) => {
+                if let Some(s) = Self::find_step(steps, step_name) {
+                    s.status = StepState::Running;
+                }
+            }
+            _ => {}
+        }
+    }
+
     fn mark_step_complete(&mut self, step_name: &str) {
         self.log_to_screen(&format!("[✓] Step complete: {}", step_name));
 
@@ -146,7 +481,8 @@ impl App {
                     steps[*step].status = StepState::Running;
                 }
             }
-            AppMode::CreateHost(CreateHostState::Generating { steps, step, .. }) => {
+            AppMode::CreateHost(CreateHostState::Generating { steps, step, .. })
+            | AppMode::Bootstrap(BootstrapState::Running { steps, step, .. }) => {
                 if let Some(s) = steps.iter_mut().find(|s| Self::step_matches(s, step_name)) {
                     s.status = StepState::Complete;
                 }
@@ -155,22 +491,23 @@ impl App {
                     steps[*step].status = StepState::Running;
                 }
             }
+            // Hosts may finish out of order; each reports its own start
+            AppMode::Fleet(FleetState::Running { steps, .. })
+            | AppMode::Pipeline(PipelineState::Running { steps, .. })
+            | AppMode::Rollback(RollbackState::Running { steps, .. }) => {
+                if let Some(s) = Self::find_step(steps, step_name) {
+                    s.status = StepState::Complete;
+                }
+            }
             _ => {}
         }
     }
 
     fn mark_step_failed(&mut self, step_name: &str, error: ParsedError) {
         // Log formatted error to screen
-        self.log_to_screen(&format!("[✗] Step failed: {}", step_name));
-        self.log_to_screen("");
-        self.log_to_screen(&format!("  Error: {}", error.summary));
-        if let Some(ref detail) = error.detail {
-            for line in detail.lines() {
-                self.log_to_screen(&format!("  {}", line));
-            }
+        for line in error.panel_lines(step_name) {
+            self.log_to_screen(&line);
         }
-        self.log_to_screen("");
-        self.log_to_screen(&format!("  Suggestion: {}", error.suggestion));
 
         match &mut self.mode {
             AppMode::Update(UpdateState::Running { steps, .. }) => {
@@ -185,12 +522,33 @@ impl App {
                 }
                 self.error = Some(error.summary);
             }
-            AppMode::CreateHost(CreateHostState::Generating { steps, .. }) => {
+            AppMode::CreateHost(CreateHostState::Generating { steps, error: failure, .. }) => {
                 if let Some(s) = steps.iter_mut().find(|s| Self::step_matches(s, step_name)) {
                     s.status = StepState::Failed;
                 }
+                // Later failures are the run giving up because of the first
+                if failure.is_none() {
+                    *failure = Some(error.detail.clone().unwrap_or_else(|| error.summary.clone()));
+                }
                 self.error = Some(error.summary);
             }
+            AppMode::Bootstrap(BootstrapState::Running { steps, .. }) => {
+                if let Some(s) = steps.iter_mut().find(|s| Self::step_matches(s, step_name)) {
+                    s.status = StepState::Failed;
+                }
+                self.error = Some(error.summary);
+            }
+            AppMode::Fleet(FleetState::Running { steps, .. })
+            | AppMode::Pipeline(PipelineState::Running { steps, .. })
+            | AppMode::Rollback(RollbackState::Running { steps, .. }) => {
+                if let Some(s) = Self::find_step(steps, step_name) {
+                    s.status = StepState::Failed;
+                }
+                self.error = Some(error.summary);
+            }
+            AppMode::Bisect(state) => {
+                state.output.push_back(format!("Error: {}", error.summary));
+            }
             _ => {}
         }
     }
@@ -208,16 +566,92 @@ impl App {
                     steps[*step].status = StepState::Running;
                 }
             }
+            AppMode::Fleet(FleetState::Running { steps, .. })
+            | AppMode::Pipeline(PipelineState::Running { steps, .. })
+            | AppMode::Rollback(RollbackState::Running { steps, .. }) => {
+                if let Some(s) = Self::find_step(steps, step_name) {
+                    s.status = StepState::Skipped;
+                }
+            }
             _ => {}
         }
     }
 
+    /// Save `nix log` of the derivations that failed in the run that just ended
+    async fn capture_build_logs(&mut self, success: bool) -> Result<()> {
+        let failed = std::mem::take(&mut self.build_logs.failed);
+        self.build_logs.saved.clear();
+        if success || failed.is_empty() {
+            return Ok(());
+        }
+        if let Some(tx) = &self.cmd_tx {
+            commands::build_log::start_build_log_capture(tx.clone(), failed).await?;
+        }
+        Ok(())
+    }
+
+    /// Open the network setup screen when a local install found no network
+    /// (Done follows, but leaves this screen alone)
+    async fn handle_network_unavailable(&mut self) -> Result<()> {
+        let AppMode::Install(InstallState::Running {
+            overview: Some(overview),
+            ..
+        }) = &self.mode
+        else {
+            return Ok(());
+        };
+        self.mode = AppMode::Install(open_network_setup((**overview).clone()));
+        self.start_initial_command().await
+    }
+
+    /// Back to the passphrase prompt after a wrong keys archive passphrase,
+    /// until the attempts run out (then Done shows the failure as usual)
+    fn handle_passphrase_rejected(&mut self) {
+        let AppMode::Keys(KeysState::Running {
+            operation,
+            force,
+            attempts,
+            ..
+        }) = &self.mode
+        else {
+            return;
+        };
+        let attempts = attempts + 1;
+        if attempts >= commands::keys::MAX_PASSPHRASE_ATTEMPTS {
+            return;
+        }
+        let left = commands::keys::MAX_PASSPHRASE_ATTEMPTS - attempts;
+        let mut prompt = PassphrasePrompt::new(false);
+        prompt.attempts = attempts;
+        prompt.error = Some(format!(
+            "Wrong passphrase, {} attempt{} left",
+            left,
+            if left == 1 { "" } else { "s" }
+        ));
+        self.mode = AppMode::Keys(KeysState::EnterPassphrase {
+            operation: operation.clone(),
+            force: *force,
+            prompt,
+        });
+    }
+
     fn handle_command_done(&mut self, success: bool) {
         self.log_to_screen(&format!(
             "\n=== Operation {} ===\n",
             if success { "COMPLETED" } else { "FAILED" }
         ));
 
+        // The host exists now; a failed run keeps the draft to retry from
+        if success && matches!(self.mode, AppMode::CreateHost(CreateHostState::Generating { .. })) {
+            self.discard_host_draft();
+        }
+
+        // A failed nixos-install leaves /mnt mounted, so it can be fixed and retried
+        let retry = match &self.mode {
+            AppMode::Install(state) => state.retry_point(success),
+            _ => None,
+        };
+
         match &mut self.mode {
             AppMode::Apps(AppProfileState::Running { output, .. }) => {
                 self.mode = AppMode::Apps(AppProfileState::Complete {
@@ -226,32 +660,104 @@ impl App {
                     scroll_offset: None, // None = auto-scroll continues
                 });
             }
-            AppMode::Keys(KeysState::Running { output, .. }) => {
+            AppMode::Keys(KeysState::Running {
+                operation, output, ..
+            }) => {
                 self.mode = AppMode::Keys(KeysState::Complete {
                     success,
                     output: output.clone(),
                     scroll_offset: None, // None = auto-scroll continues
+                    offer_github: success && *operation == KeysOp::Setup,
                 });
             }
-            AppMode::Install(InstallState::Running { output, .. }) => {
+            AppMode::Install(InstallState::Running { host, output, .. }) => {
                 self.mode = AppMode::Install(InstallState::Complete {
                     success,
                     output: output.clone(),
                     scroll_offset: None, // None = auto-scroll continues
+                    host: Some(host.clone()),
+                    retry,
                 });
             }
-            AppMode::Update(UpdateState::Running { steps, output, .. }) => {
+            AppMode::Update(UpdateState::Running {
+                steps,
+                output,
+                overrides,
+                ..
+            }) => {
                 self.mode = AppMode::Update(UpdateState::Complete {
                     success,
                     steps: steps.clone(),
                     output: output.clone(),
                     scroll_offset: None, // None = auto-scroll continues
+                    overrides: overrides.clone(),
+                });
+            }
+            AppMode::Bootstrap(BootstrapState::Running { steps, output, .. }) => {
+                self.mode = AppMode::Bootstrap(BootstrapState::Complete {
+                    success,
+                    steps: steps.clone(),
+                    output: output.clone(),
+                    scroll_offset: None, // None = auto-scroll continues
+                });
+            }
+            AppMode::Pipeline(PipelineState::Running {
+                pipeline,
+                steps,
+                output,
+            }) => {
+                self.mode = AppMode::Pipeline(PipelineState::Complete {
+                    success,
+                    title: pipeline.name.clone(),
+                    steps: steps.clone(),
+                    output: output.clone(),
+                    scroll_offset: None, // None = auto-scroll continues
+                });
+            }
+            AppMode::Rollback(RollbackState::Running {
+                target,
+                steps,
+                output,
+            }) => {
+                self.mode = AppMode::Rollback(RollbackState::Complete {
+                    success,
+                    target: *target,
+                    steps: steps.clone(),
+                    output: output.clone(),
+                    scroll_offset: None, // None = auto-scroll continues
+                });
+            }
+            AppMode::Optimise(OptimiseState::Running { progress, output, gc }) => {
+                self.mode = AppMode::Optimise(OptimiseState::Complete {
+                    success,
+                    progress: progress.clone(),
+                    output: output.clone(),
+                    scroll_offset: None, // None = auto-scroll continues
+                    gc: gc.clone(),
+                });
+            }
+            AppMode::Bisect(state) if state.testing.is_some() => {
+                state.built = Some(success);
+            }
+            AppMode::Fleet(FleetState::Running {
+                steps,
+                stages,
+                output,
+                ..
+            }) => {
+                self.mode = AppMode::Fleet(FleetState::Complete {
+                    success,
+                    steps: steps.clone(),
+                    stages: std::mem::take(stages),
+                    output: output.clone(),
+                    scroll_offset: None, // None = auto-scroll continues
                 });
             }
-            AppMode::CreateHost(CreateHostState::Generating { config, .. }) => {
+            AppMode::CreateHost(CreateHostState::Generating { config, error, .. }) => {
                 self.mode = AppMode::CreateHost(CreateHostState::Complete {
                     success,
                     config: config.clone(),
+                    error: error.take(),
                 });
             }
             _ => {}
@@ -4,30 +4,60 @@
 //! - `state.rs` - State type definitions (AppMode, InstallState, etc.)
 //! - `handlers.rs` - Keyboard input handlers
 //! - `messages.rs` - Command message handling
+//! - `scheduler.rs` - Periodic background tasks (update checks, git status)
+//! - `draft.rs` - Saving and resuming the create-host wizard
 
+mod draft;
 mod handlers;
 mod messages;
+pub mod scheduler;
 pub mod state;
 
 use anyhow::Result;
+use std::cell::Cell;
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
 use std::time::Instant;
-use tokio::sync::mpsc;
 
-use crate::commands::{self, CommandMessage};
+use crate::commands::apps::UpdateCheck;
+use crate::commands::update::report::UpdateReport;
+use crate::commands::{self, CommandSender, EditReview, SwitchPreview};
 use crate::constants::SPINNER_TICK_MS;
-use crate::system::config::{discover_hosts, HostConfig};
+use crate::system::build_progress::BuildProgress;
+use crate::system::config::{load_cached_hosts, HostConfig};
 use crate::system::hardware::{CpuVendor, GpuInfo, GpuVendor};
+use crate::system::ipc::ForgeStatus;
+use crate::system::repo_status::RepoStatus;
+use scheduler::{BackgroundTask, Scheduler};
+use state::steps_percent;
 
 // Re-export commonly used types
 pub use state::{
-    AppMode, AppOp, AppProfileState, CreateHostState, CredentialField, InstallCredentials,
-    InstallState, KeysOp, KeysState, NewHostConfig, PendingUpdates, StepState, StepStatus,
-    UpdateState, UpdateSummary, APP_MENU_ITEMS, MAIN_MENU_ITEMS,
+    AppMode, AppOp, AppProfileState, BisectState, BootstrapState, BuildLogs, Changelog,
+    ChannelsState, CreateHostState, CredentialField, DevShellState, DiskOptions, FleetState, GcRun,
+    GcState, GenerationsState, HostModule,
+    InstallCredentials, InstallState, KernelFlavor, KeysOp, KeysState, LocaleField, NewHostConfig,
+    NixConfState, ParallelismState,
+    PartitionField, PartitionInputs,
+    OptimiseState, OverridePicker, PassphrasePrompt, PendingUpdates, PipelineState,
+    RegistryField, RegistryState, RollbackState, ServicesState,
+    RootFilesystem, StepState, StepStatus, SubvolumeLayout, SwapMode, ThemePreset, UpdateState,
+    UpdateSummary, APP_MENU_ITEMS, BOOTSTRAP_MENU_ITEMS, MAIN_MENU_ITEMS,
 };
 
+/// A program to run on the terminal with the TUI suspended, e.g. the
+/// rescue shell after a failed install; the main loop picks it up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellRequest {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Working directory, None for forge's own
+    pub dir: Option<PathBuf>,
+    /// Printed before the program starts
+    pub banner: Vec<String>,
+}
+
 /// Main application state
 pub struct App {
     pub mode: AppMode,
@@ -37,17 +67,55 @@ pub struct App {
     pub pending_updates: PendingUpdates,
     /// Whether the startup update check is in progress
     pub startup_check_running: bool,
-    pub spinner_state: usize,
+    /// Last update check result, so a refresh only reopens the dialog for news
+    last_update_check: UpdateCheck,
+    /// Git status of the config repo for the main menu status bar
+    pub repo_status: Option<RepoStatus>,
+    scheduler: Scheduler,
+    /// Failed derivations of the current run and their saved build logs
+    pub build_logs: BuildLogs,
+    /// Flake input changelogs of the last update
+    pub changelog: Changelog,
+    /// Summary of the last update, for exporting
+    pub update_report: Option<UpdateReport>,
+    /// Paths copied and derivations built by the update's running build
+    pub build_progress: Option<BuildProgress>,
+    spinner_state: usize,
+    /// Whether the last frame showed a spinner, so it needs redrawing per tick
+    spinner_drawn: Cell<bool>,
     pub last_tick: Instant,
     pub error: Option<String>,
+    /// Known hosts (cached list from the last run until discovery completes)
     pub hosts: Vec<HostConfig>,
-    pub(crate) cmd_tx: Option<mpsc::Sender<CommandMessage>>,
+    /// Whether background host discovery is in progress
+    pub hosts_loading: bool,
+    /// Whether `hosts` reflects a discovery from this run
+    hosts_discovered: bool,
+    /// Whether background disk probing is in progress
+    pub disks_loading: bool,
+    /// Unfinished create-host wizard from an earlier run, offered for resuming
+    pub host_draft: Option<CreateHostState>,
+    /// Last draft written this run, to skip unchanged writes
+    host_draft_json: Option<String>,
+    /// flake.nix edit shown for approval while its command waits
+    pub edit_review: Option<EditReview>,
+    pub edit_review_scroll: usize,
+    /// nvd diff of a built system shown before the update switches to it
+    pub switch_preview: Option<SwitchPreview>,
+    pub switch_preview_scroll: usize,
+    /// Set by a key press, taken by the main loop
+    pub shell_request: Option<ShellRequest>,
+    pub(crate) cmd_tx: Option<CommandSender>,
     screen_log: Option<File>,
     pub screen_log_path: PathBuf,
+    /// Asciinema cast of this session, with `--record`
+    pub recording_path: Option<PathBuf>,
 }
 
 impl App {
     pub fn new(initial_mode: AppMode) -> Self {
+        let started = Instant::now();
+
         // Set up screen log file
         let log_dir = crate::constants::forge_data_dir();
         let _ = std::fs::create_dir_all(&log_dir);
@@ -68,23 +136,134 @@ impl App {
             let _ = file.flush();
         }
 
+        // Hosts are discovered lazily; show the cached list until then
+        let hosts = load_cached_hosts().unwrap_or_default();
+
+        // Only the main menu offers to resume a draft
+        let host_draft = match initial_mode {
+            AppMode::MainMenu { .. } => draft::load_host_draft(),
+            _ => None,
+        };
+
+        tracing::debug!("App state initialised in {:?}", started.elapsed());
+
         Self {
             mode: initial_mode,
             should_quit: false,
             show_exit_confirm: false,
             pending_updates: PendingUpdates::default(),
             startup_check_running: false,
+            last_update_check: UpdateCheck::default(),
+            repo_status: None,
+            scheduler: Scheduler::default(),
+            build_logs: BuildLogs::default(),
+            changelog: Changelog::default(),
+            update_report: None,
+            build_progress: None,
             spinner_state: 0,
+            spinner_drawn: Cell::new(false),
             last_tick: Instant::now(),
             error: None,
-            hosts: discover_hosts(),
+            hosts,
+            hosts_loading: false,
+            hosts_discovered: false,
+            disks_loading: false,
+            host_draft,
+            host_draft_json: None,
+            edit_review: None,
+            edit_review_scroll: 0,
+            switch_preview: None,
+            switch_preview_scroll: 0,
+            shell_request: None,
             cmd_tx: None,
             screen_log,
             screen_log_path,
+            recording_path: None,
+        }
+    }
+
+    /// Note in the output how a program run with the TUI suspended ended
+    pub fn shell_finished(&mut self, program: &str, result: std::io::Result<std::process::ExitStatus>) {
+        match result {
+            Ok(status) => self.append_output(&format!("{} exited ({})", program, status)),
+            Err(e) => self.append_output(&format!("Failed to start {}: {}", program, e)),
+        }
+    }
+
+    /// Whether the current screen offers the failed build log viewer
+    pub fn build_log_available(&self) -> bool {
+        !self.build_logs.saved.is_empty()
+            && (matches!(
+                self.mode,
+                AppMode::Install(InstallState::Complete { .. })
+                    | AppMode::Update(UpdateState::Complete { .. })
+                    | AppMode::Fleet(FleetState::Complete { .. })
+                    | AppMode::Pipeline(PipelineState::Complete { .. })
+            ) || matches!(&self.mode, AppMode::Bisect(state) if !state.building()))
+    }
+
+    /// Whether the current screen offers the flake input changelog
+    pub fn changelog_available(&self) -> bool {
+        !self.changelog.inputs.is_empty()
+            && matches!(self.mode, AppMode::Update(UpdateState::Complete { .. }))
+    }
+
+    /// Whether the current screen offers exporting the update report
+    pub fn report_available(&self) -> bool {
+        self.update_report.is_some()
+            && matches!(self.mode, AppMode::Update(UpdateState::Complete { .. }))
+    }
+
+    /// Write the last update's report to the reports directory
+    pub fn export_report(&mut self) {
+        let Some(report) = &self.update_report else {
+            return;
+        };
+        let stamp = report.finished.format("%Y%m%d-%H%M%S");
+        let path = crate::constants::reports_dir().join(format!("update-{}.md", stamp));
+        let line = match report.write(&path) {
+            Ok(()) => format!("Exported the update report to {}", path.display()),
+            Err(e) => format!("Failed to export the update report: {:#}", e),
+        };
+        self.append_output(&line);
+    }
+
+    /// What the status socket reports: the running operation and pending updates
+    pub fn ipc_status(&self) -> ForgeStatus {
+        let running = match &self.mode {
+            AppMode::Update(UpdateState::Running { steps, .. }) => Some(("updating", steps)),
+            AppMode::Install(InstallState::Running { steps, .. }) => Some(("installing", steps)),
+            AppMode::Fleet(FleetState::Running { steps, .. }) => Some(("deploying", steps)),
+            AppMode::Pipeline(PipelineState::Running { steps, .. }) => {
+                Some(("running pipeline", steps))
+            }
+            AppMode::Rollback(RollbackState::Running { steps, .. }) => {
+                Some(("rolling back", steps))
+            }
+            _ => None,
+        };
+        let check = &self.last_update_check;
+        let config_commits = check.commits.len().max(usize::from(check.nixos_config));
+        let pending =
+            ForgeStatus::pending_lines(config_commits, check.app_profiles, &check.flake_inputs);
+        match running {
+            Some((operation, steps)) => ForgeStatus {
+                operation: Some(operation.to_string()),
+                step: steps
+                    .iter()
+                    .find(|s| s.status == StepState::Running)
+                    .map(|s| s.name.clone()),
+                percent: Some(steps_percent(steps)),
+                pending,
+            },
+            None => ForgeStatus {
+                pending,
+                ..Default::default()
+            },
         }
     }
 
-    pub fn set_command_sender(&mut self, tx: mpsc::Sender<CommandMessage>) {
+    pub fn set_command_sender(&mut self, tx: CommandSender) {
         self.cmd_tx = Some(tx);
     }
 
@@ -96,23 +275,86 @@ impl App {
         }
     }
 
-    /// Called on each tick to update animations
-    pub fn tick(&mut self) {
-        if self.last_tick.elapsed().as_millis() >= SPINNER_TICK_MS {
-            self.spinner_state = (self.spinner_state + 1) % 10;
-            self.last_tick = Instant::now();
+    /// Current spinner frame, for screens that show one
+    pub fn spinner_frame(&self) -> usize {
+        self.spinner_drawn.set(true);
+        self.spinner_state
+    }
+
+    /// Called right before drawing a frame
+    pub fn begin_frame(&self) {
+        self.spinner_drawn.set(false);
+    }
+
+    /// Called on each tick to update animations; returns whether the screen
+    /// needs a redraw for it (the spinner moved and the last frame showed it)
+    pub fn tick(&mut self) -> bool {
+        if self.last_tick.elapsed().as_millis() < SPINNER_TICK_MS {
+            return false;
+        }
+        self.spinner_state = (self.spinner_state + 1) % 10;
+        self.last_tick = Instant::now();
+        self.spinner_drawn.get()
+    }
+
+    /// Start host discovery the first time a screen needs the host list;
+    /// returns whether it was started
+    pub async fn ensure_hosts_discovered(&mut self) -> Result<bool> {
+        let needs_hosts = matches!(
+            self.mode,
+            AppMode::Install(InstallState::SelectHost { .. }) | AppMode::CreateHost(_)
+        );
+        if !needs_hosts || self.hosts_discovered || self.hosts_loading {
+            return Ok(false);
+        }
+        let Some(tx) = &self.cmd_tx else {
+            return Ok(false);
+        };
+        self.hosts_loading = true;
+        commands::probe::start_host_discovery(tx.clone()).await?;
+        Ok(true)
+    }
+
+    /// Start the next due background task; they only run on the main menu, so
+    /// they never compete with an update or install for git and the network
+    pub async fn run_background_tasks(&mut self) -> Result<()> {
+        if !matches!(self.mode, AppMode::MainMenu { .. }) {
+            return Ok(());
+        }
+        let Some(tx) = &self.cmd_tx else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        let Some(task) = self.scheduler.next_due(now) else {
+            return Ok(());
+        };
+        self.scheduler.started(task, now);
+        match task {
+            BackgroundTask::UpdateCheck => commands::apps::start_update_refresh(tx.clone()).await,
+            BackgroundTask::GitStatus => commands::probe::start_repo_status(tx.clone()).await,
         }
     }
 
     /// Start initial command if mode requires it
     pub async fn start_initial_command(&mut self) -> Result<()> {
+        self.ensure_hosts_discovered().await?;
+
         match &mut self.mode {
-            AppMode::Update(UpdateState::Running { steps, .. }) => {
+            AppMode::Update(UpdateState::Running {
+                steps,
+                overrides,
+                options,
+                ..
+            }) => {
                 if !steps.is_empty() {
                     steps[0].status = StepState::Running;
                 }
+                self.changelog = Changelog::default();
+                self.update_report = None;
+                self.build_progress = None;
                 if let Some(tx) = &self.cmd_tx {
-                    commands::update::start_update(tx.clone()).await?;
+                    commands::update::start_update(tx.clone(), overrides.clone(), *options)
+                        .await?;
                 }
             }
             AppMode::Apps(AppProfileState::Running {
@@ -126,6 +368,9 @@ impl App {
                         AppOp::Restore => {
                             commands::apps::start_restore(tx.clone(), *force).await?;
                         }
+                        AppOp::Init { repo, backup } => {
+                            commands::apps::start_init(tx.clone(), repo.clone(), *backup).await?;
+                        }
                     }
                 }
             }
@@ -135,7 +380,10 @@ impl App {
                 }
             }
             AppMode::Keys(KeysState::Running {
-                operation, force, ..
+                operation,
+                force,
+                passphrase,
+                ..
             }) => {
                 if let Some(tx) = &self.cmd_tx {
                     match operation {
@@ -143,19 +391,151 @@ impl App {
                             commands::keys::start_setup(tx.clone()).await?;
                         }
                         KeysOp::Backup => {
-                            commands::keys::start_backup(tx.clone()).await?;
+                            commands::keys::start_backup(tx.clone(), passphrase.clone()).await?;
                         }
                         KeysOp::Restore => {
-                            commands::keys::start_restore(tx.clone(), *force).await?;
+                            commands::keys::start_restore(tx.clone(), *force, passphrase.clone())
+                                .await?;
                         }
                         KeysOp::Status => {
                             commands::keys::start_status(tx.clone()).await?;
                         }
+                        KeysOp::GitHub { deploy } => {
+                            commands::keys::start_github(tx.clone(), *deploy).await?;
+                        }
+                    }
+                }
+            }
+            AppMode::Bootstrap(BootstrapState::Running { steps, .. }) => {
+                if !steps.is_empty() {
+                    steps[0].status = StepState::Running;
+                }
+                if let Some(tx) = &self.cmd_tx {
+                    commands::bootstrap::start_bootstrap(tx.clone()).await?;
+                }
+            }
+            AppMode::Fleet(FleetState::Running { plan, steps, .. }) => {
+                if !steps.is_empty() {
+                    steps[0].status = StepState::Running;
+                }
+                if let Some(tx) = &self.cmd_tx {
+                    commands::fleet::start_fleet_update(tx.clone(), plan.clone()).await?;
+                }
+            }
+            AppMode::Fleet(FleetState::Status { report: None }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::report::start_fleet_status(tx.clone()).await?;
+                }
+            }
+            AppMode::Pipeline(PipelineState::Running { pipeline, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::pipeline::start_pipeline(tx.clone(), pipeline.clone()).await?;
+                }
+            }
+            AppMode::DevShells(DevShellState { shells: None, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::devshell::start_devshell_discovery(tx.clone()).await?;
+                }
+            }
+            AppMode::Registry(RegistryState { rows: None, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::registry::start_registry_op(tx.clone(), None).await?;
+                }
+            }
+            AppMode::Optimise(OptimiseState::Running { gc, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    match gc {
+                        Some(gc) => {
+                            commands::collect::start_gc_run(tx.clone(), gc.older_than).await?
+                        }
+                        None => commands::optimise::start_optimise(tx.clone()).await?,
+                    }
+                }
+            }
+            AppMode::Bisect(state) => {
+                let Some(tx) = &self.cmd_tx else {
+                    return Ok(());
+                };
+                match &state.bisection {
+                    None => {
+                        commands::bisect::start_bisect_fetch(
+                            tx.clone(),
+                            state.input.clone(),
+                            state.good.clone(),
+                            state.bad.clone(),
+                        )
+                        .await?;
+                    }
+                    // Rebuild the next candidate once the last one has a verdict
+                    Some(Ok(bisection)) if state.testing.is_none() => {
+                        if let Some(index) = bisection.next() {
+                            let commit = bisection.commits[index].clone();
+                            let repo = bisection.repo.clone();
+                            state.testing = Some(index);
+                            state.built = None;
+                            commands::bisect::start_bisect_build(
+                                tx.clone(),
+                                state.input.clone(),
+                                repo,
+                                commit,
+                                state.mode,
+                            )
+                            .await?;
+                        }
                     }
+                    _ => {}
                 }
             }
-            AppMode::Install(InstallState::SelectDisk { disks, .. }) => {
-                *disks = crate::system::disk::get_available_disks()?;
+            AppMode::Gc(GcState { config: None, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::gc::start_gc_op(tx.clone(), None).await?;
+                }
+            }
+            AppMode::NixConf(NixConfState { config: None, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::nix_conf::start_nix_conf_op(tx.clone(), None).await?;
+                }
+            }
+            AppMode::Rollback(RollbackState::Select { generations: None, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::rollback::start_generation_list(tx.clone()).await?;
+                }
+            }
+            AppMode::Generations(GenerationsState { generations: None, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::generations::start_generations_op(tx.clone(), None).await?;
+                }
+            }
+            AppMode::Channels(ChannelsState { hosts: None, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::channels::start_channels_op(tx.clone(), None).await?;
+                }
+            }
+            AppMode::Services(ServicesState { rows: None, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::services::start_services_op(tx.clone(), None).await?;
+                }
+            }
+            AppMode::Rollback(RollbackState::Running { target, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::rollback::start_rollback(tx.clone(), *target).await?;
+                }
+            }
+            AppMode::Install(InstallState::Preflight { checks: None }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::probe::start_preflight(tx.clone()).await?;
+                }
+            }
+            AppMode::Install(InstallState::NetworkSetup { networks: None, .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    commands::wifi::start_wifi_scan(tx.clone()).await?;
+                }
+            }
+            AppMode::Install(InstallState::SelectDisk { .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    self.disks_loading = true;
+                    commands::probe::start_disk_probe(tx.clone()).await?;
+                }
             }
             AppMode::CreateHost(CreateHostState::DetectingHardware) => {
                 match crate::system::hardware::detect_all() {
@@ -187,12 +567,16 @@ impl App {
                     }
                 }
             }
-            AppMode::CreateHost(CreateHostState::SelectDisk { disks, .. }) => {
-                *disks = crate::system::disk::get_available_disks()?;
+            AppMode::CreateHost(CreateHostState::SelectDisk { .. }) => {
+                if let Some(tx) = &self.cmd_tx {
+                    self.disks_loading = true;
+                    commands::probe::start_disk_probe(tx.clone()).await?;
+                }
             }
             AppMode::MainMenu { .. } => {
                 if let Some(tx) = &self.cmd_tx {
                     self.startup_check_running = true;
+                    self.scheduler.started(BackgroundTask::UpdateCheck, Instant::now());
                     commands::apps::start_quick_update_check(tx.clone()).await?;
                 }
             }
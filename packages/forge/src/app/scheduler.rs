@@ -0,0 +1,136 @@
+//! Periodic background tasks while the TUI is open
+//!
+//! The main loop asks for the next due task on every tick. A task is due once
+//! its interval has passed since it last started, is never started while a
+//! previous run is still going, and only one task starts per `MIN_START_GAP`
+//! so they don't all hit git at once. Results come back as
+//! `CommandMessage::TaskFinished`.
+
+use std::time::{Duration, Instant};
+
+use crate::commands::apps::UpdateCheck;
+use crate::system::repo_status::RepoStatus;
+
+/// How often the update check is repeated after the startup check
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// How often the status bar's git status is refreshed
+const GIT_STATUS_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Minimum time between starting any two tasks
+const MIN_START_GAP: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundTask {
+    /// Config, app profile and flake input update checks
+    UpdateCheck,
+    /// Git status of the config repo
+    GitStatus,
+}
+
+impl BackgroundTask {
+    pub const ALL: [BackgroundTask; 2] = [BackgroundTask::UpdateCheck, BackgroundTask::GitStatus];
+
+    fn interval(self) -> Duration {
+        match self {
+            BackgroundTask::UpdateCheck => UPDATE_CHECK_INTERVAL,
+            BackgroundTask::GitStatus => GIT_STATUS_INTERVAL,
+        }
+    }
+}
+
+/// What a background task found
+#[derive(Debug, Clone)]
+pub enum TaskResult {
+    Updates(UpdateCheck),
+    /// None when the config dir isn't a git checkout
+    GitStatus(Option<RepoStatus>),
+}
+
+impl TaskResult {
+    pub fn task(&self) -> BackgroundTask {
+        match self {
+            TaskResult::Updates(_) => BackgroundTask::UpdateCheck,
+            TaskResult::GitStatus(_) => BackgroundTask::GitStatus,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct TaskSlot {
+    last_started: Option<Instant>,
+    running: bool,
+}
+
+/// Tracks when each background task last ran
+#[derive(Debug, Default)]
+pub struct Scheduler {
+    slots: [TaskSlot; BackgroundTask::ALL.len()],
+    last_start: Option<Instant>,
+}
+
+impl Scheduler {
+    /// The task to start now, if any
+    pub fn next_due(&self, now: Instant) -> Option<BackgroundTask> {
+        if self.last_start.is_some_and(|last| now.duration_since(last) < MIN_START_GAP) {
+            return None;
+        }
+        BackgroundTask::ALL.into_iter().find(|&task| {
+            let slot = &self.slots[task as usize];
+            !slot.running
+                && slot
+                    .last_started
+                    .is_none_or(|last| now.duration_since(last) >= task.interval())
+        })
+    }
+
+    /// Record that `task` was started (also for runs started outside the scheduler)
+    pub fn started(&mut self, task: BackgroundTask, now: Instant) {
+        let slot = &mut self.slots[task as usize];
+        slot.last_started = Some(now);
+        slot.running = true;
+        self.last_start = Some(now);
+    }
+
+    pub fn finished(&mut self, task: BackgroundTask) {
+        self.slots[task as usize].running = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tasks_are_spaced_and_not_overlapping() {
+        let mut scheduler = Scheduler::default();
+        let now = Instant::now();
+        assert_eq!(scheduler.next_due(now), Some(BackgroundTask::UpdateCheck));
+        scheduler.started(BackgroundTask::UpdateCheck, now);
+        // Nothing starts within the gap, then the next task does
+        assert_eq!(scheduler.next_due(now + Duration::from_secs(1)), None);
+        let later = now + MIN_START_GAP;
+        assert_eq!(scheduler.next_due(later), Some(BackgroundTask::GitStatus));
+        scheduler.started(BackgroundTask::GitStatus, later);
+
+        // A slow git status isn't started twice
+        let after_interval = later + GIT_STATUS_INTERVAL;
+        assert_eq!(scheduler.next_due(after_interval), None);
+        scheduler.finished(BackgroundTask::GitStatus);
+        assert_eq!(scheduler.next_due(after_interval), Some(BackgroundTask::GitStatus));
+    }
+
+    #[test]
+    fn test_update_check_waits_for_interval() {
+        let mut scheduler = Scheduler::default();
+        let now = Instant::now();
+        scheduler.started(BackgroundTask::UpdateCheck, now);
+        scheduler.finished(BackgroundTask::UpdateCheck);
+        scheduler.started(BackgroundTask::GitStatus, now);
+        scheduler.finished(BackgroundTask::GitStatus);
+        let soon = now + GIT_STATUS_INTERVAL;
+        assert_eq!(scheduler.next_due(soon), Some(BackgroundTask::GitStatus));
+        scheduler.started(BackgroundTask::GitStatus, soon);
+        assert_eq!(scheduler.next_due(now + UPDATE_CHECK_INTERVAL), Some(BackgroundTask::UpdateCheck));
+    }
+}
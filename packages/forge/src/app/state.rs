@@ -1,17 +1,63 @@
 //! Application state types and enums
 
-use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::path::PathBuf;
 
-use crate::commands::update::flake::FlakeInputChange;
+use crate::commands::generations::GenerationsOp;
+use crate::commands::rollback::{ACTIVATE_STEP, ROLLBACK_STEP, SWITCH_PROFILE_STEP};
+use crate::commands::update::cache::CacheStats;
+use crate::commands::update::flake::{
+    checkout_dirs, root_inputs, CheckoutDir, FlakeInputChange, InputOverride,
+};
+use crate::commands::update::warnings::EvalWarnings;
+use crate::commands::update::timer::load_scheduled_run;
+use crate::commands::update::{Activation, UpdateOptions};
+use crate::system::accounts::{self, ExtraAccounts, KeySource, PrimaryUser};
+use crate::system::bisect::{BisectMode, Bisection};
+use crate::system::build_log::{failed_derivation, SavedBuildLog};
+use crate::system::channels::HostChannel;
 use crate::system::config::HostConfig;
-use crate::system::disk::DiskInfo;
-use crate::system::hardware::{CpuInfo, FormFactor, GpuInfo};
+use crate::system::devshell::DevShell;
+use crate::system::disk::erase::EraseMethod;
+use crate::system::disk::layout::{DiskSwap, PartitionLayout};
+use crate::system::disk::health::DiskHealth;
+use crate::system::disk::{DiskAssignment, DiskInfo, DiskMode, DiskRole, StorageKind};
+use crate::system::fleet::{DeployStage, RolloutPlan};
+use crate::system::gc::{GcConfig, GcField};
+use crate::system::generations::Generation;
+use crate::system::host_state::HostDrift;
+use crate::system::install_progress::StepProgress;
+use crate::system::install_settings;
+use crate::system::lint::LintFinding;
+use crate::system::locale::LocaleSettings;
+use crate::system::nix_conf::{read_system_nix_conf, NixConfConfig, NixConfField};
+use crate::system::optimise::OptimiseProgress;
+use crate::system::parallelism::{load_parallelism, BuildParallelism};
+use crate::system::passphrase::{self, PassphraseStrength};
+use crate::system::hardware::{
+    detect_cpu_topology, detect_memory_gib, BootMode, CpuInfo, CpuTopology, FormFactor, GpuInfo,
+    SecureBootSupport,
+};
+use crate::system::pipeline::Pipeline;
+use crate::system::preflight::PreflightCheck;
+use crate::system::registry::RegistryRow;
+use crate::system::services::ServiceRow;
+use crate::system::wifi::WifiNetwork;
 
 /// Main menu items
 pub const MAIN_MENU_ITEMS: &[&str] = &[
     "Install NixOS (fresh installation)",
     "Update system",
     "App profiles",
+    "Dev shells",
+    "Flake registry",
+    "Garbage collection",
+    "Roll back system",
+    "Generations",
+    "Update channels",
+    "Background services",
+    "Nix settings",
     "Exit",
 ];
 
@@ -20,9 +66,17 @@ pub const APP_MENU_ITEMS: &[&str] = &[
     "Backup & push to GitHub",
     "Pull & restore from GitHub",
     "Check for updates",
+    "Set up backup repo (first run)",
     "Back to main menu",
 ];
 
+/// First-boot bootstrap prompt options
+pub const BOOTSTRAP_MENU_ITEMS: &[&str] = &[
+    "Restore keys and app profiles now",
+    "Remind me at next login",
+    "Don't ask again",
+];
+
 /// Application mode/screen
 #[derive(Debug, Clone)]
 pub enum AppMode {
@@ -32,6 +86,20 @@ pub enum AppMode {
     Update(UpdateState),
     Apps(AppProfileState),
     Keys(KeysState),
+    Bootstrap(BootstrapState),
+    Fleet(FleetState),
+    Pipeline(PipelineState),
+    DevShells(DevShellState),
+    Registry(RegistryState),
+    Gc(GcState),
+    NixConf(NixConfState),
+    Parallelism(ParallelismState),
+    Optimise(OptimiseState),
+    Bisect(BisectState),
+    Rollback(RollbackState),
+    Generations(GenerationsState),
+    Channels(ChannelsState),
+    Services(ServicesState),
     #[allow(dead_code)]
     Quit,
 }
@@ -43,19 +111,230 @@ pub enum CredentialField {
     Username,
     Password,
     ConfirmPassword,
+    /// Extra usernames, separated by spaces or commas
+    ExtraUsers,
+    /// Authorized key sources for the main user (gh:<user>, a file, a pasted key)
+    SshKeys,
+    /// Disk encryption toggle (Space)
+    Encryption,
+    /// LUKS passphrase fields, skipped while encryption is off
+    Passphrase,
+    ConfirmPassphrase,
+    /// TPM2 auto-unlock toggle (Space), also skipped while encryption is off
+    Tpm2Unlock,
+}
+
+impl CredentialField {
+    const ORDER: [CredentialField; 9] = [
+        CredentialField::Username,
+        CredentialField::Password,
+        CredentialField::ConfirmPassword,
+        CredentialField::ExtraUsers,
+        CredentialField::SshKeys,
+        CredentialField::Encryption,
+        CredentialField::Passphrase,
+        CredentialField::ConfirmPassphrase,
+        CredentialField::Tpm2Unlock,
+    ];
+
+    /// Fields reachable with Tab/arrows
+    fn visible(encrypt: bool) -> &'static [CredentialField] {
+        if encrypt {
+            &Self::ORDER
+        } else {
+            &Self::ORDER[..6]
+        }
+    }
+
+    pub fn next(&self, encrypt: bool) -> Self {
+        let fields = Self::visible(encrypt);
+        let i = fields.iter().position(|f| f == self).unwrap_or(0);
+        fields[(i + 1) % fields.len()].clone()
+    }
+
+    pub fn prev(&self, encrypt: bool) -> Self {
+        let fields = Self::visible(encrypt);
+        let i = fields.iter().position(|f| f == self).unwrap_or(0);
+        fields[(i + fields.len() - 1) % fields.len()].clone()
+    }
+}
+
+/// Fields of the install wizard's locale step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LocaleField {
+    #[default]
+    Timezone,
+    Locale,
+    Keymap,
+}
+
+impl LocaleField {
+    const ORDER: [LocaleField; 3] = [LocaleField::Timezone, LocaleField::Locale, LocaleField::Keymap];
+
+    pub fn next(self) -> Self {
+        let i = Self::ORDER.iter().position(|f| *f == self).unwrap_or(0);
+        Self::ORDER[(i + 1) % Self::ORDER.len()]
+    }
+
+    pub fn prev(self) -> Self {
+        let i = Self::ORDER.iter().position(|f| *f == self).unwrap_or(0);
+        Self::ORDER[(i + Self::ORDER.len() - 1) % Self::ORDER.len()]
+    }
+}
+
+/// Fields of the install wizard's partition step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PartitionField {
+    #[default]
+    Esp,
+    Root,
+    Swap,
+    SwapSize,
+}
+
+impl PartitionField {
+    const ORDER: [PartitionField; 4] = [
+        PartitionField::Esp,
+        PartitionField::Root,
+        PartitionField::Swap,
+        PartitionField::SwapSize,
+    ];
+
+    pub fn next(self) -> Self {
+        let i = Self::ORDER.iter().position(|f| *f == self).unwrap_or(0);
+        Self::ORDER[(i + 1) % Self::ORDER.len()]
+    }
+
+    pub fn prev(self) -> Self {
+        let i = Self::ORDER.iter().position(|f| *f == self).unwrap_or(0);
+        Self::ORDER[(i + Self::ORDER.len() - 1) % Self::ORDER.len()]
+    }
+}
+
+/// Partition sizes as typed on the partition step, in GiB
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionInputs {
+    pub esp: String,
+    /// Empty: root fills the disk
+    pub root: String,
+    pub swap: DiskSwap,
+    pub swap_size: String,
+}
+
+impl PartitionInputs {
+    pub fn from_layout(layout: &PartitionLayout) -> Self {
+        Self {
+            esp: layout.esp_gib.to_string(),
+            root: layout.root_gib.map(|gib| gib.to_string()).unwrap_or_default(),
+            swap: layout.swap,
+            swap_size: layout.swap_gib.to_string(),
+        }
+    }
+
+    /// The typed sizes, checked against a disk of `disk_bytes`
+    pub fn layout(&self, disk_bytes: u64) -> anyhow::Result<PartitionLayout> {
+        let gib = |field: &str, value: &str| {
+            value
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| anyhow::anyhow!("{} must be a whole number of GiB", field))
+        };
+        let root = self.root.trim();
+        let layout = PartitionLayout {
+            esp_gib: gib("EFI size", &self.esp)?,
+            root_gib: if root.is_empty() { None } else { Some(gib("Root size", root)?) },
+            swap: self.swap,
+            swap_gib: gib("Swap size", &self.swap_size)?,
+        };
+        layout.validate(disk_bytes)?;
+        Ok(layout)
+    }
 }
 
 /// User credentials collected during installation
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct InstallCredentials {
     pub username: String,
     pub password: String,
     pub confirm_password: String,
+    /// Extra accounts and authorized key sources, as typed (see `system::accounts`)
+    pub extra_users: String,
+    pub ssh_keys: String,
+    /// Encrypt the disks with `passphrase`
+    pub encrypt: bool,
+    /// LUKS passphrase, separate from the login password
+    pub passphrase: String,
+    pub confirm_passphrase: String,
+    /// Also seal a key in the TPM2 so the disks unlock without the passphrase
+    pub tpm2_unlock: bool,
+    /// Set on the locale step; None leaves the host's own settings alone
+    pub locale: Option<LocaleSettings>,
+    /// Set on the partition step (single-disk erase installs); None keeps the
+    /// host's disko sizes
+    pub partitions: Option<PartitionLayout>,
+}
+
+impl InstallCredentials {
+    /// Check the extra users and key sources; key files are read here, so a
+    /// wrong path shows up on the credentials screen
+    pub fn check_accounts(&self) -> anyhow::Result<()> {
+        let accounts = ExtraAccounts::parse(&self.extra_users, &self.ssh_keys, &self.username)?;
+        for source in &accounts.key_sources {
+            if let KeySource::File(path) = source {
+                if accounts::read_key_file(path)?.is_empty() {
+                    anyhow::bail!("No public keys in {}", path.display());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Extra users and key sources, once `check_accounts` has passed
+    pub fn accounts(&self) -> ExtraAccounts {
+        ExtraAccounts::parse(&self.extra_users, &self.ssh_keys, &self.username).unwrap_or_default()
+    }
+
+    /// The entered user, with the ids from the installer settings
+    pub fn primary_user(&self) -> PrimaryUser {
+        let settings = &install_settings::current().user;
+        PrimaryUser {
+            name: self.username.clone(),
+            uid: settings.uid,
+            gid: settings.gid,
+        }
+    }
+}
+
+impl Default for InstallCredentials {
+    /// Starts with the username from the installer settings, if any
+    fn default() -> Self {
+        Self {
+            username: install_settings::current().user.name.clone().unwrap_or_default(),
+            password: String::new(),
+            confirm_password: String::new(),
+            extra_users: String::new(),
+            ssh_keys: String::new(),
+            encrypt: true,
+            passphrase: String::new(),
+            confirm_passphrase: String::new(),
+            tpm2_unlock: false,
+            locale: None,
+            partitions: None,
+        }
+    }
 }
 
+/// Install step that runs nixos-install, the one a rescue shell can fix
+pub const NIXOS_INSTALL_STEP: &str = "Installing NixOS";
+
 /// Installation state machine
 #[derive(Debug, Clone)]
 pub enum InstallState {
+    /// Live ISO preflight checks, shown before host selection
+    Preflight {
+        /// None while the checks are still running
+        checks: Option<Vec<PreflightCheck>>,
+    },
     SelectHost {
         selected: usize,
     },
@@ -63,25 +342,112 @@ pub enum InstallState {
         host: String,
         disks: Vec<DiskInfo>,
         selected: usize,
+        /// Erase the disk or install into its free space
+        disk_mode: DiskMode,
+        /// Paths of disks marked with Space for a multi-disk install
+        marked: Vec<String>,
+        /// Why the selected disk can't be used in this mode
+        error: Option<String>,
+    },
+    /// Give each marked disk a role (root, /home, mirror)
+    AssignRoles {
+        host: String,
+        disks: Vec<DiskInfo>,
+        roles: Vec<DiskRole>,
+        selected: usize,
+        error: Option<String>,
+    },
+    /// The disk to erase holds filesystems; its partitions are listed and the
+    /// disk's name must be typed to go on
+    ConfirmExistingData {
+        host: String,
+        disk: DiskInfo,
+        typed: String,
+        error: Option<String>,
+    },
+    /// EFI, root and swap sizes for a single-disk erase install, prefilled
+    /// from the host's disko file
+    EditPartitions {
+        host: String,
+        disk: DiskInfo,
+        credentials: InstallCredentials,
+        inputs: PartitionInputs,
+        active_field: PartitionField,
+        error: Option<String>,
     },
     EnterCredentials {
         host: String,
+        /// Root disk
         disk: DiskInfo,
+        disk_mode: DiskMode,
+        /// Home and mirror disks of a multi-disk install
+        extra_disks: Vec<DiskAssignment>,
         credentials: InstallCredentials,
         active_field: CredentialField,
         error: Option<String>,
+        /// SSH target (`user@host`) for nixos-anywhere; None installs on this machine
+        remote: Option<String>,
+    },
+    /// Timezone, locale and console keymap, prefilled from the live ISO
+    SelectLocale {
+        host: String,
+        disk: DiskInfo,
+        disk_mode: DiskMode,
+        extra_disks: Vec<DiskAssignment>,
+        credentials: InstallCredentials,
+        remote: Option<String>,
+        locale: LocaleSettings,
+        active_field: LocaleField,
+        error: Option<String>,
     },
     Overview {
         host: String,
         disk: DiskInfo,
+        disk_mode: DiskMode,
+        extra_disks: Vec<DiskAssignment>,
         credentials: InstallCredentials,
-        hardware_config: Option<NewHostConfig>,
+        remote: Option<String>,
+        hardware_config: Option<Box<NewHostConfig>>,
+        /// SMART / NVMe health of each target disk (None: not reported)
+        disk_health: Vec<(String, Option<DiskHealth>)>,
+        /// Set when the disk isn't the one recorded in the host's host-info.json
+        disk_drift: Option<String>,
+        /// Whether this machine can take forge's Secure Boot keys
+        secure_boot_support: SecureBootSupport,
+        /// Enroll Secure Boot keys and boot through lanzaboote (Tab toggles)
+        secure_boot: bool,
+        /// Each target disk and how it would be erased; empty where secure
+        /// erase isn't offered (remote and alongside installs)
+        erase_plan: Vec<(String, EraseMethod)>,
+        /// Securely erase the target disks before disko (Shift+Tab toggles)
+        secure_erase: bool,
+        /// Typed into the secure erase confirmation; None while it is closed
+        erase_confirm: Option<String>,
         input: String,
     },
+    /// The network check failed: join a wireless network, then go back to
+    /// where it failed
+    NetworkSetup {
+        /// The overview the install was started from (the install starts
+        /// over), or the preflight checks (they run again)
+        resume: Box<InstallState>,
+        /// None while scanning
+        networks: Option<Vec<WifiNetwork>>,
+        selected: usize,
+        /// Passphrase being typed for the selected network; None while picking
+        passphrase: Option<String>,
+        /// Whether a connection attempt is running
+        connecting: bool,
+        /// Why the scan or the last attempt failed
+        error: Option<String>,
+    },
     Running {
         host: String,
         disk: DiskInfo,
         credentials: InstallCredentials,
+        /// Overview a local install was started from, to start over after
+        /// network setup (None for remote installs, which don't need it)
+        overview: Option<Box<InstallState>>,
         step: usize,
         steps: Vec<StepStatus>,
         output: VecDeque<String>,
@@ -91,6 +457,11 @@ pub enum InstallState {
         output: VecDeque<String>,
         /// None = auto-scroll, Some(n) = manual scroll at position n
         scroll_offset: Option<usize>,
+        /// Host that was installed, for the log bundle; None if none was picked
+        host: Option<String>,
+        /// The run, when it was a local install stopped by nixos-install: `s`
+        /// opens a rescue shell, `r` runs nixos-install again
+        retry: Option<Box<InstallState>>,
     },
 }
 
@@ -105,13 +476,18 @@ impl InstallState {
                     size_bytes: 0,
                     model: None,
                     partitions: vec![],
+                    free_bytes: 0,
+                    by_id: None,
                 };
                 InstallState::EnterCredentials {
                     host,
                     disk,
+                    disk_mode: DiskMode::Wipe,
+                    extra_disks: Vec::new(),
                     credentials: InstallCredentials::default(),
                     active_field: CredentialField::Username,
                     error: None,
+                    remote: None,
                 }
             }
             (Some(host), None) => {
@@ -120,10 +496,63 @@ impl InstallState {
                     host,
                     disks: Vec::new(),
                     selected: 0,
+                    disk_mode: DiskMode::Wipe,
+                    marked: Vec::new(),
+                    error: None,
                 }
             }
-            _ => InstallState::SelectHost { selected: 0 },
+            _ => InstallState::Preflight { checks: None },
+        }
+    }
+
+    /// Install `host` onto another machine over SSH (`forge install --target`)
+    ///
+    /// The disk is a device on the remote machine, so it can't be listed or
+    /// checked here.
+    pub fn new_remote(host: String, disk: String, target: String) -> Self {
+        let mut state = Self::new(Some(host), Some(disk));
+        if let InstallState::EnterCredentials { remote, .. } = &mut state {
+            *remote = Some(target);
+        }
+        state
+    }
+
+    /// The run to offer a retry of once it has finished
+    ///
+    /// Only a local install that failed at nixos-install qualifies; that
+    /// leaves /mnt mounted, so it can be fixed and nixos-install run again.
+    pub fn retry_point(&self, success: bool) -> Option<Box<InstallState>> {
+        match self {
+            InstallState::Running { steps, overview: Some(_), .. }
+                if !success
+                    && steps
+                        .iter()
+                        .any(|s| s.status == StepState::Failed && s.name == NIXOS_INSTALL_STEP) =>
+            {
+                Some(Box::new(self.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Turn a retry point back into a run at nixos-install, keeping the
+    /// failed run's output
+    pub fn rerun_nixos_install(&mut self, previous: VecDeque<String>) {
+        let InstallState::Running {
+            step,
+            steps,
+            output,
+            ..
+        } = self
+        else {
+            return;
+        };
+        if let Some(index) = steps.iter().position(|s| s.name == NIXOS_INSTALL_STEP) {
+            steps[index].status = StepState::Running;
+            steps[index].progress = None;
+            *step = index;
         }
+        *output = previous;
     }
 }
 
@@ -164,214 +593,1792 @@ pub fn validate_password(password: &str, confirm: &str) -> Option<String> {
     None
 }
 
-/// Configuration being built during host creation wizard
-#[derive(Debug, Clone)]
-pub struct NewHostConfig {
-    pub hostname: String,
-    pub cpu: CpuInfo,
-    pub gpu: GpuInfo,
-    pub form_factor: FormFactor,
-    pub disk: DiskInfo,
+/// Validate the disk encryption passphrase
+///
+/// Weak passphrases are refused: once disko has run, changing one means
+/// `cryptsetup luksChangeKey` on every encrypted device.
+pub fn validate_passphrase(passphrase: &str, confirm: &str) -> Option<String> {
+    if passphrase.is_empty() {
+        return Some("Encryption passphrase cannot be empty".to_string());
+    }
+    if passphrase.len() < 8 {
+        return Some("Encryption passphrase must be at least 8 characters".to_string());
+    }
+    if passphrase::strength(passphrase) == PassphraseStrength::Weak {
+        return Some("Encryption passphrase is too weak; make it longer".to_string());
+    }
+    if passphrase != confirm {
+        return Some("Encryption passphrases do not match".to_string());
+    }
+    None
 }
 
-/// Create host wizard state machine
-/// Flow: DetectingHardware → ConfirmCpu → ConfirmGpu → ConfirmFormFactor → SelectDisk → EnterHostname → Review → Generating → Complete
-#[derive(Debug, Clone)]
-pub enum CreateHostState {
-    DetectingHardware,
-    ConfirmCpu {
-        cpu: CpuInfo,
-        detected_gpu: GpuInfo,
-        detected_form_factor: FormFactor,
-        override_menu: bool,
-        selected: usize,
-    },
-    ConfirmGpu {
-        cpu: CpuInfo,
-        gpu: GpuInfo,
-        detected_form_factor: FormFactor,
-        override_menu: bool,
-        selected: usize,
-    },
-    ConfirmFormFactor {
-        cpu: CpuInfo,
-        gpu: GpuInfo,
-        form_factor: FormFactor,
-        override_menu: bool,
-        selected: usize,
-    },
-    SelectDisk {
-        cpu: CpuInfo,
-        gpu: GpuInfo,
-        form_factor: FormFactor,
-        disks: Vec<DiskInfo>,
-        selected: usize,
-    },
-    EnterHostname {
-        cpu: CpuInfo,
-        gpu: GpuInfo,
-        form_factor: FormFactor,
-        disk: DiskInfo,
-        input: String,
-        error: Option<String>,
-    },
-    Review {
-        config: NewHostConfig,
-    },
-    Generating {
-        config: NewHostConfig,
-        step: usize,
-        steps: Vec<StepStatus>,
-        output: VecDeque<String>,
-    },
-    Complete {
-        success: bool,
-        config: NewHostConfig,
-    },
+/// Validate extra kernel parameters and split them into individual params
+///
+/// Params are emitted as Nix strings, so quotes, backslashes and `${` are rejected.
+pub fn validate_kernel_params(input: &str) -> Result<Vec<String>, String> {
+    let params: Vec<String> = input.split_whitespace().map(str::to_string).collect();
+    if let Some(bad) = params
+        .iter()
+        .find(|p| p.contains(['"', '\\', '\'', '`']) || p.contains("${"))
+    {
+        return Err(format!("Invalid kernel parameter: {}", bad));
+    }
+    Ok(params)
+}
+
+/// Validate a `user@host` SSH target for a remote install
+///
+/// The target ends up in ssh command lines, so only host name characters are allowed.
+pub fn validate_ssh_target(target: &str) -> Option<String> {
+    let Some((user, host)) = target.split_once('@') else {
+        return Some(format!("Expected user@host, got: {}", target));
+    };
+    let valid = |part: &str, extra: &[char]| {
+        !part.is_empty()
+            && !part.starts_with('-')
+            && part.chars().all(|c| {
+                c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') || extra.contains(&c)
+            })
+    };
+    if !valid(user, &[]) {
+        return Some(format!("Invalid SSH user: {}", user));
+    }
+    // IPv6 addresses are written in brackets
+    if !valid(host, &[':', '[', ']']) {
+        return Some(format!("Invalid SSH host: {}", host));
+    }
+    None
+}
+
+/// Parse a custom subvolume scheme like `@=/ @home=/home @data=/srv/data`
+///
+/// Exactly one subvolume must be mounted at `/`; names and mountpoints are unique.
+pub fn validate_subvolumes(input: &str) -> Result<Vec<(String, String)>, String> {
+    let mut subvolumes: Vec<(String, String)> = Vec::new();
+    for entry in input.split_whitespace() {
+        let Some((name, mountpoint)) = entry.split_once('=') else {
+            return Err(format!("Expected name=/mountpoint, got: {}", entry));
+        };
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '@' | '-' | '_' | '.'))
+        {
+            return Err(format!("Invalid subvolume name: {}", name));
+        }
+        if !mountpoint.starts_with('/')
+            || !mountpoint
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '-' | '_' | '.'))
+        {
+            return Err(format!("Invalid mountpoint: {}", mountpoint));
+        }
+        if subvolumes.iter().any(|(n, m)| n == name || m == mountpoint) {
+            return Err(format!("Duplicate subvolume or mountpoint: {}", entry));
+        }
+        subvolumes.push((name.to_string(), mountpoint.to_string()));
+    }
+    if !subvolumes.iter().any(|(_, m)| m == "/") {
+        return Err("One subvolume must be mounted at /".to_string());
+    }
+    Ok(subvolumes)
+}
+
+/// Why Secure Boot can't be set up during this install, if it can't
+pub fn secure_boot_blocker(support: SecureBootSupport, disk_mode: DiskMode) -> Option<String> {
+    match (support, disk_mode) {
+        (SecureBootSupport::SetupMode, DiskMode::Wipe) => None,
+        // lanzaboote replaces Limine, which holds the Windows entry
+        (SecureBootSupport::SetupMode, DiskMode::Alongside) => {
+            Some("not available when installing alongside another OS".to_string())
+        }
+        (support, _) => Some(support.to_string()),
+    }
+}
+
+/// Check a swap mode against the rest of the host config
+pub fn validate_swap(swap: SwapMode, config: &NewHostConfig) -> Option<String> {
+    match swap {
+        SwapMode::Swapfile | SwapMode::Partition if config.filesystem == RootFilesystem::Zfs => {
+            Some("ZFS hosts use zram or no swap (swap on ZFS can deadlock)".to_string())
+        }
+        SwapMode::Partition if config.boot_mode == BootMode::Bios => {
+            Some("Swap partition needs UEFI, use a swapfile on legacy BIOS".to_string())
+        }
+        SwapMode::Partition if config.memory_gib == 0 => {
+            Some("Could not read the RAM size to size the swap partition".to_string())
+        }
+        SwapMode::Swapfile
+            if config
+                .subvolumes()
+                .unwrap_or_default()
+                .iter()
+                .any(|(name, mountpoint)| name == "@swap" || mountpoint == "/swap") =>
+        {
+            Some("The subvolume layout already uses @swap or /swap".to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Inverse of `validate_subvolumes`, for editing a scheme again
+pub fn format_subvolumes(subvolumes: &[(String, String)]) -> String {
+    subvolumes
+        .iter()
+        .map(|(name, mountpoint)| format!("{}={}", name, mountpoint))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Kernel package set for a new host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum KernelFlavor {
+    /// nixpkgs default kernel (current LTS)
+    Lts,
+    /// Latest stable kernel (best hardware support)
+    #[default]
+    Latest,
+    /// Zen kernel (desktop/gaming tuned)
+    Zen,
+    /// Hardened kernel (security focused)
+    Hardened,
+}
+
+impl KernelFlavor {
+    /// All flavors, in menu order
+    pub const ALL: [KernelFlavor; 4] = [
+        KernelFlavor::Lts,
+        KernelFlavor::Latest,
+        KernelFlavor::Zen,
+        KernelFlavor::Hardened,
+    ];
+
+    /// Position in `ALL`
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|k| *k == self).unwrap_or(0)
+    }
+
+    /// nixpkgs attribute for `boot.kernelPackages`
+    pub fn package_attr(self) -> &'static str {
+        match self {
+            KernelFlavor::Lts => "linuxPackages",
+            KernelFlavor::Latest => "linuxPackages_latest",
+            KernelFlavor::Zen => "linuxPackages_zen",
+            KernelFlavor::Hardened => "linuxPackages_hardened",
+        }
+    }
+
+    /// Short description shown in the wizard
+    pub fn description(self) -> &'static str {
+        match self {
+            KernelFlavor::Lts => "nixpkgs default, long-term support",
+            KernelFlavor::Latest => "newest stable release, best hardware support",
+            KernelFlavor::Zen => "tuned for desktop responsiveness and gaming",
+            KernelFlavor::Hardened => "security hardening, may break some drivers",
+        }
+    }
+}
+
+impl std::fmt::Display for KernelFlavor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KernelFlavor::Lts => write!(f, "Default (LTS)"),
+            KernelFlavor::Latest => write!(f, "Latest"),
+            KernelFlavor::Zen => write!(f, "Zen"),
+            KernelFlavor::Hardened => write!(f, "Hardened"),
+        }
+    }
+}
+
+/// Root filesystem for a new host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RootFilesystem {
+    /// Btrfs on LUKS2 (modules/disko/default.nix, bios.nix)
+    #[default]
+    Btrfs,
+    /// ZFS pool with native encryption (modules/disko/zfs.nix, UEFI only)
+    Zfs,
+}
+
+impl RootFilesystem {
+    /// All filesystems, in menu order
+    pub const ALL: [RootFilesystem; 2] = [RootFilesystem::Btrfs, RootFilesystem::Zfs];
+
+    /// Position in `ALL`
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|f| *f == self).unwrap_or(0)
+    }
+
+    /// Short description shown in the wizard
+    pub fn description(self) -> &'static str {
+        match self {
+            RootFilesystem::Btrfs => "btrfs subvolumes on LUKS2, same as the main machines",
+            RootFilesystem::Zfs => "encrypted ZFS pool with datasets, LTS kernel, UEFI only",
+        }
+    }
+}
+
+impl std::fmt::Display for RootFilesystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RootFilesystem::Btrfs => write!(f, "Btrfs"),
+            RootFilesystem::Zfs => write!(f, "ZFS"),
+        }
+    }
+}
+
+/// Swap for a new host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SwapMode {
+    /// No swap at all, zram from common.nix switched off
+    None,
+    /// Compressed swap in RAM only (common.nix)
+    #[default]
+    Zram,
+    /// Swapfile in an @swap btrfs subvolume, used after zram
+    Swapfile,
+    /// Encrypted swap partition sized for hibernation, used after zram
+    Partition,
+}
+
+impl SwapMode {
+    /// All swap modes, in menu order
+    pub const ALL: [SwapMode; 4] = [
+        SwapMode::None,
+        SwapMode::Zram,
+        SwapMode::Swapfile,
+        SwapMode::Partition,
+    ];
+
+    /// Position in `ALL`
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|s| *s == self).unwrap_or(0)
+    }
+
+    /// Short description shown in the wizard
+    pub fn description(self) -> &'static str {
+        match self {
+            SwapMode::None => "no swap, not even zram",
+            SwapMode::Zram => "compressed RAM swap, same as the main machines",
+            SwapMode::Swapfile => "zram plus a swapfile in an @swap subvolume (btrfs)",
+            SwapMode::Partition => "zram plus an encrypted partition for hibernation (UEFI)",
+        }
+    }
+
+    /// Size of the on-disk swap in GiB for `memory_gib` of RAM; None if it has none
+    ///
+    /// A hibernation image can be as large as RAM, so the partition gets RAM plus
+    /// a sqrt(RAM) margin. The swapfile only catches overflow: half of RAM, 2-16 GiB.
+    pub fn disk_size_gib(self, memory_gib: u64) -> Option<u64> {
+        match self {
+            SwapMode::None | SwapMode::Zram => None,
+            SwapMode::Swapfile => Some(memory_gib.div_ceil(2).clamp(2, 16)),
+            SwapMode::Partition => Some(memory_gib + (memory_gib as f64).sqrt().ceil() as u64),
+        }
+    }
+}
+
+impl std::fmt::Display for SwapMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SwapMode::None => write!(f, "None"),
+            SwapMode::Zram => write!(f, "Zram"),
+            SwapMode::Swapfile => write!(f, "Swapfile"),
+            SwapMode::Partition => write!(f, "Partition"),
+        }
+    }
+}
+
+/// Btrfs subvolume scheme inside the LUKS container (`diskLayout.subvolumes`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SubvolumeLayout {
+    /// @, @home, @nix, @var-log - the shared default in modules/disko
+    #[default]
+    Standard,
+    /// @, @home, @nix, @snapshots (for snapper/btrbk)
+    Snapshots,
+    /// No subvolumes, the filesystem itself is /
+    Flat,
+    /// Impermanence with / on a tmpfs, state kept in @persist
+    EphemeralTmpfs,
+    /// Impermanence with @ rolled back to a blank snapshot at boot
+    EphemeralSnapshot,
+    /// Entered by hand
+    Custom,
+}
+
+impl SubvolumeLayout {
+    /// All layouts, in menu order
+    pub const ALL: [SubvolumeLayout; 6] = [
+        SubvolumeLayout::Standard,
+        SubvolumeLayout::Snapshots,
+        SubvolumeLayout::Flat,
+        SubvolumeLayout::EphemeralTmpfs,
+        SubvolumeLayout::EphemeralSnapshot,
+        SubvolumeLayout::Custom,
+    ];
+
+    /// Position in `ALL`
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|l| *l == self).unwrap_or(0)
+    }
+
+    /// Subvolumes (name, mountpoint) of the fixed schemes; empty for Flat and Custom
+    pub fn subvolumes(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            SubvolumeLayout::Standard => &[
+                ("@", "/"),
+                ("@home", "/home"),
+                ("@nix", "/nix"),
+                ("@var-log", "/var/log"),
+            ],
+            SubvolumeLayout::Snapshots => &[
+                ("@", "/"),
+                ("@home", "/home"),
+                ("@nix", "/nix"),
+                ("@snapshots", "/.snapshots"),
+            ],
+            SubvolumeLayout::EphemeralTmpfs => &[
+                ("@home", "/home"),
+                ("@nix", "/nix"),
+                ("@persist", "/persist"),
+                ("@var-log", "/var/log"),
+            ],
+            SubvolumeLayout::EphemeralSnapshot => &[
+                ("@", "/"),
+                ("@home", "/home"),
+                ("@nix", "/nix"),
+                ("@persist", "/persist"),
+                ("@var-log", "/var/log"),
+            ],
+            SubvolumeLayout::Flat | SubvolumeLayout::Custom => &[],
+        }
+    }
+
+    /// `diskLayout.ephemeralRoot` for the impermanence layouts
+    pub fn ephemeral_root(self) -> Option<&'static str> {
+        match self {
+            SubvolumeLayout::EphemeralTmpfs => Some("tmpfs"),
+            SubvolumeLayout::EphemeralSnapshot => Some("snapshot"),
+            _ => None,
+        }
+    }
+
+    /// Short description shown in the wizard
+    pub fn description(self) -> &'static str {
+        match self {
+            SubvolumeLayout::Standard => "@ @home @nix @var-log, same as the main machines",
+            SubvolumeLayout::Snapshots => "@ @home @nix @snapshots, ready for snapper/btrbk",
+            SubvolumeLayout::Flat => "no subvolumes, one btrfs filesystem at /",
+            SubvolumeLayout::EphemeralTmpfs => "impermanence: / on tmpfs, state in @persist",
+            SubvolumeLayout::EphemeralSnapshot => {
+                "impermanence: @ reset to a blank snapshot at boot"
+            }
+            SubvolumeLayout::Custom => "your own name=/mountpoint list",
+        }
+    }
+}
+
+impl std::fmt::Display for SubvolumeLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubvolumeLayout::Standard => write!(f, "Standard"),
+            SubvolumeLayout::Snapshots => write!(f, "Snapshots"),
+            SubvolumeLayout::Flat => write!(f, "Flat"),
+            SubvolumeLayout::EphemeralTmpfs => write!(f, "Tmpfs root"),
+            SubvolumeLayout::EphemeralSnapshot => write!(f, "Rollback"),
+            SubvolumeLayout::Custom => write!(f, "Custom"),
+        }
+    }
+}
+
+/// zstd level btrfs uses for a plain `compress=zstd`
+pub const DEFAULT_ZSTD_LEVEL: u8 = 3;
+
+/// Highest zstd level btrfs accepts
+pub const MAX_ZSTD_LEVEL: u8 = 15;
+
+/// Advanced btrfs and partition settings, edited from the review screen
+/// (`diskLayout` options in modules/disko/subvolumes.nix)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskOptions {
+    /// zstd level, None for no compression
+    pub compression: Option<u8>,
+    pub noatime: bool,
+    /// Continuous TRIM (`discard=async`) on top of the weekly fstrim
+    pub discard: bool,
+    /// Background defragmentation, for spinning disks
+    pub autodefrag: bool,
+    /// GPT label of the EFI system partition
+    pub boot_label: String,
+    /// GPT label of the root partition; empty keeps `cryptroot` / `root`
+    pub root_label: String,
+}
+
+impl Default for DiskOptions {
+    fn default() -> Self {
+        Self {
+            compression: Some(DEFAULT_ZSTD_LEVEL),
+            noatime: true,
+            discard: false,
+            autodefrag: false,
+            boot_label: "ESP".to_string(),
+            root_label: String::new(),
+        }
+    }
+}
+
+impl DiskOptions {
+    /// Defaults for the detected disk: autodefrag on spinning disks
+    pub fn for_storage(storage: StorageKind) -> Self {
+        Self {
+            autodefrag: storage == StorageKind::Hdd,
+            ..Self::default()
+        }
+    }
+
+    /// Btrfs mount options these settings produce
+    pub fn mount_options(&self) -> Vec<String> {
+        let mut options = Vec::new();
+        match self.compression {
+            Some(DEFAULT_ZSTD_LEVEL) => options.push("compress=zstd".to_string()),
+            Some(level) => options.push(format!("compress=zstd:{}", level)),
+            None => {}
+        }
+        if self.noatime {
+            options.push("noatime".to_string());
+        }
+        if self.discard {
+            options.push("discard=async".to_string());
+        }
+        if self.autodefrag {
+            options.push("autodefrag".to_string());
+        }
+        options
+    }
+
+    /// One step up or down the compression scale: off, zstd:1 ... zstd:15
+    pub fn step_compression(&mut self, up: bool) {
+        self.compression = match (self.compression, up) {
+            (None, true) => Some(1),
+            (Some(level), true) => Some((level + 1).min(MAX_ZSTD_LEVEL)),
+            (Some(1) | None, false) => None,
+            (Some(level), false) => Some(level - 1),
+        };
+    }
+}
+
+/// Row of the disk options screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskOptionField {
+    Compression,
+    Noatime,
+    Discard,
+    Autodefrag,
+    BootLabel,
+    RootLabel,
+}
+
+impl DiskOptionField {
+    /// All rows, in screen order
+    pub const ALL: [DiskOptionField; 6] = [
+        DiskOptionField::Compression,
+        DiskOptionField::Noatime,
+        DiskOptionField::Discard,
+        DiskOptionField::Autodefrag,
+        DiskOptionField::BootLabel,
+        DiskOptionField::RootLabel,
+    ];
+
+    /// Whether the row takes typed text rather than ←→/Space
+    pub fn is_text(self) -> bool {
+        matches!(self, DiskOptionField::BootLabel | DiskOptionField::RootLabel)
+    }
+}
+
+/// Check the partition labels of the disk options
+///
+/// Labels end up in `/dev/disk/by-partlabel/` paths and Nix strings, so only
+/// letters, digits, `-` and `_` are allowed, within GPT's 36 characters.
+pub fn validate_disk_options(options: &DiskOptions) -> Option<String> {
+    let labels = [("Boot", &options.boot_label), ("Root", &options.root_label)];
+    for (name, label) in labels {
+        if label.is_empty() && name == "Root" {
+            continue;
+        }
+        if label.is_empty() {
+            return Some(format!("{} label cannot be empty", name));
+        }
+        if label.len() > 36 {
+            return Some(format!("{} label is longer than 36 characters", name));
+        }
+        if !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Some(format!("{} label may only contain letters, digits, - and _", name));
+        }
+    }
+    if options.boot_label == options.root_label {
+        return Some("Boot and root partitions need different labels".to_string());
+    }
+    None
+}
+
+/// Optional feature modules offered by the create-host module picker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum HostModule {
+    Printing,
+    Scanning,
+    Android,
+    Libvirt,
+    Docker,
+    Podman,
+    VirtualBox,
+    Gaming,
+    Bootstrap,
+}
+
+impl HostModule {
+    /// All modules, in picker order
+    pub const ALL: [HostModule; 9] = [
+        HostModule::Printing,
+        HostModule::Scanning,
+        HostModule::Android,
+        HostModule::Libvirt,
+        HostModule::Docker,
+        HostModule::Podman,
+        HostModule::VirtualBox,
+        HostModule::Gaming,
+        HostModule::Bootstrap,
+    ];
+
+    /// Modules that can't be enabled together with this one
+    pub fn conflicts(self) -> &'static [HostModule] {
+        match self {
+            // Podman's docker compatibility shim replaces the docker CLI and socket
+            HostModule::Docker => &[HostModule::Podman],
+            HostModule::Podman => &[HostModule::Docker],
+            // VirtualBox and KVM fight over VT-x/AMD-V
+            HostModule::Libvirt => &[HostModule::VirtualBox],
+            HostModule::VirtualBox => &[HostModule::Libvirt],
+            _ => &[],
+        }
+    }
+
+    /// Short description shown in the picker
+    pub fn description(self) -> &'static str {
+        match self {
+            HostModule::Printing => "CUPS with common drivers, network printer discovery",
+            HostModule::Scanning => "SANE with driverless (eSCL/WSD) scanner support",
+            HostModule::Android => "adb and fastboot with device access",
+            HostModule::Libvirt => "KVM/QEMU virtual machines with virt-manager",
+            HostModule::Docker => "Docker engine (already on by default)",
+            HostModule::Podman => "rootless containers, replaces Docker",
+            HostModule::VirtualBox => "Oracle VirtualBox, excludes libvirt",
+            HostModule::Gaming => "Steam, GameMode, gamescope, 32-bit graphics",
+            HostModule::Bootstrap => "restore keys, then app profiles, on first login",
+        }
+    }
+}
+
+impl std::fmt::Display for HostModule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostModule::Printing => write!(f, "Printing"),
+            HostModule::Scanning => write!(f, "Scanning"),
+            HostModule::Android => write!(f, "Android tools"),
+            HostModule::Libvirt => write!(f, "libvirt/QEMU"),
+            HostModule::Docker => write!(f, "Docker"),
+            HostModule::Podman => write!(f, "Podman"),
+            HostModule::VirtualBox => write!(f, "VirtualBox"),
+            HostModule::Gaming => write!(f, "Gaming"),
+            HostModule::Bootstrap => write!(f, "First-login bootstrap"),
+        }
+    }
+}
+
+/// Look and feel preset (maps to `theming.preset` in modules/theming.nix)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemePreset {
+    /// The desktop shell's own theming
+    #[default]
+    Default,
+    /// Light Adwaita with larger fonts and cursor
+    Simple,
+    /// Adwaita dark with Inter and Bibata cursor
+    Dark,
+}
+
+impl ThemePreset {
+    /// All presets, in menu order
+    pub const ALL: [ThemePreset; 3] = [ThemePreset::Default, ThemePreset::Simple, ThemePreset::Dark];
+
+    /// Position in `ALL`
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|t| *t == self).unwrap_or(0)
+    }
+
+    /// Value of the `theming.preset` option
+    pub fn nix_name(self) -> &'static str {
+        match self {
+            ThemePreset::Default => "default",
+            ThemePreset::Simple => "simple",
+            ThemePreset::Dark => "dark",
+        }
+    }
+
+    /// Short description shown in the wizard
+    pub fn description(self) -> &'static str {
+        match self {
+            ThemePreset::Default => "shell colours and fonts, as on the main machines",
+            ThemePreset::Simple => "light theme, larger text and cursor",
+            ThemePreset::Dark => "dark GTK/Qt theme, Inter font, Bibata cursor",
+        }
+    }
+}
+
+impl std::fmt::Display for ThemePreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemePreset::Default => write!(f, "Default"),
+            ThemePreset::Simple => write!(f, "Simple"),
+            ThemePreset::Dark => write!(f, "Dark"),
+        }
+    }
+}
+
+/// Configuration being built during host creation wizard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewHostConfig {
+    pub hostname: String,
+    pub cpu: CpuInfo,
+    pub gpu: GpuInfo,
+    pub form_factor: FormFactor,
+    pub disk: DiskInfo,
+    /// SSD or HDD, decides fstrim and the default mount options
+    pub storage: StorageKind,
+    /// Firmware boot mode, decides bootloader and partition table
+    pub boot_mode: BootMode,
+    /// Initrd modules loaded on this machine (storage controllers, input)
+    pub initrd_modules: Vec<String>,
+    /// Kernel package set
+    pub kernel: KernelFlavor,
+    /// Extra kernel command line parameters
+    pub kernel_params: Vec<String>,
+    /// Optional modules enabled in the module picker (kept sorted)
+    pub modules: Vec<HostModule>,
+    /// Theming preset
+    pub theme: ThemePreset,
+    /// Root filesystem (btrfs or ZFS)
+    pub filesystem: RootFilesystem,
+    /// `networking.hostId`, required by ZFS to import its pools
+    pub host_id: String,
+    /// Btrfs subvolume scheme
+    pub subvolume_layout: SubvolumeLayout,
+    /// Subvolumes for `SubvolumeLayout::Custom`
+    pub custom_subvolumes: Vec<(String, String)>,
+    /// Swap setup
+    pub swap: SwapMode,
+    /// Installed RAM in GiB, sizes the swap partition (0 if unknown)
+    pub memory_gib: u64,
+    /// Compression, mount options and partition labels (btrfs hosts)
+    pub disk_options: DiskOptions,
+}
+
+impl NewHostConfig {
+    /// Whether an optional module is enabled
+    pub fn has_module(&self, module: HostModule) -> bool {
+        self.modules.contains(&module)
+    }
+
+    /// Subvolumes to write into the disko config; None keeps the shared default
+    pub fn subvolumes(&self) -> Option<Vec<(String, String)>> {
+        match self.subvolume_layout {
+            SubvolumeLayout::Standard => None,
+            SubvolumeLayout::Custom => Some(self.custom_subvolumes.clone()),
+            layout => Some(
+                layout
+                    .subvolumes()
+                    .iter()
+                    .map(|(n, m)| (n.to_string(), m.to_string()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// On-disk swap for the disko config: the mode and its size in GiB
+    pub fn disk_swap(&self) -> Option<(SwapMode, u64)> {
+        self.swap
+            .disk_size_gib(self.memory_gib)
+            .map(|size| (self.swap, size))
+    }
+
+    /// Enable or disable an optional module, dropping any it conflicts with
+    pub fn toggle_module(&mut self, module: HostModule) {
+        if let Some(pos) = self.modules.iter().position(|m| *m == module) {
+            self.modules.remove(pos);
+        } else {
+            self.modules.retain(|m| !module.conflicts().contains(m));
+            self.modules.push(module);
+            self.modules.sort();
+        }
+    }
+}
+
+/// Create host wizard state machine
+/// Flow: DetectingHardware → ConfirmCpu → ConfirmGpu → ConfirmFormFactor → SelectDisk → EnterHostname → SelectFilesystem → SelectLayout (btrfs only) → SelectKernel → SelectModules → SelectTheme → Review → Generating → Complete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CreateHostState {
+    DetectingHardware,
+    ConfirmCpu {
+        cpu: CpuInfo,
+        detected_gpu: GpuInfo,
+        detected_form_factor: FormFactor,
+        override_menu: bool,
+        selected: usize,
+    },
+    ConfirmGpu {
+        cpu: CpuInfo,
+        gpu: GpuInfo,
+        detected_form_factor: FormFactor,
+        override_menu: bool,
+        selected: usize,
+    },
+    ConfirmFormFactor {
+        cpu: CpuInfo,
+        gpu: GpuInfo,
+        form_factor: FormFactor,
+        override_menu: bool,
+        selected: usize,
+    },
+    SelectDisk {
+        cpu: CpuInfo,
+        gpu: GpuInfo,
+        form_factor: FormFactor,
+        disks: Vec<DiskInfo>,
+        selected: usize,
+    },
+    EnterHostname {
+        cpu: CpuInfo,
+        gpu: GpuInfo,
+        form_factor: FormFactor,
+        disk: DiskInfo,
+        input: String,
+        error: Option<String>,
+    },
+    /// Root filesystem menu (btrfs or ZFS)
+    SelectFilesystem {
+        config: NewHostConfig,
+        selected: usize,
+        error: Option<String>,
+    },
+    /// Swap menu (none, zram, swapfile, hibernation partition)
+    SelectSwap {
+        config: NewHostConfig,
+        selected: usize,
+        error: Option<String>,
+    },
+    /// Btrfs subvolume scheme menu plus the custom scheme input
+    SelectLayout {
+        config: NewHostConfig,
+        selected: usize,
+        custom: String,
+        error: Option<String>,
+    },
+    /// Kernel flavor menu plus a free-form extra params input
+    SelectKernel {
+        config: NewHostConfig,
+        selected: usize,
+        params: String,
+        error: Option<String>,
+    },
+    /// Checklist of optional modules (printing, scanning, ...)
+    SelectModules {
+        config: NewHostConfig,
+        selected: usize,
+    },
+    /// Theming preset menu
+    SelectTheme {
+        config: NewHostConfig,
+        selected: usize,
+    },
+    Review {
+        config: NewHostConfig,
+        /// Lint and fix the generated files with statix/deadnix (Tab toggles)
+        lint: bool,
+    },
+    /// Advanced disk settings opened from the review (btrfs hosts)
+    EditDiskOptions {
+        config: NewHostConfig,
+        lint: bool,
+        /// Settings before this screen was opened, restored by Esc
+        original: DiskOptions,
+        selected: usize,
+        error: Option<String>,
+    },
+    // Never saved as a draft (see `app::draft`): the files are being written
+    #[serde(skip)]
+    Generating {
+        config: NewHostConfig,
+        lint: bool,
+        step: usize,
+        steps: Vec<StepStatus>,
+        output: VecDeque<String>,
+        /// Details of the first failed step, kept for the Complete screen
+        error: Option<String>,
+    },
+    #[serde(skip)]
+    Complete {
+        success: bool,
+        config: NewHostConfig,
+        error: Option<String>,
+    },
+}
+
+impl CreateHostState {
+    pub fn new() -> Self {
+        CreateHostState::DetectingHardware
+    }
+
+    /// The host being created, on the steps after the hostname (the ones
+    /// saved as a draft, see `app::draft`)
+    pub fn draft_config(&self) -> Option<&NewHostConfig> {
+        match self {
+            CreateHostState::SelectFilesystem { config, .. }
+            | CreateHostState::SelectSwap { config, .. }
+            | CreateHostState::SelectLayout { config, .. }
+            | CreateHostState::SelectKernel { config, .. }
+            | CreateHostState::SelectModules { config, .. }
+            | CreateHostState::SelectTheme { config, .. }
+            | CreateHostState::Review { config, .. }
+            | CreateHostState::EditDiskOptions { config, .. } => Some(config),
+            _ => None,
+        }
+    }
+}
+
+/// Update state machine
+#[derive(Debug, Clone)]
+pub enum UpdateState {
+    /// Choose a flake input and the local checkout that replaces it
+    PickOverride(OverridePicker),
+    /// Choose whether the rebuild runs switch, boot or test
+    PickActivation {
+        selected: usize,
+        /// How the last scheduled (headless) update went, if one ran
+        last_scheduled: Option<String>,
+    },
+    Running {
+        step: usize,
+        steps: Vec<StepStatus>,
+        output: VecDeque<String>,
+        /// Inputs pointed at local checkouts for this run
+        overrides: Vec<InputOverride>,
+        options: UpdateOptions,
+        /// Where to write the update report when the update finishes (`--report`)
+        report: Option<PathBuf>,
+    },
+    Complete {
+        #[allow(dead_code)]
+        success: bool,
+        steps: Vec<StepStatus>,
+        output: VecDeque<String>,
+        /// None = auto-scroll, Some(n) = manual scroll at position n
+        scroll_offset: Option<usize>,
+        overrides: Vec<InputOverride>,
+    },
+}
+
+impl UpdateState {
+    pub fn new() -> Self {
+        Self::with_overrides(Vec::new())
+    }
+
+    /// The activation picker the main menu opens
+    pub fn pick_activation() -> Self {
+        UpdateState::PickActivation {
+            selected: 0,
+            last_scheduled: load_scheduled_run().map(|run| run.describe()),
+        }
+    }
+
+    pub fn with_overrides(overrides: Vec<InputOverride>) -> Self {
+        Self::with_options(overrides, UpdateOptions::default())
+    }
+
+    pub fn with_options(overrides: Vec<InputOverride>, options: UpdateOptions) -> Self {
+        let options = options.detect_home_manager();
+        let mut steps = vec![
+            StepStatus::new("Pulling configuration updates"),
+            StepStatus::new("Updating flake inputs"),
+        ];
+        if options.lint {
+            steps.push(StepStatus::new("Linting configuration"));
+        }
+        if options.preview {
+            steps.push(StepStatus::new("Previewing new system"));
+        }
+        steps.extend([
+            StepStatus::new("Rebuilding system"),
+        ]);
+        if options.home_manager {
+            steps.push(StepStatus::new("Switching home-manager"));
+        }
+        steps.extend([
+            StepStatus::new("Comparing packages"),
+            StepStatus::new("Updating Claude Code"),
+            StepStatus::new("Updating Codex CLI"),
+            StepStatus::new("Checking browser profiles"),
+        ]);
+        if options.gc {
+            steps.push(StepStatus::new("Collecting garbage"));
+        }
+        if options.gc || options.optimise {
+            steps.push(StepStatus::new("Optimising store"));
+        }
+        UpdateState::Running {
+            step: 0,
+            steps,
+            output: VecDeque::new(),
+            overrides,
+            options,
+            report: None,
+        }
+    }
+
+    /// Write the update report to `path` when the update finishes
+    pub fn with_report(mut self, path: Option<PathBuf>) -> Self {
+        if let UpdateState::Running { report, .. } = &mut self {
+            *report = path;
+        }
+        self
+    }
+}
+
+/// Input list, then directory browser, for overriding a flake input
+#[derive(Debug, Clone)]
+pub struct OverridePicker {
+    /// Inputs of the config flake (Err if flake.lock couldn't be read)
+    pub inputs: Result<Vec<String>, String>,
+    pub selected: usize,
+    /// Input being overridden; the directory browser is shown once set
+    pub input: Option<String>,
+    /// Directory being browsed
+    pub dir: PathBuf,
+    pub entries: Vec<CheckoutDir>,
+    pub dir_selected: usize,
+    pub error: Option<String>,
+}
+
+impl OverridePicker {
+    pub fn new() -> Self {
+        let inputs = root_inputs(&crate::constants::nixos_config_dir())
+            .map_err(|e| format!("Could not read flake.lock: {}", e));
+        let dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("/"));
+        OverridePicker {
+            inputs,
+            selected: 0,
+            input: None,
+            entries: checkout_dirs(&dir),
+            dir,
+            dir_selected: 0,
+            error: None,
+        }
+    }
+
+    /// Browse `dir` instead of the current directory
+    pub fn open(&mut self, dir: PathBuf) {
+        self.entries = checkout_dirs(&dir);
+        self.dir = dir;
+        self.dir_selected = 0;
+        self.error = None;
+    }
+}
+
+/// App profile management state (browsers, Termius, etc.)
+#[derive(Debug, Clone)]
+pub enum AppProfileState {
+    Menu { selected: usize },
+    Running {
+        operation: AppOp,
+        output: VecDeque<String>,
+        force: bool,
+    },
+    Status {
+        output: VecDeque<String>,
+    },
+    Complete {
+        success: bool,
+        output: VecDeque<String>,
+        /// None = auto-scroll, Some(n) = manual scroll at position n
+        scroll_offset: Option<usize>,
+    },
+}
+
+impl AppProfileState {
+    pub fn new_menu() -> Self {
+        AppProfileState::Menu { selected: 0 }
+    }
+
+    pub fn new_backup(force: bool) -> Self {
+        AppProfileState::Running {
+            operation: AppOp::Backup,
+            output: VecDeque::new(),
+            force,
+        }
+    }
+
+    pub fn new_restore(force: bool) -> Self {
+        AppProfileState::Running {
+            operation: AppOp::Restore,
+            output: VecDeque::new(),
+            force,
+        }
+    }
+
+    pub fn new_status() -> Self {
+        AppProfileState::Status {
+            output: VecDeque::new(),
+        }
+    }
+
+    pub fn new_init(repo: Option<String>, backup: bool) -> Self {
+        AppProfileState::Running {
+            operation: AppOp::Init { repo, backup },
+            output: VecDeque::new(),
+            force: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppOp {
+    Backup,
+    Restore,
+    /// Create the backup repo if needed (`repo` overrides the configured one),
+    /// then push the first backup unless `backup` is false
+    Init { repo: Option<String>, backup: bool },
+}
+
+/// Masked passphrase entry, asked twice when a new passphrase is chosen
+#[derive(Debug, Clone, Default)]
+pub struct PassphrasePrompt {
+    pub passphrase: String,
+    pub confirm: String,
+    /// Whether there is a confirmation field (off when unlocking something existing)
+    pub needs_confirm: bool,
+    /// Cursor is in the confirmation field
+    pub on_confirm: bool,
+    pub error: Option<String>,
+    /// Passphrases rejected so far (keys restore asks again a few times)
+    pub attempts: u32,
+}
+
+impl PassphrasePrompt {
+    pub fn new(needs_confirm: bool) -> Self {
+        Self {
+            needs_confirm,
+            ..Self::default()
+        }
+    }
+
+    fn field(&mut self) -> &mut String {
+        if self.on_confirm {
+            &mut self.confirm
+        } else {
+            &mut self.passphrase
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        if self.field().len() < crate::constants::MAX_INPUT_LENGTH {
+            self.field().push(c);
+        }
+        self.error = None;
+    }
+
+    pub fn pop(&mut self) {
+        self.field().pop();
+        self.error = None;
+    }
+
+    pub fn toggle_field(&mut self) {
+        self.on_confirm = self.needs_confirm && !self.on_confirm;
+        self.error = None;
+    }
+
+    /// Enter: move on to the confirmation, or return the passphrase once it is valid
+    pub fn submit(&mut self) -> Option<String> {
+        if self.needs_confirm && !self.on_confirm && self.confirm.is_empty() {
+            self.on_confirm = true;
+            return None;
+        }
+        self.error = if self.needs_confirm {
+            validate_passphrase(&self.passphrase, &self.confirm)
+        } else if self.passphrase.is_empty() {
+            Some("Passphrase cannot be empty".to_string())
+        } else {
+            None
+        };
+        self.error.is_none().then(|| self.passphrase.clone())
+    }
+}
+
+/// Key management state
+#[derive(Debug, Clone)]
+pub enum KeysState {
+    /// Passphrase for the keys archive, typed here instead of at the script's prompt
+    EnterPassphrase {
+        operation: KeysOp,
+        force: bool,
+        prompt: PassphrasePrompt,
+    },
+    Running {
+        operation: KeysOp,
+        output: VecDeque<String>,
+        force: bool,
+        /// Handed to keys-backup/keys-restore; None lets them use 1Password
+        passphrase: Option<String>,
+        /// Passphrases rejected before this run
+        attempts: u32,
+    },
+    Complete {
+        success: bool,
+        output: VecDeque<String>,
+        /// None = auto-scroll, Some(n) = manual scroll at position n
+        scroll_offset: Option<usize>,
+        /// After a setup: `g` registers the new key with GitHub
+        offer_github: bool,
+    },
+}
+
+impl KeysState {
+    pub fn new_setup() -> Self {
+        KeysState::Running {
+            operation: KeysOp::Setup,
+            output: VecDeque::new(),
+            force: false,
+            passphrase: None,
+            attempts: 0,
+        }
+    }
+
+    pub fn new_backup() -> Self {
+        Self::with_passphrase(KeysOp::Backup, false, PassphrasePrompt::new(true))
+    }
+
+    pub fn new_restore(force: bool) -> Self {
+        Self::with_passphrase(KeysOp::Restore, force, PassphrasePrompt::new(false))
+    }
+
+    /// Ask for the archive passphrase unless the scripts read it from 1Password
+    fn with_passphrase(operation: KeysOp, force: bool, prompt: PassphrasePrompt) -> Self {
+        if crate::commands::keys::passphrase_in_1password() {
+            KeysState::Running {
+                operation,
+                output: VecDeque::new(),
+                force,
+                passphrase: None,
+                attempts: 0,
+            }
+        } else {
+            KeysState::EnterPassphrase {
+                operation,
+                force,
+                prompt,
+            }
+        }
+    }
+
+    pub fn new_status() -> Self {
+        KeysState::Running {
+            operation: KeysOp::Status,
+            output: VecDeque::new(),
+            force: false,
+            passphrase: None,
+            attempts: 0,
+        }
+    }
+
+    pub fn new_github(deploy: bool) -> Self {
+        KeysState::Running {
+            operation: KeysOp::GitHub { deploy },
+            output: VecDeque::new(),
+            force: false,
+            passphrase: None,
+            attempts: 0,
+        }
+    }
+}
+
+/// First-boot bootstrap state machine (keys restore, then app restore)
+#[derive(Debug, Clone)]
+pub enum BootstrapState {
+    Prompt { selected: usize },
+    Running {
+        step: usize,
+        steps: Vec<StepStatus>,
+        output: VecDeque<String>,
+    },
+    Complete {
+        success: bool,
+        steps: Vec<StepStatus>,
+        output: VecDeque<String>,
+        /// None = auto-scroll, Some(n) = manual scroll at position n
+        scroll_offset: Option<usize>,
+    },
+}
+
+impl BootstrapState {
+    pub fn new() -> Self {
+        BootstrapState::Prompt { selected: 0 }
+    }
+
+    pub fn new_running() -> Self {
+        BootstrapState::Running {
+            step: 0,
+            steps: vec![
+                StepStatus::new("Restoring keys"),
+                StepStatus::new("Restoring app profiles"),
+            ],
+            output: VecDeque::new(),
+        }
+    }
+}
+
+/// Fleet screens: rolling update (one progress step per host) and drift status
+#[derive(Debug, Clone)]
+pub enum FleetState {
+    /// Host reports vs. main; None while loading
+    Status {
+        report: Option<Result<Vec<HostDrift>, String>>,
+    },
+    Running {
+        plan: RolloutPlan,
+        steps: Vec<StepStatus>,
+        /// Stage each started host got to
+        stages: HashMap<String, DeployStage>,
+        output: VecDeque<String>,
+    },
+    Complete {
+        success: bool,
+        steps: Vec<StepStatus>,
+        stages: HashMap<String, DeployStage>,
+        output: VecDeque<String>,
+        /// None = auto-scroll, Some(n) = manual scroll at position n
+        scroll_offset: Option<usize>,
+    },
+}
+
+impl FleetState {
+    pub fn new_status() -> Self {
+        FleetState::Status { report: None }
+    }
+
+    pub fn new(plan: RolloutPlan) -> Self {
+        // Host steps are matched by exact name, so they carry no decoration
+        let mut steps = vec![StepStatus::new("Pulling configuration updates")];
+        steps.extend(plan.hosts().map(|h| StepStatus::new(&h.name)));
+        FleetState::Running {
+            plan,
+            steps,
+            stages: HashMap::new(),
+            output: VecDeque::new(),
+        }
+    }
+}
+
+/// Repo-defined pipeline state (pipelines.nix)
+#[derive(Debug, Clone)]
+pub enum PipelineState {
+    Menu {
+        pipelines: Vec<Pipeline>,
+        selected: usize,
+    },
+    Running {
+        pipeline: Pipeline,
+        steps: Vec<StepStatus>,
+        output: VecDeque<String>,
+    },
+    Complete {
+        success: bool,
+        title: String,
+        steps: Vec<StepStatus>,
+        output: VecDeque<String>,
+        /// None = auto-scroll, Some(n) = manual scroll at position n
+        scroll_offset: Option<usize>,
+    },
+}
+
+impl PipelineState {
+    pub fn new_menu(pipelines: Vec<Pipeline>) -> Self {
+        PipelineState::Menu {
+            pipelines,
+            selected: 0,
+        }
+    }
+
+    pub fn new_running(pipeline: Pipeline) -> Self {
+        // Step names are unique per pipeline and matched exactly
+        let steps = pipeline
+            .steps
+            .iter()
+            .map(|s| StepStatus::new(&s.name))
+            .collect();
+        PipelineState::Running {
+            pipeline,
+            steps,
+            output: VecDeque::new(),
+        }
+    }
+}
+
+/// Dev shells screen
+#[derive(Debug, Clone)]
+pub struct DevShellState {
+    /// None while the flakes are being evaluated
+    pub shells: Option<Vec<DevShell>>,
+    pub selected: usize,
+    /// Outcome of the last launch, shown under the list
+    pub status: Option<Result<String, String>>,
+}
+
+impl DevShellState {
+    pub fn new() -> Self {
+        DevShellState {
+            shells: None,
+            selected: 0,
+            status: None,
+        }
+    }
+}
+
+/// Flake registry screen
+#[derive(Debug, Clone)]
+pub struct RegistryState {
+    /// None while loading or applying a change
+    pub rows: Option<Result<Vec<RegistryRow>, String>>,
+    pub selected: usize,
+    /// Outcome of the last change, shown under the list
+    pub status: Option<Result<String, String>>,
+    /// Add-entry form, while open
+    pub form: Option<RegistryForm>,
 }
 
-impl CreateHostState {
+impl RegistryState {
     pub fn new() -> Self {
-        CreateHostState::DetectingHardware
+        RegistryState {
+            rows: None,
+            selected: 0,
+            status: None,
+            form: None,
+        }
+    }
+
+    /// The selected entry's name, once the list is loaded
+    pub fn selected_name(&self) -> Option<&str> {
+        match &self.rows {
+            Some(Ok(rows)) => rows.get(self.selected).map(|r| r.name.as_str()),
+            _ => None,
+        }
     }
 }
 
-/// Update state machine
+/// Which add-entry field is active
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum RegistryField {
+    #[default]
+    Name,
+    Url,
+}
+
+/// Add-entry form on the registry screen
+#[derive(Debug, Clone, Default)]
+pub struct RegistryForm {
+    pub name: String,
+    pub url: String,
+    pub field: RegistryField,
+    pub error: Option<String>,
+}
+
+/// Garbage collection settings screen (gc.json)
 #[derive(Debug, Clone)]
-pub enum UpdateState {
-    Running {
-        step: usize,
-        steps: Vec<StepStatus>,
-        output: VecDeque<String>,
-    },
-    Complete {
-        #[allow(dead_code)]
-        success: bool,
-        steps: Vec<StepStatus>,
-        output: VecDeque<String>,
-        /// None = auto-scroll, Some(n) = manual scroll at position n
-        scroll_offset: Option<usize>,
-    },
+pub struct GcState {
+    /// None while loading or saving
+    pub config: Option<Result<GcConfig, String>>,
+    /// Hosts that can get their own overrides
+    pub hosts: Vec<String>,
+    /// 0 = all hosts, then one per entry in `hosts`
+    pub scope: usize,
+    /// Selected field (index into `GcField::ALL`)
+    pub selected: usize,
+    /// Text being entered for the selected field
+    pub input: Option<String>,
+    pub input_error: Option<String>,
+    /// Outcome of the last save, shown under the settings
+    pub status: Option<Result<String, String>>,
 }
 
-impl UpdateState {
+impl GcState {
     pub fn new() -> Self {
-        UpdateState::Running {
-            step: 0,
-            steps: vec![
-                StepStatus::new("Pulling configuration updates"),
-                StepStatus::new("Updating flake inputs"),
-                StepStatus::new("Rebuilding system"),
-                StepStatus::new("Comparing packages"),
-                StepStatus::new("Updating Claude Code"),
-                StepStatus::new("Updating Codex CLI"),
-                StepStatus::new("Checking browser profiles"),
-            ],
-            output: VecDeque::new(),
+        GcState {
+            config: None,
+            hosts: Vec::new(),
+            scope: 0,
+            selected: 0,
+            input: None,
+            input_error: None,
+            status: None,
         }
     }
+
+    /// Host whose overrides are shown, None for the shared defaults
+    pub fn host(&self) -> Option<&str> {
+        self.scope
+            .checked_sub(1)
+            .and_then(|i| self.hosts.get(i))
+            .map(String::as_str)
+    }
+
+    pub fn field(&self) -> GcField {
+        GcField::ALL[self.selected.min(GcField::ALL.len() - 1)]
+    }
 }
 
-/// App profile management state (browsers, Termius, etc.)
+/// nix.conf settings screen (nix-conf.json)
 #[derive(Debug, Clone)]
-pub enum AppProfileState {
-    Menu { selected: usize },
+pub struct NixConfState {
+    /// None while loading or saving
+    pub config: Option<Result<NixConfConfig, String>>,
+    /// Hosts that can get their own overrides
+    pub hosts: Vec<String>,
+    /// This machine, whose tab also shows what /etc/nix/nix.conf has now
+    pub this_host: Option<String>,
+    /// This machine's nix.conf values, keyed by setting name
+    pub live: Option<Result<BTreeMap<String, String>, String>>,
+    /// 0 = all hosts, then one per entry in `hosts`
+    pub scope: usize,
+    /// Selected field (index into `NixConfField::ALL`)
+    pub selected: usize,
+    /// Text being entered for the selected field
+    pub input: Option<String>,
+    pub input_error: Option<String>,
+    /// Outcome of the last save, shown under the settings
+    pub status: Option<Result<String, String>>,
+}
+
+impl NixConfState {
+    pub fn new() -> Self {
+        NixConfState {
+            config: None,
+            hosts: Vec::new(),
+            this_host: None,
+            live: None,
+            scope: 0,
+            selected: 0,
+            input: None,
+            input_error: None,
+            status: None,
+        }
+    }
+
+    /// Host whose overrides are shown, None for the shared defaults
+    pub fn host(&self) -> Option<&str> {
+        self.scope
+            .checked_sub(1)
+            .and_then(|i| self.hosts.get(i))
+            .map(String::as_str)
+    }
+
+    pub fn field(&self) -> NixConfField {
+        NixConfField::ALL[self.selected.min(NixConfField::ALL.len() - 1)]
+    }
+
+    /// nix.conf's value for a field, on this machine's tab only
+    pub fn live_value(&self, field: NixConfField) -> Option<&str> {
+        if self.host().is_none() || self.host() != self.this_host.as_deref() {
+            return None;
+        }
+        match &self.live {
+            Some(Ok(live)) => live.get(field.key()).map(String::as_str),
+            _ => None,
+        }
+    }
+}
+
+/// Per-run build parallelism for this host (`forge parallelism`)
+#[derive(Debug, Clone)]
+pub struct ParallelismState {
+    pub host: String,
+    pub topology: CpuTopology,
+    pub memory_gib: u64,
+    /// nix.conf's values, which unset fields leave in charge
+    pub nix_conf: BTreeMap<String, String>,
+    /// Values being edited, saved with Enter
+    pub value: BuildParallelism,
+    /// 0 = max-jobs, 1 = cores
+    pub selected: usize,
+    /// Outcome of loading or the last save
+    pub status: Option<Result<String, String>>,
+}
+
+impl ParallelismState {
+    pub fn new() -> Self {
+        let host = crate::system::network::get_hostname().unwrap_or_default();
+        let (value, status) = match load_parallelism(&host) {
+            Ok(value) => (value, None),
+            Err(e) => (BuildParallelism::default(), Some(Err(format!("{:#}", e)))),
+        };
+        ParallelismState {
+            host,
+            topology: detect_cpu_topology(),
+            memory_gib: detect_memory_gib(),
+            nix_conf: read_system_nix_conf().unwrap_or_default(),
+            value,
+            selected: 0,
+            status,
+        }
+    }
+
+    /// The selected field of `value`
+    pub fn field_mut(&mut self) -> &mut Option<u32> {
+        if self.selected == 0 {
+            &mut self.value.max_jobs
+        } else {
+            &mut self.value.cores
+        }
+    }
+}
+
+/// Store optimise run (`nix store optimise`), after a garbage collection
+/// for `forge gc run`
+#[derive(Debug, Clone)]
+pub enum OptimiseState {
     Running {
-        operation: AppOp,
-        output: VecDeque<String>,
-        force: bool,
-    },
-    Status {
+        progress: OptimiseProgress,
         output: VecDeque<String>,
+        gc: Option<GcRun>,
     },
     Complete {
         success: bool,
+        progress: OptimiseProgress,
         output: VecDeque<String>,
         /// None = auto-scroll, Some(n) = manual scroll at position n
         scroll_offset: Option<usize>,
+        gc: Option<GcRun>,
     },
 }
 
-impl AppProfileState {
-    pub fn new_menu() -> Self {
-        AppProfileState::Menu { selected: 0 }
-    }
+/// Garbage collection that runs before the optimise
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GcRun {
+    /// Days of generations to keep; None for gc.json's setting for this host
+    pub older_than: Option<u32>,
+    /// Bytes nix-collect-garbage freed, once it has finished
+    pub freed: Option<u64>,
+}
 
-    pub fn new_backup(force: bool) -> Self {
-        AppProfileState::Running {
-            operation: AppOp::Backup,
+impl OptimiseState {
+    pub fn new() -> Self {
+        OptimiseState::Running {
+            progress: OptimiseProgress::default(),
             output: VecDeque::new(),
-            force,
+            gc: None,
         }
     }
 
-    pub fn new_restore(force: bool) -> Self {
-        AppProfileState::Running {
-            operation: AppOp::Restore,
+    pub fn with_gc(older_than: Option<u32>) -> Self {
+        OptimiseState::Running {
+            progress: OptimiseProgress::default(),
             output: VecDeque::new(),
-            force,
+            gc: Some(GcRun {
+                older_than,
+                freed: None,
+            }),
         }
     }
+}
 
-    pub fn new_status() -> Self {
-        AppProfileState::Status {
+/// Bisection of a flake input (`forge bisect`)
+#[derive(Debug, Clone)]
+pub struct BisectState {
+    pub input: String,
+    pub good: String,
+    pub bad: String,
+    pub mode: BisectMode,
+    /// None while the commit range loads
+    pub bisection: Option<Result<Bisection, String>>,
+    /// Commit being tested (index into the bisection's commits)
+    pub testing: Option<usize>,
+    /// None while the rebuild runs, then whether it succeeded
+    pub built: Option<bool>,
+    pub output: VecDeque<String>,
+}
+
+impl BisectState {
+    pub fn new(input: String, good: String, bad: String, mode: BisectMode) -> Self {
+        BisectState {
+            input,
+            good,
+            bad,
+            mode,
+            bisection: None,
+            testing: None,
+            built: None,
             output: VecDeque::new(),
         }
     }
-}
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum AppOp {
-    Backup,
-    Restore,
+    /// A candidate is being rebuilt
+    pub fn building(&self) -> bool {
+        self.testing.is_some() && self.built.is_none()
+    }
+
+    /// Waiting for a good/bad/skip verdict on the tested commit
+    pub fn awaiting_verdict(&self) -> bool {
+        self.testing.is_some() && self.built.is_some()
+    }
+
+    /// Nothing left to test (or the range failed to load)
+    pub fn finished(&self) -> bool {
+        match &self.bisection {
+            Some(Ok(_)) => self.testing.is_none(),
+            Some(Err(_)) => true,
+            None => false,
+        }
+    }
 }
 
-/// Key management state
+/// Switching the system to an earlier generation (`forge rollback`)
 #[derive(Debug, Clone)]
-pub enum KeysState {
+pub enum RollbackState {
+    Select {
+        /// None while the system profile is read
+        generations: Option<Result<Vec<Generation>, String>>,
+        selected: usize,
+        /// Waiting for y/n: Some(n) to switch to generation n, None for the previous one
+        confirm: Option<Option<u32>>,
+    },
     Running {
-        operation: KeysOp,
+        /// Generation being switched to, None for `--rollback`
+        target: Option<u32>,
+        steps: Vec<StepStatus>,
         output: VecDeque<String>,
-        force: bool,
     },
     Complete {
         success: bool,
+        target: Option<u32>,
+        steps: Vec<StepStatus>,
         output: VecDeque<String>,
         /// None = auto-scroll, Some(n) = manual scroll at position n
         scroll_offset: Option<usize>,
     },
 }
 
-impl KeysState {
-    pub fn new_setup() -> Self {
-        KeysState::Running {
-            operation: KeysOp::Setup,
-            output: VecDeque::new(),
-            force: false,
+impl RollbackState {
+    pub fn new() -> Self {
+        RollbackState::Select {
+            generations: None,
+            selected: 0,
+            confirm: None,
         }
     }
 
-    pub fn new_backup() -> Self {
-        KeysState::Running {
-            operation: KeysOp::Backup,
+    pub fn new_running(target: Option<u32>) -> Self {
+        let steps = match target {
+            Some(_) => vec![StepStatus::new(SWITCH_PROFILE_STEP), StepStatus::new(ACTIVATE_STEP)],
+            None => vec![StepStatus::new(ROLLBACK_STEP)],
+        };
+        RollbackState::Running {
+            target,
+            steps,
             output: VecDeque::new(),
-            force: false,
         }
     }
+}
 
-    pub fn new_restore(force: bool) -> Self {
-        KeysState::Running {
-            operation: KeysOp::Restore,
-            output: VecDeque::new(),
-            force,
+/// What a rollback switches to, for titles and prompts
+pub fn rollback_label(target: Option<u32>) -> String {
+    match target {
+        Some(number) => format!("generation {}", number),
+        None => "the previous generation".to_string(),
+    }
+}
+
+/// System generations screen: closure sizes, deleting, the boot default
+#[derive(Debug, Clone)]
+pub struct GenerationsState {
+    /// None while loading or applying a change
+    pub generations: Option<Result<Vec<Generation>, String>>,
+    pub selected: usize,
+    /// Generations marked for deletion
+    pub marked: BTreeSet<u32>,
+    /// Outcome of the last change, shown under the table
+    pub status: Option<Result<String, String>>,
+    /// Change waiting for y/n
+    pub confirm: Option<GenerationsOp>,
+}
+
+impl GenerationsState {
+    pub fn new() -> Self {
+        GenerationsState {
+            generations: None,
+            selected: 0,
+            marked: BTreeSet::new(),
+            status: None,
+            confirm: None,
         }
     }
 
-    pub fn new_status() -> Self {
-        KeysState::Running {
-            operation: KeysOp::Status,
-            output: VecDeque::new(),
-            force: false,
+    /// The selected generation, once the list is loaded
+    pub fn selected_generation(&self) -> Option<&Generation> {
+        match &self.generations {
+            Some(Ok(list)) => list.get(self.selected),
+            _ => None,
+        }
+    }
+}
+
+/// Update channel screen: which nixpkgs each host follows
+#[derive(Debug, Clone)]
+pub struct ChannelsState {
+    /// None while loading or applying a move
+    pub hosts: Option<Result<Vec<HostChannel>, String>>,
+    /// Release of the stable inputs, None while no host uses them
+    pub stable: Option<String>,
+    pub selected: usize,
+    /// Outcome of the last move, shown under the list
+    pub status: Option<Result<String, String>>,
+}
+
+impl ChannelsState {
+    pub fn new() -> Self {
+        ChannelsState {
+            hosts: None,
+            stable: None,
+            selected: 0,
+            status: None,
+        }
+    }
+}
+
+/// Background services screen: forge's timers on this host
+#[derive(Debug, Clone)]
+pub struct ServicesState {
+    /// None while loading or saving a change
+    pub rows: Option<Result<Vec<ServiceRow>, String>>,
+    pub host: String,
+    pub selected: usize,
+    /// Outcome of the last change, shown under the list
+    pub status: Option<Result<String, String>>,
+}
+
+impl ServicesState {
+    pub fn new() -> Self {
+        ServicesState {
+            rows: None,
+            host: String::new(),
+            selected: 0,
+            status: None,
         }
     }
 }
@@ -382,6 +2389,8 @@ pub enum KeysOp {
     Backup,
     Restore,
     Status,
+    /// Register the SSH key with GitHub (`deploy`: as the app-backup repo's deploy key)
+    GitHub { deploy: bool },
 }
 
 /// Step progress status
@@ -389,6 +2398,8 @@ pub enum KeysOp {
 pub struct StepStatus {
     pub name: String,
     pub status: StepState,
+    /// Latest percentage of a running step, when its output gives one
+    pub progress: Option<StepProgress>,
 }
 
 impl StepStatus {
@@ -396,10 +2407,28 @@ impl StepStatus {
         Self {
             name: name.to_string(),
             status: StepState::Pending,
+            progress: None,
         }
     }
 }
 
+/// How far along a run of steps is (0-100), counting the running step's own
+/// progress when it reports one
+pub fn steps_percent(steps: &[StepStatus]) -> u16 {
+    if steps.is_empty() {
+        return 0;
+    }
+    let hundredths: usize = steps
+        .iter()
+        .map(|s| match s.status {
+            StepState::Complete | StepState::Failed | StepState::Skipped => 100,
+            StepState::Running => s.progress.as_ref().map_or(0, |p| usize::from(p.percent)),
+            StepState::Pending => 0,
+        })
+        .sum();
+    (hundredths / steps.len()) as u16
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum StepState {
     Pending,
@@ -422,6 +2451,16 @@ pub struct UpdateSummary {
     pub browser_status: String,
     pub rebuild_skipped: bool,
     pub rebuild_failed: bool,
+    pub switch_declined: bool,                        // --preview diff was declined
+    pub activation: Activation,                       // switch, boot or test
+    pub cache_stats: Option<CacheStats>,              // Substituted vs built during rebuild
+    pub rebuild_secs: Option<u64>,                    // Wall time of the rebuild
+    pub overrides: Vec<InputOverride>,                // Inputs built from local checkouts
+    pub warnings: EvalWarnings,                       // Evaluation warnings from the rebuild
+    pub lint: Option<Vec<LintFinding>>,               // statix/deadnix findings (--lint)
+    pub gc_freed: Option<u64>,                        // Bytes nix-collect-garbage freed (--gc)
+    pub optimise_saved: Option<u64>,                  // Bytes hard-linking saved (--gc, --optimise)
+    pub home_manager: Option<(String, bool)>,         // Standalone config, whether it switched
 }
 
 /// Information about a pending commit
@@ -438,6 +2477,8 @@ pub struct PendingUpdates {
     pub app_profiles: bool,
     /// Pending commits for nixos-config
     pub commits: Vec<CommitInfo>,
+    /// Flake inputs behind upstream, already formatted for display
+    pub flake_inputs: Vec<String>,
     /// Selected option in the dialog (0 = first option)
     pub selected: usize,
     /// True when viewing the commit list
@@ -457,6 +2498,7 @@ impl PendingUpdates {
         self.nixos_config = false;
         self.app_profiles = false;
         self.commits.clear();
+        self.flake_inputs.clear();
         self.selected = 0;
         self.viewing_commits = false;
         self.commit_scroll = 0;
@@ -464,6 +2506,105 @@ impl PendingUpdates {
     }
 }
 
+/// Failed derivations of the running command and their saved `nix log` output
+#[derive(Debug, Clone, Default)]
+pub struct BuildLogs {
+    /// Derivations reported as failed so far, in output order
+    pub failed: Vec<String>,
+    /// Logs saved after the last failed run
+    pub saved: Vec<SavedBuildLog>,
+    /// Log shown in the viewer, if open
+    pub viewing: Option<usize>,
+    /// Scroll position in the viewer
+    pub scroll: usize,
+}
+
+impl BuildLogs {
+    /// Record a failed derivation if the output line reports one
+    pub fn observe(&mut self, line: &str) {
+        if let Some(drv) = failed_derivation(line) {
+            if !self.failed.contains(&drv) {
+                self.failed.push(drv);
+            }
+        }
+    }
+
+    /// Open the viewer on the first saved log
+    pub fn open(&mut self) {
+        if !self.saved.is_empty() {
+            self.viewing = Some(0);
+            self.scroll = 0;
+        }
+    }
+
+    /// Scroll the viewer down, stopping at the last line
+    pub fn scroll_down(&mut self, lines: usize) {
+        let max = self.viewed().map_or(0, |log| log.lines.len().saturating_sub(1));
+        self.scroll = (self.scroll + lines).min(max);
+    }
+
+    /// Show the next saved log in the viewer
+    pub fn next_log(&mut self) {
+        if let Some(index) = self.viewing {
+            self.viewing = Some((index + 1) % self.saved.len());
+            self.scroll = 0;
+        }
+    }
+
+    pub fn viewed(&self) -> Option<&SavedBuildLog> {
+        self.viewing.and_then(|i| self.saved.get(i))
+    }
+}
+
+/// Changelogs of the flake inputs the last update moved
+#[derive(Debug, Clone, Default)]
+pub struct Changelog {
+    pub inputs: Vec<FlakeInputChange>,
+    /// nvd's (package, old version, new version), for the major bumps
+    pub package_changes: Vec<(String, String, String)>,
+    /// Input shown in the viewer, if open
+    pub viewing: Option<usize>,
+    /// Scroll position in the viewer
+    pub scroll: usize,
+}
+
+impl Changelog {
+    /// Open the viewer on the first input
+    pub fn open(&mut self) {
+        if !self.inputs.is_empty() {
+            self.viewing = Some(0);
+            self.scroll = 0;
+        }
+    }
+
+    /// Lines of the input shown in the viewer
+    pub fn viewed_lines(&self) -> Vec<String> {
+        self.viewing
+            .and_then(|i| self.inputs.get(i))
+            .map(|input| input.changelog_lines(&self.package_changes))
+            .unwrap_or_default()
+    }
+
+    /// Scroll the viewer down, stopping at the last line
+    pub fn scroll_down(&mut self, lines: usize) {
+        let max = self.viewed_lines().len().saturating_sub(1);
+        self.scroll = (self.scroll + lines).min(max);
+    }
+
+    /// Show the next (or previous) input in the viewer
+    pub fn select(&mut self, forward: bool) {
+        if let Some(index) = self.viewing {
+            let count = self.inputs.len();
+            self.viewing = Some(if forward {
+                (index + 1) % count
+            } else {
+                (index + count - 1) % count
+            });
+            self.scroll = 0;
+        }
+    }
+}
+
 /// Check if a host directory already exists on the filesystem
 pub fn host_dir_exists(hostname: &str) -> bool {
     crate::constants::host_dir_paths(hostname)
@@ -509,3 +2650,364 @@ pub fn validate_hostname(hostname: &str, hosts: &[HostConfig]) -> Option<String>
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_credential_field_order() {
+        let last = CredentialField::Encryption;
+        assert_eq!(last.next(false), CredentialField::Username);
+        assert_eq!(last.next(true), CredentialField::Passphrase);
+        assert_eq!(CredentialField::Username.prev(true), CredentialField::Tpm2Unlock);
+        assert_eq!(CredentialField::ConfirmPassphrase.next(true), CredentialField::Tpm2Unlock);
+        assert_eq!(CredentialField::Username.prev(false), CredentialField::Encryption);
+    }
+
+    #[test]
+    fn test_update_steps() {
+        let names = |state: UpdateState| match state {
+            UpdateState::Running { steps, .. } => {
+                steps.into_iter().map(|s| s.name).collect::<Vec<_>>()
+            }
+            _ => Vec::new(),
+        };
+        let plain = names(UpdateState::new());
+        assert!(!plain.iter().any(|s| s.starts_with("Previewing")));
+        // The preview is answered before the switch it guards
+        let options = UpdateOptions {
+            lint: true,
+            gc: true,
+            preview: true,
+            ..Default::default()
+        };
+        let preview = names(UpdateState::with_options(Vec::new(), options));
+        let at = preview.iter().position(|s| s == "Previewing new system").unwrap();
+        assert_eq!(preview[at - 1], "Linting configuration");
+        assert_eq!(preview[at + 1], "Rebuilding system");
+        assert_eq!(preview[preview.len() - 2], "Collecting garbage");
+        assert_eq!(preview.last().map(String::as_str), Some("Optimising store"));
+        // --optimise alone hard-links without collecting
+        let options = UpdateOptions {
+            optimise: true,
+            ..Default::default()
+        };
+        let optimise = names(UpdateState::with_options(Vec::new(), options));
+        assert!(!optimise.iter().any(|s| s == "Collecting garbage"));
+        assert_eq!(optimise.last().map(String::as_str), Some("Optimising store"));
+    }
+
+    #[test]
+    fn test_steps_percent() {
+        let mut steps = vec![
+            StepStatus::new("Pulling configuration updates"),
+            StepStatus::new("Updating flake inputs"),
+            StepStatus::new("Rebuilding system"),
+            StepStatus::new("Updating Claude Code"),
+        ];
+        assert_eq!(steps_percent(&steps), 0);
+        steps[0].status = StepState::Complete;
+        steps[1].status = StepState::Skipped;
+        steps[2].status = StepState::Running;
+        assert_eq!(steps_percent(&steps), 50);
+        steps[2].progress = Some(StepProgress { percent: 72, eta_secs: None });
+        assert_eq!(steps_percent(&steps), 68);
+    }
+
+    #[test]
+    fn test_validate_passphrase() {
+        let strong = "correct horse battery staple";
+        assert_eq!(validate_passphrase(strong, strong), None);
+        assert!(validate_passphrase("", "").is_some());
+        assert!(validate_passphrase("hunter22", "hunter22").unwrap().contains("weak"));
+        assert!(validate_passphrase(strong, "correct horse").unwrap().contains("match"));
+    }
+
+    #[test]
+    fn test_disk_options() {
+        let mut options = DiskOptions::default();
+        assert_eq!(options.mount_options(), vec!["compress=zstd", "noatime"]);
+        options.step_compression(true);
+        options.discard = true;
+        assert_eq!(options.mount_options(), vec!["compress=zstd:4", "noatime", "discard=async"]);
+        options.compression = Some(1);
+        options.step_compression(false);
+        assert_eq!(options.compression, None);
+        options.compression = Some(MAX_ZSTD_LEVEL);
+        options.step_compression(true);
+        assert_eq!(options.compression, Some(MAX_ZSTD_LEVEL));
+
+        let hdd = DiskOptions::for_storage(StorageKind::Hdd);
+        assert_eq!(hdd.mount_options(), vec!["compress=zstd", "noatime", "autodefrag"]);
+        assert_eq!(DiskOptions::for_storage(StorageKind::Ssd), DiskOptions::default());
+    }
+
+    #[test]
+    fn test_validate_disk_options() {
+        let mut options = DiskOptions::default();
+        assert_eq!(validate_disk_options(&options), None);
+        options.root_label = "nixos-root".to_string();
+        assert_eq!(validate_disk_options(&options), None);
+        options.root_label = "ESP".to_string();
+        assert!(validate_disk_options(&options).unwrap().contains("different"));
+        options.root_label = "my root".to_string();
+        assert!(validate_disk_options(&options).is_some());
+        options.root_label.clear();
+        options.boot_label.clear();
+        assert!(validate_disk_options(&options).unwrap().contains("empty"));
+    }
+
+    #[test]
+    fn test_secure_boot_blocker() {
+        assert_eq!(secure_boot_blocker(SecureBootSupport::SetupMode, DiskMode::Wipe), None);
+        assert!(secure_boot_blocker(SecureBootSupport::SetupMode, DiskMode::Alongside).is_some());
+        assert!(secure_boot_blocker(SecureBootSupport::UserMode, DiskMode::Wipe)
+            .unwrap()
+            .contains("Setup Mode"));
+        assert!(secure_boot_blocker(SecureBootSupport::Unsupported, DiskMode::Wipe).is_some());
+    }
+
+    #[test]
+    fn test_validate_kernel_params() {
+        assert_eq!(validate_kernel_params("").unwrap(), Vec::<String>::new());
+        assert_eq!(
+            validate_kernel_params("  mitigations=off  amd_pstate=active ").unwrap(),
+            vec!["mitigations=off", "amd_pstate=active"]
+        );
+        assert!(validate_kernel_params(r#"foo="bar""#).is_err());
+        assert!(validate_kernel_params("x=${pkgs.foo}").is_err());
+    }
+
+    #[test]
+    fn test_validate_ssh_target() {
+        assert_eq!(validate_ssh_target("root@192.168.1.20"), None);
+        assert_eq!(validate_ssh_target("nixos@kraken.lan"), None);
+        assert_eq!(validate_ssh_target("root@[fe80::1]"), None);
+        assert!(validate_ssh_target("kraken").is_some());
+        assert!(validate_ssh_target("root@host;reboot").is_some());
+        assert!(validate_ssh_target("-oProxyCommand=x@host").is_some());
+    }
+
+    #[test]
+    fn test_validate_subvolumes() {
+        assert_eq!(
+            validate_subvolumes(" @=/  @data=/srv/data ").unwrap(),
+            vec![
+                ("@".to_string(), "/".to_string()),
+                ("@data".to_string(), "/srv/data".to_string())
+            ]
+        );
+        assert!(validate_subvolumes("").is_err());
+        assert!(validate_subvolumes("@home=/home").is_err());
+        assert!(validate_subvolumes("@=/ @home").is_err());
+        assert!(validate_subvolumes("@=/ @x=/a @x=/b").is_err());
+        assert!(validate_subvolumes("@=/ @a=/home @b=/home").is_err());
+        assert!(validate_subvolumes("@=/ @home=home").is_err());
+        assert!(validate_subvolumes(r#"@=/ @"x=/x"#).is_err());
+        assert!(validate_subvolumes("@=/ @x=/${foo}").is_err());
+    }
+
+    #[test]
+    fn test_swap_disk_size() {
+        assert_eq!(SwapMode::Zram.disk_size_gib(32), None);
+        assert_eq!(SwapMode::None.disk_size_gib(32), None);
+        // RAM plus sqrt(RAM) for the hibernation image
+        assert_eq!(SwapMode::Partition.disk_size_gib(32), Some(38));
+        assert_eq!(SwapMode::Partition.disk_size_gib(16), Some(20));
+        assert_eq!(SwapMode::Swapfile.disk_size_gib(8), Some(4));
+        assert_eq!(SwapMode::Swapfile.disk_size_gib(2), Some(2));
+        assert_eq!(SwapMode::Swapfile.disk_size_gib(64), Some(16));
+    }
+
+    #[test]
+    fn test_subvolume_layout_subvolumes() {
+        assert_eq!(SubvolumeLayout::Standard.subvolumes().len(), 4);
+        assert!(SubvolumeLayout::Snapshots
+            .subvolumes()
+            .contains(&("@snapshots", "/.snapshots")));
+        assert!(SubvolumeLayout::Flat.subvolumes().is_empty());
+        assert_eq!(SubvolumeLayout::Standard.ephemeral_root(), None);
+        // A tmpfs root has no @ subvolume, the snapshot layout rolls @ back
+        let tmpfs = SubvolumeLayout::EphemeralTmpfs;
+        assert_eq!(tmpfs.ephemeral_root(), Some("tmpfs"));
+        assert!(tmpfs.subvolumes().contains(&("@persist", "/persist")));
+        assert!(!tmpfs.subvolumes().iter().any(|(_, mountpoint)| *mountpoint == "/"));
+        assert!(SubvolumeLayout::EphemeralSnapshot.subvolumes().contains(&("@", "/")));
+    }
+
+    fn devbox_config() -> NewHostConfig {
+        NewHostConfig {
+            hostname: "devbox".to_string(),
+            cpu: CpuInfo {
+                vendor: crate::system::hardware::CpuVendor::AMD,
+                model_name: String::new(),
+            },
+            gpu: GpuInfo {
+                vendor: crate::system::hardware::GpuVendor::AMD,
+                model: None,
+            },
+            form_factor: FormFactor::Desktop,
+            disk: DiskInfo {
+                path: "/dev/nvme0n1".to_string(),
+                size: "1TB".to_string(),
+                size_bytes: 0,
+                model: None,
+                partitions: vec![],
+                free_bytes: 0,
+                by_id: None,
+            },
+            storage: StorageKind::Ssd,
+            boot_mode: BootMode::Uefi,
+            initrd_modules: vec![],
+            kernel: KernelFlavor::default(),
+            kernel_params: vec![],
+            modules: vec![],
+            theme: ThemePreset::default(),
+            filesystem: RootFilesystem::default(),
+            host_id: String::new(),
+            subvolume_layout: SubvolumeLayout::default(),
+            custom_subvolumes: Vec::new(),
+            swap: SwapMode::default(),
+            memory_gib: 0,
+            disk_options: DiskOptions::default(),
+        }
+    }
+
+    #[test]
+    fn test_toggle_module_conflicts() {
+        let mut config = devbox_config();
+        config.toggle_module(HostModule::Docker);
+        config.toggle_module(HostModule::Libvirt);
+        config.toggle_module(HostModule::Podman);
+        assert_eq!(config.modules, vec![HostModule::Libvirt, HostModule::Podman]);
+
+        config.toggle_module(HostModule::VirtualBox);
+        assert_eq!(config.modules, vec![HostModule::Podman, HostModule::VirtualBox]);
+
+        config.toggle_module(HostModule::Podman);
+        assert_eq!(config.modules, vec![HostModule::VirtualBox]);
+    }
+
+    #[test]
+    fn test_validate_swap() {
+        let mut config = devbox_config();
+        assert_eq!(validate_swap(SwapMode::Swapfile, &config), None);
+        // RAM size unknown
+        assert!(validate_swap(SwapMode::Partition, &config).is_some());
+
+        config.memory_gib = 32;
+        assert_eq!(validate_swap(SwapMode::Partition, &config), None);
+        assert_eq!(config.disk_swap(), None);
+        config.swap = SwapMode::Partition;
+        assert_eq!(config.disk_swap(), Some((SwapMode::Partition, 38)));
+
+        config.subvolume_layout = SubvolumeLayout::Custom;
+        config.custom_subvolumes = vec![
+            ("@".to_string(), "/".to_string()),
+            ("@swap".to_string(), "/swap".to_string()),
+        ];
+        assert!(validate_swap(SwapMode::Swapfile, &config).is_some());
+
+        config.boot_mode = BootMode::Bios;
+        assert!(validate_swap(SwapMode::Partition, &config).is_some());
+        config.filesystem = RootFilesystem::Zfs;
+        assert!(validate_swap(SwapMode::Swapfile, &config).is_some());
+        assert_eq!(validate_swap(SwapMode::None, &config), None);
+    }
+
+    #[test]
+    fn test_kernel_flavor_index() {
+        for (i, flavor) in KernelFlavor::ALL.iter().enumerate() {
+            assert_eq!(flavor.index(), i);
+        }
+        assert_eq!(KernelFlavor::default(), KernelFlavor::Latest);
+    }
+
+    #[test]
+    fn test_passphrase_prompt_submit() {
+        let mut prompt = PassphrasePrompt::new(true);
+        "correct horse".chars().for_each(|c| prompt.push(c));
+        // First Enter moves to the confirmation
+        assert_eq!(prompt.submit(), None);
+        assert!(prompt.on_confirm);
+        "correct h".chars().for_each(|c| prompt.push(c));
+        assert_eq!(prompt.submit(), None);
+        assert!(prompt.error.is_some());
+        "orse".chars().for_each(|c| prompt.push(c));
+        assert_eq!(prompt.submit().as_deref(), Some("correct horse"));
+
+        let mut unlock = PassphrasePrompt::new(false);
+        unlock.toggle_field();
+        assert!(!unlock.on_confirm);
+        assert_eq!(unlock.submit(), None);
+        unlock.push('x');
+        assert_eq!(unlock.submit().as_deref(), Some("x"));
+    }
+
+    fn failed_install(overview: Option<Box<InstallState>>) -> InstallState {
+        let mut steps = vec![
+            StepStatus::new("Partitioning disk"),
+            StepStatus::new(NIXOS_INSTALL_STEP),
+            StepStatus::new("Setting passwords"),
+        ];
+        steps[0].status = StepState::Complete;
+        steps[1].status = StepState::Failed;
+        steps[1].progress = Some(StepProgress { percent: 40, eta_secs: None });
+        let disk = match InstallState::new(Some("host".into()), Some("/dev/vda".into())) {
+            InstallState::EnterCredentials { disk, .. } => disk,
+            _ => unreachable!(),
+        };
+        InstallState::Running {
+            host: "host".to_string(),
+            disk,
+            credentials: InstallCredentials::default(),
+            overview,
+            step: 1,
+            steps,
+            output: VecDeque::from(["error: builder failed".to_string()]),
+        }
+    }
+
+    #[test]
+    fn test_retry_point() {
+        let overview = || Some(Box::new(InstallState::Preflight { checks: None }));
+        let failed = failed_install(overview());
+        assert!(failed.retry_point(false).is_some());
+        // Success, a remote install, or a failure before nixos-install: no retry
+        assert!(failed.retry_point(true).is_none());
+        assert!(failed_install(None).retry_point(false).is_none());
+        let mut early = failed_install(overview());
+        if let InstallState::Running { steps, .. } = &mut early {
+            steps[0].status = StepState::Failed;
+            steps[1].status = StepState::Pending;
+        }
+        assert!(early.retry_point(false).is_none());
+        assert!(InstallState::Preflight { checks: None }.retry_point(false).is_none());
+    }
+
+    #[test]
+    fn test_rerun_nixos_install() {
+        let mut state = failed_install(Some(Box::new(InstallState::Preflight { checks: None })));
+        if let InstallState::Running { step, .. } = &mut state {
+            *step = 2;
+        }
+        let output = VecDeque::from(["a".to_string(), "b".to_string()]);
+        state.rerun_nixos_install(output.clone());
+        let InstallState::Running {
+            step,
+            steps,
+            output: run_output,
+            ..
+        } = state
+        else {
+            panic!("rerun should stay running");
+        };
+        assert_eq!(step, 1);
+        assert_eq!(steps[0].status, StepState::Complete);
+        assert_eq!(steps[1].status, StepState::Running);
+        assert_eq!(steps[1].progress, None);
+        assert_eq!(steps[2].status, StepState::Pending);
+        assert_eq!(run_output, output);
+    }
+}
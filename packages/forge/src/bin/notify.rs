@@ -58,21 +58,28 @@ async fn main() -> Result<()> {
 
 /// Run the update check and send notification if needed
 async fn run_check() -> Result<bool> {
-    // Load existing state
+    // Load existing state and user settings
     let mut state = notify::state::NotifyState::load().unwrap_or_default();
+    let config = notify::config::NotifyConfig::load();
 
     // Check for all updates
-    let status = notify::check_all_updates().await?;
+    let status = notify::check_all_updates(&config, &state.last_run).await?;
 
     tracing::debug!(
-        "Check results: config={}, apps={}, flake={}",
+        "Check results: config={}, apps={}, flake={}, skipped={:?}, failed={:?}",
         status.config_updates.len(),
         status.app_updates,
-        status.flake_updates.len()
+        status.flake_updates.len(),
+        status.skipped,
+        status.failed
     );
 
+    // Record which checks ran so per-check intervals are honoured next time
+    state.mark_checked(&status);
+
     // Check if we should notify
     if !state.should_notify(&status) {
+        state.save()?;
         return Ok(false);
     }
 
@@ -2,15 +2,16 @@
 
 use anyhow::Result;
 use std::time::Duration;
-use tokio::sync::mpsc;
 
 use super::executor::run_capture;
 use super::runner::{spawn_with_error_handling, CommandRunner};
-use super::CommandMessage;
-use forge::notify::checks;
+use super::{CommandMessage, CommandSender};
+use crate::app::scheduler::TaskResult;
+use forge::notify::{checks, flake};
+use forge::notify::config::NotifyConfig;
 
 /// Start app backup
-pub async fn start_backup(tx: mpsc::Sender<CommandMessage>, force: bool) -> Result<()> {
+pub async fn start_backup(tx: CommandSender, force: bool) -> Result<()> {
     spawn_with_error_handling(tx, "App backup", "Backup", move |tx| async move {
         let runner = CommandRunner::new(&tx);
         let args: Vec<&str> = if force {
@@ -32,7 +33,7 @@ pub async fn start_backup(tx: mpsc::Sender<CommandMessage>, force: bool) -> Resu
 }
 
 /// Start app restore
-pub async fn start_restore(tx: mpsc::Sender<CommandMessage>, force: bool) -> Result<()> {
+pub async fn start_restore(tx: CommandSender, force: bool) -> Result<()> {
     spawn_with_error_handling(tx, "App restore", "Restore", move |tx| async move {
         let runner = CommandRunner::new(&tx);
         let args: Vec<&str> = if force {
@@ -53,12 +54,40 @@ pub async fn start_restore(tx: mpsc::Sender<CommandMessage>, force: bool) -> Res
     })
 }
 
+/// Start first-run setup of the backup repo (app-backup-init)
+pub async fn start_init(
+    tx: CommandSender,
+    repo: Option<String>,
+    backup: bool,
+) -> Result<()> {
+    spawn_with_error_handling(tx, "Backup repo setup", "Setup", move |tx| async move {
+        let runner = CommandRunner::new(&tx);
+        let mut args: Vec<&str> = Vec::new();
+        if let Some(repo) = &repo {
+            args.extend(["--repo", repo]);
+        }
+        if !backup {
+            args.push("--no-backup");
+        }
+        runner
+            .run_simple_operation(
+                "App Backup Repo Setup",
+                "app-backup-init",
+                &args,
+                "Backup repo ready",
+                "Backup repo setup failed",
+            )
+            .await?;
+        Ok(())
+    })
+}
+
 /// Start app status check
-pub async fn start_status(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
+pub async fn start_status(tx: CommandSender) -> Result<()> {
     tokio::spawn(async move {
         if let Err(e) = run_status(&tx).await {
             tracing::error!("Status check failed: {}", e);
-            let _ = tx.send(CommandMessage::Stderr(e.to_string())).await;
+            let _ = tx.stderr(e.to_string()).await;
         }
         let _ = tx.send(CommandMessage::Done { success: true }).await;
     });
@@ -68,32 +97,90 @@ pub async fn start_status(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
 /// Timeout for startup update checks (shorter than background checks)
 const STARTUP_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
 
-/// Start parallel background checks for all update types (non-blocking, silent on failure)
-pub async fn start_quick_update_check(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
-    tokio::spawn(async move {
-        // Run both checks in parallel with a short timeout for startup
-        let (nixos_result, apps_result) = tokio::join!(
-            checks::check_nixos_config_updates(Some(STARTUP_CHECK_TIMEOUT)),
-            checks::check_app_updates(Some(STARTUP_CHECK_TIMEOUT)),
-        );
+/// Timeout cap for the periodic checks while the TUI is open
+const REFRESH_CHECK_TIMEOUT: Duration = Duration::from_secs(30);
 
-        let commits = nixos_result.unwrap_or_default();
-        let nixos_config = !commits.is_empty();
-        let app_profiles = apps_result.unwrap_or(false);
+/// Outcome of the quick update checks
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdateCheck {
+    pub nixos_config: bool,
+    pub app_profiles: bool,
+    /// Pending commits for nixos-config (hash, message)
+    pub commits: Vec<(String, String)>,
+    /// Flake inputs behind upstream (e.g. "nixpkgs (42 commits behind)")
+    pub flake_inputs: Vec<String>,
+}
 
+/// Start parallel background checks for all update types (non-blocking, silent on failure)
+///
+/// Honours the enable switches and timeouts from the notify settings file;
+/// configured timeouts are capped at `STARTUP_CHECK_TIMEOUT`.
+pub async fn start_quick_update_check(tx: CommandSender) -> Result<()> {
+    tokio::spawn(async move {
+        let check = quick_update_check(STARTUP_CHECK_TIMEOUT).await;
         // Always send message to clear startup_check_running flag
         let _ = tx
             .send(CommandMessage::UpdatesAvailable {
-                nixos_config,
-                app_profiles,
-                commits,
+                nixos_config: check.nixos_config,
+                app_profiles: check.app_profiles,
+                commits: check.commits,
+                flake_inputs: check.flake_inputs,
             })
             .await;
     });
     Ok(())
 }
 
-async fn run_status(tx: &mpsc::Sender<CommandMessage>) -> Result<()> {
+/// Repeat the update checks for the background scheduler
+pub async fn start_update_refresh(tx: CommandSender) -> Result<()> {
+    tokio::spawn(async move {
+        let check = quick_update_check(REFRESH_CHECK_TIMEOUT).await;
+        let _ = tx.send(CommandMessage::TaskFinished(TaskResult::Updates(check))).await;
+    });
+    Ok(())
+}
+
+/// Run all checks in parallel, each with its configured timeout capped at `max_timeout`
+async fn quick_update_check(max_timeout: Duration) -> UpdateCheck {
+    let config = NotifyConfig::load();
+    let (nixos_result, apps_result, flake_result) = tokio::join!(
+        async {
+            if !config.config.enabled {
+                return Ok(vec![]);
+            }
+            let timeout = config.config.timeout(max_timeout).min(max_timeout);
+            checks::check_nixos_config_updates(Some(timeout)).await
+        },
+        async {
+            if !config.apps.enabled {
+                return Ok(false);
+            }
+            let timeout = config.apps.timeout(max_timeout).min(max_timeout);
+            checks::check_app_updates(Some(timeout)).await
+        },
+        async {
+            if !config.flake.enabled {
+                return Ok(vec![]);
+            }
+            let timeout = config.flake.timeout(max_timeout).min(max_timeout);
+            flake::check_flake_updates(Some(timeout)).await
+        },
+    );
+
+    let commits = nixos_result.unwrap_or_default();
+    UpdateCheck {
+        nixos_config: !commits.is_empty(),
+        app_profiles: apps_result.unwrap_or(false),
+        commits,
+        flake_inputs: flake_result
+            .unwrap_or_default()
+            .iter()
+            .map(|u| u.describe())
+            .collect(),
+    }
+}
+
+async fn run_status(tx: &CommandSender) -> Result<()> {
     let runner = CommandRunner::new(tx);
     runner.header("App Profile Status").await;
 
@@ -102,6 +189,9 @@ async fn run_status(tx: &mpsc::Sender<CommandMessage>) -> Result<()> {
     if !local_repo.join(".git").exists() {
         runner.out("  Local repository not found.").await;
         runner.out("  Run 'forge apps restore' to clone.").await;
+        runner
+            .out("  No backup repo yet? Run 'forge apps init' to create it.")
+            .await;
         runner.footer().await;
         return Ok(());
     }
@@ -0,0 +1,149 @@
+//! Bisecting a flake input between a good and a bad revision (`forge bisect`)
+//!
+//! The commit range comes from the GitHub compare API. Each candidate is
+//! rebuilt with `--override-input <input> github:<owner>/<repo>/<rev>`, so
+//! flake.lock is never touched; the user then judges the result.
+
+use anyhow::Result;
+
+use super::executor::{get_output, run_command_transformed};
+use super::runner::{spawn_with_error_handling, CommandRunner};
+use super::update::flake::{github_source, GitHubCommit, GitHubCompareResponse};
+use super::update::transform_nix_output;
+use super::{CommandMessage, CommandSender};
+use crate::system::bisect::{BisectCommit, BisectMode, Bisection};
+
+/// Compare API page size (the maximum GitHub allows)
+const COMMITS_PER_PAGE: usize = 100;
+
+/// Larger ranges take too many API calls; narrowing them by date is faster
+const MAX_BISECT_COMMITS: usize = 5000;
+
+/// Load the commits between `good` and `bad` for `input`
+pub async fn start_bisect_fetch(
+    tx: CommandSender,
+    input: String,
+    good: String,
+    bad: String,
+) -> Result<()> {
+    tokio::spawn(async move {
+        let result = fetch_bisection(&input, &good, &bad)
+            .await
+            .map_err(|e| e.to_string());
+        let _ = tx.send(CommandMessage::BisectRange(result)).await;
+    });
+    Ok(())
+}
+
+async fn fetch_bisection(input: &str, good: &str, bad: &str) -> Result<Bisection> {
+    let (owner, repo) = github_source(&crate::constants::nixos_config_dir(), input)?;
+    let client = reqwest::Client::builder()
+        .user_agent("forge-nixos-tool")
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?;
+    let token = std::env::var("GITHUB_TOKEN").ok().filter(|t| !t.is_empty());
+
+    let mut commits = Vec::new();
+    for page in 1.. {
+        let url = format!(
+            "https://api.github.com/repos/{}/{}/compare/{}...{}?per_page={}&page={}",
+            owner, repo, good, bad, COMMITS_PER_PAGE, page
+        );
+        let mut request = client.get(&url);
+        if let Some(token) = &token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            anyhow::bail!("GitHub API returned {} for {}...{}", response.status(), good, bad);
+        }
+        let compare: GitHubCompareResponse = response.json().await?;
+
+        if commits.is_empty() {
+            if compare.total_commits == 0 {
+                anyhow::bail!("{} is not newer than {}", bad, good);
+            }
+            if compare.total_commits > MAX_BISECT_COMMITS {
+                anyhow::bail!(
+                    "{} commits between {} and {} (at most {}); pick a closer good revision",
+                    compare.total_commits,
+                    good,
+                    bad,
+                    MAX_BISECT_COMMITS
+                );
+            }
+            commits.push(to_bisect_commit(&compare.base_commit));
+        }
+        let received = compare.commits.len();
+        commits.extend(compare.commits.iter().map(to_bisect_commit));
+        if received < COMMITS_PER_PAGE || commits.len() > compare.total_commits {
+            break;
+        }
+    }
+
+    Ok(Bisection::new(&format!("{}/{}", owner, repo), commits))
+}
+
+fn to_bisect_commit(commit: &GitHubCommit) -> BisectCommit {
+    BisectCommit {
+        sha: commit.sha.clone(),
+        message: commit.commit.message.lines().next().unwrap_or("").to_string(),
+    }
+}
+
+/// Rebuild this host with `input` pinned to one commit of `repo`
+pub async fn start_bisect_build(
+    tx: CommandSender,
+    input: String,
+    repo: String,
+    commit: BisectCommit,
+    mode: BisectMode,
+) -> Result<()> {
+    spawn_with_error_handling(tx, "Bisect rebuild", "bisect", move |tx| async move {
+        run_bisect_build(&tx, &input, &repo, &commit, mode).await
+    })
+}
+
+async fn run_bisect_build(
+    tx: &CommandSender,
+    input: &str,
+    repo: &str,
+    commit: &BisectCommit,
+    mode: BisectMode,
+) -> Result<()> {
+    let runner = CommandRunner::new(tx);
+    runner
+        .header(&format!("Testing {} {}: {}", input, commit.short_sha(), commit.message))
+        .await;
+
+    let flake_dir = crate::constants::nixos_config_dir();
+    let hostname = match get_output("hostname", &[]).await {
+        Ok(h) if !h.is_empty() => h,
+        _ => "localhost".to_string(),
+    };
+    let flake_ref = format!("{}#{}", flake_dir.display(), hostname);
+    let source = format!("github:{}/{}", repo, commit.sha);
+    let args = [
+        "nixos-rebuild",
+        mode.action(),
+        "--flake",
+        &flake_ref,
+        "--override-input",
+        input,
+        &source,
+    ];
+    let success = if mode.needs_root() {
+        run_command_transformed(tx, "sudo", &args, transform_nix_output).await?
+    } else {
+        run_command_transformed(tx, args[0], &args[1..], transform_nix_output).await?
+    };
+
+    runner.out("").await;
+    if success {
+        runner.out("Check for the regression, then mark this commit").await;
+    } else {
+        runner.err("Rebuild failed - mark bad if this is the regression, else skip").await;
+    }
+    runner.footer().await;
+    runner.done(success).await
+}
@@ -0,0 +1,70 @@
+//! First-boot bootstrap: key restore followed by app profile restore
+//!
+//! Chains `forge keys restore` and `forge apps restore` so a freshly installed
+//! machine only needs one guided run. Launched on first login by
+//! home/forge-bootstrap.nix until the stamp file exists.
+
+use anyhow::{Context, Result};
+
+use super::keys::{run_restore, RestoreOutcome};
+use super::runner::{spawn_with_error_handling, CommandRunner};
+use super::{steps, CommandSender};
+use crate::constants::bootstrap_done_path;
+
+/// Start the bootstrap sequence
+pub async fn start_bootstrap(tx: CommandSender) -> Result<()> {
+    spawn_with_error_handling(tx, "Bootstrap", steps::KEYS, |tx| async move {
+        let runner = CommandRunner::new(&tx);
+
+        // Keys first: the app profile archives are only useful once SSH and
+        // age keys are back in place
+        runner.header("Key Restore").await;
+        let outcome = run_restore(&tx, &["--pull"]).await?;
+        if outcome != RestoreOutcome::Restored {
+            let error = if outcome == RestoreOutcome::WrongPassphrase {
+                "Wrong passphrase for the keys archive; retry with 'forge keys restore'"
+            } else {
+                "keys-restore exited with an error"
+            };
+            runner.step_failed(steps::KEYS, error, "Key restore").await?;
+            runner.footer().await;
+            runner.done(false).await?;
+            return Ok(());
+        }
+        runner.step_complete(steps::KEYS).await?;
+
+        runner.header("App Profile Restore").await;
+        if !runner.run("app-restore", &["--pull"]).await? {
+            runner
+                .step_failed(
+                    steps::APP_PROFILES,
+                    "app-restore exited with an error",
+                    "App restore",
+                )
+                .await?;
+            runner.footer().await;
+            runner.done(false).await?;
+            return Ok(());
+        }
+        runner.step_complete(steps::APP_PROFILES).await?;
+
+        mark_bootstrap_done()?;
+        runner.out("").await;
+        runner.out("  Keys and app profiles restored").await;
+        runner.footer().await;
+        runner.done(true).await?;
+        Ok(())
+    })
+}
+
+/// Write the stamp that stops the bootstrap from being offered again
+pub fn mark_bootstrap_done() -> Result<()> {
+    let path = bootstrap_done_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let stamp = chrono::Local::now().to_rfc3339();
+    std::fs::write(&path, format!("{}\n", stamp))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
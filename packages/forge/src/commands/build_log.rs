@@ -0,0 +1,91 @@
+//! Saving `nix log` of failed derivations after a failed build
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::executor::run_capture;
+use super::{CommandMessage, CommandSender};
+use crate::constants::{build_logs_dir, INSTALL_MOUNT_POINT};
+use crate::system::build_log::{
+    log_file_name, tail_lines, SavedBuildLog, KEEP_BUILD_LOG_RUNS, MAX_VIEW_LINES,
+};
+
+/// Save the logs of `drvs` into a new run directory in the background
+pub async fn start_build_log_capture(
+    tx: CommandSender,
+    drvs: Vec<String>,
+) -> Result<()> {
+    tokio::spawn(async move {
+        let saved = match save_build_logs(&drvs).await {
+            Ok(saved) => saved,
+            Err(e) => {
+                tracing::warn!("Failed to save build logs: {}", e);
+                Vec::new()
+            }
+        };
+        let _ = tx.send(CommandMessage::BuildLogsSaved(saved)).await;
+    });
+    Ok(())
+}
+
+async fn save_build_logs(drvs: &[String]) -> Result<Vec<SavedBuildLog>> {
+    let root = build_logs_dir();
+    let run_dir = root.join(chrono::Local::now().format("%Y%m%d-%H%M%S").to_string());
+    std::fs::create_dir_all(&run_dir)
+        .with_context(|| format!("Failed to create {}", run_dir.display()))?;
+
+    let mut saved = Vec::new();
+    for drv in drvs {
+        let text = match nix_log(drv).await {
+            Ok(log) => log,
+            Err(e) => format!("nix log {} failed: {}", drv, e),
+        };
+        let path = run_dir.join(log_file_name(drv));
+        std::fs::write(&path, &text)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        saved.push(SavedBuildLog {
+            drv: drv.clone(),
+            path,
+            lines: tail_lines(&text, MAX_VIEW_LINES),
+        });
+    }
+
+    prune_runs(&root, KEEP_BUILD_LOG_RUNS);
+    Ok(saved)
+}
+
+/// Build log of `drv`, also looking in the target store during an install
+async fn nix_log(drv: &str) -> Result<String> {
+    let (ok, stdout, stderr) = run_capture("nix", &["log", drv]).await?;
+    if ok {
+        return Ok(stdout);
+    }
+    // nixos-install builds into the store under /mnt
+    if Path::new(INSTALL_MOUNT_POINT).join("nix/store").is_dir() {
+        let (ok, stdout, _) =
+            run_capture("nix", &["log", "--store", INSTALL_MOUNT_POINT, drv]).await?;
+        if ok {
+            return Ok(stdout);
+        }
+    }
+    anyhow::bail!("{}", stderr.trim())
+}
+
+/// Remove all but the newest `keep` run directories
+fn prune_runs(root: &Path, keep: usize) {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return;
+    };
+    let mut runs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_dir())
+        .collect();
+    // Run directories are named by timestamp, so they sort oldest first
+    runs.sort();
+    for old in &runs[..runs.len().saturating_sub(keep)] {
+        if let Err(e) = std::fs::remove_dir_all(old) {
+            tracing::warn!("Failed to remove {}: {}", old.display(), e);
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! Channels from running commands to the App
+//!
+//! A command's raw output lines and its events (step transitions, progress,
+//! warnings, Done) travel on separate channels. The main loop handles events
+//! ahead of output, so a build printing thousands of lines can't hold back a
+//! StepFailed or Done queued behind them. Output lines already queued when an
+//! event arrives are taken first, so the log still reads in order.
+//!
+//! Output never makes a command wait: the output queue is bounded, and when
+//! the UI falls behind, the oldest queued stdout line makes room for the new
+//! one (the oldest stderr line only when no stdout is left, since errors
+//! matter more). The receiver reports each gap as `OutputLine::Skipped`.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc::{self, error::SendError};
+use tokio::sync::Notify;
+
+use super::CommandMessage;
+use crate::constants::{COMMAND_CHANNEL_SIZE, OUTPUT_QUEUE_SIZE};
+
+/// A line a command printed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputLine {
+    Stdout(String),
+    Stderr(String),
+    /// Lines dropped here because the UI fell behind
+    Skipped(usize),
+}
+
+impl OutputLine {
+    pub fn text(&self) -> String {
+        match self {
+            OutputLine::Stdout(line) | OutputLine::Stderr(line) => line.clone(),
+            OutputLine::Skipped(count) => format!(
+                "… {} line{} of output skipped (printed faster than shown)",
+                count,
+                if *count == 1 { "" } else { "s" }
+            ),
+        }
+    }
+}
+
+/// The output queue shared by all senders and the receiver
+#[derive(Debug)]
+struct OutputQueue {
+    lines: Mutex<VecDeque<OutputLine>>,
+    capacity: usize,
+    /// Lines dropped since the receiver last reported a gap
+    dropped: AtomicUsize,
+    senders: AtomicUsize,
+    receiver_alive: AtomicBool,
+    ready: Notify,
+}
+
+impl OutputQueue {
+    fn push(&self, line: OutputLine) -> Result<(), SendError<OutputLine>> {
+        if !self.receiver_alive.load(Ordering::Acquire) {
+            return Err(SendError(line));
+        }
+        {
+            let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+            if lines.len() >= self.capacity {
+                let oldest = lines
+                    .iter()
+                    .position(|l| matches!(l, OutputLine::Stdout(_)))
+                    .unwrap_or(0);
+                lines.remove(oldest);
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            lines.push_back(line);
+        }
+        self.ready.notify_one();
+        Ok(())
+    }
+
+    fn pop(&self) -> Option<OutputLine> {
+        let dropped = self.dropped.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            return Some(OutputLine::Skipped(dropped));
+        }
+        self.lines.lock().unwrap_or_else(|e| e.into_inner()).pop_front()
+    }
+}
+
+/// Sending half of the output queue
+#[derive(Debug)]
+struct OutputSender(Arc<OutputQueue>);
+
+impl Clone for OutputSender {
+    fn clone(&self) -> Self {
+        self.0.senders.fetch_add(1, Ordering::AcqRel);
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl Drop for OutputSender {
+    fn drop(&mut self) {
+        if self.0.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            // Wake the receiver so it sees the queue is closed
+            self.0.ready.notify_one();
+        }
+    }
+}
+
+/// Receiving half of the output queue
+#[derive(Debug)]
+pub struct OutputReceiver(Arc<OutputQueue>);
+
+impl OutputReceiver {
+    /// The next line; None once the queue is empty and every sender is gone
+    pub async fn recv(&mut self) -> Option<OutputLine> {
+        loop {
+            if let Some(line) = self.0.pop() {
+                return Some(line);
+            }
+            if self.0.senders.load(Ordering::Acquire) == 0 {
+                return None;
+            }
+            // notify_one keeps a permit, so a push between pop and here isn't missed
+            self.0.ready.notified().await;
+        }
+    }
+
+    /// The next line if one is queued
+    pub fn try_recv(&mut self) -> Option<OutputLine> {
+        self.0.pop()
+    }
+}
+
+impl Drop for OutputReceiver {
+    fn drop(&mut self) {
+        self.0.receiver_alive.store(false, Ordering::Release);
+    }
+}
+
+/// Sending half, cloned into every command task
+#[derive(Debug, Clone)]
+pub struct CommandSender {
+    output: OutputSender,
+    events: mpsc::Sender<CommandMessage>,
+}
+
+impl CommandSender {
+    /// Send an event
+    pub async fn send(&self, msg: CommandMessage) -> Result<(), SendError<CommandMessage>> {
+        self.events.send(msg).await
+    }
+
+    /// Send an event without waiting, for progress from synchronous output
+    /// callbacks; false if it was dropped because the event channel is full
+    pub fn try_send(&self, msg: CommandMessage) -> bool {
+        self.events.try_send(msg).is_ok()
+    }
+
+    /// Send a line of standard output (never waits, see the module docs)
+    pub async fn stdout(&self, line: impl Into<String>) -> Result<(), SendError<OutputLine>> {
+        self.output.0.push(OutputLine::Stdout(line.into()))
+    }
+
+    /// Send a line of standard error (never waits, see the module docs)
+    pub async fn stderr(&self, line: impl Into<String>) -> Result<(), SendError<OutputLine>> {
+        self.output.0.push(OutputLine::Stderr(line.into()))
+    }
+}
+
+/// What a running command sent next, see `CommandReceiver::recv`
+#[derive(Debug)]
+pub struct Received {
+    /// Output lines, everything queued at once
+    pub lines: Vec<OutputLine>,
+    /// The event sent after those lines, if one was waiting
+    pub event: Option<CommandMessage>,
+}
+
+/// Receiving half, owned by the main loop (or the unattended printer)
+pub struct CommandReceiver {
+    pub output: OutputReceiver,
+    pub events: mpsc::Receiver<CommandMessage>,
+}
+
+impl CommandReceiver {
+    /// The next event, or the next output when no event is waiting;
+    /// None once both channels are closed
+    pub async fn recv(&mut self) -> Option<Received> {
+        tokio::select! {
+            biased;
+            Some(msg) = self.events.recv() => {
+                // Lines printed before the event still go above it in the log
+                Some(Received {
+                    lines: self.queued_output().collect(),
+                    event: Some(msg),
+                })
+            }
+            Some(line) = self.output.recv() => {
+                let mut lines = vec![line];
+                lines.extend(self.queued_output());
+                Some(Received { lines, event: None })
+            }
+            else => None,
+        }
+    }
+
+    /// Output lines already queued, without waiting for more
+    ///
+    /// Capped at one queue's worth so a command that prints faster than
+    /// the lines are handled can't keep the caller here.
+    pub fn queued_output(&mut self) -> impl Iterator<Item = OutputLine> + '_ {
+        std::iter::from_fn(|| self.output.try_recv()).take(OUTPUT_QUEUE_SIZE)
+    }
+}
+
+/// Create the output and event channels
+pub fn command_channel() -> (CommandSender, CommandReceiver) {
+    channel_with_capacity(OUTPUT_QUEUE_SIZE)
+}
+
+fn channel_with_capacity(output_capacity: usize) -> (CommandSender, CommandReceiver) {
+    let queue = Arc::new(OutputQueue {
+        lines: Mutex::new(VecDeque::with_capacity(output_capacity)),
+        capacity: output_capacity,
+        dropped: AtomicUsize::new(0),
+        senders: AtomicUsize::new(1),
+        receiver_alive: AtomicBool::new(true),
+        ready: Notify::new(),
+    });
+    let (events_tx, events_rx) = mpsc::channel(COMMAND_CHANNEL_SIZE);
+    (
+        CommandSender {
+            output: OutputSender(Arc::clone(&queue)),
+            events: events_tx,
+        },
+        CommandReceiver {
+            output: OutputReceiver(queue),
+            events: events_rx,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_events_are_not_queued_behind_output() {
+        let (tx, mut rx) = command_channel();
+        for i in 0..10 {
+            tx.stdout(format!("line {}", i)).await.unwrap();
+        }
+        tx.send(CommandMessage::Done { success: true }).await.unwrap();
+
+        // Done is available right away, and the lines before it are all queued
+        assert!(matches!(
+            rx.events.try_recv(),
+            Ok(CommandMessage::Done { success: true })
+        ));
+        let lines: Vec<_> = rx.queued_output().collect();
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[0].text(), "line 0");
+        assert_eq!(rx.queued_output().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stdout_and_stderr() {
+        let (tx, mut rx) = command_channel();
+        tx.stdout("out").await.unwrap();
+        tx.stderr("err").await.unwrap();
+        assert_eq!(rx.output.recv().await, Some(OutputLine::Stdout("out".to_string())));
+        assert_eq!(rx.output.recv().await, Some(OutputLine::Stderr("err".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_drops_oldest_stdout() {
+        let (tx, mut rx) = channel_with_capacity(3);
+        tx.stdout("a").await.unwrap();
+        tx.stderr("error").await.unwrap();
+        tx.stdout("b").await.unwrap();
+        // Full: "a" goes, then "b", the stderr line stays
+        tx.stdout("c").await.unwrap();
+        tx.stdout("d").await.unwrap();
+
+        let lines: Vec<_> = rx.queued_output().collect();
+        assert_eq!(
+            lines,
+            vec![
+                OutputLine::Skipped(2),
+                OutputLine::Stderr("error".to_string()),
+                OutputLine::Stdout("c".to_string()),
+                OutputLine::Stdout("d".to_string()),
+            ]
+        );
+        assert_eq!(lines[0].text(), "… 2 lines of output skipped (printed faster than shown)");
+    }
+
+    #[tokio::test]
+    async fn test_overflow_counts_dropped_lines() {
+        let (tx, mut rx) = channel_with_capacity(4);
+        for i in 0..10 {
+            tx.stdout(format!("line {}", i)).await.unwrap();
+        }
+        assert_eq!(rx.output.0.dropped.load(Ordering::Relaxed), 6);
+
+        // The gap is reported once, then the newest lines follow in order
+        assert_eq!(rx.output.try_recv(), Some(OutputLine::Skipped(6)));
+        assert_eq!(rx.output.0.dropped.load(Ordering::Relaxed), 0);
+        let lines: Vec<_> = rx.queued_output().map(|l| l.text()).collect();
+        assert_eq!(lines, vec!["line 6", "line 7", "line 8", "line 9"]);
+    }
+
+    #[tokio::test]
+    async fn test_recv_routes_output_and_events() {
+        let (tx, mut rx) = command_channel();
+        tx.stdout("out").await.unwrap();
+        tx.stderr("err").await.unwrap();
+        let received = rx.recv().await.unwrap();
+        assert_eq!(
+            received.lines,
+            vec![OutputLine::Stdout("out".to_string()), OutputLine::Stderr("err".to_string())]
+        );
+        assert!(received.event.is_none());
+
+        tx.send(CommandMessage::Warning("careful".to_string())).await.unwrap();
+        let received = rx.recv().await.unwrap();
+        assert!(received.lines.is_empty());
+        assert!(matches!(received.event, Some(CommandMessage::Warning(ref m)) if m == "careful"));
+
+        drop(tx);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_recv_keeps_step_complete_before_done() {
+        let (tx, mut rx) = command_channel();
+        // Everything is queued before the receiver looks, the worst case for the split
+        tx.stdout("building").await.unwrap();
+        tx.send(CommandMessage::StepComplete { step: "Build".to_string() }).await.unwrap();
+        tx.stdout("switching").await.unwrap();
+        tx.send(CommandMessage::Done { success: true }).await.unwrap();
+        drop(tx);
+
+        let mut log = Vec::new();
+        while let Some(received) = rx.recv().await {
+            log.extend(received.lines.iter().map(OutputLine::text));
+            match received.event {
+                Some(CommandMessage::StepComplete { step }) => {
+                    log.push(format!("complete {}", step))
+                }
+                Some(CommandMessage::Done { success }) => log.push(format!("done {}", success)),
+                Some(other) => panic!("unexpected event {:?}", other),
+                None => {}
+            }
+        }
+        // Lines already queued go above the next event (module docs), events keep their order
+        assert_eq!(log, vec!["building", "switching", "complete Build", "done true"]);
+    }
+
+    #[tokio::test]
+    async fn test_output_closes_with_the_last_sender() {
+        let (tx, mut rx) = command_channel();
+        let task_tx = tx.clone();
+        tokio::spawn(async move {
+            task_tx.stdout("from task").await.unwrap();
+        });
+        drop(tx);
+        assert_eq!(rx.output.recv().await, Some(OutputLine::Stdout("from task".to_string())));
+        assert_eq!(rx.output.recv().await, None);
+
+        let (tx, rx) = command_channel();
+        drop(rx);
+        assert!(tx.stdout("nobody listening").await.is_err());
+    }
+}
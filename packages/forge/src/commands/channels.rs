@@ -0,0 +1,79 @@
+//! Update channel screen (load hosts' channels, move a host between them)
+//!
+//! A move edits flake.nix, which is formatted and reviewed like create-host's
+//! edits; `nix flake lock` then locks stable inputs that were just added.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::executor::run_capture;
+use super::format::format_nix_files;
+use super::review::review_edit;
+use super::runner::CommandRunner;
+use super::{CommandMessage, CommandSender};
+use crate::constants::{nixos_config_dir, FLAKE_NIX};
+use crate::system::channels::{host_channels, set_host_channel, stable_release, Channel};
+
+/// Move a host to a channel (if asked) in the background, then send the hosts reloaded
+pub async fn start_channels_op(tx: CommandSender, change: Option<(String, Channel)>) -> Result<()> {
+    tokio::spawn(async move {
+        let repo = nixos_config_dir();
+        let message = match change {
+            Some((host, channel)) => {
+                Some(move_host(&tx, &repo, &host, channel).await.map_err(|e| {
+                    tracing::error!("Changing {}'s channel failed: {}", host, e);
+                    format!("{:#}", e)
+                }))
+            }
+            None => None,
+        };
+
+        let flake = std::fs::read_to_string(repo.join(FLAKE_NIX));
+        let stable = flake.as_deref().ok().and_then(stable_release);
+        let hosts = flake
+            .map(|flake| host_channels(&flake))
+            .map_err(|e| format!("Failed to read {}: {}", FLAKE_NIX, e));
+        let _ = tx.send(CommandMessage::Channels { hosts, stable, message }).await;
+    });
+    Ok(())
+}
+
+async fn move_host(
+    tx: &CommandSender,
+    repo: &Path,
+    host: &str,
+    channel: Channel,
+) -> Result<String> {
+    let path = repo.join(FLAKE_NIX);
+    let original = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let edited = set_host_channel(&original, host, channel)?;
+    let adds_inputs = stable_release(&original).is_none() && stable_release(&edited).is_some();
+    std::fs::write(&path, &edited).with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let runner = CommandRunner::new(tx);
+    format_nix_files(&runner, repo, &[FLAKE_NIX]).await;
+    let written = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let reason = format!("move host '{}' to the {} channel", host, channel);
+    if !review_edit(&runner, FLAKE_NIX, &reason, &original, &written).await? {
+        std::fs::write(&path, &original)
+            .with_context(|| format!("Failed to restore {}", path.display()))?;
+        anyhow::bail!("The flake.nix change was rejected; nothing changed");
+    }
+
+    // New inputs need lock entries before anything can evaluate the flake
+    if adds_inputs {
+        let repo_str = repo.to_string_lossy();
+        let (ok, _, stderr) = run_capture("nix", &["flake", "lock", &repo_str]).await?;
+        if !ok {
+            tracing::warn!("nix flake lock failed: {}", stderr.trim());
+            return Ok(format!(
+                "{} follows {} - lock the new inputs with `nix flake lock` (it failed here)",
+                host,
+                channel.input()
+            ));
+        }
+    }
+    Ok(format!("{} follows {} - commit and rebuild to apply", host, channel.input()))
+}
@@ -0,0 +1,115 @@
+//! Garbage collection runs (`forge gc run` and `forge update --gc`)
+//!
+//! `sudo nix-collect-garbage --delete-older-than <N>d` removes the older
+//! generations of every profile, the system's included, and the store paths
+//! nothing refers to anymore; `nix store optimise` then hard-links identical
+//! files. The retention is gc.json's `delete_older_than` for this host (what
+//! `forge gc` edits) unless the command gives one.
+
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+use super::executor::{get_output, run_command_transformed};
+use super::optimise::optimise_store;
+use super::runner::{spawn_with_error_handling, CommandRunner};
+use super::{CommandMessage, CommandSender};
+use crate::constants::nixos_config_dir;
+use crate::system::gc::{load_gc_config, parse_freed_bytes, GcSettings};
+use crate::system::optimise::format_bytes;
+
+/// Collect garbage older than `older_than` days (None: gc.json's setting), then optimise
+pub async fn start_gc_run(tx: CommandSender, older_than: Option<u32>) -> Result<()> {
+    spawn_with_error_handling(tx, "Garbage collection", "gc", move |tx| async move {
+        run_gc(&tx, older_than).await
+    })
+}
+
+async fn run_gc(tx: &CommandSender, older_than: Option<u32>) -> Result<()> {
+    let runner = CommandRunner::new(tx);
+    let retention = retention(older_than).await;
+    runner.header(&format!("Collecting garbage older than {}", retention)).await;
+
+    let Some(freed) = collect_garbage(&runner, &retention).await? else {
+        runner.out("").await;
+        runner.err("nix-collect-garbage failed - see output above").await;
+        runner.footer().await;
+        return runner.done(false).await;
+    };
+    tx.send(CommandMessage::GarbageCollected { freed }).await?;
+
+    runner.out("").await;
+    let (optimised, progress) = optimise_store(&runner).await?;
+    runner.out("").await;
+    if !optimised {
+        runner.err("nix store optimise failed - see output above").await;
+    }
+    runner.out(&reclaimed_summary(freed, optimised.then_some(progress.bytes_saved))).await;
+    runner.footer().await;
+    runner.done(optimised).await
+}
+
+/// `--delete-older-than` value: `older_than` days, or gc.json's for this host
+pub async fn retention(older_than: Option<u32>) -> String {
+    if let Some(days) = older_than {
+        return format!("{}d", days);
+    }
+    let host = get_output("hostname", &[]).await.ok().filter(|h| !h.is_empty());
+    match load_gc_config(&nixos_config_dir()) {
+        Ok(config) => config.resolve(host.as_deref()).delete_older_than,
+        Err(e) => {
+            tracing::warn!("Using the default GC retention: {:#}", e);
+            GcSettings::default().delete_older_than
+        }
+    }
+}
+
+/// Run nix-collect-garbage with its output streamed; the bytes it freed, None if it failed
+pub async fn collect_garbage(runner: &CommandRunner<'_>, retention: &str) -> Result<Option<u64>> {
+    let freed = Arc::new(Mutex::new(None));
+    let observed = Arc::clone(&freed);
+    let success = run_command_transformed(
+        runner.tx(),
+        "sudo",
+        &["nix-collect-garbage", "--delete-older-than", retention],
+        move |line| {
+            if let Some(bytes) = parse_freed_bytes(line) {
+                if let Ok(mut freed) = observed.lock() {
+                    *freed = Some(bytes);
+                }
+            }
+            Some(line.to_string())
+        },
+    )
+    .await?;
+    if !success {
+        return Ok(None);
+    }
+    let freed = freed.lock().ok().and_then(|freed| *freed).unwrap_or(0);
+    Ok(Some(freed))
+}
+
+/// "Reclaimed 1.2 GiB (1.0 GiB collected, 204.8 MiB hard-linked)"
+pub fn reclaimed_summary(freed: u64, saved: Option<u64>) -> String {
+    match saved {
+        Some(saved) => format!(
+            "Reclaimed {} ({} collected, {} hard-linked)",
+            format_bytes(freed + saved),
+            format_bytes(freed),
+            format_bytes(saved)
+        ),
+        None => format!(
+            "Reclaimed {} (collected; the store was not optimised)",
+            format_bytes(freed)
+        ),
+    }
+}
+
+/// Disk line of an update summary: what the collection and the optimise
+/// reclaimed, or only the optimise's saving when there was no collection
+pub fn disk_summary(freed: Option<u64>, saved: Option<u64>) -> Option<String> {
+    match (freed, saved) {
+        (Some(freed), saved) => Some(reclaimed_summary(freed, saved)),
+        (None, Some(saved)) => Some(format!("Saved {} by hard-linking", format_bytes(saved))),
+        (None, None) => None,
+    }
+}
@@ -2,23 +2,29 @@
 
 use anyhow::{Context, Result};
 use std::fs;
-use std::path::Path;
-use tokio::sync::mpsc;
-
-use super::errors::{ErrorContext, ParsedError};
-use super::executor::run_command;
-use super::CommandMessage;
-use crate::app::{AppMode, CreateHostState, NewHostConfig};
+use std::path::{Path, PathBuf};
+
+use super::errors::{nix_error_message, ErrorContext, ParsedError};
+use super::executor::{run_capture, run_command};
+use super::format::format_nix_files;
+use super::lint;
+use super::review::review_edit;
+use super::runner::CommandRunner;
+use super::{CommandMessage, CommandSender};
+use crate::app::{AppMode, CreateHostState, NewHostConfig, RootFilesystem};
+use crate::system::disk::identity::{disk_identity, DiskIdentity};
+use crate::system::display::detect_monitors;
 use crate::system::hardware::{FormFactor, GpuVendor};
+use crate::system::offline;
 use crate::templates;
 
 const REPO_URL: &str = "https://github.com/DigitalPals/nixos-config.git";
 
 /// Start the create host process
-pub async fn start_create_host(tx: mpsc::Sender<CommandMessage>, mode: AppMode) -> Result<()> {
+pub async fn start_create_host(tx: CommandSender, mode: AppMode) -> Result<()> {
     // Extract config from mode
-    let config = match mode {
-        AppMode::CreateHost(CreateHostState::Generating { config, .. }) => config,
+    let (config, lint) = match mode {
+        AppMode::CreateHost(CreateHostState::Generating { config, lint, .. }) => (config, lint),
         _ => {
             let _ = tx
                 .send(CommandMessage::StepFailed {
@@ -37,7 +43,7 @@ pub async fn start_create_host(tx: mpsc::Sender<CommandMessage>, mode: AppMode)
     };
 
     tokio::spawn(async move {
-        if let Err(e) = run_create_host(&tx, &config).await {
+        if let Err(e) = run_create_host(&tx, &config, lint).await {
             tracing::error!("Create host failed: {}", e);
             let _ = tx
                 .send(CommandMessage::StepFailed {
@@ -57,21 +63,20 @@ pub async fn start_create_host(tx: mpsc::Sender<CommandMessage>, mode: AppMode)
 }
 
 async fn run_create_host(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     config: &NewHostConfig,
+    lint: bool,
 ) -> Result<()> {
     // Clone repository if running from live ISO and no config exists
     if crate::system::is_live_iso_environment() {
         let temp_config = crate::constants::temp_config_dir();
         if !temp_config.join(crate::constants::FLAKE_NIX).exists() {
-            tx.send(CommandMessage::Stdout(
-                "Cloning configuration repository...".to_string(),
-            ))
-            .await?;
+            tx.stdout("Cloning configuration repository...").await?;
 
             let _ = fs::remove_dir_all(&temp_config);
             let temp_config_str = temp_config.to_string_lossy();
-            std::env::set_var("NIX_CONFIG", "experimental-features = nix-command flakes");
+            std::env::set_var("NIX_CONFIG", crate::system::nix_settings::nix_config());
+            crate::system::nix_settings::apply_proxy_env();
 
             let success = run_command(
                 tx,
@@ -101,17 +106,81 @@ async fn run_create_host(
     // When running from installed system, we use the actual path
     let config_dir = get_config_dir()?;
 
-    tx.send(CommandMessage::Stdout(format!(
+    tx.stdout(format!(
         "Creating host configuration for '{}'...",
         config.hostname
-    )))
+    ))
     .await?;
 
+    // Generate into a copy of the repo and only move the files over once the
+    // flake still evaluates, so the repo never holds a half-generated host
+    let staging = std::env::temp_dir().join(format!("forge-create-host-{}", std::process::id()));
+    let result = async {
+        copy_repo(Path::new(&config_dir), &staging)
+            .with_context(|| format!("Failed to copy the repo to {}", staging.display()))?;
+        write_host(tx, config, lint, &staging.to_string_lossy()).await?;
+        eval_host(tx, &config.hostname, &staging).await?;
+        check_flake(tx, &staging).await?;
+        install_staged(tx, &config.hostname, &staging, Path::new(&config_dir)).await
+    }
+    .await;
+    if let Err(e) = fs::remove_dir_all(&staging) {
+        tracing::warn!("Failed to remove {}: {}", staging.display(), e);
+    }
+    result?;
+
+    // Success message
+    tx.stdout("\n").await?;
+    tx.stdout(format!(
+        "Host '{}' created successfully!",
+        config.hostname
+    ))
+    .await?;
+    tx.stdout("").await?;
+    tx.stdout("Configuration summary:").await?;
+    tx.stdout(format!(
+        "  CPU: {} ({})",
+        config.cpu.vendor, config.cpu.model_name
+    ))
+    .await?;
+    tx.stdout(format!(
+        "  GPU: {}{}",
+        config.gpu.vendor,
+        config
+            .gpu
+            .model
+            .as_ref()
+            .map(|m| format!(" ({})", m))
+            .unwrap_or_default()
+    ))
+    .await?;
+    tx.stdout(format!(
+        "  Form factor: {}",
+        config.form_factor
+    ))
+    .await?;
+    tx.stdout(format!(
+        "  Disk: {} ({})",
+        config.disk.path, config.disk.size
+    ))
+    .await?;
+
+    tx.send(CommandMessage::Done { success: true }).await?;
+    Ok(())
+}
+
+/// Write the host's files and add it to flake.nix in `config_dir` (the staged copy)
+async fn write_host(
+    tx: &CommandSender,
+    config: &NewHostConfig,
+    lint: bool,
+    config_dir: &str,
+) -> Result<()> {
     // Step 1: Create host directory
-    tx.send(CommandMessage::Stdout(format!(
+    tx.stdout(format!(
         "Creating hosts/{}/...",
         config.hostname
-    )))
+    ))
     .await?;
 
     let host_dir = format!("{}/hosts/{}", config_dir, config.hostname);
@@ -124,10 +193,7 @@ async fn run_create_host(
     .await?;
 
     // Step 2: Generate hardware-configuration.nix
-    tx.send(CommandMessage::Stdout(
-        "Generating hardware configuration...".to_string(),
-    ))
-    .await?;
+    tx.stdout("Generating hardware configuration...").await?;
 
     let hw_config_path = format!("{}/hardware-configuration.nix", host_dir);
 
@@ -136,10 +202,10 @@ async fn run_create_host(
     let hw_config_generated = match generate_hw_config_from_system(tx, &hw_config_path).await {
         Ok(()) => true,
         Err(e) => {
-            tx.send(CommandMessage::Stdout(format!(
+            tx.stdout(format!(
                 "Note: Using template hardware config ({})",
                 e
-            )))
+            ))
             .await?;
             false
         }
@@ -158,29 +224,69 @@ async fn run_create_host(
     .await?;
 
     // Step 3: Generate host default.nix
-    tx.send(CommandMessage::Stdout(
-        "Creating host configuration...".to_string(),
-    ))
-    .await?;
+    tx.stdout("Creating host configuration...").await?;
 
     let default_nix_path = format!("{}/default.nix", host_dir);
     let default_nix = templates::generate_host_default_nix(config);
     fs::write(&default_nix_path, default_nix)
         .with_context(|| format!("Failed to write default.nix: {}", default_nix_path))?;
 
+    // Capture the monitor layout if a display server is running
+    let monitors = tokio::task::spawn_blocking(detect_monitors)
+        .await
+        .unwrap_or_default();
+    if monitors.is_empty() {
+        tx.stdout(
+            "No display server detected, using automatic monitor configuration".to_string(),
+        )
+        .await?;
+    } else {
+        let monitors_path = format!("{}/monitors.conf", host_dir);
+        fs::write(
+            &monitors_path,
+            templates::generate_monitors_conf(&config.hostname, &monitors),
+        )
+        .with_context(|| format!("Failed to write monitors.conf: {}", monitors_path))?;
+        tx.stdout(format!(
+            "Captured monitor layout ({} display{})",
+            monitors.len(),
+            if monitors.len() == 1 { "" } else { "s" }
+        ))
+        .await?;
+    }
+
     tx.send(CommandMessage::StepComplete {
         step: "host config".to_string(),
     })
     .await?;
 
     // Step 4: Create disko configuration
-    tx.send(CommandMessage::Stdout(
-        "Creating disko configuration...".to_string(),
-    ))
-    .await?;
+    tx.stdout("Creating disko configuration...").await?;
 
     let disko_path = format!("{}/modules/disko/{}.nix", config_dir, config.hostname);
-    let disko_config = templates::generate_disko_config(&config.hostname, &config.disk.path);
+    // Recorded so install and update notice when the /dev name points elsewhere
+    let disk = disk_identity(&config.disk.path);
+    let disko_config = match config.filesystem {
+        RootFilesystem::Zfs => {
+            templates::generate_zfs_disko_config(&config.hostname, config.disk.config_path())
+        }
+        RootFilesystem::Btrfs => {
+            let subvolumes = config.subvolumes();
+            templates::generate_disko_config(
+                &config.hostname,
+                config.disk.config_path(),
+                config.boot_mode,
+                subvolumes.as_deref(),
+                config.disk_swap(),
+                &config.disk_options,
+                config.subvolume_layout.ephemeral_root(),
+            )
+        }
+    };
+    let disko_config = match &disk {
+        Some(disk) => templates::annotate_disk_identity(&disko_config, disk),
+        None => disko_config,
+    };
     fs::write(&disko_path, disko_config)
         .with_context(|| format!("Failed to write disko config: {}", disko_path))?;
 
@@ -190,8 +296,7 @@ async fn run_create_host(
     .await?;
 
     // Step 5: Update flake.nix
-    tx.send(CommandMessage::Stdout("Updating flake.nix...".to_string()))
-        .await?;
+    tx.stdout("Updating flake.nix...").await?;
 
     let flake_path = format!("{}/flake.nix", config_dir);
     let flake_content = fs::read_to_string(&flake_path)
@@ -201,65 +306,227 @@ async fn run_create_host(
     fs::write(&flake_path, updated_flake)
         .with_context(|| format!("Failed to write flake.nix: {}", flake_path))?;
 
+    // Match the repo's formatting so the new host doesn't come with noisy diffs
+    let host_rel = format!("hosts/{}", config.hostname);
+    let generated = [
+        format!("{}/default.nix", host_rel),
+        format!("{}/hardware-configuration.nix", host_rel),
+        format!("modules/disko/{}.nix", config.hostname),
+        "flake.nix".to_string(),
+    ];
+    let generated: Vec<&str> = generated.iter().map(String::as_str).collect();
+    let runner = CommandRunner::new(tx);
+    format_nix_files(&runner, Path::new(config_dir), &generated).await;
+
+    // Review the formatted result, so the diff is exactly what lands in the repo
+    let written = fs::read_to_string(&flake_path)
+        .with_context(|| format!("Failed to read flake.nix: {}", flake_path))?;
+    let reason = format!("add host '{}'", config.hostname);
+    let file = crate::constants::FLAKE_NIX;
+    if !review_edit(&runner, file, &reason, &flake_content, &written).await? {
+        anyhow::bail!("The flake.nix change was rejected; the repo was left untouched");
+    }
+
     tx.send(CommandMessage::StepComplete {
         step: "flake".to_string(),
     })
     .await?;
 
     // Step 6: Generate host-info.json metadata
-    tx.send(CommandMessage::Stdout(
-        "Generating host metadata...".to_string(),
-    ))
-    .await?;
+    tx.stdout("Generating host metadata...").await?;
 
-    write_host_metadata(&host_dir, config)?;
+    write_host_metadata(&host_dir, config, disk.as_ref())?;
 
     tx.send(CommandMessage::StepComplete {
         step: "metadata".to_string(),
     })
     .await?;
 
-    // Success message
-    tx.send(CommandMessage::Stdout("\n".to_string())).await?;
-    tx.send(CommandMessage::Stdout(format!(
-        "Host '{}' created successfully!",
-        config.hostname
-    )))
-    .await?;
-    tx.send(CommandMessage::Stdout("".to_string())).await?;
-    tx.send(CommandMessage::Stdout("Configuration summary:".to_string()))
+    // Step 7 (optional): Fix what statix/deadnix can before the files are committed
+    if lint {
+        tx.stdout("Linting generated files...").await?;
+        let host_path = format!("hosts/{}", config.hostname);
+        let disko_rel = format!("modules/disko/{}.nix", config.hostname);
+        let runner = CommandRunner::new(tx);
+        lint::lint_paths(&runner, Path::new(config_dir), &[&host_path, &disko_rel], true).await;
+        tx.send(CommandMessage::StepComplete {
+            step: "Lint".to_string(),
+        })
         .await?;
-    tx.send(CommandMessage::Stdout(format!(
-        "  CPU: {} ({})",
-        config.cpu.vendor, config.cpu.model_name
-    )))
-    .await?;
-    tx.send(CommandMessage::Stdout(format!(
-        "  GPU: {}{}",
-        config.gpu.vendor,
-        config
-            .gpu
-            .model
-            .as_ref()
-            .map(|m| format!(" ({})", m))
-            .unwrap_or_default()
-    )))
+    }
+
+    Ok(())
+}
+
+/// Evaluate the new host's system in the staged repo, ahead of the whole-flake
+/// check, so its own mistakes are reported on their own
+async fn eval_host(tx: &CommandSender, hostname: &str, staging: &Path) -> Result<()> {
+    tx.stdout(format!("Evaluating {}...", hostname)).await?;
+    let attr = format!(
+        "path:{}#nixosConfigurations.{}.config.system.build.toplevel.drvPath",
+        staging.display(),
+        hostname
+    );
+    let mut args = vec!["eval", "--raw", attr.as_str()];
+    args.extend(offline::nix_args());
+    let (ok, _, stderr) = run_capture("nix", &args).await?;
+    if !ok {
+        let message = nix_error_message(&stderr);
+        for line in message.lines() {
+            tx.stderr(line).await?;
+        }
+        tx.send(CommandMessage::StepFailed {
+            step: "Evaluating".to_string(),
+            error: ParsedError {
+                summary: format!("{} does not evaluate", hostname),
+                detail: Some(message),
+                suggestion: "Go back and adjust the host, or fix the module named above.".to_string(),
+            },
+        })
+        .await?;
+        anyhow::bail!("{} does not evaluate; the repo was left unchanged", hostname);
+    }
+    tx.send(CommandMessage::StepComplete {
+        step: "Evaluating".to_string(),
+    })
     .await?;
-    tx.send(CommandMessage::Stdout(format!(
-        "  Form factor: {}",
-        config.form_factor
-    )))
+    Ok(())
+}
+
+/// `nix flake check` on the staged repo, which evaluates every host with the new one
+async fn check_flake(tx: &CommandSender, staging: &Path) -> Result<()> {
+    tx.stdout("Checking the flake with the new host...").await?;
+    let flake = format!("path:{}", staging.display());
+    let mut args = vec!["flake", "check", "--no-build", flake.as_str()];
+    args.extend(offline::nix_args());
+    if !run_command(tx, "nix", &args).await? {
+        anyhow::bail!("nix flake check failed; the repo was left unchanged");
+    }
+    tx.send(CommandMessage::StepComplete {
+        step: "flake check".to_string(),
+    })
     .await?;
-    tx.send(CommandMessage::Stdout(format!(
-        "  Disk: {} ({})",
-        config.disk.path, config.disk.size
-    )))
+    Ok(())
+}
+
+/// Copy the staged host files into the repo, undoing the copy if one fails
+async fn install_staged(
+    tx: &CommandSender,
+    hostname: &str,
+    staging: &Path,
+    config_dir: &Path,
+) -> Result<()> {
+    tx.stdout("Moving the generated files into the repo...").await?;
+    let mut changes = FileChanges::default();
+    let mut result = Ok(());
+    for rel in staged_paths(hostname) {
+        let (src, dst) = (staging.join(&rel), config_dir.join(&rel));
+        changes.track(&dst);
+        result = copy_path(&src, &dst).with_context(|| format!("Failed to write {}", dst.display()));
+        if result.is_err() {
+            break;
+        }
+    }
+    if let Err(e) = result {
+        tx.stdout("Rolling back changes...").await?;
+        for line in changes.rollback(config_dir) {
+            tx.stdout(line).await?;
+        }
+        return Err(e);
+    }
+    tx.send(CommandMessage::StepComplete {
+        step: "into place".to_string(),
+    })
     .await?;
+    Ok(())
+}
 
-    tx.send(CommandMessage::Done { success: true }).await?;
+/// What create-host writes, relative to the repo
+fn staged_paths(hostname: &str) -> [String; 3] {
+    [
+        format!("hosts/{}", hostname),
+        format!("modules/disko/{}.nix", hostname),
+        crate::constants::FLAKE_NIX.to_string(),
+    ]
+}
+
+/// Copy the repo without its git directory and symlinks (`result` links)
+fn copy_repo(src: &Path, dst: &Path) -> std::io::Result<()> {
+    let _ = fs::remove_dir_all(dst);
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_name() == ".git" || path.is_symlink() {
+            continue;
+        }
+        copy_path(&path, &dst.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// Copy a file, or a directory with everything in it but symlinks
+fn copy_path(src: &Path, dst: &Path) -> std::io::Result<()> {
+    if !src.is_dir() {
+        return fs::copy(src, dst).map(|_| ());
+    }
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let path = entry?.path();
+        if path.is_symlink() {
+            continue;
+        }
+        if let Some(name) = path.file_name() {
+            copy_path(&path, &dst.join(name))?;
+        }
+    }
     Ok(())
 }
 
+/// Paths create-host is about to write, with what they held before
+///
+/// Files that didn't exist are removed on rollback, existing ones get their
+/// old contents back. Kept in order so rollback can undo newest first.
+#[derive(Debug, Default)]
+struct FileChanges {
+    entries: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl FileChanges {
+    /// Record `path` before it is created or overwritten
+    fn track(&mut self, path: impl Into<PathBuf>) {
+        let path = path.into();
+        if self.entries.iter().any(|(p, _)| *p == path) {
+            return;
+        }
+        // Directories that already exist are left alone, only new ones are removed
+        if path.is_dir() {
+            return;
+        }
+        let original = fs::read(&path).ok();
+        self.entries.push((path, original));
+    }
+
+    /// Undo the recorded changes, newest first; returns a line per path
+    fn rollback(&self, config_dir: &Path) -> Vec<String> {
+        let mut report = Vec::new();
+        for (path, original) in self.entries.iter().rev() {
+            let name = path.strip_prefix(config_dir).unwrap_or(path).display();
+            let result = match original {
+                Some(content) => fs::write(path, content).map(|_| format!("  Restored {}", name)),
+                None if path.is_dir() => {
+                    fs::remove_dir_all(path).map(|_| format!("  Removed {}/", name))
+                }
+                None if path.exists() => fs::remove_file(path).map(|_| format!("  Removed {}", name)),
+                // Never written, or already gone with its directory
+                None => continue,
+            };
+            report.push(result.unwrap_or_else(|e| format!("  Failed to undo {}: {}", name, e)));
+        }
+        report
+    }
+}
+
 /// Get the configuration directory path
 fn get_config_dir() -> Result<String> {
     // First try PID-specific temp directory (for concurrent installs)
@@ -294,7 +561,7 @@ fn get_config_dir() -> Result<String> {
 
 /// Generate hardware configuration using nixos-generate-config
 async fn generate_hw_config_from_system(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     output_path: &str,
 ) -> Result<()> {
     // Create a temp directory for the generated config
@@ -394,11 +661,15 @@ fn update_flake_nix(content: &str, config: &NewHostConfig) -> Result<String> {
 }
 
 /// Write host-info.json metadata file
-fn write_host_metadata(host_dir: &str, config: &NewHostConfig) -> Result<()> {
+fn write_host_metadata(
+    host_dir: &str,
+    config: &NewHostConfig,
+    disk: Option<&DiskIdentity>,
+) -> Result<()> {
     // Detect RAM
     let ram = detect_ram();
 
-    let metadata = serde_json::json!({
+    let mut metadata = serde_json::json!({
         "cpu": {
             "vendor": format!("{}", config.cpu.vendor),
             "model": config.cpu.model_name
@@ -410,6 +681,9 @@ fn write_host_metadata(host_dir: &str, config: &NewHostConfig) -> Result<()> {
         "form_factor": format!("{}", config.form_factor),
         "ram": ram
     });
+    if let Some(disk) = disk {
+        metadata["disk"] = serde_json::to_value(disk)?;
+    }
 
     let metadata_path = format!("{}/host-info.json", host_dir);
     let content = serde_json::to_string_pretty(&metadata)?;
@@ -437,3 +711,64 @@ fn detect_ram() -> Option<String> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_changes_rollback() {
+        let dir = std::env::temp_dir().join(format!("forge-create-host-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("flake.nix"), "original").unwrap();
+
+        let mut changes = FileChanges::default();
+        let host_dir = dir.join("hosts/new");
+        changes.track(&host_dir);
+        fs::create_dir_all(&host_dir).unwrap();
+        changes.track(host_dir.join("default.nix"));
+        fs::write(host_dir.join("default.nix"), "{ }").unwrap();
+        changes.track(dir.join("flake.nix"));
+        fs::write(dir.join("flake.nix"), "edited").unwrap();
+        // Tracked but never written: nothing to undo
+        changes.track(dir.join("disko.nix"));
+
+        let report = changes.rollback(&dir);
+        assert_eq!(report, vec!["  Restored flake.nix", "  Removed hosts/new/default.nix", "  Removed hosts/new/"]);
+        assert_eq!(fs::read_to_string(dir.join("flake.nix")).unwrap(), "original");
+        assert!(!host_dir.exists());
+        assert!(dir.join("hosts").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_copy_repo_skips_git() {
+        let dir = std::env::temp_dir().join(format!("forge-create-host-copy-{}", std::process::id()));
+        let (src, dst) = (dir.join("repo"), dir.join("staging"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(src.join(".git")).unwrap();
+        fs::create_dir_all(src.join("hosts/G1a")).unwrap();
+        fs::write(src.join(".git/HEAD"), "ref").unwrap();
+        fs::write(src.join("flake.nix"), "{ }").unwrap();
+        fs::write(src.join("hosts/G1a/default.nix"), "{ }").unwrap();
+
+        copy_repo(&src, &dst).unwrap();
+        assert!(dst.join("flake.nix").exists());
+        assert!(dst.join("hosts/G1a/default.nix").exists());
+        assert!(!dst.join(".git").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_nix_error_message() {
+        let stderr = "error:\n       … while calling the 'head' builtin\n         at /nix/store/abc-source/lib.nix:4:5:\n\n       error: The option `services.foo' does not exist. Definition values:\n       - In `/nix/store/abc-source/hosts/G1a/default.nix': true\n";
+        assert_eq!(
+            nix_error_message(stderr),
+            "The option `services.foo' does not exist. Definition values:\n- In `/nix/store/abc-source/hosts/G1a/default.nix': true"
+        );
+        assert_eq!(nix_error_message("error: flake has no output\n"), "flake has no output");
+    }
+}
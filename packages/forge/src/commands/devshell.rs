@@ -0,0 +1,74 @@
+//! Discover flake devShells for the "Dev shells" screen
+
+use anyhow::{bail, Result};
+use std::path::PathBuf;
+
+use super::executor::run_capture;
+use super::{CommandMessage, CommandSender};
+use crate::constants::nixos_config_dir;
+use crate::system::devshell::{
+    dev_projects_dir, find_project_flakes, nix_system, parse_shell_names, project_name, DevShell,
+};
+
+/// List devShells of the config flake and ~/code projects in the background
+pub async fn start_devshell_discovery(tx: CommandSender) -> Result<()> {
+    tokio::spawn(async move {
+        let config = nixos_config_dir();
+        let mut flakes = vec![config.clone()];
+        if let Some(projects) = dev_projects_dir() {
+            // The config repo may live in ~/code too
+            flakes.extend(
+                find_project_flakes(&projects)
+                    .into_iter()
+                    .filter(|f| *f != config),
+            );
+        }
+
+        // Evaluate all flakes at once; some projects take a while
+        let mut tasks = tokio::task::JoinSet::new();
+        for (i, flake) in flakes.into_iter().enumerate() {
+            tasks.spawn(async move { (i, flake_shells(flake).await) });
+        }
+        let mut found = Vec::new();
+        while let Some(Ok((i, shells))) = tasks.join_next().await {
+            match shells {
+                Ok(shells) => found.push((i, shells)),
+                // Most projects simply have no devShells for this system
+                Err(e) => tracing::debug!("No dev shells: {}", e),
+            }
+        }
+        found.sort_by_key(|(i, _)| *i);
+
+        let shells = found.into_iter().flat_map(|(_, shells)| shells).collect();
+        let _ = tx.send(CommandMessage::DevShellsFound(shells)).await;
+    });
+    Ok(())
+}
+
+async fn flake_shells(flake_dir: PathBuf) -> Result<Vec<DevShell>> {
+    let installable = format!("{}#devShells.{}", flake_dir.display(), nix_system());
+    let (ok, stdout, stderr) = run_capture(
+        "nix",
+        &[
+            "eval",
+            "--json",
+            &installable,
+            "--apply",
+            "builtins.attrNames",
+        ],
+    )
+    .await?;
+    if !ok {
+        bail!("{}: {}", flake_dir.display(), stderr.trim());
+    }
+
+    let project = project_name(&flake_dir);
+    Ok(parse_shell_names(&stdout)?
+        .into_iter()
+        .map(|name| DevShell {
+            project: project.clone(),
+            flake_dir: flake_dir.clone(),
+            name,
+        })
+        .collect())
+}
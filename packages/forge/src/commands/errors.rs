@@ -49,9 +49,28 @@ pub struct ErrorContext {
 }
 
 impl ParsedError {
+    /// The error panel for a failed step, as shown in the output
+    pub fn panel_lines(&self, step: &str) -> Vec<String> {
+        let mut lines = vec![
+            format!("[✗] Step failed: {}", step),
+            String::new(),
+            format!("  Error: {}", self.summary),
+        ];
+        if let Some(detail) = &self.detail {
+            lines.extend(detail.lines().map(|line| format!("  {}", line)));
+        }
+        lines.push(String::new());
+        lines.push(format!("  Suggestion: {}", self.suggestion));
+        lines
+    }
+
     /// Parse stderr output into a categorized error
     pub fn from_stderr(stderr: &str, context: ErrorContext) -> Self {
-        // Try each parser in order of specificity
+        // Try each parser in order of specificity; cache downloads before
+        // GitHub, as both report "HTTP error"
+        if let Some(err) = parse_substitution_error(stderr) {
+            return err;
+        }
         if let Some(err) = parse_github_api_error(stderr) {
             return err;
         }
@@ -102,6 +121,37 @@ impl ParsedError {
     }
 }
 
+// Binary cache download failure patterns (narinfo or NAR)
+static SUBSTITUTION_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"unable to download '(https?://[^/']+)[^']*(?:\.narinfo|/nar/[^']+)': ",
+        r"HTTP error (\d+)"
+    ))
+    .unwrap()
+});
+
+/// Whether nix's output shows a binary cache failing to serve a store path,
+/// which a retry with `--fallback` gets around by building it instead
+pub fn is_substitution_failure(stderr: &str) -> bool {
+    stderr.contains("try '--fallback'") || SUBSTITUTION_RE.is_match(stderr)
+}
+
+fn parse_substitution_error(stderr: &str) -> Option<ParsedError> {
+    if !is_substitution_failure(stderr) {
+        return None;
+    }
+    let detail = SUBSTITUTION_RE
+        .captures(stderr)
+        .map(|caps| format!("Cache: {} (HTTP {})", &caps[1], &caps[2]));
+    Some(ParsedError {
+        summary: "Binary cache download failed".to_string(),
+        detail,
+        suggestion: "The cache is having trouble. Try again later, or pass --fallback to nix \
+                     to build the missing paths from source."
+            .to_string(),
+    })
+}
+
 // GitHub API error patterns
 static GITHUB_HTTP_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(?i)HTTP\s+error\s+(\d{3})").unwrap());
@@ -122,7 +172,7 @@ fn parse_github_api_error(stderr: &str) -> Option<ParsedError> {
             .map(|m| m.as_str().trim_end_matches(".git").to_string());
 
         let (summary, suggestion) = match code {
-            504 | 503 | 502 => (
+            502..=504 => (
                 format!("GitHub API timeout (HTTP {})", code),
                 "GitHub's API is temporarily unavailable. Try again in a few minutes.".to_string(),
             ),
@@ -225,6 +275,27 @@ fn parse_nix_eval_error(stderr: &str) -> Option<ParsedError> {
     None
 }
 
+/// The last `error:` block of nix's output, without the `… while evaluating`
+/// trace leading up to it
+pub fn nix_error_message(stderr: &str) -> String {
+    let lines: Vec<&str> = stderr.lines().collect();
+    let start = lines
+        .iter()
+        .rposition(|l| l.trim_start().starts_with("error:") && !l.trim().ends_with("error:"))
+        .unwrap_or(0);
+    let message = lines[start..]
+        .iter()
+        .map(|l| l.trim())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let message = message.trim().trim_start_matches("error:").trim();
+    if message.is_empty() {
+        "nix eval failed without an error message".to_string()
+    } else {
+        message.to_string()
+    }
+}
+
 // Nix build failure pattern
 static NIX_BUILD_RE: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"builder for '([^']+)' failed").unwrap());
@@ -315,3 +386,38 @@ fn parse_permission_error(stderr: &str) -> Option<ParsedError> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> ErrorContext {
+        ErrorContext {
+            operation: "System rebuild".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_substitution_failure() {
+        let narinfo = "error: unable to download 'https://cache.nixos.org/abc.narinfo': HTTP \
+                       error 502";
+        assert!(is_substitution_failure(narinfo));
+        let err = ParsedError::from_stderr(narinfo, context());
+        assert_eq!(err.summary, "Binary cache download failed");
+        assert_eq!(err.detail.as_deref(), Some("Cache: https://cache.nixos.org (HTTP 502)"));
+
+        let some_failed = "error: some substitutes for the outputs of derivation \
+                           '/nix/store/abc-foo.drv' failed (usually happens due to networking \
+                           issues); try '--fallback' to build derivation from source";
+        assert!(is_substitution_failure(some_failed));
+
+        // A GitHub tarball is a flake input problem, not a cache one
+        let github = "error: unable to download 'https://api.github.com/repos/NixOS/nixpkgs/\
+                      tarball/abc': HTTP error 504";
+        assert!(!is_substitution_failure(github));
+        assert_eq!(
+            ParsedError::from_stderr(github, context()).summary,
+            "GitHub API timeout (HTTP 504)"
+        );
+    }
+}
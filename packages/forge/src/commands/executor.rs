@@ -5,14 +5,13 @@ use std::process::Stdio;
 use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
-use tokio::sync::mpsc;
 
-use super::CommandMessage;
+use super::CommandSender;
 use crate::constants::DEFAULT_COMMAND_TIMEOUT_SECS;
 
 /// Execute a command and stream output to the channel
 pub async fn run_command(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     cmd: &str,
     args: &[&str],
 ) -> Result<bool> {
@@ -22,7 +21,7 @@ pub async fn run_command(
 /// Execute a command and stream output, filtering lines with a predicate
 /// Lines where the filter returns false will be skipped
 pub async fn run_command_filtered<F>(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     cmd: &str,
     args: &[&str],
     filter: F,
@@ -35,7 +34,7 @@ where
 
 /// Execute a command with explicit timeout
 pub async fn run_command_with_timeout(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     cmd: &str,
     args: &[&str],
     timeout_secs: Option<u64>,
@@ -48,7 +47,7 @@ pub async fn run_command_with_timeout(
 /// - None: skip the line
 /// - Some(line): output the (possibly modified) line
 pub async fn run_command_transformed<F>(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     cmd: &str,
     args: &[&str],
     transform: F,
@@ -61,7 +60,7 @@ where
 
 /// Execute a command with transform and timeout
 pub async fn run_command_transformed_with_timeout<F>(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     cmd: &str,
     args: &[&str],
     timeout_secs: Option<u64>,
@@ -98,7 +97,7 @@ where
         while let Ok(Some(line)) = reader.next_line().await {
             // Apply transform - skip if None, output transformed line if Some
             if let Some(transformed) = transform_out(&line) {
-                if let Err(e) = tx_out.send(CommandMessage::Stdout(transformed)).await {
+                if let Err(e) = tx_out.stdout(transformed).await {
                     tracing::warn!("Failed to send stdout to channel: {}", e);
                     break;
                 }
@@ -113,7 +112,7 @@ where
         while let Ok(Some(line)) = reader.next_line().await {
             // Apply transform - skip if None, output transformed line if Some
             if let Some(transformed) = transform_err(&line) {
-                if let Err(e) = tx_err.send(CommandMessage::Stderr(transformed)).await {
+                if let Err(e) = tx_err.stderr(transformed).await {
                     tracing::warn!("Failed to send stderr to channel: {}", e);
                     break;
                 }
@@ -145,7 +144,7 @@ where
 
 /// Execute a command with explicit timeout and output filtering
 pub async fn run_command_filtered_with_timeout<F>(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     cmd: &str,
     args: &[&str],
     timeout_secs: Option<u64>,
@@ -186,7 +185,7 @@ where
                 continue;
             }
             // Log channel send failures but don't propagate - channel may be closed
-            if let Err(e) = tx_out.send(CommandMessage::Stdout(line)).await {
+            if let Err(e) = tx_out.stdout(line).await {
                 tracing::warn!("Failed to send stdout to channel: {}", e);
                 break;
             }
@@ -202,7 +201,7 @@ where
             if !filter_err(&line) {
                 continue;
             }
-            if let Err(e) = tx_err.send(CommandMessage::Stderr(line)).await {
+            if let Err(e) = tx_err.stderr(line).await {
                 tracing::warn!("Failed to send stderr to channel: {}", e);
                 break;
             }
@@ -235,7 +234,7 @@ where
 
 /// Execute a command without logging arguments (for sensitive data like passwords)
 pub async fn run_command_sensitive(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     cmd: &str,
     args: &[&str],
 ) -> Result<bool> {
@@ -262,7 +261,7 @@ pub async fn run_command_sensitive(
     let stdout_task = tokio::spawn(async move {
         let mut reader = BufReader::new(stdout).lines();
         while let Ok(Some(line)) = reader.next_line().await {
-            if let Err(e) = tx_out.send(CommandMessage::Stdout(line)).await {
+            if let Err(e) = tx_out.stdout(line).await {
                 tracing::warn!("Failed to send stdout to channel: {}", e);
                 break;
             }
@@ -273,7 +272,7 @@ pub async fn run_command_sensitive(
     let stderr_task = tokio::spawn(async move {
         let mut reader = BufReader::new(stderr).lines();
         while let Ok(Some(line)) = reader.next_line().await {
-            if let Err(e) = tx_err.send(CommandMessage::Stderr(line)).await {
+            if let Err(e) = tx_err.stderr(line).await {
                 tracing::warn!("Failed to send stderr to channel: {}", e);
                 break;
             }
@@ -305,7 +304,7 @@ pub async fn run_command_sensitive(
 /// Execute a command with sudo
 #[allow(dead_code)]
 pub async fn run_sudo(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     cmd: &str,
     args: &[&str],
 ) -> Result<bool> {
@@ -317,7 +316,17 @@ pub async fn run_sudo(
 /// Execute a command and capture output (no streaming)
 pub async fn run_capture(cmd: &str, args: &[&str]) -> Result<(bool, String, String)> {
     tracing::info!("Capturing command: {} {:?}", cmd, args);
+    capture(cmd, args).await
+}
+
+/// Execute a command and capture output without logging arguments (for
+/// secrets like a WiFi passphrase; forge.log ends up in log bundles)
+pub async fn run_capture_sensitive(cmd: &str, args: &[&str]) -> Result<(bool, String, String)> {
+    tracing::info!("Capturing command: {} [args hidden]", cmd);
+    capture(cmd, args).await
+}
 
+async fn capture(cmd: &str, args: &[&str]) -> Result<(bool, String, String)> {
     let output = Command::new(cmd)
         .args(args)
         .output()
@@ -352,3 +361,49 @@ pub async fn get_output(cmd: &str, args: &[&str]) -> Result<String> {
 
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// Log output collected in memory
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_run_capture_sensitive_hides_args() {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            runtime.block_on(async {
+                let (ok, stdout, _) = run_capture_sensitive("echo", &["hunter2"]).await.unwrap();
+                assert!(ok);
+                assert_eq!(stdout.trim(), "hunter2");
+            })
+        });
+        let log = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(log.contains("Capturing command: echo [args hidden]"));
+        assert!(!log.contains("hunter2"));
+    }
+}
@@ -0,0 +1,438 @@
+//! Fleet update: deploy the current config to several hosts over SSH
+//!
+//! Canaries go first, one at a time; the remaining hosts follow `parallel`
+//! at a time. The first failure stops any further hosts from starting.
+//! A host outside its maintenance window is held back when its turn comes;
+//! a held canary stops the rollout like a failure, without failing it.
+//!
+//! Each host goes through the `DeployStage`s: an SSH check, a build of its
+//! system (here, or on the host itself with `build = "remote"`) and
+//! `nixos-rebuild switch` on the host, which reuses that build.
+
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+use super::errors::{ErrorContext, ParsedError};
+use super::executor::{run_capture, run_command_transformed};
+use super::runner::{spawn_with_error_handling, CommandRunner};
+use super::update::{pull_config_updates, transform_nix_output};
+use super::{CommandMessage, CommandSender};
+use crate::constants::nixos_config_dir;
+use crate::system::fleet::{BuildHost, DeployStage, FleetHost, RolloutPlan};
+use crate::system::maintenance::MaintenanceWindow;
+use crate::system::manifest::record_build;
+use crate::system::provenance;
+
+/// Seconds to wait for a host to answer before giving up on it
+const SSH_CONNECT_TIMEOUT_SECS: u32 = 10;
+
+/// Result of updating a single host
+#[derive(Debug, Clone, PartialEq)]
+enum HostResult {
+    Updated(Duration),
+    Failed(String),
+    /// Not started because its maintenance window was closed
+    Held(MaintenanceWindow),
+    /// Not started because the rollout stopped before its turn
+    Skipped,
+}
+
+/// Start a fleet update
+pub async fn start_fleet_update(tx: CommandSender, plan: RolloutPlan) -> Result<()> {
+    spawn_with_error_handling(tx, "Fleet update", "pull", move |tx| async move {
+        run_fleet_update(&tx, plan).await
+    })
+}
+
+async fn run_fleet_update(tx: &CommandSender, plan: RolloutPlan) -> Result<()> {
+    let runner = CommandRunner::new(tx);
+    let flake_dir = nixos_config_dir();
+    let flake_path = flake_dir.to_string_lossy().to_string();
+
+    runner.header("Fleet Update").await;
+
+    if let Err(e) = pull_config_updates(tx, &flake_path).await {
+        // Non-fatal, same as `forge update`: deploy what is checked out
+        tracing::warn!("Failed to check for config updates: {}", e);
+    }
+
+    let mut results: Vec<(String, HostResult)> = Vec::new();
+    let mut failed = false;
+
+    // Set by a failure or by a canary that had to wait for its window
+    let mut stopped = false;
+    for host in &plan.canaries {
+        let result = match closed_window(host) {
+            Some(window) => hold_host(tx, host, window).await?,
+            None => {
+                deploy_host(tx.clone(), flake_path.clone(), host.clone(), plan.verify_signatures)
+                    .await
+            }
+        };
+        failed = matches!(result, HostResult::Failed(_));
+        stopped = failed || matches!(result, HostResult::Held(_));
+        results.push((host.name.clone(), result));
+        if stopped {
+            break;
+        }
+    }
+
+    if !stopped {
+        let mut pending = plan.rest.iter().cloned();
+        let mut running = JoinSet::new();
+        loop {
+            // Keep `parallel` hosts busy until something fails
+            while !failed && running.len() < plan.parallel {
+                let Some(host) = pending.next() else { break };
+                if let Some(window) = closed_window(&host) {
+                    results.push((host.name.clone(), hold_host(tx, &host, window).await?));
+                    continue;
+                }
+                let name = host.name.clone();
+                let tx = tx.clone();
+                let flake_path = flake_path.clone();
+                let verify = plan.verify_signatures;
+                running.spawn(async move {
+                    (name, deploy_host(tx, flake_path, host, verify).await)
+                });
+            }
+            match running.join_next().await {
+                Some(Ok((name, result))) => {
+                    failed |= matches!(result, HostResult::Failed(_));
+                    results.push((name, result));
+                }
+                Some(Err(e)) => {
+                    tracing::error!("Fleet deploy task failed: {}", e);
+                    failed = true;
+                }
+                None => break,
+            }
+        }
+    }
+
+    for host in plan.hosts() {
+        if !results.iter().any(|(name, _)| name == &host.name) {
+            tx.send(CommandMessage::StepSkipped {
+                step: host.name.clone(),
+            })
+            .await?;
+            results.push((host.name.clone(), HostResult::Skipped));
+        }
+    }
+
+    // Report in rollout order, not completion order
+    let order: Vec<&str> = plan.hosts().map(|h| h.name.as_str()).collect();
+    results.sort_by_key(|(name, _)| order.iter().position(|n| n == name));
+
+    runner.out("").await;
+    runner.out("  Fleet Summary").await;
+    runner.out("  ─────────────").await;
+    for line in summary_lines(&plan, &results) {
+        runner.out(&line).await;
+    }
+
+    runner.footer().await;
+    runner.done(!failed).await?;
+    Ok(())
+}
+
+/// The host's maintenance window if it is closed right now
+fn closed_window(host: &FleetHost) -> Option<MaintenanceWindow> {
+    let now = chrono::Local::now().naive_local();
+    host.window.filter(|window| !window.contains(now.time()))
+}
+
+/// Leave a host outside its window alone and say when it could be updated
+async fn hold_host(
+    tx: &CommandSender,
+    host: &FleetHost,
+    window: MaintenanceWindow,
+) -> Result<HostResult> {
+    let now = chrono::Local::now().naive_local();
+    let _ = tx
+        .stdout(format!(
+            "[{}] Outside its maintenance window {} ({}), not updating",
+            host.name,
+            window,
+            window.status(now)
+        ))
+        .await;
+    tx.send(CommandMessage::StepSkipped {
+        step: host.name.clone(),
+    })
+    .await?;
+    Ok(HostResult::Held(window))
+}
+
+/// Check SSH access, build the host's system and switch the remote host to it
+async fn deploy_host(
+    tx: CommandSender,
+    flake_path: String,
+    host: FleetHost,
+    verify: bool,
+) -> HostResult {
+    let started = Instant::now();
+    let target = host.ssh_target().to_string();
+    let _ = tx
+        .send(CommandMessage::StepStarted {
+            step: host.name.clone(),
+        })
+        .await;
+
+    let build = host.build.unwrap_or_default();
+    let result = match deploy(&tx, &flake_path, &host.name, &target, build, verify).await {
+        Ok(()) => HostResult::Updated(started.elapsed()),
+        Err(e) => HostResult::Failed(e),
+    };
+
+    let msg = match &result {
+        HostResult::Failed(e) => CommandMessage::StepFailed {
+            step: host.name.clone(),
+            error: ParsedError::from_stderr(
+                e,
+                ErrorContext {
+                    operation: format!("Update {}", host.name),
+                },
+            ),
+        },
+        _ => CommandMessage::StepComplete {
+            step: host.name.clone(),
+        },
+    };
+    let _ = tx.send(msg).await;
+    result
+}
+
+async fn deploy(
+    tx: &CommandSender,
+    flake_path: &str,
+    name: &str,
+    target: &str,
+    build: BuildHost,
+    verify: bool,
+) -> std::result::Result<(), String> {
+    let prefix = format!("[{}]", name);
+    let stage = |stage| CommandMessage::FleetStage {
+        host: name.to_string(),
+        stage,
+    };
+
+    let _ = tx.send(stage(DeployStage::Connect)).await;
+    let _ = tx.stdout(format!("{} Connecting to {}", prefix, target)).await;
+
+    // BatchMode fails fast instead of prompting for a password the TUI can't show
+    let connect_timeout = format!("ConnectTimeout={}", SSH_CONNECT_TIMEOUT_SECS);
+    let (ssh_ok, _, stderr) = run_capture(
+        "ssh",
+        &["-o", "BatchMode=yes", "-o", &connect_timeout, target, "true"],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    if !ssh_ok {
+        return Err(format!("SSH to {} failed: {}", target, stderr.trim()));
+    }
+
+    let _ = tx.send(stage(DeployStage::Build)).await;
+    let line_prefix = prefix.clone();
+    let installable = format!(
+        "{}#nixosConfigurations.{}.config.system.build.toplevel",
+        flake_path, name
+    );
+    let store = format!("ssh-ng://{}", target);
+    let mut args = vec!["build", installable.as_str(), "--no-link", "--print-out-paths"];
+    if build == BuildHost::Remote {
+        // Evaluated here, built and kept in the host's store
+        args.extend(["--eval-store", "auto", "--store", store.as_str()]);
+    }
+    let location = match build {
+        BuildHost::Local => "this machine",
+        BuildHost::Remote => target,
+    };
+    let _ = tx.stdout(format!("{} Building on {}", prefix, location)).await;
+    let built = Arc::new(Mutex::new(None));
+    let observed = Arc::clone(&built);
+    let success = run_command_transformed(tx, "nix", &args, move |line| {
+        if line.starts_with("/nix/store/") {
+            if let Ok(mut built) = observed.lock() {
+                *built = Some(line.trim().to_string());
+            }
+        }
+        transform_nix_output(line).map(|l| format!("{} {}", line_prefix, l))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    if !success {
+        return Err(format!("Building {} failed - see output above", name));
+    }
+    let system = built.lock().ok().and_then(|b| b.clone());
+    match system {
+        Some(system) => {
+            let store = (build == BuildHost::Remote).then_some(store.as_str());
+            check_provenance(tx, &prefix, name, &system, store, target, verify).await?;
+        }
+        None if verify => return Err(format!("nix build printed no store path for {}", name)),
+        None => {}
+    }
+
+    let _ = tx.send(stage(DeployStage::Switch)).await;
+    let line_prefix = prefix.clone();
+    let flake_ref = format!("{}#{}", flake_path, name);
+    let mut args = vec![
+        "switch",
+        "--flake",
+        flake_ref.as_str(),
+        "--target-host",
+        target,
+        "--use-remote-sudo",
+    ];
+    if build == BuildHost::Remote {
+        // Finds the build already on the host, so nothing is copied
+        args.extend(["--build-host", target]);
+    }
+    let success = run_command_transformed(tx, "nixos-rebuild", &args, move |line| {
+        transform_nix_output(line).map(|l| format!("{} {}", line_prefix, l))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    if success {
+        Ok(())
+    } else {
+        Err(format!("nixos-rebuild switch failed on {} - see output above", name))
+    }
+}
+
+/// Sign a local build, record it in the run manifest and, with
+/// `verify_signatures`, have the host check it trusts the system
+async fn check_provenance(
+    tx: &CommandSender,
+    prefix: &str,
+    name: &str,
+    system: &str,
+    store: Option<&str>,
+    target: &str,
+    verify: bool,
+) -> std::result::Result<(), String> {
+    if store.is_none() {
+        match provenance::sign(system).await {
+            Ok(true) => {
+                let _ = tx.stdout(format!("{} Signed {}", prefix, system)).await;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                let _ = tx.stdout(format!("{} ⚠ Could not sign: {:#}", prefix, e)).await;
+            }
+        }
+    }
+    match provenance::inspect(system, store).await {
+        Ok(info) => {
+            if let Err(e) = record_build(name, &info) {
+                tracing::warn!("Failed to record {} in the run manifest: {}", name, e);
+            }
+            let _ = tx
+                .stdout(format!("{} {}, {}", prefix, info.nar_hash, info.signing_status()))
+                .await;
+        }
+        Err(e) => {
+            let _ = tx.stdout(format!("{} ⚠ No provenance: {:#}", prefix, e)).await;
+        }
+    }
+    if !verify {
+        return Ok(());
+    }
+
+    if store.is_none() {
+        // The host's store only accepts it with a signature it trusts
+        let to = format!("ssh-ng://{}", target);
+        let (copied, _, stderr) = run_capture("nix", &["copy", "--to", &to, system])
+            .await
+            .map_err(|e| e.to_string())?;
+        if !copied {
+            return Err(format!("Copying to {} failed: {}", target, stderr.trim()));
+        }
+    }
+    provenance::verify_on(target, system)
+        .await
+        .map_err(|e| format!("{:#}", e))?;
+    let _ = tx.stdout(format!("{} {} trusts the signature", prefix, target)).await;
+    Ok(())
+}
+
+/// One line per host for the closing summary
+fn summary_lines(plan: &RolloutPlan, results: &[(String, HostResult)]) -> Vec<String> {
+    let width = results.iter().map(|(n, _)| n.len()).max().unwrap_or(0);
+    results
+        .iter()
+        .map(|(name, result)| {
+            let label = if plan.is_canary(name) {
+                format!("{} (canary)", name)
+            } else {
+                name.clone()
+            };
+            let label = format!("{:<width$}", label, width = width + 9);
+            match result {
+                HostResult::Updated(took) => {
+                    format!("  ✓ {} updated in {}", label, format_elapsed(*took))
+                }
+                HostResult::Failed(e) => format!("  ✗ {} {}", label, e),
+                HostResult::Held(window) => {
+                    format!("  - {} held (maintenance window {})", label, window)
+                }
+                HostResult::Skipped => format!("  - {} skipped (rollout stopped)", label),
+            }
+        })
+        .collect()
+}
+
+fn format_elapsed(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(name: &str) -> FleetHost {
+        FleetHost {
+            name: name.to_string(),
+            target: None,
+            window: None,
+            build: None,
+        }
+    }
+
+    #[test]
+    fn test_summary_lines() {
+        let plan = RolloutPlan {
+            canaries: vec![host("G1a")],
+            rest: vec![host("kraken"), host("office"), host("lab")],
+            parallel: 1,
+            verify_signatures: false,
+        };
+        let results = vec![
+            ("G1a".to_string(), HostResult::Updated(Duration::from_secs(134))),
+            ("kraken".to_string(), HostResult::Failed("SSH to kraken failed".to_string())),
+            ("office".to_string(), HostResult::Skipped),
+            ("lab".to_string(), HostResult::Held("02:00-05:00".parse().unwrap())),
+        ];
+        let lines = summary_lines(&plan, &results);
+        assert!(lines[0].starts_with("  ✓ G1a (canary)"));
+        assert!(lines[0].ends_with("updated in 2m 14s"));
+        assert!(lines[1].contains("✗ kraken") && lines[1].ends_with("SSH to kraken failed"));
+        assert!(lines[2].ends_with("skipped (rollout stopped)"));
+        assert!(lines[3].ends_with("held (maintenance window 02:00-05:00)"));
+    }
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(Duration::from_secs(42)), "42s");
+        assert_eq!(format_elapsed(Duration::from_secs(61)), "1m 01s");
+    }
+}
@@ -0,0 +1,54 @@
+//! Formatting generated Nix files with the repo's formatter
+//!
+//! Templates and flake.nix edits produce code in forge's own layout. When
+//! forge.toml names a formatter, the touched files are run through it so a
+//! generated host doesn't show up as a wall of whitespace changes. Like
+//! linting, formatting is best effort and never fails the command.
+
+use anyhow::{bail, Result};
+use std::path::Path;
+
+use super::executor::{command_exists, run_capture};
+use super::runner::CommandRunner;
+use crate::system::format::{NixFormatter, RepoConfig};
+
+/// Format `files` (relative to `repo`) if the repo configures a formatter
+pub async fn format_nix_files(runner: &CommandRunner<'_>, repo: &Path, files: &[&str]) {
+    let formatter = match RepoConfig::load(repo) {
+        Ok(config) => config.format.formatter,
+        Err(e) => {
+            runner.err(&format!("  ⚠ Not formatting: {:#}", e)).await;
+            return;
+        }
+    };
+    let Some(formatter) = formatter else {
+        return;
+    };
+
+    runner
+        .out(&format!("  Formatting {} file(s) with {}...", files.len(), formatter))
+        .await;
+    if let Err(e) = run_formatter(&repo.to_string_lossy(), formatter, files).await {
+        runner.err(&format!("  ⚠ {} failed: {}", formatter, e)).await;
+    }
+}
+
+/// Run the formatter inside `repo`, from PATH or nixpkgs
+async fn run_formatter(repo: &str, formatter: NixFormatter, files: &[&str]) -> Result<()> {
+    let installable = formatter.package().map(|p| format!("nixpkgs#{}", p));
+    let mut argv = vec!["-C", repo];
+    match &installable {
+        Some(installable) if !command_exists(formatter.program()).await => {
+            argv.extend(["nix", "run", installable.as_str(), "--"]);
+        }
+        _ => argv.push(formatter.program()),
+    }
+    argv.extend(formatter.args());
+    argv.extend(files);
+
+    let (ok, _, stderr) = run_capture("env", &argv).await?;
+    if !ok {
+        bail!("{}", stderr.lines().last().unwrap_or("exited with an error").trim());
+    }
+    Ok(())
+}
@@ -0,0 +1,55 @@
+//! Garbage collection settings screen (load and save gc.json)
+//!
+//! Changes take effect on each host at its next rebuild (modules/gc.nix).
+
+use anyhow::Result;
+
+use super::executor::run_capture;
+use super::{CommandMessage, CommandSender};
+use crate::constants::{nixos_config_dir, HOSTS_SUBDIR};
+use crate::system::gc::{load_gc_config, save_gc_config, GcConfig, GC_FILE};
+
+/// Save the edited settings (if any) in the background, then send them back reloaded
+pub async fn start_gc_op(tx: CommandSender, save: Option<GcConfig>) -> Result<()> {
+    tokio::spawn(async move {
+        let message = match save {
+            Some(config) => Some(save_config(&config).await.map_err(|e| {
+                tracing::error!("Saving GC settings failed: {}", e);
+                e.to_string()
+            })),
+            None => None,
+        };
+        let repo = nixos_config_dir();
+        let config = load_gc_config(&repo).map_err(|e| format!("{:#}", e));
+
+        // Hosts in the repo, plus any that only have a gc.json entry left
+        let mut hosts: Vec<String> = std::fs::read_dir(repo.join(HOSTS_SUBDIR))
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                    .map(|e| e.file_name().to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Ok(config) = &config {
+            hosts.extend(config.hosts.keys().cloned());
+        }
+        hosts.sort();
+        hosts.dedup();
+
+        let _ = tx.send(CommandMessage::Gc { config, hosts, message }).await;
+    });
+    Ok(())
+}
+
+async fn save_config(config: &GcConfig) -> Result<String> {
+    let repo = nixos_config_dir();
+    save_gc_config(&repo, config)?;
+    // Flakes only see tracked files; intent-to-add leaves the index otherwise alone
+    let repo_str = repo.to_string_lossy();
+    if let Err(e) = run_capture("git", &["-C", &repo_str, "add", "-N", GC_FILE]).await {
+        tracing::warn!("Could not add {} to git: {}", GC_FILE, e);
+    }
+    Ok(format!("Saved {} - commit and rebuild to apply", GC_FILE))
+}
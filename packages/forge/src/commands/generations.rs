@@ -0,0 +1,107 @@
+//! Generations screen operations (delete, set the boot default)
+//!
+//! Both change the system profile with nix-env and then rewrite the boot menu
+//! with `switch-to-configuration boot`; the running system is left alone.
+//! sudo runs non-interactively, as a password prompt can't show in the TUI.
+
+use anyhow::{bail, Context, Result};
+
+use super::executor::run_capture;
+use super::{CommandMessage, CommandSender};
+use crate::system::generations::{
+    generation_link, list_generations, parse_closure_sizes, Generation, SYSTEM_PROFILE,
+};
+
+/// A change requested from the generations screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerationsOp {
+    Delete(Vec<u32>),
+    /// Make the profile (and so the boot menu's default) point at a generation
+    SetBootDefault(u32),
+}
+
+/// Apply an operation (if any) in the background, then send the refreshed list
+pub async fn start_generations_op(tx: CommandSender, op: Option<GenerationsOp>) -> Result<()> {
+    tokio::spawn(async move {
+        let mut message = None;
+        if let Some(op) = op {
+            message = Some(apply(op).await.map_err(|e| {
+                tracing::error!("Generation change failed: {}", e);
+                format!("{:#}", e)
+            }));
+        }
+        let generations = load_generations().await.map_err(|e| format!("{:#}", e));
+        let _ = tx.send(CommandMessage::GenerationTable { generations, message }).await;
+    });
+    Ok(())
+}
+
+async fn apply(op: GenerationsOp) -> Result<String> {
+    let message = match op {
+        GenerationsOp::Delete(numbers) => {
+            let numbers: Vec<String> = numbers.iter().map(u32::to_string).collect();
+            let mut args = vec!["nix-env", "--profile", SYSTEM_PROFILE, "--delete-generations"];
+            args.extend(numbers.iter().map(String::as_str));
+            sudo(&args).await?;
+            let noun = if numbers.len() == 1 { "generation" } else { "generations" };
+            format!(
+                "Deleted {} {} - garbage collection frees their space",
+                noun,
+                numbers.join(", ")
+            )
+        }
+        GenerationsOp::SetBootDefault(number) => {
+            let generation = number.to_string();
+            sudo(&["nix-env", "--profile", SYSTEM_PROFILE, "--switch-generation", &generation])
+                .await?;
+            format!("Generation {} boots by default", number)
+        }
+    };
+
+    // The boot menu lists the profile's generations and defaults to the current one
+    let activate = format!("{}/bin/switch-to-configuration", SYSTEM_PROFILE);
+    sudo(&[&activate, "boot"])
+        .await
+        .context("The profile changed but the boot menu could not be updated")?;
+    Ok(message)
+}
+
+async fn sudo(args: &[&str]) -> Result<()> {
+    let mut sudo_args = vec!["-n"];
+    sudo_args.extend(args);
+    let (ok, _, stderr) = run_capture("sudo", &sudo_args).await?;
+    if !ok {
+        bail!("{} failed: {}", args.first().unwrap_or(&"sudo"), stderr.trim());
+    }
+    Ok(())
+}
+
+/// Generations with their closure sizes (left out if `nix path-info` fails)
+async fn load_generations() -> Result<Vec<Generation>> {
+    let mut generations = tokio::task::spawn_blocking(list_generations)
+        .await?
+        .with_context(|| format!("Failed to read {}", SYSTEM_PROFILE))?;
+    if generations.is_empty() {
+        return Ok(generations);
+    }
+
+    let links: Vec<String> = generations
+        .iter()
+        .map(|g| generation_link(g.number).to_string_lossy().to_string())
+        .collect();
+    let mut args = vec!["path-info", "--closure-size"];
+    args.extend(links.iter().map(String::as_str));
+    match run_capture("nix", &args).await {
+        Ok((true, stdout, _)) => {
+            let sizes = parse_closure_sizes(&stdout);
+            for generation in &mut generations {
+                let closure = std::fs::canonicalize(generation_link(generation.number)).ok();
+                generation.closure_size = closure
+                    .and_then(|path| sizes.get(path.to_string_lossy().as_ref()).copied());
+            }
+        }
+        Ok((false, _, stderr)) => tracing::warn!("nix path-info failed: {}", stderr.trim()),
+        Err(e) => tracing::warn!("nix path-info failed: {}", e),
+    }
+    Ok(generations)
+}
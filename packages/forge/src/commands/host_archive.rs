@@ -0,0 +1,179 @@
+//! `forge host archive`: retire a host, the reverse of create-host
+//!
+//! The host's entry leaves `nixosConfigurations` in flake.nix, its files move
+//! to `archive/` in the repo (kept in git history and easy to bring back) and
+//! the forge-state branch records it as decommissioned. The SSH key
+//! `keys-github` registered for it on GitHub is revoked, so the machine's old
+//! disk can't reach the private repos.
+
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use super::executor::{command_exists, get_output};
+use super::perms::confirm;
+use super::report::record_decommission;
+use crate::constants::{nixos_config_dir, FLAKE_NIX, HOSTS_SUBDIR};
+use crate::system::diff::unified_diff;
+use crate::system::flake_edit::{attr_span, remove_span, NIXOS_CONFIGURATIONS};
+use crate::system::fleet::FleetConfig;
+use crate::system::manifest::record_edit;
+
+/// Directory in the repo that archived host files move to
+pub const ARCHIVE_DIR: &str = "archive";
+
+/// Show what archiving `hostname` changes, then do it after confirmation
+pub async fn archive_host(hostname: &str, dry_run: bool, yes: bool) -> Result<()> {
+    let repo = nixos_config_dir();
+    let host_dir = repo.join(HOSTS_SUBDIR).join(hostname);
+    if !host_dir.is_dir() {
+        bail!("{} does not exist", host_dir.display());
+    }
+    let current = get_output("hostname", &[]).await.unwrap_or_default();
+    if current == hostname {
+        bail!("{} is this machine; archive it from another host", hostname);
+    }
+
+    let flake_path = repo.join(FLAKE_NIX);
+    let flake = fs::read_to_string(&flake_path)
+        .with_context(|| format!("Failed to read {}", flake_path.display()))?;
+    let archived_flake = remove_host_entry(&flake, hostname)?;
+    let moves = archive_moves(&repo, hostname);
+    for (_, to) in &moves {
+        if to.exists() {
+            bail!("{} already exists", to.display());
+        }
+    }
+
+    let diff = unified_diff(FLAKE_NIX, &flake, &archived_flake);
+    println!("Archiving {}:", hostname);
+    println!();
+    print!("{}", diff);
+    println!();
+    for (from, to) in &moves {
+        println!("  move {} -> {}", relative(&repo, from), relative(&repo, to));
+    }
+    println!("  record {} as decommissioned on the forge-state branch", hostname);
+    println!("  revoke the GitHub key \"{}\"", github_key_title(hostname));
+    if dry_run {
+        return Ok(());
+    }
+
+    let reason = format!("archive host '{}'", hostname);
+    let approved = yes || confirm("Archive this host?")?;
+    if let Err(e) = record_edit(FLAKE_NIX, &reason, approved, &diff) {
+        eprintln!("Failed to write the run manifest: {}", e);
+    }
+    if !approved {
+        println!("Nothing changed");
+        return Ok(());
+    }
+
+    apply(&flake_path, &archived_flake, &moves)?;
+    println!("Archived {} to {}/", hostname, ARCHIVE_DIR);
+
+    match record_decommission(hostname).await {
+        Ok(()) => println!("Recorded {} as decommissioned", hostname),
+        Err(e) => eprintln!("Could not update the forge-state branch: {:#}", e),
+    }
+    if let Err(e) = revoke_github_key(hostname).await {
+        eprintln!("Could not revoke the GitHub key: {:#}", e);
+        eprintln!(
+            "  Remove the key titled \"{}\" on GitHub by hand",
+            github_key_title(hostname)
+        );
+    }
+    let in_fleet = FleetConfig::load(&repo)
+        .map(|fleet| fleet.hosts.iter().any(|h| h.name == hostname))
+        .unwrap_or(false);
+    if in_fleet {
+        println!("{} is still listed in fleet.toml; remove its [[host]] entry", hostname);
+    }
+    println!("Commit the changes to take {} out of the flake", hostname);
+    Ok(())
+}
+
+/// Title `keys-github` registers a host's SSH key under
+fn github_key_title(hostname: &str) -> String {
+    format!("{} (forge)", hostname)
+}
+
+/// Delete the host's key from the GitHub account and the app-backup deploy keys
+async fn revoke_github_key(hostname: &str) -> Result<()> {
+    if !command_exists("keys-github").await {
+        bail!("keys-github is not installed (programs.app-backup)");
+    }
+    let title = github_key_title(hostname);
+    let status = Command::new("keys-github")
+        .args(["--revoke", "--title", &title])
+        .status()
+        .await
+        .context("Failed to run keys-github")?;
+    if !status.success() {
+        bail!("keys-github --revoke failed");
+    }
+    Ok(())
+}
+
+/// What create-host wrote for the host, paired with where it goes in the archive
+fn archive_moves(repo: &Path, hostname: &str) -> Vec<(PathBuf, PathBuf)> {
+    [
+        format!("{}/{}", HOSTS_SUBDIR, hostname),
+        format!("modules/disko/{}.nix", hostname),
+    ]
+    .into_iter()
+    .map(|rel| (repo.join(&rel), repo.join(ARCHIVE_DIR).join(&rel)))
+    .filter(|(from, _)| from.exists())
+    .collect()
+}
+
+/// Move the host's files and write the new flake.nix, undoing the moves if one fails
+fn apply(flake_path: &Path, flake: &str, moves: &[(PathBuf, PathBuf)]) -> Result<()> {
+    let mut done: Vec<&(PathBuf, PathBuf)> = Vec::new();
+    let mut result = Ok(());
+    for entry in moves {
+        let (from, to) = entry;
+        result = move_path(from, to)
+            .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()));
+        if result.is_err() {
+            break;
+        }
+        done.push(entry);
+    }
+    if result.is_ok() {
+        result = fs::write(flake_path, flake)
+            .with_context(|| format!("Failed to write {}", flake_path.display()));
+    }
+    if result.is_err() {
+        for (from, to) in done.into_iter().rev() {
+            if let Err(e) = fs::rename(to, from) {
+                eprintln!("  Failed to move {} back: {}", to.display(), e);
+            }
+        }
+    }
+    result
+}
+
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(from, to)
+}
+
+fn relative(repo: &Path, path: &Path) -> String {
+    path.strip_prefix(repo).unwrap_or(path).display().to_string()
+}
+
+/// Remove `hostname`'s entry from nixosConfigurations, with the comments above it
+fn remove_host_entry(content: &str, hostname: &str) -> Result<String> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    if !content.contains(NIXOS_CONFIGURATIONS) {
+        bail!("Could not find nixosConfigurations block in flake.nix");
+    }
+    let Some((start, end)) = attr_span(&lines, NIXOS_CONFIGURATIONS, hostname) else {
+        bail!("{} is not in nixosConfigurations in flake.nix", hostname);
+    };
+    Ok(remove_span(&lines, start, end))
+}
@@ -3,24 +3,64 @@
 //! This module handles the complete NixOS installation process, broken down into steps:
 //! 1. Network check
 //! 2. Enable flakes
-//! 3. Clone/prepare configuration repository
-//! 4. Configure disk device
-//! 5. Run disko (partition and format)
-//! 6. Install NixOS
-//! 7. Set user password
-
-use anyhow::{Context, Result};
-use std::sync::LazyLock;
-use tokio::sync::mpsc;
-
-use super::errors::{ErrorContext, ParsedError};
-use super::executor::{run_capture, run_command_sensitive};
+//! 3. Clone/prepare configuration repository (then the optional binary cache,
+//!    extra user and SSH key settings)
+//! 4. Configure disk devices (or create partitions in free space, for dual boot)
+//! 5. Check drive health (SMART / NVMe, warning only), then securely erase
+//!    the target disks (optional)
+//! 6. Run disko (partition and format)
+//! 7. Enroll a TPM2 key for LUKS (optional)
+//! 8. Create Secure Boot keys (optional)
+//! 9. Install NixOS
+//! 10. Set user passwords
+//! 11. Enroll Secure Boot keys (optional)
+//!
+//! `forge install --target` prepares the config here and hands partitioning and
+//! installing to nixos-anywhere on the remote machine (see the remote section).
+//!
+//! `forge install --offline DIR` copies the config from DIR instead of cloning
+//! it, skips the network check and keeps nix off the network (see
+//! `system::offline`).
+
+use anyhow::{bail, Context, Result};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use super::errors::{nix_error_message, ErrorContext, ParsedError};
+use super::executor::{run_capture, run_command_sensitive, run_command_transformed};
+use super::format::format_nix_files;
+use super::review::review_edit;
+use super::update::report::UpdateReport;
 use super::runner::CommandRunner;
-use super::CommandMessage;
+use super::{command_channel, CommandMessage, CommandReceiver, CommandSender, OutputLine};
+use crate::app::ShellRequest;
 use crate::constants::{
-    self, INSTALL_MOUNT_POINT, INSTALL_SYMLINK_PATH, NIXOS_CONFIG_HOME_DIR,
-    PRIMARY_USER_GID, PRIMARY_USER_UID,
+    self, FLAKE_DEFAULT_USERNAME, INSTALL_MOUNT_POINT, INSTALL_SYMLINK_PATH,
+    NIXOS_CONFIG_HOME_DIR,
+};
+use crate::system::accounts::{self, ExtraAccounts, KeySource, PrimaryUser};
+use crate::system::locale::{self, LocaleSettings};
+use crate::system::answers::InstallAnswers;
+use crate::system::install_settings;
+use crate::system::config::load_host_metadata;
+use crate::system::disk::erase::{
+    detect_erase_method, erase_progress, parse_blkdiscard_line, parse_sanitize_log, EraseMethod,
+    SanitizeStatus, SANITIZE_PROGRESS_MAX,
+};
+use crate::system::disk::health::check_disk_health;
+use crate::system::disk::identity::check_disk_drift;
+use crate::system::disk::layout::{host_swap, supports_size_editing, DiskSwap, PartitionLayout};
+use crate::system::disk::{
+    get_available_disks, read_partition_table, stable_disk_path, DiskAssignment, DiskMode, DiskRole,
+    PartitionTable, MIN_ALONGSIDE_BYTES,
 };
+use crate::system::hardware::{detect_boot_mode, detect_secure_boot, BootMode, SecureBootSupport};
+use crate::system::install_progress::InstallProgress;
+use crate::system::network::PROXY_CHECK_ARGS;
+use crate::system::nix_settings;
+use crate::system::offline::{self, OfflineSource};
+use crate::system::optimise::format_bytes;
+use crate::templates;
 
 // =============================================================================
 // Install Constants
@@ -29,12 +69,56 @@ use crate::constants::{
 /// Path to the temporary LUKS password file (used by disko)
 const LUKS_PASSWORD_FILE: &str = "/tmp/luks-password";
 
+/// Shared disko layout for UEFI hosts (GPT + ESP)
+const DISKO_UEFI_LAYOUT: &str = "default.nix";
+
+/// Shared disko layout for legacy BIOS hosts (MBR + GRUB)
+const DISKO_BIOS_LAYOUT: &str = "bios.nix";
+
+/// Shared disko layout for ZFS root hosts (UEFI only, key via LUKS_PASSWORD_FILE)
+const DISKO_ZFS_LAYOUT: &str = "zfs.nix";
+
+/// Shared disko layout for installs next to another OS (two pre-created partitions)
+const DISKO_ALONGSIDE_LAYOUT: &str = "alongside.nix";
+
+/// Shared disko layout for a root filesystem mirrored across two disks (btrfs raid1)
+const DISKO_MIRROR_LAYOUT: &str = "mirror.nix";
+
+/// Extra disk holding /home, imported next to the root layout
+const DISKO_HOME_LAYOUT: &str = "home-disk.nix";
+
+/// Partition labels the alongside layout expects (see modules/disko/alongside.nix)
+const ALONGSIDE_BOOT_LABEL: &str = "nixos-boot";
+const ALONGSIDE_ROOT_LABEL: &str = "nixos-root";
+
+/// Size of the ESP created for an alongside install, same as the default layout
+const ALONGSIDE_BOOT_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Where the Windows boot manager lives on its ESP
+const WINDOWS_LOADER_PATH: &str = "/EFI/Microsoft/Boot/bootmgfw.efi";
+
+/// Host module that switches the bootloader to lanzaboote
+const SECURE_BOOT_MODULE: &str = "../../modules/boot/secure-boot.nix";
+
+/// Host module with the swapfile's resume offset, written during the install
+const HIBERNATION_MODULE: &str = "./hibernation.nix";
+
+/// `boot.lanzaboote.pkiBundle` (see modules/boot/secure-boot.nix)
+const SBCTL_DIR: &str = "/var/lib/sbctl";
+
+/// Swapfile disko creates in the @swap subvolume (see subvolumes.nix), below the target
+const SWAPFILE: &str = "/swap/swapfile";
+
+/// PCR the TPM2 key is sealed against: the Secure Boot state, so firmware and
+/// kernel updates don't lock the disks
+const TPM2_PCRS: &str = "--tpm2-pcrs=7";
+
+/// How often step progress is sent to the UI; nix logs a line per store path
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
 /// GitHub repository URL for the NixOS configuration
 const REPO_URL: &str = "https://github.com/DigitalPals/nixos-config.git";
 
-/// Default username used in the configuration
-const DEFAULT_USERNAME: &str = "john";
-
 // =============================================================================
 // Regex Patterns
 // =============================================================================
@@ -45,9 +129,10 @@ static DISK_DEVICE_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
         .expect("Disk device regex pattern is statically validated")
 });
 
-/// Regex to match the LUKS content section where we need to inject passwordFile.
+/// Regex to match the LUKS content sections where we need to inject passwordFile
+/// (cryptroot, plus crypthome/cryptmirror on multi-disk layouts and cryptswap).
 static LUKS_NAME_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
-    regex::Regex::new(r#"(name = "cryptroot";)"#)
+    regex::Regex::new(r#"(name = "crypt[a-z]+";)"#)
         .expect("LUKS name regex pattern is statically validated")
 });
 
@@ -62,20 +147,54 @@ fn get_symlink_target(username: &str) -> String {
 }
 
 /// Start the installation process
+#[allow(clippy::too_many_arguments)]
 pub async fn start_install(
-    tx: mpsc::Sender<CommandMessage>,
+    tx: CommandSender,
     hostname: &str,
     disk: &str,
-    username: &str,
+    disk_mode: DiskMode,
+    extra_disks: &[DiskAssignment],
+    user: &PrimaryUser,
     password: &str,
+    accounts: &ExtraAccounts,
+    locale: Option<&LocaleSettings>,
+    partitions: Option<&PartitionLayout>,
+    hibernation: DiskSwap,
+    passphrase: Option<&str>,
+    tpm2_unlock: bool,
+    secure_boot: bool,
+    secure_erase: bool,
 ) -> Result<()> {
     let hostname = hostname.to_string();
     let disk = disk.to_string();
-    let username = username.to_string();
+    let extra_disks = extra_disks.to_vec();
+    let user = user.clone();
     let password = password.to_string();
+    let accounts = accounts.clone();
+    let locale = locale.cloned();
+    let partitions = partitions.cloned();
+    let passphrase = passphrase.map(str::to_string);
 
     tokio::spawn(async move {
-        if let Err(e) = run_install(&tx, &hostname, &disk, &username, &password).await {
+        if let Err(e) = run_install(
+            &tx,
+            &hostname,
+            &disk,
+            disk_mode,
+            &extra_disks,
+            &user,
+            &password,
+            &accounts,
+            locale.as_ref(),
+            partitions.as_ref(),
+            hibernation,
+            passphrase.as_deref(),
+            tpm2_unlock,
+            secure_boot,
+            secure_erase,
+        )
+        .await
+        {
             tracing::error!("Installation failed: {}", e);
             let _ = tx
                 .send(CommandMessage::StepFailed {
@@ -94,17 +213,342 @@ pub async fn start_install(
     Ok(())
 }
 
+/// Run the install from an answer file, printing progress instead of the TUI
+pub async fn run_unattended(answers: &InstallAnswers) -> Result<()> {
+    if !crate::system::is_live_iso_environment() {
+        bail!("Install can only be run from a NixOS Live ISO");
+    }
+    let password = answers.password()?;
+
+    println!(
+        "Installing {} on {} for user {} (unattended)",
+        answers.hostname, answers.disk, answers.username
+    );
+
+    let settings = &install_settings::current().user;
+    let user = PrimaryUser {
+        name: answers.username.clone(),
+        uid: settings.uid,
+        gid: settings.gid,
+    };
+    let (tx, mut rx) = command_channel();
+    start_install(
+        tx,
+        &answers.hostname,
+        &answers.disk,
+        DiskMode::Wipe,
+        &[],
+        &user,
+        &password,
+        &ExtraAccounts::default(),
+        Some(&answers.locale()),
+        None,
+        host_swap(&answers.hostname, None),
+        // The answer file has one password, also used as the LUKS passphrase
+        answers.encrypt.then_some(password.as_str()),
+        answers.encrypt && answers.tpm2_unlock,
+        answers.secure_boot,
+        answers.secure_erase,
+    )
+    .await?;
+
+    if !print_progress(&mut rx).await {
+        bail!("Installation of {} failed", answers.hostname);
+    }
+    if answers.reboot {
+        println!("Rebooting...");
+        run_capture("systemctl", &["reboot"]).await?;
+    }
+    Ok(())
+}
+
+/// Show what an install would do without writing to any disk (`forge install --plan`)
+///
+/// Runs the non-destructive part of the install against the temporary clone:
+/// disk validation, the rendered disko config, disko's format script built with
+/// `--dry-run`, and a dry build of the system closure.
+pub async fn run_plan(
+    hostname: &str,
+    disk: &str,
+    username: Option<&str>,
+    encrypt: bool,
+) -> Result<()> {
+    let settings = &install_settings::current().user;
+    let user = PrimaryUser {
+        name: username
+            .or(settings.name.as_deref())
+            .unwrap_or(FLAKE_DEFAULT_USERNAME)
+            .to_string(),
+        uid: settings.uid,
+        gid: settings.gid,
+    };
+    println!("Planning install of {} on {} for user {} (dry run)", hostname, disk, user.name);
+
+    let (tx, mut rx) = command_channel();
+    let hostname = hostname.to_string();
+    let disk = disk.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = plan_install(&tx, &hostname, &disk, &user, encrypt).await {
+            tracing::error!("Install plan failed: {}", e);
+            let _ = tx
+                .send(CommandMessage::StepFailed {
+                    step: "Plan".to_string(),
+                    error: ParsedError::from_stderr(
+                        &e.to_string(),
+                        ErrorContext {
+                            operation: "Install plan".to_string(),
+                        },
+                    ),
+                })
+                .await;
+            let _ = tx.send(CommandMessage::Done { success: false }).await;
+        }
+    });
+
+    if !print_progress(&mut rx).await {
+        bail!("The install plan found problems, see above");
+    }
+    Ok(())
+}
+
+/// Print command output to the terminal until Done; returns whether it succeeded
+pub(crate) async fn print_progress(rx: &mut CommandReceiver) -> bool {
+    print_progress_recorded(rx).await.success
+}
+
+/// What a headless run printed, for reports after it (the update mail)
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PrintedRun {
+    pub success: bool,
+    /// Output lines, in order
+    pub lines: Vec<String>,
+    /// Error panel of each failed step
+    pub errors: Vec<Vec<String>>,
+    /// Summary the update sent at its end
+    pub report: Option<UpdateReport>,
+}
+
+/// `print_progress`, keeping what was printed
+pub(crate) async fn print_progress_recorded(rx: &mut CommandReceiver) -> PrintedRun {
+    fn print_line(run: &mut PrintedRun, line: OutputLine) {
+        match &line {
+            OutputLine::Stdout(text) => println!("{}", text),
+            OutputLine::Stderr(text) => eprintln!("{}", text),
+            skipped @ OutputLine::Skipped(_) => eprintln!("{}", skipped.text()),
+        }
+        run.lines.push(line.text());
+    }
+
+    let mut run = PrintedRun::default();
+    while let Some(received) = rx.recv().await {
+        for line in received.lines {
+            print_line(&mut run, line);
+        }
+        let Some(msg) = received.event else {
+            continue;
+        };
+        match msg {
+            CommandMessage::StepComplete { step } => println!("==> {} done", step),
+            CommandMessage::StepFailed { step, error } => {
+                eprintln!("==> {} failed: {}", step, error.summary);
+                if let Some(detail) = &error.detail {
+                    eprintln!("    {}", detail);
+                }
+                eprintln!("    {}", error.suggestion);
+                run.errors.push(error.panel_lines(&step));
+            }
+            CommandMessage::Warning(message) => eprintln!("Warning: {}", message),
+            CommandMessage::UpdateReport(report) => run.report = Some(*report),
+            // Nobody is there to ask: show the change, and the manifest keeps it
+            CommandMessage::ReviewEdit(review) => {
+                println!("==> {}: {}", review.file, review.reason);
+                print!("{}", review.diff);
+                review.answer(true);
+            }
+            CommandMessage::Done { success } => {
+                run.success = success;
+                return run;
+            }
+            _ => {}
+        }
+    }
+    run
+}
+
+/// The install steps up to disko, with every destructive action replaced by a preview
+async fn plan_install(
+    tx: &CommandSender,
+    hostname: &str,
+    disk: &str,
+    user: &PrimaryUser,
+    encrypt: bool,
+) -> Result<()> {
+    let runner = CommandRunner::new(tx);
+
+    step_enable_flakes(&runner).await?;
+    let Some(temp_config) = step_prepare_repository(&runner, hostname).await? else {
+        return Ok(());
+    };
+    if !step_configure_disk(&runner, &temp_config, hostname, disk, DiskMode::Wipe, &[], user, None)
+        .await?
+    {
+        return Ok(());
+    }
+    step_check_disk_health(&runner, disk, &[]).await?;
+
+    let temp_config_str = temp_config.to_string_lossy();
+    let disko_host_file = format!("{}/modules/disko/{}.nix", temp_config_str, hostname);
+    let mut host_disko = std::fs::read_to_string(&disko_host_file)
+        .with_context(|| format!("Failed to read disko config: {}", disko_host_file))?;
+    if !encrypt {
+        host_disko = disable_encryption(&host_disko);
+        std::fs::write(&disko_host_file, &host_disko)
+            .with_context(|| format!("Failed to write disko config: {}", disko_host_file))?;
+    }
+
+    runner.out("").await;
+    describe_wiped_disk(&runner, disk).await;
+    runner.out("").await;
+    runner.out(&format!("Disko configuration (modules/disko/{}.nix):", hostname)).await;
+    for line in host_disko.lines() {
+        runner.out(&format!("  {}", line)).await;
+    }
+    runner.out("").await;
+
+    // disko only builds the script with --dry-run; it is printed, not run
+    runner.out("Building the disko format script (dry run)...").await;
+    let disko = format!("{}#disko", temp_config_str);
+    let flake = format!("{}#{}", temp_config_str, hostname);
+    let mut args = vec!["run", &disko];
+    args.extend(offline::nix_args());
+    args.extend(["--", "--mode", "format", "--dry-run", "--flake", &flake]);
+    let (ok, stdout, stderr) = run_capture("nix", &args).await?;
+    let script = stdout.lines().last().unwrap_or_default().trim().to_string();
+    if !ok || script.is_empty() {
+        runner.step_failed("disko", &stderr, "Disko dry run").await?;
+        runner.done(false).await?;
+        return Ok(());
+    }
+    runner.out(&format!("Format script: {}", script)).await;
+    if let Ok(content) = std::fs::read_to_string(&script) {
+        runner.out("It would run:").await;
+        for command in destructive_commands(&content) {
+            runner.out(&format!("  {}", command)).await;
+        }
+    }
+    runner.step_complete("disko").await?;
+    runner.out("").await;
+
+    runner.out("Dry-building the system configuration...").await;
+    let toplevel = format!(
+        "{}#nixosConfigurations.{}.config.system.build.toplevel",
+        temp_config_str, hostname
+    );
+    let mut args = vec!["build", &toplevel, "--dry-run"];
+    args.extend(offline::nix_args());
+    if !runner.run("nix", &args).await? {
+        runner.step_failed("install", "The configuration failed to build", "Dry build").await?;
+        runner.done(false).await?;
+        return Ok(());
+    }
+    runner.step_complete("install").await?;
+
+    runner.out("").await;
+    runner.out("Plan complete: nothing was partitioned, formatted or installed.").await;
+    runner.out(&format!("Run forge install {} {} to install for real.", hostname, disk)).await;
+    runner.done(true).await?;
+    Ok(())
+}
+
+/// Print the disk that would be erased and the partitions on it
+async fn describe_wiped_disk(runner: &CommandRunner<'_>, disk: &str) {
+    let info = get_available_disks()
+        .ok()
+        .and_then(|disks| disks.into_iter().find(|d| d.path == disk));
+    let Some(info) = info else {
+        runner.out(&format!("{} would be erased", disk)).await;
+        return;
+    };
+    let model = info.model.as_deref().unwrap_or("unknown model");
+    runner.out(&format!("{} ({}, {}) would be erased", disk, info.size, model)).await;
+    if info.partitions.is_empty() {
+        runner.out("  It has no partitions").await;
+    }
+    for part in &info.partitions {
+        let mut line = format!("  {} {}", part.path, part.size);
+        if !part.fstype.is_empty() {
+            line.push_str(&format!(" {}", part.fstype));
+        }
+        if let Some(label) = &part.label {
+            line.push_str(&format!(" \"{}\"", label));
+        }
+        runner.out(&line).await;
+    }
+}
+
+/// Commands in a disko script that change the disks (partitioning, LUKS, mkfs)
+fn destructive_commands(script: &str) -> Vec<String> {
+    const PROGRAMS: &[&str] = &[
+        "sgdisk", "parted", "sfdisk", "wipefs", "blkdiscard", "cryptsetup", "mkswap", "zpool",
+        "zfs", "btrfs",
+    ];
+    script
+        .lines()
+        .map(str::trim)
+        .filter(|line| {
+            let program = line.split_whitespace().next().unwrap_or_default();
+            PROGRAMS.contains(&program) || program.starts_with("mkfs")
+        })
+        .map(str::to_string)
+        .collect()
+}
+
 // =============================================================================
 // Installation Steps
 // =============================================================================
 
+/// Use the config in `dir` for this run's installs instead of cloning it
+///
+/// Copies the repo to the temporary config directory, where the repository
+/// step finds it, before the TUI (or an unattended install) starts.
+pub fn prepare_offline(dir: &std::path::Path) -> Result<()> {
+    let source = OfflineSource::locate(dir)?;
+    let temp_config = constants::temp_config_dir();
+    let _ = std::fs::remove_dir_all(&temp_config);
+    copy_dir_recursive(&source.repo.to_string_lossy(), &temp_config.to_string_lossy())
+        .with_context(|| format!("Failed to copy {}", source.repo.display()))?;
+    tracing::info!("Offline install from {:?} (cache: {:?})", source.repo, source.cache);
+    offline::enable(source);
+    Ok(())
+}
+
 /// Step 1: Check network connectivity
 async fn step_check_network(runner: &CommandRunner<'_>) -> Result<bool> {
+    if let Some(source) = offline::source() {
+        runner.out(&format!("Offline install from {}", source.repo.display())).await;
+        match &source.cache {
+            Some(cache) => {
+                runner.out(&format!("  Store paths come from {}", cache.display())).await
+            }
+            None => runner.out("  No nix-cache found: store paths must be on the ISO").await,
+        }
+        runner.step_complete("network").await?;
+        return Ok(true);
+    }
+
     runner.out("Checking network connectivity...").await;
 
-    let (success, _, _) = run_capture("ping", &["-c", "1", "-W", "5", "github.com"]).await?;
+    // ICMP rarely makes it through a proxy; ask github.com over HTTPS instead
+    let (success, _, _) = if nix_settings::current().proxy.is_some() {
+        nix_settings::apply_proxy_env();
+        run_capture("curl", PROXY_CHECK_ARGS).await?
+    } else {
+        run_capture("ping", &["-c", "1", "-W", "5", "github.com"]).await?
+    };
     if !success {
         runner.step_failed("network", "No network connection", "Network check").await?;
+        // The TUI offers WiFi setup and starts the install over
+        runner.tx().send(CommandMessage::NetworkUnavailable).await?;
         runner.done(false).await?;
         return Ok(false);
     }
@@ -116,7 +560,15 @@ async fn step_check_network(runner: &CommandRunner<'_>) -> Result<bool> {
 /// Step 2: Enable Nix flakes
 async fn step_enable_flakes(runner: &CommandRunner<'_>) -> Result<bool> {
     runner.out("Enabling Nix flakes...").await;
-    std::env::set_var("NIX_CONFIG", "experimental-features = nix-command flakes");
+    std::env::set_var("NIX_CONFIG", nix_settings::nix_config());
+    nix_settings::apply_proxy_env();
+    let settings = nix_settings::current();
+    if let Some(proxy) = &settings.proxy {
+        runner.out(&format!("  Using proxy {}", proxy)).await;
+    }
+    for url in &settings.substituters {
+        runner.out(&format!("  Extra binary cache: {}", url)).await;
+    }
     runner.step_complete("flakes").await?;
     Ok(true)
 }
@@ -136,6 +588,11 @@ async fn step_prepare_repository(
 
     if host_exists_in_temp {
         runner.out("Using existing configuration (host already created)...").await;
+    } else if let Some(source) = offline::source() {
+        let error = format!("Host '{}' is not in {}", hostname, source.repo.display());
+        runner.step_failed("repository", &error, "Offline configuration").await?;
+        runner.done(false).await?;
+        return Ok(None);
     } else {
         runner.out("Cloning configuration repository...").await;
         let _ = std::fs::remove_dir_all(&temp_config);
@@ -163,47 +620,249 @@ async fn step_prepare_repository(
     Ok(Some(temp_config))
 }
 
+/// Step 3b: Keep the extra binary caches and proxy in the host config
+///
+/// Only when some were given; the step isn't listed otherwise.
+async fn step_configure_caches(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    hostname: &str,
+) -> Result<()> {
+    let settings = nix_settings::current();
+    if settings.is_empty() {
+        return Ok(());
+    }
+    runner.out("Writing binary cache and proxy settings to the host config...").await;
+    let host_file = temp_config
+        .join(constants::HOSTS_SUBDIR)
+        .join(hostname)
+        .join("default.nix");
+    let content = std::fs::read_to_string(&host_file)
+        .with_context(|| format!("Failed to read {}", host_file.display()))?;
+    if !content.contains(nix_settings::HOST_CONFIG_MARKER) {
+        std::fs::write(&host_file, insert_before_closing_brace(&content, &settings.host_config()))
+            .with_context(|| format!("Failed to write {}", host_file.display()))?;
+        let host_rel = format!("{}/{}/default.nix", constants::HOSTS_SUBDIR, hostname);
+        format_nix_files(runner, temp_config, &[&host_rel]).await;
+    }
+    runner.step_complete("cache").await?;
+    Ok(())
+}
+
+/// Step 3c: Add the extra users and authorized SSH keys to the host config
+///
+/// Only when some were given; the step isn't listed otherwise. Keys from GitHub
+/// are fetched here, before any disk is touched.
+async fn step_configure_accounts(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    hostname: &str,
+    username: &str,
+    accounts: &ExtraAccounts,
+) -> Result<bool> {
+    if accounts.is_empty() {
+        return Ok(true);
+    }
+    runner.out("Adding authorized keys and accounts to the host config...").await;
+
+    let mut keys: Vec<String> = Vec::new();
+    for source in &accounts.key_sources {
+        runner.out(&format!("Reading keys from {}", source.describe())).await;
+        let found = match source {
+            KeySource::GitHub(_) => {
+                let url = source.describe();
+                let (success, stdout, stderr) =
+                    run_capture("curl", &["-fsSL", "--max-time", "15", &url]).await?;
+                if !success {
+                    runner.err(stderr.trim()).await;
+                }
+                accounts::public_keys(&stdout)
+            }
+            KeySource::File(path) => accounts::read_key_file(path)?,
+            KeySource::Key(key) => vec![key.clone()],
+        };
+        if found.is_empty() {
+            let message = format!("No public keys found at {}", source.describe());
+            runner.step_failed("authorized", &message, "Import SSH keys").await?;
+            runner.done(false).await?;
+            return Ok(false);
+        }
+        for key in found {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    if !keys.is_empty() {
+        runner.out(&format!("Authorizing {} key(s) for '{}'", keys.len(), username)).await;
+    }
+    for user in &accounts.users {
+        runner.out(&format!("Adding user '{}'", user)).await;
+    }
+
+    let host_file = temp_config
+        .join(constants::HOSTS_SUBDIR)
+        .join(hostname)
+        .join("default.nix");
+    let content = std::fs::read_to_string(&host_file)
+        .with_context(|| format!("Failed to read {}", host_file.display()))?;
+    if !content.contains(accounts::HOST_CONFIG_MARKER) {
+        let settings = accounts::host_config(username, &accounts.users, &keys);
+        std::fs::write(&host_file, insert_before_closing_brace(&content, &settings))
+            .with_context(|| format!("Failed to write {}", host_file.display()))?;
+        let host_rel = format!("{}/{}/default.nix", constants::HOSTS_SUBDIR, hostname);
+        format_nix_files(runner, temp_config, &[&host_rel]).await;
+    }
+    runner.step_complete("authorized").await?;
+    Ok(true)
+}
+
+/// Step 3d: Write the timezone, locale and keymap into the host config
+///
+/// Only when they were chosen; the step isn't listed otherwise.
+async fn step_configure_locale(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    hostname: &str,
+    settings: Option<&LocaleSettings>,
+) -> Result<()> {
+    let Some(settings) = settings else {
+        return Ok(());
+    };
+    runner
+        .out(&format!(
+            "Setting timezone {}, locale {}, keymap {}...",
+            settings.timezone, settings.locale, settings.keymap
+        ))
+        .await;
+    let host_file = temp_config
+        .join(constants::HOSTS_SUBDIR)
+        .join(hostname)
+        .join("default.nix");
+    let content = std::fs::read_to_string(&host_file)
+        .with_context(|| format!("Failed to read {}", host_file.display()))?;
+    if !content.contains(locale::HOST_CONFIG_MARKER) {
+        std::fs::write(&host_file, insert_before_closing_brace(&content, &settings.host_config()))
+            .with_context(|| format!("Failed to write {}", host_file.display()))?;
+        let host_rel = format!("{}/{}/default.nix", constants::HOSTS_SUBDIR, hostname);
+        format_nix_files(runner, temp_config, &[&host_rel]).await;
+    }
+    runner.step_complete("timezone").await?;
+    Ok(())
+}
+
 /// Step 4: Configure disk device and update disko configuration
+#[allow(clippy::too_many_arguments)]
 async fn step_configure_disk(
     runner: &CommandRunner<'_>,
     temp_config: &std::path::Path,
     hostname: &str,
     disk: &str,
-    username: &str,
+    disk_mode: DiskMode,
+    extra_disks: &[DiskAssignment],
+    user: &PrimaryUser,
+    partitions: Option<&PartitionLayout>,
 ) -> Result<bool> {
     let temp_config_str = temp_config.to_string_lossy();
     runner.out(&format!("Configuring disk device {}...", disk)).await;
+    for extra in extra_disks {
+        runner.out(&format!("Using {} for {}", extra.disk.path, extra.role.description())).await;
+    }
+
+    let disks = std::iter::once(disk).chain(extra_disks.iter().map(|a| a.disk.path.as_str()));
+    for path in disks {
+        // Validate disk path format
+        if !path.starts_with("/dev/") {
+            runner.step_failed(
+                "disk",
+                &format!("Invalid disk path: {}. Must start with /dev/", path),
+                "Disk validation",
+            ).await?;
+            runner.done(false).await?;
+            return Ok(false);
+        }
+
+        // Check that disk device actually exists
+        if !std::path::Path::new(path).exists() {
+            runner.step_failed(
+                "disk",
+                &format!("Disk device does not exist: {}", path),
+                "Disk validation",
+            ).await?;
+            runner.done(false).await?;
+            return Ok(false);
+        }
+    }
+
+    // Device names follow probe order; warn when this isn't the disk the host was made for
+    let host_dir = temp_config.join(constants::HOSTS_SUBDIR).join(hostname);
+    let recorded = load_host_metadata(&host_dir).and_then(|metadata| metadata.disk);
+    if let Some(warning) = recorded.and_then(|recorded| check_disk_drift(&recorded, disk)) {
+        runner.err(&format!("  ⚠ {}", warning)).await;
+    }
 
-    // Validate disk path format
-    if !disk.starts_with("/dev/") {
+    // Check disko config file exists
+    let disko_file = format!("{}/modules/disko/{}.nix", temp_config_str, hostname);
+    if !std::path::Path::new(&disko_file).exists() {
         runner.step_failed(
             "disk",
-            &format!("Invalid disk path: {}. Must start with /dev/", disk),
-            "Disk validation",
+            &format!(
+                "No disko configuration found for host '{}'. Expected: modules/disko/{}.nix",
+                hostname, hostname
+            ),
+            "Disk configuration",
         ).await?;
         runner.done(false).await?;
         return Ok(false);
     }
 
-    // Check that disk device actually exists
-    if !std::path::Path::new(disk).exists() {
+    // Update disko config with disk device
+    let disko_content = std::fs::read_to_string(&disko_file)
+        .with_context(|| format!("Failed to read disko config: {}", disko_file))?;
+
+    // A UEFI layout can't boot on legacy BIOS firmware; catch it before wiping anything
+    if disko_boot_mode(&disko_content) == BootMode::Uefi && detect_boot_mode() == BootMode::Bios {
         runner.step_failed(
             "disk",
-            &format!("Disk device does not exist: {}", disk),
-            "Disk validation",
+            &format!(
+                "Host '{}' uses a UEFI disk layout but this machine booted in legacy BIOS mode. \
+                 Reboot the ISO in UEFI mode or create a new host on this machine.",
+                hostname
+            ),
+            "Disk configuration",
         ).await?;
         runner.done(false).await?;
         return Ok(false);
     }
 
-    // Check disko config file exists
-    let disko_file = format!("{}/modules/disko/{}.nix", temp_config_str, hostname);
-    if !std::path::Path::new(&disko_file).exists() {
+    // The ISO ships without ZFS (it tracks the latest kernel), so check before wiping anything
+    if disko_uses_zfs(&disko_content) {
+        let (zfs_loaded, _, _) = run_capture("modprobe", &["zfs"]).await?;
+        if !zfs_loaded {
+            runner.step_failed(
+                "disk",
+                &format!(
+                    "Host '{}' uses a ZFS root but the ZFS kernel module is not available on \
+                     this ISO. Boot an installer with ZFS support to install this host.",
+                    hostname
+                ),
+                "Disk configuration",
+            ).await?;
+            runner.done(false).await?;
+            return Ok(false);
+        }
+    }
+
+    // Extra disks are added to the UEFI btrfs layout only
+    if !extra_disks.is_empty()
+        && (disko_uses_zfs(&disko_content) || disko_boot_mode(&disko_content) == BootMode::Bios)
+    {
         runner.step_failed(
             "disk",
             &format!(
-                "No disko configuration found for host '{}'. Expected: modules/disko/{}.nix",
-                hostname, hostname
+                "Host '{}' does not use the UEFI btrfs layout, so it can't be installed across \
+                 several disks. Select a single disk instead.",
+                hostname
             ),
             "Disk configuration",
         ).await?;
@@ -211,97 +870,352 @@ async fn step_configure_disk(
         return Ok(false);
     }
 
-    // Update disko config with disk device
-    let disko_content = std::fs::read_to_string(&disko_file)
-        .with_context(|| format!("Failed to read disko config: {}", disko_file))?;
-    let updated_content = update_disk_device(&disko_content, disk);
+    // by-id paths keep naming this disk if the kernel enumerates disks differently
+    let config_disk = stable_disk_path(disk);
+    if config_disk != disk {
+        runner.out(&format!("Writing {} as {}", disk, config_disk)).await;
+    }
+    let updated_content = match disk_mode {
+        DiskMode::Wipe if !extra_disks.is_empty() => {
+            multi_disk_config(&update_disk_device(&disko_content, &config_disk), extra_disks)
+        }
+        DiskMode::Wipe => {
+            let content = update_disk_device(&disko_content, &config_disk);
+            match partitions {
+                Some(_) if !supports_size_editing(&content) => {
+                    runner
+                        .warn("Partition sizes apply to the single-disk UEFI btrfs layout only; keeping the host's")
+                        .await;
+                    content
+                }
+                Some(layout) if PartitionLayout::from_disko(&content) != *layout => {
+                    runner.out(&format!("Partition sizes: {}", layout.describe())).await;
+                    layout.apply(&content)
+                }
+                _ => content,
+            }
+        }
+        DiskMode::Alongside => match prepare_alongside(runner, &disko_content, disk).await? {
+            Ok(content) => content,
+            Err(message) => {
+                runner.step_failed("disk", &message, "Disk configuration").await?;
+                runner.done(false).await?;
+                return Ok(false);
+            }
+        },
+    };
     std::fs::write(&disko_file, &updated_content)
         .with_context(|| format!("Failed to write disko config: {}", disko_file))?;
 
-    // Update flake.nix with username if it differs from default
-    if username != DEFAULT_USERNAME {
-        runner.out(&format!("Configuring username '{}'...", username)).await;
-
-        let flake_file = format!("{}/flake.nix", temp_config_str);
-        let flake_content = std::fs::read_to_string(&flake_file)
-            .with_context(|| format!("Failed to read flake.nix: {}", flake_file))?;
-        let updated_flake = update_flake_username(&flake_content, hostname, username);
-        std::fs::write(&flake_file, &updated_flake)
-            .with_context(|| format!("Failed to write flake.nix: {}", flake_file))?;
+    if !configure_primary_user(runner, temp_config, hostname, user).await? {
+        runner
+            .step_failed("disk", "The flake.nix username change was rejected", "Disk configuration")
+            .await?;
+        runner.done(false).await?;
+        return Ok(false);
     }
 
     runner.step_complete("disk").await?;
     Ok(true)
 }
 
-/// Step 5: Run disko to partition and format disks
-async fn step_run_disko(
+/// Update flake.nix with the username if it differs from the default, and
+/// pin the user's ids in the host config if they differ from NixOS's;
+/// false if the user rejected the flake.nix change
+async fn configure_primary_user(
     runner: &CommandRunner<'_>,
     temp_config: &std::path::Path,
     hostname: &str,
-    password: &str,
+    user: &PrimaryUser,
 ) -> Result<bool> {
-    let temp_config_str = temp_config.to_string_lossy();
-
-    runner.out("Running disko to partition and format...").await;
-    runner.out("Using provided passphrase for LUKS encryption...").await;
+    if user.name != FLAKE_DEFAULT_USERNAME {
+        runner.out(&format!("Configuring username '{}'...", user.name)).await;
 
-    // Write password to temp file for disko
-    std::fs::write(LUKS_PASSWORD_FILE, password.as_bytes())
-        .with_context(|| format!("Failed to write LUKS password file: {}", LUKS_PASSWORD_FILE))?;
+        let flake_file = format!("{}/flake.nix", temp_config.to_string_lossy());
+        let flake_content = std::fs::read_to_string(&flake_file)
+            .with_context(|| format!("Failed to read flake.nix: {}", flake_file))?;
+        let updated_flake = update_flake_username(&flake_content, hostname, &user.name);
+        std::fs::write(&flake_file, &updated_flake)
+            .with_context(|| format!("Failed to write flake.nix: {}", flake_file))?;
+        format_nix_files(runner, temp_config, &[constants::FLAKE_NIX]).await;
 
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        std::fs::set_permissions(LUKS_PASSWORD_FILE, std::fs::Permissions::from_mode(0o600))
-            .with_context(|| format!("Failed to set permissions on {}", LUKS_PASSWORD_FILE))?;
+        // Review the formatted result, and put the original back if it's rejected
+        let written = std::fs::read_to_string(&flake_file)
+            .with_context(|| format!("Failed to read flake.nix: {}", flake_file))?;
+        let reason = format!("set the username of '{}' to '{}'", hostname, user.name);
+        if !review_edit(runner, constants::FLAKE_NIX, &reason, &flake_content, &written).await? {
+            std::fs::write(&flake_file, &flake_content)
+                .with_context(|| format!("Failed to restore flake.nix: {}", flake_file))?;
+            return Ok(false);
+        }
     }
 
-    // Inject passwordFile into disko default.nix
-    let disko_default_file = format!("{}/modules/disko/default.nix", temp_config_str);
-    let disko_default_content = std::fs::read_to_string(&disko_default_file)
-        .with_context(|| format!("Failed to read disko default.nix: {}", disko_default_file))?;
-    let updated_disko = inject_luks_password_file(&disko_default_content);
-    std::fs::write(&disko_default_file, &updated_disko)
-        .with_context(|| format!("Failed to write disko default.nix: {}", disko_default_file))?;
-
-    // Verify passwordFile injection
-    if updated_disko.contains("passwordFile") {
-        runner.out("LUKS passwordFile configured successfully").await;
-        tracing::info!("passwordFile injection confirmed in disko config");
-    } else {
-        runner.err("WARNING: passwordFile injection may have failed!").await;
-        tracing::error!("passwordFile NOT found in modified disko config");
+    let Some(settings) = user.host_config() else {
+        return Ok(true);
+    };
+    runner.out(&format!("Setting uid {} and gid {} for '{}'...", user.uid, user.gid, user.name)).await;
+    let host_rel = format!("{}/{}/default.nix", constants::HOSTS_SUBDIR, hostname);
+    let host_file = temp_config.join(&host_rel);
+    let content = std::fs::read_to_string(&host_file)
+        .with_context(|| format!("Failed to read {}", host_file.display()))?;
+    if !content.contains(accounts::PRIMARY_USER_MARKER) {
+        std::fs::write(&host_file, insert_before_closing_brace(&content, &settings))
+            .with_context(|| format!("Failed to write {}", host_file.display()))?;
+        format_nix_files(runner, temp_config, &[&host_rel]).await;
     }
-
+    Ok(true)
+}
+
+/// Step 5: Report SMART / NVMe health of every target disk before it is formatted
+async fn step_check_disk_health(
+    runner: &CommandRunner<'_>,
+    disk: &str,
+    extra_disks: &[DiskAssignment],
+) -> Result<()> {
+    runner.out("Checking drive health...").await;
+    let disks = std::iter::once(disk).chain(extra_disks.iter().map(|a| a.disk.path.as_str()));
+    for path in disks {
+        match check_disk_health(path) {
+            Some(health) if !health.warnings().is_empty() => {
+                runner.err(&format!("  ⚠ {}: {}", path, health.summary())).await;
+                runner.err("  This drive may be failing; keep backups of anything on it").await;
+            }
+            Some(health) => runner.out(&format!("  {}: {}", path, health.summary())).await,
+            None => runner.out(&format!("  {}: no SMART data reported", path)).await,
+        }
+    }
+    runner.step_complete("health").await?;
+    Ok(())
+}
+
+/// Sanitize-log polls while an NVMe sanitize runs in the drive
+const SANITIZE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Step 5a: Evaluate the host's system before anything touches the disks
+///
+/// A mistake in the host config would otherwise only surface in nixos-install,
+/// after disko wiped the disk. The TPM2, Secure Boot and hibernation settings
+/// are added after disko and aren't covered.
+async fn step_validate_config(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    hostname: &str,
+) -> Result<bool> {
+    runner.out(&format!("Evaluating the {} configuration...", hostname)).await;
+    let toplevel = format!(
+        "{}#nixosConfigurations.{}.config.system.build.toplevel.drvPath",
+        temp_config.to_string_lossy(),
+        hostname
+    );
+    let mut args = vec!["eval", "--raw", toplevel.as_str()];
+    args.extend(offline::nix_args());
+    let (success, _, stderr) = run_capture("nix", &args).await?;
+    if !success {
+        for line in nix_error_message(&stderr).lines() {
+            runner.err(line).await;
+        }
+        runner.err("The configuration does not evaluate; no disk was touched").await;
+        runner.step_failed("Validating", &stderr, "Configuration check").await?;
+        runner.done(false).await?;
+        return Ok(false);
+    }
+    runner.step_complete("Validating").await?;
+    Ok(true)
+}
+
+/// Step 5b: Securely erase every target disk before disko (opt-in)
+async fn step_secure_erase(
+    runner: &CommandRunner<'_>,
+    disk: &str,
+    extra_disks: &[DiskAssignment],
+) -> Result<bool> {
+    let disks = std::iter::once(disk).chain(extra_disks.iter().map(|a| a.disk.path.as_str()));
+    for path in disks {
+        let method = detect_erase_method(path);
+        runner.out(&format!("Erasing {}: {}", path, method.describe())).await;
+        let (program, args) = method.command(path);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let success = match method {
+            _ if method.is_sanitize() => {
+                runner.run(program, &args).await? && wait_for_sanitize(runner, path).await?
+            }
+            EraseMethod::ZeroOut => zero_out_with_progress(runner, path, program, &args).await?,
+            _ => runner.run(program, &args).await?,
+        };
+        if !success {
+            runner
+                .step_failed("erase", &format!("Secure erase of {} failed", path), "Secure erase")
+                .await?;
+            runner.done(false).await?;
+            return Ok(false);
+        }
+    }
+    runner.step_complete("erase").await?;
+    Ok(true)
+}
+
+/// Poll `nvme sanitize-log` until the drive reports the sanitize finished
+async fn wait_for_sanitize(runner: &CommandRunner<'_>, device: &str) -> Result<bool> {
+    let started = Instant::now();
+    loop {
+        tokio::time::sleep(SANITIZE_POLL_INTERVAL).await;
+        let (ok, stdout, _) =
+            run_capture("nvme", &["sanitize-log", "--output-format=json", device]).await?;
+        let status = ok
+            .then(|| serde_json::from_str(&stdout).ok())
+            .flatten()
+            .and_then(|json| parse_sanitize_log(&json));
+        match status {
+            Some(SanitizeStatus::Done) => return Ok(true),
+            Some(SanitizeStatus::Failed) => {
+                runner.err(&format!("  {} reported a failed sanitize", device)).await;
+                return Ok(false);
+            }
+            Some(SanitizeStatus::Running(sprog)) => {
+                if let Some(progress) =
+                    erase_progress(sprog, SANITIZE_PROGRESS_MAX, started.elapsed())
+                {
+                    let _ = runner.tx().try_send(CommandMessage::StepProgress {
+                        step: "erase".to_string(),
+                        progress,
+                    });
+                }
+            }
+            None => {
+                runner.err(&format!("  Could not read the sanitize log of {}", device)).await;
+                return Ok(false);
+            }
+        }
+    }
+}
+
+/// `blkdiscard --zeroout`, with its per-step lines turned into progress
+async fn zero_out_with_progress(
+    runner: &CommandRunner<'_>,
+    device: &str,
+    program: &str,
+    args: &[&str],
+) -> Result<bool> {
+    let (_, size, _) = run_capture("blockdev", &["--getsize64", device]).await?;
+    let total: u64 = size.trim().parse().unwrap_or(0);
+    let tx = runner.tx().clone();
+    let started = Instant::now();
+    run_command_transformed(runner.tx(), program, args, move |line| {
+        let Some(done) = parse_blkdiscard_line(line) else {
+            return Some(line.to_string());
+        };
+        if let Some(progress) = erase_progress(done, total, started.elapsed()) {
+            let _ = tx.try_send(CommandMessage::StepProgress {
+                step: "erase".to_string(),
+                progress,
+            });
+        }
+        // One line per GiB would bury everything else
+        None
+    })
+    .await
+}
+
+/// Step 6: Run disko to partition and format disks
+async fn step_run_disko(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    hostname: &str,
+    passphrase: Option<&str>,
+) -> Result<bool> {
+    let temp_config_str = temp_config.to_string_lossy();
+
+    runner.out("Running disko to partition and format...").await;
+    let disko_host_file = format!("{}/modules/disko/{}.nix", temp_config_str, hostname);
+    let host_disko = std::fs::read_to_string(&disko_host_file).unwrap_or_default();
+
+    let Some(passphrase) = passphrase else {
+        runner.out("Disk encryption is off: formatting without LUKS").await;
+        std::fs::write(&disko_host_file, disable_encryption(&host_disko))
+            .with_context(|| format!("Failed to write disko config: {}", disko_host_file))?;
+        let wipe = disko_uses_zfs(&host_disko) || !disko_is_alongside(&host_disko);
+        return run_disko(runner, &temp_config_str, hostname, wipe).await;
+    };
+
+    runner.out("Using provided passphrase for disk encryption...").await;
+
+    // Write passphrase to temp file for disko
+    write_luks_password_file(passphrase)?;
+
+    // Inject passwordFile into the shared layouts the host imports (default.nix, bios.nix,
+    // plus home-disk.nix etc.); zfs.nix already reads its key from LUKS_PASSWORD_FILE
+    if disko_uses_zfs(&host_disko) {
+        runner.out("ZFS root: the pool key is read from the passphrase file").await;
+        return run_disko(runner, &temp_config_str, hostname, true).await;
+    }
+    let alongside = disko_is_alongside(&host_disko);
+    configure_luks_password_file(runner, &temp_config_str, &host_disko).await?;
+
+    // Alongside installs only format the partitions created in step 4
+    run_disko(runner, &temp_config_str, hostname, !alongside).await
+}
+
+/// Point every LUKS device in the layouts a host imports at LUKS_PASSWORD_FILE
+async fn configure_luks_password_file(
+    runner: &CommandRunner<'_>,
+    temp_config_str: &str,
+    host_disko: &str,
+) -> Result<()> {
+    for layout in disko_layout_files(host_disko) {
+        let disko_default_file = format!("{}/modules/disko/{}", temp_config_str, layout);
+        let disko_default_content = std::fs::read_to_string(&disko_default_file)
+            .with_context(|| format!("Failed to read disko {}: {}", layout, disko_default_file))?;
+        let updated_disko = inject_luks_password_file(&disko_default_content);
+        std::fs::write(&disko_default_file, &updated_disko)
+            .with_context(|| format!("Failed to write disko {}: {}", layout, disko_default_file))?;
+
+        // Verify passwordFile injection
+        if updated_disko.contains("passwordFile") {
+            runner.out(&format!("LUKS passwordFile configured in {}", layout)).await;
+            tracing::info!("passwordFile injection confirmed in disko {}", layout);
+        } else {
+            runner.err(&format!("WARNING: passwordFile injection may have failed in {}!", layout))
+                .await;
+            tracing::error!("passwordFile NOT found in modified disko {}", layout);
+        }
+    }
+    Ok(())
+}
+
+/// Partition, format and mount with disko, then remove the passphrase file
+///
+/// `wipe` destroys everything on the disk first; without it disko only
+/// formats the devices named in the config.
+async fn run_disko(
+    runner: &CommandRunner<'_>,
+    temp_config_str: &str,
+    hostname: &str,
+    wipe: bool,
+) -> Result<bool> {
     // Pre-fetch disko (optional optimization)
-    match runner.run("nix", &["build", &format!("{}#disko", temp_config_str), "--no-link"]).await {
+    let disko = format!("{}#disko", temp_config_str);
+    let mut prefetch = vec!["build", &disko, "--no-link"];
+    prefetch.extend(offline::nix_args());
+    match runner.run("nix", &prefetch).await {
         Ok(true) => tracing::info!("Disko pre-fetch succeeded"),
         Ok(false) => tracing::warn!("Disko pre-fetch failed - continuing anyway"),
         Err(e) => tracing::warn!("Disko pre-fetch error: {} - continuing anyway", e),
     }
 
     // Run disko
-    let success = runner
-        .run(
-            "nix",
-            &[
-                "run",
-                &format!("{}#disko", temp_config_str),
-                "--",
-                "--yes-wipe-all-disks",
-                "--mode",
-                "destroy,format,mount",
-                "--flake",
-                &format!("{}#{}", temp_config_str, hostname),
-            ],
-        )
-        .await?;
-
-    // Clean up password file immediately (security)
-    if let Err(e) = std::fs::remove_file(LUKS_PASSWORD_FILE) {
-        tracing::warn!("Failed to remove LUKS password file: {}", e);
+    let flake = format!("{}#{}", temp_config_str, hostname);
+    let mut args = vec!["run", &disko];
+    args.extend(offline::nix_args());
+    if wipe {
+        args.extend(["--", "--yes-wipe-all-disks", "--mode", "destroy,format,mount"]);
+    } else {
+        args.extend(["--", "--mode", "format,mount"]);
     }
+    args.extend(["--flake", &flake]);
+    let success = run_with_progress(runner, "disko", "nix", &args).await?;
+
+    // Clean up password file immediately (security); absent for unencrypted installs
+    remove_luks_password_file();
 
     if !success {
         runner.step_failed("disko", "Disk partitioning failed", "Disko partitioning").await?;
@@ -313,19 +1227,284 @@ async fn step_run_disko(
     Ok(true)
 }
 
-/// Step 6: Install NixOS
+/// Write the passphrase where disko and systemd-cryptenroll read it, readable by root only
+fn write_luks_password_file(passphrase: &str) -> Result<()> {
+    std::fs::write(LUKS_PASSWORD_FILE, passphrase.as_bytes())
+        .with_context(|| format!("Failed to write LUKS password file: {}", LUKS_PASSWORD_FILE))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(LUKS_PASSWORD_FILE, std::fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to set permissions on {}", LUKS_PASSWORD_FILE))?;
+    }
+    Ok(())
+}
+
+fn remove_luks_password_file() {
+    match std::fs::remove_file(LUKS_PASSWORD_FILE) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            tracing::warn!("Failed to remove LUKS password file: {}", e);
+        }
+        _ => {}
+    }
+}
+
+/// Step 7: Seal a LUKS key in the TPM2 and have the initrd unlock with it
+///
+/// Best effort, like enrolling Secure Boot keys: the passphrase keyslot stays,
+/// so if enrolling fails the disks simply ask for the passphrase at boot.
+async fn step_enroll_tpm2(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    hostname: &str,
+    passphrase: &str,
+    secure_boot: bool,
+) -> Result<()> {
+    runner.out("Enrolling the TPM2 for disk unlock...").await;
+
+    let (listed, stdout, _) = run_capture("lsblk", &["-nrpo", "NAME,TYPE,PKNAME"]).await?;
+    let volumes = if listed { parse_crypt_volumes(&stdout) } else { Vec::new() };
+    if volumes.is_empty() {
+        // ZFS roots use native encryption, which systemd-cryptenroll can't seal
+        runner.warn("No open LUKS volumes, skipping TPM2 unlock").await;
+        runner.step_complete("TPM2").await?;
+        return Ok(());
+    }
+
+    write_luks_password_file(passphrase)?;
+    let unlock = format!("--unlock-key-file={}", LUKS_PASSWORD_FILE);
+    let mut enrolled = Vec::new();
+    for (name, device) in &volumes {
+        let success = runner
+            .run(
+                "systemd-cryptenroll",
+                &[&unlock, "--tpm2-device=auto", TPM2_PCRS, device.as_str()],
+            )
+            .await?;
+        if success {
+            enrolled.push(name.as_str());
+        } else {
+            runner.warn(&format!("TPM2 enrollment failed for {}", device)).await;
+        }
+    }
+    remove_luks_password_file();
+
+    if enrolled.is_empty() {
+        runner.err("The disks will ask for the passphrase at boot").await;
+        runner.step_complete("TPM2").await?;
+        return Ok(());
+    }
+
+    let host_file = temp_config
+        .join(constants::HOSTS_SUBDIR)
+        .join(hostname)
+        .join("default.nix");
+    let content = std::fs::read_to_string(&host_file)
+        .with_context(|| format!("Failed to read {}", host_file.display()))?;
+    std::fs::write(&host_file, add_tpm2_unlock(&content, &enrolled))
+        .with_context(|| format!("Failed to write {}", host_file.display()))?;
+    let host_rel = format!("{}/{}/default.nix", constants::HOSTS_SUBDIR, hostname);
+    format_nix_files(runner, temp_config, &[&host_rel]).await;
+    runner.out(&format!("{} will unlock with the TPM2 at boot", enrolled.join(", "))).await;
+
+    if secure_boot {
+        // Enrolling the Secure Boot keys changes PCR 7, so the sealed key won't match
+        runner.out("After enabling Secure Boot in the firmware, re-seal the key with:").await;
+        for (_, device) in &volumes {
+            runner
+                .out(&format!(
+                    "  sudo systemd-cryptenroll --wipe-slot=tpm2 --tpm2-device=auto {} {}",
+                    TPM2_PCRS, device
+                ))
+                .await;
+        }
+    }
+
+    runner.step_complete("TPM2").await?;
+    Ok(())
+}
+
+/// Step 8: Create Secure Boot keys on the target and switch the host to lanzaboote
+async fn step_prepare_secure_boot(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    hostname: &str,
+) -> Result<bool> {
+    runner.out("Preparing Secure Boot...").await;
+
+    // Enrolling only works while the firmware has no platform key
+    let support = detect_secure_boot();
+    if support != SecureBootSupport::SetupMode {
+        runner
+            .step_failed(
+                "Creating Secure Boot",
+                &format!("Secure Boot keys can't be enrolled: {}", support),
+                "Secure Boot setup",
+            )
+            .await?;
+        runner.done(false).await?;
+        return Ok(false);
+    }
+
+    // lanzaboote signs the bootloader during nixos-install, so the keys must exist first
+    let target = format!("{}{}", INSTALL_MOUNT_POINT, SBCTL_DIR);
+    let create_keys = format!(
+        "sbctl create-keys --database-path {}/GUID --export {}/keys",
+        target, target
+    );
+    let success = runner.run("nix-shell", &["-p", "sbctl", "--run", &create_keys]).await?;
+    if !success {
+        runner
+            .step_failed("Creating Secure Boot", "sbctl create-keys failed", "Secure Boot setup")
+            .await?;
+        runner.done(false).await?;
+        return Ok(false);
+    }
+    runner.out(&format!("Signing keys created in {}", SBCTL_DIR)).await;
+
+    let host_file = temp_config
+        .join(constants::HOSTS_SUBDIR)
+        .join(hostname)
+        .join("default.nix");
+    let content = std::fs::read_to_string(&host_file)
+        .with_context(|| format!("Failed to read {}", host_file.display()))?;
+    let Some(updated) = add_secure_boot_import(&content) else {
+        runner
+            .step_failed(
+                "Creating Secure Boot",
+                &format!("No imports list in {}", host_file.display()),
+                "Secure Boot setup",
+            )
+            .await?;
+        runner.done(false).await?;
+        return Ok(false);
+    };
+    std::fs::write(&host_file, updated)
+        .with_context(|| format!("Failed to write {}", host_file.display()))?;
+    let host_rel = format!("{}/{}/default.nix", constants::HOSTS_SUBDIR, hostname);
+    format_nix_files(runner, temp_config, &[&host_rel]).await;
+    runner.out(&format!("Host {} imports {}", hostname, SECURE_BOOT_MODULE)).await;
+
+    runner.step_complete("Creating Secure Boot").await?;
+    Ok(true)
+}
+
+/// Point hibernation at the swapfile or swap partition the host's disko config made
+///
+/// The partition is the resume device already (disko's `resumeDevice`). A
+/// swapfile also needs the physical offset of its first page, which only
+/// exists once disko has created it, so it goes into a hibernation.nix the
+/// host imports here. Failing to find it only leaves hibernation off.
+async fn step_configure_hibernation(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    hostname: &str,
+    swap: DiskSwap,
+    encrypt: bool,
+) -> Result<()> {
+    match swap {
+        DiskSwap::None => return Ok(()),
+        DiskSwap::Partition => {
+            runner.out("Hibernation resumes from the swap partition").await;
+        }
+        DiskSwap::File => {
+            runner.out("Finding the swapfile's resume offset...").await;
+            let map = format!(
+                "btrfs inspect-internal map-swapfile -r {}{}",
+                INSTALL_MOUNT_POINT, SWAPFILE
+            );
+            let (ok, stdout, stderr) =
+                run_capture("nix-shell", &["-p", "btrfs-progs", "--run", &map]).await?;
+            let offset = stdout.trim().parse::<u64>().ok().filter(|_| ok);
+            let Some(offset) = offset else {
+                runner
+                    .warn(&format!(
+                        "No resume offset for {} ({}), hibernation stays off",
+                        SWAPFILE,
+                        stderr.trim()
+                    ))
+                    .await;
+                runner.step_complete("hibernation").await?;
+                return Ok(());
+            };
+
+            let device = resume_device(encrypt);
+            let host_dir = temp_config.join(constants::HOSTS_SUBDIR).join(hostname);
+            let host_file = host_dir.join("default.nix");
+            let content = std::fs::read_to_string(&host_file)
+                .with_context(|| format!("Failed to read {}", host_file.display()))?;
+            let Some(updated) = add_host_import(&content, HIBERNATION_MODULE, None) else {
+                runner
+                    .warn(&format!(
+                        "No imports list in {}, hibernation stays off",
+                        host_file.display()
+                    ))
+                    .await;
+                runner.step_complete("hibernation").await?;
+                return Ok(());
+            };
+            let module = host_dir.join(HIBERNATION_MODULE);
+            std::fs::write(&module, swapfile_resume_module(device, offset))
+                .with_context(|| format!("Failed to write {}", module.display()))?;
+            // The flake only sees files git tracks
+            let add = format!("git -C {} add {}", temp_config.display(), module.display());
+            if !runner.run("nix-shell", &["-p", "git", "--run", &add]).await? {
+                runner.warn("Could not add hibernation.nix to git, hibernation stays off").await;
+                runner.step_complete("hibernation").await?;
+                return Ok(());
+            }
+            std::fs::write(&host_file, updated)
+                .with_context(|| format!("Failed to write {}", host_file.display()))?;
+            let host_rel = format!("{}/{}/default.nix", constants::HOSTS_SUBDIR, hostname);
+            format_nix_files(runner, temp_config, &[&host_rel]).await;
+            runner
+                .out(&format!("Hibernation resumes from {} at offset {}", device, offset))
+                .await;
+        }
+    }
+    runner.step_complete("hibernation").await?;
+    Ok(())
+}
+
+/// Filesystem holding the swapfile: the opened LUKS root, or the btrfs by label
+fn resume_device(encrypt: bool) -> &'static str {
+    if encrypt {
+        "/dev/mapper/cryptroot"
+    } else {
+        "/dev/disk/by-label/nixos"
+    }
+}
+
+/// Module with the swapfile's resume device and offset; a module of its own,
+/// so its `boot.kernelParams` merges with the host's list instead of
+/// redefining it
+fn swapfile_resume_module(device: &str, offset: u64) -> String {
+    format!(
+        concat!(
+            "# Hibernation into the swapfile, offset found by the installer\n",
+            "{{\n",
+            "  boot.resumeDevice = \"{}\";\n",
+            "  boot.kernelParams = [ \"resume_offset={}\" ];\n",
+            "}}\n",
+        ),
+        device, offset
+    )
+}
+
+/// Step 9: Install NixOS
 async fn step_install_nixos(
     runner: &CommandRunner<'_>,
     temp_config: &std::path::Path,
     hostname: &str,
-    username: &str,
+    user: &PrimaryUser,
 ) -> Result<bool> {
     let temp_config_str = temp_config.to_string_lossy();
 
     runner.out("Installing NixOS...").await;
 
-    let config_dir = get_config_dir(username);
-    let symlink_target = get_symlink_target(username);
+    let config_dir = get_config_dir(&user.name);
+    let symlink_target = get_symlink_target(&user.name);
 
     // Copy configuration to user home directory
     let config_parent = std::path::Path::new(&config_dir)
@@ -344,19 +1523,24 @@ async fn step_install_nixos(
     init_git_repo(runner, &config_dir).await;
 
     // Set ownership
-    set_config_ownership(runner, config_parent, &config_dir).await;
+    set_config_ownership(runner, config_parent, &config_dir, &user.owner()).await;
 
-    // Run nixos-install
-    let success = runner
-        .run(
-            "nixos-install",
-            &[
-                "--flake",
-                &format!("{}#{}", config_dir, hostname),
-                "--no-root-passwd",
-            ],
-        )
-        .await?;
+    run_nixos_install(runner, &config_dir, hostname).await
+}
+
+/// Run nixos-install from the config in the new user's home
+async fn run_nixos_install(
+    runner: &CommandRunner<'_>,
+    config_dir: &str,
+    hostname: &str,
+) -> Result<bool> {
+    let success = run_with_progress(
+        runner,
+        "NixOS",
+        "nixos-install",
+        &["--flake", &format!("{}#{}", config_dir, hostname), "--no-root-passwd"],
+    )
+    .await?;
 
     if !success {
         runner.step_failed("NixOS", "nixos-install failed", "NixOS installation").await?;
@@ -368,39 +1552,76 @@ async fn step_install_nixos(
     Ok(true)
 }
 
-/// Step 7: Set user password
+/// Step 10: Set the user's password, and the extra users' (see `chpasswd_input`)
 async fn step_set_user_password(
     runner: &CommandRunner<'_>,
     username: &str,
     password: &str,
+    extra_users: &[String],
 ) -> Result<bool> {
     runner.out("Setting up user account...").await;
 
-    let escaped_password = password.replace('\'', "'\"'\"'");
     let chpasswd_script = format!(
-        "echo '{}:{}' | nixos-enter --root /mnt -c 'chpasswd'",
-        username, escaped_password
+        "printf '%s\\n' {} | nixos-enter --root /mnt -c '{}'",
+        chpasswd_input(username, password, extra_users),
+        chpasswd_command(extra_users)
     );
     let success = run_command_sensitive(runner.tx(), "sh", &["-c", &chpasswd_script]).await?;
 
     if !success {
-        runner.out("Warning: Failed to set user password. You can set it after first boot with 'passwd'.").await;
+        runner.warn("Failed to set user password. You can set it after first boot with 'passwd'.").await;
     }
 
     runner.step_complete("user").await?;
     Ok(true)
 }
 
+/// Step 11: Enroll the Secure Boot keys in the firmware
+///
+/// The system is installed either way; if enrolling fails the firmware stays
+/// in Setup Mode and it can be retried after first boot.
+async fn step_enroll_secure_boot(runner: &CommandRunner<'_>) -> Result<()> {
+    runner.out("Enrolling Secure Boot keys...").await;
+
+    // --microsoft keeps firmware option ROMs and Windows bootable
+    let success = runner
+        .run(
+            "nixos-enter",
+            &["--root", INSTALL_MOUNT_POINT, "-c", "sbctl enroll-keys --microsoft"],
+        )
+        .await?;
+    if success {
+        runner.out("Keys enrolled - enable Secure Boot in the firmware after rebooting").await;
+    } else {
+        runner.err("Warning: enrolling failed. After first boot run:").await;
+        runner.err("  sudo sbctl enroll-keys --microsoft").await;
+    }
+
+    runner.step_complete("Enrolling").await?;
+    Ok(())
+}
+
 /// Show installation completion message
-async fn show_completion_message(runner: &CommandRunner<'_>, username: &str) -> Result<()> {
+async fn show_completion_message(
+    runner: &CommandRunner<'_>,
+    username: &str,
+    encrypted: bool,
+) -> Result<()> {
+    let mut steps = vec!["Reboot: reboot".to_string()];
+    if encrypted {
+        steps.push("Enter your disk encryption passphrase at boot".to_string());
+    }
+    steps.push("Select a shell from the boot menu".to_string());
+    steps.push(format!("Login as '{}' with your chosen password", username));
+    steps.push(format!("Follow ~/{} for the remaining setup", templates::FIRST_BOOT_FILE));
+
     runner.out("\n").await;
     runner.out("Installation complete!").await;
     runner.out("").await;
     runner.out("Next steps:").await;
-    runner.out("  1. Reboot: reboot").await;
-    runner.out("  2. Enter your LUKS passphrase at boot").await;
-    runner.out("  3. Select a shell from the boot menu").await;
-    runner.out(&format!("  4. Login as '{}' with your chosen password", username)).await;
+    for (i, step) in steps.iter().enumerate() {
+        runner.out(&format!("  {}. {}", i + 1, step)).await;
+    }
     Ok(())
 }
 
@@ -408,6 +1629,89 @@ async fn show_completion_message(runner: &CommandRunner<'_>, username: &str) ->
 // Helper Functions
 // =============================================================================
 
+/// Write ~/FIRST-BOOT.md and a fish login reminder on the installed system
+///
+/// Best effort: the install has already succeeded, so failures are only logged.
+async fn write_first_boot_checklist(
+    runner: &CommandRunner<'_>,
+    hostname: &str,
+    user: &PrimaryUser,
+    disk: &str,
+    encrypted: bool,
+) {
+    let home = format!("{}/home/{}", INSTALL_MOUNT_POINT, user.name);
+    let common_nix = format!("{}/modules/common.nix", get_config_dir(&user.name));
+    let (locale, time_locale) = std::fs::read_to_string(&common_nix)
+        .map(|c| templates::parse_config_locale(&c))
+        .unwrap_or_default();
+
+    let info = templates::FirstBootInfo {
+        hostname: hostname.to_string(),
+        username: user.name.clone(),
+        disk: disk.to_string(),
+        encrypted,
+        boot_mode: detect_boot_mode(),
+        locale,
+        time_locale,
+        installed_at: chrono::Local::now(),
+    };
+
+    let checklist_path = format!("{}/{}", home, templates::FIRST_BOOT_FILE);
+    let conf_d = format!("{}/.config/fish/conf.d", home);
+    let result = std::fs::write(&checklist_path, templates::generate_first_boot_checklist(&info))
+        .and_then(|_| std::fs::create_dir_all(&conf_d))
+        .and_then(|_| {
+            std::fs::write(
+                format!("{}/forge-first-boot.fish", conf_d),
+                templates::generate_first_boot_motd(),
+            )
+        });
+    if let Err(e) = result {
+        tracing::warn!("Failed to write first boot checklist: {}", e);
+        return;
+    }
+
+    // Created as root; hand them to the user before Home Manager runs
+    let uid_gid = user.owner();
+    let config_home = format!("{}/.config", home);
+    for path in [checklist_path.as_str(), config_home.as_str()] {
+        if !matches!(runner.run("chown", &["-R", &uid_gid, path]).await, Ok(true)) {
+            tracing::warn!("Failed to set ownership on {}", path);
+        }
+    }
+
+    runner.out(&format!("First boot checklist written to ~/{}", templates::FIRST_BOOT_FILE)).await;
+}
+
+/// Run a command like `runner.run`, sending `step`'s percentage as its output comes in
+async fn run_with_progress(
+    runner: &CommandRunner<'_>,
+    step: &'static str,
+    cmd: &str,
+    args: &[&str],
+) -> Result<bool> {
+    let tx = runner.tx().clone();
+    let started = Instant::now();
+    let state = Mutex::new((InstallProgress::default(), None::<Instant>));
+    run_command_transformed(runner.tx(), cmd, args, move |line| {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        let (progress, last_sent) = &mut *state;
+        let due = last_sent.is_none_or(|sent| sent.elapsed() >= PROGRESS_INTERVAL);
+        if progress.update(line) && due {
+            if let Some(snapshot) = progress.snapshot(started.elapsed()) {
+                // Dropped if the channel is full; a later line sends a fresh one
+                let _ = tx.try_send(CommandMessage::StepProgress {
+                    step: step.to_string(),
+                    progress: snapshot,
+                });
+                *last_sent = Some(Instant::now());
+            }
+        }
+        Some(line.to_string())
+    })
+    .await
+}
+
 /// Set up the /mnt/etc/nixos symlink
 fn setup_config_symlink(symlink_target: &str) -> Result<()> {
     let symlink_parent = std::path::Path::new(INSTALL_SYMLINK_PATH)
@@ -432,24 +1736,17 @@ fn setup_config_symlink(symlink_target: &str) -> Result<()> {
 
 /// Initialize git repository in the config directory
 async fn init_git_repo(runner: &CommandRunner<'_>, config_dir: &str) {
-    match runner
-        .run(
-            "nix-shell",
-            &[
-                "-p",
-                "git",
-                "--run",
-                &format!(
-                    "cd {} && git init -b main && git remote add origin {} && git add -A && \
-                    git -c user.name='NixOS Install' -c user.email='install@localhost' \
-                    commit -m 'Initial configuration' && git fetch origin && \
-                    git branch --set-upstream-to=origin/main main",
-                    config_dir, REPO_URL
-                ),
-            ],
-        )
-        .await
-    {
+    let mut script = format!(
+        "cd {} && git init -b main && git remote add origin {} && git add -A && \
+        git -c user.name='NixOS Install' -c user.email='install@localhost' \
+        commit -m 'Initial configuration'",
+        config_dir, REPO_URL
+    );
+    // Offline, the upstream branch is left for the first `git fetch` on the new system
+    if offline::source().is_none() {
+        script.push_str(" && git fetch origin && git branch --set-upstream-to=origin/main main");
+    }
+    match runner.run("nix-shell", &["-p", "git", "--run", &script]).await {
         Ok(true) => tracing::info!("Git repository initialized successfully"),
         Ok(false) => tracing::warn!("Git repository initialization returned non-zero exit - continuing"),
         Err(e) => tracing::warn!("Git repository initialization error: {} - continuing", e),
@@ -461,16 +1758,16 @@ async fn set_config_ownership(
     runner: &CommandRunner<'_>,
     config_parent: &std::path::Path,
     config_dir: &str,
+    uid_gid: &str,
 ) {
-    let uid_gid = format!("{}:{}", PRIMARY_USER_UID, PRIMARY_USER_GID);
     let config_parent_str = config_parent.to_str().unwrap_or(".");
 
-    match runner.run("chown", &[&uid_gid, config_parent_str]).await {
+    match runner.run("chown", &[uid_gid, config_parent_str]).await {
         Ok(true) => tracing::info!("Set ownership on config parent directory"),
         Ok(false) | Err(_) => tracing::warn!("Failed to set ownership on config parent directory"),
     }
 
-    match runner.run("chown", &["-R", &uid_gid, config_dir]).await {
+    match runner.run("chown", &["-R", uid_gid, config_dir]).await {
         Ok(true) => tracing::info!("Set ownership on config directory"),
         Ok(false) | Err(_) => tracing::warn!("Failed to set ownership on config directory"),
     }
@@ -480,14 +1777,27 @@ async fn set_config_ownership(
 // Main Installation Function
 // =============================================================================
 
+#[allow(clippy::too_many_arguments)]
 async fn run_install(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     hostname: &str,
     disk: &str,
-    username: &str,
+    disk_mode: DiskMode,
+    extra_disks: &[DiskAssignment],
+    user: &PrimaryUser,
     password: &str,
+    accounts: &ExtraAccounts,
+    locale: Option<&LocaleSettings>,
+    partitions: Option<&PartitionLayout>,
+    hibernation: DiskSwap,
+    passphrase: Option<&str>,
+    tpm2_unlock: bool,
+    secure_boot: bool,
+    secure_erase: bool,
 ) -> Result<()> {
     let runner = CommandRunner::new(tx);
+    let encrypt = passphrase.is_some();
+    let username = user.name.as_str();
 
     // Step 1: Check network
     if !step_check_network(&runner).await? {
@@ -504,49 +1814,967 @@ async fn run_install(
         Some(path) => path,
         None => return Ok(()),
     };
+    step_configure_caches(&runner, &temp_config, hostname).await?;
+    if !step_configure_accounts(&runner, &temp_config, hostname, username, accounts).await? {
+        return Ok(());
+    }
+    step_configure_locale(&runner, &temp_config, hostname, locale).await?;
 
     // Step 4: Configure disk
-    if !step_configure_disk(&runner, &temp_config, hostname, disk, username).await? {
+    let configured = step_configure_disk(
+        &runner,
+        &temp_config,
+        hostname,
+        disk,
+        disk_mode,
+        extra_disks,
+        user,
+        partitions,
+    )
+    .await?;
+    if !configured {
         return Ok(());
     }
 
-    // Step 5: Run disko
-    if !step_run_disko(&runner, &temp_config, hostname, password).await? {
+    // Step 5: Check drive health (warns, never stops the install)
+    step_check_disk_health(&runner, disk, extra_disks).await?;
+
+    // Step 5a: Make sure the config evaluates while the disks are still intact
+    if !step_validate_config(&runner, &temp_config, hostname).await? {
         return Ok(());
     }
 
-    // Step 6: Install NixOS
-    if !step_install_nixos(&runner, &temp_config, hostname, username).await? {
+    // Secure erase (optional, whole disks only)
+    if secure_erase && !step_secure_erase(&runner, disk, extra_disks).await? {
         return Ok(());
     }
 
-    // Step 7: Set user password
-    step_set_user_password(&runner, username, password).await?;
+    // Step 6: Run disko
+    if !step_run_disko(&runner, &temp_config, hostname, passphrase).await? {
+        return Ok(());
+    }
 
-    // Show completion message
-    show_completion_message(&runner, username).await?;
+    // Step 7: Seal a LUKS key in the TPM2 (optional)
+    if let (true, Some(passphrase)) = (tpm2_unlock, passphrase) {
+        step_enroll_tpm2(&runner, &temp_config, hostname, passphrase, secure_boot).await?;
+    }
 
-    runner.done(true).await?;
-    Ok(())
-}
+    // Step 8: Secure Boot keys (optional)
+    if secure_boot && !step_prepare_secure_boot(&runner, &temp_config, hostname).await? {
+        return Ok(());
+    }
 
-fn update_disk_device(content: &str, disk: &str) -> String {
-    // Replace device = "/dev/..." with the new disk
-    let replacement = format!("device = \"{}\"", disk);
-    let result = DISK_DEVICE_RE.replace_all(content, replacement.as_str());
+    // Hibernation into the on-disk swap (swapfile or partition)
+    step_configure_hibernation(&runner, &temp_config, hostname, hibernation, encrypt).await?;
 
-    // Log warning if no replacement occurred (pattern not found)
-    if result == content && !content.contains(&format!("device = \"{}\"", disk)) {
-        tracing::warn!(
-            "Disk device replacement may have failed - pattern not found in disko config"
-        );
+    // Step 9: Install NixOS
+    if !step_install_nixos(&runner, &temp_config, hostname, user).await? {
+        return Ok(());
     }
 
-    result.to_string()
+    finish_install(
+        &runner,
+        &temp_config,
+        hostname,
+        disk,
+        user,
+        password,
+        accounts,
+        encrypt,
+        secure_boot,
+    )
+    .await
+}
+
+/// The steps after nixos-install: passwords, Secure Boot enrollment, checklist
+#[allow(clippy::too_many_arguments)]
+async fn finish_install(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    hostname: &str,
+    disk: &str,
+    user: &PrimaryUser,
+    password: &str,
+    accounts: &ExtraAccounts,
+    encrypt: bool,
+    secure_boot: bool,
+) -> Result<()> {
+    let username = user.name.as_str();
+
+    // Step 10: Set user passwords
+    step_set_user_password(runner, username, password, &accounts.users).await?;
+
+    // Step 11: Enroll Secure Boot keys (optional)
+    if secure_boot {
+        step_enroll_secure_boot(runner).await?;
+    }
+
+    // Leave a checklist for whoever sits down at the machine next
+    write_first_boot_checklist(runner, hostname, user, disk, encrypt).await;
+
+    // The ISO imported the pool under its own hostId; hand it over cleanly
+    if host_uses_zfs(temp_config, hostname) {
+        export_zfs_pools(runner).await;
+    }
+
+    // Show completion message
+    show_completion_message(runner, username, encrypt).await?;
+
+    runner.done(true).await?;
+    Ok(())
+}
+
+/// Run nixos-install again after a failure, and the steps after it
+///
+/// /mnt is still mounted from the failed run. The config is taken from the
+/// user's home on it, where it may have been fixed from the rescue shell.
+#[allow(clippy::too_many_arguments)]
+pub async fn start_install_retry(
+    tx: CommandSender,
+    hostname: &str,
+    disk: &str,
+    user: &PrimaryUser,
+    password: &str,
+    accounts: &ExtraAccounts,
+    encrypt: bool,
+    secure_boot: bool,
+) -> Result<()> {
+    let hostname = hostname.to_string();
+    let disk = disk.to_string();
+    let user = user.clone();
+    let password = password.to_string();
+    let accounts = accounts.clone();
+
+    tokio::spawn(async move {
+        let runner = CommandRunner::new(&tx);
+        let config_dir = get_config_dir(&user.name);
+        runner.out(&format!("Retrying nixos-install from {}...", config_dir)).await;
+        let result = async {
+            if !run_nixos_install(&runner, &config_dir, &hostname).await? {
+                return Ok(());
+            }
+            let temp_config = constants::temp_config_dir();
+            finish_install(
+                &runner,
+                &temp_config,
+                &hostname,
+                &disk,
+                &user,
+                &password,
+                &accounts,
+                encrypt,
+                secure_boot,
+            )
+            .await
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::error!("Installation retry failed: {}", e);
+            let _ = tx
+                .send(CommandMessage::StepFailed {
+                    step: "NixOS".to_string(),
+                    error: ParsedError::from_stderr(
+                        &e.to_string(),
+                        ErrorContext {
+                            operation: "Installation".to_string(),
+                        },
+                    ),
+                })
+                .await;
+            let _ = tx.send(CommandMessage::Done { success: false }).await;
+        }
+    });
+    Ok(())
+}
+
+/// Shell for fixing a failed nixos-install: `nixos-enter` into /mnt once the
+/// system is there, else a shell on the ISO in the config checkout
+pub fn rescue_shell(username: &str) -> ShellRequest {
+    let config_dir = get_config_dir(username);
+    let mut banner = vec![
+        "Forge is paused. Type exit to return to it, then press r to retry nixos-install.".to_string(),
+    ];
+    if std::path::Path::new(INSTALL_MOUNT_POINT).join("etc/NIXOS").exists() {
+        banner.push(format!(
+            "This is a shell inside the new system; the config is in {}.",
+            get_symlink_target(username)
+        ));
+        ShellRequest {
+            program: "nixos-enter".to_string(),
+            args: vec!["--root".to_string(), INSTALL_MOUNT_POINT.to_string()],
+            dir: None,
+            banner,
+        }
+    } else {
+        banner.push("The system was not installed yet, so this is a shell on the live ISO.".to_string());
+        banner.push(format!("The config is in {}.", config_dir));
+        ShellRequest {
+            program: std::env::var("SHELL").unwrap_or_else(|_| "bash".to_string()),
+            args: Vec::new(),
+            dir: Some(config_dir.into()),
+            banner,
+        }
+    }
+}
+
+// =============================================================================
+// Remote Installation (nixos-anywhere)
+// =============================================================================
+
+/// Flake nixos-anywhere is run from for `forge install --target`
+const NIXOS_ANYWHERE: &str = "github:nix-community/nixos-anywhere";
+
+/// Files nixos-anywhere copies onto the new system (the config and /etc/nixos)
+const REMOTE_EXTRA_FILES_DIR: &str = "/tmp/forge-remote-files";
+
+/// ssh options for the checks before nixos-anywhere runs: keys only, no prompts
+const SSH_OPTIONS: &[&str] = &["-o", "BatchMode=yes", "-o", "ConnectTimeout=10"];
+
+/// After kexec the target runs the installer, as root and with a new host key
+const SSH_INSTALLER_OPTIONS: &[&str] = &[
+    "-o",
+    "BatchMode=yes",
+    "-o",
+    "ConnectTimeout=10",
+    "-o",
+    "StrictHostKeyChecking=no",
+    "-o",
+    "UserKnownHostsFile=/dev/null",
+];
+
+/// Start installing a host onto another machine over SSH (`forge install --target`)
+#[allow(clippy::too_many_arguments)]
+pub async fn start_remote_install(
+    tx: CommandSender,
+    target: &str,
+    hostname: &str,
+    disk: &str,
+    user: &PrimaryUser,
+    password: &str,
+    accounts: &ExtraAccounts,
+    locale: Option<&LocaleSettings>,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let target = target.to_string();
+    let hostname = hostname.to_string();
+    let disk = disk.to_string();
+    let user = user.clone();
+    let password = password.to_string();
+    let accounts = accounts.clone();
+    let locale = locale.cloned();
+    let passphrase = passphrase.map(str::to_string);
+
+    tokio::spawn(async move {
+        if let Err(e) = run_remote_install(
+            &tx,
+            &target,
+            &hostname,
+            &disk,
+            &user,
+            &password,
+            &accounts,
+            locale.as_ref(),
+            passphrase.as_deref(),
+        )
+        .await
+        {
+            tracing::error!("Remote installation failed: {}", e);
+            let _ = tx
+                .send(CommandMessage::StepFailed {
+                    step: "Install".to_string(),
+                    error: ParsedError::from_stderr(
+                        &e.to_string(),
+                        ErrorContext {
+                            operation: "Remote installation".to_string(),
+                        },
+                    ),
+                })
+                .await;
+            let _ = tx.send(CommandMessage::Done { success: false }).await;
+        }
+        remove_luks_password_file();
+        let _ = std::fs::remove_dir_all(REMOTE_EXTRA_FILES_DIR);
+    });
+    Ok(())
+}
+
+/// Install onto `target`: the config is prepared here, nixos-anywhere kexecs the
+/// target into an installer and runs disko and nixos-install there
+#[allow(clippy::too_many_arguments)]
+async fn run_remote_install(
+    tx: &CommandSender,
+    target: &str,
+    hostname: &str,
+    disk: &str,
+    user: &PrimaryUser,
+    password: &str,
+    accounts: &ExtraAccounts,
+    locale: Option<&LocaleSettings>,
+    passphrase: Option<&str>,
+) -> Result<()> {
+    let runner = CommandRunner::new(tx);
+    let username = user.name.as_str();
+
+    if !step_enable_flakes(&runner).await? {
+        return Ok(());
+    }
+    let Some(temp_config) = step_prepare_repository(&runner, hostname).await? else {
+        return Ok(());
+    };
+    step_configure_caches(&runner, &temp_config, hostname).await?;
+    if !step_check_ssh(&runner, target).await? {
+        return Ok(());
+    }
+    if !step_configure_accounts(&runner, &temp_config, hostname, username, accounts).await? {
+        return Ok(());
+    }
+    step_configure_locale(&runner, &temp_config, hostname, locale).await?;
+    let configured = step_configure_remote_disk(
+        &runner,
+        &temp_config,
+        target,
+        hostname,
+        disk,
+        user,
+        passphrase,
+    )
+    .await?;
+    if !configured {
+        return Ok(());
+    }
+    if !step_validate_config(&runner, &temp_config, hostname).await? {
+        return Ok(());
+    }
+    if !step_run_nixos_anywhere(&runner, &temp_config, target, hostname, user, passphrase)
+        .await?
+    {
+        return Ok(());
+    }
+    step_set_remote_password(&runner, target, username, password, &accounts.users).await?;
+
+    runner.out(&format!("Rebooting {}...", target)).await;
+    // The connection drops as the machine goes down, so the exit status means nothing
+    let _ = run_ssh(&remote_root_target(target), SSH_INSTALLER_OPTIONS, "reboot").await;
+    runner.step_complete("reboot").await?;
+
+    runner.out("\n").await;
+    runner.out(&format!("Installation of {} on {} complete!", hostname, target)).await;
+    if passphrase.is_some() {
+        runner.out("Enter the disk encryption passphrase at the machine's console to boot").await;
+    }
+    runner.out(&format!("Login as '{}' with your chosen password", username)).await;
+    runner.done(true).await?;
+    Ok(())
+}
+
+/// Check the target accepts key-based SSH logins, before anything is changed
+async fn step_check_ssh(runner: &CommandRunner<'_>, target: &str) -> Result<bool> {
+    runner.out(&format!("Checking SSH access to {}...", target)).await;
+    let (success, _, stderr) = run_ssh(target, SSH_OPTIONS, "true").await?;
+    if !success {
+        runner.step_failed(
+            "ssh",
+            &format!(
+                "Cannot log in to {} with an SSH key: {}",
+                target,
+                stderr.lines().last().unwrap_or_default().trim()
+            ),
+            "SSH access",
+        ).await?;
+        runner.done(false).await?;
+        return Ok(false);
+    }
+    runner.step_complete("ssh").await?;
+    Ok(true)
+}
+
+/// Point the host's disko config at the remote disk and prepare encryption
+///
+/// Like step 4, but the disk and firmware are checked over SSH.
+async fn step_configure_remote_disk(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    target: &str,
+    hostname: &str,
+    disk: &str,
+    user: &PrimaryUser,
+    passphrase: Option<&str>,
+) -> Result<bool> {
+    let temp_config_str = temp_config.to_string_lossy();
+    runner.out(&format!("Configuring disk device {} on {}...", disk, target)).await;
+
+    let (exists, _, _) = run_ssh(target, SSH_OPTIONS, &format!("test -b {}", disk)).await?;
+    if !disk.starts_with("/dev/") || !exists {
+        runner.step_failed(
+            "disk",
+            &format!("Disk device does not exist on {}: {}", target, disk),
+            "Disk validation",
+        ).await?;
+        runner.done(false).await?;
+        return Ok(false);
+    }
+
+    let disko_file = format!("{}/modules/disko/{}.nix", temp_config_str, hostname);
+    let Ok(disko_content) = std::fs::read_to_string(&disko_file) else {
+        runner.step_failed(
+            "disk",
+            &format!(
+                "No disko configuration found for host '{}'. Expected: modules/disko/{}.nix",
+                hostname, hostname
+            ),
+            "Disk configuration",
+        ).await?;
+        runner.done(false).await?;
+        return Ok(false);
+    };
+
+    // kexec keeps the firmware mode, so the layout has to match what the target booted
+    let (efi, _, _) = run_ssh(target, SSH_OPTIONS, "test -d /sys/firmware/efi").await?;
+    if disko_boot_mode(&disko_content) == BootMode::Uefi && !efi {
+        runner.step_failed(
+            "disk",
+            &format!(
+                "Host '{}' uses a UEFI disk layout but {} booted in legacy BIOS mode.",
+                hostname, target
+            ),
+            "Disk configuration",
+        ).await?;
+        runner.done(false).await?;
+        return Ok(false);
+    }
+
+    let mut host_disko = update_disk_device(&disko_content, disk);
+    match passphrase {
+        None => {
+            runner.out("Disk encryption is off: formatting without LUKS").await;
+            host_disko = disable_encryption(&host_disko);
+        }
+        Some(passphrase) => {
+            // nixos-anywhere uploads the file to the same path on the target
+            write_luks_password_file(passphrase)?;
+            if !disko_uses_zfs(&host_disko) {
+                configure_luks_password_file(runner, &temp_config_str, &host_disko).await?;
+            }
+        }
+    }
+    std::fs::write(&disko_file, &host_disko)
+        .with_context(|| format!("Failed to write disko config: {}", disko_file))?;
+    if !configure_primary_user(runner, temp_config, hostname, user).await? {
+        runner
+            .step_failed("disk", "The flake.nix username change was rejected", "Disk configuration")
+            .await?;
+        runner.done(false).await?;
+        return Ok(false);
+    }
+
+    runner.step_complete("disk").await?;
+    Ok(true)
+}
+
+/// Run nixos-anywhere: kexec into the installer, partition with disko, install
+///
+/// The reboot phase is left out so the password can still be set in /mnt.
+async fn step_run_nixos_anywhere(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    target: &str,
+    hostname: &str,
+    user: &PrimaryUser,
+    passphrase: Option<&str>,
+) -> Result<bool> {
+    runner.out(&format!("Running nixos-anywhere against {}...", target)).await;
+
+    stage_remote_files(runner, temp_config, &user.name).await?;
+    let flake = format!("{}#{}", temp_config.to_string_lossy(), hostname);
+    let args = nixos_anywhere_args(&flake, target, user, passphrase.is_some());
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let success = run_with_progress(runner, "nixos-anywhere", "nix", &args).await?;
+
+    // Clean up password file immediately (security); absent for unencrypted installs
+    remove_luks_password_file();
+
+    if !success {
+        runner.step_failed("nixos-anywhere", "nixos-anywhere failed", "Remote installation")
+            .await?;
+        runner.done(false).await?;
+        return Ok(false);
+    }
+
+    runner.step_complete("nixos-anywhere").await?;
+    Ok(true)
+}
+
+/// Stage the config under /home/<user> and the /etc/nixos symlink for --extra-files
+async fn stage_remote_files(
+    runner: &CommandRunner<'_>,
+    temp_config: &std::path::Path,
+    username: &str,
+) -> Result<()> {
+    let _ = std::fs::remove_dir_all(REMOTE_EXTRA_FILES_DIR);
+    let config_dir = format!("{}{}", REMOTE_EXTRA_FILES_DIR, get_symlink_target(username));
+    copy_dir_recursive(&temp_config.to_string_lossy(), &config_dir)
+        .with_context(|| format!("Failed to stage the configuration in {}", config_dir))?;
+    let _ = std::fs::remove_dir_all(format!("{}/.git", config_dir));
+    init_git_repo(runner, &config_dir).await;
+
+    let etc_dir = format!("{}/etc", REMOTE_EXTRA_FILES_DIR);
+    std::fs::create_dir_all(&etc_dir)
+        .with_context(|| format!("Failed to create directory: {}", etc_dir))?;
+    std::os::unix::fs::symlink(get_symlink_target(username), format!("{}/nixos", etc_dir))
+        .context("Failed to stage the /etc/nixos symlink")?;
+    Ok(())
+}
+
+/// `nix run` arguments for nixos-anywhere, without the reboot phase
+fn nixos_anywhere_args(flake: &str, target: &str, user: &PrimaryUser, encrypted: bool) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "run",
+        NIXOS_ANYWHERE,
+        "--",
+        "--flake",
+        flake,
+        "--target-host",
+        target,
+        "--phases",
+        "kexec,disko,install",
+        "--extra-files",
+        REMOTE_EXTRA_FILES_DIR,
+        "--ssh-option",
+        "BatchMode=yes",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+    args.extend([
+        "--chown".to_string(),
+        format!("/home/{}", user.name),
+        user.owner(),
+    ]);
+    if encrypted {
+        let keys = ["--disk-encryption-keys", LUKS_PASSWORD_FILE, LUKS_PASSWORD_FILE];
+        args.extend(keys.map(String::from));
+    }
+    args
+}
+
+/// Set the passwords in the freshly installed system, still mounted at /mnt
+async fn step_set_remote_password(
+    runner: &CommandRunner<'_>,
+    target: &str,
+    username: &str,
+    password: &str,
+    extra_users: &[String],
+) -> Result<()> {
+    runner.out("Setting up user account...").await;
+
+    let chpasswd_script = format!(
+        "printf '%s\\n' {} | ssh {} {} 'nixos-enter --root {} -c \"{}\"'",
+        chpasswd_input(username, password, extra_users),
+        SSH_INSTALLER_OPTIONS.join(" "),
+        remote_root_target(target),
+        INSTALL_MOUNT_POINT,
+        chpasswd_command(extra_users)
+    );
+    let success = run_command_sensitive(runner.tx(), "sh", &["-c", &chpasswd_script]).await?;
+
+    if !success {
+        runner.warn("Failed to set user password. You can set it after first boot with 'passwd'.").await;
+    }
+
+    runner.step_complete("user").await?;
+    Ok(())
+}
+
+/// `user:password` lines for chpasswd, quoted for `sh -c`
+///
+/// Extra users start with the main user's password (see `chpasswd_command`).
+fn chpasswd_input(username: &str, password: &str, extra_users: &[String]) -> String {
+    let escaped_password = password.replace('\'', "'\"'\"'");
+    std::iter::once(username)
+        .chain(extra_users.iter().map(String::as_str))
+        .map(|user| format!("'{}:{}'", user, escaped_password))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Copies the passwords to /persist on impermanence hosts, where /etc is wiped
+/// at boot and restored from there (see modules/impermanence.nix)
+const SAVE_SHADOW_COMMAND: &str =
+    "if [ -d /persist ]; then install -D -m 0600 /etc/shadow /persist/etc/shadow; fi";
+
+/// chpasswd, then expire the extra users' passwords so they pick their own at
+/// first login
+fn chpasswd_command(extra_users: &[String]) -> String {
+    std::iter::once("chpasswd".to_string())
+        .chain(extra_users.iter().map(|user| format!("passwd -e {}", user)))
+        .chain(std::iter::once(SAVE_SHADOW_COMMAND.to_string()))
+        .collect::<Vec<_>>()
+        .join(" && ")
+}
+
+/// `root@host` for a `user@host` target: nixos-anywhere's installer logs in as root
+fn remote_root_target(target: &str) -> String {
+    let host = target.rsplit_once('@').map_or(target, |(_, host)| host);
+    format!("root@{}", host)
+}
+
+/// Run `command` on `host` over ssh and capture its output
+async fn run_ssh(host: &str, options: &[&str], command: &str) -> Result<(bool, String, String)> {
+    let mut args = options.to_vec();
+    args.push(host);
+    args.push(command);
+    run_capture("ssh", &args).await
+}
+
+fn update_disk_device(content: &str, disk: &str) -> String {
+    // Replace device = "/dev/..." with the new disk
+    let replacement = format!("device = \"{}\"", disk);
+    let result = DISK_DEVICE_RE.replace_all(content, replacement.as_str());
+
+    // Log warning if no replacement occurred (pattern not found)
+    if result == content && !content.contains(&format!("device = \"{}\"", disk)) {
+        tracing::warn!(
+            "Disk device replacement may have failed - pattern not found in disko config"
+        );
+    }
+
+    result.to_string()
+}
+
+/// Boot mode a host's disko file was generated for, based on the layout it imports
+fn disko_boot_mode(host_disko: &str) -> BootMode {
+    if host_disko.contains(&format!("./{}", DISKO_BIOS_LAYOUT)) {
+        BootMode::Bios
+    } else {
+        BootMode::Uefi
+    }
+}
+
+/// Whether a host's disko file imports the ZFS layout
+fn disko_uses_zfs(host_disko: &str) -> bool {
+    host_disko.contains(&format!("./{}", DISKO_ZFS_LAYOUT))
+}
+
+/// Whether the host being installed has a ZFS root
+fn host_uses_zfs(temp_config: &std::path::Path, hostname: &str) -> bool {
+    let disko_file = temp_config.join(format!("modules/disko/{}.nix", hostname));
+    std::fs::read_to_string(disko_file).is_ok_and(|content| disko_uses_zfs(&content))
+}
+
+/// Unmount the target and export its pools so first boot imports them without force
+///
+/// Best effort: the install has already succeeded, so failures are only logged.
+async fn export_zfs_pools(runner: &CommandRunner<'_>) {
+    runner.out("Exporting ZFS pools...").await;
+    let steps = [
+        ("umount", vec!["-R", INSTALL_MOUNT_POINT]),
+        ("zpool", vec!["export", "-a"]),
+    ];
+    for (cmd, args) in steps {
+        match run_capture(cmd, &args).await {
+            Ok((true, _, _)) => {}
+            Ok((false, _, stderr)) => tracing::warn!("{} failed: {}", cmd, stderr.trim()),
+            Err(e) => tracing::warn!("Failed to run {}: {}", cmd, e),
+        }
+    }
+}
+
+/// Whether a host's disko file was rewritten for an alongside install
+fn disko_is_alongside(host_disko: &str) -> bool {
+    host_disko.contains(&format!("./{}", DISKO_ALONGSIDE_LAYOUT))
+}
+
+/// Create the NixOS partitions in the disk's free space for an alongside install
+///
+/// Returns the host disko file rewritten for the alongside layout, or a message
+/// explaining why the disk can't be used. Existing partitions are never touched.
+async fn prepare_alongside(
+    runner: &CommandRunner<'_>,
+    host_disko: &str,
+    disk: &str,
+) -> Result<Result<String, String>> {
+    if disko_uses_zfs(host_disko) || disko_boot_mode(host_disko) == BootMode::Bios {
+        return Ok(Err(
+            "Installing alongside another OS needs a UEFI host with the default btrfs layout"
+                .to_string(),
+        ));
+    }
+
+    let table = match read_partition_table(disk) {
+        Ok(table) if table.label == "gpt" => table,
+        Ok(table) => {
+            return Ok(Err(format!(
+                "{} has a {} partition table; installing alongside needs GPT",
+                disk, table.label
+            )))
+        }
+        Err(e) => return Ok(Err(format!("Failed to read the partition table: {:#}", e))),
+    };
+    let region = match table.largest_free_region() {
+        Some(region) if region.size_bytes() >= MIN_ALONGSIDE_BYTES => region,
+        _ => {
+            return Ok(Err(format!(
+                "Not enough unallocated space on {} (need {}). Shrink the Windows partition \
+                 first (Disk Management → Shrink Volume).",
+                disk,
+                format_bytes(MIN_ALONGSIDE_BYTES)
+            )))
+        }
+    };
+    for label in [ALONGSIDE_BOOT_LABEL, ALONGSIDE_ROOT_LABEL] {
+        if std::path::Path::new(&partlabel_device(label)).exists() {
+            return Ok(Err(format!(
+                "A partition labelled '{}' already exists (earlier install attempt?). \
+                 Delete it before installing alongside.",
+                label
+            )));
+        }
+    }
+
+    let windows_entry = detect_windows_entry(runner, &table).await;
+
+    runner
+        .out(&format!(
+            "Creating NixOS partitions in {} of free space...",
+            format_bytes(region.size_bytes())
+        ))
+        .await;
+    let mut args = alongside_sgdisk_args(region.start, region.end, region.sector_size);
+    args.push(disk.to_string());
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    if !runner.run("sgdisk", &args).await? {
+        return Ok(Err("sgdisk failed to create the NixOS partitions".to_string()));
+    }
+    // Let udev create the by-partlabel links disko points at
+    let _ = run_capture("partprobe", &[disk]).await;
+    let _ = run_capture("udevadm", &["settle"]).await;
+
+    Ok(Ok(alongside_disko_config(host_disko, windows_entry.as_deref())))
+}
+
+/// sgdisk arguments creating the ESP and the LUKS partition in `start..=end`
+fn alongside_sgdisk_args(start: u64, end: u64, sector_size: u64) -> Vec<String> {
+    let boot_end = start + ALONGSIDE_BOOT_BYTES / sector_size - 1;
+    vec![
+        format!("--new=0:{}:{}", start, boot_end),
+        "--typecode=0:ef00".to_string(),
+        format!("--change-name=0:{}", ALONGSIDE_BOOT_LABEL),
+        format!("--new=0:{}:{}", boot_end + 1, end),
+        "--typecode=0:8309".to_string(),
+        format!("--change-name=0:{}", ALONGSIDE_ROOT_LABEL),
+    ]
+}
+
+fn partlabel_device(label: &str) -> String {
+    format!("/dev/disk/by-partlabel/{}", label)
+}
+
+/// Point a UEFI host disko file at the alongside layout and its root partition,
+/// adding a Limine entry for Windows if one was found
+fn alongside_disko_config(host_disko: &str, windows_entry: Option<&str>) -> String {
+    let content = host_disko.replace(
+        &format!("./{}", DISKO_UEFI_LAYOUT),
+        &format!("./{}", DISKO_ALONGSIDE_LAYOUT),
+    );
+    let mut content = update_disk_device(&content, &partlabel_device(ALONGSIDE_ROOT_LABEL));
+    if let Some(entry) = windows_entry {
+        match content.rfind('}') {
+            Some(end) => content.insert_str(end, entry),
+            None => content.push_str(entry),
+        }
+    }
+    content
+}
+
+/// Find the Windows boot manager with os-prober, falling back to the disk's ESP
+async fn detect_windows_entry(
+    runner: &CommandRunner<'_>,
+    table: &PartitionTable,
+) -> Option<String> {
+    runner.out("Looking for an existing Windows install (os-prober)...").await;
+    let probed = match run_capture("os-prober", &[]).await {
+        Ok((_, stdout, _)) => parse_os_prober(&stdout),
+        Err(e) => {
+            tracing::warn!("os-prober failed: {}", e);
+            None
+        }
+    };
+
+    let (partuuid, loader) = match probed {
+        Some((device, loader)) => {
+            let partuuid = match table.partitions.iter().find(|p| p.node == device) {
+                Some(partition) => partition.uuid.clone(),
+                None => run_capture("blkid", &["-s", "PARTUUID", "-o", "value", &device])
+                    .await
+                    .ok()
+                    .filter(|(ok, _, _)| *ok)
+                    .map(|(_, stdout, _)| stdout.trim().to_string()),
+            };
+            (partuuid, loader)
+        }
+        // Windows' own ESP on this disk, where its boot manager always lives
+        None if table.has_windows() => (
+            table.esp().and_then(|esp| esp.uuid.clone()),
+            WINDOWS_LOADER_PATH.to_string(),
+        ),
+        None => (None, String::new()),
+    };
+
+    match partuuid.filter(|uuid| !uuid.is_empty()) {
+        Some(partuuid) => {
+            runner.out("Adding a Windows entry to the Limine boot menu").await;
+            Some(limine_windows_entry(&partuuid, &loader))
+        }
+        None => {
+            runner.out("No Windows boot manager found, skipping the boot menu entry").await;
+            None
+        }
+    }
+}
+
+/// First Windows EFI loader in os-prober output, as (partition, loader path)
+///
+/// Lines look like
+/// `/dev/nvme0n1p1@/EFI/Microsoft/Boot/bootmgfw.efi:Windows Boot Manager:Windows:efi`
+fn parse_os_prober(output: &str) -> Option<(String, String)> {
+    output.lines().find_map(|line| {
+        let fields: Vec<&str> = line.trim().split(':').collect();
+        let [location, long_name, _, kind] = fields.as_slice() else {
+            return None;
+        };
+        if *kind != "efi" || !long_name.contains("Windows") {
+            return None;
+        }
+        let (device, loader) = location.split_once('@')?;
+        Some((device.to_string(), loader.to_string()))
+    })
+}
+
+/// Limine menu entry chainloading the Windows boot manager from its ESP
+fn limine_windows_entry(partuuid: &str, loader: &str) -> String {
+    format!(
+        r#"
+  # Existing Windows install, chainloaded from its own ESP
+  boot.loader.limine.extraEntries = ''
+    /Windows
+      protocol: efi
+      path: guid({}):{}
+  '';
+"#,
+        partuuid.to_lowercase(),
+        loader
+    )
+}
+
+/// Shared layout file under modules/disko for a boot mode
+fn disko_layout_file(mode: BootMode) -> &'static str {
+    match mode {
+        BootMode::Uefi => DISKO_UEFI_LAYOUT,
+        BootMode::Bios => DISKO_BIOS_LAYOUT,
+    }
+}
+
+/// Shared layout files a host disko file imports that contain LUKS devices
+fn disko_layout_files(host_disko: &str) -> Vec<&'static str> {
+    let root = if disko_is_alongside(host_disko) {
+        DISKO_ALONGSIDE_LAYOUT
+    } else if host_disko.contains(&format!("./{}", DISKO_MIRROR_LAYOUT)) {
+        DISKO_MIRROR_LAYOUT
+    } else {
+        disko_layout_file(disko_boot_mode(host_disko))
+    };
+    let mut layouts = vec![root];
+    if host_disko.contains(&format!("./{}", DISKO_HOME_LAYOUT)) {
+        layouts.push(DISKO_HOME_LAYOUT);
+    }
+    layouts
+}
+
+/// Add the home and mirror disks of a multi-disk install to a UEFI host disko file
+///
+/// A mirror disk swaps default.nix for mirror.nix; a home disk imports home-disk.nix
+/// next to it. Each extra disk gets its own device line.
+fn multi_disk_config(host_disko: &str, extra_disks: &[DiskAssignment]) -> String {
+    let device = |role: DiskRole| {
+        extra_disks.iter().find(|a| a.role == role).map(|a| a.disk.config_path())
+    };
+
+    let mut imports = vec![format!("./{}", DISKO_UEFI_LAYOUT)];
+    let mut devices = String::new();
+    if let Some(path) = device(DiskRole::Mirror) {
+        imports[0] = format!("./{}", DISKO_MIRROR_LAYOUT);
+        devices.push_str(&format!("  disko.devices.disk.mirror.device = \"{}\";\n", path));
+    }
+    if let Some(path) = device(DiskRole::Home) {
+        imports.push(format!("./{}", DISKO_HOME_LAYOUT));
+        devices.push_str(&format!("  disko.devices.disk.home.device = \"{}\";\n", path));
+    }
+
+    let mut content = host_disko.replace(&format!("./{}", DISKO_UEFI_LAYOUT), &imports.join(" "));
+    if !devices.is_empty() {
+        let devices = format!("\n  # Extra disks chosen in the installer\n{}", devices);
+        match content.rfind('}') {
+            Some(end) => content.insert_str(end, &devices),
+            None => content.push_str(&devices),
+        }
+    }
+    content
+}
+
+/// Turn off encryption in a host's disko file (see diskLayout.encrypt in subvolumes.nix)
+fn disable_encryption(host_disko: &str) -> String {
+    if host_disko.contains("diskLayout.encrypt") {
+        return host_disko.to_string();
+    }
+    let setting = "\n  # Chosen in the installer\n  diskLayout.encrypt = false;\n";
+    insert_before_closing_brace(host_disko, setting)
+}
+
+/// Unlock the given LUKS volumes with the TPM2 (needs the systemd initrd)
+fn add_tpm2_unlock(host: &str, names: &[&str]) -> String {
+    if host.contains("tpm2-device=auto") {
+        return host.to_string();
+    }
+    let mut setting = String::from(
+        "\n  # TPM2 auto-unlock, enrolled by the installer\n  boot.initrd.systemd.enable = true;\n",
+    );
+    for name in names {
+        setting.push_str(&format!(
+            "  boot.initrd.luks.devices.{}.crypttabExtraOpts = [ \"tpm2-device=auto\" ];\n",
+            name
+        ));
+    }
+    insert_before_closing_brace(host, &setting)
+}
+
+/// Add settings at the end of a module's attribute set
+fn insert_before_closing_brace(content: &str, setting: &str) -> String {
+    let mut content = content.to_string();
+    match content.rfind('}') {
+        Some(end) => content.insert_str(end, setting),
+        None => content.push_str(setting),
+    }
+    content
+}
+
+/// Open LUKS mappings made by disko (`crypt*`) and their backing partitions,
+/// from `lsblk -nrpo NAME,TYPE,PKNAME`
+fn parse_crypt_volumes(lsblk: &str) -> Vec<(String, String)> {
+    lsblk
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?.strip_prefix("/dev/mapper/")?;
+            if fields.next()? != "crypt" || !name.starts_with("crypt") {
+                return None;
+            }
+            Some((name.to_string(), fields.next()?.to_string()))
+        })
+        .collect()
 }
 
 /// Inject passwordFile into disko LUKS configuration
-/// Adds `passwordFile = "/tmp/luks-password";` after `name = "cryptroot";`
+/// Adds `passwordFile = "/tmp/luks-password";` after each `name = "crypt...";`
 fn inject_luks_password_file(content: &str) -> String {
     let replacement = format!(
         r#"$1
@@ -580,10 +2808,49 @@ fn copy_dir_recursive(src: &str, dst: &str) -> std::io::Result<()> {
     Ok(())
 }
 
+/// Add the lanzaboote module to a host's imports, after its bootloader module
+///
+/// None if the file has no `imports = [` list to extend.
+fn add_secure_boot_import(content: &str) -> Option<String> {
+    add_host_import(content, SECURE_BOOT_MODULE, Some("../../modules/boot/"))
+}
+
+/// Add `module` to a host's imports, after the line importing `after` if
+/// there is one, else first in the list
+///
+/// None if the file has no multi-line `imports = [` list to extend.
+fn add_host_import(content: &str, module: &str, after: Option<&str>) -> Option<String> {
+    if content.contains(module) {
+        return Some(content.to_string());
+    }
+    let (anchor, extra_indent) = match after.and_then(|after| content.find(after)) {
+        Some(i) => (i, ""),
+        None => (content.find("imports = [")?, "  "),
+    };
+    let line_start = content[..anchor].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = anchor + content[anchor..].find('\n')?;
+    // A one-line list has no line of its own to add ours after
+    if content[line_start..line_end].contains(']') {
+        return None;
+    }
+    let indent: String = content[line_start..]
+        .chars()
+        .take_while(|c| *c == ' ')
+        .collect();
+    Some(format!(
+        "{}\n{}{}{}{}",
+        &content[..line_end],
+        indent,
+        extra_indent,
+        module,
+        &content[line_end..]
+    ))
+}
+
 /// Update flake.nix to set username for a specific host configuration
 /// Only modifies the file if username differs from the default
 fn update_flake_username(content: &str, hostname: &str, username: &str) -> String {
-    if username == DEFAULT_USERNAME {
+    if username == FLAKE_DEFAULT_USERNAME {
         // No modification needed for default username
         return content.to_string();
     }
@@ -634,3 +2901,296 @@ fn update_flake_username(content: &str, hostname: &str, username: &str) -> Strin
     );
     content.to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::DiskOptions;
+
+    #[test]
+    fn test_disko_boot_mode() {
+        let uefi = "{ ... }:\n{\n  imports = [ ./default.nix ];\n}\n";
+        let bios = "{ ... }:\n{\n  imports = [ ./bios.nix ];\n}\n";
+        assert_eq!(disko_boot_mode(uefi), BootMode::Uefi);
+        assert_eq!(disko_boot_mode(bios), BootMode::Bios);
+        assert_eq!(disko_layout_file(BootMode::Bios), "bios.nix");
+    }
+
+    #[test]
+    fn test_add_secure_boot_import() {
+        let host = "{ ... }:\n\n{\n  imports = [\n    ./hardware-configuration.nix\n    \
+                    ../../modules/boot/limine-plymouth.nix\n  ];\n}\n";
+        let updated = add_secure_boot_import(host).unwrap();
+        assert!(updated.contains(
+            "/limine-plymouth.nix\n    ../../modules/boot/secure-boot.nix\n  ];"
+        ));
+        assert_eq!(add_secure_boot_import(&updated).unwrap(), updated);
+
+        let no_boot = "{ ... }:\n{\n  imports = [ ./hardware-configuration.nix ];\n}\n";
+        assert_eq!(add_secure_boot_import(no_boot), None);
+        let list = "{ ... }:\n{\n  imports = [\n    ./hardware-configuration.nix\n  ];\n}\n";
+        assert!(add_secure_boot_import(list)
+            .unwrap()
+            .contains("  imports = [\n    ../../modules/boot/secure-boot.nix\n    ./hardware"));
+    }
+
+    #[test]
+    fn test_disko_uses_zfs() {
+        let zfs = "{ ... }:\n{\n  imports = [ ./zfs.nix ];\n}\n";
+        let uefi = "{ ... }:\n{\n  imports = [ ./default.nix ];\n}\n";
+        assert!(disko_uses_zfs(zfs));
+        assert!(!disko_uses_zfs(uefi));
+        // ZFS hosts are UEFI only
+        assert_eq!(disko_boot_mode(zfs), BootMode::Uefi);
+    }
+
+    #[test]
+    fn test_alongside_disko_config() {
+        let host = templates::generate_disko_config(
+            "G1a",
+            "/dev/nvme0n1",
+            BootMode::Uefi,
+            None,
+            None,
+            &DiskOptions::default(),
+            None,
+        );
+        let entry = limine_windows_entry("8E3F1A2B-0C4D", WINDOWS_LOADER_PATH);
+        let config = alongside_disko_config(&host, Some(&entry));
+        assert!(disko_is_alongside(&config));
+        assert!(!config.contains("./default.nix"));
+        assert!(config.contains(r#"device = "/dev/disk/by-partlabel/nixos-root";"#));
+        assert!(config.contains("path: guid(8e3f1a2b-0c4d):/EFI/Microsoft/Boot/bootmgfw.efi"));
+        assert!(config.ends_with("  '';\n}\n"));
+
+        let plain = alongside_disko_config(&host, None);
+        assert!(!plain.contains("extraEntries"));
+    }
+
+    #[test]
+    fn test_multi_disk_config() {
+        let host = templates::generate_disko_config(
+            "kraken",
+            "/dev/nvme0n1",
+            BootMode::Uefi,
+            None,
+            None,
+            &DiskOptions::default(),
+            None,
+        );
+        let assign = |path: &str, role| DiskAssignment {
+            disk: crate::system::disk::DiskInfo {
+                path: path.to_string(),
+                size: "1T".to_string(),
+                size_bytes: 0,
+                model: None,
+                partitions: vec![],
+                free_bytes: 0,
+                by_id: None,
+            },
+            role,
+        };
+
+        let home = multi_disk_config(&host, &[assign("/dev/sda", DiskRole::Home)]);
+        assert!(home.contains("imports = [ ./default.nix ./home-disk.nix ];"));
+        assert!(home.contains(r#"disko.devices.disk.home.device = "/dev/sda";"#));
+        assert!(home.ends_with("\"/dev/sda\";\n}\n"));
+        assert_eq!(disko_layout_files(&home), vec!["default.nix", "home-disk.nix"]);
+
+        let both = multi_disk_config(
+            &host,
+            &[assign("/dev/nvme1n1", DiskRole::Mirror), assign("/dev/sda", DiskRole::Home)],
+        );
+        assert!(both.contains("imports = [ ./mirror.nix ./home-disk.nix ];"));
+        assert!(both.contains(r#"disko.devices.disk.main.device = "/dev/nvme0n1";"#));
+        assert!(both.contains(r#"disko.devices.disk.mirror.device = "/dev/nvme1n1";"#));
+        assert_eq!(disko_layout_files(&both), vec!["mirror.nix", "home-disk.nix"]);
+        assert_eq!(disko_layout_files(&host), vec!["default.nix"]);
+    }
+
+    #[test]
+    fn test_disable_encryption() {
+        let host = templates::generate_disko_config(
+            "kraken",
+            "/dev/nvme0n1",
+            BootMode::Uefi,
+            None,
+            None,
+            &DiskOptions::default(),
+            None,
+        );
+        let plain = disable_encryption(&host);
+        assert!(plain.ends_with("  diskLayout.encrypt = false;\n}\n"));
+        assert!(plain.contains(r#"disko.devices.disk.main.device = "/dev/nvme0n1";"#));
+        assert_eq!(disable_encryption(&plain), plain);
+    }
+
+    #[test]
+    fn test_add_tpm2_unlock() {
+        let host = "{ config, ... }:\n\n{\n  networking.hostName = \"G1a\";\n}\n";
+        let updated = add_tpm2_unlock(host, &["cryptroot", "crypthome"]);
+        assert!(updated.contains("  boot.initrd.systemd.enable = true;\n"));
+        assert!(updated.ends_with(concat!(
+            r#"  boot.initrd.luks.devices.crypthome.crypttabExtraOpts = [ "tpm2-device=auto" ];"#,
+            "\n}\n"
+        )));
+        assert_eq!(updated.matches("crypttabExtraOpts").count(), 2);
+        assert_eq!(add_tpm2_unlock(&updated, &["cryptroot"]), updated);
+    }
+
+    #[test]
+    fn test_swapfile_resume_module() {
+        // G1a already sets boot.kernelParams; a second definition in the same
+        // attribute set would not evaluate
+        let host = concat!(
+            "{ config, ... }:\n\n{\n  imports = [\n    ./hardware-configuration.nix\n",
+            "    ../../modules/boot/limine-plymouth.nix\n  ];\n\n",
+            "  boot.kernelParams = [\n    \"amdgpu.dcdebugmask=0x10\"\n  ];\n}\n"
+        );
+        let updated = add_host_import(host, HIBERNATION_MODULE, None).unwrap();
+        assert!(updated.contains("  imports = [\n    ./hibernation.nix\n    ./hardware"));
+        assert_eq!(updated.matches("boot.kernelParams").count(), 1);
+        assert_eq!(add_host_import(&updated, HIBERNATION_MODULE, None).unwrap(), updated);
+
+        let module = swapfile_resume_module(resume_device(true), 533760);
+        assert_eq!(
+            module,
+            concat!(
+                "# Hibernation into the swapfile, offset found by the installer\n{\n",
+                "  boot.resumeDevice = \"/dev/mapper/cryptroot\";\n",
+                "  boot.kernelParams = [ \"resume_offset=533760\" ];\n}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_crypt_volumes() {
+        let lsblk = "\
+/dev/nvme0n1 disk
+/dev/nvme0n1p1 part /dev/nvme0n1
+/dev/nvme0n1p2 part /dev/nvme0n1
+/dev/mapper/cryptroot crypt /dev/nvme0n1p2
+/dev/mapper/ventoy dm /dev/sda1
+/dev/mapper/luks-backup crypt /dev/sdb1
+";
+        assert_eq!(
+            parse_crypt_volumes(lsblk),
+            vec![("cryptroot".to_string(), "/dev/nvme0n1p2".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_destructive_commands() {
+        let script = "\
+#!/nix/store/abc-bash/bin/bash
+set -efux
+  sgdisk --align-end --new=1:0:+2G --change-name=1:disk-main-ESP /dev/nvme0n1
+  cryptsetup -q luksFormat /dev/disk/by-partlabel/disk-main-luks
+mkfs.vfat /dev/disk/by-partlabel/disk-main-ESP
+echo mkfs.btrfs in a message
+";
+        assert_eq!(
+            destructive_commands(script),
+            vec![
+                "sgdisk --align-end --new=1:0:+2G --change-name=1:disk-main-ESP /dev/nvme0n1",
+                "cryptsetup -q luksFormat /dev/disk/by-partlabel/disk-main-luks",
+                "mkfs.vfat /dev/disk/by-partlabel/disk-main-ESP",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nixos_anywhere_args() {
+        let alice = PrimaryUser { name: "alice".to_string(), uid: 1000, gid: 100 };
+        let args = nixos_anywhere_args("/tmp/cfg#kraken", "nixos@10.0.0.5", &alice, false);
+        let joined = args.join(" ");
+        assert!(joined.contains("--flake /tmp/cfg#kraken --target-host nixos@10.0.0.5"));
+        assert!(joined.contains("--phases kexec,disko,install"));
+        assert!(joined.contains("--chown /home/alice 1000:100"));
+        assert!(!joined.contains("--disk-encryption-keys"));
+
+        let args = nixos_anywhere_args("/tmp/cfg#kraken", "nixos@10.0.0.5", &alice, true);
+        assert!(args.join(" ").ends_with(
+            "--disk-encryption-keys /tmp/luks-password /tmp/luks-password"
+        ));
+    }
+
+    #[test]
+    fn test_remote_root_target() {
+        assert_eq!(remote_root_target("nixos@10.0.0.5"), "root@10.0.0.5");
+        assert_eq!(remote_root_target("root@[fe80::1]"), "root@[fe80::1]");
+    }
+
+    #[test]
+    fn test_inject_luks_password_file_all_devices() {
+        let layout = "name = \"cryptroot\";\nname = \"cryptmirror\";\nname = \"other\";\n";
+        let injected = inject_luks_password_file(layout);
+        assert_eq!(injected.matches("passwordFile").count(), 2);
+    }
+
+    #[test]
+    fn test_alongside_sgdisk_args() {
+        let args = alongside_sgdisk_args(409839616, 998166527, 512);
+        assert_eq!(args[0], "--new=0:409839616:414033919");
+        assert_eq!(args[2], "--change-name=0:nixos-boot");
+        assert_eq!(args[3], "--new=0:414033920:998166527");
+        assert_eq!(args[4], "--typecode=0:8309");
+    }
+
+    #[test]
+    fn test_parse_os_prober() {
+        let output = concat!(
+            "/dev/sda2:Ubuntu 24.04 LTS:Ubuntu:linux\n",
+            "/dev/nvme0n1p1@/EFI/Microsoft/Boot/bootmgfw.efi:Windows Boot Manager:Windows:efi\n",
+        );
+        assert_eq!(
+            parse_os_prober(output),
+            Some((
+                "/dev/nvme0n1p1".to_string(),
+                "/EFI/Microsoft/Boot/bootmgfw.efi".to_string()
+            ))
+        );
+        assert_eq!(parse_os_prober(""), None);
+        assert_eq!(parse_os_prober("/dev/sda2:Ubuntu 24.04 LTS:Ubuntu:linux\n"), None);
+    }
+
+    #[test]
+    fn test_update_disk_device_updates_grub_device() {
+        let content = r#"  disko.devices.disk.main.device = "/dev/sda";
+  boot.loader.grub.device = "/dev/sda";"#;
+        let updated = update_disk_device(content, "/dev/vda");
+        assert!(!updated.contains("/dev/sda"));
+        assert_eq!(updated.matches("\"/dev/vda\"").count(), 2);
+
+        // A by-id path written by create-host is replaced the same way
+        let by_id = "/dev/disk/by-id/ata-ST2000DM008_ZFL1ABCD";
+        let updated = update_disk_device(&update_disk_device(content, by_id), "/dev/vda");
+        assert_eq!(updated.matches("\"/dev/vda\"").count(), 2);
+    }
+
+    #[test]
+    fn test_chpasswd_extra_users() {
+        assert_eq!(chpasswd_input("cor", "it's", &[]), "'cor:it'\"'\"'s'");
+        assert_eq!(chpasswd_command(&[]), format!("chpasswd && {}", SAVE_SHADOW_COMMAND));
+
+        let extra = ["alice".to_string()];
+        assert_eq!(chpasswd_input("cor", "pw", &extra), "'cor:pw' 'alice:pw'");
+        assert_eq!(
+            chpasswd_command(&extra),
+            format!("chpasswd && passwd -e alice && {}", SAVE_SHADOW_COMMAND)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_print_progress_handles_both_channels() {
+        let (tx, mut rx) = command_channel();
+        tx.stdout("building").await.unwrap();
+        tx.send(CommandMessage::StepComplete { step: "Build".to_string() }).await.unwrap();
+        tx.stderr("warning: dirty tree").await.unwrap();
+        tx.send(CommandMessage::Done { success: true }).await.unwrap();
+
+        let run = print_progress_recorded(&mut rx).await;
+        assert!(run.success);
+        assert_eq!(run.lines, vec!["building", "warning: dirty tree"]);
+    }
+}
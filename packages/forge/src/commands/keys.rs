@@ -1,13 +1,80 @@
 //! Key management commands (Age and SSH keys)
+//!
+//! The keys archive passphrase is typed into forge and handed to
+//! keys-backup/keys-restore through a private temporary file, unless
+//! app-backup's config names a 1Password reference for it.
 
-use anyhow::Result;
-use tokio::sync::mpsc;
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
+use super::executor::run_command_transformed;
 use super::runner::{spawn_with_error_handling, CommandRunner};
-use super::CommandMessage;
+use super::{CommandMessage, CommandSender};
+use crate::constants;
+
+/// Times forge asks for the archive passphrase before keys restore gives up
+pub const MAX_PASSPHRASE_ATTEMPTS: u32 = 3;
+
+/// Line keys-restore prints (and exits 3 after) when age rejects the passphrase
+const WRONG_PASSPHRASE_MARKER: &str = "Wrong passphrase for keys archive";
+
+/// How a keys-restore run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreOutcome {
+    Restored,
+    WrongPassphrase,
+    Failed,
+}
+
+/// Whether the scripts read the archive passphrase from 1Password
+pub fn passphrase_in_1password() -> bool {
+    std::fs::read_to_string(constants::app_backup_config_path())
+        .map(|config| has_1password_reference(&config))
+        .unwrap_or(false)
+}
+
+fn has_1password_reference(config: &str) -> bool {
+    config.lines().any(|line| {
+        line.trim()
+            .strip_prefix("KEYS_PASSPHRASE_1PASSWORD=")
+            .is_some_and(|value| !value.trim().trim_matches(['"', '\'']).is_empty())
+    })
+}
+
+/// Passphrase written to a file only the user can read, removed on drop
+struct PassphraseFile(PathBuf);
+
+impl PassphraseFile {
+    fn create(passphrase: &str) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("forge-keys-{}", std::process::id()));
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        file.write_all(passphrase.as_bytes())?;
+        Ok(Self(path))
+    }
+
+    fn path(&self) -> String {
+        self.0.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for PassphraseFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
 
 /// Start key setup from 1Password
-pub async fn start_setup(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
+pub async fn start_setup(tx: CommandSender) -> Result<()> {
     spawn_with_error_handling(tx, "Key setup", "Setup", |tx| async move {
         let runner = CommandRunner::new(&tx);
         runner
@@ -24,14 +91,23 @@ pub async fn start_setup(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
 }
 
 /// Start key backup
-pub async fn start_backup(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
-    spawn_with_error_handling(tx, "Key backup", "Backup", |tx| async move {
+pub async fn start_backup(
+    tx: CommandSender,
+    passphrase: Option<String>,
+) -> Result<()> {
+    spawn_with_error_handling(tx, "Key backup", "Backup", move |tx| async move {
         let runner = CommandRunner::new(&tx);
+        let file = passphrase.as_deref().map(PassphraseFile::create).transpose()?;
+        let mut args = vec!["--push".to_string()];
+        if let Some(file) = &file {
+            args.extend(["--passphrase-file".to_string(), file.path()]);
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
         runner
             .run_simple_operation(
                 "Key Backup",
                 "keys-backup",
-                &["--push"],
+                &args,
                 "Keys backed up successfully",
                 "Backup failed",
             )
@@ -41,21 +117,75 @@ pub async fn start_backup(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
 }
 
 /// Start key restore
-pub async fn start_restore(tx: mpsc::Sender<CommandMessage>, force: bool) -> Result<()> {
+pub async fn start_restore(
+    tx: CommandSender,
+    force: bool,
+    passphrase: Option<String>,
+) -> Result<()> {
     spawn_with_error_handling(tx, "Key restore", "Restore", move |tx| async move {
         let runner = CommandRunner::new(&tx);
-        let args: Vec<&str> = if force {
-            vec!["--pull", "--force"]
-        } else {
-            vec!["--pull"]
-        };
+        let file = passphrase.as_deref().map(PassphraseFile::create).transpose()?;
+        let mut args = vec!["--pull".to_string()];
+        if force {
+            args.push("--force".to_string());
+        }
+        if let Some(file) = &file {
+            args.extend(["--passphrase-file".to_string(), file.path()]);
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+        runner.header("Key Restore").await;
+        let outcome = run_restore(&tx, &args).await?;
+        runner.out("").await;
+        match outcome {
+            RestoreOutcome::Restored => runner.out("  Keys restored successfully").await,
+            RestoreOutcome::WrongPassphrase => {
+                runner.out("  Restore failed: wrong passphrase").await;
+                tx.send(CommandMessage::PassphraseRejected).await?;
+            }
+            RestoreOutcome::Failed => runner.out("  Restore failed").await,
+        }
+        runner.footer().await;
+        runner.done(outcome == RestoreOutcome::Restored).await?;
+        Ok(())
+    })
+}
+
+/// Run keys-restore, streaming its output and noting a rejected passphrase
+pub async fn run_restore(
+    tx: &CommandSender,
+    args: &[&str],
+) -> Result<RestoreOutcome> {
+    let rejected = Arc::new(AtomicBool::new(false));
+    let seen = Arc::clone(&rejected);
+    let success = run_command_transformed(tx, "keys-restore", args, move |line| {
+        if line.contains(WRONG_PASSPHRASE_MARKER) {
+            seen.store(true, Ordering::Relaxed);
+        }
+        Some(line.to_string())
+    })
+    .await?;
+    Ok(if success {
+        RestoreOutcome::Restored
+    } else if rejected.load(Ordering::Relaxed) {
+        RestoreOutcome::WrongPassphrase
+    } else {
+        RestoreOutcome::Failed
+    })
+}
+
+/// Start registering the SSH key with GitHub (keys-github), then checking access
+pub async fn start_github(tx: CommandSender, deploy: bool) -> Result<()> {
+    spawn_with_error_handling(tx, "GitHub key registration", "GitHub", move |tx| async move {
+        let runner = CommandRunner::new(&tx);
+        let args: &[&str] = if deploy { &["--deploy"] } else { &[] };
         runner
             .run_simple_operation(
-                "Key Restore",
-                "keys-restore",
-                &args,
-                "Keys restored successfully",
-                "Restore failed",
+                "Register Key with GitHub",
+                "keys-github",
+                args,
+                "SSH key registered and access verified",
+                "GitHub registration failed",
             )
             .await?;
         Ok(())
@@ -63,7 +193,7 @@ pub async fn start_restore(tx: mpsc::Sender<CommandMessage>, force: bool) -> Res
 }
 
 /// Start key status check
-pub async fn start_status(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
+pub async fn start_status(tx: CommandSender) -> Result<()> {
     tokio::spawn(async move {
         let runner = CommandRunner::new(&tx);
         runner.header("Key Status").await;
@@ -82,3 +212,16 @@ pub async fn start_status(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
     });
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_1password_reference() {
+        let config = "BACKUP_REPO=\"x\"\nKEYS_PASSPHRASE_1PASSWORD=\"op://Private/keys/pw\"\n";
+        assert!(has_1password_reference(config));
+        assert!(!has_1password_reference("KEYS_PASSPHRASE_1PASSWORD=\"\"\n"));
+        assert!(!has_1password_reference("# KEYS_PASSPHRASE_1PASSWORD=\"op://x\"\n"));
+    }
+}
@@ -0,0 +1,90 @@
+//! Linting the config repo with statix and deadnix
+//!
+//! The linters are taken from PATH when installed, otherwise run from
+//! nixpkgs. Linting only reports: a finding or a missing tool never fails
+//! the command it runs in.
+
+use anyhow::{bail, Result};
+use std::path::Path;
+
+use super::executor::{command_exists, run_capture};
+use super::runner::CommandRunner;
+use crate::system::lint::{
+    count_summary, parse_deadnix, parse_statix, report_lines, LintFinding, LintTool,
+};
+
+/// Unused `{ config, pkgs, ... }` arguments are normal in NixOS modules
+const DEADNIX_FLAGS: [&str; 1] = ["--no-lambda-pattern-names"];
+
+/// Lint `paths` (relative to `repo`) and print the findings grouped by file
+///
+/// With `fix`, `statix fix` and `deadnix --edit` rewrite the files first, so
+/// only what they can't fix is reported.
+pub async fn lint_paths(
+    runner: &CommandRunner<'_>,
+    repo: &Path,
+    paths: &[&str],
+    fix: bool,
+) -> Vec<LintFinding> {
+    let repo = repo.to_string_lossy();
+
+    if fix {
+        runner.out("  Applying statix and deadnix fixes...").await;
+        for path in paths {
+            if let Err(e) = run_tool(&repo, LintTool::Statix, &["fix", path]).await {
+                tracing::warn!("statix fix {} failed: {}", path, e);
+            }
+        }
+        let mut args = vec!["--edit"];
+        args.extend(DEADNIX_FLAGS);
+        args.extend(paths);
+        if let Err(e) = run_tool(&repo, LintTool::Deadnix, &args).await {
+            tracing::warn!("deadnix --edit failed: {}", e);
+        }
+    }
+
+    let mut findings = Vec::new();
+    // statix checks one file or directory per run
+    for path in paths {
+        match run_tool(&repo, LintTool::Statix, &["check", "-o", "errfmt", path]).await {
+            Ok(output) => findings.extend(parse_statix(&output)),
+            Err(e) => {
+                runner.err(&format!("  ⚠ statix could not run: {}", e)).await;
+                break;
+            }
+        }
+    }
+    let mut args = vec!["-o", "json"];
+    args.extend(DEADNIX_FLAGS);
+    args.extend(paths);
+    match run_tool(&repo, LintTool::Deadnix, &args).await {
+        Ok(output) => findings.extend(parse_deadnix(&output)),
+        Err(e) => runner.err(&format!("  ⚠ deadnix could not run: {}", e)).await,
+    }
+
+    runner.out(&format!("  Lint: {}", count_summary(&findings))).await;
+    for line in report_lines(&findings) {
+        runner.out(&line).await;
+    }
+    findings
+}
+
+/// Run a linter inside `repo`; Err only if it couldn't run at all
+async fn run_tool(repo: &str, tool: LintTool, args: &[&str]) -> Result<String> {
+    let name = tool.to_string();
+    let installable = format!("nixpkgs#{}", name);
+    let mut argv = vec!["-C", repo];
+    if command_exists(&name).await {
+        argv.push(&name);
+    } else {
+        argv.extend(["nix", "run", installable.as_str(), "--"]);
+    }
+    argv.extend(args);
+
+    let (ok, stdout, stderr) = run_capture("env", &argv).await?;
+    // statix exits non-zero when it reports something, so only silence is a failure
+    if !ok && stdout.trim().is_empty() {
+        bail!("{}", stderr.lines().last().unwrap_or("exited with an error").trim());
+    }
+    Ok(stdout)
+}
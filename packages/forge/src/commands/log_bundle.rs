@@ -0,0 +1,151 @@
+//! Packing the install log bundle (see `system::log_bundle`)
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::executor::run_capture;
+use super::{CommandMessage, CommandSender};
+use crate::constants::{forge_data_dir, temp_config_dir, INSTALL_MOUNT_POINT};
+use crate::system::hardware::{detect_all, detect_boot_mode, detect_secure_boot};
+use crate::system::log_bundle::{
+    bundle_dir, bundle_name, forge_log_files, host_config_paths, BundleSources,
+};
+use crate::system::manifest::run_manifest_path;
+
+/// Columns of the `lsblk -J` snapshot
+const LSBLK_COLUMNS: &str = "NAME,PATH,SIZE,TYPE,FSTYPE,LABEL,PARTLABEL,MOUNTPOINTS,MODEL";
+
+/// Write the log bundle in the background
+pub async fn start_log_bundle(tx: CommandSender, sources: BundleSources) -> Result<()> {
+    tokio::spawn(async move {
+        let result = write_bundle(&sources).await.map_err(|e| {
+            tracing::warn!("Failed to save install logs: {:#}", e);
+            format!("{:#}", e)
+        });
+        let _ = tx.send(CommandMessage::LogBundleSaved(result)).await;
+    });
+    Ok(())
+}
+
+async fn write_bundle(sources: &BundleSources) -> Result<PathBuf> {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let name = bundle_name(sources.host.as_deref(), &stamp);
+    let staging_root = std::env::temp_dir();
+    let staging = staging_root.join(&name);
+    std::fs::create_dir_all(&staging)
+        .with_context(|| format!("Failed to create {}", staging.display()))?;
+
+    let result = async {
+        collect(sources, &staging).await?;
+
+        let target_mounted = run_capture("mountpoint", &["-q", INSTALL_MOUNT_POINT])
+            .await
+            .map(|(ok, _, _)| ok)
+            .unwrap_or(false);
+        let (dir, _) = bundle_dir(target_mounted);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let bundle = dir.join(format!("{}.tar.gz", name));
+
+        let bundle_arg = bundle.to_string_lossy().to_string();
+        let root_arg = staging_root.to_string_lossy().to_string();
+        let (ok, _, stderr) =
+            run_capture("tar", &["-czf", &bundle_arg, "-C", &root_arg, &name]).await?;
+        if !ok {
+            anyhow::bail!("tar failed: {}", stderr.trim());
+        }
+        Ok(bundle)
+    }
+    .await;
+
+    if let Err(e) = std::fs::remove_dir_all(&staging) {
+        tracing::warn!("Failed to remove {}: {}", staging.display(), e);
+    }
+    result
+}
+
+/// Copy the logs and configs into `staging` and write the system snapshots
+async fn collect(sources: &BundleSources, staging: &Path) -> Result<()> {
+    copy_into(&sources.screen_log, staging).await;
+    if let Some(recording) = &sources.recording {
+        copy_into(recording, staging).await;
+    }
+    copy_into(run_manifest_path(), staging).await;
+    for log in forge_log_files(&forge_data_dir()) {
+        copy_into(&log, staging).await;
+    }
+
+    if !sources.build_logs.is_empty() {
+        let dir = staging.join("build-logs");
+        std::fs::create_dir_all(&dir)?;
+        for log in &sources.build_logs {
+            copy_into(log, &dir).await;
+        }
+    }
+
+    if let Some(host) = &sources.host {
+        let config = temp_config_dir();
+        let dir = staging.join("config");
+        for rel in host_config_paths(host) {
+            let dest = dir.join(&rel);
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            copy_into(&config.join(&rel), dest.parent().unwrap_or(&dir)).await;
+        }
+    }
+
+    let (_, lsblk, stderr) = run_capture("lsblk", &["-J", "-o", LSBLK_COLUMNS]).await?;
+    write_snapshot(staging, "lsblk.json", if lsblk.is_empty() { &stderr } else { &lsblk })?;
+    write_snapshot(staging, "hardware.txt", &hardware_summary().await)?;
+    Ok(())
+}
+
+/// Copy a file or directory into `dir`, keeping its name; missing sources are skipped
+async fn copy_into(src: &Path, dir: &Path) {
+    if !src.exists() {
+        return;
+    }
+    let src_arg = src.to_string_lossy().to_string();
+    let dir_arg = dir.to_string_lossy().to_string();
+    match run_capture("cp", &["-r", &src_arg, &dir_arg]).await {
+        Ok((true, _, _)) => {}
+        Ok((false, _, stderr)) => {
+            tracing::warn!("Failed to copy {}: {}", src.display(), stderr.trim())
+        }
+        Err(e) => tracing::warn!("Failed to copy {}: {}", src.display(), e),
+    }
+}
+
+fn write_snapshot(staging: &Path, name: &str, text: &str) -> Result<()> {
+    let path = staging.join(name);
+    std::fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Boot mode, detected hardware and the raw outputs they come from
+async fn hardware_summary() -> String {
+    let mut out = format!(
+        "Boot mode: {}\nSecure Boot: {}\n",
+        detect_boot_mode(),
+        detect_secure_boot()
+    );
+    match detect_all() {
+        Ok(info) => out.push_str(&format!("{:#?}\n", info)),
+        Err(e) => out.push_str(&format!("Hardware detection failed: {}\n", e)),
+    }
+    for (cmd, args) in [("uname", &["-a"][..]), ("lspci", &["-nnk"][..])] {
+        out.push_str(&format!("\n$ {} {}\n", cmd, args.join(" ")));
+        match run_capture(cmd, args).await {
+            Ok((_, stdout, stderr)) => {
+                out.push_str(&stdout);
+                out.push_str(&stderr);
+            }
+            Err(e) => out.push_str(&format!("{}\n", e)),
+        }
+    }
+    for file in ["/proc/cpuinfo", "/proc/meminfo"] {
+        out.push_str(&format!("\n$ cat {}\n", file));
+        out.push_str(&std::fs::read_to_string(file).unwrap_or_else(|e| format!("{}\n", e)));
+    }
+    out
+}
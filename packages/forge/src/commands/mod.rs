@@ -1,15 +1,68 @@
 //! Command execution module
 
 pub mod apps;
+pub mod bisect;
+pub mod bootstrap;
+pub mod build_log;
+pub mod channel;
+pub mod channels;
+pub mod collect;
 pub mod create_host;
+pub mod devshell;
 pub mod errors;
 pub mod executor;
+pub mod fleet;
+pub mod format;
+pub mod gc;
+pub mod generations;
+pub mod host_archive;
 pub mod install;
 pub mod keys;
+pub mod lint;
+pub mod log_bundle;
+pub mod nix_conf;
+pub mod optimise;
+pub mod perms;
+pub mod pipeline;
+pub mod probe;
+pub mod registry;
+pub mod report;
+pub mod review;
+pub mod rollback;
 pub mod runner;
+pub mod services;
+pub mod status;
 pub mod update;
+pub mod wifi;
 
+pub use channel::{command_channel, CommandReceiver, CommandSender, OutputLine};
 pub use errors::ParsedError;
+pub use review::EditReview;
+pub use update::preview::SwitchPreview;
+
+use crate::app::scheduler::TaskResult;
+use update::flake::FlakeInputChange;
+use update::report::UpdateReport;
+use crate::system::bisect::Bisection;
+use crate::system::build_log::SavedBuildLog;
+use crate::system::build_progress::BuildProgress;
+use crate::system::channels::HostChannel;
+use crate::system::config::HostConfig;
+use crate::system::devshell::DevShell;
+use crate::system::disk::DiskInfo;
+use crate::system::fleet::DeployStage;
+use crate::system::gc::GcConfig;
+use crate::system::generations::Generation;
+use crate::system::host_state::HostDrift;
+use crate::system::install_progress::StepProgress;
+use crate::system::nix_conf::NixConfConfig;
+use crate::system::optimise::OptimiseProgress;
+use crate::system::preflight::PreflightCheck;
+use crate::system::registry::RegistryRow;
+use crate::system::services::ServiceRow;
+use crate::system::wifi::WifiNetwork;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 /// Standard step names for consistent messaging
 #[allow(dead_code)]
@@ -35,6 +88,10 @@ pub mod steps {
     pub const RESTORE: &str = "Restore";
     pub const UPDATE: &str = "Update";
 
+    // Bootstrap steps
+    pub const KEYS: &str = "keys";
+    pub const APP_PROFILES: &str = "app profiles";
+
     // Create host steps
     pub const HOST_DIR: &str = "host";
     pub const HW_CONFIG: &str = "hardware";
@@ -43,26 +100,117 @@ pub mod steps {
     pub const FLAKE_NIX: &str = "flake";
 }
 
-/// Messages sent from command execution to UI
+/// Events sent from command execution to UI (output lines go on their own
+/// channel, see `channel`)
 #[derive(Debug, Clone)]
 pub enum CommandMessage {
-    /// Standard output line
-    Stdout(String),
-    /// Standard error line
-    Stderr(String),
+    /// Something worth noting that doesn't fail the step
+    Warning(String),
+    /// Step started (for steps that don't run strictly in order, e.g. fleet hosts)
+    StepStarted { step: String },
     /// Step completed successfully
     StepComplete { step: String },
     /// Step failed with rich error information
     StepFailed { step: String, error: ParsedError },
     /// Step was skipped
     StepSkipped { step: String },
+    /// Percentage (and ETA) of a long-running step, parsed from its output
+    StepProgress { step: String, progress: StepProgress },
+    /// keys-restore rejected the archive passphrase (sent before Done)
+    PassphraseRejected,
+    /// The install's network check failed (sent before Done)
+    NetworkUnavailable,
+    /// An edit to a config file waiting for approval (see `review`)
+    ReviewEdit(EditReview),
+    /// A built system waiting for the go-ahead to switch (`update --preview`)
+    PreviewSwitch(SwitchPreview),
     /// Command fully completed
     Done { success: bool },
+    /// Host discovery finished (runs in the background on first need)
+    HostsDiscovered(Vec<HostConfig>),
+    /// Disk probing finished for the current disk selection screen
+    DisksDetected(Vec<DiskInfo>),
+    /// Live ISO preflight checks finished
+    PreflightComplete(Vec<PreflightCheck>),
+    /// A fleet host moved on to the next stage of its deploy
+    FleetStage { host: String, stage: DeployStage },
+    /// Host reports loaded from the state branch (or why they couldn't be)
+    FleetStatus(Result<Vec<HostDrift>, String>),
+    /// Flake devShells found for the dev shells screen
+    DevShellsFound(Vec<DevShell>),
+    /// Registry list after a load or change, with the change's outcome
+    Registry {
+        rows: Result<Vec<RegistryRow>, String>,
+        message: Option<Result<String, String>>,
+    },
+    /// gc.json after a load or save, with the save's outcome
+    Gc {
+        config: Result<GcConfig, String>,
+        hosts: Vec<String>,
+        message: Option<Result<String, String>>,
+    },
+    /// nix-conf.json after a load or save, with this machine's nix.conf values
+    /// and the save's outcome
+    NixConf {
+        config: Result<NixConfConfig, String>,
+        hosts: Vec<String>,
+        this_host: Option<String>,
+        live: Result<BTreeMap<String, String>, String>,
+        message: Option<Result<String, String>>,
+    },
+    /// Hosts' update channels after a load or a move, with the move's outcome
+    Channels {
+        hosts: Result<Vec<HostChannel>, String>,
+        /// Release of the stable inputs, None while no host uses them
+        stable: Option<String>,
+        message: Option<Result<String, String>>,
+    },
+    /// Background services on this host after a load or a change, with the change's outcome
+    Services {
+        host: String,
+        rows: Result<Vec<ServiceRow>, String>,
+        message: Option<Result<String, String>>,
+    },
+    /// Paths copied and derivations built by the running nix build
+    BuildProgress(BuildProgress),
+    /// Running totals from `nix store optimise`
+    OptimiseProgress(OptimiseProgress),
+    /// nix-collect-garbage finished, freeing this many bytes
+    GarbageCollected { freed: u64 },
+    /// System generations for `forge rollback` (or why they couldn't be read)
+    Generations(Result<Vec<Generation>, String>),
+    /// Generations with closure sizes after a load or change, with the change's outcome
+    GenerationTable {
+        generations: Result<Vec<Generation>, String>,
+        message: Option<Result<String, String>>,
+    },
+    /// Commit range for `forge bisect` (or why it couldn't be loaded)
+    BisectRange(Result<Bisection, String>),
+    /// `nix log` of the derivations that failed in the last run
+    BuildLogsSaved(Vec<SavedBuildLog>),
+    /// Where the install log bundle was written (or why it couldn't be)
+    LogBundleSaved(Result<PathBuf, String>),
+    /// Wireless networks found for the network setup screen
+    WifiNetworks(Result<Vec<WifiNetwork>, String>),
+    /// Outcome of joining a wireless network
+    WifiConnected(Result<String, String>),
+    /// Flake inputs an update moved, for the changelog screen
+    Changelog {
+        inputs: Vec<FlakeInputChange>,
+        /// nvd's (package, old version, new version)
+        package_changes: Vec<(String, String, String)>,
+    },
+    /// The finished update's summary, for `--report` and the export action
+    UpdateReport(Box<UpdateReport>),
     /// Updates available notification (sent after startup checks complete)
     UpdatesAvailable {
         nixos_config: bool,
         app_profiles: bool,
         /// Pending commits for nixos-config (hash, message)
         commits: Vec<(String, String)>,
+        /// Flake inputs behind upstream (e.g. "nixpkgs (42 commits behind)")
+        flake_inputs: Vec<String>,
     },
+    /// A periodic background task finished (see `app::scheduler`)
+    TaskFinished(TaskResult),
 }
@@ -0,0 +1,71 @@
+//! nix.conf settings screen (load and save nix-conf.json)
+//!
+//! Changes take effect on each host at its next rebuild (modules/nix-conf.nix);
+//! /etc/nix/nix.conf is only read, to show what this machine runs with now.
+
+use anyhow::Result;
+
+use super::executor::run_capture;
+use super::{CommandMessage, CommandSender};
+use crate::constants::{nixos_config_dir, HOSTS_SUBDIR};
+use crate::system::network::get_hostname;
+use crate::system::nix_conf::{
+    load_nix_conf_config, read_system_nix_conf, save_nix_conf_config, NixConfConfig, NIX_CONF_FILE,
+};
+
+/// Save the edited settings (if any) in the background, then send them back
+/// reloaded along with this machine's nix.conf
+pub async fn start_nix_conf_op(tx: CommandSender, save: Option<NixConfConfig>) -> Result<()> {
+    tokio::spawn(async move {
+        let message = match save {
+            Some(config) => Some(save_config(&config).await.map_err(|e| {
+                tracing::error!("Saving nix.conf settings failed: {}", e);
+                e.to_string()
+            })),
+            None => None,
+        };
+        let repo = nixos_config_dir();
+        let config = load_nix_conf_config(&repo).map_err(|e| format!("{:#}", e));
+
+        // Hosts in the repo, plus any that only have a nix-conf.json entry left
+        let mut hosts: Vec<String> = std::fs::read_dir(repo.join(HOSTS_SUBDIR))
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                    .map(|e| e.file_name().to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Ok(config) = &config {
+            hosts.extend(config.hosts.keys().cloned());
+        }
+        hosts.sort();
+        hosts.dedup();
+
+        let this_host = get_hostname().ok().filter(|h| !h.is_empty());
+        let live = read_system_nix_conf().map_err(|e| format!("{:#}", e));
+
+        let _ = tx
+            .send(CommandMessage::NixConf {
+                config,
+                hosts,
+                this_host,
+                live,
+                message,
+            })
+            .await;
+    });
+    Ok(())
+}
+
+async fn save_config(config: &NixConfConfig) -> Result<String> {
+    let repo = nixos_config_dir();
+    save_nix_conf_config(&repo, config)?;
+    // Flakes only see tracked files; intent-to-add leaves the index otherwise alone
+    let repo_str = repo.to_string_lossy();
+    if let Err(e) = run_capture("git", &["-C", &repo_str, "add", "-N", NIX_CONF_FILE]).await {
+        tracing::warn!("Could not add {} to git: {}", NIX_CONF_FILE, e);
+    }
+    Ok(format!("Saved {} - commit and rebuild to apply", NIX_CONF_FILE))
+}
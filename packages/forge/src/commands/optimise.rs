@@ -0,0 +1,73 @@
+//! Store deduplication (`nix store optimise`) with live progress
+
+use anyhow::{Context, Result};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+
+use super::runner::{spawn_with_error_handling, CommandRunner};
+use super::{CommandMessage, CommandSender};
+use crate::system::optimise::OptimiseProgress;
+
+/// How often progress is sent to the UI; Nix reports once per store path
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Start `nix store optimise`
+pub async fn start_optimise(tx: CommandSender) -> Result<()> {
+    spawn_with_error_handling(tx, "Store optimise", "optimise", |tx| async move {
+        run_optimise(&tx).await
+    })
+}
+
+async fn run_optimise(tx: &CommandSender) -> Result<()> {
+    let runner = CommandRunner::new(tx);
+    runner.header("Optimising Nix store").await;
+    let (success, progress) = optimise_store(&runner).await?;
+
+    runner.out("").await;
+    if success {
+        runner.out(&progress.summary()).await;
+    } else {
+        runner.err("nix store optimise failed - see output above").await;
+    }
+    runner.footer().await;
+    runner.done(success).await
+}
+
+/// Run `nix store optimise`, sending its progress; whether it succeeded, and the totals
+pub async fn optimise_store(runner: &CommandRunner<'_>) -> Result<(bool, OptimiseProgress)> {
+    let tx = runner.tx();
+    runner.out("Hard-linking identical files in /nix/store...").await;
+
+    let args = ["--log-format", "internal-json", "store", "optimise"];
+    tracing::info!("Running command: nix {:?}", args);
+    let mut child = Command::new("nix")
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to spawn command: nix")?;
+    let stderr = child
+        .stderr
+        .take()
+        .context("Failed to capture stderr for command: nix")?;
+
+    let mut progress = OptimiseProgress::default();
+    let mut last_sent = Instant::now();
+    let mut reader = BufReader::new(stderr).lines();
+    while let Ok(Some(line)) = reader.next_line().await {
+        if let Some(message) = progress.update(&line) {
+            runner.out(&message).await;
+        }
+        if last_sent.elapsed() >= PROGRESS_INTERVAL {
+            tx.send(CommandMessage::OptimiseProgress(progress.clone()))
+                .await?;
+            last_sent = Instant::now();
+        }
+    }
+    let status = child.wait().await.context("Failed to wait for command: nix")?;
+    tx.send(CommandMessage::OptimiseProgress(progress.clone()))
+        .await?;
+    Ok((status.success(), progress))
+}
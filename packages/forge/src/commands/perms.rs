@@ -0,0 +1,119 @@
+//! `forge fix-perms`: preview and apply the repairs from `system::perms`
+//!
+//! Runs as the user; only the chowns go through sudo.
+
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::constants::{self, FORGE_CONFIG_DIR};
+use crate::system::config::{expand_tilde, load_browser_config};
+use crate::system::perms::{plan, ssh_private_files, AuditTargets, Fix, Owner};
+
+/// The trees and key files of the user's home that forge and its scripts write
+fn audit_targets(home: &Path) -> AuditTargets {
+    let mut targets = AuditTargets::default();
+
+    // /etc/nixos is a symlink into the checkout; root owning /etc is fine
+    let config_dir = constants::nixos_config_dir();
+    if let Ok(config_dir) = config_dir.canonicalize() {
+        if config_dir.starts_with(home) {
+            targets.trees.push(config_dir);
+        }
+    }
+    targets.trees.push(constants::forge_data_dir());
+    targets.trees.push(home.join(FORGE_CONFIG_DIR));
+    targets.trees.push(constants::app_backup_data_dir());
+    if let Some(app_backup_config) = constants::app_backup_config_path().parent() {
+        targets.trees.push(app_backup_config.to_path_buf());
+    }
+
+    targets.private = ssh_private_files(&home.join(".ssh"));
+    let age_key = load_browser_config(&constants::app_backup_config_path())
+        .ok()
+        .and_then(|config| config.age_key_path);
+    if let Some(age_key) = age_key {
+        targets.private.push(PathBuf::from(expand_tilde(&age_key)));
+    }
+    targets
+}
+
+/// Whether forge runs as root (`/proc/self` belongs to the effective user)
+fn running_as_root() -> bool {
+    std::fs::metadata("/proc/self").map(|m| m.uid() == 0).unwrap_or(false)
+}
+
+/// Show what is wrong, then fix it after confirmation (or right away with `yes`)
+pub async fn fix_perms(dry_run: bool, yes: bool) -> Result<()> {
+    let home = dirs::home_dir().context("Could not determine the home directory")?;
+    let owner = Owner::of(&home).with_context(|| format!("Failed to read {}", home.display()))?;
+    if owner.uid == 0 {
+        bail!("{} belongs to root; run forge fix-perms as your user, it asks sudo for the chowns", home.display());
+    }
+
+    let fixes = plan(&audit_targets(&home), owner);
+    if fixes.is_empty() {
+        println!("Ownership and permissions are fine");
+        return Ok(());
+    }
+
+    let sudo = !running_as_root();
+    println!("Planned changes:");
+    for fix in &fixes {
+        let prefix = if sudo && fix.needs_root() { "sudo " } else { "" };
+        println!("  {}{}", prefix, fix.command().join(" "));
+        println!("      {}", fix.reason());
+    }
+    if dry_run {
+        return Ok(());
+    }
+    if !yes && !confirm("Apply these changes?")? {
+        println!("Nothing changed");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for fix in &fixes {
+        if let Err(e) = apply(fix, sudo && fix.needs_root()).await {
+            eprintln!("  {:#}", e);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        bail!("{} of {} changes failed", failed, fixes.len());
+    }
+    println!("Applied {} change{}", fixes.len(), if fixes.len() == 1 { "" } else { "s" });
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal; anything but yes is a no
+pub fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+async fn apply(fix: &Fix, sudo: bool) -> Result<()> {
+    let command = fix.command();
+    let mut cmd = if sudo {
+        let mut cmd = Command::new("sudo");
+        cmd.args(&command);
+        cmd
+    } else {
+        let mut cmd = Command::new(&command[0]);
+        cmd.args(&command[1..]);
+        cmd
+    };
+    let status = cmd
+        .status()
+        .await
+        .with_context(|| format!("Failed to run {}", command[0]))?;
+    if !status.success() {
+        bail!("{} failed", command.join(" "));
+    }
+    Ok(())
+}
@@ -0,0 +1,123 @@
+//! Run a repo-defined pipeline (`forge run <name>`)
+
+use anyhow::Result;
+
+use super::errors::{ErrorContext, ParsedError};
+use super::executor::{get_output, run_capture, run_command_with_timeout};
+use super::runner::{spawn_with_error_handling, CommandRunner};
+use super::{CommandMessage, CommandSender};
+use crate::constants::nixos_config_dir;
+use crate::system::pipeline::{Pipeline, PipelineStep};
+
+/// Start a pipeline
+pub async fn start_pipeline(tx: CommandSender, pipeline: Pipeline) -> Result<()> {
+    spawn_with_error_handling(tx, "Pipeline", "pipeline", move |tx| async move {
+        run_pipeline(&tx, pipeline).await
+    })
+}
+
+async fn run_pipeline(tx: &CommandSender, pipeline: Pipeline) -> Result<()> {
+    let runner = CommandRunner::new(tx);
+    let repo = nixos_config_dir().to_string_lossy().to_string();
+    let hostname = get_output("hostname", &[]).await.unwrap_or_default();
+
+    runner
+        .header(pipeline.description.as_deref().unwrap_or(&pipeline.name))
+        .await;
+
+    let mut success = true;
+    for (i, step) in pipeline.steps.iter().enumerate() {
+        if let Some(reason) = skip_reason(step, &hostname, &repo).await {
+            runner.out(&format!("  - {}: skipped ({})", step.name, reason)).await;
+            tx.send(CommandMessage::StepSkipped {
+                step: step.name.clone(),
+            })
+            .await?;
+            continue;
+        }
+
+        tx.send(CommandMessage::StepStarted {
+            step: step.name.clone(),
+        })
+        .await?;
+        runner.out("").await;
+        runner.out(&format!("▶ {}", step.name)).await;
+        runner.out(&format!("  $ {}", step.run)).await;
+
+        let ok = run_command_with_timeout(tx, "env", &in_repo(&repo, &step.run), step.timeout)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("Pipeline step '{}' failed to run: {}", step.name, e);
+                false
+            });
+
+        if ok {
+            runner.step_complete(&step.name).await?;
+            continue;
+        }
+
+        success = false;
+        tx.send(CommandMessage::StepFailed {
+            step: step.name.clone(),
+            error: ParsedError::from_stderr(
+                &format!("'{}' exited with an error - see output above", step.run),
+                ErrorContext {
+                    operation: step.name.clone(),
+                },
+            ),
+        })
+        .await?;
+        if !step.continue_on_error {
+            // Mark the rest skipped so the list shows where it stopped
+            for rest in &pipeline.steps[i + 1..] {
+                tx.send(CommandMessage::StepSkipped {
+                    step: rest.name.clone(),
+                })
+                .await?;
+            }
+            break;
+        }
+    }
+
+    runner.out("").await;
+    runner
+        .out(if success {
+            "  ✓ Pipeline finished"
+        } else {
+            "  ✗ Pipeline failed"
+        })
+        .await;
+    runner.footer().await;
+    runner.done(success).await?;
+    Ok(())
+}
+
+/// Why a step won't run here, if it won't
+async fn skip_reason(step: &PipelineStep, hostname: &str, repo: &str) -> Option<String> {
+    if !step.runs_on(hostname) {
+        return Some(format!("only on {}", step.hosts.join(", ")));
+    }
+    let condition = step.only_if.as_deref()?;
+    match run_capture("env", &in_repo(repo, condition)).await {
+        Ok((true, _, _)) => None,
+        _ => Some(format!("condition `{}` not met", condition)),
+    }
+}
+
+/// `env` arguments that run a shell command from the repo root
+fn in_repo<'a>(repo: &'a str, command: &'a str) -> [&'a str; 5] {
+    ["-C", repo, "sh", "-c", command]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_repo() {
+        assert_eq!(
+            in_repo("/etc/nixos", "echo hi"),
+            ["-C", "/etc/nixos", "sh", "-c", "echo hi"]
+        );
+    }
+}
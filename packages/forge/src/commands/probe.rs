@@ -0,0 +1,85 @@
+//! Background system probes (host discovery, disk detection, preflight checks,
+//! the config repo's git status)
+//!
+//! These run off the UI thread so screens that need them can show a loading
+//! indicator instead of blocking startup.
+
+use anyhow::Result;
+use std::time::Instant;
+
+use super::{CommandMessage, CommandSender};
+use crate::app::scheduler::TaskResult;
+use crate::constants::nixos_config_dir;
+use crate::system::config::{discover_hosts, save_host_cache};
+use crate::system::disk::get_available_disks;
+use crate::system::preflight::run_preflight;
+use crate::system::repo_status::RepoStatus;
+
+/// Start host discovery and refresh the host cache
+pub async fn start_host_discovery(tx: CommandSender) -> Result<()> {
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let hosts = match tokio::task::spawn_blocking(discover_hosts).await {
+            Ok(hosts) => hosts,
+            Err(e) => {
+                tracing::error!("Host discovery panicked: {}", e);
+                Vec::new()
+            }
+        };
+        tracing::info!("Discovered {} hosts in {:?}", hosts.len(), started.elapsed());
+
+        if let Err(e) = save_host_cache(&hosts) {
+            tracing::warn!("Failed to cache host list: {}", e);
+        }
+
+        let _ = tx.send(CommandMessage::HostsDiscovered(hosts)).await;
+    });
+    Ok(())
+}
+
+/// Start probing available disks
+pub async fn start_disk_probe(tx: CommandSender) -> Result<()> {
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let disks = match tokio::task::spawn_blocking(get_available_disks).await {
+            Ok(Ok(disks)) => disks,
+            Ok(Err(e)) => {
+                tracing::error!("Disk detection failed: {}", e);
+                Vec::new()
+            }
+            Err(e) => {
+                tracing::error!("Disk detection panicked: {}", e);
+                Vec::new()
+            }
+        };
+        tracing::info!("Detected {} disks in {:?}", disks.len(), started.elapsed());
+
+        let _ = tx.send(CommandMessage::DisksDetected(disks)).await;
+    });
+    Ok(())
+}
+
+/// Start the Live ISO preflight checks
+pub async fn start_preflight(tx: CommandSender) -> Result<()> {
+    tokio::spawn(async move {
+        let checks = tokio::task::spawn_blocking(run_preflight)
+            .await
+            .unwrap_or_default();
+        let _ = tx.send(CommandMessage::PreflightComplete(checks)).await;
+    });
+    Ok(())
+}
+
+/// Read the config repo's git status for the status bar
+pub async fn start_repo_status(tx: CommandSender) -> Result<()> {
+    tokio::spawn(async move {
+        let status = tokio::task::spawn_blocking(|| RepoStatus::read(&nixos_config_dir()))
+            .await
+            .unwrap_or_else(|e| {
+                tracing::error!("Git status panicked: {}", e);
+                None
+            });
+        let _ = tx.send(CommandMessage::TaskFinished(TaskResult::GitStatus(status))).await;
+    });
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! Flake registry screen operations (add, pin, unpin, remove)
+//!
+//! Changes are written to registry.json in the config repo and take effect
+//! on each host at its next rebuild (modules/registry.nix).
+
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Value};
+
+use super::executor::run_capture;
+use super::{CommandMessage, CommandSender};
+use crate::constants::nixos_config_dir;
+use crate::system::registry::{
+    flake_ref_from_metadata, load_registry, save_registry, system_registry_names, RegistryEntry,
+    RegistryRow, REGISTRY_FILE,
+};
+
+/// A change requested from the registry screen
+#[derive(Debug, Clone)]
+pub enum RegistryOp {
+    Add { name: String, url: String },
+    /// Lock to the current revision (again, for an already pinned entry)
+    Pin(String),
+    Unpin(String),
+    Remove(String),
+}
+
+/// Apply an operation (if any) in the background, then send the refreshed list
+pub async fn start_registry_op(
+    tx: CommandSender,
+    op: Option<RegistryOp>,
+) -> Result<()> {
+    tokio::spawn(async move {
+        let mut message = None;
+        if let Some(op) = op {
+            message = Some(apply(op).await.map_err(|e| {
+                tracing::error!("Registry change failed: {}", e);
+                e.to_string()
+            }));
+        }
+        let rows = load_rows().await.map_err(|e| e.to_string());
+        let _ = tx.send(CommandMessage::Registry { rows, message }).await;
+    });
+    Ok(())
+}
+
+async fn apply(op: RegistryOp) -> Result<String> {
+    let repo = nixos_config_dir();
+    let mut registry = load_registry(&repo)?;
+
+    let message = match op {
+        RegistryOp::Add { name, url } => {
+            if registry.contains_key(&name) {
+                bail!("'{}' is already in the registry", name);
+            }
+            let to = resolve(&url, false).await?;
+            registry.insert(
+                name.clone(),
+                RegistryEntry {
+                    url,
+                    to,
+                    pinned: false,
+                },
+            );
+            format!("Added {}", name)
+        }
+        RegistryOp::Pin(name) => {
+            let entry = registry.get_mut(&name).context("No such registry entry")?;
+            entry.to = resolve(&entry.url, true).await?;
+            entry.pinned = true;
+            format!(
+                "Pinned {} to {}",
+                name,
+                entry.pinned_rev().unwrap_or_else(|| "its current revision".to_string())
+            )
+        }
+        RegistryOp::Unpin(name) => {
+            let entry = registry.get_mut(&name).context("No such registry entry")?;
+            entry.to = resolve(&entry.url, false).await?;
+            entry.pinned = false;
+            format!("Unpinned {}", name)
+        }
+        RegistryOp::Remove(name) => {
+            registry.remove(&name).context("No such registry entry")?;
+            format!("Removed {}", name)
+        }
+    };
+
+    save_registry(&repo, &registry)?;
+    // Flakes only see tracked files; intent-to-add leaves the index otherwise alone
+    let repo_str = repo.to_string_lossy();
+    if let Err(e) = run_capture("git", &["-C", &repo_str, "add", "-N", REGISTRY_FILE]).await {
+        tracing::warn!("Could not add {} to git: {}", REGISTRY_FILE, e);
+    }
+
+    Ok(format!("{} - commit and rebuild to apply", message))
+}
+
+/// Registry attrs for a flake reference (`locked` when pinning)
+async fn resolve(url: &str, pinned: bool) -> Result<Map<String, Value>> {
+    let mut args = vec!["flake", "metadata", "--json"];
+    if pinned {
+        // Pin to what upstream has now, not a cached lookup
+        args.push("--refresh");
+    }
+    args.push(url);
+
+    let (ok, stdout, stderr) = run_capture("nix", &args).await?;
+    if !ok {
+        bail!("Could not resolve {}: {}", url, stderr.trim());
+    }
+    flake_ref_from_metadata(&stdout, pinned)
+}
+
+async fn load_rows() -> Result<Vec<RegistryRow>> {
+    let registry = load_registry(&nixos_config_dir())?;
+    let applied = match run_capture("nix", &["registry", "list"]).await {
+        Ok((true, stdout, _)) => system_registry_names(&stdout),
+        _ => Default::default(),
+    };
+
+    Ok(registry
+        .into_iter()
+        .map(|(name, entry)| RegistryRow {
+            applied: applied.contains(&name),
+            pinned_rev: entry.pinned_rev(),
+            url: entry.url,
+            name,
+        })
+        .collect())
+}
@@ -0,0 +1,291 @@
+//! Host heartbeat reporting and fleet drift status
+//!
+//! Reports are committed to the `forge-state` branch with git plumbing and a
+//! throwaway index, so the working tree and current branch are never touched.
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::executor::get_output;
+use super::{CommandMessage, CommandSender};
+use crate::constants::{nixos_config_dir, FLAKE_NIX};
+use crate::system::channels::host_channels;
+use crate::system::fleet::FleetConfig;
+use crate::system::host_state::{Drift, HostDrift, HostState, STATE_BRANCH, STATE_DIR};
+
+/// Attempts before giving up when another host pushed at the same time
+const PUSH_ATTEMPTS: usize = 3;
+
+/// Remote-tracking ref the state branch is fetched into
+fn state_ref() -> String {
+    format!("refs/remotes/origin/{}", STATE_BRANCH)
+}
+
+/// Run git in the config repo, optionally with a separate index and stdin
+async fn git(
+    repo: &Path,
+    args: &[&str],
+    index: Option<&Path>,
+    stdin: Option<&str>,
+) -> Result<String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C")
+        .arg(repo)
+        .args(args)
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(index) = index {
+        cmd.env("GIT_INDEX_FILE", index);
+    }
+
+    let mut child = cmd
+        .spawn()
+        .with_context(|| format!("Failed to run git {}", args.join(" ")))?;
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        pipe.write_all(input.as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Fetch the state branch; false if it doesn't exist yet
+async fn fetch_state_branch(repo: &Path) -> bool {
+    let refspec = format!("+refs/heads/{}:{}", STATE_BRANCH, state_ref());
+    git(repo, &["fetch", "--quiet", "origin", &refspec], None, None)
+        .await
+        .is_ok()
+}
+
+/// Record this host on the state branch (`forge report-state`)
+pub async fn report_state() -> Result<()> {
+    let repo = nixos_config_dir();
+    let hostname = get_output("hostname", &[])
+        .await
+        .ok()
+        .filter(|h| !h.is_empty())
+        .context("Could not determine hostname")?;
+
+    let state = HostState::collect(&hostname);
+    let json = serde_json::to_string_pretty(&state)? + "\n";
+    let message = format!(
+        "state: {} generation {}",
+        state.hostname,
+        state.generation.map_or("?".to_string(), |g| g.to_string())
+    );
+
+    let mut last_error = None;
+    for attempt in 1..=PUSH_ATTEMPTS {
+        match push_state(&repo, &state.file_name(), &json, &message).await {
+            Ok(()) => {
+                println!(
+                    "Reported {} (generation {}, revision {})",
+                    state.hostname,
+                    state.generation.map_or("?".to_string(), |g| g.to_string()),
+                    state.revision.as_deref().unwrap_or("unknown")
+                );
+                return Ok(());
+            }
+            Err(e) => {
+                // Most likely another host pushed in between; refetch and retry
+                tracing::warn!("State push attempt {} failed: {}", attempt, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("State push failed")))
+}
+
+/// Mark a host as decommissioned on the state branch (`forge host archive`)
+///
+/// Its last report is kept, so the branch still shows what it ran last.
+pub async fn record_decommission(hostname: &str) -> Result<()> {
+    let repo = nixos_config_dir();
+    let message = format!("state: {} decommissioned", hostname);
+
+    let mut last_error = None;
+    for attempt in 1..=PUSH_ATTEMPTS {
+        let mut state = last_report(&repo, hostname).await.unwrap_or_else(|| HostState {
+            hostname: hostname.to_string(),
+            generation: None,
+            revision: None,
+            nixos_version: None,
+            reported_at: Utc::now(),
+            decommissioned_at: None,
+        });
+        state.decommissioned_at = Some(Utc::now());
+        let json = serde_json::to_string_pretty(&state)? + "\n";
+        match push_state(&repo, &state.file_name(), &json, &message).await {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                tracing::warn!("State push attempt {} failed: {}", attempt, e);
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("State push failed")))
+}
+
+/// The host's report on the state branch, if it ever sent one
+async fn last_report(repo: &Path, hostname: &str) -> Option<HostState> {
+    if !fetch_state_branch(repo).await {
+        return None;
+    }
+    let file = format!("{}:{}/{}.json", state_ref(), STATE_DIR, hostname);
+    let content = git(repo, &["show", &file], None, None).await.ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+async fn push_state(repo: &Path, file: &str, json: &str, message: &str) -> Result<()> {
+    let has_branch = fetch_state_branch(repo).await;
+    let parent = state_ref();
+
+    let index = TempIndex::new();
+    if has_branch {
+        git(repo, &["read-tree", &parent], Some(&index.0), None).await?;
+    }
+
+    let blob = git(repo, &["hash-object", "-w", "--stdin"], None, Some(json)).await?;
+    let cacheinfo = format!("100644,{},{}", blob, file);
+    git(
+        repo,
+        &["update-index", "--add", "--cacheinfo", &cacheinfo],
+        Some(&index.0),
+        None,
+    )
+    .await?;
+    let tree = git(repo, &["write-tree"], Some(&index.0), None).await?;
+
+    let mut args = vec!["commit-tree", tree.as_str(), "-m", message];
+    if has_branch {
+        args.extend(["-p", parent.as_str()]);
+    }
+    let commit = git(repo, &args, None, None).await?;
+
+    let refspec = format!("{}:refs/heads/{}", commit, STATE_BRANCH);
+    git(repo, &["push", "--quiet", "origin", &refspec], None, None).await?;
+    Ok(())
+}
+
+/// Index file removed again when dropped
+struct TempIndex(PathBuf);
+
+impl TempIndex {
+    fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("forge-state-index-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        Self(path)
+    }
+}
+
+impl Drop for TempIndex {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Load every host report and compare it with main (`forge fleet status`)
+pub async fn start_fleet_status(tx: CommandSender) -> Result<()> {
+    tokio::spawn(async move {
+        let result = load_fleet_status(&nixos_config_dir())
+            .await
+            .map_err(|e| {
+                tracing::error!("Fleet status failed: {}", e);
+                e.to_string()
+            });
+        let _ = tx.send(CommandMessage::FleetStatus(result)).await;
+    });
+    Ok(())
+}
+
+async fn load_fleet_status(repo: &Path) -> Result<Vec<HostDrift>> {
+    if !fetch_state_branch(repo).await {
+        bail!(
+            "No host reports yet - the {} branch is created by the first `forge report-state`",
+            STATE_BRANCH
+        );
+    }
+    let _ = git(repo, &["fetch", "--quiet", "origin"], None, None).await;
+
+    // Same fallback as the update pull: main, then master
+    let main = match git(repo, &["rev-parse", "--verify", "origin/main"], None, None).await {
+        Ok(rev) => rev,
+        Err(_) => git(repo, &["rev-parse", "--verify", "origin/master"], None, None).await?,
+    };
+
+    let listing = git(
+        repo,
+        &["ls-tree", "--name-only", &state_ref(), &format!("{}/", STATE_DIR)],
+        None,
+        None,
+    )
+    .await?;
+
+    // Channels come from the checkout, like the hosts forge builds
+    let channels = std::fs::read_to_string(repo.join(FLAKE_NIX))
+        .map(|flake| host_channels(&flake))
+        .unwrap_or_default();
+    let fleet = FleetConfig::load(repo).ok();
+
+    let mut hosts = Vec::new();
+    for file in listing.lines().filter(|f| f.ends_with(".json")) {
+        let content = git(repo, &["show", &format!("{}:{}", state_ref(), file)], None, None).await?;
+        let state: HostState = match serde_json::from_str(&content) {
+            Ok(state) => state,
+            Err(e) => {
+                tracing::warn!("Skipping unreadable report {}: {}", file, e);
+                continue;
+            }
+        };
+        if state.decommissioned_at.is_some() {
+            continue;
+        }
+        let drift = drift_from_main(repo, &state, &main).await;
+        let channel = channels
+            .iter()
+            .find(|h| h.host == state.hostname)
+            .map(|h| h.channel);
+        let window = fleet.as_ref().and_then(|f| f.window_for(&state.hostname));
+        hosts.push(HostDrift {
+            state,
+            drift,
+            channel,
+            window,
+        });
+    }
+    hosts.sort_by(|a, b| a.state.hostname.cmp(&b.state.hostname));
+    Ok(hosts)
+}
+
+async fn drift_from_main(repo: &Path, state: &HostState, main: &str) -> Drift {
+    let Some(commit) = state.commit() else {
+        return Drift::Unknown;
+    };
+    // Fails both for commits that aren't ancestors and for ones we don't have
+    let on_main = git(repo, &["merge-base", "--is-ancestor", commit, main], None, None)
+        .await
+        .is_ok();
+    if !on_main {
+        return Drift::Diverged;
+    }
+    let range = format!("{}..{}", commit, main);
+    match git(repo, &["rev-list", "--count", &range], None, None).await {
+        Ok(count) => match count.parse::<usize>() {
+            Ok(0) => Drift::UpToDate,
+            Ok(n) => Drift::Behind(n),
+            Err(_) => Drift::Unknown,
+        },
+        Err(_) => Drift::Unknown,
+    }
+}
@@ -0,0 +1,79 @@
+//! Confirming forge's edits to flake.nix before they are kept
+//!
+//! The command sends the diff to the UI and waits for an answer; either way
+//! the diff and the answer go into the run manifest.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use tokio::sync::oneshot;
+
+use super::runner::CommandRunner;
+use super::CommandMessage;
+use crate::system::diff::unified_diff;
+use crate::system::manifest::record_edit;
+
+/// An edit waiting for the user's approval
+#[derive(Clone)]
+pub struct EditReview {
+    /// Path relative to the repo
+    pub file: String,
+    /// What the edit is for, e.g. "add host 'kraken'"
+    pub reason: String,
+    pub diff: String,
+    reply: Arc<Mutex<Option<oneshot::Sender<bool>>>>,
+}
+
+impl EditReview {
+    /// Approve or reject the edit; only the first answer counts
+    pub fn answer(&self, approved: bool) {
+        let reply = self.reply.lock().ok().and_then(|mut reply| reply.take());
+        if let Some(reply) = reply {
+            let _ = reply.send(approved);
+        }
+    }
+}
+
+impl std::fmt::Debug for EditReview {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EditReview")
+            .field("file", &self.file)
+            .field("reason", &self.reason)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Ask the user to approve the change from `old` to `new`; an unchanged file
+/// needs no approval, and a review dropped unanswered counts as rejected
+pub async fn review_edit(
+    runner: &CommandRunner<'_>,
+    file: &str,
+    reason: &str,
+    old: &str,
+    new: &str,
+) -> Result<bool> {
+    let diff = unified_diff(file, old, new);
+    if diff.is_empty() {
+        return Ok(true);
+    }
+
+    let (reply, answer) = oneshot::channel();
+    runner
+        .tx()
+        .send(CommandMessage::ReviewEdit(EditReview {
+            file: file.to_string(),
+            reason: reason.to_string(),
+            diff: diff.clone(),
+            reply: Arc::new(Mutex::new(Some(reply))),
+        }))
+        .await?;
+    let approved = answer.await.unwrap_or(false);
+
+    if let Err(e) = record_edit(file, reason, approved, &diff) {
+        runner.warn(&format!("Failed to write the run manifest: {}", e)).await;
+    }
+    if !approved {
+        runner.err(&format!("  ✗ {} change rejected", file)).await;
+    }
+    Ok(approved)
+}
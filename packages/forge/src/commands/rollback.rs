@@ -0,0 +1,110 @@
+//! Switching the system back to an earlier generation (`forge rollback`)
+
+use anyhow::Result;
+
+use super::runner::{spawn_with_error_handling, CommandRunner};
+use super::{CommandMessage, CommandSender};
+use crate::system::generations::{list_generations, SYSTEM_PROFILE};
+
+/// Step of a rollback to the previous generation (`nixos-rebuild switch --rollback`)
+pub const ROLLBACK_STEP: &str = "Rolling back to the previous generation";
+/// Steps of a switch to a chosen generation
+pub const SWITCH_PROFILE_STEP: &str = "Switching the system profile";
+pub const ACTIVATE_STEP: &str = "Activating the configuration";
+
+/// Read the system profile's generations in the background
+pub async fn start_generation_list(tx: CommandSender) -> Result<()> {
+    tokio::spawn(async move {
+        let result = tokio::task::spawn_blocking(list_generations)
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|listed| {
+                listed.map_err(|e| format!("Failed to read {}: {}", SYSTEM_PROFILE, e))
+            });
+        let _ = tx.send(CommandMessage::Generations(result)).await;
+    });
+    Ok(())
+}
+
+/// Switch to `generation`, or to the one before the current with None
+pub async fn start_rollback(tx: CommandSender, generation: Option<u32>) -> Result<()> {
+    spawn_with_error_handling(tx, "Rollback", ROLLBACK_STEP, move |tx| async move {
+        match generation {
+            Some(number) => run_switch(&tx, number).await,
+            None => run_rollback(&tx).await,
+        }
+    })
+}
+
+async fn run_rollback(tx: &CommandSender) -> Result<()> {
+    let runner = CommandRunner::new(tx);
+    runner.header("Rolling back to the previous generation").await;
+    step_started(tx, ROLLBACK_STEP).await?;
+
+    let success = runner.run("sudo", &["nixos-rebuild", "switch", "--rollback"]).await?;
+    runner.out("").await;
+    if success {
+        runner.out("  ✓ Switched to the previous generation").await;
+        runner.step_complete(ROLLBACK_STEP).await?;
+    } else {
+        runner.err("  ✗ nixos-rebuild switch --rollback failed").await;
+        runner
+            .step_failed(ROLLBACK_STEP, "nixos-rebuild switch --rollback failed", "Rollback")
+            .await?;
+    }
+    runner.footer().await;
+    runner.done(success).await
+}
+
+async fn run_switch(tx: &CommandSender, number: u32) -> Result<()> {
+    let runner = CommandRunner::new(tx);
+    runner.header(&format!("Switching to generation {}", number)).await;
+
+    step_started(tx, SWITCH_PROFILE_STEP).await?;
+    let generation = number.to_string();
+    let switched = runner
+        .run(
+            "sudo",
+            &["nix-env", "--profile", SYSTEM_PROFILE, "--switch-generation", &generation],
+        )
+        .await?;
+    if !switched {
+        runner
+            .step_failed(
+                SWITCH_PROFILE_STEP,
+                &format!("nix-env could not switch to generation {}", number),
+                "Rollback",
+            )
+            .await?;
+        runner.footer().await;
+        return runner.done(false).await;
+    }
+    runner.step_complete(SWITCH_PROFILE_STEP).await?;
+
+    // Activates the profile's new target and points the boot menu at it
+    step_started(tx, ACTIVATE_STEP).await?;
+    let activate = format!("{}/bin/switch-to-configuration", SYSTEM_PROFILE);
+    let activated = runner.run("sudo", &[&activate, "switch"]).await?;
+    runner.out("").await;
+    if activated {
+        runner.out(&format!("  ✓ Running generation {}", number)).await;
+        runner.step_complete(ACTIVATE_STEP).await?;
+    } else {
+        runner
+            .err(&format!("  ✗ Generation {} is the default but failed to activate", number))
+            .await;
+        runner
+            .step_failed(ACTIVATE_STEP, "switch-to-configuration switch failed", "Rollback")
+            .await?;
+    }
+    runner.footer().await;
+    runner.done(activated).await
+}
+
+async fn step_started(tx: &CommandSender, step: &str) -> Result<()> {
+    tx.send(CommandMessage::StepStarted {
+        step: step.to_string(),
+    })
+    .await?;
+    Ok(())
+}
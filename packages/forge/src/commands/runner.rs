@@ -6,36 +6,40 @@
 //! - Handle step failures consistently
 
 use anyhow::Result;
-use tokio::sync::mpsc;
 
 use super::errors::{ErrorContext, ParsedError};
 use super::executor::run_command;
-use super::CommandMessage;
+use super::{CommandMessage, CommandSender};
 
 /// A helper for running commands with consistent formatting and error handling
 pub struct CommandRunner<'a> {
-    tx: &'a mpsc::Sender<CommandMessage>,
+    tx: &'a CommandSender,
 }
 
 impl<'a> CommandRunner<'a> {
     /// Create a new command runner
-    pub fn new(tx: &'a mpsc::Sender<CommandMessage>) -> Self {
+    pub fn new(tx: &'a CommandSender) -> Self {
         Self { tx }
     }
 
     /// Get a reference to the underlying sender
-    pub fn tx(&self) -> &mpsc::Sender<CommandMessage> {
+    pub fn tx(&self) -> &CommandSender {
         self.tx
     }
 
     /// Send a stdout message
     pub async fn out(&self, msg: &str) {
-        let _ = self.tx.send(CommandMessage::Stdout(msg.to_string())).await;
+        let _ = self.tx.stdout(msg).await;
     }
 
     /// Send a stderr message
     pub async fn err(&self, msg: &str) {
-        let _ = self.tx.send(CommandMessage::Stderr(msg.to_string())).await;
+        let _ = self.tx.stderr(msg).await;
+    }
+
+    /// Send a warning: shown in the output and collected, never fails the step
+    pub async fn warn(&self, msg: &str) {
+        let _ = self.tx.send(CommandMessage::Warning(msg.to_string())).await;
     }
 
     /// Print a header with title
@@ -151,13 +155,13 @@ macro_rules! spawn_command {
 
 /// Helper function to spawn a command task with error handling
 pub fn spawn_with_error_handling<F, Fut>(
-    tx: mpsc::Sender<CommandMessage>,
+    tx: CommandSender,
     operation: &'static str,
     step: &'static str,
     f: F,
 ) -> Result<()>
 where
-    F: FnOnce(mpsc::Sender<CommandMessage>) -> Fut + Send + 'static,
+    F: FnOnce(CommandSender) -> Fut + Send + 'static,
     Fut: std::future::Future<Output = Result<()>> + Send,
 {
     tokio::spawn(async move {
@@ -183,35 +187,48 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::commands::{command_channel, OutputLine};
 
     #[tokio::test]
     async fn test_command_runner_out() {
-        let (tx, mut rx) = mpsc::channel(10);
+        let (tx, mut rx) = command_channel();
         let runner = CommandRunner::new(&tx);
 
         runner.out("test message").await;
         drop(tx);
 
-        let msg = rx.recv().await.unwrap();
-        match msg {
-            CommandMessage::Stdout(s) => assert_eq!(s, "test message"),
-            _ => panic!("Expected Stdout message"),
-        }
+        let line = rx.output.recv().await.unwrap();
+        assert_eq!(line, OutputLine::Stdout("test message".to_string()));
     }
 
     #[tokio::test]
     async fn test_command_runner_header() {
-        let (tx, mut rx) = mpsc::channel(10);
+        let (tx, mut rx) = command_channel();
         let runner = CommandRunner::new(&tx);
 
         runner.header("Test Title").await;
         drop(tx);
 
         // Should receive: empty, separator, title, separator, empty
-        let mut messages = Vec::new();
-        while let Some(msg) = rx.recv().await {
-            messages.push(msg);
+        let mut lines = Vec::new();
+        while let Some(line) = rx.output.recv().await {
+            lines.push(line);
         }
-        assert_eq!(messages.len(), 5);
+        assert_eq!(lines.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_command_runner_warn_is_an_event() {
+        let (tx, mut rx) = command_channel();
+        let runner = CommandRunner::new(&tx);
+
+        runner.warn("disk is slow").await;
+        drop(tx);
+
+        assert!(rx.output.recv().await.is_none());
+        assert!(matches!(
+            rx.events.recv().await,
+            Some(CommandMessage::Warning(message)) if message == "disk is slow"
+        ));
     }
 }
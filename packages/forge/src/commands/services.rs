@@ -0,0 +1,142 @@
+//! Background services: `forge enable-service` and the services screen
+//!
+//! Both turn a service on or off for this host in services.json; its units
+//! appear or go away at the next rebuild (modules/forge-services.nix).
+//! systemctl shows what is installed and running right now.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::executor::{get_output, run_capture};
+use super::perms::confirm;
+use super::{CommandMessage, CommandSender};
+use crate::constants::nixos_config_dir;
+use crate::system::diff::unified_diff;
+use crate::system::manifest::record_edit;
+use crate::system::services::{
+    load_services_config, Service, ServiceRow, ServicesConfig, SERVICES_FILE,
+};
+
+/// Turn `service` on (or off) for `host` (default: this machine) after showing the change
+pub async fn enable_service(
+    service: Service,
+    disable: bool,
+    host: Option<String>,
+    yes: bool,
+) -> Result<()> {
+    let repo = nixos_config_dir();
+    let host = match host {
+        Some(host) => host,
+        None => current_host().await,
+    };
+    let mut config = load_services_config(&repo)?;
+    let before = config.to_json()?;
+    config.set(&host, service, !disable);
+    let after = config.to_json()?;
+
+    let verb = if disable { "Disable" } else { "Enable" };
+    let diff = unified_diff(SERVICES_FILE, &before, &after);
+    if diff.is_empty() {
+        println!("{} is already {}d on {}", service.name(), verb.to_lowercase(), host);
+    } else {
+        println!("{} {} on {} ({}):", verb, service.name(), host, service.description());
+        println!();
+        print!("{}", diff);
+        println!();
+        let reason = format!("{} {} on {}", verb.to_lowercase(), service.name(), host);
+        let approved = yes || confirm(&format!("{} {}?", verb, service.name()))?;
+        if let Err(e) = record_edit(SERVICES_FILE, &reason, approved, &diff) {
+            eprintln!("Failed to write the run manifest: {}", e);
+        }
+        if !approved {
+            println!("Nothing changed");
+            return Ok(());
+        }
+        write_config(&repo, &after).await?;
+        println!("Saved {} - commit and rebuild {} to apply", SERVICES_FILE, host);
+    }
+
+    if host == current_host().await {
+        let enabled = unit_state(service, "is-enabled").await;
+        let active = unit_state(service, "is-active").await;
+        println!("{} here: {}, {}", service.unit(), enabled, active);
+    }
+    Ok(())
+}
+
+/// Switch a service for this host (if asked) in the background, then send the rows reloaded
+pub async fn start_services_op(tx: CommandSender, change: Option<(Service, bool)>) -> Result<()> {
+    tokio::spawn(async move {
+        let repo = nixos_config_dir();
+        let host = current_host().await;
+        let message = match change {
+            Some((service, enabled)) => {
+                Some(save_change(&repo, &host, service, enabled).await.map_err(|e| {
+                    tracing::error!("Saving {} failed: {}", SERVICES_FILE, e);
+                    format!("{:#}", e)
+                }))
+            }
+            None => None,
+        };
+
+        let rows = match load_services_config(&repo) {
+            Ok(config) => Ok(service_rows(&config, &host).await),
+            Err(e) => Err(format!("{:#}", e)),
+        };
+        let _ = tx.send(CommandMessage::Services { host, rows, message }).await;
+    });
+    Ok(())
+}
+
+async fn save_change(repo: &Path, host: &str, service: Service, enabled: bool) -> Result<String> {
+    let mut config = load_services_config(repo)?;
+    config.set(host, service, enabled);
+    write_config(repo, &config.to_json()?).await?;
+    let verb = if enabled { "Enabled" } else { "Disabled" };
+    Ok(format!("{} {} - commit and rebuild to apply", verb, service.name()))
+}
+
+async fn write_config(repo: &Path, json: &str) -> Result<()> {
+    let path = repo.join(SERVICES_FILE);
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))?;
+    // Flakes only see tracked files; intent-to-add leaves the index otherwise alone
+    let repo_str = repo.to_string_lossy();
+    if let Err(e) = run_capture("git", &["-C", &repo_str, "add", "-N", SERVICES_FILE]).await {
+        tracing::warn!("Could not add {} to git: {}", SERVICES_FILE, e);
+    }
+    Ok(())
+}
+
+async fn service_rows(config: &ServicesConfig, host: &str) -> Vec<ServiceRow> {
+    let mut rows = Vec::new();
+    for service in Service::ALL {
+        rows.push(ServiceRow {
+            service,
+            configured: config.enabled(host, service),
+            enabled: unit_state(service, "is-enabled").await,
+            active: unit_state(service, "is-active").await,
+        });
+    }
+    rows
+}
+
+/// `systemctl is-enabled` / `is-active` answer for the service's timer
+async fn unit_state(service: Service, query: &str) -> String {
+    let mut args = vec![query, service.unit()];
+    if service.is_user_unit() {
+        args.insert(0, "--user");
+    }
+    // Both print the state and exit non-zero for anything but enabled/active
+    match run_capture("systemctl", &args).await {
+        Ok((_, stdout, _)) if !stdout.trim().is_empty() => stdout.trim().to_string(),
+        _ => "unknown".to_string(),
+    }
+}
+
+async fn current_host() -> String {
+    get_output("hostname", &[])
+        .await
+        .ok()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
@@ -0,0 +1,47 @@
+//! `forge status`: the running operation and pending updates, for status bars
+//!
+//! Asks the TUI over its status socket; with no forge running, pending
+//! updates come from forge-notify's last check.
+
+use anyhow::Result;
+use forge::notify::state::NotifyState;
+
+use crate::system::ipc::{query_status, ForgeStatus};
+
+pub async fn print_status(waybar: bool) -> Result<()> {
+    let status = match query_status().await {
+        Some(status) => status,
+        None => last_check(),
+    };
+    if waybar {
+        println!("{}", status.waybar_json());
+        return Ok(());
+    }
+
+    if let Some(operation) = &status.operation {
+        let step = status.step.as_deref().unwrap_or("starting");
+        println!("Forge is {}: {} ({}%)", operation, step, status.percent.unwrap_or(0));
+    }
+    if status.pending.is_empty() {
+        println!("No updates pending");
+    } else {
+        println!("{}:", status.pending_text());
+        for line in &status.pending {
+            println!("  - {}", line);
+        }
+    }
+    Ok(())
+}
+
+/// Idle status with what forge-notify found on its last run
+fn last_check() -> ForgeStatus {
+    let found = NotifyState::load().unwrap_or_default().found;
+    ForgeStatus {
+        pending: ForgeStatus::pending_lines(
+            found.config_commits,
+            found.app_updates,
+            &found.flake_inputs,
+        ),
+        ..Default::default()
+    }
+}
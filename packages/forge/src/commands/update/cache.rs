@@ -0,0 +1,192 @@
+//! Binary cache hit-rate diagnostics for the rebuild step
+//!
+//! Nix logs one line per store path it realises while `nixos-rebuild` runs:
+//!
+//! ```text
+//! these 3 derivations will be built:
+//! these 412 paths will be fetched (301.2 MiB download, 1.4 GiB unpacked):
+//! copying path '/nix/store/abc-firefox-133.0' from 'https://cache.nixos.org'...
+//! building '/nix/store/xyz-linux-6.18.2.drv'...
+//! ```
+//!
+//! Counting them shows when a misconfigured or unreachable cache turned an
+//! update into an hour of local compilation.
+
+use std::collections::BTreeMap;
+
+/// How many locally built derivations the summary lists by name
+const MAX_BUILT_SHOWN: usize = 5;
+
+/// Store paths substituted vs built during one rebuild
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Paths copied per substituter URL
+    pub substituted: BTreeMap<String, usize>,
+    /// Names of derivations built locally (store hash stripped)
+    pub built: Vec<String>,
+    /// "N derivations will be built" as announced up front
+    pub planned_builds: usize,
+    /// "N paths will be fetched" as announced up front
+    pub planned_fetches: usize,
+    /// Download size from the fetch announcement, e.g. "301.2 MiB"
+    pub download: Option<String>,
+}
+
+impl CacheStats {
+    /// Fold one line of rebuild output into the counts
+    pub fn observe(&mut self, line: &str) {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("copying path '") {
+            if let Some((_, from)) = rest.split_once("' from '") {
+                let url = from.trim_end_matches("...").trim_end_matches('\'');
+                *self.substituted.entry(url.to_string()).or_default() += 1;
+            }
+        } else if let Some(rest) = line.strip_prefix("building '") {
+            if let Some(drv) = rest.split('\'').next() {
+                self.built.push(store_name(drv).trim_end_matches(".drv").to_string());
+            }
+        } else if let Some(count) = announced_count(line, "will be built") {
+            self.planned_builds += count;
+        } else if let Some(count) = announced_count(line, "will be fetched") {
+            self.planned_fetches += count;
+            if let Some(size) = line
+                .split_once('(')
+                .and_then(|(_, rest)| rest.split_once(" download"))
+                .map(|(size, _)| size.to_string())
+            {
+                self.download = Some(size);
+            }
+        }
+    }
+
+    pub fn substituted_total(&self) -> usize {
+        self.substituted.values().sum()
+    }
+
+    /// Nothing was realised (configuration already built)
+    pub fn is_empty(&self) -> bool {
+        self.substituted.is_empty() && self.built.is_empty()
+    }
+
+    /// Share of realised paths that came from a cache, 0-100
+    pub fn hit_rate(&self) -> usize {
+        let total = self.substituted_total() + self.built.len();
+        if total == 0 {
+            return 100;
+        }
+        self.substituted_total() * 100 / total
+    }
+
+    /// Summary lines for the update report
+    pub fn summary_lines(&self) -> Vec<String> {
+        if self.is_empty() {
+            return vec!["    Nothing to fetch or build".to_string()];
+        }
+
+        let mut lines = vec![format!(
+            "    {} substituted, {} built locally ({}% from cache{})",
+            self.substituted_total(),
+            self.built.len(),
+            self.hit_rate(),
+            self.download
+                .as_deref()
+                .map(|d| format!(", {} downloaded", d))
+                .unwrap_or_default()
+        )];
+        for (url, count) in &self.substituted {
+            lines.push(format!("      {}: {}", url, count));
+        }
+        if !self.built.is_empty() {
+            let mut names: Vec<&str> =
+                self.built.iter().take(MAX_BUILT_SHOWN).map(String::as_str).collect();
+            if self.built.len() > MAX_BUILT_SHOWN {
+                names.push("...");
+            }
+            lines.push(format!("      built: {}", names.join(", ")));
+        }
+        if self.planned_builds > self.built.len() {
+            lines.push(format!(
+                "    {} of {} planned builds ran (rebuild stopped early)",
+                self.built.len(),
+                self.planned_builds
+            ));
+        }
+        if self.substituted.is_empty() && !self.built.is_empty() {
+            lines.push(
+                "    ⚠ No paths came from a cache - check nix.settings.substituters and network"
+                    .to_string(),
+            );
+        }
+        lines
+    }
+}
+
+/// Count from "these 12 derivations will be built:" / "this path will be fetched (...)"
+fn announced_count(line: &str, phrase: &str) -> Option<usize> {
+    if !line.contains(phrase) {
+        return None;
+    }
+    let mut words = line.split_whitespace();
+    match words.next()? {
+        "this" => Some(1),
+        "these" => words.next()?.parse().ok(),
+        _ => None,
+    }
+}
+
+/// "/nix/store/<hash>-name" -> "name"
+fn store_name(path: &str) -> &str {
+    let base = path.rsplit('/').next().unwrap_or(path);
+    match base.split_once('-') {
+        Some((hash, name)) if hash.len() == 32 => name,
+        _ => base,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observe_rebuild_output() {
+        let mut stats = CacheStats::default();
+        let lines = [
+            "these 2 derivations will be built:",
+            "  /nix/store/0lz8ij7kq4iqa7lmzw9ypz0qbk3r4rfl-linux-6.18.2.drv",
+            "these 3 paths will be fetched (301.2 MiB download, 1.4 GiB unpacked):",
+            "copying path '/nix/store/1c7lb5ihbzaxc6bvr5gq4jz3xz7xn4v6-firefox-133.0' from 'https://cache.nixos.org'...",
+            "copying path '/nix/store/2aq5vxb3k7jqi0qkgrw6m0dfjnjzyw5k-zlib-1.3' from 'https://cache.nixos.org'...",
+            "copying path '/nix/store/3dnm9ldrz8k5qpi0qz2c5h4ppmxj0k3x-hyprland-0.53' from 'https://hyprland.cachix.org'...",
+            "building '/nix/store/0lz8ij7kq4iqa7lmzw9ypz0qbk3r4rfl-linux-6.18.2.drv'...",
+            "building '/nix/store/4xk0s7y4ndy3ihq7mbw3krp0y4mfmz1b-nixos-system-G1a.drv'...",
+            "activating the configuration...",
+        ];
+        for line in lines {
+            stats.observe(line);
+        }
+        assert_eq!(stats.planned_builds, 2);
+        assert_eq!(stats.planned_fetches, 3);
+        assert_eq!(stats.download.as_deref(), Some("301.2 MiB"));
+        assert_eq!(stats.substituted["https://cache.nixos.org"], 2);
+        assert_eq!(stats.substituted["https://hyprland.cachix.org"], 1);
+        assert_eq!(stats.built, vec!["linux-6.18.2", "nixos-system-G1a"]);
+        assert_eq!(stats.hit_rate(), 60);
+        assert_eq!(
+            stats.summary_lines()[0],
+            "    3 substituted, 2 built locally (60% from cache, 301.2 MiB downloaded)"
+        );
+    }
+
+    #[test]
+    fn test_cache_miss_warning() {
+        let mut stats = CacheStats::default();
+        stats.observe("this derivation will be built:");
+        stats.observe("building '/nix/store/0lz8ij7kq4iqa7lmzw9ypz0qbk3r4rfl-linux-6.18.2.drv'...");
+        assert_eq!(stats.planned_builds, 1);
+        assert_eq!(stats.hit_rate(), 0);
+        assert!(stats.summary_lines().iter().any(|l| l.contains("No paths came from a cache")));
+
+        assert_eq!(CacheStats::default().hit_rate(), 100);
+        assert_eq!(store_name("/nix/store/short-name"), "short-name");
+    }
+}
@@ -0,0 +1,86 @@
+//! One retry with `--fallback` when a binary cache fails to serve a path
+//!
+//! Flaky caches (HTTP errors on a narinfo or NAR) are common enough to be
+//! worth one more attempt that builds whatever they can't serve. The preview
+//! build and the rebuild both get it.
+
+use std::sync::{Arc, Mutex};
+
+use super::out;
+use crate::commands::errors::{is_substitution_failure, ErrorContext, ParsedError};
+use crate::commands::CommandSender;
+
+/// The nix option the retry adds
+pub const FALLBACK_ARG: &str = "--fallback";
+
+/// First binary cache failure in one attempt's output
+#[derive(Debug, Clone, Default)]
+pub struct CacheFailure(Arc<Mutex<Option<String>>>);
+
+impl CacheFailure {
+    /// Wrap an output transform so cache failures are recorded before it
+    /// sees the line
+    pub fn watch<F>(&self, transform: F) -> impl Fn(&str) -> Option<String> + Send + Sync + 'static
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        let failure = self.clone();
+        move |line| {
+            if is_substitution_failure(line) {
+                if let Ok(mut first) = failure.0.lock() {
+                    first.get_or_insert_with(|| line.to_string());
+                }
+            }
+            transform(line)
+        }
+    }
+
+    fn get(&self) -> Option<String> {
+        self.0.lock().ok().and_then(|failure| failure.clone())
+    }
+}
+
+/// Whether a failed `operation` is worth running again with `--fallback`,
+/// announcing the retry in the output if so
+pub async fn retry_with_fallback(
+    tx: &CommandSender,
+    operation: &str,
+    success: bool,
+    failure: &CacheFailure,
+) -> bool {
+    let Some(failure) = failure.get().filter(|_| !success) else {
+        return false;
+    };
+    let error = ParsedError::from_stderr(
+        &failure,
+        ErrorContext {
+            operation: operation.to_string(),
+        },
+    );
+    out(tx, "").await;
+    out(tx, &format!("  ⟳ {}; retrying once with {}", error.summary, FALLBACK_ARG)).await;
+    if let Some(detail) = &error.detail {
+        out(tx, &format!("    {}", detail)).await;
+    }
+    out(tx, "").await;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_keeps_first_failure() {
+        let failure = CacheFailure::default();
+        let transform = failure.watch(|line| Some(line.to_string()));
+        assert_eq!(transform("building 'a.drv'...").as_deref(), Some("building 'a.drv'..."));
+        assert_eq!(failure.get(), None);
+
+        let first = "error: unable to download 'https://cache.nixos.org/abc.narinfo': HTTP \
+                     error 502";
+        transform(first);
+        transform("error: some substitutes failed; try '--fallback' to build from source");
+        assert_eq!(failure.get().as_deref(), Some(first));
+    }
+}
@@ -3,13 +3,22 @@
 use anyhow::Result;
 use serde::Deserialize;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::commands::executor::run_capture;
 
 /// Maximum number of commits to fetch per input (to avoid huge responses)
 const MAX_COMMITS_TO_FETCH: usize = 10;
 
+/// Most releases shown per input, newest first
+const MAX_RELEASES: usize = 5;
+
+/// Lines of each release's notes kept for the changelog screen
+const MAX_RELEASE_NOTE_LINES: usize = 40;
+
+/// Files that name an input's release series, e.g. nixpkgs' `.version` = "25.11"
+const VERSION_FILES: &[(&str, &str)] = &[("nixpkgs", ".version"), ("home-manager", "release.json")];
+
 /// Commit info from GitHub API
 #[derive(Debug, Clone)]
 pub struct CommitInfo {
@@ -28,17 +37,208 @@ pub struct FlakeInputChange {
     pub commits: Vec<CommitInfo>,
     pub total_commits: usize,
     pub compare_url: Option<String>,
+    /// Branch the input follows, e.g. "nixos-unstable"
+    pub branch: Option<String>,
+    /// Commit times (lastModified) of the old and new revisions
+    pub old_modified: Option<i64>,
+    pub new_modified: Option<i64>,
+    /// Release series at the old and new revisions (nixpkgs, home-manager)
+    pub old_version: Option<String>,
+    pub new_version: Option<String>,
+    /// GitHub releases published between the two revisions, newest first
+    pub releases: Vec<ReleaseNote>,
+}
+
+/// A GitHub release of a flake input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseNote {
+    pub tag: String,
+    pub name: String,
+    pub url: String,
+    /// First lines of the release notes
+    pub notes: Vec<String>,
+}
+
+impl FlakeInputChange {
+    /// "nixos-unstable advanced 6 days, 25.11 → 26.05 (new release)"; None
+    /// when neither the branch nor the version says anything
+    pub fn channel_line(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(branch) = &self.branch {
+            match (self.old_modified, self.new_modified) {
+                (Some(old), Some(new)) => {
+                    let days = (new - old).max(0) / 86_400;
+                    let span = match days {
+                        0 => "less than a day".to_string(),
+                        1 => "1 day".to_string(),
+                        n => format!("{} days", n),
+                    };
+                    parts.push(format!("{} advanced {}", branch, span));
+                }
+                _ => parts.push(branch.clone()),
+            }
+        }
+        match (&self.old_version, &self.new_version) {
+            (Some(old), Some(new)) if old != new => {
+                parts.push(format!("{} → {} (new release)", old, new));
+            }
+            (_, Some(new)) => parts.push(format!("release {}", new)),
+            _ => {}
+        }
+        (!parts.is_empty()).then(|| parts.join(", "))
+    }
+
+    /// The input's changelog as shown on the changelog screen
+    pub fn changelog_lines(&self, bumps: &[(String, String, String)]) -> Vec<String> {
+        let mut lines = vec![format!(
+            "{}/{}  {} → {}",
+            self.owner,
+            self.repo,
+            short_rev(&self.old_rev),
+            short_rev(&self.new_rev)
+        )];
+        if let Some(channel) = self.channel_line() {
+            lines.push(channel);
+        }
+        if let Some(url) = &self.compare_url {
+            lines.push(url.clone());
+        }
+
+        if !self.releases.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("Releases ({})", self.releases.len()));
+            for release in &self.releases {
+                lines.push(String::new());
+                if release.name.is_empty() || release.name == release.tag {
+                    lines.push(format!("  {}", release.tag));
+                } else {
+                    lines.push(format!("  {} - {}", release.tag, release.name));
+                }
+                lines.push(format!("  {}", release.url));
+                lines.extend(release.notes.iter().map(|l| format!("    {}", l)));
+            }
+        }
+
+        lines.push(String::new());
+        if self.commits.is_empty() {
+            lines.push("Commits: not available".to_string());
+        } else {
+            lines.push(format!("Commits ({}, newest first)", self.total_commits));
+            for commit in &self.commits {
+                lines.push(format!("  {} {}", commit.hash, commit.message));
+            }
+            if self.total_commits > self.commits.len() {
+                lines.push(format!("  ... and {} more", self.total_commits - self.commits.len()));
+            }
+        }
+
+        // nvd's version changes come from nixpkgs
+        if self.repo == "nixpkgs" {
+            let majors = major_bumps(bumps);
+            if !majors.is_empty() {
+                lines.push(String::new());
+                lines.push(format!("Major package bumps ({})", majors.len()));
+                for (pkg, old, new) in majors {
+                    lines.push(format!("  {} {} → {}", pkg, old, new));
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// Package changes whose major version moved, e.g. firefox 130.0 → 131.0
+pub fn major_bumps(changes: &[(String, String, String)]) -> Vec<&(String, String, String)> {
+    let major = |version: &str| {
+        let digits: String = version.chars().take_while(|c| c.is_ascii_digit()).collect();
+        digits.parse::<u64>().ok()
+    };
+    changes
+        .iter()
+        .filter(|(_, old, new)| match (major(old), major(new)) {
+            (Some(old), Some(new)) => old != new,
+            _ => false,
+        })
+        .collect()
+}
+
+fn short_rev(rev: &str) -> &str {
+    &rev[..7.min(rev.len())]
+}
+
+/// A flake input pointed at a local checkout for one update run
+///
+/// Passed to the rebuild as `--override-input <input> <path>`. The lock file
+/// is restored afterwards, so the override never ends up in the repo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputOverride {
+    pub input: String,
+    pub path: PathBuf,
+}
+
+impl InputOverride {
+    /// Parse `--override-input INPUT PATH` pairs, checking each path is a flake
+    pub fn from_args(values: &[String]) -> Result<Vec<InputOverride>> {
+        values
+            .chunks(2)
+            .map(|pair| {
+                let [input, path] = pair else {
+                    anyhow::bail!("--override-input needs an input name and a path");
+                };
+                let path = std::fs::canonicalize(path)
+                    .map_err(|e| anyhow::anyhow!("Cannot use {} for {}: {}", path, input, e))?;
+                if !path.join("flake.nix").exists() {
+                    anyhow::bail!("{} has no flake.nix", path.display());
+                }
+                Ok(InputOverride {
+                    input: input.clone(),
+                    path,
+                })
+            })
+            .collect()
+    }
+
+    /// `--override-input` arguments for every override
+    pub fn args(overrides: &[InputOverride]) -> Vec<String> {
+        overrides
+            .iter()
+            .flat_map(|o| {
+                [
+                    "--override-input".to_string(),
+                    o.input.clone(),
+                    o.path.display().to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+/// Subdirectory shown in the local checkout picker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckoutDir {
+    pub name: String,
+    /// Contains a flake.nix, so it can be used as an override
+    pub is_flake: bool,
 }
 
 /// Flake.lock JSON structure
 #[derive(Debug, Deserialize)]
 struct FlakeLock {
     nodes: HashMap<String, FlakeNode>,
+    #[serde(default = "default_root")]
+    root: String,
+}
+
+fn default_root() -> String {
+    "root".to_string()
 }
 
 #[derive(Debug, Deserialize)]
 struct FlakeNode {
     locked: Option<LockedInfo>,
+    original: Option<OriginalInfo>,
+    #[serde(default)]
+    inputs: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -48,24 +248,49 @@ struct LockedInfo {
     rev: Option<String>,
     #[serde(rename = "type")]
     source_type: Option<String>,
+    #[serde(rename = "lastModified")]
+    last_modified: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OriginalInfo {
+    #[serde(rename = "ref")]
+    git_ref: Option<String>,
+}
+
+/// GitHub API release
+#[derive(Debug, Deserialize)]
+struct GitHubRelease {
+    tag_name: String,
+    #[serde(default)]
+    name: Option<String>,
+    html_url: String,
+    #[serde(default)]
+    body: Option<String>,
+    published_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
 }
 
 /// GitHub API compare response
 #[derive(Debug, Deserialize)]
-struct GitHubCompareResponse {
-    total_commits: usize,
-    commits: Vec<GitHubCommit>,
+pub(crate) struct GitHubCompareResponse {
+    pub(crate) total_commits: usize,
+    pub(crate) base_commit: GitHubCommit,
+    pub(crate) commits: Vec<GitHubCommit>,
 }
 
 #[derive(Debug, Deserialize)]
-struct GitHubCommit {
-    sha: String,
-    commit: GitHubCommitInfo,
+pub(crate) struct GitHubCommit {
+    pub(crate) sha: String,
+    pub(crate) commit: GitHubCommitInfo,
 }
 
 #[derive(Debug, Deserialize)]
-struct GitHubCommitInfo {
-    message: String,
+pub(crate) struct GitHubCommitInfo {
+    pub(crate) message: String,
 }
 
 /// Get the SHA256 hash of flake.lock file
@@ -100,6 +325,79 @@ pub async fn save_flake_lock_backup(dir: &Path) -> Option<String> {
     }
 }
 
+/// Names of the config flake's own inputs, sorted, from flake.lock
+pub fn root_inputs(dir: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(dir.join("flake.lock"))?;
+    parse_root_inputs(&content)
+}
+
+fn parse_root_inputs(content: &str) -> Result<Vec<String>> {
+    let lock: FlakeLock = serde_json::from_str(content)?;
+    let mut inputs: Vec<String> = lock
+        .nodes
+        .get(&lock.root)
+        .map(|root| root.inputs.keys().cloned().collect())
+        .unwrap_or_default();
+    inputs.sort();
+    Ok(inputs)
+}
+
+/// GitHub owner and repo a root input is locked to, e.g. ("NixOS", "nixpkgs")
+pub fn github_source(dir: &Path, input: &str) -> Result<(String, String)> {
+    let content = std::fs::read_to_string(dir.join("flake.lock"))?;
+    parse_github_source(&content, input)
+}
+
+fn parse_github_source(content: &str, input: &str) -> Result<(String, String)> {
+    let lock: FlakeLock = serde_json::from_str(content)?;
+    // Inputs that follow another input are arrays; only direct ones name a node
+    let node = lock
+        .nodes
+        .get(&lock.root)
+        .and_then(|root| root.inputs.get(input))
+        .and_then(|node| node.as_str())
+        .ok_or_else(|| anyhow::anyhow!("flake.lock has no input named '{}'", input))?;
+    match lock.nodes.get(node).and_then(|n| n.locked.as_ref()) {
+        Some(LockedInfo {
+            owner: Some(owner),
+            repo: Some(repo),
+            source_type: Some(source_type),
+            ..
+        }) if source_type == "github" => Ok((owner.clone(), repo.clone())),
+        _ => anyhow::bail!("Input '{}' is not a GitHub input", input),
+    }
+}
+
+/// Non-hidden subdirectories of `dir`, sorted by name
+pub fn checkout_dirs(dir: &Path) -> Vec<CheckoutDir> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<CheckoutDir> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            (!name.starts_with('.')).then(|| CheckoutDir {
+                is_flake: entry.path().join("flake.nix").exists(),
+                name,
+            })
+        })
+        .collect();
+    dirs.sort_by(|a, b| a.name.cmp(&b.name));
+    dirs
+}
+
+/// Put flake.lock back to `snapshot` if it changed; returns whether it did
+pub fn restore_flake_lock(dir: &Path, snapshot: &[u8]) -> Result<bool> {
+    let lock_path = dir.join("flake.lock");
+    if std::fs::read(&lock_path).ok().as_deref() == Some(snapshot) {
+        return Ok(false);
+    }
+    std::fs::write(&lock_path, snapshot)?;
+    Ok(true)
+}
+
 /// Parse changes in flake.lock between old backup and current
 pub async fn parse_flake_changes(dir: &Path) -> Result<Vec<FlakeInputChange>> {
     let lock_path = dir.join("flake.lock");
@@ -160,8 +458,14 @@ pub async fn parse_flake_changes(dir: &Path) -> Result<Vec<FlakeInputChange>> {
                             total_commits: 0,
                             compare_url: Some(format!(
                                 "https://github.com/{}/{}/compare/{}...{}",
-                                owner, repo, &old_rev[..7.min(old_rev.len())], &new_rev[..7.min(new_rev.len())]
+                                owner, repo, short_rev(old_rev), short_rev(new_rev)
                             )),
+                            branch: new_node.original.as_ref().and_then(|o| o.git_ref.clone()),
+                            old_modified: old_locked.last_modified,
+                            new_modified: new_locked.last_modified,
+                            old_version: None,
+                            new_version: None,
+                            releases: Vec::new(),
                         });
                     }
                 }
@@ -169,7 +473,7 @@ pub async fn parse_flake_changes(dir: &Path) -> Result<Vec<FlakeInputChange>> {
         }
     }
 
-    // Fetch commit messages from GitHub API
+    // Fetch commit messages, release notes and release series from GitHub
     fetch_commits_for_changes(&mut changes).await;
 
     // Clean up backup file
@@ -179,7 +483,7 @@ pub async fn parse_flake_changes(dir: &Path) -> Result<Vec<FlakeInputChange>> {
 }
 
 /// Fetch commit messages from GitHub API for each changed input
-async fn fetch_commits_for_changes(changes: &mut Vec<FlakeInputChange>) {
+async fn fetch_commits_for_changes(changes: &mut [FlakeInputChange]) {
     let client = match reqwest::Client::builder()
         .user_agent("forge-nixos-tool")
         .timeout(std::time::Duration::from_secs(10))
@@ -207,7 +511,98 @@ async fn fetch_commits_for_changes(changes: &mut Vec<FlakeInputChange>) {
                 );
             }
         }
+        match fetch_github_releases(&client, change).await {
+            Ok(releases) => change.releases = releases,
+            Err(e) => {
+                tracing::debug!(
+                    "Failed to fetch releases for {}/{}: {}",
+                    change.owner,
+                    change.repo,
+                    e
+                );
+            }
+        }
+        if let Some((_, file)) = VERSION_FILES.iter().find(|(repo, _)| *repo == change.repo) {
+            change.old_version = fetch_version(&client, change, &change.old_rev, file).await;
+            change.new_version = fetch_version(&client, change, &change.new_rev, file).await;
+        }
+    }
+}
+
+/// Releases published between the old and the new revision's commit times
+async fn fetch_github_releases(
+    client: &reqwest::Client,
+    change: &FlakeInputChange,
+) -> Result<Vec<ReleaseNote>> {
+    let (Some(old), Some(new)) = (change.old_modified, change.new_modified) else {
+        return Ok(Vec::new());
+    };
+    let url = format!(
+        "https://api.github.com/repos/{}/{}/releases?per_page=30",
+        change.owner, change.repo
+    );
+    let response = client.get(&url).send().await?;
+    if !response.status().is_success() {
+        anyhow::bail!("GitHub API returned {}", response.status());
     }
+    let releases: Vec<GitHubRelease> = response.json().await?;
+    Ok(releases_between(releases, old, new))
+}
+
+/// Published, non-draft releases in `(old, new]`, newest first
+fn releases_between(mut releases: Vec<GitHubRelease>, old: i64, new: i64) -> Vec<ReleaseNote> {
+    releases.retain(|r| {
+        !r.draft
+            && !r.prerelease
+            && r.published_at
+                .is_some_and(|at| at.timestamp() > old && at.timestamp() <= new)
+    });
+    releases.sort_by_key(|r| std::cmp::Reverse(r.published_at));
+    releases
+        .into_iter()
+        .take(MAX_RELEASES)
+        .map(|r| ReleaseNote {
+            name: r.name.unwrap_or_default(),
+            notes: r
+                .body
+                .unwrap_or_default()
+                .lines()
+                .map(|l| l.trim_end().to_string())
+                .take(MAX_RELEASE_NOTE_LINES)
+                .collect(),
+            tag: r.tag_name,
+            url: r.html_url,
+        })
+        .collect()
+}
+
+/// Release series named by `file` at `rev`, e.g. "25.11"
+async fn fetch_version(
+    client: &reqwest::Client,
+    change: &FlakeInputChange,
+    rev: &str,
+    file: &str,
+) -> Option<String> {
+    let url = format!(
+        "https://raw.githubusercontent.com/{}/{}/{}/{}",
+        change.owner, change.repo, rev, file
+    );
+    let response = client.get(&url).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    parse_version_file(file, &response.text().await.ok()?)
+}
+
+/// `.version` holds the bare version, release.json a `release` field
+fn parse_version_file(file: &str, content: &str) -> Option<String> {
+    let version = if file.ends_with(".json") {
+        let json: serde_json::Value = serde_json::from_str(content).ok()?;
+        json.get("release")?.as_str()?.to_string()
+    } else {
+        content.trim().to_string()
+    };
+    (!version.is_empty()).then_some(version)
 }
 
 /// Fetch commits between two revisions from GitHub API
@@ -245,3 +640,132 @@ async fn fetch_github_commits(
 
     Ok((commits, compare.total_commits))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_root_inputs() {
+        let lock = r#"{
+            "nodes": {
+                "nixpkgs": { "locked": { "type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "abc" } },
+                "home-manager": { "inputs": { "nixpkgs": ["nixpkgs"] } },
+                "root": { "inputs": { "nixpkgs": "nixpkgs", "home-manager": "home-manager" } }
+            },
+            "root": "root",
+            "version": 7
+        }"#;
+        assert_eq!(parse_root_inputs(lock).unwrap(), vec!["home-manager", "nixpkgs"]);
+        assert!(parse_root_inputs("not json").is_err());
+
+        assert_eq!(
+            parse_github_source(lock, "nixpkgs").unwrap(),
+            ("NixOS".to_string(), "nixpkgs".to_string())
+        );
+        assert!(parse_github_source(lock, "home-manager").is_err());
+        assert!(parse_github_source(lock, "missing").is_err());
+    }
+
+    fn change() -> FlakeInputChange {
+        FlakeInputChange {
+            name: "nixpkgs".to_string(),
+            owner: "NixOS".to_string(),
+            repo: "nixpkgs".to_string(),
+            old_rev: "aaaaaaaaaa".to_string(),
+            new_rev: "bbbbbbbbbb".to_string(),
+            commits: vec![CommitInfo {
+                hash: "bbbbbbb".to_string(),
+                message: "firefox: 130.0 -> 131.0".to_string(),
+            }],
+            total_commits: 3,
+            compare_url: None,
+            branch: Some("nixos-unstable".to_string()),
+            old_modified: Some(1_000_000),
+            new_modified: Some(1_000_000 + 6 * 86_400 + 5),
+            old_version: Some("25.11".to_string()),
+            new_version: Some("26.05".to_string()),
+            releases: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_channel_line() {
+        let mut change = change();
+        assert_eq!(
+            change.channel_line().unwrap(),
+            "nixos-unstable advanced 6 days, 25.11 → 26.05 (new release)"
+        );
+        change.old_version = change.new_version.clone();
+        change.new_modified = change.old_modified;
+        assert_eq!(
+            change.channel_line().unwrap(),
+            "nixos-unstable advanced less than a day, release 26.05"
+        );
+        change.branch = None;
+        change.new_version = None;
+        assert_eq!(change.channel_line(), None);
+    }
+
+    #[test]
+    fn test_changelog_lines() {
+        let bumps = vec![
+            ("firefox".to_string(), "130.0".to_string(), "131.0".to_string()),
+            ("git".to_string(), "2.46.0".to_string(), "2.46.1".to_string()),
+        ];
+        let lines = change().changelog_lines(&bumps);
+        assert_eq!(lines[0], "NixOS/nixpkgs  aaaaaaa → bbbbbbb");
+        assert!(lines.contains(&"  ... and 2 more".to_string()));
+        assert_eq!(lines.last().unwrap(), "  firefox 130.0 → 131.0");
+        assert!(!lines.iter().any(|l| l.contains("git 2.46")));
+    }
+
+    #[test]
+    fn test_releases_between() {
+        let release = |tag: &str, at: i64, draft: bool| GitHubRelease {
+            tag_name: tag.to_string(),
+            name: Some(format!("Release {}", tag)),
+            html_url: format!("https://github.com/o/r/releases/tag/{}", tag),
+            body: Some("## Changes\r\n- fix".to_string()),
+            published_at: chrono::DateTime::from_timestamp(at, 0),
+            draft,
+            prerelease: false,
+        };
+        let releases = vec![
+            release("v1", 100, false),
+            release("v2", 200, false),
+            release("v3", 300, false),
+            release("v4", 250, true),
+        ];
+        let notes = releases_between(releases, 100, 300);
+        let tags: Vec<&str> = notes.iter().map(|r| r.tag.as_str()).collect();
+        assert_eq!(tags, ["v3", "v2"]);
+        assert_eq!(notes[0].notes, vec!["## Changes", "- fix"]);
+    }
+
+    #[test]
+    fn test_parse_version_file() {
+        assert_eq!(parse_version_file(".version", "25.11\n").as_deref(), Some("25.11"));
+        let json = r#"{ "release": "25.05", "isReleaseBranch": true }"#;
+        assert_eq!(parse_version_file("release.json", json).as_deref(), Some("25.05"));
+        assert_eq!(parse_version_file("release.json", "{}"), None);
+    }
+
+    #[test]
+    fn test_override_args() {
+        let overrides = [InputOverride {
+            input: "nixpkgs".to_string(),
+            path: PathBuf::from("/home/me/nixpkgs"),
+        }];
+        assert_eq!(
+            InputOverride::args(&overrides),
+            vec!["--override-input", "nixpkgs", "/home/me/nixpkgs"]
+        );
+        assert!(InputOverride::args(&[]).is_empty());
+
+        let missing = ["nixpkgs".to_string(), "/nonexistent/forge-checkout".to_string()];
+        assert!(InputOverride::from_args(&missing).is_err());
+        assert!(InputOverride::from_args(&["nixpkgs".to_string()]).is_err());
+        assert_eq!(InputOverride::from_args(&[]).unwrap(), vec![]);
+    }
+}
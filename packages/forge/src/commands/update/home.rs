@@ -0,0 +1,95 @@
+//! Standalone home-manager switch after the system rebuild
+//!
+//! Hosts that use home-manager as a NixOS module get the home configuration
+//! with the rebuild. A host whose user has a `homeConfigurations` entry in
+//! flake.nix (`"john@kraken"` or `john`) instead gets `home-manager switch`
+//! as its own step.
+
+use std::path::Path;
+
+use crate::system::flake_edit::{block_attrs, HOME_CONFIGURATIONS};
+
+use super::transform_nix_output;
+
+/// Extension home-manager moves files in its way to, as the NixOS module does
+const BACKUP_EXTENSION: &str = "backup";
+
+/// The homeConfigurations entry `home-manager switch` would pick for `user`
+/// on `host`: `user@host`, else `user`
+pub fn standalone_config(flake_nix: &str, user: &str, host: &str) -> Option<String> {
+    let lines: Vec<&str> = flake_nix.split_inclusive('\n').collect();
+    let names: Vec<String> = block_attrs(&lines, HOME_CONFIGURATIONS)
+        .into_iter()
+        .map(|(name, _, _)| name)
+        .collect();
+    [format!("{}@{}", user, host), user.to_string()]
+        .into_iter()
+        .find(|candidate| names.contains(candidate))
+}
+
+/// This user's standalone home configuration in the config repo, if any
+pub fn detect(flake_dir: &Path) -> Option<String> {
+    let flake = std::fs::read_to_string(flake_dir.join("flake.nix")).ok()?;
+    let user = std::env::var("USER").ok()?;
+    let host = std::fs::read_to_string("/proc/sys/kernel/hostname").ok()?;
+    standalone_config(&flake, &user, host.trim())
+}
+
+/// `home-manager switch` arguments for `config`
+pub fn switch_args(flake_path: &str, config: &str) -> Vec<String> {
+    vec![
+        "switch".to_string(),
+        "-b".to_string(),
+        BACKUP_EXTENSION.to_string(),
+        "--flake".to_string(),
+        format!("{}#{}", flake_path, config),
+    ]
+}
+
+/// home-manager's activation chatter dropped, files in the way explained,
+/// and the nix build output filtered as for the rebuild
+pub fn transform_home_manager_output(line: &str) -> Option<String> {
+    let trimmed = line.trim();
+    if trimmed.starts_with("Activating ")
+        || trimmed.starts_with("Starting Home Manager activation")
+        || trimmed.starts_with("No change so reusing latest profile generation")
+    {
+        return None;
+    }
+    if trimmed.starts_with("Existing file '") && trimmed.contains("is in the way") {
+        return Some(format!("⚠ {} (remove the old .{} copy)", trimmed, BACKUP_EXTENSION));
+    }
+    transform_nix_output(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standalone_config() {
+        let flake = "  outputs = {\n    homeConfigurations = {\n      \
+                     \"john@kraken\" = mkHome { };\n      alex = mkHome { };\n    };\n  };\n";
+        assert_eq!(standalone_config(flake, "john", "kraken").as_deref(), Some("john@kraken"));
+        assert_eq!(standalone_config(flake, "alex", "kraken").as_deref(), Some("alex"));
+        assert_eq!(standalone_config(flake, "john", "G1a"), None);
+        assert_eq!(standalone_config("nixosConfigurations = {\n};\n", "john", "kraken"), None);
+    }
+
+    #[test]
+    fn test_transform_home_manager_output() {
+        assert_eq!(transform_home_manager_output("Activating linkGeneration"), None);
+        assert_eq!(
+            transform_home_manager_output("Existing file '/home/john/.bashrc' is in the way of x"),
+            Some(
+                "⚠ Existing file '/home/john/.bashrc' is in the way of x (remove the old .backup \
+                 copy)"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            transform_home_manager_output("building '/nix/store/abc-home.drv'..."),
+            transform_nix_output("building '/nix/store/abc-home.drv'...")
+        );
+    }
+}
@@ -2,29 +2,66 @@
 //!
 //! This module handles the full NixOS system update process:
 //! - Flake input updates
-//! - System rebuild
+//! - System rebuild (with a progress bar from nix's internal-json log, binary
+//!   cache hit-rate diagnostics and optional `--override-input` to local
+//!   checkouts, reverted in flake.lock afterwards), retried once with
+//!   `--fallback` when a binary cache fails to serve a path
+//! - Standalone home-manager switch, for users with a homeConfigurations entry
+//! - Optional build-first preview, switching only after the nvd diff is accepted
 //! - Package comparison
 //! - CLI tool updates (Claude Code, Codex)
 //! - Browser profile status check
+//! - Headless runs from a systemd timer (`timer`)
+//! - JSON/Markdown reports of the summary (`report`)
+//! - Flake input updates proposed as pull requests (`propose`)
 
+pub mod cache;
+mod fallback;
 pub mod flake;
+mod home;
 mod packages;
+pub mod preview;
+mod progress;
+pub mod propose;
+pub mod report;
 mod shell;
+pub mod timer;
 mod tools;
+pub mod warnings;
 
 use anyhow::Result;
 use regex::Regex;
-use std::sync::LazyLock;
-use tokio::sync::mpsc;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Instant;
 
 use crate::app::UpdateSummary;
 use crate::commands::errors::{ErrorContext, ParsedError};
-use crate::commands::executor::{command_exists, get_output, run_capture, run_command, run_command_transformed};
-use crate::commands::CommandMessage;
-
-use flake::{get_flake_lock_hash, parse_flake_changes, save_flake_lock_backup};
+use crate::commands::executor::{command_exists, get_output, run_capture, run_command_transformed};
+use crate::commands::collect;
+use crate::commands::lint;
+use crate::commands::optimise::optimise_store;
+use crate::commands::runner::CommandRunner;
+use crate::commands::{CommandMessage, CommandSender};
+use crate::system::config::load_host_metadata;
+use crate::system::fleet::FleetConfig;
+use crate::system::generations::SYSTEM_PROFILE;
+use crate::system::manifest::record_build;
+use crate::system::optimise::format_bytes;
+use crate::system::parallelism::{load_parallelism, BuildParallelism};
+use crate::system::provenance;
+use crate::system::disk::identity::{check_disk_drift, configured_disk_device};
+use crate::system::lint::count_summary;
+
+use cache::CacheStats;
+use fallback::{retry_with_fallback, CacheFailure, FALLBACK_ARG};
+use flake::{
+    get_flake_lock_hash, parse_flake_changes, restore_flake_lock, save_flake_lock_backup,
+    InputOverride,
+};
 use packages::{parse_package_changes_from_history, PackageCompareResult};
+use progress::{track_build_progress, LOG_FORMAT_ARGS};
 use tools::{check_browser_status, clean_version, get_npm_package_version};
+use warnings::EvalWarnings;
 
 /// Regex to extract "message" from JSON error responses
 static JSON_MESSAGE_RE: LazyLock<Regex> =
@@ -32,7 +69,7 @@ static JSON_MESSAGE_RE: LazyLock<Regex> =
 
 /// Transform nix command output to remove noise and extract useful info from errors
 /// Returns None to skip the line, Some(line) to include it (possibly transformed)
-fn transform_nix_output(line: &str) -> Option<String> {
+pub(crate) fn transform_nix_output(line: &str) -> Option<String> {
     let trimmed = line.trim();
 
     // Keep intentional empty lines
@@ -116,10 +153,78 @@ fn transform_nix_output(line: &str) -> Option<String> {
     Some(line.to_string())
 }
 
+/// How `nixos-rebuild` activates the new system
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Activation {
+    /// Activate it now and boot into it from now on
+    #[default]
+    Switch,
+    /// Boot into it from the next reboot, leaving the running system alone
+    Boot,
+    /// Activate it now without making it the boot default
+    Test,
+}
+
+impl Activation {
+    pub const ALL: [Activation; 3] = [Activation::Switch, Activation::Boot, Activation::Test];
+
+    /// The nixos-rebuild subcommand
+    pub fn verb(self) -> &'static str {
+        match self {
+            Activation::Switch => "switch",
+            Activation::Boot => "boot",
+            Activation::Test => "test",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Activation::Switch => "Activate now and make it the boot default",
+            Activation::Boot => "Apply after the next reboot",
+            Activation::Test => "Activate now; the next reboot goes back",
+        }
+    }
+}
+
+/// Optional parts of an update, from `forge update`'s flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UpdateOptions {
+    /// Run statix and deadnix before the rebuild
+    pub lint: bool,
+    /// Collect garbage and optimise the store at the end
+    pub gc: bool,
+    /// Optimise the store at the end, without collecting garbage
+    pub optimise: bool,
+    /// Build first and activate only after the diff is accepted
+    pub preview: bool,
+    pub activation: Activation,
+    /// Run `home-manager switch` after the rebuild; set by `detect_home_manager`
+    pub home_manager: bool,
+    /// max-jobs and cores for this run, over the host's saved preference
+    pub parallelism: BuildParallelism,
+}
+
+impl UpdateOptions {
+    /// The same options, switching home-manager when this user has a
+    /// standalone configuration in the config repo
+    pub fn detect_home_manager(self) -> Self {
+        Self {
+            home_manager: home::detect(&crate::constants::nixos_config_dir()).is_some(),
+            ..self
+        }
+    }
+}
+
 /// Start the update process
-pub async fn start_update(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
+pub async fn start_update(
+    tx: CommandSender,
+    overrides: Vec<InputOverride>,
+    options: UpdateOptions,
+) -> Result<()> {
     tokio::spawn(async move {
-        if let Err(e) = run_update(&tx).await {
+        if let Err(e) = run_update(&tx, &overrides, options).await {
             tracing::error!("Update failed: {}", e);
             let _ = tx
                 .send(CommandMessage::StepFailed {
@@ -138,8 +243,16 @@ pub async fn start_update(tx: mpsc::Sender<CommandMessage>) -> Result<()> {
     Ok(())
 }
 
-async fn run_update(tx: &mpsc::Sender<CommandMessage>) -> Result<()> {
-    let mut summary = UpdateSummary::default();
+async fn run_update(
+    tx: &CommandSender,
+    overrides: &[InputOverride],
+    options: UpdateOptions,
+) -> Result<()> {
+    let mut summary = UpdateSummary {
+        overrides: overrides.to_vec(),
+        activation: options.activation,
+        ..Default::default()
+    };
 
     // Find the flake directory
     let flake_dir = crate::constants::nixos_config_dir();
@@ -159,9 +272,44 @@ async fn run_update(tx: &mpsc::Sender<CommandMessage>) -> Result<()> {
     out(tx, "  NixOS System Update").await;
     out(tx, "==============================================").await;
     out(tx, "").await;
+    for o in overrides {
+        out(tx, &format!("  ⚑ {} overridden by {}", o.input, o.path.display())).await;
+    }
+    if !overrides.is_empty() {
+        out(tx, "    flake.lock is restored after the rebuild").await;
+        out(tx, "").await;
+    }
+    let saved = load_parallelism(&hostname).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring saved build parallelism: {:#}", e);
+        BuildParallelism::default()
+    });
+    let parallelism = options.parallelism.or(saved);
+    if parallelism.is_set() {
+        let setting = |value: Option<u32>| value.map_or("nix.conf".to_string(), |v| v.to_string());
+        out(
+            tx,
+            &format!(
+                "  ⚙ Building with max-jobs {}, cores {}",
+                setting(parallelism.max_jobs),
+                setting(parallelism.cores)
+            ),
+        )
+        .await;
+        out(tx, "").await;
+    }
 
     let flake_path = flake_dir.to_str().unwrap_or(".");
 
+    // With reviewed updates flake.lock only moves through merged commits, so
+    // the update is whatever the pull brings in
+    let reviewed = FleetConfig::reviewed_updates(&flake_dir)?;
+    let lock_before_pull = if reviewed {
+        save_flake_lock_backup(&flake_dir).await;
+        Some(get_flake_lock_hash(&flake_dir).await)
+    } else {
+        None
+    };
+
     // Step 1: Pull configuration updates
     let pull_result = pull_config_updates(tx, flake_path).await;
     if let Err(e) = pull_result {
@@ -170,8 +318,14 @@ async fn run_update(tx: &mpsc::Sender<CommandMessage>) -> Result<()> {
     }
 
     // Save flake.lock hash and backup before update
-    let lock_before = get_flake_lock_hash(&flake_dir).await;
-    save_flake_lock_backup(&flake_dir).await;
+    let lock_original = std::fs::read(flake_dir.join("flake.lock")).ok();
+    let lock_before = match lock_before_pull {
+        Some(hash) => hash,
+        None => {
+            save_flake_lock_backup(&flake_dir).await;
+            get_flake_lock_hash(&flake_dir).await
+        }
+    };
 
     // Step 2: Flake update (with streaming output)
     out(tx, "").await;
@@ -180,63 +334,141 @@ async fn run_update(tx: &mpsc::Sender<CommandMessage>) -> Result<()> {
     out(tx, "══════════════════════════════════════════════").await;
     out(tx, "").await;
 
-    // Transform output: filter noise and extract useful info from errors
-    let success = run_command_transformed(tx, "nix", &["flake", "update", "--flake", flake_path], transform_nix_output).await?;
+    if reviewed {
+        out(tx, "  - Inputs are reviewed (fleet.toml): using the merged flake.lock").await;
+        out(tx, "    Propose an update with `forge update --propose`").await;
+    } else {
+        // Transform output: filter noise and extract useful info from errors
+        let args = ["flake", "update", "--flake", flake_path];
+        let success = run_command_transformed(tx, "nix", &args, transform_nix_output).await?;
 
-    out(tx, "").await;
-    if !success {
-        out(tx, "  ✗ Flake update failed").await;
-        let error = ParsedError::from_stderr(
-            "Flake update failed - see output above for details",
-            ErrorContext {
-                operation: "Flake update".to_string(),
-            },
-        );
-        tx.send(CommandMessage::StepFailed {
-            step: "flake".to_string(),
-            error,
-        })
-        .await?;
-        tx.send(CommandMessage::Done { success: false }).await?;
-        return Ok(());
+        out(tx, "").await;
+        if !success {
+            out(tx, "  ✗ Flake update failed").await;
+            let error = ParsedError::from_stderr(
+                "Flake update failed - see output above for details",
+                ErrorContext {
+                    operation: "Flake update".to_string(),
+                },
+            );
+            tx.send(CommandMessage::StepFailed {
+                step: "flake".to_string(),
+                error,
+            })
+            .await?;
+            tx.send(CommandMessage::Done { success: false }).await?;
+            return Ok(());
+        }
+        out(tx, "  ✓ Flake inputs updated").await;
     }
-    out(tx, "  ✓ Flake inputs updated").await;
     tx.send(CommandMessage::StepComplete {
         step: "flake".to_string(),
     })
     .await?;
 
+    // Optional: statix/deadnix over the whole repo, report only
+    if options.lint {
+        out(tx, "").await;
+        out(tx, "══════════════════════════════════════════════").await;
+        out(tx, "  Linting Configuration").await;
+        out(tx, "══════════════════════════════════════════════").await;
+        out(tx, "").await;
+        let runner = CommandRunner::new(tx);
+        summary.lint = Some(lint::lint_paths(&runner, &flake_dir, &["."], false).await);
+        runner.step_complete("Lint").await?;
+    }
+
     // Check if flake.lock changed
     let lock_after = get_flake_lock_hash(&flake_dir).await;
-    let needs_rebuild = lock_before != lock_after;
+    let lock_changed = lock_before != lock_after;
 
-    if needs_rebuild {
+    if lock_changed {
         summary.flake_changes = parse_flake_changes(&flake_dir).await.unwrap_or_default();
     }
 
+    // A local checkout may have changed without the lock noticing, so always rebuild
+    let needs_rebuild = lock_changed || !overrides.is_empty();
+    // What the lock should look like once the overrides are gone
+    let lock_snapshot = std::fs::read(flake_dir.join("flake.lock")).ok();
+
+    // Optional: build first and switch only once the diff is accepted
+    let mut switch = needs_rebuild;
+    if options.preview && needs_rebuild {
+        switch =
+            preview_switch(tx, flake_path, &hostname, overrides, parallelism, &mut summary).await?;
+        // Declining keeps the running system's inputs; a failed build only drops the overrides
+        let snapshot = if summary.switch_declined {
+            lock_original.as_deref()
+        } else {
+            lock_snapshot.as_deref().filter(|_| !overrides.is_empty())
+        };
+        if let Some(snapshot) = snapshot.filter(|_| !switch) {
+            match restore_flake_lock(&flake_dir, snapshot) {
+                Ok(true) => out(tx, "  ✓ Restored flake.lock").await,
+                Ok(false) => {}
+                Err(e) => out(tx, &format!("  ⚠ Could not restore flake.lock: {}", e)).await,
+            }
+        }
+    } else if options.preview {
+        tx.send(CommandMessage::StepSkipped {
+            step: "Preview".to_string(),
+        })
+        .await?;
+    }
+
     // Step 3: Rebuild (only if needed)
-    if needs_rebuild {
+    if switch {
         out(tx, "").await;
         out(tx, "══════════════════════════════════════════════").await;
         out(tx, "  Rebuilding System").await;
         out(tx, "══════════════════════════════════════════════").await;
         out(tx, "").await;
+        if let Some(warning) = disk_drift_warning(&flake_dir, &hostname) {
+            out(tx, &format!("  ⚠ {}", warning)).await;
+            out(tx, &format!("    Check modules/disko/{}.nix before reinstalling", hostname)).await;
+            out(tx, "").await;
+        }
 
         let config_name = hostname.clone();
         let flake_ref = format!("{}#{}", flake_path, config_name);
-        let success =
-            run_command(tx, "sudo", &["nixos-rebuild", "switch", "--flake", &flake_ref]).await?;
+        let started = Instant::now();
+        let mut build_args = InputOverride::args(overrides);
+        build_args.extend(parallelism.nix_args());
+        let mut args = vec!["nixos-rebuild", options.activation.verb(), "--flake", &flake_ref];
+        args.extend(build_args.iter().map(String::as_str));
+        args.extend(LOG_FORMAT_ARGS);
+        let mut run = rebuild(tx, &args).await?;
+        if retry_with_fallback(tx, "System rebuild", run.success, &run.cache_failure).await {
+            // The summary describes the attempt that counts
+            args.push(FALLBACK_ARG);
+            run = rebuild(tx, &args).await?;
+        }
+        let success = run.success;
+        summary.rebuild_secs = Some(started.elapsed().as_secs());
+        summary.cache_stats = Some(run.stats);
+        summary.warnings = run.warnings;
+
+        if let Some(snapshot) = lock_snapshot.as_deref().filter(|_| !overrides.is_empty()) {
+            match restore_flake_lock(&flake_dir, snapshot) {
+                Ok(true) => out(tx, "  ✓ Reverted flake.lock changes from the override").await,
+                Ok(false) => {}
+                Err(e) => out(tx, &format!("  ⚠ Could not restore flake.lock: {}", e)).await,
+            }
+        }
 
         out(tx, "").await;
         if success {
             out(tx, "  ✓ System rebuilt successfully").await;
+            record_provenance(tx, &hostname, options.activation).await;
             tx.send(CommandMessage::StepComplete {
                 step: "Rebuild".to_string(),
             })
             .await?;
 
-            // Check if shell needs restart due to store path change
-            if let Ok(Some(shell_name)) = shell::restart_shell_if_needed(tx).await {
+            if options.activation == Activation::Boot {
+                out(tx, "  - The new system applies after the next reboot").await;
+            } else if let Ok(Some(shell_name)) = shell::restart_shell_if_needed(tx).await {
+                // The running system's store paths changed under the shell
                 out(tx, &format!("  ✓ Restarted {} shell", shell_name)).await;
             }
         } else {
@@ -254,6 +486,12 @@ async fn run_update(tx: &mpsc::Sender<CommandMessage>) -> Result<()> {
             })
             .await?;
         }
+    } else if needs_rebuild {
+        out(tx, "  - Not switching to the new system").await;
+        tx.send(CommandMessage::StepSkipped {
+            step: "Rebuild".to_string(),
+        })
+        .await?;
     } else {
         out(tx, "").await;
         out(tx, "  - Skipping rebuild (no changes)").await;
@@ -264,6 +502,12 @@ async fn run_update(tx: &mpsc::Sender<CommandMessage>) -> Result<()> {
         .await?;
     }
 
+    // Standalone home-manager, once the new system is in place
+    if options.home_manager {
+        let rebuilt = switch && !summary.rebuild_failed;
+        switch_home_manager(tx, &flake_dir, flake_path, rebuilt, &mut summary).await?;
+    }
+
     // Step 3: Compare packages
     out(tx, "").await;
     out(tx, "  Comparing packages...").await;
@@ -296,19 +540,215 @@ async fn run_update(tx: &mpsc::Sender<CommandMessage>) -> Result<()> {
     // Step 6: Check app profiles
     check_app_profiles(tx, &mut summary).await?;
 
+    // Optional: collect garbage and optimise the store
+    if options.gc {
+        collect_garbage(tx, &mut summary).await?;
+    }
+    if options.gc || options.optimise {
+        optimise(tx, &mut summary).await?;
+    }
+
     // Output summary
     output_summary(tx, &summary).await?;
+    if !summary.flake_changes.is_empty() {
+        tx.send(CommandMessage::Changelog {
+            inputs: summary.flake_changes.clone(),
+            package_changes: summary.package_changes.clone(),
+        })
+        .await?;
+    }
+    let report = report::UpdateReport::new(&summary, &hostname, chrono::Local::now());
+    tx.send(CommandMessage::UpdateReport(Box::new(report))).await?;
 
+    let home_failed = matches!(summary.home_manager, Some((_, false)));
     tx.send(CommandMessage::Done {
-        success: !summary.rebuild_failed,
+        success: !summary.rebuild_failed && !home_failed,
     })
     .await?;
 
     Ok(())
 }
 
+/// One nixos-rebuild attempt and what its output showed
+struct RebuildRun {
+    success: bool,
+    stats: CacheStats,
+    warnings: EvalWarnings,
+    cache_failure: CacheFailure,
+}
+
+/// Run nixos-rebuild, feeding its output to the cache and warning diagnostics
+async fn rebuild(tx: &CommandSender, args: &[&str]) -> Result<RebuildRun> {
+    let stats = Arc::new(Mutex::new(CacheStats::default()));
+    let observed = Arc::clone(&stats);
+    let warnings = Arc::new(Mutex::new(EvalWarnings::default()));
+    let observed_warnings = Arc::clone(&warnings);
+    let cache_failure = CacheFailure::default();
+    let success = run_command_transformed(
+        tx,
+        "sudo",
+        args,
+        track_build_progress(
+            tx,
+            cache_failure.watch(move |line| {
+                if let Ok(mut stats) = observed.lock() {
+                    stats.observe(line);
+                }
+                if let Ok(mut warnings) = observed_warnings.lock() {
+                    warnings.observe(line);
+                }
+                Some(line.to_string())
+            }),
+        ),
+    )
+    .await?;
+    Ok(RebuildRun {
+        success,
+        stats: stats.lock().map(|stats| stats.clone()).unwrap_or_default(),
+        warnings: warnings.lock().map(|w| w.clone()).unwrap_or_default(),
+        cache_failure,
+    })
+}
+
+/// `home-manager switch` for this user's standalone configuration
+async fn switch_home_manager(
+    tx: &CommandSender,
+    flake_dir: &std::path::Path,
+    flake_path: &str,
+    rebuilt: bool,
+    summary: &mut UpdateSummary,
+) -> Result<()> {
+    out(tx, "").await;
+    out(tx, "══════════════════════════════════════════════").await;
+    out(tx, "  Switching Home Manager").await;
+    out(tx, "══════════════════════════════════════════════").await;
+    out(tx, "").await;
+
+    let config = home::detect(flake_dir);
+    let Some(config) = config.filter(|_| rebuilt) else {
+        out(tx, "  - Skipping home-manager (no new system)").await;
+        tx.send(CommandMessage::StepSkipped {
+            step: "Home".to_string(),
+        })
+        .await?;
+        return Ok(());
+    };
+
+    let args = home::switch_args(flake_path, &config);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let success = if command_exists("home-manager").await {
+        run_command_transformed(tx, "home-manager", &args, home::transform_home_manager_output)
+            .await?
+    } else {
+        out(tx, "  ✗ home-manager is not installed").await;
+        false
+    };
+
+    out(tx, "").await;
+    summary.home_manager = Some((config.clone(), success));
+    if success {
+        out(tx, &format!("  ✓ Switched home-manager ({})", config)).await;
+        tx.send(CommandMessage::StepComplete {
+            step: "Home".to_string(),
+        })
+        .await?;
+    } else {
+        out(tx, "  ✗ home-manager switch failed").await;
+        let error = ParsedError::from_stderr(
+            "home-manager switch failed - see output above for details",
+            ErrorContext {
+                operation: "Home Manager switch".to_string(),
+            },
+        );
+        tx.send(CommandMessage::StepFailed {
+            step: "Home".to_string(),
+            error,
+        })
+        .await?;
+    }
+    Ok(())
+}
+
+/// Sign the new system when there is a signing key and record it in the run manifest
+async fn record_provenance(tx: &CommandSender, hostname: &str, activation: Activation) {
+    // `test` leaves the system profile alone
+    let link = match activation {
+        Activation::Test => "/run/current-system",
+        Activation::Switch | Activation::Boot => SYSTEM_PROFILE,
+    };
+    let Ok(system) = get_output("readlink", &["-f", link]).await else {
+        return;
+    };
+    match provenance::sign(&system).await {
+        Ok(true) => out(tx, "  ✓ Signed the new system").await,
+        Ok(false) => {}
+        Err(e) => out(tx, &format!("  ⚠ Could not sign the new system: {:#}", e)).await,
+    }
+    match provenance::inspect(&system, None).await {
+        Ok(info) => {
+            if let Err(e) = record_build(hostname, &info) {
+                tracing::warn!("Failed to record the build in the run manifest: {}", e);
+            }
+            out(tx, &format!("  - {} ({})", info.path, info.signing_status())).await;
+        }
+        Err(e) => tracing::warn!("No provenance for {}: {:#}", system, e),
+    }
+}
+
+/// Build the new system and show its diff; whether to go on and switch
+async fn preview_switch(
+    tx: &CommandSender,
+    flake_path: &str,
+    hostname: &str,
+    overrides: &[InputOverride],
+    parallelism: BuildParallelism,
+    summary: &mut UpdateSummary,
+) -> Result<bool> {
+    out(tx, "").await;
+    out(tx, "══════════════════════════════════════════════").await;
+    out(tx, "  Previewing New System").await;
+    out(tx, "══════════════════════════════════════════════").await;
+    out(tx, "").await;
+
+    let mut build_args = InputOverride::args(overrides);
+    build_args.extend(parallelism.nix_args());
+    let Some(system) = preview::build_system(tx, flake_path, hostname, &build_args).await?
+    else {
+        out(tx, "").await;
+        out(tx, "  ✗ Building the new system failed").await;
+        summary.rebuild_failed = true;
+        let error = ParsedError::from_stderr(
+            "Building the new system failed - see output above for details",
+            ErrorContext {
+                operation: "System build".to_string(),
+            },
+        );
+        tx.send(CommandMessage::StepFailed {
+            step: "Preview".to_string(),
+            error,
+        })
+        .await?;
+        return Ok(false);
+    };
+
+    out(tx, "").await;
+    out(tx, &format!("  ✓ Built {}", system)).await;
+    let switch = preview::confirm_switch(tx, &system, summary.activation).await?;
+    if switch {
+        out(tx, "  ✓ Accepted").await;
+    } else {
+        out(tx, "  ✗ Declined").await;
+        summary.switch_declined = true;
+    }
+    tx.send(CommandMessage::StepComplete {
+        step: "Preview".to_string(),
+    })
+    .await?;
+    Ok(switch)
+}
+
 async fn update_claude_code(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     summary: &mut UpdateSummary,
 ) -> Result<()> {
     let claude_path = crate::constants::claude_cli_path();
@@ -349,7 +789,7 @@ async fn update_claude_code(
 }
 
 async fn update_codex_cli(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     summary: &mut UpdateSummary,
 ) -> Result<()> {
     let codex_path = crate::constants::codex_cli_path();
@@ -384,7 +824,7 @@ async fn update_codex_cli(
 }
 
 async fn check_app_profiles(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
     summary: &mut UpdateSummary,
 ) -> Result<()> {
     if command_exists("app-restore").await {
@@ -415,7 +855,78 @@ async fn check_app_profiles(
     Ok(())
 }
 
-async fn output_summary(tx: &mpsc::Sender<CommandMessage>, summary: &UpdateSummary) -> Result<()> {
+async fn collect_garbage(
+    tx: &CommandSender,
+    summary: &mut UpdateSummary,
+) -> Result<()> {
+    let runner = CommandRunner::new(tx);
+    // The generations it would delete are what a failed rebuild falls back on
+    if summary.rebuild_failed {
+        out(tx, "  - Skipping garbage collection (rebuild failed)").await;
+        tx.send(CommandMessage::StepSkipped {
+            step: "garbage".to_string(),
+        })
+        .await?;
+        return Ok(());
+    }
+
+    let retention = collect::retention(None).await;
+    out(tx, "").await;
+    out(tx, "══════════════════════════════════════════════").await;
+    out(tx, &format!("  Collecting Garbage (older than {})", retention)).await;
+    out(tx, "══════════════════════════════════════════════").await;
+    out(tx, "").await;
+
+    let Some(freed) = collect::collect_garbage(&runner, &retention).await? else {
+        out(tx, "").await;
+        out(tx, "  ✗ Garbage collection failed").await;
+        runner
+            .step_failed(
+                "garbage",
+                "nix-collect-garbage failed - see output above",
+                "Garbage collection",
+            )
+            .await?;
+        return Ok(());
+    };
+    summary.gc_freed = Some(freed);
+    runner.step_complete("garbage").await?;
+    Ok(())
+}
+
+async fn optimise(tx: &CommandSender, summary: &mut UpdateSummary) -> Result<()> {
+    let runner = CommandRunner::new(tx);
+    out(tx, "").await;
+    out(tx, "══════════════════════════════════════════════").await;
+    out(tx, "  Optimising Store").await;
+    out(tx, "══════════════════════════════════════════════").await;
+    out(tx, "").await;
+
+    let (optimised, progress) = optimise_store(&runner).await?;
+    if !optimised {
+        out(tx, "").await;
+        out(tx, "  ✗ nix store optimise failed").await;
+        runner
+            .step_failed(
+                "Optimising",
+                "nix store optimise failed - see output above",
+                "Store optimisation",
+            )
+            .await?;
+        return Ok(());
+    }
+    summary.optimise_saved = Some(progress.bytes_saved);
+    out(tx, "").await;
+    out(
+        tx,
+        &format!("  ✓ {} saved by hard-linking", format_bytes(progress.bytes_saved)),
+    )
+    .await;
+    runner.step_complete("Optimising").await?;
+    Ok(())
+}
+
+async fn output_summary(tx: &CommandSender, summary: &UpdateSummary) -> Result<()> {
     out(tx, "").await;
     out(tx, "╔══════════════════════════════════════════════╗").await;
     out(tx, "║            Update Summary                    ║").await;
@@ -474,6 +985,23 @@ async fn output_summary(tx: &mpsc::Sender<CommandMessage>, summary: &UpdateSumma
                     out(tx, &format!("    → {}", url)).await;
                 }
             }
+            if let Some(channel) = change.channel_line() {
+                out(tx, &format!("    {}", channel)).await;
+            }
+            if !change.releases.is_empty() {
+                let tags: Vec<&str> = change.releases.iter().map(|r| r.tag.as_str()).collect();
+                out(tx, &format!("    Releases: {}", tags.join(", "))).await;
+            }
+        }
+        out(tx, "").await;
+        out(tx, "  Press c for the full changelog of each input").await;
+    }
+
+    if !summary.overrides.is_empty() {
+        out(tx, "").await;
+        out(tx, "  Overridden inputs (this generation only, not in flake.lock):").await;
+        for o in &summary.overrides {
+            out(tx, &format!("    {} → {}", o.input, o.path.display())).await;
         }
     }
 
@@ -527,6 +1055,38 @@ async fn output_summary(tx: &mpsc::Sender<CommandMessage>, summary: &UpdateSumma
         out(tx, &format!("  Closure: {}", closure)).await;
     }
 
+    // Where the rebuild's store paths came from
+    if let Some(ref stats) = summary.cache_stats {
+        out(tx, "").await;
+        let duration = summary
+            .rebuild_secs
+            .map(|secs| format!(" (rebuild took {})", format_duration(secs)))
+            .unwrap_or_default();
+        out(tx, &format!("  Binary cache{}:", duration)).await;
+        for line in stats.summary_lines() {
+            out(tx, &line).await;
+        }
+    }
+
+    // Renamed/deprecated options, easy to miss in the rebuild output
+    if !summary.warnings.is_empty() {
+        out(tx, "").await;
+        out(tx, &format!("  Warnings ({}):", summary.warnings.messages.len())).await;
+        for line in summary.warnings.summary_lines() {
+            out(tx, &line).await;
+        }
+    }
+
+    if let Some(ref findings) = summary.lint {
+        out(tx, "").await;
+        out(tx, &format!("  Lint: {}", count_summary(findings))).await;
+    }
+
+    if let Some(disk) = collect::disk_summary(summary.gc_freed, summary.optimise_saved) {
+        out(tx, "").await;
+        out(tx, &format!("  Disk: {}", disk)).await;
+    }
+
     // Status section
     out(tx, "").await;
     out(tx, "  ─────────────────────────────────────────").await;
@@ -535,8 +1095,21 @@ async fn output_summary(tx: &mpsc::Sender<CommandMessage>, summary: &UpdateSumma
     // System status
     if summary.rebuild_failed {
         out(tx, "  System:      Rebuild failed").await;
+    } else if summary.switch_declined {
+        out(tx, "  System:      New system declined (flake.lock restored)").await;
     } else if summary.rebuild_skipped {
         out(tx, "  System:      Already up to date").await;
+    } else if summary.activation == Activation::Boot {
+        out(tx, "  System:      Applies after the next reboot (boot)").await;
+    } else if summary.activation == Activation::Test {
+        out(tx, "  System:      Active until the next reboot (test)").await;
+    }
+    match &summary.home_manager {
+        Some((config, true)) => out(tx, &format!("  Home:        Switched ({})", config)).await,
+        Some((config, false)) => {
+            out(tx, &format!("  Home:        home-manager switch failed ({})", config)).await
+        }
+        None => {}
     }
 
     // Show versions that weren't updated
@@ -572,8 +1145,17 @@ async fn output_summary(tx: &mpsc::Sender<CommandMessage>, summary: &UpdateSumma
     Ok(())
 }
 
+/// "42s", "12m 05s", "1h 02m"
+fn format_duration(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
 /// Pull configuration updates from remote repository
-async fn pull_config_updates(tx: &mpsc::Sender<CommandMessage>, config_path: &str) -> Result<()> {
+pub(crate) async fn pull_config_updates(tx: &CommandSender, config_path: &str) -> Result<()> {
     // Check if this is a git repository
     let git_dir = std::path::Path::new(config_path).join(".git");
     if !git_dir.exists() {
@@ -665,6 +1247,15 @@ async fn pull_config_updates(tx: &mpsc::Sender<CommandMessage>, config_path: &st
 }
 
 /// Helper to send stdout message
-pub(crate) async fn out(tx: &mpsc::Sender<CommandMessage>, msg: &str) {
-    let _ = tx.send(CommandMessage::Stdout(msg.to_string())).await;
+/// Whether the disk in this host's disko config is still the one recorded in host-info.json
+fn disk_drift_warning(flake_dir: &std::path::Path, hostname: &str) -> Option<String> {
+    let host_dir = flake_dir.join(crate::constants::HOSTS_SUBDIR).join(hostname);
+    let recorded = load_host_metadata(&host_dir)?.disk?;
+    let disko = flake_dir.join("modules/disko").join(format!("{}.nix", hostname));
+    let device = configured_disk_device(&std::fs::read_to_string(disko).ok()?)?;
+    check_disk_drift(&recorded, &device)
+}
+
+pub(crate) async fn out(tx: &CommandSender, msg: &str) {
+    let _ = tx.stdout(msg).await;
 }
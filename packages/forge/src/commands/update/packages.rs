@@ -1,30 +1,21 @@
 //! Package comparison utilities using nvd
 
 use anyhow::Result;
-use tokio::sync::mpsc;
 
 use super::out;
 use crate::commands::executor::{get_output, run_capture};
-use crate::commands::CommandMessage;
+use crate::commands::CommandSender;
 
 /// Result of package comparison containing version changes and closure summary
+#[derive(Default)]
 pub struct PackageCompareResult {
     pub changes: Vec<(String, String, String)>,
     pub closure_summary: Option<String>,
 }
 
-impl Default for PackageCompareResult {
-    fn default() -> Self {
-        Self {
-            changes: Vec::new(),
-            closure_summary: None,
-        }
-    }
-}
-
 /// Compare current system generation to previous generation using nvd
 pub async fn parse_package_changes_from_history(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
 ) -> Result<PackageCompareResult> {
     // Get current generation number from /nix/var/nix/profiles/system
     let current_gen = match get_output("readlink", &["/nix/var/nix/profiles/system"]).await {
@@ -79,7 +70,7 @@ pub async fn parse_package_changes_from_history(
 #[allow(dead_code)]
 pub async fn parse_package_changes(
     old_system: Option<&str>,
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
 ) -> Result<PackageCompareResult> {
     let old_path = match old_system {
         Some(p) if !p.is_empty() => p,
@@ -117,9 +108,9 @@ pub async fn parse_package_changes(
 }
 
 /// Parse nvd diff output into package changes and closure summary
-async fn parse_nvd_output(
+pub(super) async fn parse_nvd_output(
     stdout: &str,
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
 ) -> Result<PackageCompareResult> {
     // Parse nvd output - extract version changes and closure summary
     // Update format: "[U.]  #015  firefox    146.0 -> 146.0.1"
@@ -0,0 +1,123 @@
+//! Build-before-switch preview (`forge update --preview`)
+//!
+//! The new system is built next to the running one and compared with nvd; the
+//! switch only happens once the user has seen the diff and accepted it.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use tokio::sync::oneshot;
+
+use super::packages::{parse_nvd_output, PackageCompareResult};
+use super::fallback::{retry_with_fallback, CacheFailure, FALLBACK_ARG};
+use super::progress::{track_build_progress, LOG_FORMAT_ARGS};
+use super::{out, transform_nix_output, Activation};
+use crate::commands::executor::{get_output, run_capture, run_command_transformed};
+use crate::commands::{CommandMessage, CommandSender};
+use crate::constants::forge_data_dir;
+
+/// Out-link of the previewed system, kept until the next preview
+const PREVIEW_LINK: &str = "preview-system";
+
+/// A built system waiting for the go-ahead to switch to it
+#[derive(Clone)]
+pub struct SwitchPreview {
+    /// Store path of the new system
+    pub system: String,
+    /// nvd's diff against /run/current-system
+    pub diff: String,
+    /// e.g. "2478 -> 2490 (30 paths added, 18 paths removed, delta +12, disk usage +40MiB)"
+    pub closure_summary: Option<String>,
+    /// Packages with a version change
+    pub updated: usize,
+    /// What accepting runs: `nixos-rebuild switch`, `boot` or `test`
+    pub activation: Activation,
+    reply: Arc<Mutex<Option<oneshot::Sender<bool>>>>,
+}
+
+impl SwitchPreview {
+    /// Switch or not; only the first answer counts
+    pub fn answer(&self, switch: bool) {
+        let reply = self.reply.lock().ok().and_then(|mut reply| reply.take());
+        if let Some(reply) = reply {
+            let _ = reply.send(switch);
+        }
+    }
+}
+
+impl std::fmt::Debug for SwitchPreview {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SwitchPreview")
+            .field("system", &self.system)
+            .field("updated", &self.updated)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Build `hostname`'s system without activating it; the store path, or None
+/// if the build failed
+pub async fn build_system(
+    tx: &CommandSender,
+    flake_path: &str,
+    hostname: &str,
+    override_args: &[String],
+) -> Result<Option<String>> {
+    let installable = format!(
+        "{}#nixosConfigurations.{}.config.system.build.toplevel",
+        flake_path, hostname
+    );
+    let link = forge_data_dir().join(PREVIEW_LINK);
+    if let Some(parent) = link.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let link = link.to_string_lossy().to_string();
+    let mut args = vec!["build", installable.as_str(), "--out-link", link.as_str()];
+    args.extend(override_args.iter().map(String::as_str));
+    args.extend(LOG_FORMAT_ARGS);
+    let failure = CacheFailure::default();
+    let transform = track_build_progress(tx, failure.watch(transform_nix_output));
+    let mut built = run_command_transformed(tx, "nix", &args, transform).await?;
+    if retry_with_fallback(tx, "Preview build", built, &failure).await {
+        args.push(FALLBACK_ARG);
+        let transform = track_build_progress(tx, transform_nix_output);
+        built = run_command_transformed(tx, "nix", &args, transform).await?;
+    }
+    if !built {
+        return Ok(None);
+    }
+    Ok(get_output("readlink", &["-f", &link]).await.ok().filter(|s| !s.is_empty()))
+}
+
+/// Diff `system` against the running one and wait for the user's answer; a
+/// preview dropped unanswered counts as declined
+pub async fn confirm_switch(
+    tx: &CommandSender,
+    system: &str,
+    activation: Activation,
+) -> Result<bool> {
+    let (success, stdout, stderr) =
+        run_capture("nvd", &["diff", "/run/current-system", system]).await?;
+    let (diff, result) = if success {
+        out(tx, "  Changes against the running system:").await;
+        let result = parse_nvd_output(&stdout, tx).await?;
+        (stdout, result)
+    } else {
+        out(tx, &format!("  ⚠ nvd diff failed: {}", stderr.trim())).await;
+        (String::new(), PackageCompareResult::default())
+    };
+    if let Some(ref closure) = result.closure_summary {
+        out(tx, &format!("    Closure: {}", closure)).await;
+    }
+
+    let (reply, answer) = oneshot::channel();
+    tx.send(CommandMessage::PreviewSwitch(SwitchPreview {
+        system: system.to_string(),
+        diff,
+        closure_summary: result.closure_summary,
+        updated: result.changes.len(),
+        activation,
+        reply: Arc::new(Mutex::new(Some(reply))),
+    }))
+    .await?;
+    Ok(answer.await.unwrap_or(false))
+}
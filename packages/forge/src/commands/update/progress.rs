@@ -0,0 +1,55 @@
+//! Live build progress for the preview build and the rebuild
+//!
+//! Both run nix with `--log-format internal-json`; the events drive the
+//! progress bar on the update screen, and the text they carry goes to the
+//! output log as the plain format would have printed it.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::commands::{CommandMessage, CommandSender};
+use crate::system::build_progress::BuildTracker;
+
+/// Arguments that switch nix (and nixos-rebuild's nix calls) to events
+pub const LOG_FORMAT_ARGS: [&str; 2] = ["--log-format", "internal-json"];
+
+/// How often progress is sent to the UI; nix reports once per path
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Wrap an output transform so progress events are turned into
+/// `BuildProgress` messages and `transform` sees the text, one line at a time
+pub fn track_build_progress<F>(
+    tx: &CommandSender,
+    transform: F,
+) -> impl Fn(&str) -> Option<String> + Send + Sync + 'static
+where
+    F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+{
+    let tx = tx.clone();
+    let tracker = BuildTracker::default();
+    // A fresh bar, rather than the last build's totals until nix reports
+    let _ = tx.try_send(CommandMessage::BuildProgress(tracker.progress()));
+    let state = Mutex::new((tracker, Instant::now()));
+    move |line| {
+        let text = {
+            let mut state = state.lock().ok()?;
+            let (tracker, last_sent) = &mut *state;
+            let text = tracker.update(line);
+            if line.starts_with("@nix ") && last_sent.elapsed() >= PROGRESS_INTERVAL {
+                // Dropped when the channel is full; the next one catches up
+                let _ = tx.try_send(CommandMessage::BuildProgress(tracker.progress()));
+                *last_sent = Instant::now();
+            }
+            text?
+        };
+        if !text.contains('\n') {
+            return transform(&text);
+        }
+        let kept: Vec<String> = text.lines().filter_map(&transform).collect();
+        if kept.is_empty() {
+            None
+        } else {
+            Some(kept.join("\n"))
+        }
+    }
+}
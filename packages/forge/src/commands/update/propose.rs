@@ -0,0 +1,200 @@
+//! Flake input updates proposed as pull requests (`forge update --propose`)
+//!
+//! The inputs are updated in a temporary worktree off the default branch, so
+//! the checkout in use is left alone. The new flake.lock is committed to a
+//! `forge/update-<date>` branch and a PR opened with gh; with `review = true`
+//! in fleet.toml, hosts apply it only once the PR is merged.
+
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use super::flake::{parse_flake_changes, save_flake_lock_backup, FlakeInputChange};
+use super::report::InputReport;
+use crate::commands::executor::command_exists;
+use crate::constants::nixos_config_dir;
+
+/// Prefix of the branches proposals are pushed to
+const PROPOSAL_BRANCH_PREFIX: &str = "forge/update-";
+
+/// Update the flake inputs on a branch and open a pull request for them
+pub async fn propose() -> Result<()> {
+    if !command_exists("gh").await {
+        bail!("gh is not installed; it opens the pull request");
+    }
+    let repo = nixos_config_dir();
+    run(&repo, "git", &["fetch", "--quiet", "origin"]).await?;
+    let base = default_branch(&repo).await;
+    let branch = format!("{}{}", PROPOSAL_BRANCH_PREFIX, Local::now().format("%Y%m%d-%H%M"));
+
+    let mut worktree = Worktree::add(&repo, &branch, &format!("origin/{}", base)).await?;
+    let dir = worktree.path.to_string_lossy().to_string();
+
+    println!("Updating flake inputs on {}...", base);
+    save_flake_lock_backup(&worktree.path).await;
+    run(&worktree.path, "nix", &["flake", "update", "--flake", &dir]).await?;
+    if run(&worktree.path, "git", &["status", "--porcelain", "flake.lock"]).await?.is_empty() {
+        println!("All inputs are up to date; nothing to propose");
+        return Ok(());
+    }
+    let changes = parse_flake_changes(&worktree.path).await.unwrap_or_default();
+
+    let title = proposal_title(&changes);
+    run(&worktree.path, "git", &["commit", "--quiet", "-m", &title, "flake.lock"]).await?;
+    run(&worktree.path, "git", &["push", "--quiet", "-u", "origin", &branch]).await?;
+    worktree.keep_branch();
+    println!("Pushed {}", branch);
+
+    let body = proposal_body(&changes);
+    let url = run(
+        &worktree.path,
+        "gh",
+        &["pr", "create", "--base", &base, "--head", &branch, "--title", &title, "--body", &body],
+    )
+    .await?;
+    println!("Opened {}", url);
+    println!("Hosts pick it up at their next `forge update` once it is merged");
+    Ok(())
+}
+
+/// Commit subject and PR title, e.g. "flake.lock: update home-manager, nixpkgs"
+fn proposal_title(changes: &[FlakeInputChange]) -> String {
+    if changes.is_empty() {
+        return "flake.lock: update inputs".to_string();
+    }
+    let mut names: Vec<&str> = changes.iter().map(|c| c.name.as_str()).collect();
+    names.sort_unstable();
+    format!("flake.lock: update {}", names.join(", "))
+}
+
+/// PR description: what each input brings, as in `forge update --report`
+fn proposal_body(changes: &[FlakeInputChange]) -> String {
+    let mut md = Vec::new();
+    for change in changes {
+        md.extend(InputReport::from(change).markdown());
+        md.push(String::new());
+    }
+    md.push(
+        "Proposed by `forge update --propose`. Hosts with `review = true` in fleet.toml \
+         apply this flake.lock at their next `forge update` once it is merged."
+            .to_string(),
+    );
+    md.join("\n")
+}
+
+/// Branch origin/HEAD points at, falling back to main
+async fn default_branch(repo: &Path) -> String {
+    run(repo, "git", &["symbolic-ref", "--short", "refs/remotes/origin/HEAD"])
+        .await
+        .ok()
+        .and_then(|head| head.strip_prefix("origin/").map(str::to_string))
+        .unwrap_or_else(|| "main".to_string())
+}
+
+/// Run a command in `dir`, returning its trimmed stdout
+async fn run(dir: &Path, program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        bail!(
+            "{} {} failed: {}",
+            program,
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Worktree on a new branch, removed again when dropped
+struct Worktree {
+    repo: PathBuf,
+    path: PathBuf,
+    branch: String,
+    /// Whether the branch goes too, until it was pushed
+    delete_branch: bool,
+}
+
+impl Worktree {
+    async fn add(repo: &Path, branch: &str, start: &str) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("forge-propose-{}", std::process::id()));
+        let dir = path.to_string_lossy();
+        run(repo, "git", &["worktree", "add", "--quiet", "-b", branch, &dir, start]).await?;
+        Ok(Self {
+            repo: repo.to_path_buf(),
+            path,
+            branch: branch.to_string(),
+            delete_branch: true,
+        })
+    }
+
+    fn keep_branch(&mut self) {
+        self.delete_branch = false;
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let git = |args: &[&str]| {
+            let _ = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&self.repo)
+                .args(args)
+                .output();
+        };
+        git(&["worktree", "remove", "--force", &self.path.to_string_lossy()]);
+        if self.delete_branch {
+            git(&["branch", "-D", &self.branch]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::update::flake::CommitInfo;
+
+    fn change(name: &str) -> FlakeInputChange {
+        FlakeInputChange {
+            name: name.to_string(),
+            owner: "NixOS".to_string(),
+            repo: name.to_string(),
+            old_rev: "aaaaaaaaaa".to_string(),
+            new_rev: "bbbbbbbbbb".to_string(),
+            commits: vec![CommitInfo {
+                hash: "bbbbbbb".to_string(),
+                message: "firefox: 130.0 -> 131.0".to_string(),
+            }],
+            total_commits: 1,
+            compare_url: None,
+            branch: None,
+            old_modified: None,
+            new_modified: None,
+            old_version: None,
+            new_version: None,
+            releases: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_proposal_title() {
+        assert_eq!(proposal_title(&[]), "flake.lock: update inputs");
+        assert_eq!(
+            proposal_title(&[change("nixpkgs"), change("home-manager")]),
+            "flake.lock: update home-manager, nixpkgs"
+        );
+    }
+
+    #[test]
+    fn test_proposal_body() {
+        let body = proposal_body(&[change("nixpkgs")]);
+        assert!(body.starts_with("### nixpkgs (NixOS/nixpkgs)\n\n`aaaaaaa` → `bbbbbbb`"));
+        assert!(body.contains("- `bbbbbbb` firefox: 130.0 -> 131.0\n"));
+        assert!(body.ends_with("once it is merged."));
+    }
+}
@@ -0,0 +1,334 @@
+//! Update summaries exported for archiving or posting (`forge update --report`)
+//!
+//! The format follows the file name: `.md` or `.markdown` writes Markdown,
+//! anything else JSON.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::path::Path;
+
+use super::flake::FlakeInputChange;
+use super::{format_duration, Activation};
+use crate::app::UpdateSummary;
+
+/// How a report is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Markdown,
+}
+
+impl ReportFormat {
+    pub fn for_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("md" | "markdown") => ReportFormat::Markdown,
+            _ => ReportFormat::Json,
+        }
+    }
+}
+
+/// A flake input the update moved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InputReport {
+    pub name: String,
+    /// owner/repo
+    pub source: String,
+    pub old_rev: String,
+    pub new_rev: String,
+    pub total_commits: usize,
+    /// "hash message" of the commits that were fetched
+    pub commits: Vec<String>,
+    pub compare_url: Option<String>,
+    /// e.g. "nixos-unstable advanced 6 days"
+    pub channel: Option<String>,
+    pub releases: Vec<String>,
+}
+
+impl From<&FlakeInputChange> for InputReport {
+    fn from(change: &FlakeInputChange) -> Self {
+        InputReport {
+            name: change.name.clone(),
+            source: format!("{}/{}", change.owner, change.repo),
+            old_rev: change.old_rev.clone(),
+            new_rev: change.new_rev.clone(),
+            total_commits: change.total_commits,
+            commits: change
+                .commits
+                .iter()
+                .map(|c| format!("{} {}", c.hash, c.message))
+                .collect(),
+            compare_url: change.compare_url.clone(),
+            channel: change.channel_line(),
+            releases: change.releases.iter().map(|r| r.tag.clone()).collect(),
+        }
+    }
+}
+
+impl InputReport {
+    /// `### name (owner/repo)` and what moved, as Markdown lines
+    pub fn markdown(&self) -> Vec<String> {
+        let mut md = vec![format!("### {} ({})", self.name, self.source), String::new()];
+        let mut line = format!(
+            "`{}` → `{}`, {} commit{}",
+            short(&self.old_rev),
+            short(&self.new_rev),
+            self.total_commits,
+            if self.total_commits == 1 { "" } else { "s" }
+        );
+        if let Some(url) = &self.compare_url {
+            line.push_str(&format!(" ([compare]({}))", url));
+        }
+        md.push(line);
+        if let Some(channel) = &self.channel {
+            md.extend([String::new(), channel.clone()]);
+        }
+        if !self.releases.is_empty() {
+            md.extend([String::new(), format!("Releases: {}", self.releases.join(", "))]);
+        }
+        if !self.commits.is_empty() {
+            md.push(String::new());
+            for commit in &self.commits {
+                let (hash, message) = commit.split_once(' ').unwrap_or((commit, ""));
+                md.push(format!("- `{}` {}", hash, message));
+            }
+        }
+        md
+    }
+}
+
+/// A package or tool going from one version to another
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VersionChange {
+    pub name: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// What an update changed, in a form that outlives the TUI
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UpdateReport {
+    pub host: String,
+    pub finished: DateTime<Local>,
+    /// "updated", "up to date", "rebuild failed" or "switch declined"
+    pub outcome: String,
+    pub activation: Activation,
+    pub flake_inputs: Vec<InputReport>,
+    pub packages: Vec<VersionChange>,
+    /// nvd closure size summary
+    pub closure: Option<String>,
+    pub tools: Vec<VersionChange>,
+    pub rebuild_secs: Option<u64>,
+    pub gc_freed: Option<u64>,
+    pub optimise_saved: Option<u64>,
+}
+
+impl UpdateReport {
+    pub fn new(summary: &UpdateSummary, host: &str, finished: DateTime<Local>) -> Self {
+        let outcome = if summary.rebuild_failed {
+            "rebuild failed"
+        } else if summary.switch_declined {
+            "switch declined"
+        } else if summary.rebuild_skipped {
+            "up to date"
+        } else {
+            "updated"
+        };
+        let flake_inputs = summary.flake_changes.iter().map(InputReport::from).collect();
+        let packages = summary
+            .package_changes
+            .iter()
+            .map(|(name, old, new)| VersionChange {
+                name: name.clone(),
+                old: old.clone(),
+                new: new.clone(),
+            })
+            .collect();
+        let tools = [
+            ("Claude Code", &summary.claude_old, &summary.claude_new),
+            ("Codex CLI", &summary.codex_old, &summary.codex_new),
+        ]
+        .into_iter()
+        .filter_map(|(name, old, new)| {
+            Some(VersionChange {
+                name: name.to_string(),
+                old: old.clone()?,
+                new: new.clone()?,
+            })
+        })
+        .collect();
+
+        Self {
+            host: host.to_string(),
+            finished,
+            outcome: outcome.to_string(),
+            activation: summary.activation,
+            flake_inputs,
+            packages,
+            closure: summary.closure_summary.clone(),
+            tools,
+            rebuild_secs: summary.rebuild_secs,
+            gc_freed: summary.gc_freed,
+            optimise_saved: summary.optimise_saved,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = vec![
+            format!("# forge update on {}", self.host),
+            String::new(),
+            format!(
+                "{} · {} · nixos-rebuild {}",
+                self.finished.format("%Y-%m-%d %H:%M"),
+                self.outcome,
+                self.activation.verb()
+            ),
+        ];
+        if let Some(secs) = self.rebuild_secs {
+            md.push(String::new());
+            md.push(format!("Rebuild took {}.", format_duration(secs)));
+        }
+
+        if !self.flake_inputs.is_empty() {
+            md.extend([String::new(), "## Flake inputs".to_string()]);
+            for input in &self.flake_inputs {
+                md.push(String::new());
+                md.extend(input.markdown());
+            }
+        }
+
+        if !self.packages.is_empty() {
+            md.extend([
+                String::new(),
+                "## Packages".to_string(),
+                String::new(),
+                "| Package | Old | New |".to_string(),
+                "|---|---|---|".to_string(),
+            ]);
+            for pkg in &self.packages {
+                md.push(format!("| {} | {} | {} |", pkg.name, pkg.old, pkg.new));
+            }
+        }
+
+        if let Some(closure) = &self.closure {
+            md.extend([String::new(), "## Closure".to_string(), String::new(), closure.clone()]);
+        }
+
+        if !self.tools.is_empty() {
+            md.extend([String::new(), "## Tools".to_string(), String::new()]);
+            for tool in &self.tools {
+                if tool.old == tool.new {
+                    md.push(format!("- {}: {}", tool.name, tool.new));
+                } else {
+                    md.push(format!("- {}: {} → {}", tool.name, tool.old, tool.new));
+                }
+            }
+        }
+
+        if let Some(disk) =
+            crate::commands::collect::disk_summary(self.gc_freed, self.optimise_saved)
+        {
+            md.extend([String::new(), format!("Disk: {}", disk)]);
+        }
+
+        let mut text = md.join("\n");
+        text.push('\n');
+        text
+    }
+
+    /// Write the report to `path`, in the format its extension asks for
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = match ReportFormat::for_path(path) {
+            ReportFormat::Json => self.to_json(),
+            ReportFormat::Markdown => self.to_markdown(),
+        };
+        std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+fn short(rev: &str) -> &str {
+    &rev[..7.min(rev.len())]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::update::flake::{CommitInfo, FlakeInputChange};
+    use chrono::TimeZone;
+
+    fn report() -> UpdateReport {
+        let summary = UpdateSummary {
+            flake_changes: vec![FlakeInputChange {
+                name: "nixpkgs".to_string(),
+                owner: "NixOS".to_string(),
+                repo: "nixpkgs".to_string(),
+                old_rev: "abcdef0123".to_string(),
+                new_rev: "1234567890".to_string(),
+                commits: vec![CommitInfo {
+                    hash: "1234567".to_string(),
+                    message: "firefox: 130.0 -> 131.0".to_string(),
+                }],
+                total_commits: 1,
+                compare_url: Some("https://github.com/NixOS/nixpkgs/compare/a...b".to_string()),
+                branch: None,
+                old_modified: None,
+                new_modified: None,
+                old_version: None,
+                new_version: None,
+                releases: Vec::new(),
+            }],
+            package_changes: vec![(
+                "firefox".to_string(),
+                "130.0".to_string(),
+                "131.0".to_string(),
+            )],
+            claude_old: Some("1.0.1".to_string()),
+            claude_new: Some("1.0.2".to_string()),
+            codex_old: Some("0.5.0".to_string()),
+            rebuild_secs: Some(125),
+            ..Default::default()
+        };
+        let finished = Local.with_ymd_and_hms(2026, 10, 17, 4, 13, 0).unwrap();
+        UpdateReport::new(&summary, "kraken", finished)
+    }
+
+    #[test]
+    fn test_report_format_for_path() {
+        assert_eq!(ReportFormat::for_path(Path::new("a/update.md")), ReportFormat::Markdown);
+        assert_eq!(ReportFormat::for_path(Path::new("update.markdown")), ReportFormat::Markdown);
+        assert_eq!(ReportFormat::for_path(Path::new("update.json")), ReportFormat::Json);
+        assert_eq!(ReportFormat::for_path(Path::new("update")), ReportFormat::Json);
+    }
+
+    #[test]
+    fn test_report_from_summary() {
+        let report = report();
+        assert_eq!(report.outcome, "updated");
+        assert_eq!(report.flake_inputs[0].source, "NixOS/nixpkgs");
+        assert_eq!(report.flake_inputs[0].commits, vec!["1234567 firefox: 130.0 -> 131.0"]);
+        // Codex has no new version, so it is left out
+        assert_eq!(report.tools.len(), 1);
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["activation"], "switch");
+        assert_eq!(json["packages"][0]["new"], "131.0");
+    }
+
+    #[test]
+    fn test_report_markdown() {
+        let md = report().to_markdown();
+        assert!(md.starts_with("# forge update on kraken\n\n2026-10-17 04:13 · updated"));
+        assert!(md.contains("Rebuild took 2m 05s."));
+        assert!(md.contains("`abcdef0` → `1234567`, 1 commit ([compare](https://"));
+        assert!(md.contains("- `1234567` firefox: 130.0 -> 131.0\n"));
+        assert!(md.contains("| firefox | 130.0 | 131.0 |\n"));
+        assert!(md.contains("- Claude Code: 1.0.1 → 1.0.2\n"));
+    }
+}
@@ -6,10 +6,9 @@
 
 use anyhow::Result;
 use std::path::PathBuf;
-use tokio::sync::mpsc;
 
 use crate::commands::executor::{get_output, run_capture};
-use crate::commands::CommandMessage;
+use crate::commands::CommandSender;
 
 use super::out;
 
@@ -163,7 +162,7 @@ pub async fn get_expected_shell_path(shell: ShellType) -> Option<String> {
 /// Check if shell needs restart and restart if necessary
 /// Returns Some(shell_name) if restarted, None if not needed
 pub async fn restart_shell_if_needed(
-    tx: &mpsc::Sender<CommandMessage>,
+    tx: &CommandSender,
 ) -> Result<Option<String>> {
     // Get info about running quickshell
     let running_info = match get_running_quickshell_info().await {
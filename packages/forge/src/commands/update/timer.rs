@@ -0,0 +1,363 @@
+//! Scheduled updates: `forge update --install-timer`, `--headless` and `--timer-status`
+//!
+//! The timer is a systemd user service and timer written to
+//! ~/.config/systemd/user. It runs `forge update --headless`, which prints to
+//! the journal instead of drawing the TUI and records how the run went, for
+//! `--timer-status`. Rebuilding relies on passwordless sudo for wheel
+//! (modules/common.nix). With a `~/.config/forge/mail.toml` the summary, or
+//! the failure, is also mailed (see `system::mail`).
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::{start_update, Activation, UpdateOptions};
+use crate::commands::command_channel;
+use crate::commands::executor::{get_output, run_capture};
+use crate::commands::install::{print_progress_recorded, PrintedRun};
+use crate::constants::scheduled_update_path;
+use crate::system::mail::MailSettings;
+
+/// Unit name of the service and the timer
+const TIMER_UNIT: &str = "forge-update";
+
+/// forge from the system profile, so the units survive updates and garbage collection
+const FORGE_BIN: &str = "/run/current-system/sw/bin/forge";
+
+/// Output lines a mail falls back to when the run printed no summary
+const MAIL_TAIL_LINES: usize = 50;
+
+/// systemd user units get a bare PATH; the update needs sudo, nix and git
+const UNIT_PATH: &str = concat!(
+    "/run/wrappers/bin:/run/current-system/sw/bin:",
+    "/etc/profiles/per-user/%u/bin:%h/.nix-profile/bin"
+);
+
+/// How often the timer runs the update (`off` removes it)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TimerSchedule {
+    Daily,
+    Weekly,
+    Monthly,
+    Off,
+}
+
+impl TimerSchedule {
+    /// systemd OnCalendar value
+    fn on_calendar(self) -> Option<&'static str> {
+        match self {
+            TimerSchedule::Daily => Some("daily"),
+            TimerSchedule::Weekly => Some("weekly"),
+            TimerSchedule::Monthly => Some("monthly"),
+            TimerSchedule::Off => None,
+        }
+    }
+}
+
+/// How the last headless update went
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledRun {
+    pub started: DateTime<Local>,
+    pub finished: DateTime<Local>,
+    pub success: bool,
+    pub activation: Activation,
+}
+
+impl ScheduledRun {
+    /// e.g. "2026-10-12 04:13, succeeded after 12m 5s (nixos-rebuild boot)"
+    pub fn describe(&self) -> String {
+        let secs = (self.finished - self.started).num_seconds().max(0);
+        format!(
+            "{}, {} after {}m {}s (nixos-rebuild {})",
+            self.started.format("%Y-%m-%d %H:%M"),
+            if self.success { "succeeded" } else { "failed" },
+            secs / 60,
+            secs % 60,
+            self.activation.verb()
+        )
+    }
+}
+
+/// Read the last headless update, if there was one
+pub fn load_scheduled_run() -> Option<ScheduledRun> {
+    let content = std::fs::read_to_string(scheduled_update_path()).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Run the update with its output on stdout, then record (and mail) the outcome
+/// and write the report to `report_path`
+pub async fn run_headless(options: UpdateOptions, report_path: Option<PathBuf>) -> Result<()> {
+    let started = Local::now();
+    let (tx, mut rx) = command_channel();
+    start_update(tx, Vec::new(), options.detect_home_manager()).await?;
+    let printed = print_progress_recorded(&mut rx).await;
+    let success = printed.success;
+
+    if let (Some(path), Some(report)) = (&report_path, &printed.report) {
+        match report.write(path) {
+            Ok(()) => println!("Wrote the update report to {}", path.display()),
+            Err(e) => eprintln!("Failed to write the update report: {:#}", e),
+        }
+    }
+
+    let run = ScheduledRun {
+        started,
+        finished: Local::now(),
+        success,
+        activation: options.activation,
+    };
+    let path = scheduled_update_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&run)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    // A mail that can't go out is reported, but doesn't change the run's outcome
+    match MailSettings::load() {
+        Ok(Some(mail)) if mail.wants(success) => {
+            let host = get_output("hostname", &[]).await.unwrap_or_default();
+            let (subject, body) = mail_report(&host, &run, &printed);
+            match mail.send(&host, &subject, &body).await {
+                Ok(()) => println!("Mailed the report to {}", mail.to.join(", ")),
+                Err(e) => eprintln!("Failed to mail the report: {:#}", e),
+            }
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Not mailing the report: {:#}", e),
+    }
+
+    if !success {
+        bail!("Update failed, see above");
+    }
+    Ok(())
+}
+
+/// Subject and body of the mail about a headless run: the update summary, or
+/// the error panels and the output leading up to the failure
+fn mail_report(host: &str, run: &ScheduledRun, printed: &PrintedRun) -> (String, String) {
+    let outcome = if run.success { "succeeded" } else { "FAILED" };
+    let subject = format!("[forge] {}: update {}", host, outcome);
+
+    let mut body = vec![format!("forge update on {}: {}", host, run.describe()), String::new()];
+    for panel in &printed.errors {
+        body.extend(panel.iter().cloned());
+        body.push(String::new());
+    }
+    // The summary starts with the line above its title (the box's top)
+    let summary = printed
+        .lines
+        .iter()
+        .position(|line| line.contains("Update Summary"))
+        .map(|i| i.saturating_sub(1));
+    match summary {
+        Some(start) if run.success || printed.errors.is_empty() => {
+            body.extend(printed.lines[start..].iter().cloned());
+        }
+        _ => {
+            body.push(format!("Last {} lines of output:", MAIL_TAIL_LINES));
+            body.push(String::new());
+            let tail = printed.lines.len().saturating_sub(MAIL_TAIL_LINES);
+            body.extend(printed.lines[tail..].iter().cloned());
+        }
+    }
+    (subject, body.join("\n"))
+}
+
+/// Write (or with `off`, remove) the update timer and let systemd pick it up
+pub async fn install_timer(schedule: TimerSchedule, options: UpdateOptions) -> Result<()> {
+    let dir = unit_dir()?;
+    let service = dir.join(format!("{}.service", TIMER_UNIT));
+    let timer = dir.join(format!("{}.timer", TIMER_UNIT));
+    let timer_name = format!("{}.timer", TIMER_UNIT);
+
+    let Some(on_calendar) = schedule.on_calendar() else {
+        let _ = run_capture("systemctl", &["--user", "disable", "--now", &timer_name]).await;
+        for path in [&service, &timer] {
+            if path.exists() {
+                std::fs::remove_file(path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+        }
+        systemctl(&["daemon-reload"]).await?;
+        println!("Removed the scheduled update");
+        return Ok(());
+    };
+
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    std::fs::write(&service, service_unit(options))
+        .with_context(|| format!("Failed to write {}", service.display()))?;
+    std::fs::write(&timer, timer_unit(on_calendar))
+        .with_context(|| format!("Failed to write {}", timer.display()))?;
+    systemctl(&["daemon-reload"]).await?;
+    systemctl(&["enable", "--now", &timer_name]).await?;
+
+    println!("Scheduled `forge update` {} ({})", on_calendar, timer.display());
+    println!("Next run: {}", next_run().await.unwrap_or_else(|| "unknown".to_string()));
+    println!("Follow a run with: journalctl --user -u {}", TIMER_UNIT);
+    Ok(())
+}
+
+/// When the timer runs next and how the last headless update went
+pub async fn print_timer_status() -> Result<()> {
+    let timer = unit_dir()?.join(format!("{}.timer", TIMER_UNIT));
+    match std::fs::read_to_string(&timer) {
+        Ok(content) => {
+            let schedule = content
+                .lines()
+                .find_map(|l| l.strip_prefix("OnCalendar="))
+                .unwrap_or("unknown");
+            println!("Schedule: {}", schedule);
+            println!("Next run: {}", next_run().await.unwrap_or_else(|| "unknown".to_string()));
+        }
+        Err(_) => println!("Schedule: none (forge update --install-timer weekly)"),
+    }
+    match load_scheduled_run() {
+        Some(run) => println!("Last run: {}", run.describe()),
+        None => println!("Last run: never"),
+    }
+    println!("Logs: journalctl --user -u {}", TIMER_UNIT);
+    Ok(())
+}
+
+fn unit_dir() -> Result<PathBuf> {
+    dirs::config_dir()
+        .map(|dir| dir.join("systemd/user"))
+        .context("Could not find the user config directory")
+}
+
+async fn systemctl(args: &[&str]) -> Result<()> {
+    let mut full = vec!["--user"];
+    full.extend_from_slice(args);
+    let (success, _, stderr) = run_capture("systemctl", &full).await?;
+    if !success {
+        bail!("systemctl --user {} failed: {}", args.join(" "), stderr.trim());
+    }
+    Ok(())
+}
+
+/// The timer's next elapse as systemd prints it
+async fn next_run() -> Option<String> {
+    let timer = format!("{}.timer", TIMER_UNIT);
+    let args = ["--user", "show", &timer, "-p", "NextElapseUSecRealtime", "--value"];
+    let (_, stdout, _) = run_capture("systemctl", &args).await.ok()?;
+    Some(stdout.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+fn service_unit(options: UpdateOptions) -> String {
+    let mut command = format!(
+        "{} update --headless --activation {}",
+        FORGE_BIN,
+        options.activation.verb()
+    );
+    if options.lint {
+        command.push_str(" --lint");
+    }
+    if options.gc {
+        command.push_str(" --gc");
+    } else if options.optimise {
+        command.push_str(" --optimise");
+    }
+    if let Some(jobs) = options.parallelism.max_jobs {
+        command.push_str(&format!(" --max-jobs {}", jobs));
+    }
+    if let Some(cores) = options.parallelism.cores {
+        command.push_str(&format!(" --cores {}", cores));
+    }
+    format!(
+        "# Written by forge update --install-timer\n\
+         [Unit]\n\
+         Description=Scheduled forge update\n\
+         After=network-online.target\n\
+         Wants=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         Environment=PATH={}\n\
+         ExecStart={}\n",
+        UNIT_PATH, command
+    )
+}
+
+fn timer_unit(on_calendar: &str) -> String {
+    format!(
+        "# Written by forge update --install-timer\n\
+         [Unit]\n\
+         Description=Run forge update {}\n\
+         \n\
+         [Timer]\n\
+         OnCalendar={}\n\
+         RandomizedDelaySec=1h\n\
+         Persistent=true\n\
+         \n\
+         [Install]\n\
+         WantedBy=timers.target\n",
+        on_calendar, on_calendar
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_units() {
+        let options = UpdateOptions {
+            gc: true,
+            activation: Activation::Boot,
+            ..Default::default()
+        };
+        let service = service_unit(options);
+        assert!(service.contains(
+            "ExecStart=/run/current-system/sw/bin/forge update --headless --activation boot --gc\n"
+        ));
+        assert!(service.contains("Environment=PATH=/run/wrappers/bin:"));
+        let timer = timer_unit("weekly");
+        assert!(timer.contains("\nOnCalendar=weekly\n"));
+        assert!(timer.contains("\nWantedBy=timers.target\n"));
+    }
+
+    #[test]
+    fn test_mail_report() {
+        let started = "2026-10-12T04:13:00+02:00".parse::<DateTime<Local>>().unwrap();
+        let mut run = ScheduledRun {
+            started,
+            finished: started,
+            success: true,
+            activation: Activation::Switch,
+        };
+        let mut printed = PrintedRun {
+            success: true,
+            lines: ["building...", "╔═══╗", "║ Update Summary ║", "  Flake inputs updated:"]
+                .map(String::from)
+                .to_vec(),
+            errors: Vec::new(),
+            report: None,
+        };
+        let (subject, body) = mail_report("kraken", &run, &printed);
+        assert_eq!(subject, "[forge] kraken: update succeeded");
+        assert!(body.ends_with("╔═══╗\n║ Update Summary ║\n  Flake inputs updated:"));
+        assert!(!body.contains("building..."));
+
+        run.success = false;
+        printed.success = false;
+        printed.errors = vec![vec!["[✗] Step failed: Rebuild".to_string()]];
+        let (subject, body) = mail_report("kraken", &run, &printed);
+        assert_eq!(subject, "[forge] kraken: update FAILED");
+        assert!(body.contains("[✗] Step failed: Rebuild\n\nLast 50 lines of output:\n\nbuilding..."));
+    }
+
+    #[test]
+    fn test_describe_run() {
+        let started = "2026-10-12T04:13:00+02:00".parse::<DateTime<Local>>().unwrap();
+        let run = ScheduledRun {
+            started,
+            finished: started + chrono::Duration::seconds(725),
+            success: true,
+            activation: Activation::Boot,
+        };
+        assert!(run.describe().ends_with(", succeeded after 12m 5s (nixos-rebuild boot)"));
+    }
+}
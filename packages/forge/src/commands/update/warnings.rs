@@ -0,0 +1,111 @@
+//! Evaluation warnings from the rebuild output
+//!
+//! Renamed and deprecated options are reported while the configuration is
+//! evaluated, and then buried under thousands of build lines:
+//!
+//! ```text
+//! evaluation warning: The option `hardware.opengl.enable' defined in `…' has been renamed …
+//! trace: warning: The option `sound.enable' can no longer be used since it's been removed.
+//! ```
+//!
+//! Older Nix prints `trace: warning:`, newer Nix `evaluation warning:`.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// How many warnings the summary lists before eliding the rest
+const MAX_WARNINGS_SHOWN: usize = 10;
+
+const WARNING_PREFIXES: [&str; 2] = ["evaluation warning: ", "trace: warning: "];
+
+/// Store path of the flake source, stripped so file names stay readable
+static SOURCE_PATH_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"/nix/store/[0-9a-z]{32}-source/").unwrap());
+
+/// Distinct evaluation warnings seen during one rebuild, in order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvalWarnings {
+    pub messages: Vec<String>,
+}
+
+impl EvalWarnings {
+    /// Record the line if it is an evaluation warning
+    pub fn observe(&mut self, line: &str) {
+        let Some(message) = WARNING_PREFIXES
+            .iter()
+            .find_map(|prefix| line.find(prefix).map(|i| &line[i + prefix.len()..]))
+        else {
+            return;
+        };
+        let message = SOURCE_PATH_RE.replace_all(message.trim(), "").to_string();
+        // Every host in the evaluation repeats the same warning
+        if !message.is_empty() && !self.messages.contains(&message) {
+            self.messages.push(message);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Summary lines for the update report
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .messages
+            .iter()
+            .take(MAX_WARNINGS_SHOWN)
+            .map(|m| format!("    ⚠ {}", m))
+            .collect();
+        if self.messages.len() > MAX_WARNINGS_SHOWN {
+            lines.push(format!(
+                "    ... and {} more (see the rebuild output)",
+                self.messages.len() - MAX_WARNINGS_SHOWN
+            ));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observe_warnings() {
+        let mut warnings = EvalWarnings::default();
+        let renamed = "evaluation warning: The option `hardware.opengl.enable' defined in \
+                       `/nix/store/0c5n8qnmv7aqz1w1k4kxkbd7xxmp1fhx-source/hosts/G1a/default.nix' \
+                       has been renamed to `hardware.graphics.enable'.";
+        let lines = [
+            "building the system configuration...",
+            renamed,
+            "trace: warning: The option `sound.enable' can no longer be used.",
+            renamed,
+            "warning: Git tree '/home/john/nixos-config' is dirty",
+            "building '/nix/store/0lz8ij7kq4iqa7lmzw9ypz0qbk3r4rfl-linux-6.18.2.drv'...",
+        ];
+        for line in lines {
+            warnings.observe(line);
+        }
+        assert_eq!(warnings.messages.len(), 2);
+        assert_eq!(
+            warnings.messages[0],
+            "The option `hardware.opengl.enable' defined in `hosts/G1a/default.nix' has been \
+             renamed to `hardware.graphics.enable'."
+        );
+        assert!(warnings.messages[1].starts_with("The option `sound.enable'"));
+        assert!(warnings.summary_lines()[0].starts_with("    ⚠ The option"));
+    }
+
+    #[test]
+    fn test_summary_elides_long_lists() {
+        let mut warnings = EvalWarnings::default();
+        for i in 0..12 {
+            warnings.observe(&format!("evaluation warning: deprecated option {}", i));
+        }
+        let lines = warnings.summary_lines();
+        assert_eq!(lines.len(), MAX_WARNINGS_SHOWN + 1);
+        assert_eq!(lines.last().unwrap(), "    ... and 2 more (see the rebuild output)");
+        assert!(EvalWarnings::default().is_empty());
+    }
+}
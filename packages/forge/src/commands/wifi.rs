@@ -0,0 +1,141 @@
+//! Scanning for and joining wireless networks (see `system::wifi`)
+
+use anyhow::Result;
+use std::time::Duration;
+
+use super::executor::{command_exists, run_capture, run_capture_sensitive};
+use super::{CommandMessage, CommandSender};
+use crate::system::network::check_connectivity;
+use crate::system::wifi::{
+    parse_iwctl_networks, parse_nmcli_networks, wireless_interfaces, WifiBackend, WifiNetwork,
+};
+
+/// How long to wait for the connection to reach github.com after joining
+const ONLINE_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// List the networks the wireless interfaces see, in the background
+pub async fn start_wifi_scan(tx: CommandSender) -> Result<()> {
+    tokio::spawn(async move {
+        let result = scan().await.map_err(|e| {
+            tracing::warn!("WiFi scan failed: {:#}", e);
+            format!("{:#}", e)
+        });
+        let _ = tx.send(CommandMessage::WifiNetworks(result)).await;
+    });
+    Ok(())
+}
+
+/// Join `network`, then wait until github.com answers
+pub async fn start_wifi_connect(
+    tx: CommandSender,
+    network: WifiNetwork,
+    passphrase: String,
+) -> Result<()> {
+    tokio::spawn(async move {
+        let result = connect(&network, &passphrase).await.map_err(|e| {
+            tracing::warn!("Failed to join {}: {:#}", network.ssid, e);
+            format!("{:#}", e)
+        });
+        let _ = tx.send(CommandMessage::WifiConnected(result)).await;
+    });
+    Ok(())
+}
+
+async fn backend() -> Result<WifiBackend> {
+    if command_exists("nmcli").await {
+        let running = run_capture("nmcli", &["-t", "-f", "RUNNING", "general"])
+            .await
+            .map(|(ok, stdout, _)| ok && stdout.trim() == "running")
+            .unwrap_or(false);
+        if running {
+            return Ok(WifiBackend::NetworkManager);
+        }
+    }
+    if command_exists("iwctl").await {
+        return Ok(WifiBackend::Iwd);
+    }
+    anyhow::bail!("Neither NetworkManager nor iwd is running")
+}
+
+async fn scan() -> Result<Vec<WifiNetwork>> {
+    let interfaces = wireless_interfaces();
+    if interfaces.is_empty() {
+        anyhow::bail!("No wireless interface found; plug in a network cable instead");
+    }
+    match backend().await? {
+        WifiBackend::NetworkManager => {
+            let (ok, stdout, stderr) = run_capture(
+                "nmcli",
+                &[
+                    "-t", "-f", "DEVICE,SSID,SIGNAL,SECURITY",
+                    "device", "wifi", "list", "--rescan", "yes",
+                ],
+            )
+            .await?;
+            if !ok {
+                anyhow::bail!("nmcli: {}", stderr.trim());
+            }
+            Ok(parse_nmcli_networks(&stdout))
+        }
+        WifiBackend::Iwd => {
+            let mut networks = Vec::new();
+            for interface in &interfaces {
+                // The scan runs in the background; give it a moment before listing
+                let _ = run_capture("iwctl", &["station", interface, "scan"]).await;
+                tokio::time::sleep(Duration::from_secs(3)).await;
+                let (ok, stdout, stderr) =
+                    run_capture("iwctl", &["station", interface, "get-networks"]).await?;
+                if !ok {
+                    anyhow::bail!("iwctl: {}", stderr.trim());
+                }
+                networks.extend(parse_iwctl_networks(interface, &stdout));
+            }
+            Ok(networks)
+        }
+    }
+}
+
+/// Join `network`; the passphrase is an argument, so the commands are run
+/// without logging them
+async fn connect(network: &WifiNetwork, passphrase: &str) -> Result<String> {
+    let backend = backend().await?;
+    let (ok, stdout, stderr) = match backend {
+        WifiBackend::NetworkManager => {
+            let mut args = vec!["device", "wifi", "connect", network.ssid.as_str()];
+            if network.secured {
+                args.extend(["password", passphrase]);
+            }
+            args.extend(["ifname", network.interface.as_str()]);
+            run_capture_sensitive("nmcli", &args).await?
+        }
+        WifiBackend::Iwd => {
+            let mut args = Vec::new();
+            if network.secured {
+                args.extend(["--passphrase", passphrase]);
+            }
+            args.extend(["station", network.interface.as_str(), "connect", network.ssid.as_str()]);
+            run_capture_sensitive("iwctl", &args).await?
+        }
+    };
+    if !ok {
+        let message = if stderr.trim().is_empty() { stdout } else { stderr };
+        anyhow::bail!("{}", message.trim());
+    }
+
+    // DHCP may still be running after the association
+    let deadline = tokio::time::Instant::now() + ONLINE_TIMEOUT;
+    loop {
+        let online = tokio::task::spawn_blocking(check_connectivity)
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .unwrap_or(false);
+        if online {
+            return Ok(format!("Connected to {} via {}", network.ssid, backend));
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("Joined {} but github.com is still unreachable", network.ssid);
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
@@ -18,22 +18,46 @@ pub const EVENT_POLL_TIMEOUT_MS: u64 = 100;
 /// Spinner animation interval in milliseconds
 pub const SPINNER_TICK_MS: u128 = 100;
 
-/// Channel buffer size for command messages
+/// Minimum time between redraws (~30fps), so streaming output doesn't redraw
+/// per line on the live ISO's software-rendered console
+pub const MIN_FRAME_INTERVAL_MS: u64 = 33;
+
+/// Channel buffer size for command events (steps, progress, Done)
 pub const COMMAND_CHANNEL_SIZE: usize = 100;
 
+/// Command output lines queued for the UI; beyond this the oldest are dropped
+pub const OUTPUT_QUEUE_SIZE: usize = 1000;
+
 /// Maximum length for user text input (prevents memory exhaustion)
 pub const MAX_INPUT_LENGTH: usize = 100;
 
+/// Maximum length of the SSH keys input, which can hold pasted public keys
+pub const MAX_KEYS_INPUT_LENGTH: usize = 4000;
+
 // =============================================================================
 // User Constants
 // =============================================================================
 
-/// Primary user UID (first regular user on NixOS)
+/// Primary user UID (first regular user on NixOS), unless install.toml sets one
 pub const PRIMARY_USER_UID: u32 = 1000;
 
-/// Primary user GID (users group on NixOS)
+/// Primary user GID (users group on NixOS), unless install.toml sets one
 pub const PRIMARY_USER_GID: u32 = 100;
 
+/// Username `mkNixosSystem` in flake.nix defaults to; hosts installed for
+/// anyone else get theirs written into the flake
+pub const FLAKE_DEFAULT_USERNAME: &str = "john";
+
+// =============================================================================
+// Install Requirements
+// =============================================================================
+
+/// Minimum RAM for nixos-install from the Live ISO (MiB); below this we block
+pub const MIN_INSTALL_RAM_MB: u64 = 2048;
+
+/// Recommended RAM for nixos-install from the Live ISO (MiB); below this we warn
+pub const RECOMMENDED_INSTALL_RAM_MB: u64 = 4096;
+
 // =============================================================================
 // Path Constants
 // =============================================================================
@@ -72,6 +96,46 @@ pub const FORGE_LOG_FILE: &str = "forge.log";
 /// Screen log filename
 pub const SCREEN_LOG_FILE: &str = "screen.log";
 
+/// Cached host list filename (speeds up host selection on large repos)
+pub const HOSTS_CACHE_FILE: &str = "hosts-cache.json";
+
+/// Unfinished create-host wizard, offered for resuming at the next launch
+pub const HOST_DRAFT_FILE: &str = "create-host-draft.json";
+
+/// Saved `nix log` output of failed builds, one subdirectory per failed run
+pub const BUILD_LOGS_DIR: &str = "build-logs";
+
+/// Asciinema recordings of TUI sessions (`forge --record`)
+pub const RECORDINGS_DIR: &str = "recordings";
+
+/// Per-run manifests of the config edits forge made, with their diffs
+pub const RUN_MANIFESTS_DIR: &str = "runs";
+
+/// Update reports exported from the update's Complete screen
+pub const REPORTS_DIR: &str = "reports";
+
+/// Outcome of the last headless update, e.g. from the update timer
+pub const SCHEDULED_UPDATE_FILE: &str = "scheduled-update.json";
+
+/// Forge settings directory (relative to home)
+pub const FORGE_CONFIG_DIR: &str = ".config/forge";
+
+/// Installer settings, in the settings directory (see `system::install_settings`)
+pub const INSTALL_SETTINGS_FILE: &str = "install.toml";
+
+/// Mail settings for headless update reports, in the settings directory (see `system::mail`)
+pub const MAIL_SETTINGS_FILE: &str = "mail.toml";
+
+/// Per-host build parallelism, in the settings directory (see `system::parallelism`)
+pub const PARALLELISM_SETTINGS_FILE: &str = "parallelism.toml";
+
+/// Stamp written once the first-boot bootstrap has run (or was dismissed);
+/// home/forge-bootstrap.nix checks for it before launching `forge bootstrap`
+pub const BOOTSTRAP_DONE_FILE: &str = "bootstrap-done";
+
+/// Status socket of a running forge, in $XDG_RUNTIME_DIR (see `system::ipc`)
+pub const IPC_SOCKET_FILE: &str = "forge.sock";
+
 // =============================================================================
 // App Backup Paths (relative to home directory)
 // =============================================================================
@@ -109,6 +173,83 @@ pub fn forge_data_dir() -> PathBuf {
         .unwrap_or_else(|| PathBuf::from("/tmp/forge"))
 }
 
+/// Get the failed build log directory
+pub fn build_logs_dir() -> PathBuf {
+    forge_data_dir().join(BUILD_LOGS_DIR)
+}
+
+/// Get the session recording directory
+pub fn recordings_dir() -> PathBuf {
+    forge_data_dir().join(RECORDINGS_DIR)
+}
+
+/// Get the run manifest directory
+pub fn run_manifests_dir() -> PathBuf {
+    forge_data_dir().join(RUN_MANIFESTS_DIR)
+}
+
+/// Get the exported update report directory
+pub fn reports_dir() -> PathBuf {
+    forge_data_dir().join(REPORTS_DIR)
+}
+
+/// Get the cached host list path
+pub fn hosts_cache_path() -> PathBuf {
+    forge_data_dir().join(HOSTS_CACHE_FILE)
+}
+
+/// Get the create-host wizard draft path
+pub fn host_draft_path() -> PathBuf {
+    forge_data_dir().join(HOST_DRAFT_FILE)
+}
+
+/// Get the installer settings path
+pub fn install_settings_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|h| h.join(FORGE_CONFIG_DIR))
+        .unwrap_or_else(|| PathBuf::from("/tmp/forge"))
+        .join(INSTALL_SETTINGS_FILE)
+}
+
+/// Get the mail settings path
+pub fn mail_settings_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|h| h.join(FORGE_CONFIG_DIR))
+        .unwrap_or_else(|| PathBuf::from("/tmp/forge"))
+        .join(MAIL_SETTINGS_FILE)
+}
+
+/// Get the build parallelism settings path
+pub fn parallelism_settings_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|h| h.join(FORGE_CONFIG_DIR))
+        .unwrap_or_else(|| PathBuf::from("/tmp/forge"))
+        .join(PARALLELISM_SETTINGS_FILE)
+}
+
+/// Get the last headless update record path
+pub fn scheduled_update_path() -> PathBuf {
+    forge_data_dir().join(SCHEDULED_UPDATE_FILE)
+}
+
+/// Get the per-session private directory ($XDG_RUNTIME_DIR, else the data directory)
+pub fn runtime_dir() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(forge_data_dir)
+}
+
+/// Get the status socket path
+pub fn ipc_socket_path() -> PathBuf {
+    runtime_dir().join(IPC_SOCKET_FILE)
+}
+
+/// Get the first-boot bootstrap stamp path
+pub fn bootstrap_done_path() -> PathBuf {
+    forge_data_dir().join(BOOTSTRAP_DONE_FILE)
+}
+
 /// Get the app backup data directory, checking both new and legacy paths
 pub fn app_backup_data_dir() -> PathBuf {
     dirs::home_dir()
@@ -246,6 +387,7 @@ mod tests {
         assert!(!FORGE_DATA_DIR.is_empty());
         assert!(!FORGE_LOG_FILE.is_empty());
         assert!(!SCREEN_LOG_FILE.is_empty());
+        assert!(!HOSTS_CACHE_FILE.is_empty());
     }
 
     #[test]
@@ -263,13 +405,16 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_buffer_constants_reasonable() {
         assert!(OUTPUT_BUFFER_SIZE > 0);
         assert!(OUTPUT_BUFFER_SIZE <= 10000); // Reasonable upper bound
         assert!(DEFAULT_COMMAND_TIMEOUT_SECS > 0);
         assert!(EVENT_POLL_TIMEOUT_MS > 0);
         assert!(SPINNER_TICK_MS > 0);
+        assert!(MIN_FRAME_INTERVAL_MS < EVENT_POLL_TIMEOUT_MS);
         assert!(COMMAND_CHANNEL_SIZE > 0);
+        assert!(OUTPUT_QUEUE_SIZE >= COMMAND_CHANNEL_SIZE);
         assert!(MAX_INPUT_LENGTH > 0);
     }
 
@@ -18,12 +18,19 @@ use crossterm::{
 use futures::StreamExt;
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
-use std::time::Duration;
-use tokio::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
-use app::{App, AppMode};
-use commands::CommandMessage;
+use app::{App, AppMode, ShellRequest};
+use commands::CommandReceiver;
+use system::ipc::IpcServer;
+use system::recording::{recording_path, CastRecorder, TuiOutput};
+
+type Tui = Terminal<CrosstermBackend<TuiOutput>>;
+
+/// Set by `--record`; every TUI started by the command records itself
+static RECORD_SESSION: AtomicBool = AtomicBool::new(false);
 
 /// NixOS Configuration Tool
 #[derive(Parser)]
@@ -34,6 +41,9 @@ use commands::CommandMessage;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Record the TUI session to an asciinema cast in ~/.local/share/forge/recordings
+    #[arg(long, global = true)]
+    record: bool,
 }
 
 #[derive(Subcommand)]
@@ -44,6 +54,33 @@ enum Commands {
         hostname: Option<String>,
         /// Target disk device (e.g., /dev/nvme0n1)
         disk: Option<String>,
+        /// Install without prompts from a TOML answer file
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["hostname", "disk"])]
+        answers: Option<std::path::PathBuf>,
+        /// Show what would be done (disk, disko config, dry build) without writing anything
+        #[arg(long)]
+        plan: bool,
+        /// Install onto another machine over SSH with nixos-anywhere (disk is on that machine)
+        #[arg(
+            long,
+            value_name = "USER@HOST",
+            requires_all = ["hostname", "disk"],
+            conflicts_with_all = ["answers", "plan"]
+        )]
+        target: Option<String>,
+        /// Install without network from DIR: a nixos-config checkout (or DIR/nixos-config),
+        /// with an optional binary cache in DIR/nix-cache
+        #[arg(long, value_name = "DIR", conflicts_with = "target")]
+        offline: Option<std::path::PathBuf>,
+        /// Extra binary cache to use during the install and on the new system (repeatable)
+        #[arg(long, value_name = "URL", conflicts_with = "answers")]
+        substituter: Vec<String>,
+        /// Public key of an extra binary cache, name:base64 (repeatable)
+        #[arg(long, value_name = "KEY", conflicts_with = "answers")]
+        trusted_public_key: Vec<String>,
+        /// HTTP(S) proxy for the install and the new system (default: $https_proxy)
+        #[arg(long, value_name = "URL", conflicts_with = "answers")]
+        proxy: Option<String>,
     },
     /// Create a new host configuration
     CreateHost {
@@ -51,7 +88,53 @@ enum Commands {
         hostname: Option<String>,
     },
     /// Update flake inputs, rebuild system, and update CLI tools
-    Update,
+    Update {
+        /// Build with a flake input replaced by a local checkout (flake.lock is left unchanged)
+        #[arg(long, num_args = 2, value_names = ["INPUT", "PATH"])]
+        override_input: Vec<String>,
+        /// Run statix and deadnix over the config repo before rebuilding
+        #[arg(long)]
+        lint: bool,
+        /// Collect garbage (gc.json's retention) and optimise the store after the update
+        #[arg(long)]
+        gc: bool,
+        /// Optimise the store after the update without collecting garbage (implied by --gc)
+        #[arg(long)]
+        optimise: bool,
+        /// Builds nix runs at once for this update (default: `forge parallelism`, then nix.conf)
+        #[arg(long, value_name = "N")]
+        max_jobs: Option<u32>,
+        /// Threads each build may use for this update, 0 for all of them
+        #[arg(long, value_name = "N")]
+        cores: Option<u32>,
+        /// Build the new system first and switch only after confirming its nvd diff
+        #[arg(long, conflicts_with = "headless")]
+        preview: bool,
+        /// How to activate the rebuilt system (boot applies it after the next reboot)
+        #[arg(long, value_enum, default_value_t = commands::update::Activation::Switch)]
+        activation: commands::update::Activation,
+        /// Print the output instead of drawing the TUI and record the result (for timers)
+        #[arg(long, conflicts_with = "override_input")]
+        headless: bool,
+        /// Run this update on a systemd user timer (with --activation, --lint, --gc and
+        /// --optimise)
+        #[arg(
+            long,
+            value_enum,
+            value_name = "SCHEDULE",
+            conflicts_with_all = ["headless", "preview", "override_input"]
+        )]
+        install_timer: Option<commands::update::timer::TimerSchedule>,
+        /// Show the update timer's schedule and how the last scheduled update went
+        #[arg(long, exclusive = true)]
+        timer_status: bool,
+        /// Write the update summary to PATH when done (Markdown for .md, JSON otherwise)
+        #[arg(long, value_name = "PATH", conflicts_with = "install_timer")]
+        report: Option<std::path::PathBuf>,
+        /// Update the flake inputs on a branch and open a PR instead of rebuilding (needs gh)
+        #[arg(long, exclusive = true)]
+        propose: bool,
+    },
     /// App profile management (browsers, Termius, etc.)
     #[command(alias = "browser")]
     Apps {
@@ -63,6 +146,109 @@ enum Commands {
         #[command(subcommand)]
         action: KeysAction,
     },
+    /// First-boot guide: restore keys, then app profiles
+    Bootstrap,
+    /// Record this host's generation and config revision on the forge-state branch
+    ReportState,
+    /// Open a terminal in a flake devShell (config repo and ~/code projects)
+    Shells,
+    /// Manage flake registry pins shared through registry.json in the config repo
+    Registry,
+    /// Pick the nixpkgs channel (unstable or stable) each host follows in flake.nix
+    Channels,
+    /// Garbage collection schedule for all hosts, with per-host overrides (gc.json)
+    Gc {
+        #[command(subcommand)]
+        action: Option<GcAction>,
+    },
+    /// Nix experimental features, build jobs, cores and sandbox for all hosts, with per-host
+    /// overrides (nix-conf.json); takes effect at each host's next rebuild
+    NixConf,
+    /// max-jobs and cores for this host's own rebuilds, passed per run as --option, with a
+    /// suggestion from the CPU topology
+    Parallelism,
+    /// Turn a background service (update notifications, nightly auto-update, app profile
+    /// backups) on for a host in services.json; takes effect at its next rebuild
+    EnableService {
+        #[arg(value_enum)]
+        service: system::services::Service,
+        /// Turn it off instead
+        #[arg(long)]
+        disable: bool,
+        /// Host to change (default: this machine)
+        #[arg(long)]
+        host: Option<String>,
+        /// Apply without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Show forge's background services on this host and turn them on or off
+    Services,
+    /// What a running forge is doing and which updates are pending, for status bars
+    Status {
+        /// Print JSON for a Waybar custom module (return-type: json)
+        #[arg(long)]
+        waybar: bool,
+    },
+    /// Deduplicate the Nix store (nix store optimise) with live progress
+    Optimise,
+    /// Switch the system back to an earlier generation, picked from a list
+    Rollback,
+    /// Give root-owned files in the config repo and forge/app-backup data back to the user,
+    /// and make SSH and age keys private again
+    FixPerms {
+        /// Only show what would change
+        #[arg(long)]
+        dry_run: bool,
+        /// Apply without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Find the commit of a flake input that introduced a regression
+    Bisect {
+        /// Last known good revision
+        good: String,
+        /// First known bad revision
+        bad: String,
+        /// Flake input to bisect
+        #[arg(long, default_value = "nixpkgs")]
+        input: String,
+        /// How each candidate is rebuilt
+        #[arg(long, default_value = "test", value_parser = ["build", "dry-activate", "test"])]
+        mode: String,
+    },
+    /// Run a pipeline from pipelines.nix in the config repo (menu if no name given)
+    Run {
+        /// Pipeline name (attribute in pipelines.nix)
+        name: Option<String>,
+    },
+    /// Host configurations in the config repo
+    Host {
+        #[command(subcommand)]
+        action: HostAction,
+    },
+    /// Multi-host operations over SSH (hosts and order from fleet.toml)
+    Fleet {
+        #[command(subcommand)]
+        action: FleetAction,
+    },
+    /// Build and switch remote hosts over SSH (fleet.toml, or every host in flake.nix)
+    Deploy(DeployArgs),
+}
+
+#[derive(clap::Args)]
+struct DeployArgs {
+    /// Only deploy these hosts (default: every host in fleet.toml or flake.nix)
+    hosts: Vec<String>,
+    /// Hosts to deploy at once after the canaries (overrides fleet.toml)
+    #[arg(short, long)]
+    parallel: Option<usize>,
+    /// Deploy hosts now even outside their maintenance windows
+    #[arg(long)]
+    ignore_window: bool,
+    /// Build every system here or on its host (overrides fleet.toml)
+    #[arg(long, value_enum, value_name = "WHERE")]
+    build_on: Option<system::fleet::BuildHost>,
 }
 
 #[derive(Subcommand)]
@@ -81,6 +267,51 @@ enum AppsAction {
     },
     /// Check for app profile updates
     Status,
+    /// Create the private backup repo on GitHub and push the first backup
+    Init {
+        /// Repo to use instead of the configured one (OWNER/NAME or a git URL)
+        #[arg(long)]
+        repo: Option<String>,
+        /// Only set up the repo, without running a backup
+        #[arg(long)]
+        no_backup: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GcAction {
+    /// Collect garbage now, then optimise the store, and report the space reclaimed
+    Run {
+        /// Keep generations from the last DAYS days (default: gc.json's delete_older_than)
+        #[arg(long, value_name = "DAYS", value_parser = clap::value_parser!(u32).range(1..))]
+        older_than: Option<u32>,
+    },
+}
+
+#[derive(Subcommand)]
+enum HostAction {
+    /// Retire a host: drop it from flake.nix, move its files to archive/,
+    /// record it as decommissioned on the forge-state branch and revoke its
+    /// GitHub key
+    Archive {
+        /// nixosConfigurations attribute (and hosts/ directory) of the host
+        name: String,
+        /// Only show what would change
+        #[arg(long)]
+        dry_run: bool,
+        /// Archive without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum FleetAction {
+    /// Deploy the current config to each host: canaries first, stop on first failure
+    /// (same as `forge deploy`)
+    Update(DeployArgs),
+    /// Show which hosts are behind or have drifted from the repo
+    Status,
 }
 
 #[derive(Subcommand)]
@@ -97,6 +328,12 @@ enum KeysAction {
     },
     /// Show key status
     Status,
+    /// Register the SSH key with GitHub and check the private repos are reachable
+    Github {
+        /// Add it as a deploy key of the app-backup repo instead of to your account
+        #[arg(long)]
+        deploy: bool,
+    },
 }
 
 #[tokio::main]
@@ -116,16 +353,120 @@ async fn main() -> Result<()> {
     tracing::info!("Forge starting");
 
     let cli = Cli::parse();
+    RECORD_SESSION.store(cli.record, Ordering::Relaxed);
+
+    if let Some(Commands::Install {
+        offline,
+        substituter,
+        trusted_public_key,
+        proxy,
+        answers,
+        ..
+    }) = &cli.command
+    {
+        // An answer file brings its own [nix] table
+        if answers.is_none() {
+            let settings = system::nix_settings::NixSettings {
+                substituters: substituter.clone(),
+                trusted_public_keys: trusted_public_key.clone(),
+                proxy: proxy.clone(),
+            };
+            settings.validate()?;
+            system::nix_settings::configure(settings);
+        }
+        if let Some(dir) = offline {
+            commands::install::prepare_offline(dir)?;
+        }
+    }
 
     match cli.command {
-        Some(Commands::Install { hostname, disk }) => {
+        Some(Commands::Install {
+            answers: Some(path),
+            plan,
+            ..
+        }) => {
+            // Validate the whole file before touching any disk
+            let answers = system::answers::InstallAnswers::load(&path)?;
+            system::nix_settings::configure(answers.nix.clone());
+            if plan {
+                commands::install::run_plan(
+                    &answers.hostname,
+                    &answers.disk,
+                    Some(&answers.username),
+                    answers.encrypt,
+                )
+                .await
+            } else {
+                commands::install::run_unattended(&answers).await
+            }
+        }
+        Some(Commands::Install {
+            hostname,
+            disk,
+            plan: true,
+            ..
+        }) => match (hostname, disk) {
+            (Some(hostname), Some(disk)) => {
+                commands::install::run_plan(&hostname, &disk, None, true).await
+            }
+            _ => anyhow::bail!("--plan needs a hostname and disk, or --answers"),
+        },
+        Some(Commands::Install {
+            hostname: Some(hostname),
+            disk: Some(disk),
+            target: Some(target),
+            ..
+        }) => {
+            if let Some(err) = app::state::validate_ssh_target(&target) {
+                anyhow::bail!("{}", err);
+            }
+            run_tui(AppMode::Install(app::InstallState::new_remote(hostname, disk, target))).await
+        }
+        Some(Commands::Install { hostname, disk, .. }) => {
             run_tui(AppMode::Install(app::InstallState::new(hostname, disk))).await
         }
         Some(Commands::CreateHost { hostname: _ }) => {
             // Hostname is now entered at the end of the wizard, so we always start with hardware detection
             run_tui(AppMode::CreateHost(app::CreateHostState::new())).await
         }
-        Some(Commands::Update) => run_tui(AppMode::Update(app::UpdateState::new())).await,
+        Some(Commands::Update {
+            override_input,
+            lint,
+            gc,
+            optimise,
+            max_jobs,
+            cores,
+            preview,
+            activation,
+            headless,
+            install_timer,
+            timer_status,
+            report,
+            propose,
+        }) => {
+            let overrides = commands::update::flake::InputOverride::from_args(&override_input)?;
+            let options = commands::update::UpdateOptions {
+                lint,
+                gc,
+                optimise,
+                preview,
+                activation,
+                parallelism: system::parallelism::BuildParallelism { max_jobs, cores },
+                ..Default::default()
+            };
+            if timer_status {
+                commands::update::timer::print_timer_status().await
+            } else if propose {
+                commands::update::propose::propose().await
+            } else if let Some(schedule) = install_timer {
+                commands::update::timer::install_timer(schedule, options).await
+            } else if headless {
+                commands::update::timer::run_headless(options, report).await
+            } else {
+                let state = app::UpdateState::with_options(overrides, options).with_report(report);
+                run_tui(AppMode::Update(state)).await
+            }
+        }
         Some(Commands::Apps { action }) => match action {
             Some(AppsAction::Backup { force }) => {
                 run_tui(AppMode::Apps(app::AppProfileState::new_backup(force))).await
@@ -136,6 +477,9 @@ async fn main() -> Result<()> {
             Some(AppsAction::Status) => {
                 run_tui(AppMode::Apps(app::AppProfileState::new_status())).await
             }
+            Some(AppsAction::Init { repo, no_backup }) => {
+                run_tui(AppMode::Apps(app::AppProfileState::new_init(repo, !no_backup))).await
+            }
             None => run_tui(AppMode::Apps(app::AppProfileState::new_menu())).await,
         },
         Some(Commands::Keys { action }) => match action {
@@ -151,28 +495,123 @@ async fn main() -> Result<()> {
             KeysAction::Status => {
                 run_tui(AppMode::Keys(app::KeysState::new_status())).await
             }
+            KeysAction::Github { deploy } => {
+                run_tui(AppMode::Keys(app::KeysState::new_github(deploy))).await
+            }
+        },
+        Some(Commands::Fleet { action }) => match action {
+            FleetAction::Update(args) => deploy(args).await,
+            FleetAction::Status => run_tui(AppMode::Fleet(app::FleetState::new_status())).await,
         },
+        Some(Commands::Deploy(args)) => deploy(args).await,
+        Some(Commands::Host {
+            action: HostAction::Archive { name, dry_run, yes },
+        }) => commands::host_archive::archive_host(&name, dry_run, yes).await,
+        Some(Commands::Run { name }) => {
+            // Evaluation errors are reported before the TUI starts
+            let pipelines = system::pipeline::load_pipelines(&constants::nixos_config_dir())?;
+            let mode = match name {
+                Some(name) => {
+                    let Some(pipeline) = pipelines.iter().find(|p| p.name == name).cloned() else {
+                        let known: Vec<&str> = pipelines.iter().map(|p| p.name.as_str()).collect();
+                        anyhow::bail!("Unknown pipeline '{}' (available: {})", name, known.join(", "));
+                    };
+                    app::PipelineState::new_running(pipeline)
+                }
+                None => app::PipelineState::new_menu(pipelines),
+            };
+            run_tui(AppMode::Pipeline(mode)).await
+        }
+        Some(Commands::Shells) => run_tui(AppMode::DevShells(app::DevShellState::new())).await,
+        Some(Commands::Registry) => run_tui(AppMode::Registry(app::RegistryState::new())).await,
+        Some(Commands::Channels) => run_tui(AppMode::Channels(app::ChannelsState::new())).await,
+        Some(Commands::Gc { action: None }) => run_tui(AppMode::Gc(app::GcState::new())).await,
+        Some(Commands::Gc {
+            action: Some(GcAction::Run { older_than }),
+        }) => run_tui(AppMode::Optimise(app::OptimiseState::with_gc(older_than))).await,
+        Some(Commands::NixConf) => run_tui(AppMode::NixConf(app::NixConfState::new())).await,
+        Some(Commands::Parallelism) => {
+            run_tui(AppMode::Parallelism(app::ParallelismState::new())).await
+        }
+        Some(Commands::Optimise) => run_tui(AppMode::Optimise(app::OptimiseState::new())).await,
+        Some(Commands::Rollback) => run_tui(AppMode::Rollback(app::RollbackState::new())).await,
+        Some(Commands::EnableService {
+            service,
+            disable,
+            host,
+            yes,
+        }) => commands::services::enable_service(service, disable, host, yes).await,
+        Some(Commands::Status { waybar }) => commands::status::print_status(waybar).await,
+        Some(Commands::Services) => run_tui(AppMode::Services(app::ServicesState::new())).await,
+        Some(Commands::FixPerms { dry_run, yes }) => commands::perms::fix_perms(dry_run, yes).await,
+        Some(Commands::Bisect {
+            good,
+            bad,
+            input,
+            mode,
+        }) => {
+            let mode = system::bisect::BisectMode::ALL
+                .into_iter()
+                .find(|m| m.action() == mode)
+                .unwrap_or_default();
+            run_tui(AppMode::Bisect(app::BisectState::new(input, good, bad, mode))).await
+        }
+        // Runs from a timer, so no TUI
+        Some(Commands::ReportState) => commands::report::report_state().await,
+        Some(Commands::Bootstrap) => {
+            run_tui(AppMode::Bootstrap(app::BootstrapState::new())).await
+        }
         None => run_tui(AppMode::MainMenu { selected: 0 }).await,
     }
 }
 
+/// `forge deploy` and `forge fleet update`
+async fn deploy(args: DeployArgs) -> Result<()> {
+    // Config errors are reported before the TUI starts
+    let fleet = system::fleet::FleetConfig::load_or_flake(&constants::nixos_config_dir())?;
+    let mut plan = system::fleet::plan_rollout(&fleet, &args.hosts, args.parallel)?;
+    if args.ignore_window {
+        plan = plan.without_windows();
+    }
+    if let Some(build) = args.build_on {
+        plan = plan.built_on(build);
+    }
+    run_tui(AppMode::Fleet(app::FleetState::new(plan))).await
+}
+
 async fn run_tui(initial_mode: AppMode) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
+    let recording = if RECORD_SESSION.load(Ordering::Relaxed) {
+        start_recording()
+    } else {
+        None
+    };
+    let recording_path = recording.as_ref().map(|(path, _)| path.clone());
+    let backend = CrosstermBackend::new(TuiOutput::new(recording.map(|(_, recorder)| recorder)));
     let mut terminal = Terminal::new(backend)?;
 
     // Create app state
     let mut app = App::new(initial_mode);
+    app.recording_path = recording_path;
 
-    // Create command channel
-    let (cmd_tx, mut cmd_rx) = mpsc::channel::<CommandMessage>(constants::COMMAND_CHANNEL_SIZE);
+    // Create command output and event channels
+    let (cmd_tx, mut cmd_rx) = commands::command_channel();
     app.set_command_sender(cmd_tx);
 
     // Run the app
-    let result = run_app(&mut terminal, &mut app, &mut cmd_rx).await;
+    // Progress and pending updates for desktop bars (`forge status`)
+    let ipc = match IpcServer::start().await {
+        Ok(server) => server,
+        Err(e) => {
+            tracing::warn!("Status socket unavailable: {:#}", e);
+            None
+        }
+    };
+
+    let result = run_app(&mut terminal, &mut app, &mut cmd_rx, ipc.as_ref()).await;
 
     // Restore terminal
     disable_raw_mode()?;
@@ -185,6 +624,9 @@ async fn run_tui(initial_mode: AppMode) -> Result<()> {
 
     // Print log path
     println!("Screen log: {}", app.screen_log_path.display());
+    if let Some(path) = &app.recording_path {
+        println!("Recording: {} (replay with asciinema play)", path.display());
+    }
 
     if let Err(err) = result {
         eprintln!("Error: {err:?}");
@@ -194,10 +636,50 @@ async fn run_tui(initial_mode: AppMode) -> Result<()> {
     Ok(())
 }
 
+/// Open a cast file for this session; the TUI runs unrecorded if that fails
+fn start_recording() -> Option<(std::path::PathBuf, CastRecorder<io::BufWriter<std::fs::File>>)> {
+    let (width, height) = crossterm::terminal::size().unwrap_or((80, 24));
+    let path = recording_path(&chrono::Local::now().format("%Y%m%d-%H%M%S").to_string());
+    match CastRecorder::create(&path, width, height) {
+        Ok(recorder) => Some((path, recorder)),
+        Err(e) => {
+            tracing::warn!("Failed to start recording {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Run `request` on the plain terminal, then bring the TUI back
+async fn run_suspended(
+    terminal: &mut Tui,
+    request: &ShellRequest,
+) -> Result<io::Result<std::process::ExitStatus>> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    terminal.show_cursor()?;
+    for line in &request.banner {
+        println!("{}", line);
+    }
+    println!();
+
+    let mut command = tokio::process::Command::new(&request.program);
+    command.args(&request.args);
+    if let Some(dir) = &request.dir {
+        command.current_dir(dir);
+    }
+    let result = command.status().await;
+
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+    Ok(result)
+}
+
 async fn run_app(
-    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    terminal: &mut Tui,
     app: &mut App,
-    cmd_rx: &mut mpsc::Receiver<CommandMessage>,
+    cmd_rx: &mut CommandReceiver,
+    ipc: Option<&IpcServer>,
 ) -> Result<()> {
     // Start any initial commands based on mode
     app.start_initial_command().await?;
@@ -205,34 +687,82 @@ async fn run_app(
     // Create async event stream for responsive input
     let mut event_stream = EventStream::new();
 
+    let frame_interval = Duration::from_millis(constants::MIN_FRAME_INTERVAL_MS);
+    let poll_timeout = Duration::from_millis(constants::EVENT_POLL_TIMEOUT_MS);
+    // Redraw only when something changed, and at most once per frame interval
+    let mut dirty = true;
+    let mut last_draw: Option<Instant> = None;
+
     loop {
-        // Draw UI
-        terminal.draw(|frame| ui::draw(frame, app))?;
+        let since_draw = last_draw.map_or(frame_interval, |at| at.elapsed());
+        if dirty && since_draw >= frame_interval {
+            app.begin_frame();
+            terminal.draw(|frame| ui::draw(frame, app))?;
+            last_draw = Some(Instant::now());
+            dirty = false;
+        }
 
-        // Handle all events with proper async - no blocking delays
-        let timeout = Duration::from_millis(constants::EVENT_POLL_TIMEOUT_MS);
+        // A pending redraw wakes the loop at the end of the frame interval
+        let timeout = if dirty {
+            frame_interval.saturating_sub(since_draw)
+        } else {
+            poll_timeout
+        };
 
         tokio::select! {
-            biased;  // Prioritize in order: keys, commands, timeout
+            biased;  // Prioritize in order: keys, command events and output, timeout
 
-            // Terminal key events (instant response)
+            // Terminal events: keys (instant response) and resizes
             Some(Ok(event)) = event_stream.next() => {
                 if let Event::Key(key) = event {
                     if key.kind == KeyEventKind::Press {
                         app.handle_key(key.code).await?;
+                        app.sync_host_draft();
                     }
                 }
+                dirty = true;
             }
-            // Command output from async tasks
-            Some(msg) = cmd_rx.recv() => {
-                app.handle_command_message(msg).await?;
+            // Step transitions, progress and Done ahead of any output backlog;
+            // output comes everything queued at once for a single redraw
+            Some(received) = cmd_rx.recv() => {
+                for line in received.lines {
+                    app.handle_output_line(line);
+                }
+                if let Some(msg) = received.event {
+                    app.handle_command_message(msg).await?;
+                }
+                dirty = true;
             }
-            // Timeout for spinner animation and redraw
+            // Timeout for spinner animation and pending redraws
             _ = tokio::time::sleep(timeout) => {}
         }
 
+        // A program the last key asked for, e.g. the install's rescue shell
+        if let Some(request) = app.shell_request.take() {
+            // The event stream would otherwise read the program's input
+            drop(event_stream);
+            let result = run_suspended(terminal, &request).await?;
+            app.shell_finished(&request.program, result);
+            event_stream = EventStream::new();
+            dirty = true;
+        }
+
         // Update spinner animation
-        app.tick();
+        if app.tick() {
+            dirty = true;
+        }
+
+        // Kick off host discovery once a screen needs it
+        if app.ensure_hosts_discovered().await? {
+            dirty = true;
+        }
+
+        // Periodic update checks and git status, when due
+        app.run_background_tasks().await?;
+
+        if let Some(ipc) = ipc {
+            ipc.publish(app.ipc_status());
+        }
 
         if app.should_quit {
             break;
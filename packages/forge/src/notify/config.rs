@@ -0,0 +1,182 @@
+//! User settings for update checks
+//!
+//! Loaded from `~/.config/forge/notify.toml`. Every key is optional and falls
+//! back to the built-in defaults, so a missing or partial file is fine:
+//!
+//! ```toml
+//! [apps]
+//! enabled = false        # skip the app profile fetch entirely
+//!
+//! [flake]
+//! timeout_secs = 30      # per-check fetch timeout
+//! interval_mins = 360    # background checker only runs this every 6 hours
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::paths::notify_config_path;
+
+/// The individual update checks that can be configured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckKind {
+    /// nixos-config repo commits
+    Config,
+    /// App profile (private-settings) repo
+    Apps,
+    /// Flake input revisions
+    Flake,
+}
+
+impl CheckKind {
+    /// All checks, in the order they are reported
+    pub const ALL: [CheckKind; 3] = [CheckKind::Config, CheckKind::Apps, CheckKind::Flake];
+}
+
+/// Settings for a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CheckSettings {
+    /// Whether the check runs at all
+    pub enabled: bool,
+    /// Fetch timeout in seconds (None = built-in default)
+    pub timeout_secs: Option<u64>,
+    /// Minimum minutes between background runs (0 = every run)
+    pub interval_mins: u64,
+}
+
+impl Default for CheckSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout_secs: None,
+            interval_mins: 0,
+        }
+    }
+}
+
+impl CheckSettings {
+    /// Configured timeout, or `default` when none is set
+    pub fn timeout(&self, default: Duration) -> Duration {
+        self.timeout_secs.map(Duration::from_secs).unwrap_or(default)
+    }
+
+    /// Whether the check should run now given when it last ran
+    pub fn is_due(&self, last_run: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        match last_run {
+            Some(last) if self.interval_mins > 0 => {
+                now.signed_duration_since(last) >= chrono::Duration::minutes(self.interval_mins as i64)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Settings for all update checks
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotifyConfig {
+    pub config: CheckSettings,
+    pub apps: CheckSettings,
+    pub flake: CheckSettings,
+}
+
+impl NotifyConfig {
+    /// Load settings from disk, falling back to defaults if missing or invalid
+    pub fn load() -> Self {
+        let path = notify_config_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::warn!("Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Settings for a specific check
+    pub fn get(&self, kind: CheckKind) -> &CheckSettings {
+        match kind {
+            CheckKind::Config => &self.config,
+            CheckKind::Apps => &self.apps,
+            CheckKind::Flake => &self.flake,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_config_uses_defaults() {
+        let config: NotifyConfig = toml::from_str("").unwrap();
+        assert_eq!(config, NotifyConfig::default());
+        for kind in CheckKind::ALL {
+            assert!(config.get(kind).enabled);
+        }
+    }
+
+    #[test]
+    fn test_partial_config() {
+        let config: NotifyConfig = toml::from_str(
+            r#"
+            [apps]
+            enabled = false
+
+            [flake]
+            timeout_secs = 30
+            interval_mins = 360
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.config.enabled);
+        assert!(!config.apps.enabled);
+        assert_eq!(config.flake.timeout_secs, Some(30));
+        assert_eq!(config.flake.interval_mins, 360);
+    }
+
+    #[test]
+    fn test_timeout_fallback() {
+        let default = Duration::from_secs(10);
+        assert_eq!(CheckSettings::default().timeout(default), default);
+
+        let settings = CheckSettings {
+            timeout_secs: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(settings.timeout(default), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_is_due() {
+        let now = Utc::now();
+        let hourly = CheckSettings {
+            interval_mins: 60,
+            ..Default::default()
+        };
+
+        assert!(hourly.is_due(None, now));
+        assert!(!hourly.is_due(Some(now - chrono::Duration::minutes(30)), now));
+        assert!(hourly.is_due(Some(now - chrono::Duration::minutes(60)), now));
+
+        // No interval means every run
+        assert!(CheckSettings::default().is_due(Some(now), now));
+
+        let disabled = CheckSettings {
+            enabled: false,
+            ..Default::default()
+        };
+        assert!(!disabled.is_due(None, now));
+    }
+}
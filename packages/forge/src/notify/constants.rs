@@ -17,6 +17,12 @@ pub const FLAKE_CHECK_TIMEOUT_SECS: u64 = 15;
 /// Timeout for HTTP client requests (seconds)
 pub const HTTP_CLIENT_TIMEOUT_SECS: u64 = 10;
 
+/// Timeout for a single `git ls-remote` call (seconds)
+pub const LS_REMOTE_TIMEOUT_SECS: u64 = 8;
+
+/// How long cached per-input flake check results are reused (seconds)
+pub const FLAKE_CACHE_TTL_SECS: u64 = 600;
+
 /// Duration for desktop notification display (milliseconds)
 pub const NOTIFICATION_TIMEOUT_MS: i32 = 10000;
 
@@ -39,13 +45,17 @@ pub fn http_client_timeout() -> Duration {
     Duration::from_secs(HTTP_CLIENT_TIMEOUT_SECS)
 }
 
+/// Get flake check cache lifetime as Duration
+pub fn flake_cache_ttl() -> Duration {
+    Duration::from_secs(FLAKE_CACHE_TTL_SECS)
+}
+
 // =============================================================================
 // Priority Inputs
 // =============================================================================
 
-/// Priority inputs to check for updates (in order of importance)
-/// We only check these to minimize API calls and avoid GitHub rate limiting.
-/// The GitHub API has a limit of 60 requests/hour for unauthenticated requests.
+/// Priority inputs, reported first (in order of importance)
+/// Other direct inputs are still checked, but listed after these.
 pub const PRIORITY_INPUTS: &[&str] = &["nixpkgs"];
 
 // =============================================================================
@@ -70,6 +80,7 @@ mod tests {
         assert_eq!(git_fetch_timeout(), Duration::from_secs(10));
         assert_eq!(flake_check_timeout(), Duration::from_secs(15));
         assert_eq!(http_client_timeout(), Duration::from_secs(10));
+        assert_eq!(flake_cache_ttl(), Duration::from_secs(600));
     }
 
     #[test]
@@ -93,6 +104,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn test_notification_timeout_is_positive() {
         assert!(NOTIFICATION_TIMEOUT_MS > 0);
     }
@@ -1,26 +1,43 @@
 //! Flake input update detection
 //!
-//! Checks if any flake inputs have newer versions available on GitHub.
-//! To minimize API calls (and avoid rate limiting), only checks nixpkgs
-//! by default since that's where most updates come from.
+//! Resolves each direct flake input's branch with `git ls-remote` against its
+//! locked URL, so no clones are fetched and nothing is written to the network.
+//! For GitHub inputs that are behind, the compare API reports how many commits
+//! behind they are. Results are cached per input so repeated checks (e.g. on
+//! every launch) are cheap.
 
 use anyhow::Result;
-use serde::Deserialize;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::{timeout_at, Instant};
 
 use super::constants::{
-    default_branch_for_repo, flake_check_timeout, http_client_timeout, PRIORITY_INPUTS,
+    default_branch_for_repo, flake_cache_ttl, flake_check_timeout, http_client_timeout,
+    LS_REMOTE_TIMEOUT_SECS, PRIORITY_INPUTS,
 };
-use super::paths::nixos_config_dir;
+use super::paths::{flake_cache_path, nixos_config_dir};
 
 /// Flake.lock JSON structure
 #[derive(Debug, Deserialize)]
 struct FlakeLock {
     nodes: HashMap<String, FlakeNode>,
+    #[serde(default = "default_root")]
+    root: String,
+}
+
+fn default_root() -> String {
+    "root".to_string()
 }
 
 #[derive(Debug, Deserialize)]
 struct FlakeNode {
+    /// Input name -> node key (or a `follows` path, which we ignore)
+    #[serde(default)]
+    inputs: HashMap<String, serde_json::Value>,
     locked: Option<LockedInfo>,
     original: Option<OriginalInfo>,
 }
@@ -30,6 +47,9 @@ struct LockedInfo {
     owner: Option<String>,
     repo: Option<String>,
     rev: Option<String>,
+    url: Option<String>,
+    #[serde(rename = "ref")]
+    git_ref: Option<String>,
     #[serde(rename = "type")]
     source_type: Option<String>,
 }
@@ -40,16 +60,92 @@ struct OriginalInfo {
     git_ref: Option<String>,
 }
 
-/// Input configurations to check
+/// A remotely resolvable flake input
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct InputConfig {
-    owner: String,
-    repo: String,
+    /// Clone URL passed to `git ls-remote`
+    url: String,
+    /// Branch (or ref) to resolve; `HEAD` when unknown
     branch: String,
     current_rev: String,
+    /// (owner, repo) for GitHub inputs, used to count commits behind
+    github: Option<(String, String)>,
+}
+
+/// A flake input whose upstream branch has moved past the locked revision
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlakeInputUpdate {
+    pub name: String,
+    /// Revision the branch currently points at
+    pub remote_rev: String,
+    /// Number of commits the locked revision is behind (GitHub inputs only)
+    pub commits_behind: Option<u64>,
+}
+
+impl FlakeInputUpdate {
+    /// Stable identifier used for notification deduplication (`name@rev`)
+    pub fn key(&self) -> String {
+        format!("{}@{}", self.name, &self.remote_rev[..7.min(self.remote_rev.len())])
+    }
+
+    /// Human-readable description, e.g. `nixpkgs (42 commits behind)`
+    pub fn describe(&self) -> String {
+        match self.commits_behind {
+            Some(1) => format!("{} (1 commit behind)", self.name),
+            Some(n) => format!("{} ({} commits behind)", self.name, n),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// Cached result for a single input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedInput {
+    locked_rev: String,
+    remote_rev: String,
+    commits_behind: Option<u64>,
+    checked_at: DateTime<Utc>,
+}
+
+/// Per-input cache stored in the forge data directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FlakeCheckCache {
+    inputs: HashMap<String, CachedInput>,
+}
+
+impl FlakeCheckCache {
+    fn load() -> Self {
+        std::fs::read_to_string(flake_cache_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = flake_cache_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Cached entry for an input if it is still fresh and matches the lock
+    fn fresh(&self, name: &str, locked_rev: &str, now: DateTime<Utc>) -> Option<&CachedInput> {
+        let ttl = chrono::Duration::from_std(flake_cache_ttl()).ok()?;
+        self.inputs
+            .get(name)
+            .filter(|c| c.locked_rev == locked_rev && now.signed_duration_since(c.checked_at) < ttl)
+    }
 }
 
-/// Check for flake input updates (only checks priority inputs to save API calls)
-pub async fn check_flake_updates() -> Result<Vec<String>> {
+/// Check for flake input updates
+///
+/// # Arguments
+/// * `timeout` - Optional custom timeout for the whole check. Uses default if None.
+///   Inputs still unresolved by then are left out; the rest are cached and
+///   reported, so a slow network still makes progress from run to run.
+pub async fn check_flake_updates(timeout: Option<Duration>) -> Result<Vec<FlakeInputUpdate>> {
     let config_dir = nixos_config_dir();
     let lock_path = config_dir.join("flake.lock");
 
@@ -61,149 +157,347 @@ pub async fn check_flake_updates() -> Result<Vec<String>> {
     let content = std::fs::read_to_string(&lock_path)?;
     let lock: FlakeLock = serde_json::from_str(&content)?;
 
-    // Find GitHub inputs to check (only priority inputs)
-    let inputs = extract_priority_inputs(&lock);
-
+    let inputs = extract_root_inputs(&lock);
     if inputs.is_empty() {
         return Ok(vec![]);
     }
 
-    // Check inputs with timeout (typically just 1 API call for nixpkgs)
-    let updates = tokio::time::timeout(
-        flake_check_timeout(),
-        check_inputs_rest(inputs),
-    )
-    .await
-    .unwrap_or_else(|_| Ok(vec![]))?;
-
-    Ok(updates)
+    let deadline = Instant::now() + timeout.unwrap_or_else(flake_check_timeout);
+    check_inputs(inputs, deadline).await
 }
 
-/// Extract only priority GitHub inputs from flake.lock (to minimize API calls)
-fn extract_priority_inputs(lock: &FlakeLock) -> Vec<(String, InputConfig)> {
-    let mut inputs = Vec::new();
-
-    for priority_name in PRIORITY_INPUTS {
-        if let Some(node) = lock.nodes.get(*priority_name) {
-            if let Some(config) = extract_input_config(*priority_name, node) {
-                inputs.push((priority_name.to_string(), config));
-            }
-        }
-    }
-
+/// Extract the direct inputs of the root flake that can be resolved remotely
+///
+/// Priority inputs come first so they are reported first.
+fn extract_root_inputs(lock: &FlakeLock) -> Vec<(String, InputConfig)> {
+    let Some(root) = lock.nodes.get(&lock.root) else {
+        return vec![];
+    };
+
+    let mut inputs: Vec<(String, InputConfig)> = root
+        .inputs
+        .iter()
+        .filter_map(|(name, target)| {
+            // Array values are `follows` paths and have no lock of their own
+            let node = lock.nodes.get(target.as_str()?)?;
+            Some((name.clone(), extract_input_config(node)?))
+        })
+        .collect();
+
+    inputs.sort_by_key(|(name, _)| {
+        let priority = PRIORITY_INPUTS
+            .iter()
+            .position(|p| p == name)
+            .unwrap_or(PRIORITY_INPUTS.len());
+        (priority, name.clone())
+    });
     inputs
 }
 
 /// Extract config for a single input node
-fn extract_input_config(_name: &str, node: &FlakeNode) -> Option<InputConfig> {
+fn extract_input_config(node: &FlakeNode) -> Option<InputConfig> {
     let locked = node.locked.as_ref()?;
-
-    // Only handle GitHub sources
-    if locked.source_type.as_deref() != Some("github") {
-        return None;
-    }
-
-    let owner = locked.owner.as_ref()?;
-    let repo = locked.repo.as_ref()?;
     let rev = locked.rev.as_ref()?;
+    let original_ref = node.original.as_ref().and_then(|o| o.git_ref.clone());
+
+    let (url, github, default_branch) = match locked.source_type.as_deref()? {
+        "github" => {
+            let owner = locked.owner.as_ref()?;
+            let repo = locked.repo.as_ref()?;
+            (
+                format!("https://github.com/{}/{}.git", owner, repo),
+                Some((owner.clone(), repo.clone())),
+                default_branch_for_repo(owner, repo).to_string(),
+            )
+        }
+        "gitlab" => {
+            let owner = locked.owner.as_ref()?;
+            let repo = locked.repo.as_ref()?;
+            (format!("https://gitlab.com/{}/{}.git", owner, repo), None, "HEAD".to_string())
+        }
+        "git" => {
+            let url = locked.url.as_ref()?;
+            // Local repositories can't be behind a remote
+            if url.starts_with("file:") || url.starts_with('/') {
+                return None;
+            }
+            (url.clone(), None, "HEAD".to_string())
+        }
+        _ => return None,
+    };
 
-    // Get the branch from original, with sensible defaults
-    let branch = node
-        .original
-        .as_ref()
-        .and_then(|o| o.git_ref.clone())
-        .unwrap_or_else(|| default_branch_for_repo(owner, repo).to_string());
+    let branch = original_ref
+        .or_else(|| locked.git_ref.clone())
+        .unwrap_or(default_branch);
 
     Some(InputConfig {
-        owner: owner.clone(),
-        repo: repo.clone(),
+        url,
         branch,
         current_rev: rev.clone(),
+        github,
     })
 }
 
-/// Extract all GitHub inputs from flake.lock (for potential future use)
-#[allow(dead_code)]
-fn extract_all_github_inputs(lock: &FlakeLock) -> Vec<(String, InputConfig)> {
-    let mut inputs = Vec::new();
+/// Resolve every input concurrently, using and refreshing the cache
+///
+/// Inputs not resolved by `deadline` are dropped from this run's results.
+async fn check_inputs(
+    inputs: Vec<(String, InputConfig)>,
+    deadline: Instant,
+) -> Result<Vec<FlakeInputUpdate>> {
+    let mut cache = FlakeCheckCache::load();
+    let now = Utc::now();
 
-    for (name, node) in &lock.nodes {
-        if name == "root" {
-            continue;
-        }
-        if let Some(config) = extract_input_config(name, node) {
-            inputs.push((name.clone(), config));
-        }
-    }
-
-    inputs
-}
-
-/// Check inputs using REST API
-async fn check_inputs_rest(inputs: Vec<(String, InputConfig)>) -> Result<Vec<String>> {
     let client = reqwest::Client::builder()
         .user_agent("forge-notify")
         .timeout(http_client_timeout())
         .build()?;
 
-    let mut updates = Vec::new();
     let mut handles = Vec::new();
-
     for (name, config) in inputs {
+        if let Some(cached) = cache.fresh(&name, &config.current_rev, now) {
+            handles.push((name, config, None, Some(cached.clone())));
+            continue;
+        }
+        let previous = cache.inputs.get(&name).cloned();
         let client = client.clone();
+        let task_config = config.clone();
         let handle = tokio::spawn(async move {
-            match check_single_input_rest(&client, &config).await {
-                Ok(true) => Some(name),
-                _ => None,
-            }
+            resolve_input(&client, &task_config, previous.as_ref()).await
         });
-        handles.push(handle);
+        handles.push((name, config, Some(handle), None));
     }
 
-    for handle in handles {
-        if let Ok(Some(name)) = handle.await {
-            updates.push(name);
+    let mut updates = Vec::new();
+    for (name, config, handle, cached) in handles {
+        let entry = match (handle, cached) {
+            (Some(mut handle), _) => match timeout_at(deadline, &mut handle).await {
+                Ok(Ok(Ok(entry))) => {
+                    cache.inputs.insert(name.clone(), entry.clone());
+                    entry
+                }
+                Ok(Ok(Err(e))) => {
+                    tracing::debug!("Failed to check flake input {}: {}", name, e);
+                    continue;
+                }
+                Ok(Err(_)) => continue,
+                Err(_) => {
+                    tracing::debug!("Flake input {} not resolved before the deadline", name);
+                    handle.abort();
+                    continue;
+                }
+            },
+            (None, Some(cached)) => cached,
+            (None, None) => continue,
+        };
+
+        if entry.remote_rev != config.current_rev {
+            updates.push(FlakeInputUpdate {
+                name,
+                remote_rev: entry.remote_rev,
+                commits_behind: entry.commits_behind,
+            });
         }
     }
 
+    if let Err(e) = cache.save() {
+        tracing::debug!("Failed to save flake check cache: {}", e);
+    }
+
     Ok(updates)
 }
 
-/// Check a single input using REST API
-async fn check_single_input_rest(client: &reqwest::Client, config: &InputConfig) -> Result<bool> {
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/branches/{}",
-        config.owner, config.repo, config.branch
-    );
-
-    let response = client.get(&url).send().await?;
+/// Resolve a single input's remote revision and how far behind it is
+async fn resolve_input(
+    client: &reqwest::Client,
+    config: &InputConfig,
+    previous: Option<&CachedInput>,
+) -> Result<CachedInput> {
+    let remote_rev = ls_remote(&config.url, &config.branch).await?;
+
+    let commits_behind = if remote_rev == config.current_rev {
+        Some(0)
+    } else if let Some(prev) = previous.filter(|p| {
+        p.locked_rev == config.current_rev && p.remote_rev == remote_rev
+    }) {
+        // Same pair as last time; don't spend another API call
+        prev.commits_behind
+    } else if let Some((owner, repo)) = &config.github {
+        count_commits_behind(client, owner, repo, &config.current_rev, &remote_rev)
+            .await
+            .ok()
+    } else {
+        None
+    };
+
+    Ok(CachedInput {
+        locked_rev: config.current_rev.clone(),
+        remote_rev,
+        commits_behind,
+        checked_at: Utc::now(),
+    })
+}
 
-    if !response.status().is_success() {
-        return Ok(false);
+/// Resolve a ref on a remote without cloning or fetching
+async fn ls_remote(url: &str, branch: &str) -> Result<String> {
+    let child = Command::new("git")
+        .args(["ls-remote", url, branch])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+
+    let output = tokio::time::timeout(Duration::from_secs(LS_REMOTE_TIMEOUT_SECS), child).await??;
+    if !output.status.success() {
+        anyhow::bail!("git ls-remote {} failed", url);
     }
 
+    parse_ls_remote(&String::from_utf8_lossy(&output.stdout), branch)
+        .ok_or_else(|| anyhow::anyhow!("ref {} not found on {}", branch, url))
+}
+
+/// Pick the revision for `branch` out of `git ls-remote` output
+///
+/// Prefers an exact branch match over tags or other refs sharing the suffix.
+fn parse_ls_remote(output: &str, branch: &str) -> Option<String> {
+    let refs: Vec<(&str, &str)> = output
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .collect();
+
+    let wanted = [
+        branch.to_string(),
+        format!("refs/heads/{}", branch),
+        format!("refs/tags/{}", branch),
+    ];
+
+    wanted
+        .iter()
+        .find_map(|w| refs.iter().find(|(_, r)| r == w))
+        .or_else(|| refs.first())
+        .map(|(rev, _)| rev.to_string())
+}
+
+/// Count commits between two revisions using the GitHub compare API
+async fn count_commits_behind(
+    client: &reqwest::Client,
+    owner: &str,
+    repo: &str,
+    base: &str,
+    head: &str,
+) -> Result<u64> {
     #[derive(Deserialize)]
-    struct BranchResponse {
-        commit: CommitRef,
+    struct CompareResponse {
+        ahead_by: u64,
     }
 
-    #[derive(Deserialize)]
-    struct CommitRef {
-        sha: String,
+    let url = format!(
+        "https://api.github.com/repos/{}/{}/compare/{}...{}",
+        owner, repo, base, head
+    );
+    let response = client.get(&url).send().await?;
+
+    if !response.status().is_success() {
+        anyhow::bail!("GitHub API returned {}", response.status());
     }
 
-    let branch_info: BranchResponse = response.json().await?;
-    Ok(branch_info.commit.sha != config.current_rev)
+    let compare: CompareResponse = response.json().await?;
+    Ok(compare.ahead_by)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const LOCK: &str = r#"{
+        "nodes": {
+            "home-manager": {
+                "inputs": { "nixpkgs": ["nixpkgs"] },
+                "locked": { "owner": "nix-community", "repo": "home-manager", "rev": "aaa", "type": "github" },
+                "original": { "owner": "nix-community", "repo": "home-manager", "type": "github" }
+            },
+            "nixpkgs": {
+                "locked": { "owner": "NixOS", "repo": "nixpkgs", "rev": "bbb", "type": "github" },
+                "original": { "owner": "NixOS", "ref": "nixos-unstable", "repo": "nixpkgs", "type": "github" }
+            },
+            "local": {
+                "locked": { "url": "file:///home/user/src", "rev": "ccc", "type": "git" }
+            },
+            "root": {
+                "inputs": { "home-manager": "home-manager", "nixpkgs": "nixpkgs", "local": "local" }
+            }
+        },
+        "root": "root",
+        "version": 7
+    }"#;
+
     #[test]
     fn test_priority_inputs_contains_nixpkgs() {
         assert!(PRIORITY_INPUTS.contains(&"nixpkgs"));
     }
 
-    // Note: default_branch_for_repo tests are now in constants.rs
+    #[test]
+    fn test_extract_root_inputs() {
+        let lock: FlakeLock = serde_json::from_str(LOCK).unwrap();
+        let inputs = extract_root_inputs(&lock);
+        let names: Vec<&str> = inputs.iter().map(|(n, _)| n.as_str()).collect();
+
+        // nixpkgs first (priority), local file input skipped
+        assert_eq!(names, vec!["nixpkgs", "home-manager"]);
+
+        let nixpkgs = &inputs[0].1;
+        assert_eq!(nixpkgs.url, "https://github.com/NixOS/nixpkgs.git");
+        assert_eq!(nixpkgs.branch, "nixos-unstable");
+        assert_eq!(nixpkgs.current_rev, "bbb");
+
+        // No ref in original: falls back to the well-known default branch
+        assert_eq!(inputs[1].1.branch, "master");
+    }
+
+    #[test]
+    fn test_parse_ls_remote_prefers_branch() {
+        let output = "111\trefs/tags/nixos-unstable\n222\trefs/heads/nixos-unstable\n";
+        assert_eq!(parse_ls_remote(output, "nixos-unstable"), Some("222".to_string()));
+    }
+
+    #[test]
+    fn test_parse_ls_remote_head() {
+        let output = "333\tHEAD\n";
+        assert_eq!(parse_ls_remote(output, "HEAD"), Some("333".to_string()));
+        assert_eq!(parse_ls_remote("", "HEAD"), None);
+    }
+
+    #[test]
+    fn test_update_key_and_description() {
+        let update = FlakeInputUpdate {
+            name: "nixpkgs".to_string(),
+            remote_rev: "0123456789abcdef".to_string(),
+            commits_behind: Some(42),
+        };
+        assert_eq!(update.key(), "nixpkgs@0123456");
+        assert_eq!(update.describe(), "nixpkgs (42 commits behind)");
+    }
+
+    #[test]
+    fn test_cache_freshness() {
+        let now = Utc::now();
+        let mut cache = FlakeCheckCache::default();
+        cache.inputs.insert(
+            "nixpkgs".to_string(),
+            CachedInput {
+                locked_rev: "bbb".to_string(),
+                remote_rev: "ddd".to_string(),
+                commits_behind: Some(3),
+                checked_at: now,
+            },
+        );
+
+        assert!(cache.fresh("nixpkgs", "bbb", now).is_some());
+        // Lock changed since the cached check
+        assert!(cache.fresh("nixpkgs", "eee", now).is_none());
+        // Expired
+        let later = now + chrono::Duration::from_std(flake_cache_ttl()).unwrap();
+        assert!(cache.fresh("nixpkgs", "bbb", later).is_none());
+    }
 }
@@ -6,12 +6,18 @@
 //! - Flake input updates (nixpkgs, home-manager, etc.)
 
 pub mod checks;
+pub mod config;
 pub mod constants;
 pub mod flake;
 pub mod paths;
 pub mod state;
 
 use anyhow::Result;
+use chrono::Utc;
+
+use config::{CheckKind, NotifyConfig};
+use constants::{flake_check_timeout, git_fetch_timeout};
+use state::CheckTimes;
 
 /// Status of all update checks
 #[derive(Debug, Default)]
@@ -21,10 +27,19 @@ pub struct UpdateStatus {
     /// Whether app profiles have updates
     pub app_updates: bool,
     /// Flake inputs that have updates available
-    pub flake_updates: Vec<String>,
+    pub flake_updates: Vec<flake::FlakeInputUpdate>,
+    /// Checks that did not run (disabled or not yet due)
+    pub skipped: Vec<CheckKind>,
+    /// Checks that errored or timed out; their results are unknown, not empty
+    pub failed: Vec<CheckKind>,
 }
 
 impl UpdateStatus {
+    /// Whether `kind` ran to completion this pass, so its result can be trusted
+    pub fn ran(&self, kind: CheckKind) -> bool {
+        !self.skipped.contains(&kind) && !self.failed.contains(&kind)
+    }
+
     /// Returns true if any updates are available
     pub fn has_updates(&self) -> bool {
         !self.config_updates.is_empty() || self.app_updates || !self.flake_updates.is_empty()
@@ -48,7 +63,8 @@ impl UpdateStatus {
         }
 
         if !self.flake_updates.is_empty() {
-            let names = self.flake_updates.join(", ");
+            let names: Vec<String> = self.flake_updates.iter().map(|u| u.describe()).collect();
+            let names = names.join(", ");
             lines.push(format!("- Flake inputs: {}", names));
         }
 
@@ -56,17 +72,78 @@ impl UpdateStatus {
     }
 }
 
-/// Run all update checks concurrently
-pub async fn check_all_updates() -> Result<UpdateStatus> {
+/// Run all enabled and due update checks concurrently
+///
+/// Checks that are disabled in `config`, or whose interval has not elapsed since
+/// `last_run`, are skipped and listed in `UpdateStatus::skipped`.
+pub async fn check_all_updates(config: &NotifyConfig, last_run: &CheckTimes) -> Result<UpdateStatus> {
+    let now = Utc::now();
+    let due = |kind: CheckKind| config.get(kind).is_due(last_run.get(kind), now);
+    let (run_config, run_apps, run_flake) =
+        (due(CheckKind::Config), due(CheckKind::Apps), due(CheckKind::Flake));
+
     let (config_result, apps_result, flake_result) = tokio::join!(
-        checks::check_nixos_config_updates(None),
-        checks::check_app_updates(None),
-        flake::check_flake_updates(),
+        async {
+            if !run_config {
+                return Ok(vec![]);
+            }
+            checks::check_nixos_config_updates(Some(config.config.timeout(git_fetch_timeout())))
+                .await
+        },
+        async {
+            if !run_apps {
+                return Ok(false);
+            }
+            checks::check_app_updates(Some(config.apps.timeout(git_fetch_timeout()))).await
+        },
+        async {
+            if !run_flake {
+                return Ok(vec![]);
+            }
+            flake::check_flake_updates(Some(config.flake.timeout(flake_check_timeout()))).await
+        },
     );
 
+    let skipped = [
+        (CheckKind::Config, run_config),
+        (CheckKind::Apps, run_apps),
+        (CheckKind::Flake, run_flake),
+    ]
+    .into_iter()
+    .filter(|(_, ran)| !ran)
+    .map(|(kind, _)| kind)
+    .collect();
+
+    let mut failed = Vec::new();
     Ok(UpdateStatus {
-        config_updates: config_result.unwrap_or_default(),
-        app_updates: apps_result.unwrap_or(false),
-        flake_updates: flake_result.unwrap_or_default(),
+        config_updates: outcome(CheckKind::Config, config_result, &mut failed),
+        app_updates: outcome(CheckKind::Apps, apps_result, &mut failed),
+        flake_updates: outcome(CheckKind::Flake, flake_result, &mut failed),
+        skipped,
+        failed,
+    })
+}
+
+/// A check's result, or its empty default with the check listed in `failed`
+fn outcome<T: Default>(kind: CheckKind, result: Result<T>, failed: &mut Vec<CheckKind>) -> T {
+    result.unwrap_or_else(|e| {
+        tracing::warn!("{:?} update check failed: {:#}", kind, e);
+        failed.push(kind);
+        T::default()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcome_records_failures() {
+        let mut failed = Vec::new();
+        assert_eq!(outcome(CheckKind::Config, Ok(vec![1]), &mut failed), vec![1]);
+        assert!(failed.is_empty());
+        let timed_out: Result<bool> = Err(anyhow::anyhow!("timed out"));
+        assert!(!outcome(CheckKind::Apps, timed_out, &mut failed));
+        assert_eq!(failed, vec![CheckKind::Apps]);
+    }
+}
@@ -17,6 +17,15 @@ pub const FORGE_LOG_FILE: &str = "forge.log";
 /// Notification state filename
 const NOTIFY_STATE_FILE: &str = "notify-state.json";
 
+/// Flake check cache filename
+const FLAKE_CACHE_FILE: &str = "flake-check-cache.json";
+
+/// Forge config directory relative to home
+const FORGE_CONFIG_SUBDIR: &str = ".config/forge";
+
+/// Update check settings filename
+const NOTIFY_CONFIG_FILE: &str = "notify.toml";
+
 /// App backup data directory relative to home
 const APP_BACKUP_DATA_SUBDIR: &str = ".local/share/app-backup";
 
@@ -67,6 +76,20 @@ pub fn notify_state_path() -> PathBuf {
         .unwrap_or_else(|| PathBuf::from(FALLBACK_NOTIFY_STATE_FILE))
 }
 
+/// Get the per-input flake check cache path
+pub fn flake_cache_path() -> PathBuf {
+    forge_data_dir().join(FLAKE_CACHE_FILE)
+}
+
+/// Get the update check settings file path
+/// Falls back to the forge data directory fallback if home directory is unavailable
+pub fn notify_config_path() -> PathBuf {
+    dirs::home_dir()
+        .map(|h| h.join(FORGE_CONFIG_SUBDIR))
+        .unwrap_or_else(|| PathBuf::from(FALLBACK_FORGE_DATA_DIR))
+        .join(NOTIFY_CONFIG_FILE)
+}
+
 /// Get the NixOS config directory
 /// Checks multiple locations in order of preference:
 /// 1. /etc/nixos (if it contains flake.nix)
@@ -132,6 +155,12 @@ mod tests {
         assert!(path_str.ends_with(".json"), "Notify state should be a JSON file");
     }
 
+    #[test]
+    fn test_notify_config_path_is_toml() {
+        let path = notify_config_path();
+        assert!(path.ends_with(NOTIFY_CONFIG_FILE), "Notify config should be notify.toml");
+    }
+
     #[test]
     fn test_forge_log_path_is_log() {
         let path = forge_log_path();
@@ -154,6 +183,9 @@ mod tests {
         assert!(!FORGE_DATA_SUBDIR.is_empty());
         assert!(!FORGE_LOG_FILE.is_empty());
         assert!(!NOTIFY_STATE_FILE.is_empty());
+        assert!(!FLAKE_CACHE_FILE.is_empty());
+        assert!(!FORGE_CONFIG_SUBDIR.is_empty());
+        assert!(!NOTIFY_CONFIG_FILE.is_empty());
         assert!(!APP_BACKUP_DATA_SUBDIR.is_empty());
         assert!(!NIXOS_CONFIG_HOME_SUBDIR.is_empty());
         assert!(!NIXOS_CONFIG_SYSTEM.is_empty());
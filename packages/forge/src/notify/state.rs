@@ -6,6 +6,7 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::config::CheckKind;
 use super::paths::notify_state_path;
 
 /// State file for tracking notified updates
@@ -16,6 +17,52 @@ pub struct NotifyState {
 
     /// Last notified state to avoid re-notifying
     pub last_notified: NotifiedState,
+
+    /// When each individual check last ran (for per-check intervals)
+    #[serde(default)]
+    pub last_run: CheckTimes,
+
+    /// What the last run of each check found, notified or not (`forge status`)
+    #[serde(default)]
+    pub found: FoundUpdates,
+}
+
+/// Last run time of each check
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CheckTimes {
+    pub config: Option<DateTime<Utc>>,
+    pub apps: Option<DateTime<Utc>>,
+    pub flake: Option<DateTime<Utc>>,
+}
+
+impl CheckTimes {
+    /// Last run time of a specific check
+    pub fn get(&self, kind: CheckKind) -> Option<DateTime<Utc>> {
+        match kind {
+            CheckKind::Config => self.config,
+            CheckKind::Apps => self.apps,
+            CheckKind::Flake => self.flake,
+        }
+    }
+
+    /// Record that a check ran at `at`
+    pub fn set(&mut self, kind: CheckKind, at: DateTime<Utc>) {
+        match kind {
+            CheckKind::Config => self.config = Some(at),
+            CheckKind::Apps => self.apps = Some(at),
+            CheckKind::Flake => self.flake = Some(at),
+        }
+    }
+}
+
+/// Updates the checks last found
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FoundUpdates {
+    /// Commits in the config repo not yet pulled
+    pub config_commits: usize,
+    pub app_updates: bool,
+    /// Outdated flake inputs, e.g. "nixpkgs (42 commits behind)"
+    pub flake_inputs: Vec<String>,
 }
 
 /// What we last notified the user about
@@ -59,7 +106,33 @@ impl NotifyState {
         Ok(())
     }
 
+    /// Record which checks ran in this pass
+    ///
+    /// A failed check keeps its last run time, so it is retried next pass
+    /// rather than after its interval, and keeps what it last found.
+    pub fn mark_checked(&mut self, status: &super::UpdateStatus) {
+        let now = Utc::now();
+        self.last_check = Some(now);
+        for kind in CheckKind::ALL {
+            if !status.ran(kind) {
+                continue;
+            }
+            self.last_run.set(kind, now);
+            match kind {
+                CheckKind::Config => self.found.config_commits = status.config_updates.len(),
+                CheckKind::Apps => self.found.app_updates = status.app_updates,
+                CheckKind::Flake => {
+                    self.found.flake_inputs =
+                        status.flake_updates.iter().map(|u| u.describe()).collect();
+                }
+            }
+        }
+    }
+
     /// Update the notified state based on current updates
+    ///
+    /// Checks that were skipped or failed this pass keep their previous
+    /// notified state.
     pub fn mark_notified(&mut self, status: &super::UpdateStatus) {
         self.last_check = Some(Utc::now());
 
@@ -70,11 +143,15 @@ impl NotifyState {
 
         // Update app notification state - track current status
         // This allows re-notification when updates become available again after being applied
-        self.last_notified.app_updates = status.app_updates;
+        if status.ran(CheckKind::Apps) {
+            self.last_notified.app_updates = status.app_updates;
+        }
 
         // Update flake inputs - track current set
         // If updates are empty, clear the list to allow re-notification for new updates
-        self.last_notified.flake_inputs = status.flake_updates.clone();
+        if status.ran(CheckKind::Flake) {
+            self.last_notified.flake_inputs = status.flake_updates.iter().map(|u| u.key()).collect();
+        }
     }
 
     /// Check if we should notify based on current status vs last notified
@@ -100,7 +177,7 @@ impl NotifyState {
         if !status.flake_updates.is_empty() {
             // Any input not in the last notified set triggers notification
             for input in &status.flake_updates {
-                if !self.last_notified.flake_inputs.contains(input) {
+                if !self.last_notified.flake_inputs.contains(&input.key()) {
                     return true;
                 }
             }
@@ -135,6 +212,8 @@ mod tests {
             config_updates: vec![("abc1234".to_string(), "Test commit".to_string())],
             app_updates: false,
             flake_updates: vec![],
+            skipped: vec![],
+            failed: vec![],
         };
         assert!(state.should_notify(&status));
     }
@@ -148,7 +227,75 @@ mod tests {
             config_updates: vec![("abc1234".to_string(), "Test commit".to_string())],
             app_updates: false,
             flake_updates: vec![],
+            skipped: vec![],
+            failed: vec![],
         };
         assert!(!state.should_notify(&status));
     }
+
+    #[test]
+    fn test_mark_notified_keeps_skipped_checks() {
+        let mut state = NotifyState::default();
+        state.last_notified.app_updates = true;
+        state.last_notified.flake_inputs = vec!["nixpkgs@0123456".to_string()];
+
+        let status = super::super::UpdateStatus {
+            skipped: vec![CheckKind::Apps, CheckKind::Flake],
+            ..Default::default()
+        };
+        state.mark_notified(&status);
+        state.mark_checked(&status);
+
+        assert!(state.last_notified.app_updates);
+        assert_eq!(state.last_notified.flake_inputs, vec!["nixpkgs@0123456".to_string()]);
+        assert!(state.last_run.config.is_some());
+        assert!(state.last_run.apps.is_none());
+        assert!(state.last_run.flake.is_none());
+    }
+
+    #[test]
+    fn test_mark_checked_records_found() {
+        let mut state = NotifyState::default();
+        state.found.app_updates = true;
+
+        let status = super::super::UpdateStatus {
+            config_updates: vec![("abc1234".to_string(), "Test commit".to_string())],
+            skipped: vec![CheckKind::Apps],
+            ..Default::default()
+        };
+        state.mark_checked(&status);
+
+        assert_eq!(state.found.config_commits, 1);
+        assert!(state.found.app_updates);
+        assert!(state.found.flake_inputs.is_empty());
+    }
+
+    #[test]
+    fn test_failed_checks_keep_last_run_and_found() {
+        let earlier = Utc::now() - chrono::Duration::hours(1);
+        let mut state = NotifyState::default();
+        state.last_run.set(CheckKind::Apps, earlier);
+        state.found.app_updates = true;
+        state.last_notified.app_updates = true;
+
+        // The app check timed out; its empty result means nothing
+        let status = super::super::UpdateStatus {
+            failed: vec![CheckKind::Apps],
+            ..Default::default()
+        };
+        state.mark_checked(&status);
+        state.mark_notified(&status);
+
+        assert_eq!(state.last_run.apps, Some(earlier));
+        assert!(state.found.app_updates);
+        assert!(state.last_notified.app_updates);
+        assert!(state.last_run.config.is_some());
+    }
+
+    #[test]
+    fn test_load_state_without_last_run() {
+        let json = r#"{"last_check": null, "last_notified": {"config_commit": null, "app_updates": false, "flake_inputs": []}}"#;
+        let state: NotifyState = serde_json::from_str(json).unwrap();
+        assert!(state.last_run.get(CheckKind::Flake).is_none());
+    }
 }
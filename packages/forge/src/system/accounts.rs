@@ -0,0 +1,266 @@
+//! Extra user accounts and authorized SSH keys added at install time
+//!
+//! Both come from the credentials screen: a list of extra usernames, and key
+//! sources separated by commas, each one of
+//!
+//! ```text
+//! gh:octocat                         keys published at https://github.com/octocat.keys
+//! /run/media/nixos/USB/id_ed25519.pub  a file of public keys (~/ works too)
+//! ssh-ed25519 AAAAC3Nza... me@laptop   a pasted public key
+//! ```
+//!
+//! The keys are authorized for the main user. They end up in the host's
+//! default.nix, together with the extra users and an SSH server to log in to.
+
+use anyhow::{bail, Context, Result};
+use std::path::PathBuf;
+
+use crate::app::state::validate_username;
+use crate::constants::{PRIMARY_USER_GID, PRIMARY_USER_UID};
+
+/// Comment heading the accounts in a host's default.nix
+pub const HOST_CONFIG_MARKER: &str = "# Extra users and SSH keys, given to the installer";
+
+/// Comment heading the main user's ids in a host's default.nix
+pub const PRIMARY_USER_MARKER: &str = "# Main user ids, from the installer settings";
+
+/// Key types accepted in authorized_keys
+const KEY_TYPES: &[&str] = &[
+    "ssh-ed25519",
+    "ssh-rsa",
+    "ecdsa-sha2-nistp256",
+    "ecdsa-sha2-nistp384",
+    "ecdsa-sha2-nistp521",
+    "sk-ssh-ed25519@openssh.com",
+    "sk-ecdsa-sha2-nistp256@openssh.com",
+];
+
+/// Groups the extra users are put in (not wheel: only the main user administers)
+const EXTRA_USER_GROUPS: &[&str] = &["networkmanager", "video"];
+
+/// Where to take authorized keys from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySource {
+    /// GitHub username, whose keys are fetched at install time
+    GitHub(String),
+    File(PathBuf),
+    /// A public key pasted as is
+    Key(String),
+}
+
+impl KeySource {
+    pub fn parse(source: &str) -> Result<Self> {
+        let source = source.trim();
+        if let Some(user) = source.strip_prefix("gh:") {
+            let valid = !user.is_empty()
+                && user.len() <= 39
+                && user.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+            if !valid {
+                bail!("'{}' is not a GitHub username", user);
+            }
+            Ok(KeySource::GitHub(user.to_string()))
+        } else if source.starts_with('/') || source.starts_with("~/") {
+            Ok(KeySource::File(PathBuf::from(source)))
+        } else if is_public_key(source) {
+            Ok(KeySource::Key(source.to_string()))
+        } else {
+            bail!("'{}' is not gh:<user>, a file path or a public key", source);
+        }
+    }
+
+    /// Where the keys are fetched from, for the progress output
+    pub fn describe(&self) -> String {
+        match self {
+            KeySource::GitHub(user) => format!("https://github.com/{}.keys", user),
+            KeySource::File(path) => path.display().to_string(),
+            KeySource::Key(key) => key.split_whitespace().take(3).collect::<Vec<_>>().join(" "),
+        }
+    }
+}
+
+/// Whether `line` is a single OpenSSH public key ("<type> <base64> [comment]")
+pub fn is_public_key(line: &str) -> bool {
+    let mut fields = line.split_whitespace();
+    let (Some(kind), Some(blob)) = (fields.next(), fields.next()) else {
+        return false;
+    };
+    KEY_TYPES.contains(&kind)
+        && blob.len() >= 16
+        && blob.chars().all(|c| c.is_ascii_alphanumeric() || "+/=".contains(c))
+        && !line.contains('"')
+        && !line.contains('\\')
+        && !line.contains("${")
+}
+
+/// The public keys in a file or a `.keys` download, skipping anything else
+pub fn public_keys(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| is_public_key(line))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The main user: the name from the credentials step, the ids from
+/// `system::install_settings`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrimaryUser {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl PrimaryUser {
+    /// `uid:gid`, for chown
+    pub fn owner(&self) -> String {
+        format!("{}:{}", self.uid, self.gid)
+    }
+
+    /// Settings for the host's default.nix pinning the ids, None when they are
+    /// the ones NixOS picks by itself
+    pub fn host_config(&self) -> Option<String> {
+        if self.uid == PRIMARY_USER_UID && self.gid == PRIMARY_USER_GID {
+            return None;
+        }
+        let mut config = format!("\n  {}\n", PRIMARY_USER_MARKER);
+        config.push_str(&format!("  users.users.{}.uid = {};\n", self.name, self.uid));
+        if self.gid != PRIMARY_USER_GID {
+            config.push_str(&format!("  users.groups.{}.gid = {};\n", self.name, self.gid));
+            config.push_str(&format!("  users.users.{}.group = \"{}\";\n", self.name, self.name));
+        }
+        Some(config)
+    }
+}
+
+/// Extra users and key sources from the credentials screen
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtraAccounts {
+    pub users: Vec<String>,
+    pub key_sources: Vec<KeySource>,
+}
+
+impl ExtraAccounts {
+    /// Parse the two inputs: usernames separated by spaces or commas, and key
+    /// sources separated by commas
+    pub fn parse(users: &str, key_sources: &str, main_user: &str) -> Result<Self> {
+        let mut accounts = Self::default();
+        for user in users.split([',', ' ']).filter(|u| !u.is_empty()) {
+            if let Some(err) = validate_username(user) {
+                bail!("Extra user: {}", err);
+            }
+            if user == main_user || accounts.users.iter().any(|u| u == user) {
+                bail!("Extra user '{}' is listed twice", user);
+            }
+            accounts.users.push(user.to_string());
+        }
+        for source in key_sources.split(',').filter(|s| !s.trim().is_empty()) {
+            accounts.key_sources.push(KeySource::parse(source)?);
+        }
+        Ok(accounts)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty() && self.key_sources.is_empty()
+    }
+}
+
+/// Read a key file given on the credentials screen
+pub fn read_key_file(path: &std::path::Path) -> Result<Vec<String>> {
+    let path = match path.strip_prefix("~") {
+        Ok(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        Err(_) => path.to_path_buf(),
+    };
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(public_keys(&text))
+}
+
+/// Settings for the host's default.nix
+///
+/// The SSH server is only turned on when there are keys to log in with, and
+/// then without password logins.
+pub fn host_config(main_user: &str, users: &[String], keys: &[String]) -> String {
+    let list = |items: &[&str]| {
+        items.iter().map(|item| format!("\"{}\"", item)).collect::<Vec<_>>().join(" ")
+    };
+    let mut config = format!("\n  {}\n", HOST_CONFIG_MARKER);
+    for user in users {
+        config.push_str(&format!(
+            "  users.users.{} = {{\n    isNormalUser = true;\n    extraGroups = [ {} ];\n  }};\n",
+            user,
+            list(EXTRA_USER_GROUPS)
+        ));
+    }
+    if !keys.is_empty() {
+        config.push_str(&format!(
+            "  users.users.{}.openssh.authorizedKeys.keys = [\n    {}\n  ];\n",
+            main_user,
+            keys.iter().map(|key| format!("\"{}\"", key)).collect::<Vec<_>>().join("\n    ")
+        ));
+        config.push_str("  services.openssh.enable = true;\n");
+        config.push_str("  services.openssh.settings.PasswordAuthentication = false;\n");
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = concat!(
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIOMqqnkVzrm0SdG6UOoqKLsabgH5C9okWi0dh2l9GKJl",
+        " me@laptop"
+    );
+
+    #[test]
+    fn test_parse() {
+        let sources = format!("gh:octocat, ~/id.pub, {}", KEY);
+        let accounts = ExtraAccounts::parse("alice, bob", &sources, "cor").unwrap();
+        assert_eq!(accounts.users, ["alice", "bob"]);
+        assert_eq!(
+            accounts.key_sources,
+            [
+                KeySource::GitHub("octocat".to_string()),
+                KeySource::File(PathBuf::from("~/id.pub")),
+                KeySource::Key(KEY.to_string()),
+            ]
+        );
+        assert!(ExtraAccounts::parse("", "", "cor").unwrap().is_empty());
+
+        assert!(ExtraAccounts::parse("cor", "", "cor").is_err());
+        assert!(ExtraAccounts::parse("Alice", "", "cor").is_err());
+        assert!(ExtraAccounts::parse("", "gh:", "cor").is_err());
+        assert!(ExtraAccounts::parse("", "ssh-ed25519", "cor").is_err());
+        let injected = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5\" ]; x";
+        assert!(ExtraAccounts::parse("", injected, "cor").is_err());
+    }
+
+    #[test]
+    fn test_public_keys_and_host_config() {
+        let keys = public_keys(&format!("{}\n\n# not a key\n<html>\n", KEY));
+        assert_eq!(keys, [KEY]);
+
+        let config = host_config("cor", &["alice".to_string()], &keys);
+        assert!(config.contains("users.users.alice = {\n    isNormalUser = true;"));
+        let authorized =
+            format!("users.users.cor.openssh.authorizedKeys.keys = [\n    \"{}\"", KEY);
+        assert!(config.contains(&authorized));
+        assert!(config.contains("services.openssh.enable = true;"));
+        assert!(!host_config("cor", &["alice".to_string()], &[]).contains("openssh"));
+    }
+
+    #[test]
+    fn test_primary_user_host_config() {
+        let user = |uid, gid| PrimaryUser { name: "cor".to_string(), uid, gid };
+        assert_eq!(user(1000, 100).owner(), "1000:100");
+        assert!(user(PRIMARY_USER_UID, PRIMARY_USER_GID).host_config().is_none());
+
+        let config = user(1001, 100).host_config().unwrap();
+        assert!(config.contains("users.users.cor.uid = 1001;"));
+        assert!(!config.contains("users.groups"));
+
+        let config = user(1001, 1001).host_config().unwrap();
+        assert!(config.contains("users.groups.cor.gid = 1001;"));
+        assert!(config.contains("users.users.cor.group = \"cor\";"));
+    }
+}
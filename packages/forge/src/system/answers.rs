@@ -0,0 +1,198 @@
+//! Answer file for unattended installs (`forge install --answers <file>`)
+//!
+//! ```toml
+//! hostname = "G1a"             # existing host in the config repo
+//! disk = "/dev/nvme0n1"        # wiped!
+//! username = "john"
+//! password_file = "/run/pw"    # or: password = "..." (also the LUKS passphrase)
+//! wipe_disk = true             # required, stands in for typing "yes"
+//! reboot = true                # reboot when the install succeeds (default false)
+//! encrypt = false              # skip LUKS disk encryption (default true)
+//! tpm2_unlock = true           # also unlock the disk with the TPM2 (default false)
+//! secure_erase = true          # sanitize / discard the disk before disko (default false)
+//! timezone = "Europe/Berlin"   # these three default to what the live ISO uses
+//! locale = "de_DE.UTF-8"
+//! keymap = "de"
+//!
+//! [nix]                        # optional, see system::nix_settings
+//! substituters = ["https://cache.corp.example"]
+//! trusted_public_keys = ["cache.corp.example-1:..."]
+//! proxy = "http://proxy.corp.example:3128"
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::app::state::{validate_password, validate_username};
+use crate::system::locale::{self, LocaleSettings};
+use crate::system::nix_settings::NixSettings;
+
+/// Parsed answer file
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InstallAnswers {
+    pub hostname: String,
+    pub disk: String,
+    pub username: String,
+    /// Inline password; prefer `password_file` so it stays out of the file
+    pub password: Option<String>,
+    /// File whose first line is the password
+    pub password_file: Option<String>,
+    /// Must be true: the disk is repartitioned without asking
+    #[serde(default)]
+    pub wipe_disk: bool,
+    #[serde(default)]
+    pub reboot: bool,
+    /// LUKS-encrypt the disk with the password as passphrase
+    #[serde(default = "default_encrypt")]
+    pub encrypt: bool,
+    /// Enroll forge's own Secure Boot keys (firmware must be in Setup Mode)
+    #[serde(default)]
+    pub secure_boot: bool,
+    /// Seal a LUKS key in the TPM2 as well (needs `encrypt`)
+    #[serde(default)]
+    pub tpm2_unlock: bool,
+    /// Securely erase the disk before partitioning (see `system::disk::erase`)
+    #[serde(default)]
+    pub secure_erase: bool,
+    /// tz database name for `time.timeZone`
+    pub timezone: Option<String>,
+    /// `i18n.defaultLocale`
+    pub locale: Option<String>,
+    /// `console.keyMap`
+    pub keymap: Option<String>,
+    /// Extra binary caches and proxy
+    #[serde(default)]
+    pub nix: NixSettings,
+}
+
+fn default_encrypt() -> bool {
+    true
+}
+
+impl InstallAnswers {
+    /// Read and validate an answer file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid answer file {}", path.display()))
+    }
+
+    /// Parse and validate everything except the password source
+    pub fn parse(content: &str) -> Result<Self> {
+        let answers: InstallAnswers = toml::from_str(content)?;
+
+        if answers.hostname.trim().is_empty() {
+            bail!("hostname is required");
+        }
+        if !answers.disk.starts_with("/dev/") {
+            bail!("disk must be a device path like /dev/nvme0n1");
+        }
+        if let Some(err) = validate_username(&answers.username) {
+            bail!("username: {}", err);
+        }
+        match (&answers.password, &answers.password_file) {
+            (Some(_), Some(_)) => bail!("Set either password or password_file, not both"),
+            (None, None) => bail!("password or password_file is required"),
+            _ => {}
+        }
+        if !answers.wipe_disk {
+            bail!(
+                "wipe_disk = true is required - everything on {} will be erased",
+                answers.disk
+            );
+        }
+        if let Some(timezone) = &answers.timezone {
+            locale::validate_timezone(timezone)?;
+        }
+        if let Some(value) = &answers.locale {
+            locale::validate_locale(value)?;
+        }
+        if let Some(keymap) = &answers.keymap {
+            locale::validate_keymap(keymap)?;
+        }
+        answers.nix.validate()?;
+        Ok(answers)
+    }
+
+    /// Locale settings for the host: the ISO's, with the ones given here
+    pub fn locale(&self) -> LocaleSettings {
+        LocaleSettings::detect().with_overrides(
+            self.timezone.as_deref(),
+            self.locale.as_deref(),
+            self.keymap.as_deref(),
+        )
+    }
+
+    /// The password, read from `password_file` if that's how it was given
+    pub fn password(&self) -> Result<String> {
+        let password = match (&self.password, &self.password_file) {
+            (Some(password), _) => password.clone(),
+            (None, Some(file)) => std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read password file {}", file))?
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            (None, None) => bail!("No password given"),
+        };
+        if let Some(err) = validate_password(&password, &password) {
+            bail!("password: {}", err);
+        }
+        Ok(password)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANSWERS: &str = r#"
+        hostname = "G1a"
+        disk = "/dev/nvme0n1"
+        username = "john"
+        password = "correct horse"
+        wipe_disk = true
+    "#;
+
+    #[test]
+    fn test_parse_answers() {
+        let answers = InstallAnswers::parse(ANSWERS).unwrap();
+        assert_eq!(answers.hostname, "G1a");
+        assert_eq!(answers.disk, "/dev/nvme0n1");
+        assert!(!answers.reboot);
+        assert!(!answers.secure_boot);
+        assert!(answers.encrypt);
+        let plain = InstallAnswers::parse(&format!("{}encrypt = false\n", ANSWERS)).unwrap();
+        assert!(!plain.encrypt);
+        assert!(!answers.tpm2_unlock);
+        assert!(!answers.secure_erase);
+        assert_eq!(answers.password().unwrap(), "correct horse");
+        let german = InstallAnswers::parse(&format!("{}locale = \"de_DE.UTF-8\"\n", ANSWERS)).unwrap();
+        assert_eq!(german.locale().locale, "de_DE.UTF-8");
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_answers() {
+        // Disk wipe must be acknowledged
+        assert!(InstallAnswers::parse(&ANSWERS.replace("wipe_disk = true", "")).is_err());
+        assert!(InstallAnswers::parse(&ANSWERS.replace("/dev/nvme0n1", "nvme0n1")).is_err());
+        assert!(InstallAnswers::parse(&ANSWERS.replace("\"john\"", "\"Root\"")).is_err());
+        // Exactly one password source
+        assert!(InstallAnswers::parse(&ANSWERS.replace("password = \"correct horse\"", "")).is_err());
+        assert!(InstallAnswers::parse(&format!("{}password_file = \"/tmp/pw\"\n", ANSWERS)).is_err());
+        // Typos shouldn't be silently ignored
+        assert!(InstallAnswers::parse(&format!("{}reboto = true\n", ANSWERS)).is_err());
+        let bad_proxy = format!("{}[nix]\nproxy = \"proxy:80\"\n", ANSWERS);
+        assert!(InstallAnswers::parse(&bad_proxy).is_err());
+        let bad_keymap = format!("{}keymap = \"de\\\"\"\n", ANSWERS);
+        assert!(InstallAnswers::parse(&bad_keymap).is_err());
+    }
+
+    #[test]
+    fn test_short_password_rejected() {
+        let answers = InstallAnswers::parse(&ANSWERS.replace("correct horse", "short")).unwrap();
+        assert!(answers.password().is_err());
+    }
+}
@@ -0,0 +1,214 @@
+//! Bisection over a flake input's commits (`forge bisect`)
+//!
+//! The commit list runs from the known good revision (index 0) to the known
+//! bad one (last index). Each verdict narrows `good..bad` until the first bad
+//! commit is next to the last good one. Commits that can't be judged (e.g.
+//! an unrelated build failure) are skipped and never offered again.
+
+use std::collections::BTreeSet;
+
+/// One commit of the bisected input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BisectCommit {
+    pub sha: String,
+    /// First line of the commit message
+    pub message: String,
+}
+
+impl BisectCommit {
+    pub fn short_sha(&self) -> &str {
+        &self.sha[..7.min(self.sha.len())]
+    }
+}
+
+/// Answer for a tested commit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Good,
+    Bad,
+    Skip,
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Good => write!(f, "good"),
+            Verdict::Bad => write!(f, "bad"),
+            Verdict::Skip => write!(f, "skipped"),
+        }
+    }
+}
+
+/// How each candidate is rebuilt before asking for a verdict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BisectMode {
+    /// Build only; for regressions that show up as build failures
+    Build,
+    /// Show what activation would change, without changing anything
+    DryActivate,
+    /// Activate without a boot entry; rebooting returns to the current generation
+    #[default]
+    Test,
+}
+
+impl BisectMode {
+    pub const ALL: [BisectMode; 3] = [BisectMode::Build, BisectMode::DryActivate, BisectMode::Test];
+
+    /// The `nixos-rebuild` action
+    pub fn action(&self) -> &'static str {
+        match self {
+            BisectMode::Build => "build",
+            BisectMode::DryActivate => "dry-activate",
+            BisectMode::Test => "test",
+        }
+    }
+
+    /// Whether the action changes the running system, so it needs sudo
+    pub fn needs_root(&self) -> bool {
+        !matches!(self, BisectMode::Build)
+    }
+}
+
+/// State of one bisection
+#[derive(Debug, Clone)]
+pub struct Bisection {
+    /// Upstream repository, e.g. "NixOS/nixpkgs"
+    pub repo: String,
+    /// Oldest (good) to newest (bad)
+    pub commits: Vec<BisectCommit>,
+    /// Verdicts so far, oldest first
+    pub history: Vec<(usize, Verdict)>,
+    good: usize,
+    bad: usize,
+    skipped: BTreeSet<usize>,
+}
+
+impl Bisection {
+    /// Start from `commits`, whose first entry is good and last is bad
+    pub fn new(repo: &str, commits: Vec<BisectCommit>) -> Self {
+        Bisection {
+            repo: repo.to_string(),
+            bad: commits.len().saturating_sub(1),
+            commits,
+            history: Vec::new(),
+            good: 0,
+            skipped: BTreeSet::new(),
+        }
+    }
+
+    /// Next commit to test: the untested one closest to the middle of the range
+    pub fn next(&self) -> Option<usize> {
+        if self.bad <= self.good + 1 {
+            return None;
+        }
+        let mid = (self.good + self.bad) / 2;
+        (0..self.bad - self.good)
+            .flat_map(|d| [mid.checked_sub(d), Some(mid + d)])
+            .flatten()
+            .find(|i| *i > self.good && *i < self.bad && !self.skipped.contains(i))
+    }
+
+    pub fn mark(&mut self, index: usize, verdict: Verdict) {
+        match verdict {
+            Verdict::Good => self.good = self.good.max(index),
+            Verdict::Bad => self.bad = self.bad.min(index),
+            Verdict::Skip => {
+                self.skipped.insert(index);
+            }
+        }
+        self.history.push((index, verdict));
+    }
+
+    /// Commits that may still be the first bad one
+    pub fn candidates(&self) -> &[BisectCommit] {
+        &self.commits[(self.good + 1).min(self.bad)..=self.bad]
+    }
+
+    /// The first bad commit, once the range is narrowed to one
+    pub fn culprit(&self) -> Option<&BisectCommit> {
+        match self.candidates() {
+            [only] => Some(only),
+            _ => None,
+        }
+    }
+
+    /// Rough number of verdicts still needed
+    pub fn steps_left(&self) -> u32 {
+        let candidates = self.candidates().len();
+        if candidates <= 1 {
+            0
+        } else {
+            usize::BITS - (candidates - 1).leading_zeros()
+        }
+    }
+
+    /// Last known good and first known bad commit
+    pub fn bounds(&self) -> (&BisectCommit, &BisectCommit) {
+        (&self.commits[self.good], &self.commits[self.bad])
+    }
+
+    /// GitHub page of a commit
+    pub fn commit_url(&self, commit: &BisectCommit) -> String {
+        format!("https://github.com/{}/commit/{}", self.repo, commit.sha)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commits(n: usize) -> Vec<BisectCommit> {
+        (0..n)
+            .map(|i| BisectCommit {
+                sha: format!("{:040x}", i),
+                message: format!("commit {}", i),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bisection_finds_first_bad_commit() {
+        // Commit 37 introduced the regression
+        let mut bisection = Bisection::new("NixOS/nixpkgs", commits(101));
+        assert_eq!(bisection.steps_left(), 7);
+        let mut steps = 0;
+        while let Some(i) = bisection.next() {
+            let verdict = if i >= 37 { Verdict::Bad } else { Verdict::Good };
+            bisection.mark(i, verdict);
+            steps += 1;
+        }
+        assert!(steps <= 7);
+        assert_eq!(bisection.culprit().map(|c| c.message.as_str()), Some("commit 37"));
+        assert_eq!(bisection.steps_left(), 0);
+        assert_eq!(
+            bisection.commit_url(&bisection.commits[37]),
+            format!("https://github.com/NixOS/nixpkgs/commit/{:040x}", 37)
+        );
+    }
+
+    #[test]
+    fn test_bisection_skips() {
+        let mut bisection = Bisection::new("NixOS/nixpkgs", commits(4));
+        // Candidates 1 and 2 (3 is known bad)
+        let first = bisection.next().unwrap();
+        bisection.mark(first, Verdict::Skip);
+        let second = bisection.next().unwrap();
+        assert_ne!(first, second);
+        bisection.mark(second, Verdict::Skip);
+
+        // Nothing left to test, and no single culprit
+        assert_eq!(bisection.next(), None);
+        assert_eq!(bisection.culprit(), None);
+        assert_eq!(bisection.candidates().len(), 3);
+        assert_eq!(bisection.history.len(), 2);
+    }
+
+    #[test]
+    fn test_adjacent_range_is_done() {
+        let bisection = Bisection::new("NixOS/nixpkgs", commits(2));
+        assert_eq!(bisection.next(), None);
+        assert_eq!(bisection.culprit().map(|c| c.short_sha()), Some("0000000"));
+        assert_eq!(BisectMode::default().action(), "test");
+        assert!(!BisectMode::Build.needs_root());
+    }
+}
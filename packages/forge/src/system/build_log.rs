@@ -0,0 +1,100 @@
+//! Build logs of failed derivations
+//!
+//! When a rebuild fails, `nix log` of each derivation named in the error is
+//! saved under `build-logs/<run>/` in the forge data directory, so the log is
+//! still there after the TUI closes (and after a later build evicts it).
+
+use regex::Regex;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+/// Failed runs to keep; older run directories are removed
+pub const KEEP_BUILD_LOG_RUNS: usize = 10;
+
+/// Lines of each log kept in memory for the viewer (the file has everything)
+pub const MAX_VIEW_LINES: usize = 2000;
+
+/// `error: builder for '/nix/store/…drv' failed …` and `error: Cannot build '/nix/store/…drv'.`
+static FAILED_DRV_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:builder for|Cannot build) '(/nix/store/[0-9a-z]{32}-[^']+\.drv)'").unwrap()
+});
+
+/// `nix log` output of one failed derivation
+#[derive(Debug, Clone)]
+pub struct SavedBuildLog {
+    pub drv: String,
+    /// Where the full log was written
+    pub path: PathBuf,
+    /// Tail of the log (or why it couldn't be read)
+    pub lines: Vec<String>,
+}
+
+impl SavedBuildLog {
+    /// Derivation name without the store hash, e.g. `hello-2.12.1`
+    pub fn name(&self) -> &str {
+        drv_name(&self.drv)
+    }
+}
+
+/// Derivation whose builder failed, if the output line reports one
+pub fn failed_derivation(line: &str) -> Option<String> {
+    FAILED_DRV_RE.captures(line).map(|caps| caps[1].to_string())
+}
+
+/// Strip the store directory, hash and `.drv` suffix
+pub fn drv_name(drv: &str) -> &str {
+    let base = drv.rsplit('/').next().unwrap_or(drv);
+    let base = base.strip_suffix(".drv").unwrap_or(base);
+    match base.split_once('-') {
+        Some((hash, name)) if hash.len() == 32 => name,
+        _ => base,
+    }
+}
+
+/// File name for a derivation's log inside a run directory
+pub fn log_file_name(drv: &str) -> String {
+    format!("{}.log", drv_name(drv))
+}
+
+/// Last `max` lines of `text`
+pub fn tail_lines(text: &str, max: usize) -> Vec<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    lines[lines.len().saturating_sub(max)..]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DRV: &str = "/nix/store/0c5n8qnmv7aqz1w1k4kxkbd7xxmp1fhx-hello-2.12.1.drv";
+
+    #[test]
+    fn test_failed_derivation() {
+        let builder = format!("error: builder for '{}' failed with exit code 2;", DRV);
+        assert_eq!(failed_derivation(&builder).as_deref(), Some(DRV));
+        let cannot = format!("error: Cannot build '{}'.", DRV);
+        assert_eq!(failed_derivation(&cannot).as_deref(), Some(DRV));
+
+        // Dependents of the failed build aren't the cause
+        let dependents = format!("error: 1 dependencies of derivation '{}' failed to build", DRV);
+        assert_eq!(failed_derivation(&dependents), None);
+        assert_eq!(failed_derivation("building '/nix/store/x.drv'..."), None);
+    }
+
+    #[test]
+    fn test_log_file_name() {
+        assert_eq!(drv_name(DRV), "hello-2.12.1");
+        assert_eq!(log_file_name(DRV), "hello-2.12.1.log");
+        assert_eq!(log_file_name("/nix/store/short-name.drv"), "short-name.log");
+    }
+
+    #[test]
+    fn test_tail_lines() {
+        assert_eq!(tail_lines("a\nb\nc\n", 2), vec!["b", "c"]);
+        assert_eq!(tail_lines("a", 5), vec!["a"]);
+        assert!(tail_lines("", 5).is_empty());
+    }
+}
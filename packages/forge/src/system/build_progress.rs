@@ -0,0 +1,187 @@
+//! Progress of a nix build, read from `--log-format internal-json`
+//!
+//! Each nix process reports its substitutions as one "copy paths" activity
+//! (type 103) and its builds as one "builds" activity (type 104), whose
+//! progress results carry `[done, expected, running, failed]`. Everything
+//! the plain log format would print arrives as activity text or messages:
+//!
+//! ```text
+//! @nix {"action":"start","id":7,"type":103,"level":5,"text":"","parent":0,"fields":[]}
+//! @nix {"action":"result","id":7,"type":105,"fields":[812,1204,4,0]}
+//! @nix {"action":"start","id":9,"type":105,"level":3,"text":"building '/nix/store/xyz-a.drv'"}
+//! @nix {"action":"msg","level":0,"msg":"error: builder for '/nix/store/xyz-a.drv' failed"}
+//! ```
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+const ACT_COPY_PATHS: u64 = 103;
+const ACT_BUILDS: u64 = 104;
+const RES_PROGRESS: u64 = 105;
+/// Activities above this level (talkative and chattier) stay out of the log
+const LVL_INFO: u64 = 3;
+
+/// Paths copied and derivations built so far, across every nix process
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BuildProgress {
+    pub copied: u64,
+    pub to_copy: u64,
+    pub built: u64,
+    pub to_build: u64,
+}
+
+impl BuildProgress {
+    /// Whether nix has announced any work yet
+    pub fn is_known(&self) -> bool {
+        self.to_copy + self.to_build > 0
+    }
+
+    /// Share of paths and derivations done, 0-100
+    pub fn percent(&self) -> u16 {
+        let total = self.to_copy + self.to_build;
+        if total == 0 {
+            return 0;
+        }
+        let done = self.copied.min(self.to_copy) + self.built.min(self.to_build);
+        (done * 100 / total) as u16
+    }
+
+    /// "812/1204 paths copied · 3/12 derivations built"
+    pub fn label(&self) -> String {
+        let mut parts = Vec::new();
+        if self.to_copy > 0 {
+            parts.push(format!("{}/{} paths copied", self.copied, self.to_copy));
+        }
+        if self.to_build > 0 {
+            parts.push(format!("{}/{} derivations built", self.built, self.to_build));
+        }
+        parts.join(" · ")
+    }
+}
+
+/// Running counters per copy-paths and builds activity
+#[derive(Debug, Clone, Default)]
+pub struct BuildTracker {
+    /// Activity id → its type, for the two activities that count work
+    activities: BTreeMap<u64, u64>,
+    /// Activity id → latest `(done, expected)`
+    counts: BTreeMap<u64, (u64, u64)>,
+}
+
+impl BuildTracker {
+    /// Fold one line of nix's stderr into the counters
+    ///
+    /// Returns the text the plain log format would have shown: activity
+    /// descriptions ("building '…'", "copying path '…'"), log messages, and
+    /// any line that isn't internal-json (nixos-rebuild's own output).
+    /// Messages can span several lines.
+    pub fn update(&mut self, line: &str) -> Option<String> {
+        let Some(json) = line.strip_prefix("@nix ") else {
+            return Some(line.to_string());
+        };
+        let event: Value = serde_json::from_str(json).ok()?;
+        let id = event.get("id").and_then(Value::as_u64);
+        let kind = event.get("type").and_then(Value::as_u64);
+        let field = |i: usize| {
+            event
+                .get("fields")
+                .and_then(|f| f.get(i))
+                .and_then(Value::as_u64)
+                .unwrap_or(0)
+        };
+
+        match event.get("action").and_then(Value::as_str)? {
+            "start" => {
+                if let (Some(id), Some(kind @ (ACT_COPY_PATHS | ACT_BUILDS))) = (id, kind) {
+                    self.activities.insert(id, kind);
+                }
+                let level = event.get("level").and_then(Value::as_u64).unwrap_or(0);
+                event
+                    .get("text")
+                    .and_then(Value::as_str)
+                    .filter(|text| !text.is_empty() && level <= LVL_INFO)
+                    .map(|text| format!("{}...", text))
+            }
+            "result" if kind == Some(RES_PROGRESS) => {
+                if let Some(id) = id.filter(|id| self.activities.contains_key(id)) {
+                    self.counts.insert(id, (field(0), field(1)));
+                }
+                None
+            }
+            "msg" => event
+                .get("msg")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            _ => None,
+        }
+    }
+
+    /// Totals over every activity seen so far
+    pub fn progress(&self) -> BuildProgress {
+        let mut progress = BuildProgress::default();
+        for (id, &(done, expected)) in &self.counts {
+            match self.activities.get(id) {
+                Some(&ACT_COPY_PATHS) => {
+                    progress.copied += done;
+                    progress.to_copy += expected;
+                }
+                Some(&ACT_BUILDS) => {
+                    progress.built += done;
+                    progress.to_build += expected;
+                }
+                _ => {}
+            }
+        }
+        progress
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_counts_paths_and_builds() {
+        let mut tracker = BuildTracker::default();
+        let lines = [
+            r#"@nix {"action":"start","id":1,"type":103,"level":5,"text":""}"#,
+            r#"@nix {"action":"start","id":2,"type":104,"level":5,"text":""}"#,
+            r#"@nix {"action":"result","id":1,"type":105,"fields":[30,40,2,0]}"#,
+            r#"@nix {"action":"result","id":2,"type":105,"fields":[1,10,1,0]}"#,
+            r#"@nix {"action":"result","id":5,"type":105,"fields":[4096,8192,0,0]}"#,
+        ];
+        for line in lines {
+            assert_eq!(tracker.update(line), None);
+        }
+        let progress = tracker.progress();
+        assert_eq!(progress.label(), "30/40 paths copied · 1/10 derivations built");
+        assert_eq!(progress.percent(), 62);
+
+        // A second nix process adds its own activities to the totals
+        tracker.update(r#"@nix {"action":"start","id":9,"type":104,"level":5,"text":""}"#);
+        tracker.update(r#"@nix {"action":"result","id":9,"type":105,"fields":[0,2,0,0]}"#);
+        assert_eq!(tracker.progress().to_build, 12);
+    }
+
+    #[test]
+    fn test_tracker_passes_log_text_through() {
+        let mut tracker = BuildTracker::default();
+        assert_eq!(
+            tracker.update(
+                r#"@nix {"action":"start","id":3,"type":105,"level":3,"text":"building 'a.drv'"}"#
+            ),
+            Some("building 'a.drv'...".to_string())
+        );
+        let chatty = r#"@nix {"action":"start","id":4,"type":0,"level":4,"text":"querying info"}"#;
+        assert_eq!(tracker.update(chatty), None);
+        assert_eq!(
+            tracker.update(r#"@nix {"action":"msg","level":0,"msg":"error: a\n  b"}"#),
+            Some("error: a\n  b".to_string())
+        );
+        assert_eq!(
+            tracker.update("activating the configuration..."),
+            Some("activating the configuration...".to_string())
+        );
+        assert!(!tracker.progress().is_known());
+    }
+}
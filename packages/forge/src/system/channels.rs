@@ -0,0 +1,263 @@
+//! Update channels: which nixpkgs each host is built from
+//!
+//! Hosts follow nixpkgs-unstable unless their flake.nix entry says
+//! `channel = "stable";`. The stable channel needs its own nixpkgs and a
+//! Home Manager release that follows it; those inputs are in flake.nix only
+//! while some host uses them:
+//!
+//! ```nix
+//! nixpkgs-stable.url = "github:NixOS/nixpkgs/nixos-26.05";
+//! home-manager-stable = {
+//!   url = "github:nix-community/home-manager/release-26.05";
+//!   inputs.nixpkgs.follows = "nixpkgs-stable";
+//! };
+//! ```
+
+use anyhow::{bail, Context, Result};
+use std::fmt;
+
+use super::flake_edit::{attr_span, block_attrs, remove_span, INPUTS, NIXOS_CONFIGURATIONS};
+
+/// NixOS release the stable inputs are added with
+pub const STABLE_RELEASE: &str = "26.05";
+
+/// Input holding the stable nixpkgs
+pub const STABLE_NIXPKGS: &str = "nixpkgs-stable";
+
+/// Input holding the Home Manager release for the stable nixpkgs
+pub const STABLE_HOME_MANAGER: &str = "home-manager-stable";
+
+/// nixpkgs a host is built from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Channel {
+    #[default]
+    Unstable,
+    Stable,
+}
+
+impl Channel {
+    pub fn toggled(self) -> Self {
+        match self {
+            Channel::Unstable => Channel::Stable,
+            Channel::Stable => Channel::Unstable,
+        }
+    }
+
+    /// The flake input the channel's nixpkgs comes from
+    pub fn input(self) -> &'static str {
+        match self {
+            Channel::Unstable => "nixpkgs",
+            Channel::Stable => STABLE_NIXPKGS,
+        }
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Channel::Unstable => write!(f, "unstable"),
+            Channel::Stable => write!(f, "stable"),
+        }
+    }
+}
+
+/// A host of nixosConfigurations and the channel it follows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostChannel {
+    pub host: String,
+    pub channel: Channel,
+}
+
+/// Hosts built with mkNixosSystem, in flake.nix order (the installer ISO is not one)
+pub fn host_channels(flake: &str) -> Vec<HostChannel> {
+    let lines: Vec<&str> = flake.split_inclusive('\n').collect();
+    block_attrs(&lines, NIXOS_CONFIGURATIONS)
+        .into_iter()
+        .filter(|(_, start, _)| lines[*start].contains("mkNixosSystem"))
+        .map(|(host, start, end)| {
+            let stable = lines[start..=end]
+                .iter()
+                .any(|line| channel_value(line) == Some("stable"));
+            let channel = if stable { Channel::Stable } else { Channel::Unstable };
+            HostChannel { host, channel }
+        })
+        .collect()
+}
+
+/// Release of the stable inputs (e.g. "26.05"), None without them
+pub fn stable_release(flake: &str) -> Option<String> {
+    let lines: Vec<&str> = flake.split_inclusive('\n').collect();
+    let (start, end) = attr_span(&lines, INPUTS, STABLE_NIXPKGS)?;
+    lines[start..=end].iter().find_map(|line| {
+        let release = line.split("nixos-").nth(1)?;
+        let release: String = release
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        (!release.is_empty()).then_some(release)
+    })
+}
+
+/// Put `host` on `channel`, adding the stable inputs for the first stable
+/// host and removing them with the last one
+pub fn set_host_channel(flake: &str, host: &str, channel: Channel) -> Result<String> {
+    let lines: Vec<&str> = flake.split_inclusive('\n').collect();
+    let (start, end) = attr_span(&lines, NIXOS_CONFIGURATIONS, host)
+        .with_context(|| format!("{} is not in nixosConfigurations in flake.nix", host))?;
+
+    let mut edited: Vec<String> = Vec::new();
+    edited.extend(lines[..start].iter().map(|line| line.to_string()));
+    let mut placed = channel == Channel::Unstable;
+    for line in &lines[start..=end] {
+        // The default channel is left implicit
+        if channel_value(line).is_some() {
+            continue;
+        }
+        edited.push(line.to_string());
+        if !placed && line.trim_start().starts_with("hostname =") {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            edited.push(format!("{}channel = \"{}\";\n", indent, channel));
+            placed = true;
+        }
+    }
+    if !placed {
+        bail!("{}'s entry in flake.nix has no hostname line to add the channel after", host);
+    }
+    edited.extend(lines[end + 1..].iter().map(|line| line.to_string()));
+    sync_stable_inputs(&edited.concat())
+}
+
+/// Add or remove the stable inputs to match whether any host uses them
+fn sync_stable_inputs(flake: &str) -> Result<String> {
+    let any_stable = host_channels(flake).iter().any(|h| h.channel == Channel::Stable);
+    let lines: Vec<&str> = flake.split_inclusive('\n').collect();
+    let nixpkgs = attr_span(&lines, INPUTS, STABLE_NIXPKGS);
+    let home_manager = attr_span(&lines, INPUTS, STABLE_HOME_MANAGER);
+
+    match (any_stable, nixpkgs, home_manager) {
+        (true, None, None) => {
+            let (start, end) = attr_span(&lines, INPUTS, "home-manager")
+                .context("Could not find the home-manager input in flake.nix")?;
+            let indent = &lines[start][..lines[start].len() - lines[start].trim_start().len()];
+            let mut edited = lines[..=end].concat();
+            edited.push('\n');
+            edited.push_str(&stable_inputs(indent, STABLE_RELEASE));
+            edited.push_str(&lines[end + 1..].concat());
+            Ok(edited)
+        }
+        // Removed together, so they take one blank line with them
+        (false, Some((a, a_end)), Some((b, b_end))) => {
+            Ok(remove_span(&lines, a.min(b), a_end.max(b_end)))
+        }
+        (false, Some((start, end)), None) | (false, None, Some((start, end))) => {
+            Ok(remove_span(&lines, start, end))
+        }
+        _ => Ok(flake.to_string()),
+    }
+}
+
+fn stable_inputs(indent: &str, release: &str) -> String {
+    [
+        "# Stable channel for hosts with `channel = \"stable\";` (forge channels)".to_string(),
+        format!("{}.url = \"github:NixOS/nixpkgs/nixos-{}\";", STABLE_NIXPKGS, release),
+        format!("{} = {{", STABLE_HOME_MANAGER),
+        format!("  url = \"github:nix-community/home-manager/release-{}\";", release),
+        format!("  inputs.nixpkgs.follows = \"{}\";", STABLE_NIXPKGS),
+        "};".to_string(),
+    ]
+    .iter()
+    .map(|line| format!("{}{}\n", indent, line))
+    .collect()
+}
+
+/// `stable` of `channel = "stable";`
+fn channel_value(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix("channel")?
+        .trim_start()
+        .strip_prefix('=')?
+        .trim()
+        .strip_suffix(';')?
+        .trim()
+        .strip_prefix('"')?
+        .strip_suffix('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLAKE: &str = r#"{
+  inputs = {
+    nixpkgs.url = "github:NixOS/nixpkgs/nixos-unstable";
+
+    home-manager = {
+      url = "github:nix-community/home-manager";
+      inputs.nixpkgs.follows = "nixpkgs";
+    };
+
+    disko.url = "github:nix-community/disko";
+  };
+
+  outputs = { self, nixpkgs, ... }@inputs: {
+    nixosConfigurations = {
+      kraken = mkNixosSystem {
+        hostname = "kraken";
+      };
+
+      G1a = mkNixosSystem {
+        hostname = "G1a";
+      };
+
+      iso = nixpkgs.lib.nixosSystem {
+        modules = [ ./modules/iso ];
+      };
+    };
+  };
+}
+"#;
+
+    #[test]
+    fn test_set_host_channel() {
+        let host = |flake: &str, name: &str| {
+            host_channels(flake).into_iter().find(|h| h.host == name).map(|h| h.channel)
+        };
+        assert_eq!(host_channels(FLAKE).len(), 2);
+        assert_eq!(host(FLAKE, "kraken"), Some(Channel::Unstable));
+        assert_eq!(stable_release(FLAKE), None);
+
+        let stable = set_host_channel(FLAKE, "kraken", Channel::Stable).unwrap();
+        assert!(stable.contains(
+            "      kraken = mkNixosSystem {\n        hostname = \"kraken\";\n        \
+             channel = \"stable\";\n      };\n"
+        ));
+        assert!(stable.contains(
+            "      inputs.nixpkgs.follows = \"nixpkgs\";\n    };\n\n    \
+             # Stable channel for hosts with `channel = \"stable\";` (forge channels)\n    \
+             nixpkgs-stable.url = \"github:NixOS/nixpkgs/nixos-26.05\";\n    \
+             home-manager-stable = {\n      \
+             url = \"github:nix-community/home-manager/release-26.05\";\n      \
+             inputs.nixpkgs.follows = \"nixpkgs-stable\";\n    };\n\n    disko.url"
+        ));
+        assert_eq!(host(&stable, "kraken"), Some(Channel::Stable));
+        assert_eq!(host(&stable, "G1a"), Some(Channel::Unstable));
+        assert_eq!(stable_release(&stable).as_deref(), Some("26.05"));
+
+        // A second stable host reuses the inputs; the last one back removes them
+        let both = set_host_channel(&stable, "G1a", Channel::Stable).unwrap();
+        assert_eq!(both.matches("nixpkgs-stable.url").count(), 1);
+        let one = set_host_channel(&both, "G1a", Channel::Unstable).unwrap();
+        assert_eq!(one, stable);
+        assert_eq!(set_host_channel(&one, "kraken", Channel::Unstable).unwrap(), FLAKE);
+
+        assert!(set_host_channel(FLAKE, "missing", Channel::Stable).is_err());
+    }
+
+    #[test]
+    fn test_channel_value() {
+        assert_eq!(channel_value("        channel = \"stable\";\n"), Some("stable"));
+        assert_eq!(channel_value("channel=\"unstable\";"), Some("unstable"));
+        assert_eq!(channel_value("hostname = \"stable\";"), None);
+        assert_eq!(channel_value("channel = stable;"), None);
+    }
+}
@@ -3,35 +3,40 @@
 #![allow(dead_code)]
 
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
 
+use super::disk::identity::DiskIdentity;
+
 /// CPU metadata from host-info.json
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CpuMeta {
     pub vendor: String,
     pub model: String,
 }
 
 /// GPU metadata from host-info.json
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpuMeta {
     pub vendor: String,
     pub model: Option<String>,
 }
 
 /// Host hardware metadata loaded from host-info.json
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HostMetadata {
     pub cpu: Option<CpuMeta>,
     pub gpu: Option<GpuMeta>,
     pub form_factor: Option<String>,
     pub ram: Option<String>,
+    /// Serial/WWN of the disk the host was created for
+    #[serde(default)]
+    pub disk: Option<DiskIdentity>,
 }
 
 /// Host configuration discovered from filesystem
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HostConfig {
     pub name: String,
     pub description: String,
@@ -39,7 +44,7 @@ pub struct HostConfig {
 }
 
 /// Load host metadata from host-info.json
-fn load_host_metadata(host_path: &Path) -> Option<HostMetadata> {
+pub fn load_host_metadata(host_path: &Path) -> Option<HostMetadata> {
     let metadata_path = host_path.join("host-info.json");
     if let Ok(content) = std::fs::read_to_string(&metadata_path) {
         serde_json::from_str(&content).ok()
@@ -49,11 +54,15 @@ fn load_host_metadata(host_path: &Path) -> Option<HostMetadata> {
 }
 
 /// Discover available hosts from the hosts/ directory
-/// Checks multiple locations in order: /tmp/nixos-config, ~/nixos-config, /etc/nixos
+/// Checks multiple locations in order: the offline repo (`forge install --offline`),
+/// /tmp/nixos-config, ~/nixos-config, /etc/nixos
 pub fn discover_hosts() -> Vec<HostConfig> {
     use crate::constants::{HOSTS_SUBDIR, NIXOS_CONFIG_HOME_DIR, NIXOS_CONFIG_SYSTEM, NIXOS_CONFIG_TEMP};
 
+    let offline = super::offline::source()
+        .map(|source| format!("{}/{}", source.repo.display(), HOSTS_SUBDIR));
     let locations = [
+        offline.unwrap_or_default(),
         format!("{}/{}", NIXOS_CONFIG_TEMP, HOSTS_SUBDIR),
         dirs::home_dir()
             .map(|h| format!("{}/{}/{}", h.display(), NIXOS_CONFIG_HOME_DIR, HOSTS_SUBDIR))
@@ -102,6 +111,24 @@ pub fn discover_hosts() -> Vec<HostConfig> {
     hosts
 }
 
+/// Load the host list cached by the previous run, if any
+///
+/// Used to populate host selection instantly while `discover_hosts` runs in the background.
+pub fn load_cached_hosts() -> Option<Vec<HostConfig>> {
+    let content = std::fs::read_to_string(crate::constants::hosts_cache_path()).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Cache a discovered host list for the next run
+pub fn save_host_cache(hosts: &[HostConfig]) -> Result<()> {
+    let path = crate::constants::hosts_cache_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string(hosts)?)?;
+    Ok(())
+}
+
 /// Parse description from first line comment: "# hostname - Description"
 fn parse_host_description(content: &str) -> String {
     if let Some(first_line) = content.lines().next() {
@@ -261,6 +288,29 @@ mod tests {
         assert!(cloned.metadata.is_none());
     }
 
+    #[test]
+    fn test_host_config_cache_roundtrip() {
+        let hosts = vec![HostConfig {
+            name: "kraken".to_string(),
+            description: "Desktop workstation".to_string(),
+            metadata: Some(HostMetadata {
+                cpu: None,
+                gpu: None,
+                form_factor: Some("Desktop".to_string()),
+                ram: None,
+                disk: None,
+            }),
+        }];
+        let json = serde_json::to_string(&hosts).unwrap();
+        let parsed: Vec<HostConfig> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].name, "kraken");
+        assert_eq!(
+            parsed[0].metadata.as_ref().unwrap().form_factor,
+            Some("Desktop".to_string())
+        );
+    }
+
     #[test]
     fn test_host_metadata_with_values() {
         let metadata = HostMetadata {
@@ -274,10 +324,24 @@ mod tests {
             }),
             form_factor: Some("Desktop".to_string()),
             ram: Some("64 GB".to_string()),
+            disk: None,
         };
         assert_eq!(metadata.cpu.as_ref().unwrap().vendor, "AMD");
         assert_eq!(metadata.gpu.as_ref().unwrap().vendor, "NVIDIA");
         assert_eq!(metadata.form_factor, Some("Desktop".to_string()));
         assert_eq!(metadata.ram, Some("64 GB".to_string()));
     }
+
+    #[test]
+    fn test_host_metadata_disk() {
+        let json = r#"{"form_factor": "Desktop", "disk": {"serial": "S6B0NL0T123456"}}"#;
+        let metadata: HostMetadata = serde_json::from_str(json).unwrap();
+        let disk = metadata.disk.unwrap();
+        assert_eq!(disk.serial.as_deref(), Some("S6B0NL0T123456"));
+        assert_eq!(disk.wwn, None);
+
+        // Files written before serials were recorded
+        let metadata: HostMetadata = serde_json::from_str(r#"{"ram": "64 GB"}"#).unwrap();
+        assert!(metadata.disk.is_none());
+    }
 }
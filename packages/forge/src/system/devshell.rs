@@ -0,0 +1,133 @@
+//! Flake devShells for the "Dev shells" screen
+//!
+//! Shells come from the nixos-config flake and from any project flake one
+//! level below `~/code`. Selecting one opens a terminal running `nix develop`.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use crate::constants::FLAKE_NIX;
+
+/// Project directory scanned for flakes (relative to home)
+pub const DEV_PROJECTS_DIR: &str = "code";
+
+/// Terminal used to open shells (same one the desktop config installs)
+pub const DEV_SHELL_TERMINAL: &str = "ghostty";
+
+/// One `devShells.<system>.<name>` output of a flake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DevShell {
+    /// Flake directory name (e.g. "nixos-config")
+    pub project: String,
+    pub flake_dir: PathBuf,
+    pub name: String,
+}
+
+impl DevShell {
+    /// Installable passed to `nix develop`
+    pub fn flake_ref(&self) -> String {
+        format!("{}#{}", self.flake_dir.display(), self.name)
+    }
+
+    /// Open a detached terminal in the flake directory running `nix develop`
+    pub fn launch(&self) -> Result<()> {
+        use std::os::unix::process::CommandExt;
+
+        std::process::Command::new(DEV_SHELL_TERMINAL)
+            .arg(format!("--title={} ({})", self.project, self.name))
+            .arg(format!("--working-directory={}", self.flake_dir.display()))
+            .args(["-e", "nix", "develop", &self.flake_ref()])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            // Own process group, so closing forge's terminal doesn't take it down
+            .process_group(0)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", DEV_SHELL_TERMINAL))?;
+        Ok(())
+    }
+}
+
+/// Nix system double for this machine (e.g. "x86_64-linux")
+pub fn nix_system() -> String {
+    format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+/// The projects directory in home, if there is one
+pub fn dev_projects_dir() -> Option<PathBuf> {
+    dirs::home_dir()
+        .map(|h| h.join(DEV_PROJECTS_DIR))
+        .filter(|d| d.is_dir())
+}
+
+/// Flake directories directly below `dir`, sorted by name
+pub fn find_project_flakes(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut flakes: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.join(FLAKE_NIX).is_file())
+        .collect();
+    flakes.sort();
+    flakes
+}
+
+/// Display name for a flake directory
+pub fn project_name(flake_dir: &Path) -> String {
+    flake_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| flake_dir.display().to_string())
+}
+
+/// Parse `nix eval --json ... --apply builtins.attrNames` output
+pub fn parse_shell_names(json: &str) -> Result<Vec<String>> {
+    serde_json::from_str(json.trim()).context("Unexpected nix eval output")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flake_ref() {
+        let shell = DevShell {
+            project: "forge".to_string(),
+            flake_dir: PathBuf::from("/home/john/code/forge"),
+            name: "default".to_string(),
+        };
+        assert_eq!(shell.flake_ref(), "/home/john/code/forge#default");
+    }
+
+    #[test]
+    fn test_parse_shell_names() {
+        assert_eq!(
+            parse_shell_names("[\"default\",\"rust\"]\n").unwrap(),
+            ["default", "rust"]
+        );
+        assert!(parse_shell_names("[]").unwrap().is_empty());
+        assert!(parse_shell_names("error: attribute missing").is_err());
+    }
+
+    #[test]
+    fn test_find_project_flakes() {
+        let dir = std::env::temp_dir().join(format!("forge-devshell-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for project in ["b-flake", "a-flake", "not-a-flake"] {
+            std::fs::create_dir_all(dir.join(project)).unwrap();
+        }
+        std::fs::write(dir.join("a-flake").join(FLAKE_NIX), "{}").unwrap();
+        std::fs::write(dir.join("b-flake").join(FLAKE_NIX), "{}").unwrap();
+
+        let names: Vec<String> = find_project_flakes(&dir)
+            .iter()
+            .map(|p| project_name(p))
+            .collect();
+        assert_eq!(names, ["a-flake", "b-flake"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+//! Unified diffs of the config files forge edits
+//!
+//! The edits touch a few lines of files a few hundred lines long, so a plain
+//! longest-common-subsequence table is fast enough and keeps this dependency
+//! free.
+
+/// Unchanged lines shown around each change
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// `diff -u` style diff of `old` and `new`, labelled `a/<path>` and `b/<path>`;
+/// empty when they are the same
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let ops = line_ops(&old, &new);
+    if ops.iter().all(|op| *op == Op::Equal) {
+        return String::new();
+    }
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", path, path);
+    // Position of each op in the old and new file
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut i, mut j) = (0, 0);
+    for op in &ops {
+        positions.push((i, j));
+        match op {
+            Op::Equal => (i, j) = (i + 1, j + 1),
+            Op::Delete => i += 1,
+            Op::Insert => j += 1,
+        }
+    }
+
+    let changed: Vec<usize> = (0..ops.len()).filter(|&k| ops[k] != Op::Equal).collect();
+    let mut k = 0;
+    while k < changed.len() {
+        // Grow the hunk while the next change is within two contexts' reach
+        let start = changed[k].saturating_sub(CONTEXT_LINES);
+        let mut last = changed[k];
+        while k + 1 < changed.len() && changed[k + 1] - last <= 2 * CONTEXT_LINES {
+            k += 1;
+            last = changed[k];
+        }
+        let end = (last + CONTEXT_LINES + 1).min(ops.len());
+        k += 1;
+
+        let slice = &ops[start..end];
+        let old_len = slice.iter().filter(|op| **op != Op::Insert).count();
+        let new_len = slice.iter().filter(|op| **op != Op::Delete).count();
+        let (old_start, new_start) = positions[start];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_len),
+            hunk_range(new_start, new_len)
+        ));
+        for (offset, op) in slice.iter().enumerate() {
+            let (i, j) = positions[start + offset];
+            match op {
+                Op::Equal => out.push_str(&format!(" {}\n", old[i])),
+                Op::Delete => out.push_str(&format!("-{}\n", old[i])),
+                Op::Insert => out.push_str(&format!("+{}\n", new[j])),
+            }
+        }
+    }
+    out
+}
+
+/// `start,len` with 1-based start; an empty range names the line before it
+fn hunk_range(start: usize, len: usize) -> String {
+    if len == 0 {
+        format!("{},0", start)
+    } else {
+        format!("{},{}", start + 1, len)
+    }
+}
+
+/// Edit script turning `old` into `new`, deletions before insertions
+fn line_ops(old: &[&str], new: &[&str]) -> Vec<Op> {
+    // lcs[i][j]: longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(old.len() + new.len());
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push(Op::Equal);
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(Op::Delete);
+            i += 1;
+        } else {
+            ops.push(Op::Insert);
+            j += 1;
+        }
+    }
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(unified_diff("flake.nix", "a\nb\n", "a\nb\n"), "");
+
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n16\n";
+        let new = "1\n2\n3\n4\n5\nfive\n6\n7\n8\n9\n10\n11\n12\n13\n14\nfifteen\n16\n";
+        assert_eq!(
+            unified_diff("flake.nix", old, new),
+            "--- a/flake.nix\n+++ b/flake.nix\n\
+             @@ -3,6 +3,7 @@\n 3\n 4\n 5\n+five\n 6\n 7\n 8\n\
+             @@ -12,5 +13,5 @@\n 12\n 13\n 14\n-15\n+fifteen\n 16\n"
+        );
+
+        // Changes close together share a hunk; appending to an empty file starts at 0
+        let diff = unified_diff("f", "a\nb\nc\nd\n", "a\nB\nc\nD\n");
+        assert_eq!(diff.matches("@@").count(), 2);
+        assert!(unified_diff("f", "", "new\n").contains("@@ -0,0 +1,1 @@\n+new\n"));
+    }
+}
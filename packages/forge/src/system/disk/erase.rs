@@ -0,0 +1,252 @@
+//! Opt-in secure erase of install targets, run right before disko
+//!
+//! For machines that had other owners: NVMe drives are sanitized (`nvme
+//! sanitize`, crypto erase when the controller offers it, else block erase) or,
+//! without sanitize support, formatted with a user-data erase (`nvme format
+//! --ses=1`). Other drives with TRIM are discarded whole with `blkdiscard`;
+//! drives without it (spinning disks) are overwritten with zeros, which takes
+//! hours on a large disk.
+//!
+//! Sanitize and format act on the whole NVMe controller, every namespace on
+//! it, whichever namespace they are given. A namespace that shares its
+//! controller is erased like any other drive instead.
+
+use serde_json::Value;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::system::install_progress::StepProgress;
+
+/// `sanicap` bits of `nvme id-ctrl`
+const SANICAP_CRYPTO_ERASE: u64 = 1 << 0;
+const SANICAP_BLOCK_ERASE: u64 = 1 << 1;
+
+/// `sprog` counts up to this when a sanitize is done
+pub const SANITIZE_PROGRESS_MAX: u64 = 65535;
+
+/// Bytes zeroed per `blkdiscard --step`, one progress line each
+pub const ZERO_STEP: &str = "1G";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EraseMethod {
+    /// `nvme sanitize --sanact=4`: the drive throws away its media key
+    NvmeCryptoErase,
+    /// `nvme sanitize --sanact=2`: every block is erased
+    NvmeBlockErase,
+    /// `nvme format --ses=1`, for controllers without sanitize
+    NvmeFormat,
+    /// `blkdiscard`: TRIM the whole drive
+    Discard,
+    /// `blkdiscard -z`: write zeros over the whole drive
+    ZeroOut,
+}
+
+impl EraseMethod {
+    /// For the overview and the install output
+    pub fn describe(self) -> &'static str {
+        match self {
+            EraseMethod::NvmeCryptoErase => "NVMe sanitize (crypto erase)",
+            EraseMethod::NvmeBlockErase => "NVMe sanitize (block erase)",
+            EraseMethod::NvmeFormat => "NVMe format with user data erase",
+            EraseMethod::Discard => "discard every block (blkdiscard)",
+            EraseMethod::ZeroOut => "overwrite with zeros (slow)",
+        }
+    }
+
+    /// Program and arguments that erase `device`
+    pub fn command(self, device: &str) -> (&'static str, Vec<String>) {
+        let args: &[&str] = match self {
+            EraseMethod::NvmeCryptoErase => &["sanitize", "--sanact=4"],
+            EraseMethod::NvmeBlockErase => &["sanitize", "--sanact=2"],
+            EraseMethod::NvmeFormat => &["format", "--ses=1", "--force"],
+            EraseMethod::Discard => &["--force", "--verbose"],
+            EraseMethod::ZeroOut => &["--zeroout", "--force", "--verbose", "--step", ZERO_STEP],
+        };
+        let program = if self.is_sanitize() || self == EraseMethod::NvmeFormat {
+            "nvme"
+        } else {
+            "blkdiscard"
+        };
+        let mut args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        args.push(device.to_string());
+        (program, args)
+    }
+
+    /// Sanitize runs in the drive after the command returns; poll `nvme sanitize-log`
+    pub fn is_sanitize(self) -> bool {
+        matches!(self, EraseMethod::NvmeCryptoErase | EraseMethod::NvmeBlockErase)
+    }
+}
+
+/// The best erase `disk` supports without touching any other disk
+pub fn detect_erase_method(disk: &str) -> EraseMethod {
+    let device = std::fs::canonicalize(disk).unwrap_or_else(|_| disk.into());
+    let device = device.to_string_lossy();
+    if let Some(name) = device.strip_prefix("/dev/") {
+        if name.starts_with("nvme") && controller_namespaces(name) == Some(1) {
+            let sanicap = Command::new("nvme")
+                .args(["id-ctrl", "--output-format=json", &device])
+                .output()
+                .ok()
+                .and_then(|out| serde_json::from_slice::<Value>(&out.stdout).ok())
+                .and_then(|json| find_number(&json, "sanicap"))
+                .unwrap_or(0);
+            return nvme_method(sanicap);
+        }
+        let discard_max = std::fs::read_to_string(format!("/sys/block/{}/queue/discard_max_bytes", name))
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        if discard_max > 0 {
+            return EraseMethod::Discard;
+        }
+    }
+    EraseMethod::ZeroOut
+}
+
+/// Namespaces on the controller of NVMe namespace `name` (e.g. "nvme0n1")
+fn controller_namespaces(name: &str) -> Option<usize> {
+    let entries = std::fs::read_dir(format!("/sys/block/{}/device", name)).ok()?;
+    Some(
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| is_namespace(&entry.file_name().to_string_lossy()))
+            .count(),
+    )
+}
+
+/// Whether a controller's sysfs entry is a namespace block device
+/// ("nvme0n2"), rather than a partition, path or char device
+fn is_namespace(entry: &str) -> bool {
+    let Some((controller, namespace)) =
+        entry.strip_prefix("nvme").and_then(|rest| rest.split_once('n'))
+    else {
+        return false;
+    };
+    let numeric = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    numeric(controller) && numeric(namespace)
+}
+
+fn nvme_method(sanicap: u64) -> EraseMethod {
+    if sanicap & SANICAP_CRYPTO_ERASE != 0 {
+        EraseMethod::NvmeCryptoErase
+    } else if sanicap & SANICAP_BLOCK_ERASE != 0 {
+        EraseMethod::NvmeBlockErase
+    } else {
+        EraseMethod::NvmeFormat
+    }
+}
+
+/// State of a sanitize, from `nvme sanitize-log --output-format=json`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanitizeStatus {
+    /// Progress out of `SANITIZE_PROGRESS_MAX`
+    Running(u64),
+    Done,
+    Failed,
+}
+
+/// Read the sanitize log; nvme-cli nests it under the device name, and newer
+/// versions wrap numbers as {"value": n}
+pub fn parse_sanitize_log(json: &Value) -> Option<SanitizeStatus> {
+    let sstat = find_number(json, "sstat")?;
+    // Low three bits: 1 done, 2 in progress, 3 failed, 4 done without deallocation
+    Some(match sstat & 0x7 {
+        2 => SanitizeStatus::Running(find_number(json, "sprog").unwrap_or(0)),
+        3 => SanitizeStatus::Failed,
+        1 | 4 => SanitizeStatus::Done,
+        _ => return None,
+    })
+}
+
+/// First number called `key` anywhere in `json`
+fn find_number(json: &Value, key: &str) -> Option<u64> {
+    match json {
+        Value::Object(map) => map
+            .get(key)
+            .and_then(|v| v.as_u64().or_else(|| v["value"].as_u64()))
+            .or_else(|| map.values().find_map(|v| find_number(v, key))),
+        Value::Array(items) => items.iter().find_map(|v| find_number(v, key)),
+        _ => None,
+    }
+}
+
+/// Bytes done after a `blkdiscard --verbose` line such as
+/// "/dev/sda: Zero-filled 1073741824 bytes from the offset 2147483648"
+pub fn parse_blkdiscard_line(line: &str) -> Option<u64> {
+    let (_, rest) = line.split_once(": ")?;
+    let mut words = rest.split_whitespace();
+    let _verb = words.next()?;
+    let length: u64 = words.next()?.parse().ok()?;
+    let offset: u64 = words.last()?.parse().ok()?;
+    Some(offset + length)
+}
+
+/// Share of `total` done, with time left at the rate so far; never 100
+pub fn erase_progress(done: u64, total: u64, elapsed: Duration) -> Option<StepProgress> {
+    if total == 0 || done == 0 {
+        return None;
+    }
+    let done = done.min(total);
+    let percent = (done as u128 * 100 / total as u128).min(99) as u16;
+    let eta = elapsed.as_secs_f64() * (total - done) as f64 / done as f64;
+    Some(StepProgress {
+        percent,
+        eta_secs: Some(eta as u64),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nvme_method() {
+        assert_eq!(nvme_method(0x3), EraseMethod::NvmeCryptoErase);
+        assert_eq!(nvme_method(0x2), EraseMethod::NvmeBlockErase);
+        assert_eq!(nvme_method(0), EraseMethod::NvmeFormat);
+        let (program, args) = EraseMethod::NvmeCryptoErase.command("/dev/nvme0n1");
+        assert_eq!(program, "nvme");
+        assert_eq!(args, vec!["sanitize", "--sanact=4", "/dev/nvme0n1"]);
+        let (program, args) = EraseMethod::ZeroOut.command("/dev/sda");
+        assert_eq!(program, "blkdiscard");
+        assert_eq!(args.last().map(String::as_str), Some("/dev/sda"));
+    }
+
+    #[test]
+    fn test_is_namespace() {
+        assert!(is_namespace("nvme0n1"));
+        assert!(is_namespace("nvme1n12"));
+        for entry in ["nvme0n1p1", "nvme0c0n1", "ng0n1", "hwmon2", "nvme0", "nvme0n"] {
+            assert!(!is_namespace(entry), "{}", entry);
+        }
+    }
+
+    #[test]
+    fn test_parse_sanitize_log() {
+        let json: Value = serde_json::from_str(
+            r#"{ "nvme0": { "sprog": 16384, "sstat": 2, "cdw10_info": 4 } }"#,
+        )
+        .unwrap();
+        assert_eq!(parse_sanitize_log(&json), Some(SanitizeStatus::Running(16384)));
+        let json: Value =
+            serde_json::from_str(r#"{ "sprog": 65535, "sstat": { "value": 257 } }"#).unwrap();
+        assert_eq!(parse_sanitize_log(&json), Some(SanitizeStatus::Done));
+        let json: Value = serde_json::from_str(r#"{ "sstat": 3 }"#).unwrap();
+        assert_eq!(parse_sanitize_log(&json), Some(SanitizeStatus::Failed));
+        assert_eq!(parse_sanitize_log(&Value::Null), None);
+    }
+
+    #[test]
+    fn test_blkdiscard_progress() {
+        let line = "/dev/sda: Zero-filled 1073741824 bytes from the offset 2147483648";
+        assert_eq!(parse_blkdiscard_line(line), Some(3221225472));
+        assert_eq!(parse_blkdiscard_line("blkdiscard: cannot open /dev/sdz"), None);
+
+        let progress = erase_progress(1, 4, Duration::from_secs(60)).unwrap();
+        assert_eq!(progress.percent, 25);
+        assert_eq!(progress.eta_secs, Some(180));
+        assert_eq!(erase_progress(4, 4, Duration::from_secs(60)).unwrap().percent, 99);
+        assert_eq!(erase_progress(0, 4, Duration::from_secs(1)), None);
+    }
+}
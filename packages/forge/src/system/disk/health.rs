@@ -0,0 +1,177 @@
+//! Drive health of install targets
+//!
+//! SATA/SAS drives are read with `smartctl -H -A --json`, NVMe drives with
+//! `nvme smart-log --output-format=json` (both ship on the Forge ISO). A
+//! drive that reports nothing, e.g. behind a USB bridge, has no health.
+
+use serde_json::Value;
+use std::process::Command;
+
+/// SMART attribute ids counting sectors that went bad
+const REALLOCATED_SECTORS_ID: u64 = 5;
+const PENDING_SECTORS_ID: u64 = 197;
+
+/// Share of its rated endurance an NVMe drive may use before it is flagged
+const NVME_WEAR_WARNING_PERCENT: u64 = 90;
+
+/// What the drive reports about itself
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiskHealth {
+    /// Overall self-assessment (NVMe: no critical warning bits set)
+    pub passed: Option<bool>,
+    pub reallocated_sectors: Option<u64>,
+    pub pending_sectors: Option<u64>,
+    /// NVMe: percentage of the rated endurance used, may exceed 100
+    pub wear_percent: Option<u64>,
+    pub media_errors: Option<u64>,
+}
+
+impl DiskHealth {
+    /// Reasons not to trust this drive with a fresh install
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.passed == Some(false) {
+            warnings.push("drive reports a failing health check".to_string());
+        }
+        if let Some(count) = self.reallocated_sectors.filter(|&n| n > 0) {
+            warnings.push(format!("{} reallocated sectors", count));
+        }
+        if let Some(count) = self.pending_sectors.filter(|&n| n > 0) {
+            warnings.push(format!("{} sectors pending reallocation", count));
+        }
+        if let Some(wear) = self.wear_percent.filter(|&n| n >= NVME_WEAR_WARNING_PERCENT) {
+            warnings.push(format!("{}% of rated endurance used", wear));
+        }
+        if let Some(count) = self.media_errors.filter(|&n| n > 0) {
+            warnings.push(format!("{} media errors", count));
+        }
+        warnings
+    }
+
+    /// One line for the overview, e.g. "healthy, 3% worn"
+    pub fn summary(&self) -> String {
+        let warnings = self.warnings();
+        if !warnings.is_empty() {
+            return warnings.join(", ");
+        }
+        let mut parts = vec!["healthy".to_string()];
+        if let Some(wear) = self.wear_percent {
+            parts.push(format!("{}% worn", wear));
+        }
+        if self.reallocated_sectors.is_some() {
+            parts.push("no reallocated sectors".to_string());
+        }
+        parts.join(", ")
+    }
+}
+
+/// Read the health of `disk`; None when the tools or the drive give nothing
+pub fn check_disk_health(disk: &str) -> Option<DiskHealth> {
+    let device = std::fs::canonicalize(disk).unwrap_or_else(|_| disk.into());
+    let device = device.to_string_lossy();
+    let nvme = device.starts_with("/dev/nvme");
+    let output = if nvme {
+        Command::new("nvme")
+            .args(["smart-log", "--output-format=json", &device])
+            .output()
+    } else {
+        // smartctl's exit status is a bit mask that is non-zero for many
+        // healthy drives, so only the JSON counts
+        Command::new("smartctl").args(["-H", "-A", "--json", &device]).output()
+    }
+    .ok()?;
+
+    let json: Value = serde_json::from_slice(&output.stdout).ok()?;
+    let health = if nvme {
+        parse_nvme_smart_log(&json)
+    } else {
+        parse_smartctl(&json)
+    };
+    (health != DiskHealth::default()).then_some(health)
+}
+
+/// `smartctl --json` output (ATA attributes, or the NVMe log smartctl also reads)
+fn parse_smartctl(json: &Value) -> DiskHealth {
+    let attribute = |id: u64| {
+        json["ata_smart_attributes"]["table"]
+            .as_array()?
+            .iter()
+            .find(|attr| attr["id"].as_u64() == Some(id))?["raw"]["value"]
+            .as_u64()
+    };
+    let nvme = &json["nvme_smart_health_information_log"];
+    DiskHealth {
+        passed: json["smart_status"]["passed"].as_bool(),
+        reallocated_sectors: attribute(REALLOCATED_SECTORS_ID),
+        pending_sectors: attribute(PENDING_SECTORS_ID),
+        wear_percent: nvme["percentage_used"].as_u64(),
+        media_errors: nvme["media_errors"].as_u64(),
+    }
+}
+
+/// `nvme smart-log --output-format=json` output
+fn parse_nvme_smart_log(json: &Value) -> DiskHealth {
+    // Newer nvme-cli nests the bit field as {"value": n, ...}
+    let critical_warning = json["critical_warning"]
+        .as_u64()
+        .or_else(|| json["critical_warning"]["value"].as_u64());
+    DiskHealth {
+        passed: critical_warning.map(|bits| bits == 0),
+        reallocated_sectors: None,
+        pending_sectors: None,
+        wear_percent: json["percent_used"]
+            .as_u64()
+            .or_else(|| json["percentage_used"].as_u64()),
+        media_errors: json["media_errors"].as_u64(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_smartctl_ata() {
+        let json: Value = serde_json::from_str(
+            r#"{
+              "smart_status": { "passed": true },
+              "ata_smart_attributes": { "table": [
+                { "id": 5, "name": "Reallocated_Sector_Ct", "raw": { "value": 24 } },
+                { "id": 9, "name": "Power_On_Hours", "raw": { "value": 31000 } },
+                { "id": 197, "name": "Current_Pending_Sector", "raw": { "value": 0 } }
+              ] }
+            }"#,
+        )
+        .unwrap();
+        let health = parse_smartctl(&json);
+        assert_eq!(health.passed, Some(true));
+        assert_eq!(health.reallocated_sectors, Some(24));
+        assert_eq!(health.pending_sectors, Some(0));
+        assert_eq!(health.warnings(), vec!["24 reallocated sectors"]);
+    }
+
+    #[test]
+    fn test_parse_nvme_smart_log() {
+        let json: Value = serde_json::from_str(
+            r#"{ "critical_warning": 0, "percent_used": 3, "media_errors": 0 }"#,
+        )
+        .unwrap();
+        let health = parse_nvme_smart_log(&json);
+        assert!(health.warnings().is_empty());
+        assert_eq!(health.summary(), "healthy, 3% worn");
+
+        let json: Value = serde_json::from_str(
+            r#"{ "critical_warning": { "value": 4 }, "percent_used": 97, "media_errors": 2 }"#,
+        )
+        .unwrap();
+        let health = parse_nvme_smart_log(&json);
+        assert_eq!(health.passed, Some(false));
+        assert_eq!(health.warnings().len(), 3);
+    }
+
+    #[test]
+    fn test_nothing_reported() {
+        assert_eq!(parse_smartctl(&Value::Null), DiskHealth::default());
+        assert_eq!(DiskHealth::default().summary(), "healthy");
+    }
+}
@@ -0,0 +1,212 @@
+//! Physical identity of disks (serial, WWN), to notice /dev name drift
+//!
+//! Kernel names like nvme0n1 follow probe order and can swap after a drive is
+//! added or moved. create-host records the target's serial and WWN in
+//! host-info.json; install and update compare them with whatever the
+//! configured /dev path points at now.
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// Serial number and World Wide Name of a drive, as lsblk reports them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskIdentity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wwn: Option<String>,
+}
+
+impl DiskIdentity {
+    pub fn is_empty(&self) -> bool {
+        self.serial.is_none() && self.wwn.is_none()
+    }
+
+    /// Whether both name the same drive; the WWN wins when both have one
+    pub fn same_disk(&self, other: &DiskIdentity) -> bool {
+        match (&self.wwn, &other.wwn) {
+            (Some(a), Some(b)) => a == b,
+            _ => matches!((&self.serial, &other.serial), (Some(a), Some(b)) if a == b),
+        }
+    }
+
+    /// e.g. "serial S6B0NL0T123456, WWN eui.002538b231b1a2c3"
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(serial) = &self.serial {
+            parts.push(format!("serial {}", serial));
+        }
+        if let Some(wwn) = &self.wwn {
+            parts.push(format!("WWN {}", wwn));
+        }
+        if parts.is_empty() {
+            return "no serial or WWN".to_string();
+        }
+        parts.join(", ")
+    }
+}
+
+#[derive(Deserialize)]
+struct LsblkIdentities {
+    blockdevices: Vec<LsblkIdentity>,
+}
+
+#[derive(Deserialize)]
+struct LsblkIdentity {
+    path: String,
+    serial: Option<String>,
+    wwn: Option<String>,
+}
+
+/// Serial and WWN of every whole disk, by /dev path
+pub fn list_disk_identities() -> Vec<(String, DiskIdentity)> {
+    Command::new("lsblk")
+        .args(["-d", "-J", "-o", "PATH,SERIAL,WWN"])
+        .output()
+        .ok()
+        .and_then(|output| parse_lsblk_identities(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+fn parse_lsblk_identities(json: &str) -> Option<Vec<(String, DiskIdentity)>> {
+    let clean = |value: Option<String>| {
+        value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+    };
+    let lsblk: LsblkIdentities = serde_json::from_str(json).ok()?;
+    Some(
+        lsblk
+            .blockdevices
+            .into_iter()
+            .map(|dev| {
+                let identity = DiskIdentity {
+                    serial: clean(dev.serial),
+                    wwn: clean(dev.wwn),
+                };
+                (dev.path, identity)
+            })
+            .collect(),
+    )
+}
+
+/// Identity of the disk at `path` (symlinks such as /dev/disk/by-id resolved)
+pub fn disk_identity(path: &str) -> Option<DiskIdentity> {
+    let device = resolve_device(path);
+    list_disk_identities()
+        .into_iter()
+        .find(|(p, _)| *p == device)
+        .map(|(_, identity)| identity)
+        .filter(|identity| !identity.is_empty())
+}
+
+fn resolve_device(path: &str) -> String {
+    std::fs::canonicalize(path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// The main disk's /dev path from a host's disko config
+pub fn configured_disk_device(host_disko: &str) -> Option<String> {
+    host_disko.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix("disko.devices.disk.main.device")?;
+        let value = rest.trim_start().strip_prefix('=')?.trim();
+        Some(value.strip_prefix('"')?.split('"').next()?.to_string())
+    })
+}
+
+/// Compare the disk recorded for a host with what `path` is now
+pub fn check_disk_drift(recorded: &DiskIdentity, path: &str) -> Option<String> {
+    drift_warning(recorded, &resolve_device(path), &list_disk_identities())
+}
+
+/// Warning when `path` is no longer the recorded disk, naming where it went
+fn drift_warning(
+    recorded: &DiskIdentity,
+    path: &str,
+    disks: &[(String, DiskIdentity)],
+) -> Option<String> {
+    if recorded.is_empty() {
+        return None;
+    }
+    let current = disks.iter().find(|(p, _)| p == path).map(|(_, id)| id);
+    match current {
+        Some(current) if current.is_empty() || recorded.same_disk(current) => return None,
+        _ => {}
+    }
+    let moved = disks.iter().find(|(_, id)| recorded.same_disk(id)).map(|(p, _)| p);
+    Some(match (current, moved) {
+        (_, Some(now)) => format!(
+            "{} is not the recorded disk ({}), which is now {}",
+            path,
+            recorded.describe(),
+            now
+        ),
+        (Some(_), None) => format!(
+            "{} is not the recorded disk ({}), which is not attached",
+            path,
+            recorded.describe()
+        ),
+        (None, None) => format!(
+            "{} does not exist and the recorded disk ({}) is not attached",
+            path,
+            recorded.describe()
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(serial: &str, wwn: Option<&str>) -> DiskIdentity {
+        DiskIdentity {
+            serial: Some(serial.to_string()),
+            wwn: wwn.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_lsblk_identities() {
+        let json = r#"{"blockdevices": [
+            {"path": "/dev/nvme0n1", "serial": "S6B0NL0T123456", "wwn": "eui.002538b231b1a2c3"},
+            {"path": "/dev/sda", "serial": "  WD-WX12  ", "wwn": null},
+            {"path": "/dev/vda", "serial": null, "wwn": ""}
+        ]}"#;
+        let disks = parse_lsblk_identities(json).unwrap();
+        assert_eq!(disks[0].1, id("S6B0NL0T123456", Some("eui.002538b231b1a2c3")));
+        assert_eq!(disks[1].1, id("WD-WX12", None));
+        assert!(disks[2].1.is_empty());
+    }
+
+    #[test]
+    fn test_drift_warning() {
+        let os = id("OS-1", Some("eui.1"));
+        let data = id("DATA-2", Some("eui.2"));
+        let disks = vec![
+            ("/dev/nvme0n1".to_string(), data.clone()),
+            ("/dev/nvme1n1".to_string(), os.clone()),
+        ];
+        assert_eq!(drift_warning(&os, "/dev/nvme1n1", &disks), None);
+        assert!(drift_warning(&os, "/dev/nvme0n1", &disks)
+            .unwrap()
+            .ends_with("which is now /dev/nvme1n1"));
+
+        let gone = id("OLD-3", None);
+        assert!(drift_warning(&gone, "/dev/nvme0n1", &disks)
+            .unwrap()
+            .contains("not attached"));
+        assert_eq!(drift_warning(&DiskIdentity::default(), "/dev/sdz", &disks), None);
+    }
+
+    #[test]
+    fn test_configured_disk_device() {
+        let disko = "{\n  disko.devices.disk.main.device = \"/dev/nvme0n1\";\n}\n";
+        assert_eq!(configured_disk_device(disko).as_deref(), Some("/dev/nvme0n1"));
+        assert_eq!(configured_disk_device("{ imports = [ ./zfs.nix ]; }"), None);
+    }
+
+    #[test]
+    fn test_same_disk_prefers_wwn() {
+        assert!(!id("A", Some("eui.1")).same_disk(&id("A", Some("eui.2"))));
+        assert!(id("A", None).same_disk(&id("A", Some("eui.2"))));
+    }
+}
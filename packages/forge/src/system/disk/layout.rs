@@ -0,0 +1,341 @@
+//! Partition sizes for the single-disk UEFI layout (modules/disko/default.nix)
+//!
+//! Edited on the install wizard's partition step and written into the host's
+//! modules/disko/<host>.nix as `diskLayout` options (see subvolumes.nix). The
+//! host file is read as a list of `diskLayout.<key> = <value>;` lines: the
+//! sizes it already sets prefill the editor, and applying an edited layout
+//! replaces exactly those lines, leaving the rest of the file alone.
+
+use anyhow::{bail, Result};
+
+use crate::constants::host_dir_paths;
+
+/// The `diskLayout` options this model owns
+const SIZE_KEYS: &[&str] = &["espSize", "rootSize", "swapfileSize", "swapPartitionSize"];
+
+/// Comment heading the sizes in a host's disko file
+pub const HOST_CONFIG_MARKER: &str = "# Partition sizes, set on forge install's partition step";
+
+pub const DEFAULT_ESP_GIB: u64 = 2;
+pub const MIN_ESP_GIB: u64 = 1;
+pub const MAX_ESP_GIB: u64 = 8;
+/// Smallest root partition that still holds a desktop closure and a few generations
+pub const MIN_ROOT_GIB: u64 = 32;
+/// Smallest /home partition worth splitting off
+pub const MIN_HOME_GIB: u64 = 10;
+const DEFAULT_SWAP_GIB: u64 = 8;
+
+/// Swap on the disk, beyond the zram every host has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiskSwap {
+    #[default]
+    None,
+    /// Swapfile in an @swap subvolume
+    File,
+    /// Encrypted partition, usable for hibernation
+    Partition,
+}
+
+impl DiskSwap {
+    pub fn next(self) -> Self {
+        match self {
+            DiskSwap::None => DiskSwap::File,
+            DiskSwap::File => DiskSwap::Partition,
+            DiskSwap::Partition => DiskSwap::None,
+        }
+    }
+}
+
+impl std::fmt::Display for DiskSwap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiskSwap::None => write!(f, "none (zram only)"),
+            DiskSwap::File => write!(f, "swapfile"),
+            DiskSwap::Partition => write!(f, "partition (hibernation)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionLayout {
+    pub esp_gib: u64,
+    /// None: root fills the disk and /home is a subvolume
+    pub root_gib: Option<u64>,
+    pub swap: DiskSwap,
+    /// Used unless `swap` is None
+    pub swap_gib: u64,
+}
+
+impl Default for PartitionLayout {
+    fn default() -> Self {
+        Self {
+            esp_gib: DEFAULT_ESP_GIB,
+            root_gib: None,
+            swap: DiskSwap::None,
+            swap_gib: DEFAULT_SWAP_GIB,
+        }
+    }
+}
+
+impl PartitionLayout {
+    /// The sizes a host disko file sets; defaults for the rest
+    pub fn from_disko(content: &str) -> Self {
+        let mut layout = Self::default();
+        for (key, value) in disk_layout_options(content) {
+            let Some(gib) = parse_gib(value) else {
+                continue;
+            };
+            match key {
+                "espSize" => layout.esp_gib = gib,
+                "rootSize" => layout.root_gib = Some(gib),
+                "swapfileSize" => (layout.swap, layout.swap_gib) = (DiskSwap::File, gib),
+                "swapPartitionSize" => (layout.swap, layout.swap_gib) = (DiskSwap::Partition, gib),
+                _ => {}
+            }
+        }
+        layout
+    }
+
+    /// Check the sizes fit on a disk of `disk_bytes`
+    pub fn validate(&self, disk_bytes: u64) -> Result<()> {
+        if !(MIN_ESP_GIB..=MAX_ESP_GIB).contains(&self.esp_gib) {
+            bail!("EFI partition must be {}-{} GiB", MIN_ESP_GIB, MAX_ESP_GIB);
+        }
+        if self.swap != DiskSwap::None && self.swap_gib == 0 {
+            bail!("Swap size must be at least 1 GiB");
+        }
+        let disk_gib = disk_bytes >> 30;
+        let swap_partition = match self.swap {
+            DiskSwap::Partition => self.swap_gib,
+            _ => 0,
+        };
+        let fixed = self.esp_gib + swap_partition;
+        match self.root_gib {
+            Some(root) if root < MIN_ROOT_GIB => {
+                bail!("Root partition must be at least {} GiB", MIN_ROOT_GIB)
+            }
+            Some(root) if fixed + root + MIN_HOME_GIB > disk_gib => bail!(
+                "Root of {} GiB leaves less than {} GiB for /home on this {} GiB disk",
+                root,
+                MIN_HOME_GIB,
+                disk_gib
+            ),
+            None if fixed + MIN_ROOT_GIB > disk_gib => bail!(
+                "EFI and swap partitions leave less than {} GiB for the root on this {} GiB disk",
+                MIN_ROOT_GIB,
+                disk_gib
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// `diskLayout` lines for the sizes that differ from the layout's defaults
+    fn options(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if self.esp_gib != DEFAULT_ESP_GIB {
+            lines.push(format!("  diskLayout.espSize = \"{}G\";", self.esp_gib));
+        }
+        if let Some(root) = self.root_gib {
+            lines.push(format!("  diskLayout.rootSize = \"{}G\";", root));
+        }
+        match self.swap {
+            DiskSwap::None => {}
+            DiskSwap::File => {
+                lines.push(format!("  diskLayout.swapfileSize = \"{}G\";", self.swap_gib))
+            }
+            DiskSwap::Partition => {
+                lines.push(format!("  diskLayout.swapPartitionSize = \"{}G\";", self.swap_gib))
+            }
+        }
+        lines
+    }
+
+    /// Rewrite a host disko file with these sizes
+    ///
+    /// Size options already in the file are removed along with the comment
+    /// right above them; the new ones go before the closing brace.
+    pub fn apply(&self, content: &str) -> String {
+        let lines: Vec<&str> = content.lines().collect();
+        let mut kept: Vec<&str> = Vec::with_capacity(lines.len());
+        for line in &lines {
+            let owned = parse_option(line).is_some_and(|(key, _)| SIZE_KEYS.contains(&key));
+            if !owned {
+                kept.push(line);
+                continue;
+            }
+            // Drop the comment heading it and the blank line before that
+            if kept.last().is_some_and(|l| l.trim_start().starts_with('#')) {
+                kept.pop();
+                if kept.last().is_some_and(|l| l.trim().is_empty()) {
+                    kept.pop();
+                }
+            }
+        }
+        let mut out = kept.join("\n");
+        out.push('\n');
+
+        let options = self.options();
+        if options.is_empty() {
+            return out;
+        }
+        let block = format!("\n  {}\n{}\n", HOST_CONFIG_MARKER, options.join("\n"));
+        match out.rfind('}') {
+            Some(end) => out.insert_str(end, &block),
+            None => out.push_str(&block),
+        }
+        out
+    }
+
+    /// One line for the overview
+    pub fn describe(&self) -> String {
+        let root = match self.root_gib {
+            Some(root) => format!("root {} GiB, /home the rest", root),
+            None => "root the rest (/home subvolume)".to_string(),
+        };
+        let swap = match self.swap {
+            DiskSwap::None => String::new(),
+            swap => format!(", {} {} GiB", swap, self.swap_gib),
+        };
+        format!("EFI {} GiB, {}{}", self.esp_gib, root, swap)
+    }
+}
+
+/// The host's modules/disko/<host>.nix from the local config checkout
+pub fn read_host_disko(hostname: &str) -> Option<String> {
+    host_dir_paths(hostname).into_iter().find_map(|host_dir| {
+        let repo = host_dir.parent()?.parent()?;
+        std::fs::read_to_string(repo.join(format!("modules/disko/{}.nix", hostname))).ok()
+    })
+}
+
+/// Swap an install of `hostname` gets: the edited layout's, else what its
+/// disko file in the local checkout sets
+pub fn host_swap(hostname: &str, edited: Option<&PartitionLayout>) -> DiskSwap {
+    edited
+        .map(|layout| layout.swap)
+        .or_else(|| read_host_disko(hostname).map(|c| PartitionLayout::from_disko(&c).swap))
+        .unwrap_or_default()
+}
+
+/// Whether a host disko file uses the single-disk UEFI btrfs layout these sizes apply to
+pub fn supports_size_editing(content: &str) -> bool {
+    content.contains("./default.nix") && !content.contains("./home-disk.nix")
+}
+
+/// `diskLayout.<key> = <value>;` lines of a disko file, in order
+fn disk_layout_options(content: &str) -> impl Iterator<Item = (&str, &str)> {
+    content.lines().filter_map(parse_option)
+}
+
+fn parse_option(line: &str) -> Option<(&str, &str)> {
+    let rest = line.trim().strip_prefix("diskLayout.")?;
+    let (key, value) = rest.split_once('=')?;
+    let value = value.trim().strip_suffix(';')?;
+    Some((key.trim(), value.trim()))
+}
+
+/// Whole GiB from a disko size such as "\"38G\"" or "\"1T\""
+pub fn parse_gib(value: &str) -> Option<u64> {
+    let value = value.trim().trim_matches('"');
+    let (number, unit) = value.split_at(value.find(|c: char| !c.is_ascii_digit())?);
+    let number: u64 = number.parse().ok()?;
+    match unit {
+        "G" | "GiB" => Some(number),
+        "T" | "TiB" => Some(number * 1024),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOST_DISKO: &str = r#"# Disko configuration for test
+{ ... }:
+
+{
+  imports = [ ./default.nix ];
+
+  disko.devices.disk.main.device = "/dev/nvme0n1";
+
+  # Encrypted swap partition, RAM plus margin for hibernation
+  diskLayout.swapPartitionSize = "38G";
+
+  # Btrfs mount options and partition labels
+  diskLayout.noatime = false;
+}
+"#;
+
+    #[test]
+    fn test_from_disko() {
+        let layout = PartitionLayout::from_disko(HOST_DISKO);
+        assert_eq!(layout.esp_gib, DEFAULT_ESP_GIB);
+        assert_eq!(layout.root_gib, None);
+        assert_eq!((layout.swap, layout.swap_gib), (DiskSwap::Partition, 38));
+        assert_eq!(PartitionLayout::from_disko("{ }\n"), PartitionLayout::default());
+    }
+
+    #[test]
+    fn test_apply_replaces_only_size_options() {
+        let layout = PartitionLayout {
+            esp_gib: 1,
+            root_gib: Some(200),
+            swap: DiskSwap::File,
+            swap_gib: 16,
+        };
+        let updated = layout.apply(HOST_DISKO);
+        assert!(!updated.contains("swapPartitionSize"));
+        assert!(!updated.contains("# Encrypted swap partition"));
+        assert!(updated.contains("  diskLayout.noatime = false;\n"));
+        assert!(updated.contains(HOST_CONFIG_MARKER));
+        assert!(updated.contains("  diskLayout.espSize = \"1G\";\n"));
+        assert!(updated.contains("  diskLayout.rootSize = \"200G\";\n"));
+        assert!(updated.ends_with("  diskLayout.swapfileSize = \"16G\";\n}\n"));
+        // Applying again reads back the same layout without piling up blocks
+        let again = layout.apply(&updated);
+        assert_eq!(again, updated);
+        assert_eq!(PartitionLayout::from_disko(&again), layout);
+
+        // Defaults write nothing
+        let plain = PartitionLayout::default().apply(HOST_DISKO);
+        assert!(!plain.contains("diskLayout.swap"));
+        assert!(plain.contains("diskLayout.noatime"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let disk = 500u64 << 30;
+        assert!(PartitionLayout::default().validate(disk).is_ok());
+        let mut layout = PartitionLayout {
+            root_gib: Some(100),
+            ..Default::default()
+        };
+        assert!(layout.validate(disk).is_ok());
+        layout.root_gib = Some(495);
+        assert!(layout.validate(disk).is_err());
+        layout.root_gib = Some(16);
+        assert!(layout.validate(disk).is_err());
+        layout.root_gib = None;
+        layout.esp_gib = 0;
+        assert!(layout.validate(disk).is_err());
+        layout.esp_gib = 2;
+        layout.swap = DiskSwap::Partition;
+        layout.swap_gib = 480;
+        assert!(layout.validate(disk).is_err());
+    }
+
+    #[test]
+    fn test_parse_gib() {
+        assert_eq!(parse_gib("\"38G\""), Some(38));
+        assert_eq!(parse_gib("\"1T\""), Some(1024));
+        assert_eq!(parse_gib("\"512M\""), None);
+        assert_eq!(parse_gib("null"), None);
+    }
+
+    #[test]
+    fn test_supports_size_editing() {
+        assert!(supports_size_editing(HOST_DISKO));
+        assert!(!supports_size_editing("{ imports = [ ./zfs.nix ]; }"));
+        assert!(!supports_size_editing("{ imports = [ ./default.nix ./home-disk.nix ]; }"));
+    }
+}
@@ -0,0 +1,1028 @@
+//! Disk enumeration utilities
+
+pub mod erase;
+pub mod health;
+pub mod identity;
+pub mod layout;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+/// Detected operating system type on a partition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OsType {
+    NixOS,
+    Fedora,
+    Ubuntu,
+    Debian,
+    Arch,
+    Windows,
+    Other(String),
+    Unknown,
+}
+
+impl std::fmt::Display for OsType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OsType::NixOS => write!(f, "NixOS"),
+            OsType::Fedora => write!(f, "Fedora"),
+            OsType::Ubuntu => write!(f, "Ubuntu"),
+            OsType::Debian => write!(f, "Debian"),
+            OsType::Arch => write!(f, "Arch"),
+            OsType::Windows => write!(f, "Windows"),
+            OsType::Other(name) => write!(f, "{}", name),
+            OsType::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// Information about a partition on a disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartitionInfo {
+    pub path: String,
+    pub size: String,
+    pub fstype: String,
+    pub label: Option<String>,
+    pub os_type: Option<OsType>,
+}
+
+/// Information about a disk device
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskInfo {
+    pub path: String,
+    pub size: String,
+    pub size_bytes: u64,
+    pub model: Option<String>,
+    pub partitions: Vec<PartitionInfo>,
+    /// Largest unpartitioned region of a GPT disk, in bytes (0 if none)
+    pub free_bytes: u64,
+    /// Stable /dev/disk/by-id path, None when udev has none for this disk
+    pub by_id: Option<String>,
+}
+
+impl DiskInfo {
+    /// Path written into generated configs: by-id when known, so a change in
+    /// kernel enumeration order (nvme0n1 vs nvme1n1) can't point at another disk
+    pub fn config_path(&self) -> &str {
+        self.by_id.as_deref().unwrap_or(&self.path)
+    }
+
+    /// Kernel name (`nvme0n1`), typed to confirm erasing a disk with data on it
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    /// Partitions holding a filesystem blkid recognised
+    pub fn existing_data(&self) -> Vec<&PartitionInfo> {
+        self.partitions.iter().filter(|p| !p.fstype.is_empty()).collect()
+    }
+
+    /// Whether `typed` names this disk (`nvme0n1` or `/dev/nvme0n1`)
+    pub fn confirms_name(&self, typed: &str) -> bool {
+        let typed = typed.trim();
+        typed == self.name() || typed == self.path
+    }
+}
+
+/// How the installer uses the selected disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiskMode {
+    /// Wipe the whole disk
+    #[default]
+    Wipe,
+    /// Keep existing partitions (e.g. Windows) and install into free space
+    Alongside,
+}
+
+impl std::fmt::Display for DiskMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiskMode::Wipe => write!(f, "Erase disk"),
+            DiskMode::Alongside => write!(f, "Install alongside"),
+        }
+    }
+}
+
+/// udev's stable names for whole disks and partitions
+const DISK_BY_ID_DIR: &str = "/dev/disk/by-id";
+
+/// Stable /dev/disk/by-id path of a whole disk, or `path` itself if it has none
+pub fn stable_disk_path(path: &str) -> String {
+    if path.starts_with(DISK_BY_ID_DIR) {
+        return path.to_string();
+    }
+    let (Ok(target), Ok(entries)) = (std::fs::canonicalize(path), std::fs::read_dir(DISK_BY_ID_DIR))
+    else {
+        return path.to_string();
+    };
+    let names: Vec<String> = entries
+        .flatten()
+        .filter(|entry| std::fs::canonicalize(entry.path()).is_ok_and(|p| p == target))
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    match pick_by_id_name(&names) {
+        Some(name) => format!("{}/{}", DISK_BY_ID_DIR, name),
+        None => path.to_string(),
+    }
+}
+
+/// Pick the most readable by-id name: bus, model and serial (`nvme-Samsung_..._S6Z2...`)
+/// over EUI/WWN numbers, shortest first (NVMe also has a `_1` namespace alias)
+fn pick_by_id_name(names: &[String]) -> Option<&String> {
+    const BUSES: &[&str] = &["nvme-", "ata-", "scsi-", "usb-", "virtio-", "mmc-", "wwn-"];
+    names.iter().filter(|name| !name.contains("-part")).min_by_key(|name| {
+        let numeric = name.starts_with("nvme-eui.") || name.starts_with("nvme-nvme.");
+        let bus = BUSES.iter().position(|b| name.starts_with(b)).unwrap_or(BUSES.len());
+        (numeric, bus, name.len(), name.as_str())
+    })
+}
+
+/// Solid state or spinning disk, from the kernel's rotational flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StorageKind {
+    /// SSD or NVMe: TRIM applies
+    Ssd,
+    /// Spinning disk
+    Hdd,
+    #[default]
+    Unknown,
+}
+
+impl StorageKind {
+    /// Parse `/sys/class/block/<dev>/queue/rotational`
+    fn from_rotational(content: &str) -> Self {
+        match content.trim() {
+            "0" => StorageKind::Ssd,
+            "1" => StorageKind::Hdd,
+            _ => StorageKind::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for StorageKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageKind::Ssd => write!(f, "SSD/NVMe"),
+            StorageKind::Hdd => write!(f, "HDD"),
+            StorageKind::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+/// Whether `disk_path` (a /dev path, symlinks allowed) is an SSD or an HDD
+pub fn detect_storage_kind(disk_path: &str) -> StorageKind {
+    let device = std::fs::canonicalize(disk_path).unwrap_or_else(|_| disk_path.into());
+    let Some(name) = device.file_name() else {
+        return StorageKind::Unknown;
+    };
+    let rotational = std::path::Path::new("/sys/class/block")
+        .join(name)
+        .join("queue/rotational");
+    std::fs::read_to_string(rotational)
+        .map(|content| StorageKind::from_rotational(&content))
+        .unwrap_or_default()
+}
+
+/// What a disk holds in a multi-disk install
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiskRole {
+    /// ESP and the encrypted root filesystem
+    #[default]
+    Root,
+    /// Separate encrypted /home
+    Home,
+    /// Second copy of the root filesystem (btrfs raid1)
+    Mirror,
+}
+
+impl DiskRole {
+    pub const ALL: [DiskRole; 3] = [DiskRole::Root, DiskRole::Home, DiskRole::Mirror];
+
+    /// Next role in the cycle, used by the role assignment screen
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|r| *r == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            DiskRole::Root => "boot partition and encrypted root",
+            DiskRole::Home => "encrypted /home on its own disk",
+            DiskRole::Mirror => "raid1 copy of the root filesystem",
+        }
+    }
+}
+
+impl std::fmt::Display for DiskRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiskRole::Root => write!(f, "Root"),
+            DiskRole::Home => write!(f, "Home"),
+            DiskRole::Mirror => write!(f, "Mirror"),
+        }
+    }
+}
+
+/// A disk and the role it was given
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskAssignment {
+    pub disk: DiskInfo,
+    pub role: DiskRole,
+}
+
+/// Check a role assignment: one root disk, at most one home and one mirror
+pub fn validate_disk_roles(roles: &[DiskRole]) -> Option<String> {
+    let count = |role: DiskRole| roles.iter().filter(|r| **r == role).count();
+    match count(DiskRole::Root) {
+        0 => return Some("Assign the Root role to one disk".to_string()),
+        1 => {}
+        _ => return Some("Only one disk can hold the root filesystem".to_string()),
+    }
+    if count(DiskRole::Home) > 1 {
+        return Some("Only one disk can hold /home".to_string());
+    }
+    if count(DiskRole::Mirror) > 1 {
+        return Some("Only one disk can mirror the root filesystem".to_string());
+    }
+    None
+}
+
+/// Free space needed to install alongside another OS (2GB ESP + root)
+pub const MIN_ALONGSIDE_BYTES: u64 = 34 * 1024 * 1024 * 1024;
+
+/// GPT type GUID of an EFI system partition
+const ESP_TYPE_GUID: &str = "C12A7328-F81F-11D2-BA4B-00A0C93EC93B";
+
+/// GPT type GUID of a Microsoft basic data partition (NTFS/FAT data, i.e. Windows)
+const MS_DATA_TYPE_GUID: &str = "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7";
+
+/// Partitions are aligned to 1MiB, like every other partitioning tool
+const ALIGN_BYTES: u64 = 1024 * 1024;
+
+/// Partition table as reported by `sfdisk --json` (sizes in sectors)
+#[derive(Debug, Clone, Deserialize)]
+pub struct PartitionTable {
+    pub label: String,
+    #[serde(default)]
+    pub firstlba: u64,
+    #[serde(default)]
+    pub lastlba: u64,
+    #[serde(default = "default_sector_size")]
+    pub sectorsize: u64,
+    #[serde(default)]
+    pub partitions: Vec<TablePartition>,
+}
+
+/// One partition table entry
+#[derive(Debug, Clone, Deserialize)]
+pub struct TablePartition {
+    pub node: String,
+    pub start: u64,
+    pub size: u64,
+    #[serde(rename = "type", default)]
+    pub type_guid: String,
+    /// Partition GUID (PARTUUID)
+    #[serde(default)]
+    pub uuid: Option<String>,
+}
+
+/// Unpartitioned sectors, `start..=end`, aligned to 1MiB
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FreeRegion {
+    pub start: u64,
+    pub end: u64,
+    pub sector_size: u64,
+}
+
+impl FreeRegion {
+    pub fn size_bytes(&self) -> u64 {
+        (self.end + 1 - self.start) * self.sector_size
+    }
+}
+
+fn default_sector_size() -> u64 {
+    512
+}
+
+#[derive(Debug, Deserialize)]
+struct SfdiskOutput {
+    partitiontable: PartitionTable,
+}
+
+impl PartitionTable {
+    /// Parse `sfdisk --json` output
+    pub fn parse(json: &str) -> Result<Self> {
+        let output: SfdiskOutput = serde_json::from_str(json)?;
+        Ok(output.partitiontable)
+    }
+
+    /// Largest gap between partitions, None if nothing usable is left
+    pub fn largest_free_region(&self) -> Option<FreeRegion> {
+        let align = (ALIGN_BYTES / self.sectorsize).max(1);
+        let align_up = |lba: u64| lba.div_ceil(align) * align;
+
+        let mut partitions: Vec<&TablePartition> = self.partitions.iter().collect();
+        partitions.sort_by_key(|p| p.start);
+
+        let mut gaps = Vec::new();
+        let mut cursor = align_up(self.firstlba);
+        for partition in partitions {
+            if partition.start > cursor {
+                gaps.push((cursor, partition.start - 1));
+            }
+            cursor = cursor.max(align_up(partition.start + partition.size));
+        }
+        if self.lastlba >= cursor {
+            gaps.push((cursor, self.lastlba));
+        }
+
+        gaps.into_iter()
+            .filter(|(start, end)| end + 1 - start >= align)
+            .max_by_key(|(start, end)| end - start)
+            .map(|(start, end)| FreeRegion {
+                start,
+                end,
+                sector_size: self.sectorsize,
+            })
+    }
+
+    /// First EFI system partition on the disk
+    pub fn esp(&self) -> Option<&TablePartition> {
+        self.partitions
+            .iter()
+            .find(|p| p.type_guid.eq_ignore_ascii_case(ESP_TYPE_GUID))
+    }
+
+    /// Whether the disk holds Windows data partitions
+    pub fn has_windows(&self) -> bool {
+        self.partitions
+            .iter()
+            .any(|p| p.type_guid.eq_ignore_ascii_case(MS_DATA_TYPE_GUID))
+    }
+}
+
+/// Read a disk's partition table with sfdisk
+pub fn read_partition_table(disk: &str) -> Result<PartitionTable> {
+    let output = Command::new("sfdisk").args(["--json", disk]).output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "sfdisk failed on {}: {}",
+            disk,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    PartitionTable::parse(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Largest free region of a GPT disk in bytes; 0 for MBR or unpartitioned disks
+fn gpt_free_bytes(disk: &str) -> u64 {
+    read_partition_table(disk)
+        .ok()
+        .filter(|table| table.label == "gpt")
+        .and_then(|table| table.largest_free_region())
+        .map(|region| region.size_bytes())
+        .unwrap_or(0)
+}
+
+/// JSON structure for lsblk output
+#[derive(Debug, Deserialize)]
+struct LsblkOutput {
+    blockdevices: Vec<BlockDevice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockDevice {
+    name: String,
+    size: Option<String>,
+    model: Option<String>,
+    #[serde(rename = "type")]
+    device_type: Option<String>,
+    fstype: Option<String>,
+    label: Option<String>,
+    #[serde(default)]
+    children: Vec<BlockDevice>,
+}
+
+/// Get list of available disks (excluding loop, ram, rom, zram devices)
+pub fn get_available_disks() -> Result<Vec<DiskInfo>> {
+    // Use JSON output for reliable parsing (handles model names with spaces)
+    // Include children to get partition info
+    let output = Command::new("lsblk")
+        .args(["-J", "-o", "NAME,SIZE,MODEL,TYPE,FSTYPE,LABEL"])
+        .output()?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut disks = Vec::new();
+
+    // Parse JSON output
+    let lsblk: LsblkOutput = match serde_json::from_str(&stdout) {
+        Ok(v) => v,
+        Err(_) => {
+            // Fallback to text parsing if JSON fails
+            return get_available_disks_text_fallback();
+        }
+    };
+
+    for device in lsblk.blockdevices {
+        // Check if it's a disk
+        if device.device_type.as_deref() != Some("disk") {
+            continue;
+        }
+
+        let name = &device.name;
+
+        // Skip non-physical devices
+        if name.starts_with("loop")
+            || name.starts_with("ram")
+            || name.starts_with("zram")
+            || name.starts_with("sr")
+            || name.starts_with("fd")
+        {
+            continue;
+        }
+
+        let path = format!("/dev/{}", name);
+        let size = device.size.clone().unwrap_or_default();
+        let size_bytes = parse_size(&size);
+
+        // Clean up model name (remove extra whitespace)
+        let model = device
+            .model
+            .as_ref()
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty());
+
+        // Process partitions (children)
+        let partitions = process_partitions(&device.children);
+        let free_bytes = if partitions.is_empty() { 0 } else { gpt_free_bytes(&path) };
+
+        let by_id = Some(stable_disk_path(&path)).filter(|p| *p != path);
+        disks.push(DiskInfo {
+            path,
+            size,
+            size_bytes,
+            model,
+            partitions,
+            free_bytes,
+            by_id,
+        });
+    }
+
+    // Sort by size (largest first)
+    disks.sort_by_key(|d| std::cmp::Reverse(d.size_bytes));
+
+    Ok(disks)
+}
+
+/// Process partition children from lsblk output
+fn process_partitions(children: &[BlockDevice]) -> Vec<PartitionInfo> {
+    children
+        .iter()
+        .filter(|child| child.device_type.as_deref() == Some("part"))
+        .map(|child| {
+            let path = format!("/dev/{}", child.name);
+            let fstype = child.fstype.clone().unwrap_or_default();
+            let label = child.label.clone();
+
+            // Detect OS type based on filesystem and by probing
+            let os_type = detect_os_type(&path, &fstype);
+
+            PartitionInfo {
+                path,
+                size: child.size.clone().unwrap_or_default(),
+                fstype,
+                label,
+                os_type,
+            }
+        })
+        .collect()
+}
+
+/// Detect OS type on a partition
+fn detect_os_type(partition_path: &str, fstype: &str) -> Option<OsType> {
+    // NTFS is almost always Windows
+    if fstype == "ntfs" {
+        return Some(OsType::Windows);
+    }
+
+    // EFI partition - skip OS detection
+    if fstype == "vfat" {
+        return None;
+    }
+
+    // For Linux filesystems, try to detect the distro
+    if matches!(fstype, "ext4" | "ext3" | "btrfs" | "xfs" | "f2fs") {
+        return detect_linux_os(partition_path);
+    }
+
+    None
+}
+
+/// Try to detect Linux distro by mounting and checking /etc/os-release
+fn detect_linux_os(partition_path: &str) -> Option<OsType> {
+    use std::fs;
+    use std::path::Path;
+
+    // Create a unique temp mount point
+    let mount_point = format!("/tmp/forge-detect-{}", std::process::id());
+
+    // Create mount directory
+    if fs::create_dir_all(&mount_point).is_err() {
+        return None;
+    }
+
+    // Try to mount read-only
+    let mount_result = Command::new("mount")
+        .args(["-o", "ro,noexec,nosuid", partition_path, &mount_point])
+        .output();
+
+    let os_type = if mount_result.is_ok() && mount_result.unwrap().status.success() {
+        // Check for NixOS first (has /etc/nixos directory)
+        let nixos_path = Path::new(&mount_point).join("etc/nixos");
+        if nixos_path.exists() {
+            Some(OsType::NixOS)
+        } else {
+            // Check /etc/os-release
+            let os_release_path = Path::new(&mount_point).join("etc/os-release");
+            if let Ok(content) = fs::read_to_string(&os_release_path) {
+                parse_os_release(&content)
+            } else {
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Always unmount and cleanup
+    let _ = Command::new("umount").arg(&mount_point).output();
+    let _ = fs::remove_dir(&mount_point);
+
+    os_type
+}
+
+/// Parse /etc/os-release content to determine OS type
+fn parse_os_release(content: &str) -> Option<OsType> {
+    for line in content.lines() {
+        if let Some(id) = line.strip_prefix("ID=") {
+            let id = id.trim().trim_matches('"').to_lowercase();
+            return Some(match id.as_str() {
+                "nixos" => OsType::NixOS,
+                "fedora" => OsType::Fedora,
+                "ubuntu" => OsType::Ubuntu,
+                "debian" => OsType::Debian,
+                "arch" | "archlinux" => OsType::Arch,
+                "opensuse" | "opensuse-leap" | "opensuse-tumbleweed" => {
+                    OsType::Other("openSUSE".to_string())
+                }
+                "manjaro" => OsType::Other("Manjaro".to_string()),
+                "pop" => OsType::Other("Pop!_OS".to_string()),
+                "linuxmint" | "mint" => OsType::Other("Linux Mint".to_string()),
+                "elementary" => OsType::Other("elementary OS".to_string()),
+                "gentoo" => OsType::Other("Gentoo".to_string()),
+                "void" => OsType::Other("Void Linux".to_string()),
+                other if !other.is_empty() => OsType::Other(other.to_string()),
+                _ => OsType::Unknown,
+            });
+        }
+    }
+    None
+}
+
+/// Fallback text-based parsing for older lsblk versions without JSON support
+fn get_available_disks_text_fallback() -> Result<Vec<DiskInfo>> {
+    let output = Command::new("lsblk")
+        .args(["-dno", "NAME,SIZE,TYPE"])
+        .output()?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut disks = Vec::new();
+
+    for line in stdout.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 3 {
+            continue;
+        }
+
+        let name = parts[0];
+        let size = parts[1];
+        let disk_type = parts[2];
+
+        if disk_type != "disk" {
+            continue;
+        }
+
+        // Skip non-physical devices
+        if name.starts_with("loop")
+            || name.starts_with("ram")
+            || name.starts_with("zram")
+            || name.starts_with("sr")
+            || name.starts_with("fd")
+        {
+            continue;
+        }
+
+        let path = format!("/dev/{}", name);
+        let size_bytes = parse_size(size);
+        let by_id = Some(stable_disk_path(&path)).filter(|p| *p != path);
+
+        disks.push(DiskInfo {
+            path,
+            size: size.to_string(),
+            size_bytes,
+            model: None,       // Can't reliably parse model in text mode
+            partitions: vec![], // No partition info in text fallback mode
+            free_bytes: 0,
+            by_id,
+        });
+    }
+
+    // Sort by size (largest first)
+    disks.sort_by_key(|d| std::cmp::Reverse(d.size_bytes));
+
+    Ok(disks)
+}
+
+/// Parse size string like "1.8T" to bytes
+fn parse_size(size: &str) -> u64 {
+    let size = size.trim();
+    if size.is_empty() {
+        return 0;
+    }
+
+    let (num_str, unit) = if let Some(n) = size.strip_suffix('T') {
+        (n, 1024u64 * 1024 * 1024 * 1024)
+    } else if let Some(n) = size.strip_suffix('G') {
+        (n, 1024u64 * 1024 * 1024)
+    } else if let Some(n) = size.strip_suffix('M') {
+        (n, 1024u64 * 1024)
+    } else if let Some(n) = size.strip_suffix('K') {
+        (n, 1024u64)
+    } else {
+        (size, 1u64)
+    };
+
+    match num_str.parse::<f64>() {
+        Ok(num) => (num * unit as f64) as u64,
+        Err(e) => {
+            tracing::warn!("Failed to parse disk size '{}': {}", size, e);
+            0
+        }
+    }
+}
+
+/// Random 8-hex-digit `networking.hostId`, needed by ZFS to import its pools
+pub fn generate_host_id() -> String {
+    use std::io::Read;
+
+    let mut bytes = [0u8; 4];
+    let random = std::fs::File::open("/dev/urandom").and_then(|mut f| f.read_exact(&mut bytes));
+    let id = match random {
+        Ok(()) => u32::from_ne_bytes(bytes),
+        Err(e) => {
+            // Uniqueness per machine is all ZFS needs; the clock is good enough for that
+            tracing::warn!("Failed to read /dev/urandom, deriving hostId from the clock: {}", e);
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.subsec_nanos() ^ d.as_secs() as u32)
+                .unwrap_or(0)
+        }
+    };
+    format!("{:08x}", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SFDISK_WINDOWS: &str = r#"{
+       "partitiontable": {
+          "label": "gpt",
+          "id": "0D4C5E0E-7A3B-4B8E-9C2A-3F1B2E4D5C6A",
+          "device": "/dev/nvme0n1",
+          "unit": "sectors",
+          "firstlba": 34,
+          "lastlba": 1000215182,
+          "sectorsize": 512,
+          "partitions": [
+             {
+                "node": "/dev/nvme0n1p1", "start": 2048, "size": 204800,
+                "type": "C12A7328-F81F-11D2-BA4B-00A0C93EC93B",
+                "uuid": "8E3F1A2B-0C4D-4E5F-8A6B-7C8D9E0F1A2B", "name": "EFI system partition"
+             },
+             {
+                "node": "/dev/nvme0n1p2", "start": 206848, "size": 32768,
+                "type": "E3C9E316-0B5C-4DB8-817D-F92DF00215AE"
+             },
+             {
+                "node": "/dev/nvme0n1p3", "start": 239616, "size": 409600000,
+                "type": "EBD0A0A2-B9E5-4433-87C0-68B6B72699C7"
+             },
+             {
+                "node": "/dev/nvme0n1p4", "start": 998166528, "size": 2048000,
+                "type": "DE94BBA4-06D1-4D40-A16A-BFD50179D6AC"
+             }
+          ]
+       }
+    }"#;
+
+    #[test]
+    fn test_largest_free_region() {
+        let table = PartitionTable::parse(SFDISK_WINDOWS).unwrap();
+        // Between the shrunk Windows partition and the recovery partition
+        let region = table.largest_free_region().unwrap();
+        assert_eq!(region.start, 409839616);
+        assert_eq!(region.end, 998166527);
+        assert_eq!(region.size_bytes(), (998166528 - 409839616) * 512);
+        assert_eq!(table.esp().unwrap().node, "/dev/nvme0n1p1");
+        assert!(table.has_windows());
+
+        // A full disk has nothing left to offer
+        let full = PartitionTable {
+            label: "gpt".to_string(),
+            firstlba: 34,
+            lastlba: 2048 + 4095,
+            sectorsize: 512,
+            partitions: vec![TablePartition {
+                node: "/dev/sda1".to_string(),
+                start: 2048,
+                size: 4096,
+                type_guid: String::new(),
+                uuid: None,
+            }],
+        };
+        assert_eq!(full.largest_free_region(), None);
+        assert!(full.esp().is_none());
+        assert!(!full.has_windows());
+    }
+
+    #[test]
+    fn test_generate_host_id() {
+        let id = generate_host_id();
+        assert_eq!(id.len(), 8);
+        assert!(id.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+    }
+
+    #[test]
+    fn test_validate_disk_roles() {
+        use DiskRole::*;
+        assert_eq!(validate_disk_roles(&[Root, Home]), None);
+        assert_eq!(validate_disk_roles(&[Mirror, Root, Home]), None);
+        assert!(validate_disk_roles(&[Home, Mirror]).is_some());
+        assert!(validate_disk_roles(&[Root, Root]).is_some());
+        assert!(validate_disk_roles(&[Root, Home, Home]).is_some());
+        assert!(validate_disk_roles(&[Root, Mirror, Mirror]).is_some());
+        assert_eq!(Mirror.next(), Root);
+    }
+
+    #[test]
+    fn test_parse_size_terabytes() {
+        assert_eq!(parse_size("1T"), 1024 * 1024 * 1024 * 1024);
+        assert_eq!(parse_size("2T"), 2 * 1024 * 1024 * 1024 * 1024);
+        // Fractional terabytes
+        let size_1_8t = parse_size("1.8T");
+        assert!(size_1_8t > 1024 * 1024 * 1024 * 1024, "1.8T should be > 1TB");
+        assert!(size_1_8t < 2 * 1024 * 1024 * 1024 * 1024, "1.8T should be < 2TB");
+    }
+
+    #[test]
+    fn test_parse_size_gigabytes() {
+        assert_eq!(parse_size("500G"), 500 * 1024 * 1024 * 1024);
+        assert_eq!(parse_size("1G"), 1024 * 1024 * 1024);
+        // Fractional gigabytes
+        let size_1_5g = parse_size("1.5G");
+        assert!(size_1_5g > 1024 * 1024 * 1024, "1.5G should be > 1GB");
+        assert!(size_1_5g < 2 * 1024 * 1024 * 1024, "1.5G should be < 2GB");
+    }
+
+    #[test]
+    fn test_parse_size_megabytes() {
+        assert_eq!(parse_size("256M"), 256 * 1024 * 1024);
+        assert_eq!(parse_size("512M"), 512 * 1024 * 1024);
+        assert_eq!(parse_size("1M"), 1024 * 1024);
+    }
+
+    #[test]
+    fn test_parse_size_kilobytes() {
+        assert_eq!(parse_size("1K"), 1024);
+        assert_eq!(parse_size("512K"), 512 * 1024);
+    }
+
+    #[test]
+    fn test_parse_size_bytes() {
+        assert_eq!(parse_size("1024"), 1024);
+        assert_eq!(parse_size("512"), 512);
+    }
+
+    #[test]
+    fn test_parse_size_edge_cases() {
+        assert_eq!(parse_size(""), 0);
+        assert_eq!(parse_size("   "), 0);
+        assert_eq!(parse_size("invalid"), 0);
+        assert_eq!(parse_size("0G"), 0);
+    }
+
+    #[test]
+    fn test_os_type_display() {
+        assert_eq!(format!("{}", OsType::NixOS), "NixOS");
+        assert_eq!(format!("{}", OsType::Fedora), "Fedora");
+        assert_eq!(format!("{}", OsType::Ubuntu), "Ubuntu");
+        assert_eq!(format!("{}", OsType::Debian), "Debian");
+        assert_eq!(format!("{}", OsType::Arch), "Arch");
+        assert_eq!(format!("{}", OsType::Windows), "Windows");
+        assert_eq!(format!("{}", OsType::Other("Pop!_OS".to_string())), "Pop!_OS");
+        assert_eq!(format!("{}", OsType::Unknown), "Unknown");
+    }
+
+    #[test]
+    fn test_os_type_equality() {
+        assert_eq!(OsType::NixOS, OsType::NixOS);
+        assert_ne!(OsType::NixOS, OsType::Ubuntu);
+        assert_eq!(OsType::Other("Foo".to_string()), OsType::Other("Foo".to_string()));
+        assert_ne!(OsType::Other("Foo".to_string()), OsType::Other("Bar".to_string()));
+    }
+
+    #[test]
+    fn test_parse_os_release_nixos() {
+        let content = r#"ID=nixos
+NAME="NixOS"
+VERSION="23.11"
+"#;
+        let os_type = parse_os_release(content);
+        assert_eq!(os_type, Some(OsType::NixOS));
+    }
+
+    #[test]
+    fn test_parse_os_release_fedora() {
+        let content = r#"ID=fedora
+NAME="Fedora Linux"
+VERSION="39"
+"#;
+        let os_type = parse_os_release(content);
+        assert_eq!(os_type, Some(OsType::Fedora));
+    }
+
+    #[test]
+    fn test_parse_os_release_ubuntu() {
+        let content = r#"ID=ubuntu
+NAME="Ubuntu"
+VERSION="22.04"
+"#;
+        let os_type = parse_os_release(content);
+        assert_eq!(os_type, Some(OsType::Ubuntu));
+    }
+
+    #[test]
+    fn test_parse_os_release_arch() {
+        let content = r#"ID=arch
+NAME="Arch Linux"
+"#;
+        let os_type = parse_os_release(content);
+        assert_eq!(os_type, Some(OsType::Arch));
+    }
+
+    #[test]
+    fn test_parse_os_release_archlinux() {
+        let content = r#"ID=archlinux
+NAME="Arch Linux"
+"#;
+        let os_type = parse_os_release(content);
+        assert_eq!(os_type, Some(OsType::Arch));
+    }
+
+    #[test]
+    fn test_parse_os_release_other() {
+        let content = r#"ID=manjaro
+NAME="Manjaro Linux"
+"#;
+        let os_type = parse_os_release(content);
+        assert_eq!(os_type, Some(OsType::Other("Manjaro".to_string())));
+    }
+
+    #[test]
+    fn test_parse_os_release_quoted_id() {
+        let content = r#"ID="fedora"
+NAME="Fedora Linux"
+"#;
+        let os_type = parse_os_release(content);
+        assert_eq!(os_type, Some(OsType::Fedora));
+    }
+
+    #[test]
+    fn test_parse_os_release_empty() {
+        let content = "";
+        let os_type = parse_os_release(content);
+        assert_eq!(os_type, None);
+    }
+
+    #[test]
+    fn test_parse_os_release_no_id() {
+        let content = r#"NAME="Some Linux"
+VERSION="1.0"
+"#;
+        let os_type = parse_os_release(content);
+        assert_eq!(os_type, None);
+    }
+
+    #[test]
+    fn test_disk_info_clone() {
+        let disk = DiskInfo {
+            path: "/dev/nvme0n1".to_string(),
+            size: "1T".to_string(),
+            size_bytes: 1024 * 1024 * 1024 * 1024,
+            model: Some("Samsung SSD".to_string()),
+            partitions: vec![],
+            free_bytes: 0,
+            by_id: None,
+        };
+        let cloned = disk.clone();
+        assert_eq!(cloned.path, "/dev/nvme0n1");
+        assert_eq!(cloned.size, "1T");
+        assert_eq!(cloned.model, Some("Samsung SSD".to_string()));
+    }
+
+    #[test]
+    fn test_partition_info_clone() {
+        let partition = PartitionInfo {
+            path: "/dev/nvme0n1p1".to_string(),
+            size: "512M".to_string(),
+            fstype: "vfat".to_string(),
+            label: Some("EFI".to_string()),
+            os_type: None,
+        };
+        let cloned = partition.clone();
+        assert_eq!(cloned.path, "/dev/nvme0n1p1");
+        assert_eq!(cloned.fstype, "vfat");
+        assert_eq!(cloned.label, Some("EFI".to_string()));
+    }
+
+    #[test]
+    fn test_existing_data_and_name() {
+        let partition = |path: &str, fstype: &str| PartitionInfo {
+            path: path.to_string(),
+            size: "100G".to_string(),
+            fstype: fstype.to_string(),
+            label: None,
+            os_type: None,
+        };
+        let disk = DiskInfo {
+            path: "/dev/nvme0n1".to_string(),
+            size: "1T".to_string(),
+            size_bytes: 1 << 40,
+            model: None,
+            partitions: vec![partition("/dev/nvme0n1p1", "vfat"), partition("/dev/nvme0n1p2", "")],
+            free_bytes: 0,
+            by_id: None,
+        };
+        let found: Vec<_> = disk.existing_data().iter().map(|p| p.path.as_str()).collect();
+        assert_eq!(found, vec!["/dev/nvme0n1p1"]);
+        assert_eq!(disk.name(), "nvme0n1");
+        assert!(disk.confirms_name(" nvme0n1 "));
+        assert!(disk.confirms_name("/dev/nvme0n1"));
+        assert!(!disk.confirms_name("yes"));
+        assert!(!disk.confirms_name("nvme0n"));
+    }
+
+    #[test]
+    fn test_disk_info_equality() {
+        let disk1 = DiskInfo {
+            path: "/dev/sda".to_string(),
+            size: "500G".to_string(),
+            size_bytes: 500 * 1024 * 1024 * 1024,
+            model: None,
+            partitions: vec![],
+            free_bytes: 0,
+            by_id: None,
+        };
+        let disk2 = disk1.clone();
+        assert_eq!(disk1, disk2);
+    }
+
+    #[test]
+    fn test_storage_kind_from_rotational() {
+        assert_eq!(StorageKind::from_rotational("0\n"), StorageKind::Ssd);
+        assert_eq!(StorageKind::from_rotational("1\n"), StorageKind::Hdd);
+        assert_eq!(StorageKind::from_rotational(""), StorageKind::Unknown);
+    }
+
+    #[test]
+    fn test_pick_by_id_name() {
+        let names: Vec<String> = [
+            "nvme-eui.002538b231b1a2c3",
+            "nvme-Samsung_SSD_990_PRO_2TB_S6Z2NJ0W123456_1",
+            "nvme-Samsung_SSD_990_PRO_2TB_S6Z2NJ0W123456",
+            "nvme-Samsung_SSD_990_PRO_2TB_S6Z2NJ0W123456-part1",
+        ]
+        .map(String::from)
+        .to_vec();
+        assert_eq!(
+            pick_by_id_name(&names).map(String::as_str),
+            Some("nvme-Samsung_SSD_990_PRO_2TB_S6Z2NJ0W123456")
+        );
+
+        let names = ["wwn-0x5000c500a1b2c3d4", "ata-ST2000DM008_ZFL1ABCD"].map(String::from);
+        assert_eq!(pick_by_id_name(&names).map(String::as_str), Some("ata-ST2000DM008_ZFL1ABCD"));
+        assert_eq!(pick_by_id_name(&[]), None);
+    }
+}
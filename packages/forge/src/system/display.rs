@@ -0,0 +1,191 @@
+//! Monitor layout capture
+//!
+//! Reads the current output layout from `wlr-randr` (wlroots/Hyprland
+//! sessions) or `xrandr` (X11) so a new host can start with the right
+//! resolution, refresh rate and scaling. Nothing is captured from a bare TTY.
+
+use serde::Deserialize;
+use std::process::Command;
+
+/// A connected, enabled output
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// Refresh rate in Hz
+    pub refresh: f64,
+    pub x: i32,
+    pub y: i32,
+    pub scale: f64,
+}
+
+/// Capture the current monitor layout, empty if no display server is reachable
+pub fn detect_monitors() -> Vec<MonitorInfo> {
+    if let Some(output) = run_display_tool("wlr-randr", &["--json"]) {
+        let monitors = parse_wlr_randr_json(&output);
+        if !monitors.is_empty() {
+            return monitors;
+        }
+    }
+
+    if let Some(output) = run_display_tool("xrandr", &["--query"]) {
+        return parse_xrandr(&output);
+    }
+
+    Vec::new()
+}
+
+fn run_display_tool(cmd: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(cmd).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[derive(Deserialize)]
+struct WlrOutput {
+    name: String,
+    #[serde(default)]
+    enabled: bool,
+    #[serde(default)]
+    modes: Vec<WlrMode>,
+    position: Option<WlrPosition>,
+    scale: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct WlrMode {
+    width: u32,
+    height: u32,
+    refresh: f64,
+    #[serde(default)]
+    current: bool,
+}
+
+#[derive(Deserialize)]
+struct WlrPosition {
+    x: i32,
+    y: i32,
+}
+
+/// Parse `wlr-randr --json` output
+fn parse_wlr_randr_json(json: &str) -> Vec<MonitorInfo> {
+    let Ok(outputs) = serde_json::from_str::<Vec<WlrOutput>>(json) else {
+        return Vec::new();
+    };
+
+    outputs
+        .into_iter()
+        .filter(|o| o.enabled)
+        .filter_map(|o| {
+            let mode = o.modes.iter().find(|m| m.current)?;
+            let (x, y) = o.position.map(|p| (p.x, p.y)).unwrap_or((0, 0));
+            Some(MonitorInfo {
+                name: o.name,
+                width: mode.width,
+                height: mode.height,
+                refresh: mode.refresh,
+                x,
+                y,
+                scale: o.scale.unwrap_or(1.0),
+            })
+        })
+        .collect()
+}
+
+/// Parse `xrandr --query` output (X11 has no per-output scale, assume 1.0)
+fn parse_xrandr(output: &str) -> Vec<MonitorInfo> {
+    let mut monitors: Vec<MonitorInfo> = Vec::new();
+    let mut current: Option<MonitorInfo> = None;
+
+    for line in output.lines() {
+        if !line.starts_with(' ') {
+            monitors.extend(current.take());
+            // "DP-1 connected primary 3840x2160+0+0 (normal left ...) 600mm x 340mm"
+            let mut parts = line.split_whitespace();
+            let (Some(name), Some("connected")) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let Some(geometry) = parts.find(|p| p.contains('x') && p.contains('+')) else {
+                continue; // connected but disabled
+            };
+            if let Some((width, height, x, y)) = parse_xrandr_geometry(geometry) {
+                current = Some(MonitorInfo {
+                    name: name.to_string(),
+                    width,
+                    height,
+                    refresh: 60.0,
+                    x,
+                    y,
+                    scale: 1.0,
+                });
+            }
+        } else if let Some(monitor) = current.as_mut() {
+            // "   3840x2160     60.00 +  165.00*"
+            let mut parts = line.split_whitespace();
+            if parts.next() != Some(&format!("{}x{}", monitor.width, monitor.height)) {
+                continue;
+            }
+            if let Some(rate) = parts.find(|p| p.contains('*')) {
+                if let Ok(refresh) = rate.trim_end_matches(['*', '+']).parse() {
+                    monitor.refresh = refresh;
+                }
+            }
+        }
+    }
+    monitors.extend(current);
+    monitors
+}
+
+/// Parse "3840x2160+0+0" into (width, height, x, y)
+fn parse_xrandr_geometry(geometry: &str) -> Option<(u32, u32, i32, i32)> {
+    let (size, offset) = geometry.split_once('+')?;
+    let (width, height) = size.split_once('x')?;
+    let (x, y) = offset.split_once('+')?;
+    Some((width.parse().ok()?, height.parse().ok()?, x.parse().ok()?, y.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wlr_randr_json() {
+        let json = r#"[
+            {"name": "DP-1", "enabled": true, "scale": 1.5,
+             "position": {"x": 0, "y": 0},
+             "modes": [
+                {"width": 3840, "height": 2160, "refresh": 60.0, "preferred": true, "current": false},
+                {"width": 3840, "height": 2160, "refresh": 165.0, "preferred": false, "current": true}
+             ]},
+            {"name": "HDMI-A-1", "enabled": false, "modes": []}
+        ]"#;
+        let monitors = parse_wlr_randr_json(json);
+        assert_eq!(monitors.len(), 1);
+        assert_eq!(monitors[0].name, "DP-1");
+        assert_eq!(monitors[0].refresh, 165.0);
+        assert_eq!(monitors[0].scale, 1.5);
+        assert!(parse_wlr_randr_json("not json").is_empty());
+    }
+
+    #[test]
+    fn test_parse_xrandr() {
+        let output = "\
+Screen 0: minimum 8 x 8, current 5760 x 2160, maximum 32767 x 32767
+DP-1 connected primary 3840x2160+0+0 (normal left inverted right x axis y axis) 600mm x 340mm
+   3840x2160     60.00 +  143.99*
+   2560x1440    143.97
+HDMI-1 connected 1920x1080+3840+0 (normal left inverted right x axis y axis) 530mm x 300mm
+   1920x1080     60.00*+
+HDMI-2 disconnected (normal left inverted right x axis y axis)
+";
+        let monitors = parse_xrandr(output);
+        assert_eq!(monitors.len(), 2);
+        assert_eq!(monitors[0].name, "DP-1");
+        assert_eq!(monitors[0].refresh, 143.99);
+        assert_eq!((monitors[1].x, monitors[1].y), (3840, 0));
+        assert_eq!(monitors[1].refresh, 60.0);
+    }
+}
@@ -0,0 +1,168 @@
+//! Line-based edits of attribute sets in flake.nix
+//!
+//! flake.nix is hand-written Nix, so forge edits it as text: an attribute is
+//! found by its name at the top level of a block like `nixosConfigurations = {`
+//! and spans to the `;` that closes it. Braces inside strings or comments are
+//! not expected in the blocks forge touches.
+
+/// Opening line of the hosts block
+pub const NIXOS_CONFIGURATIONS: &str = "nixosConfigurations = {";
+
+/// Opening line of the flake inputs block
+pub const INPUTS: &str = "inputs = {";
+
+/// Opening line of standalone home-manager configurations (`"john@kraken" = ...`)
+pub const HOME_CONFIGURATIONS: &str = "homeConfigurations = {";
+
+/// First and last line of the attribute `name` at the top level of `block`
+pub fn attr_span(lines: &[&str], block: &str, name: &str) -> Option<(usize, usize)> {
+    block_attrs(lines, block)
+        .into_iter()
+        .find(|(attr, _, _)| attr == name)
+        .map(|(_, start, end)| (start, end))
+}
+
+/// Names and line spans of the attributes at the top level of `block`
+pub fn block_attrs(lines: &[&str], block: &str) -> Vec<(String, usize, usize)> {
+    let Some(open) = lines.iter().position(|line| line.contains(block)) else {
+        return Vec::new();
+    };
+
+    let mut attrs = Vec::new();
+    let mut i = open + 1;
+    while i < lines.len() {
+        let line = lines[i].trim_start();
+        if line.starts_with('}') {
+            break;
+        }
+        let Some(name) = attr_name(line) else {
+            i += 1;
+            continue;
+        };
+        // The attribute ends at the first `;` with its braces closed
+        let mut depth = 0;
+        let mut end = None;
+        for (j, line) in lines.iter().enumerate().skip(i) {
+            depth += brace_balance(line);
+            if depth == 0 && line.trim_end().ends_with(';') {
+                end = Some(j);
+                break;
+            }
+        }
+        let Some(end) = end else {
+            break;
+        };
+        attrs.push((name.to_string(), i, end));
+        i = end + 1;
+    }
+    attrs
+}
+
+/// Remove lines `start..=end`, the comment lines right above them and one
+/// blank line next to them (after, or else before), so the spacing of the
+/// block stays as it was
+pub fn remove_span(lines: &[&str], start: usize, end: usize) -> String {
+    let (mut start, mut end) = (start, end);
+    while start > 0 && lines[start - 1].trim_start().starts_with('#') {
+        start -= 1;
+    }
+    if lines.get(end + 1).is_some_and(|line| line.trim().is_empty()) {
+        end += 1;
+    } else if start > 0 && lines[start - 1].trim().is_empty() {
+        start -= 1;
+    }
+    lines[..start].concat() + &lines[end + 1..].concat()
+}
+
+/// `foo` of `foo = ...`, `foo.url = ...` or `"foo@bar" = ...`; None for
+/// comments and other lines
+fn attr_name(line: &str) -> Option<&str> {
+    if let Some(quoted) = line.strip_prefix('"') {
+        let (name, rest) = quoted.split_once('"')?;
+        return (!name.is_empty() && rest.trim_start().starts_with('=')).then_some(name);
+    }
+    let end = line.find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))?;
+    let (name, rest) = line.split_at(end);
+    let rest = rest.trim_start();
+    (!name.is_empty() && (rest.starts_with('=') || rest.starts_with('.'))).then_some(name)
+}
+
+fn brace_balance(line: &str) -> i32 {
+    line.chars()
+        .map(|c| match c {
+            '{' => 1,
+            '}' => -1,
+            _ => 0,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLAKE: &str = r#"  {
+    nixosConfigurations = {
+      # Desktop with NVIDIA RTX 5090
+      kraken = mkNixosSystem {
+        hostname = "kraken";
+        extraModules = [ ./modules/hardware/nvidia.nix ];
+      };
+
+      # HP ZBook Ultra G1a (AMD Strix Halo)
+      G1a = mkNixosSystem {
+        hostname = "G1a";
+      };
+
+      # Forge Installer ISO
+      iso = nixpkgs.lib.nixosSystem {
+        modules = [ ./modules/iso ];
+      };
+    };
+  }
+"#;
+
+    #[test]
+    fn test_block_attrs() {
+        let lines: Vec<&str> = FLAKE.split_inclusive('\n').collect();
+        assert_eq!(
+            block_attrs(&lines, NIXOS_CONFIGURATIONS),
+            vec![
+                ("kraken".to_string(), 3, 6),
+                ("G1a".to_string(), 9, 11),
+                ("iso".to_string(), 14, 16)
+            ]
+        );
+        // Only whole names at the top of the block count
+        assert_eq!(attr_span(&lines, NIXOS_CONFIGURATIONS, "G1"), None);
+        assert_eq!(attr_span(&lines, NIXOS_CONFIGURATIONS, "hostname"), None);
+        assert!(block_attrs(&lines, INPUTS).is_empty());
+
+        let inputs = "inputs = {\n  nixpkgs.url = \"github:NixOS/nixpkgs\";\n  hm = {\n  };\n};\n";
+        let lines: Vec<&str> = inputs.split_inclusive('\n').collect();
+        assert_eq!(attr_span(&lines, INPUTS, "nixpkgs"), Some((1, 1)));
+        assert_eq!(attr_span(&lines, INPUTS, "hm"), Some((2, 3)));
+
+        let homes = "homeConfigurations = {\n  \"john@kraken\" = mkHome { };\n};\n";
+        let lines: Vec<&str> = homes.split_inclusive('\n').collect();
+        assert_eq!(attr_span(&lines, HOME_CONFIGURATIONS, "john@kraken"), Some((1, 1)));
+    }
+
+    #[test]
+    fn test_remove_span() {
+        let lines: Vec<&str> = FLAKE.split_inclusive('\n').collect();
+        assert_eq!(
+            remove_span(&lines, 3, 6),
+            "  {\n    nixosConfigurations = {\n      # HP ZBook Ultra G1a (AMD Strix Halo)\n      \
+             G1a = mkNixosSystem {\n        hostname = \"G1a\";\n      };\n\n      \
+             # Forge Installer ISO\n      iso = nixpkgs.lib.nixosSystem {\n        \
+             modules = [ ./modules/iso ];\n      };\n    };\n  }\n"
+        );
+        let without_g1a = remove_span(&lines, 9, 11);
+        assert!(!without_g1a.contains("G1a"));
+        assert!(without_g1a.contains("      };\n\n      # Forge Installer ISO\n"));
+        // The last entry takes the blank line before it
+        let without_iso = remove_span(&lines, 14, 16);
+        assert!(without_iso.ends_with("        hostname = \"G1a\";\n      };\n    };\n  }\n"));
+    }
+}
@@ -0,0 +1,422 @@
+//! Fleet definition for `forge deploy` (and `forge fleet update`)
+//!
+//! Loaded from `fleet.toml` at the root of the nixos-config repo so every
+//! machine shares the same rollout order:
+//!
+//! ```toml
+//! canary = ["G1a"]   # updated first, one at a time
+//! parallel = 2       # hosts deployed at once after the canaries (default 1)
+//! window = "02:00-05:00"  # when hosts may be updated (default any time)
+//! build = "local"    # where systems are built: local or remote (default local)
+//! review = true      # flake.lock changes only through merged PRs (forge update --propose)
+//! verify_signatures = true  # hosts must trust a system's signature before switching
+//!
+//! [[host]]
+//! name = "G1a"                 # nixosConfigurations attribute
+//! target = "john@g1a.lan"      # SSH destination (defaults to the name)
+//! window = "12:00-13:00"       # overrides the fleet's window
+//! build = "remote"             # overrides the fleet's build location
+//!
+//! [[host]]
+//! name = "kraken"
+//! ```
+//!
+//! Without a fleet.toml every nixosConfigurations host in flake.nix is
+//! deployed, one at a time, to an SSH target of the same name.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::flake_edit::{block_attrs, NIXOS_CONFIGURATIONS};
+use super::maintenance::MaintenanceWindow;
+
+/// Fleet definition filename (repo root)
+pub const FLEET_FILE: &str = "fleet.toml";
+
+/// Where a host's system is built
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildHost {
+    /// On this machine, then copied to the host
+    #[default]
+    Local,
+    /// On the host itself, from derivations evaluated here
+    Remote,
+}
+
+/// What a host deploy is doing, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DeployStage {
+    Connect,
+    Build,
+    Switch,
+}
+
+impl DeployStage {
+    pub const ALL: [DeployStage; 3] = [
+        DeployStage::Connect,
+        DeployStage::Build,
+        DeployStage::Switch,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DeployStage::Connect => "Connect",
+            DeployStage::Build => "Build",
+            DeployStage::Switch => "Switch",
+        }
+    }
+}
+
+/// A host that can be updated remotely
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FleetHost {
+    /// nixosConfigurations attribute to build
+    pub name: String,
+    /// SSH destination, e.g. `john@kraken.lan`
+    pub target: Option<String>,
+    /// Hours the host may be updated in; in a rollout plan, the fleet's
+    /// window when the host has none of its own
+    pub window: Option<MaintenanceWindow>,
+    /// Where to build; in a rollout plan, always set
+    pub build: Option<BuildHost>,
+}
+
+impl FleetHost {
+    /// SSH destination, falling back to the host name
+    pub fn ssh_target(&self) -> &str {
+        self.target.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Contents of fleet.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct FleetConfig {
+    /// Hosts updated first, in this order, before any other host is touched
+    pub canary: Vec<String>,
+    /// How many non-canary hosts to deploy at once
+    pub parallel: Option<usize>,
+    /// Maintenance window for hosts without their own
+    pub window: Option<MaintenanceWindow>,
+    /// Build location for hosts without their own
+    pub build: Option<BuildHost>,
+    /// Flake input updates are proposed as pull requests, and `forge update`
+    /// applies only the flake.lock that was merged
+    pub review: bool,
+    /// Each host checks the built system's signatures before it is switched to
+    /// (see `system::provenance`)
+    pub verify_signatures: bool,
+    /// All hosts, in rollout order
+    #[serde(rename = "host")]
+    pub hosts: Vec<FleetHost>,
+}
+
+impl FleetConfig {
+    /// Load fleet.toml from the config repo
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let path = config_dir.join(FLEET_FILE);
+        let content = std::fs::read_to_string(&path).with_context(|| {
+            format!(
+                "No fleet definition at {} (see `forge fleet update --help`)",
+                path.display()
+            )
+        })?;
+        Self::parse(&content).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    /// Whether fleet.toml asks for reviewed flake input updates; false without one
+    pub fn reviewed_updates(config_dir: &Path) -> Result<bool> {
+        if !config_dir.join(FLEET_FILE).exists() {
+            return Ok(false);
+        }
+        Ok(Self::load(config_dir)?.review)
+    }
+
+    /// fleet.toml if the repo has one, otherwise the hosts in flake.nix
+    pub fn load_or_flake(config_dir: &Path) -> Result<Self> {
+        if config_dir.join(FLEET_FILE).exists() {
+            return Self::load(config_dir);
+        }
+        let path = config_dir.join("flake.nix");
+        let flake = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config = Self::from_flake(&flake);
+        if config.hosts.is_empty() {
+            bail!("No {} and no hosts in nixosConfigurations in flake.nix", FLEET_FILE);
+        }
+        Ok(config)
+    }
+
+    /// Every nixosConfigurations entry with a `hostname`, which leaves out
+    /// images like the installer ISO
+    pub fn from_flake(flake: &str) -> Self {
+        let lines: Vec<&str> = flake.split_inclusive('\n').collect();
+        let hosts = block_attrs(&lines, NIXOS_CONFIGURATIONS)
+            .into_iter()
+            .filter(|(_, start, end)| {
+                lines[*start..=*end]
+                    .iter()
+                    .any(|line| line.trim_start().starts_with("hostname ="))
+            })
+            .map(|(name, _, _)| FleetHost {
+                name,
+                target: None,
+                window: None,
+                build: None,
+            })
+            .collect();
+        FleetConfig {
+            hosts,
+            ..Default::default()
+        }
+    }
+
+    /// Parse and validate fleet.toml contents
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: FleetConfig = toml::from_str(content)?;
+
+        let mut seen = HashSet::new();
+        for host in &config.hosts {
+            if !seen.insert(host.name.as_str()) {
+                bail!("Host '{}' is listed more than once", host.name);
+            }
+        }
+        for canary in &config.canary {
+            if !seen.contains(canary.as_str()) {
+                bail!("Canary '{}' is not a [[host]] entry", canary);
+            }
+        }
+        if config.parallel == Some(0) {
+            bail!("parallel must be at least 1");
+        }
+
+        Ok(config)
+    }
+
+    /// Maintenance window that applies to `name`, None if it may update any time
+    pub fn window_for(&self, name: &str) -> Option<MaintenanceWindow> {
+        self.hosts
+            .iter()
+            .find(|h| h.name == name)
+            .and_then(|h| h.window)
+            .or(self.window)
+    }
+
+    /// Build location for `name`
+    pub fn build_for(&self, name: &str) -> BuildHost {
+        self.hosts
+            .iter()
+            .find(|h| h.name == name)
+            .and_then(|h| h.build)
+            .or(self.build)
+            .unwrap_or_default()
+    }
+}
+
+/// Order in which hosts are updated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RolloutPlan {
+    /// Updated one at a time; any failure stops the rollout
+    pub canaries: Vec<FleetHost>,
+    /// Updated after all canaries succeeded, `parallel` at a time
+    pub rest: Vec<FleetHost>,
+    pub parallel: usize,
+    /// Whether hosts check signatures before switching
+    pub verify_signatures: bool,
+}
+
+impl RolloutPlan {
+    /// All hosts in rollout order
+    pub fn hosts(&self) -> impl Iterator<Item = &FleetHost> {
+        self.canaries.iter().chain(self.rest.iter())
+    }
+
+    /// Whether the host is one of the canaries
+    pub fn is_canary(&self, name: &str) -> bool {
+        self.canaries.iter().any(|h| h.name == name)
+    }
+
+    /// The same plan with every host allowed to update now (`--ignore-window`)
+    pub fn without_windows(mut self) -> Self {
+        for host in self.canaries.iter_mut().chain(self.rest.iter_mut()) {
+            host.window = None;
+        }
+        self
+    }
+
+    /// The same plan with every host built at `build` (`--build-on`)
+    pub fn built_on(mut self, build: BuildHost) -> Self {
+        for host in self.canaries.iter_mut().chain(self.rest.iter_mut()) {
+            host.build = Some(build);
+        }
+        self
+    }
+}
+
+/// Build the rollout order, optionally restricted to `only` and with a
+/// `--parallel` override
+pub fn plan_rollout(
+    config: &FleetConfig,
+    only: &[String],
+    parallel: Option<usize>,
+) -> Result<RolloutPlan> {
+    if config.hosts.is_empty() {
+        bail!("{} lists no [[host]] entries", FLEET_FILE);
+    }
+    for name in only {
+        if !config.hosts.iter().any(|h| &h.name == name) {
+            let known: Vec<&str> = config.hosts.iter().map(|h| h.name.as_str()).collect();
+            bail!("Unknown host '{}' (fleet: {})", name, known.join(", "));
+        }
+    }
+
+    let selected = |h: &&FleetHost| only.is_empty() || only.contains(&h.name);
+    let with_window = |h: &FleetHost| FleetHost {
+        window: config.window_for(&h.name),
+        build: Some(config.build_for(&h.name)),
+        ..h.clone()
+    };
+
+    let canaries: Vec<FleetHost> = config
+        .canary
+        .iter()
+        .filter_map(|name| config.hosts.iter().find(|h| &h.name == name))
+        .filter(selected)
+        .map(with_window)
+        .collect();
+    let rest: Vec<FleetHost> = config
+        .hosts
+        .iter()
+        .filter(|h| !config.canary.contains(&h.name))
+        .filter(selected)
+        .map(with_window)
+        .collect();
+
+    Ok(RolloutPlan {
+        canaries,
+        rest,
+        parallel: parallel.or(config.parallel).unwrap_or(1).max(1),
+        verify_signatures: config.verify_signatures,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLEET: &str = r#"
+        canary = ["G1a"]
+        parallel = 2
+        window = "02:00-05:00"
+
+        [[host]]
+        name = "kraken"
+        target = "john@kraken.lan"
+
+        [[host]]
+        name = "G1a"
+        window = "12:00-13:00"
+        build = "remote"
+
+        [[host]]
+        name = "office"
+    "#;
+
+    fn names(hosts: &[FleetHost]) -> Vec<&str> {
+        hosts.iter().map(|h| h.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_fleet() {
+        let config = FleetConfig::parse(FLEET).unwrap();
+        assert_eq!(config.hosts.len(), 3);
+        assert_eq!(config.hosts[0].ssh_target(), "john@kraken.lan");
+        assert_eq!(config.hosts[1].ssh_target(), "G1a");
+        assert!(!config.review);
+        assert!(FleetConfig::parse("review = true").unwrap().review);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_fleet() {
+        assert!(FleetConfig::parse("canary = [\"nope\"]\n[[host]]\nname = \"a\"").is_err());
+        assert!(FleetConfig::parse("[[host]]\nname = \"a\"\n[[host]]\nname = \"a\"").is_err());
+        assert!(FleetConfig::parse("parallel = 0\n[[host]]\nname = \"a\"").is_err());
+        assert!(FleetConfig::parse("window = \"2am\"\n[[host]]\nname = \"a\"").is_err());
+        assert!(FleetConfig::parse("build = \"cloud\"\n[[host]]\nname = \"a\"").is_err());
+    }
+
+    #[test]
+    fn test_hosts_from_flake() {
+        let flake = r#"
+    nixosConfigurations = {
+      kraken = mkNixosSystem {
+        hostname = "kraken";
+      };
+
+      G1a = mkNixosSystem {
+        hostname = "G1a";
+      };
+
+      iso = nixpkgs.lib.nixosSystem {
+        modules = [ ./modules/iso ];
+      };
+    };
+"#;
+        let config = FleetConfig::from_flake(flake);
+        assert_eq!(names(&config.hosts), ["kraken", "G1a"]);
+        assert_eq!(config.hosts[1].ssh_target(), "G1a");
+        assert!(FleetConfig::from_flake("{ }").hosts.is_empty());
+    }
+
+    #[test]
+    fn test_plan_build_location() {
+        let config = FleetConfig::parse(FLEET).unwrap();
+        let builds = |plan: &RolloutPlan| plan.hosts().map(|h| h.build).collect::<Vec<_>>();
+        let plan = plan_rollout(&config, &[], None).unwrap();
+        let (local, remote) = (Some(BuildHost::Local), Some(BuildHost::Remote));
+        assert_eq!(builds(&plan), [remote, local, local]);
+        assert_eq!(builds(&plan.built_on(BuildHost::Remote)), [remote, remote, remote]);
+    }
+
+    #[test]
+    fn test_plan_canary_first() {
+        let config = FleetConfig::parse(FLEET).unwrap();
+        let plan = plan_rollout(&config, &[], None).unwrap();
+        assert_eq!(names(&plan.canaries), ["G1a"]);
+        assert_eq!(names(&plan.rest), ["kraken", "office"]);
+        assert_eq!(plan.parallel, 2);
+        assert!(plan.is_canary("G1a"));
+        assert_eq!(plan.hosts().count(), 3);
+    }
+
+    #[test]
+    fn test_plan_windows() {
+        let config = FleetConfig::parse(FLEET).unwrap();
+        let windows = |plan: &RolloutPlan| {
+            plan.hosts()
+                .map(|h| h.window.map(|w| w.to_string()).unwrap_or_default())
+                .collect::<Vec<_>>()
+        };
+        // A host's own window wins over the fleet's
+        let plan = plan_rollout(&config, &[], None).unwrap();
+        assert_eq!(windows(&plan), ["12:00-13:00", "02:00-05:00", "02:00-05:00"]);
+        let fleet_window = config.window_for("unknown").map(|w| w.to_string());
+        assert_eq!(fleet_window.as_deref(), Some("02:00-05:00"));
+        assert_eq!(windows(&plan.without_windows()), ["", "", ""]);
+    }
+
+    #[test]
+    fn test_plan_filter_and_parallel_override() {
+        let config = FleetConfig::parse(FLEET).unwrap();
+        let plan = plan_rollout(&config, &["office".to_string()], Some(4)).unwrap();
+        assert!(plan.canaries.is_empty());
+        assert_eq!(names(&plan.rest), ["office"]);
+        assert_eq!(plan.parallel, 4);
+
+        assert!(plan_rollout(&config, &["nope".to_string()], None).is_err());
+        assert_eq!(plan_rollout(&config, &[], Some(0)).unwrap().parallel, 1);
+    }
+}
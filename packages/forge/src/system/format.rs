@@ -0,0 +1,128 @@
+//! Per-repo Nix formatter settings for generated files
+//!
+//! Read from the `[format]` table of `forge.toml` at the root of the
+//! nixos-config repo. Without it, generated files are left as written:
+//!
+//! ```toml
+//! [format]
+//! formatter = "nixfmt"   # or "alejandra", or "nix-fmt" for the flake's formatter output
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Repo settings filename (repo root)
+pub const FORGE_CONFIG_FILE: &str = "forge.toml";
+
+/// Formatter run on generated Nix files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NixFormatter {
+    Nixfmt,
+    Alejandra,
+    /// `nix fmt`, using the flake's own `formatter` output
+    NixFmt,
+}
+
+impl NixFormatter {
+    /// Executable looked up on PATH
+    pub fn program(&self) -> &'static str {
+        match self {
+            NixFormatter::Nixfmt => "nixfmt",
+            NixFormatter::Alejandra => "alejandra",
+            NixFormatter::NixFmt => "nix",
+        }
+    }
+
+    /// nixpkgs attribute to run when the program isn't installed
+    pub fn package(&self) -> Option<&'static str> {
+        match self {
+            NixFormatter::Nixfmt => Some("nixfmt-rfc-style"),
+            NixFormatter::Alejandra => Some("alejandra"),
+            NixFormatter::NixFmt => None,
+        }
+    }
+
+    /// Arguments placed before the file list
+    pub fn args(&self) -> &'static [&'static str] {
+        match self {
+            NixFormatter::Nixfmt => &[],
+            NixFormatter::Alejandra => &["--quiet"],
+            NixFormatter::NixFmt => &["fmt", "--"],
+        }
+    }
+}
+
+impl std::fmt::Display for NixFormatter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NixFormatter::Nixfmt => write!(f, "nixfmt"),
+            NixFormatter::Alejandra => write!(f, "alejandra"),
+            NixFormatter::NixFmt => write!(f, "nix fmt"),
+        }
+    }
+}
+
+/// `[format]` table of forge.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FormatConfig {
+    pub formatter: Option<NixFormatter>,
+}
+
+/// Contents of forge.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct RepoConfig {
+    pub format: FormatConfig,
+}
+
+impl RepoConfig {
+    /// Load forge.toml from the config repo; a missing file means defaults
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let path = config_dir.join(FORGE_CONFIG_FILE);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()))
+            }
+        };
+        Self::parse(&content).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_formatter() {
+        let config = RepoConfig::parse("[format]\nformatter = \"alejandra\"\n").unwrap();
+        assert_eq!(config.format.formatter, Some(NixFormatter::Alejandra));
+        assert_eq!(config.format.formatter.unwrap().args(), ["--quiet"]);
+
+        let config = RepoConfig::parse("[format]\nformatter = \"nix-fmt\"\n").unwrap();
+        assert_eq!(config.format.formatter, Some(NixFormatter::NixFmt));
+        assert_eq!(config.format.formatter.unwrap().package(), None);
+
+        assert_eq!(RepoConfig::parse("").unwrap().format.formatter, None);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown() {
+        assert!(RepoConfig::parse("[format]\nformatter = \"nixpkgs-fmt\"\n").is_err());
+        assert!(RepoConfig::parse("[format]\nformater = \"nixfmt\"\n").is_err());
+    }
+
+    #[test]
+    fn test_missing_file_is_default() {
+        let dir = std::env::temp_dir().join(format!("forge-format-{}", std::process::id()));
+        assert_eq!(RepoConfig::load(&dir).unwrap(), RepoConfig::default());
+    }
+}
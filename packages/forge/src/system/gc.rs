@@ -0,0 +1,363 @@
+//! Garbage collection schedule shared through the config repo
+//!
+//! `gc.json` at the repo root holds settings for every host plus per-host
+//! overrides. modules/gc.nix turns it into `nix.gc` and
+//! `nix.settings.auto-optimise-store`:
+//!
+//! ```json
+//! {
+//!   "defaults": { "dates": "weekly", "delete_older_than": "14d" },
+//!   "hosts": { "kraken": { "dates": "daily", "optimise_store": false } }
+//! }
+//! ```
+//!
+//! Unset fields are inherited: a host falls back to `defaults`, and `defaults`
+//! to `GcSettings::default()` (the same values modules/gc.nix starts from).
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// GC settings filename (repo root)
+pub const GC_FILE: &str = "gc.json";
+
+/// One editable setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GcField {
+    /// `nix.gc.automatic`
+    Automatic,
+    /// `nix.gc.dates` (systemd calendar expression)
+    Dates,
+    /// `--delete-older-than` in `nix.gc.options`
+    DeleteOlderThan,
+    /// `nix.settings.auto-optimise-store`
+    OptimiseStore,
+}
+
+impl GcField {
+    /// All fields, in screen order
+    pub const ALL: [GcField; 4] = [
+        GcField::Automatic,
+        GcField::Dates,
+        GcField::DeleteOlderThan,
+        GcField::OptimiseStore,
+    ];
+
+    /// Label shown on the settings screen
+    pub fn label(self) -> &'static str {
+        match self {
+            GcField::Automatic => "Automatic GC",
+            GcField::Dates => "Schedule",
+            GcField::DeleteOlderThan => "Delete older than",
+            GcField::OptimiseStore => "Optimise store",
+        }
+    }
+
+    /// On/off setting (toggled) rather than text (typed)
+    pub fn is_toggle(self) -> bool {
+        matches!(self, GcField::Automatic | GcField::OptimiseStore)
+    }
+}
+
+/// Settings in effect for a host once overrides are applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GcSettings {
+    pub automatic: bool,
+    pub dates: String,
+    pub delete_older_than: String,
+    pub optimise_store: bool,
+}
+
+impl Default for GcSettings {
+    // Keep in sync with the defaults in modules/gc.nix
+    fn default() -> Self {
+        GcSettings {
+            automatic: true,
+            dates: "weekly".to_string(),
+            delete_older_than: "14d".to_string(),
+            optimise_store: true,
+        }
+    }
+}
+
+impl GcSettings {
+    /// Display value of a field
+    pub fn value(&self, field: GcField) -> String {
+        let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+        match field {
+            GcField::Automatic => on_off(self.automatic),
+            GcField::Dates => self.dates.clone(),
+            GcField::DeleteOlderThan => self.delete_older_than.clone(),
+            GcField::OptimiseStore => on_off(self.optimise_store),
+        }
+    }
+}
+
+/// Fields set at one level (defaults or a host); unset fields are inherited
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GcOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub automatic: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dates: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delete_older_than: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub optimise_store: Option<bool>,
+}
+
+impl GcOverrides {
+    /// Whether this level sets the field itself
+    pub fn is_set(&self, field: GcField) -> bool {
+        match field {
+            GcField::Automatic => self.automatic.is_some(),
+            GcField::Dates => self.dates.is_some(),
+            GcField::DeleteOlderThan => self.delete_older_than.is_some(),
+            GcField::OptimiseStore => self.optimise_store.is_some(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        GcField::ALL.iter().all(|f| !self.is_set(*f))
+    }
+
+    fn apply(&self, settings: &mut GcSettings) {
+        if let Some(automatic) = self.automatic {
+            settings.automatic = automatic;
+        }
+        if let Some(dates) = &self.dates {
+            settings.dates = dates.clone();
+        }
+        if let Some(age) = &self.delete_older_than {
+            settings.delete_older_than = age.clone();
+        }
+        if let Some(optimise) = self.optimise_store {
+            settings.optimise_store = optimise;
+        }
+    }
+}
+
+/// gc.json contents
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GcConfig {
+    /// Applies to every host
+    #[serde(default)]
+    pub defaults: GcOverrides,
+    /// Per-host overrides, keyed by hostname
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hosts: BTreeMap<String, GcOverrides>,
+}
+
+impl GcConfig {
+    /// Settings in effect for a host, or for hosts without overrides (None)
+    pub fn resolve(&self, host: Option<&str>) -> GcSettings {
+        let mut settings = GcSettings::default();
+        self.defaults.apply(&mut settings);
+        if let Some(overrides) = host.and_then(|h| self.hosts.get(h)) {
+            overrides.apply(&mut settings);
+        }
+        settings
+    }
+
+    /// Whether a field is set at this level rather than inherited
+    pub fn is_set(&self, host: Option<&str>, field: GcField) -> bool {
+        match host {
+            None => self.defaults.is_set(field),
+            Some(h) => self.hosts.get(h).is_some_and(|o| o.is_set(field)),
+        }
+    }
+
+    fn overrides_mut(&mut self, host: Option<&str>) -> &mut GcOverrides {
+        match host {
+            None => &mut self.defaults,
+            Some(h) => self.hosts.entry(h.to_string()).or_default(),
+        }
+    }
+
+    /// Flip an on/off field, starting from the value currently in effect
+    pub fn toggle(&mut self, host: Option<&str>, field: GcField) {
+        let current = self.resolve(host);
+        let overrides = self.overrides_mut(host);
+        match field {
+            GcField::Automatic => overrides.automatic = Some(!current.automatic),
+            GcField::OptimiseStore => overrides.optimise_store = Some(!current.optimise_store),
+            GcField::Dates | GcField::DeleteOlderThan => {}
+        }
+    }
+
+    /// Set a text field, validating the value first
+    pub fn set_text(
+        &mut self,
+        host: Option<&str>,
+        field: GcField,
+        value: &str,
+    ) -> Result<(), String> {
+        let value = value.trim();
+        let error = match field {
+            GcField::Dates => validate_gc_dates(value),
+            GcField::DeleteOlderThan => validate_gc_age(value),
+            GcField::Automatic | GcField::OptimiseStore => None,
+        };
+        if let Some(err) = error {
+            return Err(err);
+        }
+        let overrides = self.overrides_mut(host);
+        match field {
+            GcField::Dates => overrides.dates = Some(value.to_string()),
+            GcField::DeleteOlderThan => overrides.delete_older_than = Some(value.to_string()),
+            GcField::Automatic | GcField::OptimiseStore => {}
+        }
+        Ok(())
+    }
+
+    /// Unset a field at this level so it is inherited again
+    pub fn reset(&mut self, host: Option<&str>, field: GcField) {
+        let overrides = self.overrides_mut(host);
+        match field {
+            GcField::Automatic => overrides.automatic = None,
+            GcField::Dates => overrides.dates = None,
+            GcField::DeleteOlderThan => overrides.delete_older_than = None,
+            GcField::OptimiseStore => overrides.optimise_store = None,
+        }
+        // Don't leave empty host entries behind in gc.json
+        if let Some(h) = host {
+            if self.hosts.get(h).is_some_and(GcOverrides::is_empty) {
+                self.hosts.remove(h);
+            }
+        }
+    }
+}
+
+/// Read gc.json (all defaults if the file doesn't exist yet)
+pub fn load_gc_config(config_dir: &Path) -> Result<GcConfig> {
+    let path = config_dir.join(GC_FILE);
+    if !path.exists() {
+        return Ok(GcConfig::default());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+}
+
+/// Write gc.json, pretty-printed for readable diffs
+pub fn save_gc_config(config_dir: &Path, config: &GcConfig) -> Result<()> {
+    let path = config_dir.join(GC_FILE);
+    let json = serde_json::to_string_pretty(config)? + "\n";
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Schedules are systemd calendar expressions ("weekly", "Sun 03:00", "*-*-1,15")
+pub fn validate_gc_dates(dates: &str) -> Option<String> {
+    if dates.is_empty() {
+        return Some("Schedule is required".to_string());
+    }
+    if !dates
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || " *:-,./~".contains(c))
+    {
+        return Some("Not a systemd calendar expression (e.g. weekly, Sun 03:00)".to_string());
+    }
+    None
+}
+
+/// `nix-collect-garbage --delete-older-than` only takes a number of days ("14d")
+pub fn validate_gc_age(age: &str) -> Option<String> {
+    match age.strip_suffix('d').map(str::parse::<u32>) {
+        Some(Ok(days)) if days > 0 => None,
+        _ => Some("Use a number of days, e.g. 14d".to_string()),
+    }
+}
+
+/// Bytes freed, from the last line of nix-collect-garbage
+/// ("1234 store paths deleted, 567.89 MiB freed")
+pub fn parse_freed_bytes(line: &str) -> Option<u64> {
+    let freed = line.trim().strip_suffix(" freed")?;
+    let (value, unit) = freed.rsplit(", ").next()?.split_once(' ')?;
+    let value: f64 = value.parse().ok()?;
+    let scale: u64 = match unit {
+        "B" | "bytes" => 1,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        "TiB" => 1 << 40,
+        _ => return None,
+    };
+    Some((value * scale as f64).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GC_JSON: &str = r#"{
+        "defaults": { "delete_older_than": "30d" },
+        "hosts": { "kraken": { "dates": "daily", "optimise_store": false } }
+    }"#;
+
+    #[test]
+    fn test_resolve_overrides() {
+        let config: GcConfig = serde_json::from_str(GC_JSON).unwrap();
+
+        let defaults = config.resolve(None);
+        assert_eq!(defaults.dates, "weekly");
+        assert_eq!(defaults.delete_older_than, "30d");
+        assert!(defaults.optimise_store);
+
+        let kraken = config.resolve(Some("kraken"));
+        assert_eq!(kraken.dates, "daily");
+        assert_eq!(kraken.delete_older_than, "30d");
+        assert!(!kraken.optimise_store);
+
+        // Hosts without an entry get the defaults
+        assert_eq!(config.resolve(Some("G1a")), defaults);
+        assert!(config.is_set(Some("kraken"), GcField::Dates));
+        assert!(!config.is_set(Some("kraken"), GcField::DeleteOlderThan));
+    }
+
+    #[test]
+    fn test_edit_and_reset() {
+        let mut config = GcConfig::default();
+        config.toggle(Some("G1a"), GcField::Automatic);
+        assert!(!config.resolve(Some("G1a")).automatic);
+        assert!(config.resolve(None).automatic);
+
+        assert!(config.set_text(Some("G1a"), GcField::Dates, "Sun 03:00").is_ok());
+        assert!(config.set_text(None, GcField::DeleteOlderThan, "two weeks").is_err());
+        assert_eq!(config.resolve(None).delete_older_than, "14d");
+
+        config.reset(Some("G1a"), GcField::Automatic);
+        config.reset(Some("G1a"), GcField::Dates);
+        assert!(config.hosts.is_empty());
+        assert_eq!(serde_json::to_string(&config).unwrap(), r#"{"defaults":{}}"#);
+    }
+
+    #[test]
+    fn test_validate_gc_values() {
+        assert!(validate_gc_dates("weekly").is_none());
+        assert!(validate_gc_dates("Mon,Thu *-*-* 04:30:00").is_none());
+        assert!(validate_gc_dates("").is_some());
+        assert!(validate_gc_dates("weekly; rm").is_some());
+
+        assert!(validate_gc_age("14d").is_none());
+        assert!(validate_gc_age("0d").is_some());
+        assert!(validate_gc_age("2w").is_some());
+        assert!(validate_gc_age("d").is_some());
+    }
+
+    #[test]
+    fn test_parse_freed_bytes() {
+        assert_eq!(parse_freed_bytes("1234 store paths deleted, 2.50 MiB freed"), Some(2_621_440));
+        assert_eq!(parse_freed_bytes("0 store paths deleted, 0.00 MiB freed\n"), Some(0));
+        assert_eq!(parse_freed_bytes("12 store paths deleted, 1.5 GiB freed"), Some(1_610_612_736));
+        assert_eq!(parse_freed_bytes("removing old generations of profile system"), None);
+        assert_eq!(parse_freed_bytes("3 store paths deleted, 12 furlongs freed"), None);
+    }
+
+    #[test]
+    fn test_unknown_keys_rejected() {
+        assert!(serde_json::from_str::<GcConfig>(r#"{"defaults":{"dats":"daily"}}"#).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+//! System profile generations for `forge rollback`
+//!
+//! Each generation is a `system-<n>-link` symlink in the profiles directory,
+//! pointing at a system closure. The closure names its NixOS version in
+//! `nixos-version` and its kernel in `kernel-modules/lib/modules/<version>`.
+
+use chrono::{DateTime, Local};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::host_state::parse_generation;
+
+/// Directory holding the system profile and its generation links
+pub const PROFILES_DIR: &str = "/nix/var/nix/profiles";
+
+/// The system profile itself, a link to the current generation's link
+pub const SYSTEM_PROFILE: &str = "/nix/var/nix/profiles/system";
+
+/// Closure the machine booted into
+const BOOTED_SYSTEM: &str = "/run/booted-system";
+
+/// A generation of the system profile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Generation {
+    pub number: u32,
+    /// When the generation was created (the link's mtime)
+    pub created: Option<DateTime<Local>>,
+    pub nixos_version: Option<String>,
+    pub kernel: Option<String>,
+    /// The profile points at it, i.e. it is what `switch` last activated
+    pub current: bool,
+    /// The running kernel and initrd came from it
+    pub booted: bool,
+    /// Bytes in its closure; only the generations screen looks it up
+    pub closure_size: Option<u64>,
+}
+
+/// The profile link of generation `number`
+pub fn generation_link(number: u32) -> PathBuf {
+    Path::new(PROFILES_DIR).join(format!("system-{}-link", number))
+}
+
+/// Generations of the system profile, newest first
+pub fn list_generations() -> std::io::Result<Vec<Generation>> {
+    let booted = std::fs::canonicalize(BOOTED_SYSTEM).ok();
+    list_in(Path::new(PROFILES_DIR), booted.as_deref())
+}
+
+fn list_in(dir: &Path, booted: Option<&Path>) -> std::io::Result<Vec<Generation>> {
+    let current = std::fs::read_link(dir.join("system"))
+        .ok()
+        .and_then(|target| parse_generation(&target.to_string_lossy()));
+
+    let mut generations: Vec<Generation> = std::fs::read_dir(dir)?
+        .flatten()
+        .filter_map(|entry| {
+            let number = parse_generation(&entry.file_name().to_string_lossy())?;
+            let link = entry.path();
+            let closure = std::fs::canonicalize(&link).ok();
+            Some(Generation {
+                number,
+                created: link
+                    .symlink_metadata()
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .map(DateTime::<Local>::from),
+                nixos_version: std::fs::read_to_string(link.join("nixos-version"))
+                    .ok()
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty()),
+                kernel: kernel_version(&link),
+                current: current == Some(number),
+                booted: closure.is_some() && closure.as_deref() == booted,
+                closure_size: None,
+            })
+        })
+        .collect();
+    generations.sort_by_key(|g| std::cmp::Reverse(g.number));
+    Ok(generations)
+}
+
+/// Kernel version of a system closure, from its module directory
+fn kernel_version(system: &Path) -> Option<String> {
+    std::fs::read_dir(system.join("kernel-modules/lib/modules"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .next()
+}
+
+/// Closure sizes by store path, from `nix path-info --closure-size` output
+/// (`<store path>  <bytes>` per line)
+pub fn parse_closure_sizes(output: &str) -> HashMap<String, u64> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let path = fields.next()?;
+            let size = fields.next_back()?.parse().ok()?;
+            Some((path.to_string(), size))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn test_list_generations() {
+        let root = std::env::temp_dir().join(format!("forge-generations-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let profiles = root.join("profiles");
+        std::fs::create_dir_all(&profiles).unwrap();
+        for (number, version, kernel) in [(7, "24.05.1", "6.6.30"), (8, "24.11.2", "6.12.4")] {
+            let closure = root.join(format!("system-{}", number));
+            std::fs::create_dir_all(closure.join("kernel-modules/lib/modules").join(kernel)).unwrap();
+            std::fs::write(closure.join("nixos-version"), format!("{}\n", version)).unwrap();
+            symlink(&closure, profiles.join(format!("system-{}-link", number))).unwrap();
+        }
+        symlink("system-8-link", profiles.join("system")).unwrap();
+        std::fs::write(profiles.join("per-user"), "").unwrap();
+
+        let booted = std::fs::canonicalize(root.join("system-7")).unwrap();
+        let generations = list_in(&profiles, Some(&booted)).unwrap();
+        let numbers: Vec<u32> = generations.iter().map(|g| g.number).collect();
+        assert_eq!(numbers, vec![8, 7]);
+        assert!(generations[0].current && !generations[0].booted);
+        assert!(!generations[1].current && generations[1].booted);
+        assert_eq!(generations[0].nixos_version.as_deref(), Some("24.11.2"));
+        assert_eq!(generations[1].kernel.as_deref(), Some("6.6.30"));
+        assert!(generations[0].created.is_some());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_parse_closure_sizes() {
+        let output = "/nix/store/aaa-nixos-system-kraken-24.11\t 8123456789\n\
+                      /nix/store/bbb-nixos-system-kraken-25.05   9001\n\
+                      error: something odd\n";
+        let sizes = parse_closure_sizes(output);
+        assert_eq!(sizes.len(), 2);
+        assert_eq!(sizes["/nix/store/aaa-nixos-system-kraken-24.11"], 8123456789);
+        assert_eq!(sizes["/nix/store/bbb-nixos-system-kraken-25.05"], 9001);
+    }
+}
@@ -1,11 +1,14 @@
 //! Hardware detection utilities for CPU, GPU, and form factor
 
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use std::fs;
 use std::process::Command;
 
 /// CPU vendor types
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CpuVendor {
     AMD,
     Intel,
@@ -23,7 +26,8 @@ impl std::fmt::Display for CpuVendor {
 }
 
 /// GPU vendor types
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GpuVendor {
     NVIDIA,
     AMD,
@@ -43,7 +47,7 @@ impl std::fmt::Display for GpuVendor {
 }
 
 /// System form factor
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FormFactor {
     Laptop,
     Desktop,
@@ -58,15 +62,55 @@ impl std::fmt::Display for FormFactor {
     }
 }
 
+/// Firmware boot mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BootMode {
+    Uefi,
+    /// Legacy BIOS (GRUB with an MBR partition table)
+    Bios,
+}
+
+impl std::fmt::Display for BootMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BootMode::Uefi => write!(f, "UEFI"),
+            BootMode::Bios => write!(f, "Legacy BIOS"),
+        }
+    }
+}
+
+/// Whether forge can enroll its own Secure Boot keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecureBootSupport {
+    /// Legacy BIOS, or firmware without Secure Boot variables
+    Unsupported,
+    /// A platform key is enrolled; clear the keys in firmware setup first
+    UserMode,
+    /// No platform key yet, so `sbctl enroll-keys` can add ours
+    SetupMode,
+}
+
+impl std::fmt::Display for SecureBootSupport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecureBootSupport::Unsupported => write!(f, "not supported"),
+            SecureBootSupport::UserMode => {
+                write!(f, "firmware keys enrolled (reset to Setup Mode to use)")
+            }
+            SecureBootSupport::SetupMode => write!(f, "Setup Mode"),
+        }
+    }
+}
+
 /// CPU information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CpuInfo {
     pub vendor: CpuVendor,
     pub model_name: String,
 }
 
 /// GPU information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpuInfo {
     pub vendor: GpuVendor,
     pub model: Option<String>,
@@ -93,6 +137,151 @@ pub fn detect_all() -> Result<HardwareInfo> {
     })
 }
 
+/// Detect whether the running system was booted via UEFI or legacy BIOS
+pub fn detect_boot_mode() -> BootMode {
+    if std::path::Path::new("/sys/firmware/efi").exists() {
+        BootMode::Uefi
+    } else {
+        BootMode::Bios
+    }
+}
+
+/// EFI variable that is 1 while the firmware accepts new Secure Boot keys
+const SETUP_MODE_EFIVAR: &str =
+    "/sys/firmware/efi/efivars/SetupMode-8be4df61-93ca-11d2-aa0d-e398c7f81f9b";
+
+/// Detect whether Secure Boot keys can be enrolled on this machine
+pub fn detect_secure_boot() -> SecureBootSupport {
+    if detect_boot_mode() == BootMode::Bios {
+        return SecureBootSupport::Unsupported;
+    }
+    match fs::read(SETUP_MODE_EFIVAR) {
+        Ok(data) => parse_setup_mode(&data),
+        Err(_) => SecureBootSupport::Unsupported,
+    }
+}
+
+/// efivarfs data is 4 attribute bytes followed by the value
+fn parse_setup_mode(data: &[u8]) -> SecureBootSupport {
+    match data.get(4) {
+        Some(1) => SecureBootSupport::SetupMode,
+        Some(_) => SecureBootSupport::UserMode,
+        None => SecureBootSupport::Unsupported,
+    }
+}
+
+/// Major version of the first TPM, as the kernel reports it
+const TPM_VERSION_FILE: &str = "/sys/class/tpm/tpm0/tpm_version_major";
+
+/// Whether a TPM 2.0 chip is available to seal a LUKS key against
+pub fn detect_tpm2() -> bool {
+    fs::read_to_string(TPM_VERSION_FILE).is_ok_and(|v| v.trim() == "2")
+}
+
+/// Kernel modules that may be needed in the initrd to reach the root disk or
+/// type the LUKS passphrase. Only modules loaded on the running system end up
+/// in the generated config.
+const INITRD_MODULE_WHITELIST: &[&str] = &[
+    // NVMe / SATA / IDE
+    "nvme", "ahci", "ata_piix", "pata_acpi", "sata_nv", "sata_sil24", "sata_via",
+    // SCSI, SAS and RAID HBAs
+    "sd_mod", "sr_mod", "mpt3sas", "mptsas", "mptspi", "megaraid_sas", "hpsa",
+    "aacraid", "smartpqi", "isci", "3w_sas", "arcmsr",
+    // Virtual machines
+    "virtio_pci", "virtio_blk", "virtio_scsi", "vmw_pvscsi", "hv_storvsc",
+    "xen_blkfront",
+    // USB storage and SD/MMC card readers
+    "uas", "usb_storage", "mmc_block", "sdhci_pci", "sdhci_acpi", "rtsx_pci_sdmmc",
+    "rtsx_usb_sdmmc",
+    // Host controllers and keyboards for the LUKS prompt
+    "xhci_pci", "ehci_pci", "ohci_pci", "uhci_hcd", "thunderbolt", "usbhid",
+    "hid_generic", "hid_apple", "hid_lenovo", "hid_logitech_dj", "i2c_hid_acpi",
+    "atkbd", "i8042",
+    // Device mapper (LUKS)
+    "dm_mod", "dm_crypt",
+];
+
+/// Detect initrd-relevant kernel modules currently loaded (equivalent to `lsmod`)
+pub fn detect_initrd_modules() -> Vec<String> {
+    fs::read_to_string("/proc/modules")
+        .map(|content| filter_initrd_modules(&content))
+        .unwrap_or_default()
+}
+
+/// Intersect /proc/modules with the whitelist, keeping whitelist order
+fn filter_initrd_modules(proc_modules: &str) -> Vec<String> {
+    let loaded: std::collections::HashSet<&str> = proc_modules
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .collect();
+
+    INITRD_MODULE_WHITELIST
+        .iter()
+        .filter(|module| loaded.contains(*module))
+        .map(|module| module.to_string())
+        .collect()
+}
+
+/// Installed memory in GiB (rounded up), 0 if /proc/meminfo can't be read
+pub fn detect_memory_gib() -> u64 {
+    fs::read_to_string("/proc/meminfo")
+        .map(|content| parse_memory_gib(&content))
+        .unwrap_or(0)
+}
+
+/// `MemTotal` from /proc/meminfo (in kB) as whole GiB, rounded up
+fn parse_memory_gib(meminfo: &str) -> u64 {
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb.div_ceil(1024 * 1024))
+        .unwrap_or(0)
+}
+
+/// Physical cores and hardware threads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuTopology {
+    pub cores: u32,
+    pub threads: u32,
+}
+
+/// CPU topology from /proc/cpuinfo, falling back to the threads Rust sees
+pub fn detect_cpu_topology() -> CpuTopology {
+    let topology = fs::read_to_string("/proc/cpuinfo")
+        .map(|content| parse_cpu_topology(&content))
+        .unwrap_or(CpuTopology { cores: 0, threads: 0 });
+    if topology.threads > 0 {
+        return topology;
+    }
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get() as u32);
+    CpuTopology { cores: threads, threads }
+}
+
+/// Threads are `processor` entries, cores the distinct (physical id, core id)
+/// pairs; without core ids (most ARM boards) every thread is a core
+fn parse_cpu_topology(cpuinfo: &str) -> CpuTopology {
+    let mut threads = 0;
+    let mut cores = BTreeSet::new();
+    let mut package = "";
+    for line in cpuinfo.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key.trim() {
+            "processor" => threads += 1,
+            "physical id" => package = value.trim(),
+            "core id" => {
+                cores.insert((package, value.trim()));
+            }
+            _ => {}
+        }
+    }
+    let cores = if cores.is_empty() { threads } else { cores.len() as u32 };
+    CpuTopology { cores, threads }
+}
+
 /// Detect CPU vendor and model from /proc/cpuinfo
 pub fn detect_cpu() -> Result<CpuInfo> {
     let cpuinfo = fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
@@ -251,6 +440,13 @@ pub fn detect_form_factor() -> Result<FormFactor> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_setup_mode() {
+        assert_eq!(parse_setup_mode(&[6, 0, 0, 0, 1]), SecureBootSupport::SetupMode);
+        assert_eq!(parse_setup_mode(&[6, 0, 0, 0, 0]), SecureBootSupport::UserMode);
+        assert_eq!(parse_setup_mode(&[6, 0]), SecureBootSupport::Unsupported);
+    }
+
     #[test]
     fn test_cpu_vendor_display() {
         assert_eq!(format!("{}", CpuVendor::AMD), "AMD");
@@ -266,6 +462,44 @@ mod tests {
         assert_eq!(format!("{}", GpuVendor::None), "None (integrated/software)");
     }
 
+    #[test]
+    fn test_parse_cpu_topology() {
+        // Two cores with two threads each
+        let smt = "processor\t: 0\nphysical id\t: 0\ncore id\t\t: 0\n\n\
+                   processor\t: 1\nphysical id\t: 0\ncore id\t\t: 1\n\n\
+                   processor\t: 2\nphysical id\t: 0\ncore id\t\t: 0\n\n\
+                   processor\t: 3\nphysical id\t: 0\ncore id\t\t: 1\n";
+        assert_eq!(parse_cpu_topology(smt), CpuTopology { cores: 2, threads: 4 });
+        let arm = "processor\t: 0\nBogoMIPS\t: 108.00\n\nprocessor\t: 1\nBogoMIPS\t: 108.00\n";
+        assert_eq!(parse_cpu_topology(arm), CpuTopology { cores: 2, threads: 2 });
+    }
+
+    #[test]
+    fn test_parse_memory_gib() {
+        // 32 GB of RAM shows up slightly below 32 GiB once the kernel reserves its share
+        let meminfo = "MemTotal:       32563536 kB\nMemFree:         1200000 kB\n";
+        assert_eq!(parse_memory_gib(meminfo), 32);
+        assert_eq!(parse_memory_gib("MemTotal: 16777216 kB"), 16);
+        assert_eq!(parse_memory_gib(""), 0);
+    }
+
+    #[test]
+    fn test_filter_initrd_modules() {
+        let proc_modules = "\
+nvidia_drm 131072 4 - Live 0x0000000000000000 (POE)
+megaraid_sas 184320 2 - Live 0x0000000000000000
+xhci_pci 24576 0 - Live 0x0000000000000000
+nvme 57344 3 - Live 0x0000000000000000
+snd_hda_intel 61440 2 - Live 0x0000000000000000
+";
+        // Whitelist order, unrelated modules dropped
+        assert_eq!(
+            filter_initrd_modules(proc_modules),
+            vec!["nvme", "megaraid_sas", "xhci_pci"]
+        );
+        assert!(filter_initrd_modules("").is_empty());
+    }
+
     #[test]
     fn test_form_factor_display() {
         assert_eq!(format!("{}", FormFactor::Laptop), "Laptop");
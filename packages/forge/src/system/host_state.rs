@@ -0,0 +1,246 @@
+//! Host heartbeat: what each machine is running, and how far it is from the repo
+//!
+//! `forge report-state` records the local host as `hosts/<name>.json` on the
+//! `forge-state` branch of the config repo; `forge fleet status` reads every
+//! report back and compares it with the main branch.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::channels::Channel;
+use super::generations::SYSTEM_PROFILE;
+use super::maintenance::MaintenanceWindow;
+
+/// Branch that holds the per-host reports (kept off main to avoid noise)
+pub const STATE_BRANCH: &str = "forge-state";
+
+/// Directory inside the state branch with one JSON file per host
+pub const STATE_DIR: &str = "hosts";
+
+/// Reports older than this are flagged as stale (host offline or timer broken)
+pub const STALE_REPORT_HOURS: i64 = 48;
+
+/// Suffix flakes add to `self.dirtyRev`
+const DIRTY_SUFFIX: &str = "-dirty";
+
+/// What a host reported about itself
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HostState {
+    pub hostname: String,
+    /// System profile generation number
+    pub generation: Option<u32>,
+    /// `system.configurationRevision` of the running system
+    pub revision: Option<String>,
+    pub nixos_version: Option<String>,
+    pub reported_at: DateTime<Utc>,
+    /// When `forge host archive` retired the host; fleet status leaves it out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decommissioned_at: Option<DateTime<Utc>>,
+}
+
+impl HostState {
+    /// Collect the state of the running system
+    pub fn collect(hostname: &str) -> Self {
+        let generation = std::fs::read_link(SYSTEM_PROFILE)
+            .ok()
+            .and_then(|target| parse_generation(&target.to_string_lossy()));
+
+        let (revision, nixos_version) = std::process::Command::new("nixos-version")
+            .arg("--json")
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| parse_nixos_version_json(&String::from_utf8_lossy(&o.stdout)))
+            .unwrap_or((None, None));
+
+        Self {
+            hostname: hostname.to_string(),
+            generation,
+            revision,
+            nixos_version,
+            reported_at: Utc::now(),
+            decommissioned_at: None,
+        }
+    }
+
+    /// Whether the system was built from uncommitted changes
+    pub fn is_dirty(&self) -> bool {
+        self.revision
+            .as_deref()
+            .is_some_and(|r| r.ends_with(DIRTY_SUFFIX))
+    }
+
+    /// Commit the system was built from (without the dirty suffix)
+    pub fn commit(&self) -> Option<&str> {
+        self.revision
+            .as_deref()
+            .map(|r| r.strip_suffix(DIRTY_SUFFIX).unwrap_or(r))
+    }
+
+    /// Path of this host's report inside the state branch
+    pub fn file_name(&self) -> String {
+        format!("{}/{}.json", STATE_DIR, self.hostname)
+    }
+}
+
+/// Extract the generation from a profile link target like `system-412-link`
+pub fn parse_generation(target: &str) -> Option<u32> {
+    let name = target.rsplit('/').next()?;
+    name.strip_prefix("system-")?
+        .strip_suffix("-link")?
+        .parse()
+        .ok()
+}
+
+/// Pull the revision and version out of `nixos-version --json`
+pub fn parse_nixos_version_json(json: &str) -> (Option<String>, Option<String>) {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(json) else {
+        return (None, None);
+    };
+    let field = |key: &str| {
+        value
+            .get(key)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+    };
+    (field("configurationRevision"), field("nixosVersion"))
+}
+
+/// How a host compares to the repo's main branch
+#[derive(Debug, Clone, PartialEq)]
+pub enum Drift {
+    UpToDate,
+    /// Running an older main commit, this many commits behind
+    Behind(usize),
+    /// Built from a commit that is not on main (local branch, rewritten history)
+    Diverged,
+    /// No revision recorded (built from a path, not a flake)
+    Unknown,
+}
+
+/// One row of the fleet status screen
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostDrift {
+    pub state: HostState,
+    pub drift: Drift,
+    /// Channel the host follows in flake.nix, None if it isn't there
+    pub channel: Option<Channel>,
+    /// Maintenance window from fleet.toml, None if it may update any time
+    pub window: Option<MaintenanceWindow>,
+}
+
+impl HostDrift {
+    /// Whether the last report is too old to trust
+    pub fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        now.signed_duration_since(self.state.reported_at)
+            > chrono::Duration::hours(STALE_REPORT_HOURS)
+    }
+
+    /// Whether the host needs attention
+    pub fn needs_attention(&self, now: DateTime<Utc>) -> bool {
+        self.drift != Drift::UpToDate || self.state.is_dirty() || self.is_stale(now)
+    }
+}
+
+/// Human readable age, e.g. "5m ago", "3h ago", "2d ago"
+pub fn format_age(since: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let minutes = now.signed_duration_since(since).num_minutes().max(0);
+    if minutes < 60 {
+        format!("{}m ago", minutes)
+    } else if minutes < 60 * 24 {
+        format!("{}h ago", minutes / 60)
+    } else {
+        format!("{}d ago", minutes / (60 * 24))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(revision: Option<&str>) -> HostState {
+        HostState {
+            hostname: "kraken".to_string(),
+            generation: Some(412),
+            revision: revision.map(String::from),
+            nixos_version: None,
+            reported_at: Utc::now(),
+            decommissioned_at: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_generation() {
+        assert_eq!(parse_generation("system-412-link"), Some(412));
+        assert_eq!(parse_generation("/nix/var/nix/profiles/system-7-link"), Some(7));
+        assert_eq!(parse_generation("/nix/store/abc-nixos-system"), None);
+    }
+
+    #[test]
+    fn test_parse_nixos_version_json() {
+        let json = r#"{"configurationRevision":"1a2b3c","nixosVersion":"25.11.20260101.abcdef","nixpkgsRevision":"abcdef"}"#;
+        assert_eq!(
+            parse_nixos_version_json(json),
+            (Some("1a2b3c".to_string()), Some("25.11.20260101.abcdef".to_string()))
+        );
+        assert_eq!(parse_nixos_version_json(r#"{"nixosVersion":"25.11"}"#).0, None);
+        assert_eq!(parse_nixos_version_json("not json"), (None, None));
+    }
+
+    #[test]
+    fn test_decommissioned_field() {
+        // Reports written before hosts could be archived don't have the field
+        let json = r#"{"hostname":"G1a","generation":7,"revision":null,"nixos_version":null,"reported_at":"2026-10-17T09:30:00Z"}"#;
+        let mut report: HostState = serde_json::from_str(json).unwrap();
+        assert_eq!(report.decommissioned_at, None);
+        assert!(!serde_json::to_string(&report).unwrap().contains("decommissioned_at"));
+
+        report.decommissioned_at = Some(report.reported_at);
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(serde_json::from_str::<HostState>(&json).unwrap(), report);
+    }
+
+    #[test]
+    fn test_dirty_revision() {
+        let dirty = state(Some("1a2b3c-dirty"));
+        assert!(dirty.is_dirty());
+        assert_eq!(dirty.commit(), Some("1a2b3c"));
+
+        let clean = state(Some("1a2b3c"));
+        assert!(!clean.is_dirty());
+        assert_eq!(clean.commit(), Some("1a2b3c"));
+        assert_eq!(clean.file_name(), "hosts/kraken.json");
+    }
+
+    #[test]
+    fn test_needs_attention() {
+        let now = Utc::now();
+        let ok = HostDrift {
+            state: state(Some("1a2b3c")),
+            drift: Drift::UpToDate,
+            channel: Some(Channel::Unstable),
+            window: None,
+        };
+        assert!(!ok.needs_attention(now));
+
+        let mut stale = ok.clone();
+        stale.state.reported_at = now - chrono::Duration::hours(STALE_REPORT_HOURS + 1);
+        assert!(stale.is_stale(now));
+        assert!(stale.needs_attention(now));
+
+        let behind = HostDrift {
+            drift: Drift::Behind(3),
+            ..ok
+        };
+        assert!(behind.needs_attention(now));
+    }
+
+    #[test]
+    fn test_format_age() {
+        let now = Utc::now();
+        assert_eq!(format_age(now - chrono::Duration::minutes(5), now), "5m ago");
+        assert_eq!(format_age(now - chrono::Duration::hours(3), now), "3h ago");
+        assert_eq!(format_age(now - chrono::Duration::days(2), now), "2d ago");
+    }
+}
@@ -0,0 +1,234 @@
+//! Progress of the long install steps, read from plain nix and disko output
+//!
+//! Nix announces what it is about to do and then logs one line per path:
+//!
+//! ```text
+//! these 12 derivations will be built:
+//! these 1834 paths will be fetched (1.21 GiB download, 5.40 GiB unpacked):
+//! copying path '/nix/store/...-glibc-2.40' from 'https://cache.nixos.org'...
+//! building '/nix/store/...-etc.drv'...
+//! ```
+//!
+//! `nix copy` (nixos-anywhere) says `copying 1834 paths...` instead. disko's
+//! script runs under `set -x`, so the traced commands give its phase.
+
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use regex::Regex;
+
+/// "these 12 derivations will be built:" / "this derivation will be built:"
+static BUILD_TOTAL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:these (\d+) derivations|this derivation) will be built")
+        .expect("Build total regex pattern is statically validated")
+});
+
+/// "these 1834 paths will be fetched (...)", "this path will be fetched (...)",
+/// or nix copy's "copying 1834 paths..."
+static FETCH_TOTAL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:these (\d+) paths will be fetched|this path will be fetched|copying (\d+) paths)",
+    )
+    .expect("Fetch total regex pattern is statically validated")
+});
+
+/// What a disko format script is doing, in the order it does it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiskoPhase {
+    Wiping,
+    Partitioning,
+    Encrypting,
+    Formatting,
+    Mounting,
+}
+
+impl DiskoPhase {
+    /// Phase of a `set -x` trace line, e.g. "+ mkfs.vfat -n ESP /dev/..."
+    fn from_trace(line: &str) -> Option<Self> {
+        let command = line.trim_start().trim_start_matches('+').trim_start();
+        let program = command.split_whitespace().next()?;
+        let program = program.rsplit('/').next().unwrap_or(program);
+        match program {
+            "wipefs" | "blkdiscard" => Some(DiskoPhase::Wiping),
+            "sgdisk" | "parted" | "sfdisk" | "partprobe" => Some(DiskoPhase::Partitioning),
+            "cryptsetup" => Some(DiskoPhase::Encrypting),
+            "mkswap" | "zpool" | "zfs" | "btrfs" => Some(DiskoPhase::Formatting),
+            p if p.starts_with("mkfs") => Some(DiskoPhase::Formatting),
+            "mount" => Some(DiskoPhase::Mounting),
+            _ => None,
+        }
+    }
+
+    /// Rough share of the disko run done once this phase starts
+    fn percent(self) -> u16 {
+        match self {
+            DiskoPhase::Wiping => 5,
+            DiskoPhase::Partitioning => 20,
+            DiskoPhase::Encrypting => 35,
+            DiskoPhase::Formatting => 60,
+            DiskoPhase::Mounting => 85,
+        }
+    }
+}
+
+/// Percentage and estimated time left of a running step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepProgress {
+    /// 0-100
+    pub percent: u16,
+    pub eta_secs: Option<u64>,
+}
+
+impl StepProgress {
+    /// e.g. "42%, ~3m left"
+    pub fn label(&self) -> String {
+        match self.eta_secs {
+            Some(secs) => format!("{}%, ~{} left", self.percent, format_eta(secs)),
+            None => format!("{}%", self.percent),
+        }
+    }
+}
+
+/// Counters folded from one command's output
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstallProgress {
+    paths_total: u64,
+    paths_fetched: u64,
+    builds_total: u64,
+    builds_started: u64,
+    phase: Option<DiskoPhase>,
+}
+
+impl InstallProgress {
+    /// Fold one output line into the counters; returns whether they changed
+    pub fn update(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let count = |m: Option<regex::Match>| m.map_or(Some(1), |m| m.as_str().parse().ok());
+
+        if let Some(caps) = BUILD_TOTAL_RE.captures(line) {
+            self.builds_total += count(caps.get(1)).unwrap_or(0);
+        } else if let Some(caps) = FETCH_TOTAL_RE.captures(line) {
+            self.paths_total += count(caps.get(1).or(caps.get(2))).unwrap_or(0);
+        } else if line.starts_with("copying path '") {
+            self.paths_fetched += 1;
+        } else if line.starts_with("building '") {
+            self.builds_started += 1;
+        } else if let Some(phase) = line.starts_with('+').then(|| DiskoPhase::from_trace(line)) {
+            // Phases only move forward; disko mounts (and re-probes) along the way
+            match phase {
+                Some(phase) if Some(phase) > self.phase => self.phase = Some(phase),
+                _ => return false,
+            }
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Share done, 0-99, once there is anything to go on
+    ///
+    /// Never 100: the step itself reports when it is finished.
+    pub fn percent(&self) -> Option<u16> {
+        if let Some(phase) = self.phase {
+            return Some(phase.percent());
+        }
+        let total = self.paths_total + self.builds_total;
+        if total == 0 {
+            return None;
+        }
+        let done = (self.paths_fetched + self.builds_started).min(total);
+        Some((done * 100 / total).min(99) as u16)
+    }
+
+    /// Time left at the rate so far; None until some paths are done
+    pub fn eta(&self, elapsed: Duration) -> Option<Duration> {
+        if self.phase.is_some() {
+            return None;
+        }
+        let total = self.paths_total + self.builds_total;
+        let done = (self.paths_fetched + self.builds_started).min(total);
+        if done == 0 {
+            return None;
+        }
+        let left = (total - done) as u32;
+        Some(elapsed / done as u32 * left)
+    }
+
+    /// Snapshot for the progress screen
+    pub fn snapshot(&self, elapsed: Duration) -> Option<StepProgress> {
+        Some(StepProgress {
+            percent: self.percent()?,
+            eta_secs: self.eta(elapsed).map(|eta| eta.as_secs()),
+        })
+    }
+}
+
+/// Rough duration, e.g. "<1m", "12m", "1h 05m"
+pub fn format_eta(secs: u64) -> String {
+    match secs / 60 {
+        0 => "<1m".to_string(),
+        minutes if minutes < 60 => format!("{}m", minutes),
+        minutes => format!("{}h {:02}m", minutes / 60, minutes % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nix_counters() {
+        let mut progress = InstallProgress::default();
+        assert_eq!(progress.percent(), None);
+
+        let lines = [
+            "these 2 derivations will be built:",
+            "  /nix/store/abc-etc.drv",
+            "these 6 paths will be fetched (12.3 MiB download, 40.1 MiB unpacked):",
+            "copying path '/nix/store/def-glibc-2.40' from 'https://cache.nixos.org'...",
+            "copying path '/nix/store/ghi-bash-5.2' from 'https://cache.nixos.org'...",
+            "building '/nix/store/abc-etc.drv'...",
+        ];
+        for line in lines {
+            progress.update(line);
+        }
+        assert_eq!(progress.percent(), Some(37));
+        let eta = progress.eta(Duration::from_secs(30)).unwrap();
+        assert_eq!(eta.as_secs(), 50);
+        assert_eq!(
+            progress.snapshot(Duration::from_secs(30)).unwrap().label(),
+            "37%, ~<1m left"
+        );
+    }
+
+    #[test]
+    fn test_single_path_and_nix_copy() {
+        let mut progress = InstallProgress::default();
+        assert!(progress.update("this path will be fetched (0.1 MiB download, 0.3 MiB unpacked):"));
+        assert!(progress.update("copying 3 paths..."));
+        assert!(progress.update("copying path '/nix/store/x' to 'ssh-ng://root@host'..."));
+        assert!(!progress.update("installing the boot loader..."));
+        assert_eq!(progress.percent(), Some(25));
+    }
+
+    #[test]
+    fn test_disko_phases() {
+        let mut progress = InstallProgress::default();
+        progress.update("+ wipefs --all -f /dev/nvme0n1");
+        assert_eq!(progress.percent(), Some(5));
+        progress.update("++ sgdisk --new=1:0:+2G /dev/nvme0n1");
+        progress.update("+ mkfs.vfat -n ESP /dev/disk/by-partlabel/disk-main-ESP");
+        assert_eq!(progress.percent(), Some(60));
+        // A later partprobe doesn't move the bar back
+        assert!(!progress.update("+ partprobe /dev/nvme0n1"));
+        assert_eq!(progress.percent(), Some(60));
+        assert_eq!(progress.eta(Duration::from_secs(10)), None);
+    }
+
+    #[test]
+    fn test_format_eta() {
+        assert_eq!(format_eta(42), "<1m");
+        assert_eq!(format_eta(12 * 60 + 5), "12m");
+        assert_eq!(format_eta(65 * 60), "1h 05m");
+    }
+}
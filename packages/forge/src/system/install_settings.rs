@@ -0,0 +1,109 @@
+//! Installer defaults for the main user
+//!
+//! Loaded from `~/.config/forge/install.toml`. Every key is optional; without
+//! the file the credentials step starts with an empty username and the user
+//! gets uid 1000 in the `users` group (100), what NixOS gives the first normal
+//! user anyway:
+//!
+//! ```toml
+//! [user]
+//! name = "alice"   # filled in on the credentials step, the default of `forge install --plan`
+//! uid = 1001
+//! gid = 1001       # anything but 100 gets a group named after the user
+//! ```
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+use crate::app::state::validate_username;
+use crate::constants::{install_settings_path, PRIMARY_USER_GID, PRIMARY_USER_UID};
+
+/// Lowest uid NixOS gives normal users; below it are system accounts
+const MIN_USER_UID: u32 = 1000;
+
+static SETTINGS: OnceLock<InstallSettings> = OnceLock::new();
+
+/// Parsed install.toml
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InstallSettings {
+    pub user: UserSettings,
+}
+
+/// The `[user]` table: the main user the installer sets up
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UserSettings {
+    /// Username filled in on the credentials step
+    pub name: Option<String>,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self {
+            name: None,
+            uid: PRIMARY_USER_UID,
+            gid: PRIMARY_USER_GID,
+        }
+    }
+}
+
+impl InstallSettings {
+    pub fn parse(content: &str) -> Result<Self> {
+        let settings: Self = toml::from_str(content)?;
+        let user = &settings.user;
+        if let Some(error) = user.name.as_deref().and_then(validate_username) {
+            bail!("user.name: {}", error);
+        }
+        if user.uid < MIN_USER_UID {
+            bail!("user.uid {} belongs to the system accounts, use {} or above", user.uid, MIN_USER_UID);
+        }
+        if user.gid == 0 {
+            bail!("user.gid 0 is the root group");
+        }
+        Ok(settings)
+    }
+
+    /// Read the settings file, falling back to the defaults if missing or invalid
+    fn load() -> Self {
+        let path = install_settings_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        match Self::parse(&content) {
+            Ok(settings) => settings,
+            Err(e) => {
+                tracing::warn!("Ignoring invalid {}: {:#}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Settings in effect, read on first use
+pub fn current() -> &'static InstallSettings {
+    SETTINGS.get_or_init(InstallSettings::load)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_install_settings() {
+        let settings = InstallSettings::parse("[user]\nname = \"alice\"\nuid = 1001\ngid = 1001\n").unwrap();
+        assert_eq!(settings.user.name.as_deref(), Some("alice"));
+        assert_eq!((settings.user.uid, settings.user.gid), (1001, 1001));
+
+        let defaults = InstallSettings::parse("").unwrap();
+        assert_eq!(defaults, InstallSettings::default());
+        assert_eq!(defaults.user.uid, PRIMARY_USER_UID);
+
+        assert!(InstallSettings::parse("[user]\nname = \"Root\"\n").is_err());
+        assert!(InstallSettings::parse("[user]\nuid = 0\n").is_err());
+        assert!(InstallSettings::parse("[user]\nhome = \"/home/a\"\n").is_err());
+    }
+}
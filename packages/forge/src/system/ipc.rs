@@ -0,0 +1,196 @@
+//! Status socket for desktop bars
+//!
+//! While the TUI runs it listens on `$XDG_RUNTIME_DIR/forge.sock` and answers
+//! every connection with one JSON line: the operation in progress and the
+//! pending updates. `forge status` reads it (or, with no forge running, what
+//! forge-notify last found) and prints it for a Waybar custom module.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use crate::constants::ipc_socket_path;
+
+/// How long `forge status` waits for a running forge to answer
+const QUERY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// What forge is doing and what is waiting to be applied
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForgeStatus {
+    /// e.g. "updating"; None while idle
+    pub operation: Option<String>,
+    /// The running step, e.g. "Rebuilding system"
+    pub step: Option<String>,
+    /// 0-100, from the finished steps and the running step's own progress
+    pub percent: Option<u16>,
+    /// One line per pending update, e.g. "3 config commits"
+    pub pending: Vec<String>,
+}
+
+impl ForgeStatus {
+    /// Pending lines for what an update check found
+    pub fn pending_lines(
+        config_commits: usize,
+        app_profiles: bool,
+        flake_inputs: &[String],
+    ) -> Vec<String> {
+        let mut lines = Vec::new();
+        match config_commits {
+            0 => {}
+            1 => lines.push("1 config commit".to_string()),
+            n => lines.push(format!("{} config commits", n)),
+        }
+        if app_profiles {
+            lines.push("App profiles".to_string());
+        }
+        lines.extend(flake_inputs.iter().cloned());
+        lines
+    }
+
+    /// Bar text: "updating 43%", "3 updates pending" or empty when idle
+    pub fn text(&self) -> String {
+        match (&self.operation, self.percent) {
+            (Some(op), Some(percent)) => format!("{} {}%", op, percent),
+            (Some(op), None) => op.clone(),
+            (None, _) => self.pending_text(),
+        }
+    }
+
+    /// "3 updates pending", or empty when there are none
+    pub fn pending_text(&self) -> String {
+        match self.pending.len() {
+            0 => String::new(),
+            1 => "1 update pending".to_string(),
+            n => format!("{} updates pending", n),
+        }
+    }
+
+    /// Waybar `return-type: json` output; class is busy, pending or idle
+    pub fn waybar_json(&self) -> String {
+        let (class, tooltip) = match &self.operation {
+            Some(_) => ("busy", self.step.clone().unwrap_or_default()),
+            None if !self.pending.is_empty() => ("pending", self.pending.join("\n")),
+            None => ("idle", "No updates pending".to_string()),
+        };
+        serde_json::json!({
+            "text": self.text(),
+            "tooltip": tooltip,
+            "class": class,
+            "percentage": self.percent.unwrap_or(0),
+        })
+        .to_string()
+    }
+}
+
+/// The socket while the TUI runs; dropping it stops listening
+pub struct IpcServer {
+    path: PathBuf,
+    status: watch::Sender<ForgeStatus>,
+    task: JoinHandle<()>,
+}
+
+impl IpcServer {
+    /// Start listening, unless another forge already answers on the socket
+    pub async fn start() -> Result<Option<Self>> {
+        let path = ipc_socket_path();
+        if path.exists() {
+            if UnixStream::connect(&path).await.is_ok() {
+                return Ok(None);
+            }
+            // Left behind by a forge that didn't exit cleanly
+            let _ = std::fs::remove_file(&path);
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let listener = UnixListener::bind(&path)
+            .with_context(|| format!("Failed to listen on {}", path.display()))?;
+        let (status, rx) = watch::channel(ForgeStatus::default());
+        let task = tokio::spawn(serve(listener, rx));
+        Ok(Some(IpcServer { path, status, task }))
+    }
+
+    /// Make `status` what the next client sees
+    pub fn publish(&self, status: ForgeStatus) {
+        self.status.send_if_modified(|current| {
+            if *current == status {
+                return false;
+            }
+            *current = status;
+            true
+        });
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        self.task.abort();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+async fn serve(listener: UnixListener, status: watch::Receiver<ForgeStatus>) {
+    loop {
+        let mut stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                tracing::warn!("Status socket stopped: {}", e);
+                return;
+            }
+        };
+        let line = match serde_json::to_string(&*status.borrow()) {
+            Ok(json) => json + "\n",
+            Err(_) => continue,
+        };
+        tokio::spawn(async move {
+            let _ = stream.write_all(line.as_bytes()).await;
+        });
+    }
+}
+
+/// Ask a running forge for its status; None if none is listening
+pub async fn query_status() -> Option<ForgeStatus> {
+    let read = async {
+        let mut stream = UnixStream::connect(ipc_socket_path()).await.ok()?;
+        let mut line = String::new();
+        stream.read_to_string(&mut line).await.ok()?;
+        serde_json::from_str(line.trim()).ok()
+    };
+    tokio::time::timeout(QUERY_TIMEOUT, read).await.ok().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_text() {
+        let mut status = ForgeStatus::default();
+        assert_eq!(status.text(), "");
+
+        status.pending = ForgeStatus::pending_lines(3, true, &["nixpkgs".to_string()]);
+        assert_eq!(status.pending, vec!["3 config commits", "App profiles", "nixpkgs"]);
+        assert_eq!(status.text(), "3 updates pending");
+
+        status.operation = Some("updating".to_string());
+        status.percent = Some(43);
+        assert_eq!(status.text(), "updating 43%");
+    }
+
+    #[test]
+    fn test_waybar_json() {
+        let status = ForgeStatus {
+            pending: vec!["1 config commit".to_string()],
+            ..Default::default()
+        };
+        let json: serde_json::Value = serde_json::from_str(&status.waybar_json()).unwrap();
+        assert_eq!(json["text"], "1 update pending");
+        assert_eq!(json["class"], "pending");
+        assert_eq!(json["tooltip"], "1 config commit");
+    }
+}
@@ -0,0 +1,239 @@
+//! Nix lint findings from statix and deadnix
+//!
+//! statix reports anti-patterns (`statix check -o errfmt`, one line each):
+//!
+//! ```text
+//! hosts/G1a/default.nix>12:3:W:3:Assignment instead of inherit from
+//! ```
+//!
+//! deadnix reports unused bindings (`deadnix -o json`, one object per file):
+//!
+//! ```text
+//! {"file":"modules/foo.nix","results":[{"line":4,"column":7,"message":"Unused let binding: x"}]}
+//! ```
+//!
+//! Both can fix what they report (`statix fix`, `deadnix --edit`).
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Linter that produced a finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintTool {
+    Statix,
+    Deadnix,
+}
+
+impl std::fmt::Display for LintTool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LintTool::Statix => write!(f, "statix"),
+            LintTool::Deadnix => write!(f, "deadnix"),
+        }
+    }
+}
+
+/// Most severe first, so sorting puts errors on top
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LintSeverity {
+    Error,
+    Warning,
+    Hint,
+}
+
+impl std::fmt::Display for LintSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // pad() so report columns line up
+        f.pad(match self {
+            LintSeverity::Error => "error",
+            LintSeverity::Warning => "warning",
+            LintSeverity::Hint => "hint",
+        })
+    }
+}
+
+/// One reported problem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+    pub severity: LintSeverity,
+    pub tool: LintTool,
+    pub message: String,
+}
+
+/// Parse `statix check -o errfmt` output
+pub fn parse_statix(output: &str) -> Vec<LintFinding> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (file, rest) = line.split_once('>')?;
+            let mut fields = rest.splitn(5, ':');
+            let line_no = fields.next()?.parse().ok()?;
+            let column = fields.next()?.parse().ok()?;
+            let severity = match fields.next()? {
+                "E" => LintSeverity::Error,
+                "W" => LintSeverity::Warning,
+                _ => LintSeverity::Hint,
+            };
+            let _code = fields.next()?;
+            Some(LintFinding {
+                file: clean_path(file),
+                line: line_no,
+                column,
+                severity,
+                tool: LintTool::Statix,
+                message: fields.next()?.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct DeadnixFile {
+    file: String,
+    results: Vec<DeadnixResult>,
+}
+
+#[derive(Deserialize)]
+struct DeadnixResult {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+/// Parse `deadnix -o json` output; unused code is reported as a warning
+pub fn parse_deadnix(output: &str) -> Vec<LintFinding> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<DeadnixFile>(line).ok())
+        .flat_map(|file| {
+            let path = clean_path(&file.file);
+            file.results.into_iter().map(move |r| LintFinding {
+                file: path.clone(),
+                line: r.line,
+                column: r.column,
+                severity: LintSeverity::Warning,
+                tool: LintTool::Deadnix,
+                message: r.message,
+            })
+        })
+        .collect()
+}
+
+fn clean_path(path: &str) -> String {
+    path.trim().trim_start_matches("./").to_string()
+}
+
+/// Findings per file, each file's list sorted by severity then position
+pub fn group_by_file(findings: &[LintFinding]) -> BTreeMap<&str, Vec<&LintFinding>> {
+    let mut groups: BTreeMap<&str, Vec<&LintFinding>> = BTreeMap::new();
+    for finding in findings {
+        groups.entry(finding.file.as_str()).or_default().push(finding);
+    }
+    for list in groups.values_mut() {
+        list.sort_by_key(|f| (f.severity, f.line, f.column));
+    }
+    groups
+}
+
+/// Report lines for the command output, grouped by file
+pub fn report_lines(findings: &[LintFinding]) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (file, list) in group_by_file(findings) {
+        lines.push(format!("  {}", file));
+        for f in list {
+            lines.push(format!(
+                "    {}:{} {:<7} {} ({})",
+                f.line, f.column, f.severity, f.message, f.tool
+            ));
+        }
+    }
+    lines
+}
+
+/// One-line count, e.g. "1 error, 3 warnings in 2 files"
+pub fn count_summary(findings: &[LintFinding]) -> String {
+    let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+    let plural = |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+    let mut parts = Vec::new();
+    for (severity, word) in [
+        (LintSeverity::Error, "error"),
+        (LintSeverity::Warning, "warning"),
+        (LintSeverity::Hint, "hint"),
+    ] {
+        let n = count(severity);
+        if n > 0 {
+            parts.push(plural(n, word));
+        }
+    }
+    if parts.is_empty() {
+        return "no findings".to_string();
+    }
+    format!("{} in {}", parts.join(", "), plural(group_by_file(findings).len(), "file"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATIX: &str = "\
+hosts/G1a/default.nix>12:3:W:3:Assignment instead of inherit from
+./flake.nix>40:7:E:13:This let-in expression is empty
+modules/common.nix>5:1:I:20:Found empty pattern in function argument
+not a finding
+";
+
+    const DEADNIX: &str = concat!(
+        r#"{"file":"./hosts/G1a/default.nix","results":[{"column":5,"endColumn":9,"#,
+        r#""line":3,"message":"Unused let binding: cfg"}]}"#,
+        "\n",
+        r#"{"file":"./modules/common.nix","results":[]}"#,
+        "\n",
+    );
+
+    #[test]
+    fn test_parse_statix() {
+        let findings = parse_statix(STATIX);
+        assert_eq!(findings.len(), 3);
+        assert_eq!(findings[0].file, "hosts/G1a/default.nix");
+        assert_eq!((findings[0].line, findings[0].column), (12, 3));
+        assert_eq!(findings[0].severity, LintSeverity::Warning);
+        assert_eq!(findings[0].message, "Assignment instead of inherit from");
+        assert_eq!(findings[1].file, "flake.nix");
+        assert_eq!(findings[1].severity, LintSeverity::Error);
+        assert_eq!(findings[2].severity, LintSeverity::Hint);
+    }
+
+    #[test]
+    fn test_parse_deadnix() {
+        let findings = parse_deadnix(DEADNIX);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].file, "hosts/G1a/default.nix");
+        assert_eq!(findings[0].tool, LintTool::Deadnix);
+        assert_eq!(findings[0].message, "Unused let binding: cfg");
+        assert!(parse_deadnix("error: not json").is_empty());
+    }
+
+    #[test]
+    fn test_report_grouped_by_file() {
+        let mut findings = parse_statix(STATIX);
+        findings.extend(parse_deadnix(DEADNIX));
+        let lines = report_lines(&findings);
+        assert_eq!(
+            lines,
+            vec![
+                "  flake.nix",
+                "    40:7 error   This let-in expression is empty (statix)",
+                "  hosts/G1a/default.nix",
+                "    3:5 warning Unused let binding: cfg (deadnix)",
+                "    12:3 warning Assignment instead of inherit from (statix)",
+                "  modules/common.nix",
+                "    5:1 hint    Found empty pattern in function argument (statix)",
+            ]
+        );
+        assert_eq!(count_summary(&findings), "1 error, 2 warnings, 1 hint in 3 files");
+        assert_eq!(count_summary(&[]), "no findings");
+    }
+}
@@ -0,0 +1,194 @@
+//! Timezone, locale and console keymap for a new host
+//!
+//! Prefilled from the live ISO (`/etc/localtime`, `LANG`, `/etc/vconsole.conf`)
+//! and confirmed on the install wizard's locale step, or given in the answer
+//! file. They end up in the host's default.nix as `time.timeZone`,
+//! `i18n.defaultLocale` and `console.keyMap`; modules/common.nix only has
+//! defaults for them.
+
+use anyhow::{bail, Result};
+use std::path::Path;
+
+/// Comment heading the settings in a host's default.nix
+pub const HOST_CONFIG_MARKER: &str = "# Timezone, locale and keymap, given to the installer";
+
+/// Where the tz database lives (NixOS, then other distros)
+const ZONEINFO_DIRS: &[&str] = &["/etc/zoneinfo", "/usr/share/zoneinfo"];
+
+const DEFAULT_TIMEZONE: &str = "UTC";
+const DEFAULT_LOCALE: &str = "en_US.UTF-8";
+const DEFAULT_KEYMAP: &str = "us";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleSettings {
+    /// tz database name, e.g. "Europe/Amsterdam"
+    pub timezone: String,
+    /// e.g. "en_US.UTF-8"
+    pub locale: String,
+    /// Console keymap, e.g. "us" or "de-latin1"
+    pub keymap: String,
+}
+
+impl LocaleSettings {
+    /// What the live ISO is using, falling back to UTC, en_US.UTF-8 and us
+    pub fn detect() -> Self {
+        let timezone = std::fs::read_link("/etc/localtime")
+            .ok()
+            .and_then(|target| timezone_from_link(&target));
+        let locale = std::env::var("LANG")
+            .ok()
+            .filter(|lang| validate_locale(lang).is_ok() && lang != "C")
+            .or_else(|| read_var("/etc/locale.conf", "LANG"));
+        let keymap = read_var("/etc/vconsole.conf", "KEYMAP");
+        Self {
+            timezone: timezone.unwrap_or_else(|| DEFAULT_TIMEZONE.to_string()),
+            locale: locale.unwrap_or_else(|| DEFAULT_LOCALE.to_string()),
+            keymap: keymap.unwrap_or_else(|| DEFAULT_KEYMAP.to_string()),
+        }
+    }
+
+    /// Replace the fields that were given
+    pub fn with_overrides(
+        mut self,
+        timezone: Option<&str>,
+        locale: Option<&str>,
+        keymap: Option<&str>,
+    ) -> Self {
+        if let Some(timezone) = timezone {
+            self.timezone = timezone.to_string();
+        }
+        if let Some(locale) = locale {
+            self.locale = locale.to_string();
+        }
+        if let Some(keymap) = keymap {
+            self.keymap = keymap.to_string();
+        }
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_timezone(&self.timezone)?;
+        validate_locale(&self.locale)?;
+        validate_keymap(&self.keymap)
+    }
+
+    /// Settings for the host's default.nix
+    pub fn host_config(&self) -> String {
+        format!(
+            "\n  {}\n  time.timeZone = \"{}\";\n  i18n.defaultLocale = \"{}\";\n  console.keyMap = \"{}\";\n",
+            HOST_CONFIG_MARKER, self.timezone, self.locale, self.keymap
+        )
+    }
+}
+
+/// "Europe/Amsterdam" from a link such as /etc/zoneinfo/Europe/Amsterdam
+fn timezone_from_link(target: &Path) -> Option<String> {
+    let target = target.to_str()?;
+    let (_, zone) = target.rsplit_once("zoneinfo/")?;
+    validate_name(zone, &['/', '_', '-', '+']).then(|| zone.to_string())
+}
+
+/// Value of `KEY=value` in a shell-style config file, quotes stripped
+fn read_var(path: &str, key: &str) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    parse_var(&content, key)
+}
+
+fn parse_var(content: &str, key: &str) -> Option<String> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix(key)?.strip_prefix('='))
+        .map(|value| value.trim().trim_matches('"').to_string())
+        .rfind(|value| !value.is_empty())
+}
+
+/// ASCII letters and digits plus `extra`; the values are written as Nix strings
+fn validate_name(value: &str, extra: &[char]) -> bool {
+    !value.is_empty()
+        && value.len() <= 64
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || extra.contains(&c))
+}
+
+pub fn validate_timezone(timezone: &str) -> Result<()> {
+    if !validate_name(timezone, &['/', '_', '-', '+'])
+        || timezone.starts_with('/')
+        || timezone.contains("..")
+    {
+        bail!("'{}' is not a timezone like Europe/Amsterdam", timezone);
+    }
+    // Checked against the tz database when the ISO has one
+    let known = ZONEINFO_DIRS.iter().map(Path::new).filter(|dir| dir.is_dir()).collect::<Vec<_>>();
+    if !known.is_empty() && !known.iter().any(|dir| dir.join(timezone).is_file()) {
+        bail!("Unknown timezone '{}'", timezone);
+    }
+    Ok(())
+}
+
+pub fn validate_locale(locale: &str) -> Result<()> {
+    if !validate_name(locale, &['_', '.', '-', '@']) {
+        bail!("'{}' is not a locale like en_US.UTF-8", locale);
+    }
+    Ok(())
+}
+
+pub fn validate_keymap(keymap: &str) -> Result<()> {
+    if !validate_name(keymap, &['_', '-']) {
+        bail!("'{}' is not a console keymap like us or de-latin1", keymap);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timezone_from_link() {
+        let zone = |path: &str| timezone_from_link(Path::new(path));
+        assert_eq!(zone("/etc/zoneinfo/Europe/Amsterdam").as_deref(), Some("Europe/Amsterdam"));
+        assert_eq!(
+            zone("/nix/store/abc-tzdata/share/zoneinfo/America/New_York").as_deref(),
+            Some("America/New_York")
+        );
+        assert_eq!(zone("../usr/share/zoneinfo/UTC").as_deref(), Some("UTC"));
+        assert_eq!(zone("/etc/localtime.bak"), None);
+    }
+
+    #[test]
+    fn test_parse_var() {
+        let vconsole = "# generated\nKEYMAP=\"de-latin1\"\nFONT=ter-v16n\n";
+        assert_eq!(parse_var(vconsole, "KEYMAP").as_deref(), Some("de-latin1"));
+        assert_eq!(parse_var(vconsole, "FONT").as_deref(), Some("ter-v16n"));
+        assert_eq!(parse_var("KEYMAP=\n", "KEYMAP"), None);
+        assert_eq!(parse_var("LANG=C\n", "LANGUAGE"), None);
+    }
+
+    #[test]
+    fn test_validate_rejects_nix_injection() {
+        assert!(validate_locale("en_US.UTF-8").is_ok());
+        assert!(validate_locale("sr_RS@latin").is_ok());
+        assert!(validate_locale("en_US\"; evil = \"").is_err());
+        assert!(validate_keymap("de-latin1").is_ok());
+        assert!(validate_keymap("us ${x}").is_err());
+        assert!(validate_timezone("../../etc/passwd").is_err());
+        assert!(validate_timezone("/etc/zoneinfo/UTC").is_err());
+        assert!(validate_timezone("").is_err());
+    }
+
+    #[test]
+    fn test_host_config() {
+        let settings = LocaleSettings {
+            timezone: "Europe/Berlin".to_string(),
+            locale: "de_DE.UTF-8".to_string(),
+            keymap: "de".to_string(),
+        };
+        let config = settings.host_config();
+        assert!(config.contains(HOST_CONFIG_MARKER));
+        assert!(config.contains("  time.timeZone = \"Europe/Berlin\";\n"));
+        assert!(config.contains("  i18n.defaultLocale = \"de_DE.UTF-8\";\n"));
+        assert!(config.contains("  console.keyMap = \"de\";\n"));
+        let keep = settings.with_overrides(None, Some("en_GB.UTF-8"), None);
+        assert_eq!(keep.timezone, "Europe/Berlin");
+        assert_eq!(keep.locale, "en_GB.UTF-8");
+    }
+}
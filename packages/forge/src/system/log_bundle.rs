@@ -0,0 +1,100 @@
+//! Install log bundle
+//!
+//! From the install's Complete screen, `b` packs everything needed to debug
+//! the install into one tarball: the screen log (and the `--record` cast),
+//! forge.log, saved build logs, the host's generated configs, `lsblk -J` and a
+//! hardware summary. It goes to /var/log/forge on the installed system when
+//! that is still mounted, so it outlives the live ISO; otherwise it stays in
+//! the forge data directory.
+
+use std::path::{Path, PathBuf};
+
+use crate::constants::{forge_data_dir, FORGE_LOG_FILE, INSTALL_MOUNT_POINT};
+
+/// Directory on the installed system, relative to its root
+const INSTALLED_LOG_DIR: &str = "var/log/forge";
+
+/// Fallback directory in the forge data directory of the live system
+const LOCAL_LOG_DIR: &str = "install-logs";
+
+/// What to put in the bundle besides the command outputs
+#[derive(Debug, Clone)]
+pub struct BundleSources {
+    /// Host being installed; None if the install never got that far
+    pub host: Option<String>,
+    pub screen_log: PathBuf,
+    /// Cast of the session when forge runs with `--record`
+    pub recording: Option<PathBuf>,
+    /// Build logs saved for failed derivations
+    pub build_logs: Vec<PathBuf>,
+}
+
+/// Directory the bundle is written to, and whether it is on the installed system
+pub fn bundle_dir(target_mounted: bool) -> (PathBuf, bool) {
+    if target_mounted {
+        (Path::new(INSTALL_MOUNT_POINT).join(INSTALLED_LOG_DIR), true)
+    } else {
+        (forge_data_dir().join(LOCAL_LOG_DIR), false)
+    }
+}
+
+/// Base name (without extension) of a bundle created at `stamp` (`%Y%m%d-%H%M%S`)
+pub fn bundle_name(host: Option<&str>, stamp: &str) -> String {
+    match host {
+        Some(host) => format!("forge-install-{}-{}", host, stamp),
+        None => format!("forge-install-{}", stamp),
+    }
+}
+
+/// forge.log and its daily rotations (`forge.log.2026-10-17`) in `dir`
+pub fn forge_log_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|e| is_forge_log(&e.file_name().to_string_lossy()))
+        .map(|e| e.path())
+        .collect();
+    files.sort();
+    files
+}
+
+fn is_forge_log(name: &str) -> bool {
+    name == FORGE_LOG_FILE || name.starts_with(&format!("{}.", FORGE_LOG_FILE))
+}
+
+/// Config files of `host` in a config checkout, relative to it
+pub fn host_config_paths(host: &str) -> Vec<String> {
+    vec![
+        format!("{}/{}", crate::constants::HOSTS_SUBDIR, host),
+        format!("modules/disko/{}.nix", host),
+        "flake.nix".to_string(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_dir_and_name() {
+        let (dir, installed) = bundle_dir(true);
+        assert_eq!(dir, PathBuf::from("/mnt/var/log/forge"));
+        assert!(installed);
+        let (dir, installed) = bundle_dir(false);
+        assert!(dir.ends_with("install-logs"));
+        assert!(!installed);
+
+        assert_eq!(bundle_name(Some("G1a"), "20261017-120000"), "forge-install-G1a-20261017-120000");
+        assert_eq!(bundle_name(None, "20261017-120000"), "forge-install-20261017-120000");
+    }
+
+    #[test]
+    fn test_is_forge_log() {
+        assert!(is_forge_log("forge.log"));
+        assert!(is_forge_log("forge.log.2026-10-17"));
+        assert!(!is_forge_log("forge.logs"));
+        assert!(!is_forge_log("screen.log"));
+    }
+}
@@ -0,0 +1,241 @@
+//! Mail reports for headless runs on machines without desktop notifications
+//!
+//! Loaded from `~/.config/forge/mail.toml`; without it nothing is mailed.
+//! Mail goes out through sendmail (e.g. msmtp's, `programs.msmtp`) or
+//! straight to an SMTP server with curl:
+//!
+//! ```toml
+//! to = ["admin@example.com"]
+//! from = "forge@server.lan"   # default forge@<hostname>
+//! on = "failure"              # always (default) or failure
+//! backend = "smtp"            # sendmail (default) or smtp
+//!
+//! [smtp]
+//! url = "smtps://smtp.example.com:465"   # smtp:// upgrades with STARTTLS
+//! user = "forge@example.com"
+//! password_file = "/run/secrets/smtp-password"
+//! ```
+
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use serde::Deserialize;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::constants::{mail_settings_path, runtime_dir};
+
+/// Which runs are mailed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailOn {
+    #[default]
+    Always,
+    Failure,
+}
+
+/// How mail leaves the machine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailBackend {
+    #[default]
+    Sendmail,
+    Smtp,
+}
+
+/// The `[smtp]` table
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SmtpSettings {
+    /// smtps://host:465, or smtp://host:587 for STARTTLS
+    pub url: String,
+    pub user: Option<String>,
+    /// File holding the password, so it stays out of mail.toml
+    pub password_file: Option<PathBuf>,
+}
+
+/// Parsed mail.toml
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MailSettings {
+    pub to: Vec<String>,
+    pub from: Option<String>,
+    #[serde(default)]
+    pub on: MailOn,
+    #[serde(default)]
+    pub backend: MailBackend,
+    /// sendmail binary (default: sendmail on PATH)
+    pub sendmail: Option<String>,
+    pub smtp: Option<SmtpSettings>,
+}
+
+impl MailSettings {
+    pub fn parse(content: &str) -> Result<Self> {
+        let settings: Self = toml::from_str(content)?;
+        if settings.to.is_empty() {
+            bail!("to lists no recipients");
+        }
+        for address in settings.to.iter().chain(settings.from.iter()) {
+            if !address.contains('@') || address.contains(['\r', '\n']) {
+                bail!("'{}' is not a mail address", address);
+            }
+        }
+        if settings.backend == MailBackend::Smtp && settings.smtp.is_none() {
+            bail!("backend = \"smtp\" needs an [smtp] table");
+        }
+        Ok(settings)
+    }
+
+    /// mail.toml, or None when there is none
+    pub fn load() -> Result<Option<Self>> {
+        let path = mail_settings_path();
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Ok(None);
+        };
+        Self::parse(&content)
+            .map(Some)
+            .with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    /// Whether a run that succeeded or failed is mailed
+    pub fn wants(&self, success: bool) -> bool {
+        self.on == MailOn::Always || !success
+    }
+
+    /// Send a plain-text mail to every recipient
+    pub async fn send(&self, host: &str, subject: &str, body: &str) -> Result<()> {
+        let from = self
+            .from
+            .clone()
+            .unwrap_or_else(|| format!("forge@{}", host));
+        let message = message(&from, &self.to, subject, body);
+        match self.backend {
+            MailBackend::Sendmail => {
+                let sendmail = self.sendmail.as_deref().unwrap_or("sendmail");
+                // -t takes the recipients from the headers, -i keeps lone dots
+                pipe(sendmail, &["-t", "-i", "-f", &from], &message).await
+            }
+            MailBackend::Smtp => {
+                let smtp = self.smtp.as_ref().context("No [smtp] settings")?;
+                send_smtp(smtp, &from, &self.to, &message).await
+            }
+        }
+    }
+}
+
+/// RFC 5322 message with a UTF-8 plain-text body, in local line endings
+/// (curl's `crlf` converts them for SMTP)
+fn message(from: &str, to: &[String], subject: &str, body: &str) -> String {
+    let mut message = format!(
+        "From: {}\nTo: {}\nSubject: {}\nDate: {}\nMIME-Version: 1.0\n\
+         Content-Type: text/plain; charset=utf-8\nContent-Transfer-Encoding: 8bit\n\n",
+        from,
+        to.join(", "),
+        subject.replace(['\r', '\n'], " "),
+        Local::now().to_rfc2822()
+    );
+    for line in body.lines() {
+        message.push_str(line);
+        message.push('\n');
+    }
+    message
+}
+
+/// Upload the message with curl; its config, with the credentials, goes in on
+/// stdin rather than the command line
+async fn send_smtp(smtp: &SmtpSettings, from: &str, to: &[String], message: &str) -> Result<()> {
+    let path = runtime_dir().join(format!("forge-mail-{}.eml", std::process::id()));
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&path)
+        .and_then(|mut file| file.write_all(message.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    let mut config = format!(
+        "url = {}\nmail-from = {}\nupload-file = {}\ncrlf\nssl-reqd\nsilent\nshow-error\n",
+        quoted(&smtp.url),
+        quoted(from),
+        quoted(&path.to_string_lossy())
+    );
+    for rcpt in to {
+        config.push_str(&format!("mail-rcpt = {}\n", quoted(rcpt)));
+    }
+    if let Some(user) = &smtp.user {
+        let password = match &smtp.password_file {
+            Some(file) => std::fs::read_to_string(file)
+                .with_context(|| format!("Failed to read {}", file.display()))?
+                .trim_end()
+                .to_string(),
+            None => String::new(),
+        };
+        config.push_str(&format!("user = {}\n", quoted(&format!("{}:{}", user, password))));
+    }
+    let result = pipe("curl", &["--config", "-"], &config).await;
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+/// A curl config string value
+fn quoted(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+async fn pipe(program: &str, args: &[&str], input: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        bail!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mail_settings() {
+        let settings = MailSettings::parse("to = [\"a@example.com\"]\non = \"failure\"\n").unwrap();
+        assert_eq!(settings.backend, MailBackend::Sendmail);
+        assert!(settings.wants(false));
+        assert!(!settings.wants(true));
+
+        assert!(MailSettings::parse("to = []").is_err());
+        assert!(MailSettings::parse("to = [\"nobody\"]").is_err());
+        assert!(MailSettings::parse("to = [\"a@example.com\"]\nbackend = \"smtp\"").is_err());
+        assert!(MailSettings::parse("to = [\"a@example.com\"]\ncc = \"b@example.com\"").is_err());
+    }
+
+    #[test]
+    fn test_message() {
+        let to = vec!["a@example.com".to_string(), "b@example.com".to_string()];
+        let message = message("forge@kraken", &to, "Update\nfailed", "line one\nline two");
+        assert!(message.starts_with("From: forge@kraken\nTo: a@example.com, b@example.com\n"));
+        assert!(message.contains("\nSubject: Update failed\n"));
+        assert!(message.ends_with("8bit\n\nline one\nline two\n"));
+    }
+
+    #[test]
+    fn test_quoted() {
+        assert_eq!(quoted(r#"pa"ss\word"#), r#""pa\"ss\\word""#);
+    }
+}
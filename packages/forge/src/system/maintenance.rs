@@ -0,0 +1,138 @@
+//! Maintenance windows: the hours a host may be updated without someone
+//! asking for it right now
+//!
+//! Written as `"HH:MM-HH:MM"` in fleet.toml, in the local time of the machine
+//! running forge. A window whose end is before its start runs past midnight,
+//! e.g. `"23:00-02:00"`.
+
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Daily span of time during which updates may start
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct MaintenanceWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl MaintenanceWindow {
+    /// Whether an update may start at `time`
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// When the window next opens after `now` (tomorrow's if it opened today)
+    pub fn next_start(&self, now: NaiveDateTime) -> NaiveDateTime {
+        let today = now.date().and_time(self.start);
+        if today > now {
+            today
+        } else {
+            today + Duration::days(1)
+        }
+    }
+
+    /// "open until 05:00" or "opens in 3h 20m"
+    pub fn status(&self, now: NaiveDateTime) -> String {
+        if self.contains(now.time()) {
+            return format!("open until {}", self.end.format("%H:%M"));
+        }
+        let minutes = (self.next_start(now) - now).num_minutes().max(1);
+        if minutes < 60 {
+            format!("opens in {}m", minutes)
+        } else {
+            format!("opens in {}h {:02}m", minutes / 60, minutes % 60)
+        }
+    }
+}
+
+impl FromStr for MaintenanceWindow {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((start, end)) = s.split_once('-') else {
+            bail!("Maintenance window '{}' is not HH:MM-HH:MM", s);
+        };
+        let time = |t: &str| {
+            let t = t.trim();
+            NaiveTime::parse_from_str(t, "%H:%M")
+                .with_context(|| format!("Invalid time '{}' in maintenance window '{}'", t, s))
+        };
+        let window = MaintenanceWindow {
+            start: time(start)?,
+            end: time(end)?,
+        };
+        if window.start == window.end {
+            bail!("Maintenance window '{}' is empty", s);
+        }
+        Ok(window)
+    }
+}
+
+impl TryFrom<String> for MaintenanceWindow {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for MaintenanceWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start.format("%H:%M"), self.end.format("%H:%M"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2026, 10, 17)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_window() {
+        let window: MaintenanceWindow = "02:00-05:00".parse().unwrap();
+        assert_eq!(window.to_string(), "02:00-05:00");
+        let spaced: MaintenanceWindow = " 23:30 - 01:00 ".parse().unwrap();
+        assert_eq!(spaced.to_string(), "23:30-01:00");
+        assert!("02:00".parse::<MaintenanceWindow>().is_err());
+        assert!("02:00-25:00".parse::<MaintenanceWindow>().is_err());
+        assert!("03:00-03:00".parse::<MaintenanceWindow>().is_err());
+    }
+
+    #[test]
+    fn test_window_contains() {
+        let night: MaintenanceWindow = "02:00-05:00".parse().unwrap();
+        assert!(night.contains(at(2, 0).time()));
+        assert!(night.contains(at(4, 59).time()));
+        assert!(!night.contains(at(5, 0).time()));
+        assert!(!night.contains(at(14, 0).time()));
+
+        let midnight: MaintenanceWindow = "23:00-02:00".parse().unwrap();
+        assert!(midnight.contains(at(23, 30).time()));
+        assert!(midnight.contains(at(1, 0).time()));
+        assert!(!midnight.contains(at(2, 0).time()));
+    }
+
+    #[test]
+    fn test_window_status() {
+        let night: MaintenanceWindow = "02:00-05:00".parse().unwrap();
+        assert_eq!(night.next_start(at(1, 0)), at(2, 0));
+        assert_eq!(night.next_start(at(3, 0)), at(2, 0) + Duration::days(1));
+        assert_eq!(night.status(at(3, 0)), "open until 05:00");
+        assert_eq!(night.status(at(1, 15)), "opens in 45m");
+        assert_eq!(night.status(at(22, 40)), "opens in 3h 20m");
+    }
+}
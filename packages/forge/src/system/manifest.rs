@@ -0,0 +1,113 @@
+//! Run manifest: an audit trail of the config edits forge made
+//!
+//! Each forge process appends to its own file in `~/.local/share/forge/runs`.
+//! An entry names the file, why it was edited and whether the edit was
+//! approved, followed by the unified diff that was reviewed. Systems forge
+//! built are recorded too, with their narHash and signatures.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Local};
+
+use crate::constants::run_manifests_dir;
+use crate::system::provenance::Provenance;
+
+/// Manifest of this run; the name is fixed by the process's first call
+pub fn run_manifest_path() -> &'static Path {
+    static PATH: OnceLock<PathBuf> = OnceLock::new();
+    PATH.get_or_init(|| {
+        let stamp = Local::now().format("%Y%m%d-%H%M%S");
+        run_manifests_dir().join(format!("forge-{}-{}.manifest", stamp, std::process::id()))
+    })
+}
+
+/// Append a reviewed edit to this run's manifest
+pub fn record_edit(file: &str, reason: &str, approved: bool, diff: &str) -> io::Result<()> {
+    append_entry(run_manifest_path(), &entry(Local::now(), file, reason, approved, diff))
+}
+
+/// Append a built system of `host` to this run's manifest
+pub fn record_build(host: &str, provenance: &Provenance) -> io::Result<()> {
+    append_entry(run_manifest_path(), &build_entry(Local::now(), host, provenance))
+}
+
+fn append_entry(path: &Path, entry: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(entry.as_bytes())
+}
+
+fn entry(time: DateTime<Local>, file: &str, reason: &str, approved: bool, diff: &str) -> String {
+    let verdict = if approved { "approved" } else { "rejected" };
+    let mut entry = format!(
+        "== {} {}: {} ({})\n{}",
+        time.format("%Y-%m-%d %H:%M:%S"),
+        file,
+        reason,
+        verdict,
+        diff
+    );
+    if !entry.ends_with('\n') {
+        entry.push('\n');
+    }
+    entry.push('\n');
+    entry
+}
+
+fn build_entry(time: DateTime<Local>, host: &str, provenance: &Provenance) -> String {
+    format!(
+        "== {} build {}: {} ({})\nnarHash: {}\n\n",
+        time.format("%Y-%m-%d %H:%M:%S"),
+        host,
+        provenance.path,
+        provenance.signing_status(),
+        provenance.nar_hash
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_append_entry() {
+        let dir = std::env::temp_dir().join(format!("forge-manifest-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("runs/forge.manifest");
+        let time = Local.with_ymd_and_hms(2026, 10, 17, 9, 30, 0).unwrap();
+
+        let diff = "--- a/flake.nix\n+++ b/flake.nix\n@@ -1,1 +1,1 @@\n-a\n+b\n";
+        append_entry(&path, &entry(time, "flake.nix", "add host 'kraken'", true, diff)).unwrap();
+        append_entry(&path, &entry(time, "flake.nix", "set username 'alex'", false, "")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!(
+                "== 2026-10-17 09:30:00 flake.nix: add host 'kraken' (approved)\n{}\n\
+                 == 2026-10-17 09:30:00 flake.nix: set username 'alex' (rejected)\n\n",
+                diff
+            )
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_build_entry() {
+        let time = Local.with_ymd_and_hms(2026, 10, 17, 9, 30, 0).unwrap();
+        let provenance = Provenance {
+            path: "/nix/store/abc-nixos-system-kraken".to_string(),
+            nar_hash: "sha256-AAAA".to_string(),
+            signers: Vec::new(),
+        };
+        assert_eq!(
+            build_entry(time, "kraken", &provenance),
+            "== 2026-10-17 09:30:00 build kraken: /nix/store/abc-nixos-system-kraken (unsigned)\n\
+             narHash: sha256-AAAA\n\n"
+        );
+    }
+}
@@ -1,9 +1,48 @@
 //! System utilities
 
+pub mod accounts;
+pub mod answers;
+pub mod bisect;
+pub mod build_log;
+pub mod build_progress;
+pub mod channels;
 pub mod config;
+pub mod devshell;
+pub mod diff;
 pub mod disk;
+pub mod display;
+pub mod flake_edit;
+pub mod fleet;
+pub mod format;
+pub mod gc;
+pub mod generations;
 pub mod hardware;
+pub mod host_state;
+pub mod install_progress;
+pub mod install_settings;
+pub mod ipc;
+pub mod lint;
+pub mod locale;
+pub mod log_bundle;
+pub mod mail;
+pub mod maintenance;
+pub mod manifest;
 pub mod network;
+pub mod nix_conf;
+pub mod nix_settings;
+pub mod offline;
+pub mod optimise;
+pub mod parallelism;
+pub mod passphrase;
+pub mod perms;
+pub mod pipeline;
+pub mod preflight;
+pub mod provenance;
+pub mod recording;
+pub mod registry;
+pub mod repo_status;
+pub mod services;
+pub mod wifi;
 
 /// Check if we're running from a NixOS Live ISO environment
 pub fn is_live_iso_environment() -> bool {
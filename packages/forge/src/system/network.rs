@@ -5,8 +5,22 @@
 use anyhow::Result;
 use std::process::Command;
 
+/// curl arguments for the connectivity check through a proxy
+pub const PROXY_CHECK_ARGS: &[&str] = &["-sSI", "--max-time", "10", "https://github.com"];
+
 /// Check if network is available by pinging github.com
+///
+/// Behind a proxy ICMP usually doesn't get out, so then it asks over HTTPS.
 pub fn check_connectivity() -> Result<bool> {
+    if super::nix_settings::current().proxy.is_some() {
+        super::nix_settings::apply_proxy_env();
+        let status = Command::new("curl")
+            .args(PROXY_CHECK_ARGS)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status()?;
+        return Ok(status.success());
+    }
     let status = Command::new("ping")
         .args(["-c", "1", "-W", "5", "github.com"])
         .stdout(std::process::Stdio::null())
@@ -0,0 +1,412 @@
+//! nix.conf settings forge relies on, shared through the config repo
+//!
+//! `nix-conf.json` at the repo root holds settings for every host plus
+//! per-host overrides, in the same shape as gc.json. modules/nix-conf.nix
+//! turns it into `nix.settings`, so changes land with the next rebuild
+//! instead of being written to /etc/nix/nix.conf by hand:
+//!
+//! ```json
+//! {
+//!   "defaults": { "max_jobs": "4" },
+//!   "hosts": { "G1a": { "cores": 2, "sandbox": false } }
+//! }
+//! ```
+//!
+//! Unset fields are inherited: a host falls back to `defaults`, and `defaults`
+//! to `NixConfSettings::default()` (the same values modules/nix-conf.nix
+//! starts from). What this machine runs with now is read back from
+//! /etc/nix/nix.conf, so settings still waiting for a rebuild stand out.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// nix.conf settings filename (repo root)
+pub const NIX_CONF_FILE: &str = "nix-conf.json";
+
+/// nix.conf NixOS generates from `nix.settings`
+pub const SYSTEM_NIX_CONF: &str = "/etc/nix/nix.conf";
+
+/// Experimental features forge can't work without
+const REQUIRED_FEATURES: &[&str] = &["nix-command", "flakes"];
+
+/// One editable setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NixConfField {
+    /// `experimental-features`
+    ExperimentalFeatures,
+    /// `max-jobs` (a number or "auto")
+    MaxJobs,
+    /// `cores` (0 = all of them)
+    Cores,
+    /// `sandbox`
+    Sandbox,
+}
+
+impl NixConfField {
+    /// All fields, in screen order
+    pub const ALL: [NixConfField; 4] = [
+        NixConfField::ExperimentalFeatures,
+        NixConfField::MaxJobs,
+        NixConfField::Cores,
+        NixConfField::Sandbox,
+    ];
+
+    /// Label shown on the settings screen
+    pub fn label(self) -> &'static str {
+        match self {
+            NixConfField::ExperimentalFeatures => "Experimental features",
+            NixConfField::MaxJobs => "Max jobs",
+            NixConfField::Cores => "Cores per build",
+            NixConfField::Sandbox => "Sandbox",
+        }
+    }
+
+    /// Name of the setting in nix.conf
+    pub fn key(self) -> &'static str {
+        match self {
+            NixConfField::ExperimentalFeatures => "experimental-features",
+            NixConfField::MaxJobs => "max-jobs",
+            NixConfField::Cores => "cores",
+            NixConfField::Sandbox => "sandbox",
+        }
+    }
+
+    /// On/off setting (toggled) rather than text (typed)
+    pub fn is_toggle(self) -> bool {
+        self == NixConfField::Sandbox
+    }
+}
+
+/// Settings in effect for a host once overrides are applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NixConfSettings {
+    pub experimental_features: Vec<String>,
+    pub max_jobs: String,
+    pub cores: u32,
+    pub sandbox: bool,
+}
+
+impl Default for NixConfSettings {
+    // Keep in sync with the defaults in modules/nix-conf.nix
+    fn default() -> Self {
+        NixConfSettings {
+            experimental_features: REQUIRED_FEATURES.iter().map(|f| f.to_string()).collect(),
+            max_jobs: "auto".to_string(),
+            cores: 0,
+            sandbox: true,
+        }
+    }
+}
+
+impl NixConfSettings {
+    /// Value of a field as nix.conf writes it ("nix-command flakes", "true")
+    pub fn value(&self, field: NixConfField) -> String {
+        match field {
+            NixConfField::ExperimentalFeatures => self.experimental_features.join(" "),
+            NixConfField::MaxJobs => self.max_jobs.clone(),
+            NixConfField::Cores => self.cores.to_string(),
+            NixConfField::Sandbox => self.sandbox.to_string(),
+        }
+    }
+
+    /// Whether nix.conf's value for a field already matches this one
+    pub fn matches(&self, field: NixConfField, live: &str) -> bool {
+        match field {
+            // Order doesn't matter to nix
+            NixConfField::ExperimentalFeatures => {
+                let mut live: Vec<&str> = live.split_whitespace().collect();
+                let mut wanted: Vec<&str> =
+                    self.experimental_features.iter().map(String::as_str).collect();
+                live.sort_unstable();
+                wanted.sort_unstable();
+                live == wanted
+            }
+            _ => live.trim() == self.value(field),
+        }
+    }
+}
+
+/// Fields set at one level (defaults or a host); unset fields are inherited
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NixConfOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub experimental_features: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_jobs: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cores: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox: Option<bool>,
+}
+
+impl NixConfOverrides {
+    /// Whether this level sets the field itself
+    pub fn is_set(&self, field: NixConfField) -> bool {
+        match field {
+            NixConfField::ExperimentalFeatures => self.experimental_features.is_some(),
+            NixConfField::MaxJobs => self.max_jobs.is_some(),
+            NixConfField::Cores => self.cores.is_some(),
+            NixConfField::Sandbox => self.sandbox.is_some(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        NixConfField::ALL.iter().all(|f| !self.is_set(*f))
+    }
+
+    fn apply(&self, settings: &mut NixConfSettings) {
+        if let Some(features) = &self.experimental_features {
+            settings.experimental_features = features.clone();
+        }
+        if let Some(jobs) = &self.max_jobs {
+            settings.max_jobs = jobs.clone();
+        }
+        if let Some(cores) = self.cores {
+            settings.cores = cores;
+        }
+        if let Some(sandbox) = self.sandbox {
+            settings.sandbox = sandbox;
+        }
+    }
+}
+
+/// nix-conf.json contents
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NixConfConfig {
+    /// Applies to every host
+    #[serde(default)]
+    pub defaults: NixConfOverrides,
+    /// Per-host overrides, keyed by hostname
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub hosts: BTreeMap<String, NixConfOverrides>,
+}
+
+impl NixConfConfig {
+    /// Settings in effect for a host, or for hosts without overrides (None)
+    pub fn resolve(&self, host: Option<&str>) -> NixConfSettings {
+        let mut settings = NixConfSettings::default();
+        self.defaults.apply(&mut settings);
+        if let Some(overrides) = host.and_then(|h| self.hosts.get(h)) {
+            overrides.apply(&mut settings);
+        }
+        settings
+    }
+
+    /// Whether a field is set at this level rather than inherited
+    pub fn is_set(&self, host: Option<&str>, field: NixConfField) -> bool {
+        match host {
+            None => self.defaults.is_set(field),
+            Some(h) => self.hosts.get(h).is_some_and(|o| o.is_set(field)),
+        }
+    }
+
+    fn overrides_mut(&mut self, host: Option<&str>) -> &mut NixConfOverrides {
+        match host {
+            None => &mut self.defaults,
+            Some(h) => self.hosts.entry(h.to_string()).or_default(),
+        }
+    }
+
+    /// Flip the sandbox, starting from the value currently in effect
+    pub fn toggle(&mut self, host: Option<&str>, field: NixConfField) {
+        let current = self.resolve(host);
+        if field == NixConfField::Sandbox {
+            self.overrides_mut(host).sandbox = Some(!current.sandbox);
+        }
+    }
+
+    /// Set a text field, validating the value first
+    pub fn set_text(
+        &mut self,
+        host: Option<&str>,
+        field: NixConfField,
+        value: &str,
+    ) -> Result<(), String> {
+        let value = value.trim();
+        let overrides = self.overrides_mut(host);
+        match field {
+            NixConfField::ExperimentalFeatures => {
+                overrides.experimental_features = Some(parse_features(value)?);
+            }
+            NixConfField::MaxJobs => overrides.max_jobs = Some(parse_max_jobs(value)?),
+            NixConfField::Cores => {
+                overrides.cores =
+                    Some(value.parse().map_err(|_| "Use a number, 0 for all cores")?);
+            }
+            NixConfField::Sandbox => {}
+        }
+        Ok(())
+    }
+
+    /// Unset a field at this level so it is inherited again
+    pub fn reset(&mut self, host: Option<&str>, field: NixConfField) {
+        let overrides = self.overrides_mut(host);
+        match field {
+            NixConfField::ExperimentalFeatures => overrides.experimental_features = None,
+            NixConfField::MaxJobs => overrides.max_jobs = None,
+            NixConfField::Cores => overrides.cores = None,
+            NixConfField::Sandbox => overrides.sandbox = None,
+        }
+        // Don't leave empty host entries behind in nix-conf.json
+        if let Some(h) = host {
+            if self.hosts.get(h).is_some_and(NixConfOverrides::is_empty) {
+                self.hosts.remove(h);
+            }
+        }
+    }
+}
+
+/// Space-separated feature names; nix-command and flakes can't be dropped
+fn parse_features(value: &str) -> Result<Vec<String>, String> {
+    let mut features: Vec<String> = Vec::new();
+    for feature in value.split_whitespace() {
+        if !feature.chars().all(|c| c.is_ascii_lowercase() || c == '-') {
+            return Err(format!("'{}' is not a feature name", feature));
+        }
+        if !features.iter().any(|f| f == feature) {
+            features.push(feature.to_string());
+        }
+    }
+    if let Some(missing) = REQUIRED_FEATURES
+        .iter()
+        .find(|f| !features.iter().any(|feature| feature == *f))
+    {
+        return Err(format!("forge needs {} (keep nix-command flakes)", missing));
+    }
+    Ok(features)
+}
+
+/// "auto" or a number of builds
+fn parse_max_jobs(value: &str) -> Result<String, String> {
+    if value == "auto" || value.parse::<u32>().is_ok() {
+        Ok(value.to_string())
+    } else {
+        Err("Use a number of builds or auto".to_string())
+    }
+}
+
+/// Read nix-conf.json (all defaults if the file doesn't exist yet)
+pub fn load_nix_conf_config(config_dir: &Path) -> Result<NixConfConfig> {
+    let path = config_dir.join(NIX_CONF_FILE);
+    if !path.exists() {
+        return Ok(NixConfConfig::default());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+}
+
+/// Write nix-conf.json, pretty-printed for readable diffs
+pub fn save_nix_conf_config(config_dir: &Path, config: &NixConfConfig) -> Result<()> {
+    let path = config_dir.join(NIX_CONF_FILE);
+    let json = serde_json::to_string_pretty(config)? + "\n";
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The fields' values in a nix.conf ("key = value" lines, # comments)
+pub fn parse_nix_conf(content: &str) -> BTreeMap<String, String> {
+    let keys: Vec<&str> = NixConfField::ALL.iter().map(|f| f.key()).collect();
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.split('#').next()?;
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            keys.contains(&key)
+                .then(|| (key.to_string(), value.split_whitespace().collect::<Vec<_>>().join(" ")))
+        })
+        .collect()
+}
+
+/// What this machine's nix runs with now, keyed by nix.conf name
+pub fn read_system_nix_conf() -> Result<BTreeMap<String, String>> {
+    let content = std::fs::read_to_string(SYSTEM_NIX_CONF)
+        .with_context(|| format!("Failed to read {}", SYSTEM_NIX_CONF))?;
+    Ok(parse_nix_conf(&content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NIX_CONF_JSON: &str = r#"{
+        "defaults": { "max_jobs": "4" },
+        "hosts": { "G1a": { "cores": 2, "sandbox": false } }
+    }"#;
+
+    #[test]
+    fn test_resolve_overrides() {
+        let config: NixConfConfig = serde_json::from_str(NIX_CONF_JSON).unwrap();
+
+        let defaults = config.resolve(None);
+        assert_eq!(defaults.max_jobs, "4");
+        assert_eq!(defaults.value(NixConfField::ExperimentalFeatures), "nix-command flakes");
+        assert!(defaults.sandbox);
+
+        let g1a = config.resolve(Some("G1a"));
+        assert_eq!(g1a.max_jobs, "4");
+        assert_eq!(g1a.cores, 2);
+        assert!(!g1a.sandbox);
+
+        assert_eq!(config.resolve(Some("kraken")), defaults);
+        assert!(config.is_set(Some("G1a"), NixConfField::Cores));
+        assert!(!config.is_set(Some("G1a"), NixConfField::MaxJobs));
+    }
+
+    #[test]
+    fn test_edit_and_reset() {
+        let mut config = NixConfConfig::default();
+        config.toggle(Some("kraken"), NixConfField::Sandbox);
+        assert!(!config.resolve(Some("kraken")).sandbox);
+
+        let features = "nix-command flakes ca-derivations flakes";
+        assert!(config.set_text(None, NixConfField::ExperimentalFeatures, features).is_ok());
+        assert_eq!(
+            config.resolve(Some("kraken")).experimental_features,
+            vec!["nix-command", "flakes", "ca-derivations"]
+        );
+        assert!(config.set_text(None, NixConfField::ExperimentalFeatures, "flakes").is_err());
+        assert!(config.set_text(None, NixConfField::ExperimentalFeatures, "nix_command").is_err());
+
+        assert!(config.set_text(None, NixConfField::MaxJobs, "auto").is_ok());
+        assert!(config.set_text(None, NixConfField::MaxJobs, "many").is_err());
+        assert!(config.set_text(Some("kraken"), NixConfField::Cores, "-1").is_err());
+
+        config.reset(Some("kraken"), NixConfField::Sandbox);
+        assert!(config.hosts.is_empty());
+        config.reset(None, NixConfField::ExperimentalFeatures);
+        assert_eq!(
+            serde_json::to_string(&config).unwrap(),
+            r#"{"defaults":{"max_jobs":"auto"}}"#
+        );
+    }
+
+    #[test]
+    fn test_parse_nix_conf() {
+        let conf = "# WARNING: this file is generated\n\
+                    cores = 0\n\
+                    experimental-features = flakes  nix-command\n\
+                    max-jobs = auto\n\
+                    sandbox = true # builds are isolated\n\
+                    substituters = https://cache.nixos.org/\n";
+        let live = parse_nix_conf(conf);
+        assert_eq!(live.len(), 4);
+        assert_eq!(live["sandbox"], "true");
+
+        let settings = NixConfSettings::default();
+        for field in NixConfField::ALL {
+            assert!(settings.matches(field, &live[field.key()]), "{}", field.key());
+        }
+        assert!(!settings.matches(NixConfField::Sandbox, "relaxed"));
+    }
+
+    #[test]
+    fn test_unknown_keys_rejected() {
+        let json = r#"{"defaults":{"max-jobs":"4"}}"#;
+        assert!(serde_json::from_str::<NixConfConfig>(json).is_err());
+    }
+}
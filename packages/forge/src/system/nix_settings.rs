@@ -0,0 +1,185 @@
+//! Extra binary caches and an HTTP(S) proxy for installs
+//!
+//! Given with `forge install --substituter/--trusted-public-key/--proxy` or the
+//! answer file's `[nix]` table; a proxy already exported on the ISO
+//! (`https_proxy`, `http_proxy`) is picked up as well. Install commands see the
+//! settings through NIX_CONFIG and the proxy variables, and the host's
+//! default.nix gets them too, so the new system keeps working on that network.
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+use super::offline;
+
+/// Proxy variables, in the order they are looked up
+const PROXY_VARS: &[&str] = &["https_proxy", "HTTPS_PROXY", "http_proxy", "HTTP_PROXY"];
+
+/// Comment heading the settings in a host's default.nix
+pub const HOST_CONFIG_MARKER: &str = "# Binary caches and proxy, given to the installer";
+
+/// URL schemes nix can substitute from
+const SUBSTITUTER_SCHEMES: &[&str] =
+    &["https://", "http://", "file://", "s3://", "ssh://", "ssh-ng://"];
+
+static SETTINGS: OnceLock<NixSettings> = OnceLock::new();
+
+/// Binary caches and proxy beyond the NixOS defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NixSettings {
+    /// Extra substituters, e.g. "https://cache.corp.example"
+    #[serde(default)]
+    pub substituters: Vec<String>,
+    /// Keys those caches sign with, "name:base64"
+    #[serde(default)]
+    pub trusted_public_keys: Vec<String>,
+    /// e.g. "http://proxy.corp.example:3128"
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+impl NixSettings {
+    pub fn is_empty(&self) -> bool {
+        self.substituters.is_empty()
+            && self.trusted_public_keys.is_empty()
+            && self.proxy.is_none()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for url in &self.substituters {
+            if !SUBSTITUTER_SCHEMES.iter().any(|scheme| url.starts_with(scheme)) {
+                bail!("Substituter '{}' needs a scheme such as https://", url);
+            }
+        }
+        for key in &self.trusted_public_keys {
+            match key.split_once(':') {
+                Some((name, value)) if !name.is_empty() && !value.is_empty() => {}
+                _ => bail!("Trusted public key '{}' should look like name:base64", key),
+            }
+        }
+        if let Some(proxy) = &self.proxy {
+            if !proxy.starts_with("http://") && !proxy.starts_with("https://") {
+                bail!("Proxy '{}' should start with http:// or https://", proxy);
+            }
+        }
+        Ok(())
+    }
+
+    /// Take the proxy from the environment when none was given
+    fn with_env_proxy(mut self) -> Self {
+        if self.proxy.is_none() {
+            self.proxy = PROXY_VARS
+                .iter()
+                .filter_map(|var| std::env::var(var).ok())
+                .find(|value| !value.trim().is_empty());
+        }
+        self
+    }
+
+    /// NIX_CONFIG lines for the caches (empty when there are none)
+    fn nix_config_lines(&self) -> String {
+        let mut lines = String::new();
+        if !self.substituters.is_empty() {
+            lines.push_str(&format!("\nextra-substituters = {}", self.substituters.join(" ")));
+        }
+        if !self.trusted_public_keys.is_empty() {
+            lines.push_str(&format!(
+                "\nextra-trusted-public-keys = {}",
+                self.trusted_public_keys.join(" ")
+            ));
+        }
+        lines
+    }
+
+    /// Settings for the host's default.nix
+    pub fn host_config(&self) -> String {
+        let list = |items: &[String]| {
+            items.iter().map(|item| format!("\"{}\"", item)).collect::<Vec<_>>().join(" ")
+        };
+        let mut config = format!("\n  {}\n", HOST_CONFIG_MARKER);
+        if !self.substituters.is_empty() {
+            config.push_str(&format!(
+                "  nix.settings.extra-substituters = [ {} ];\n",
+                list(&self.substituters)
+            ));
+        }
+        if !self.trusted_public_keys.is_empty() {
+            config.push_str(&format!(
+                "  nix.settings.extra-trusted-public-keys = [ {} ];\n",
+                list(&self.trusted_public_keys)
+            ));
+        }
+        if let Some(proxy) = &self.proxy {
+            config.push_str(&format!("  networking.proxy.default = \"{}\";\n", proxy));
+        }
+        config
+    }
+}
+
+/// Use `settings` for this run's installs; called once, before the TUI starts
+pub fn configure(settings: NixSettings) {
+    let _ = SETTINGS.set(settings.with_env_proxy());
+}
+
+/// Settings in effect (only an exported proxy, if none were configured)
+pub fn current() -> &'static NixSettings {
+    SETTINGS.get_or_init(|| NixSettings::default().with_env_proxy())
+}
+
+/// NIX_CONFIG for install commands: flakes, offline settings and extra caches
+pub fn nix_config() -> String {
+    offline::nix_config() + &current().nix_config_lines()
+}
+
+/// Export the proxy to the commands forge runs (curl, git and nix read these)
+pub fn apply_proxy_env() {
+    if let Some(proxy) = &current().proxy {
+        for var in PROXY_VARS {
+            std::env::set_var(var, proxy);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> NixSettings {
+        NixSettings {
+            substituters: vec!["https://cache.corp.example".to_string()],
+            trusted_public_keys: vec!["cache.corp.example-1:abc=".to_string()],
+            proxy: Some("http://proxy.corp.example:3128".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(settings().validate().is_ok());
+        assert!(NixSettings::default().validate().is_ok());
+
+        let mut bad = settings();
+        bad.substituters = vec!["cache.corp.example".to_string()];
+        assert!(bad.validate().is_err());
+        let mut bad = settings();
+        bad.trusted_public_keys = vec!["abc=".to_string()];
+        assert!(bad.validate().is_err());
+        let mut bad = settings();
+        bad.proxy = Some("proxy:3128".to_string());
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_output() {
+        let settings = settings();
+        assert_eq!(
+            settings.nix_config_lines(),
+            "\nextra-substituters = https://cache.corp.example\n\
+             extra-trusted-public-keys = cache.corp.example-1:abc="
+        );
+        let host = settings.host_config();
+        assert!(host.contains("extra-substituters = [ \"https://cache.corp.example\" ];"));
+        assert!(host.contains("networking.proxy.default = \"http://proxy.corp.example:3128\";"));
+        assert_eq!(NixSettings::default().nix_config_lines(), "");
+    }
+}
@@ -0,0 +1,123 @@
+//! Offline installs from a local copy of the config repo (`forge install --offline DIR`)
+//!
+//! DIR is a nixos-config checkout, or a directory (e.g. a USB stick) holding
+//! one as `nixos-config/`. Next to the checkout there may be a binary cache
+//! made with `nix copy --to file://$DIR/nix-cache <closure>`; without one,
+//! everything the install needs must already be in the ISO's store.
+
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::constants::{FLAKE_NIX, HOSTS_SUBDIR};
+
+/// Repo directory name looked for under DIR
+const REPO_DIR: &str = "nixos-config";
+
+/// Binary cache directory name looked for under DIR
+const CACHE_DIR: &str = "nix-cache";
+
+/// Nix settings every install needs
+const BASE_NIX_CONFIG: &str = "experimental-features = nix-command flakes";
+
+static OFFLINE: OnceLock<OfflineSource> = OnceLock::new();
+
+/// Where an offline install takes its config and store paths from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfflineSource {
+    pub repo: PathBuf,
+    /// `file://` binary cache, if the archive has one
+    pub cache: Option<PathBuf>,
+}
+
+impl OfflineSource {
+    /// Find the repo (and cache) in `dir`
+    pub fn locate(dir: &Path) -> Result<Self> {
+        let is_repo =
+            |path: &Path| path.join(FLAKE_NIX).is_file() && path.join(HOSTS_SUBDIR).is_dir();
+        let repo = if is_repo(dir) {
+            dir.to_path_buf()
+        } else if is_repo(&dir.join(REPO_DIR)) {
+            dir.join(REPO_DIR)
+        } else {
+            bail!(
+                "{} is not a nixos-config checkout and has no {}/ inside",
+                dir.display(),
+                REPO_DIR
+            );
+        };
+        let cache = [dir.join(CACHE_DIR), repo.join(CACHE_DIR)]
+            .into_iter()
+            .find(|path| path.join("nix-cache-info").is_file());
+        Ok(Self { repo, cache })
+    }
+
+    /// NIX_CONFIG for this source: only the local cache, or no substitution at all
+    pub fn nix_config(&self) -> String {
+        match &self.cache {
+            // Locally built paths in the archive carry no signature
+            Some(cache) => format!(
+                "{}\nsubstituters = file://{}\nrequire-sigs = false",
+                BASE_NIX_CONFIG,
+                cache.display()
+            ),
+            None => format!("{}\nsubstitute = false", BASE_NIX_CONFIG),
+        }
+    }
+}
+
+/// Switch this process to offline installs; called once, before the TUI starts
+pub fn enable(source: OfflineSource) {
+    let _ = OFFLINE.set(source);
+}
+
+/// The offline source, when installing without network
+pub fn source() -> Option<&'static OfflineSource> {
+    OFFLINE.get()
+}
+
+/// NIX_CONFIG for install commands (flakes, plus the offline settings)
+pub fn nix_config() -> String {
+    source().map_or_else(|| BASE_NIX_CONFIG.to_string(), OfflineSource::nix_config)
+}
+
+/// Extra `nix` flags: `--offline` when there is no cache to substitute from
+///
+/// `--offline` also turns off substituters, so it is left out when the
+/// archive brings a cache.
+pub fn nix_args() -> &'static [&'static str] {
+    match source() {
+        Some(OfflineSource { cache: None, .. }) => &["--offline"],
+        _ => &[],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate() {
+        let dir = std::env::temp_dir().join(format!("forge-offline-test-{}", std::process::id()));
+        let repo = dir.join(REPO_DIR);
+        std::fs::create_dir_all(repo.join(HOSTS_SUBDIR)).unwrap();
+        std::fs::write(repo.join(FLAKE_NIX), "{ }").unwrap();
+
+        let source = OfflineSource::locate(&dir).unwrap();
+        assert_eq!(source.repo, repo);
+        assert_eq!(source.cache, None);
+        assert!(source.nix_config().ends_with("substitute = false"));
+        assert!(OfflineSource::locate(&dir.join(HOSTS_SUBDIR)).is_err());
+
+        std::fs::create_dir_all(dir.join(CACHE_DIR)).unwrap();
+        std::fs::write(dir.join(CACHE_DIR).join("nix-cache-info"), "StoreDir: /nix/store\n")
+            .unwrap();
+        let source = OfflineSource::locate(&repo).unwrap();
+        assert_eq!(source.cache, None);
+        let source = OfflineSource::locate(&dir).unwrap();
+        assert_eq!(source.cache.as_deref(), Some(dir.join(CACHE_DIR).as_path()));
+        assert!(source.nix_config().contains("substituters = file://"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,159 @@
+//! Progress of `nix store optimise`, read from `--log-format internal-json`
+//!
+//! Nix reports the run as an activity (type 106) whose progress results carry
+//! `[done, expected, ...]` store paths, and one "file linked" result per
+//! hard link with the file size:
+//!
+//! ```text
+//! @nix {"action":"start","id":1,"type":106,"level":0,"text":"","parent":0,"fields":[]}
+//! @nix {"action":"result","id":1,"type":105,"fields":[120,5000,0,0]}
+//! @nix {"action":"result","id":7,"type":100,"fields":[4096,8]}
+//! @nix {"action":"msg","level":1,"msg":"1.2 MiB freed by hard-linking 310 files"}
+//! ```
+
+use serde_json::Value;
+
+const ACT_OPTIMISE_STORE: u64 = 106;
+const RES_FILE_LINKED: u64 = 100;
+const RES_PROGRESS: u64 = 105;
+
+/// Running totals for one optimise run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OptimiseProgress {
+    /// Store paths scanned so far
+    pub paths_done: u64,
+    /// Store paths to scan (0 until Nix reports it)
+    pub paths_total: u64,
+    pub files_linked: u64,
+    pub bytes_saved: u64,
+    /// Id of the optimise activity, so its progress isn't confused with others
+    activity: Option<u64>,
+}
+
+impl OptimiseProgress {
+    /// Fold one line of Nix's stderr into the totals
+    ///
+    /// Returns text worth showing in the output log: log messages, and any
+    /// line that isn't internal-json (e.g. an error from a wrapper).
+    pub fn update(&mut self, line: &str) -> Option<String> {
+        let Some(json) = line.strip_prefix("@nix ") else {
+            return Some(line.to_string());
+        };
+        let event: Value = serde_json::from_str(json).ok()?;
+        let id = event.get("id").and_then(Value::as_u64);
+        let kind = event.get("type").and_then(Value::as_u64);
+        let field = |i: usize| {
+            event
+                .get("fields")
+                .and_then(|f| f.get(i))
+                .and_then(Value::as_u64)
+                .unwrap_or(0)
+        };
+
+        match event.get("action").and_then(Value::as_str)? {
+            "start" if kind == Some(ACT_OPTIMISE_STORE) => {
+                self.activity = id;
+                None
+            }
+            "result" if kind == Some(RES_PROGRESS) && id.is_some() && id == self.activity => {
+                self.paths_done = field(0);
+                self.paths_total = field(1);
+                None
+            }
+            "result" if kind == Some(RES_FILE_LINKED) => {
+                self.files_linked += 1;
+                self.bytes_saved += field(0);
+                None
+            }
+            "msg" => event
+                .get("msg")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            _ => None,
+        }
+    }
+
+    /// Share of store paths scanned, 0-100
+    pub fn percent(&self) -> u16 {
+        if self.paths_total == 0 {
+            return 0;
+        }
+        (self.paths_done.min(self.paths_total) * 100 / self.paths_total) as u16
+    }
+
+    /// One-line result, e.g. "Hard-linked 310 files, saved 1.2 MiB"
+    pub fn summary(&self) -> String {
+        format!(
+            "Hard-linked {} files, saved {}",
+            self.files_linked,
+            format_bytes(self.bytes_saved)
+        )
+    }
+}
+
+/// Human-readable size in binary units
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_from_internal_json() {
+        let mut progress = OptimiseProgress::default();
+        let lines = [
+            r#"@nix {"action":"start","id":1,"type":106,"level":0,"text":"","parent":0,"fields":[]}"#,
+            r#"@nix {"action":"result","id":1,"type":105,"fields":[0,200,0,0]}"#,
+            r#"@nix {"action":"start","id":2,"type":0,"level":4,"text":"optimising path '/nix/store/abc-foo'","parent":0,"fields":[]}"#,
+            r#"@nix {"action":"result","id":2,"type":100,"fields":[4096,8]}"#,
+            r#"@nix {"action":"result","id":2,"type":100,"fields":[1048576,2048]}"#,
+            r#"@nix {"action":"stop","id":2}"#,
+            // A copy's progress must not move the bar
+            r#"@nix {"action":"result","id":9,"type":105,"fields":[5,6,0,0]}"#,
+            r#"@nix {"action":"result","id":1,"type":105,"fields":[50,200,0,0]}"#,
+        ];
+        for line in lines {
+            assert_eq!(progress.update(line), None);
+        }
+        assert_eq!(progress.paths_done, 50);
+        assert_eq!(progress.paths_total, 200);
+        assert_eq!(progress.percent(), 25);
+        assert_eq!(progress.files_linked, 2);
+        assert_eq!(progress.bytes_saved, 1_052_672);
+        assert_eq!(progress.summary(), "Hard-linked 2 files, saved 1.0 MiB");
+
+        let msg =
+            r#"@nix {"action":"msg","level":1,"msg":"1.0 MiB freed by hard-linking 2 files"}"#;
+        assert_eq!(
+            progress.update(msg).as_deref(),
+            Some("1.0 MiB freed by hard-linking 2 files")
+        );
+        assert_eq!(
+            progress.update("error: permission denied").as_deref(),
+            Some("error: permission denied")
+        );
+        assert_eq!(progress.update("@nix {not json"), None);
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+        assert_eq!(OptimiseProgress::default().percent(), 0);
+    }
+}
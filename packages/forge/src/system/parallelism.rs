@@ -0,0 +1,214 @@
+//! Build parallelism for forge's own rebuilds (`max-jobs` and `cores`)
+//!
+//! Unlike nix-conf.json, which changes nix.conf on every host, these apply per
+//! run: the rebuild and preview build get them as `--option`. The preference
+//! is kept per host in `~/.config/forge/parallelism.toml`, and
+//! `forge update --max-jobs/--cores` overrides it for one run:
+//!
+//! ```toml
+//! [kraken]
+//! max_jobs = 4
+//! cores = 8
+//! ```
+//!
+//! Unset fields leave nix.conf's value in charge.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::hardware::CpuTopology;
+use crate::constants::parallelism_settings_path;
+
+/// Memory a parallel build is assumed to need, for the suggestion
+const GIB_PER_JOB: u64 = 8;
+
+/// Builds running at once, and threads each may use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BuildParallelism {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_jobs: Option<u32>,
+    /// 0 lets each build use every thread
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cores: Option<u32>,
+}
+
+impl BuildParallelism {
+    pub fn is_set(&self) -> bool {
+        self.max_jobs.is_some() || self.cores.is_some()
+    }
+
+    /// These values, with the fields they leave unset taken from `fallback`
+    pub fn or(self, fallback: BuildParallelism) -> Self {
+        BuildParallelism {
+            max_jobs: self.max_jobs.or(fallback.max_jobs),
+            cores: self.cores.or(fallback.cores),
+        }
+    }
+
+    /// `--option` arguments for nix and nixos-rebuild
+    pub fn nix_args(&self) -> Vec<String> {
+        [("max-jobs", self.max_jobs), ("cores", self.cores)]
+            .into_iter()
+            .filter_map(|(name, value)| {
+                Some(["--option".to_string(), name.to_string(), value?.to_string()])
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Jobs and threads per job a build ends up with, unset fields falling
+    /// back to nix.conf's values (`auto` and 0 meaning every thread)
+    pub fn effective(&self, nix_conf: &BTreeMap<String, String>, threads: u32) -> (u32, u32) {
+        let jobs = self.max_jobs.unwrap_or_else(|| {
+            nix_conf
+                .get("max-jobs")
+                .and_then(|jobs| jobs.parse().ok())
+                .unwrap_or(threads)
+        });
+        let cores = self
+            .cores
+            .or_else(|| nix_conf.get("cores").and_then(|cores| cores.parse().ok()))
+            .unwrap_or(0);
+        (jobs, if cores == 0 { threads } else { cores })
+    }
+
+    /// "4 jobs × 8 threads: up to 32 compilers on 32 hardware threads"
+    pub fn describe(jobs: u32, cores: u32, topology: CpuTopology) -> String {
+        format!(
+            "{} job{} × {} thread{}: up to {} compilers on {} hardware threads",
+            jobs,
+            if jobs == 1 { "" } else { "s" },
+            cores,
+            if cores == 1 { "" } else { "s" },
+            jobs * cores,
+            topology.threads
+        )
+    }
+
+    /// Whether that many compilers would fight over the CPU rather than
+    /// keep it busy while other builds wait on downloads or linking
+    pub fn oversubscribed(jobs: u32, cores: u32, topology: CpuTopology) -> bool {
+        jobs * cores > topology.threads * 2
+    }
+}
+
+/// A starting point for a host: a job per four physical cores, no more jobs
+/// than its memory carries, and the threads shared out between them
+pub fn suggest(topology: CpuTopology, memory_gib: u64) -> BuildParallelism {
+    let mut jobs = (topology.cores / 4).max(1);
+    if memory_gib > 0 {
+        jobs = jobs.min((memory_gib / GIB_PER_JOB).max(1) as u32);
+    }
+    BuildParallelism {
+        max_jobs: Some(jobs),
+        cores: Some((topology.threads / jobs).max(1)),
+    }
+}
+
+/// The saved preference for `host` (unset when there is none)
+pub fn load_parallelism(host: &str) -> Result<BuildParallelism> {
+    Ok(load_all()?.remove(host).unwrap_or_default())
+}
+
+/// Save the preference for `host`; an unset one drops its entry
+pub fn save_parallelism(host: &str, parallelism: BuildParallelism) -> Result<()> {
+    let path = parallelism_settings_path();
+    let mut all = load_all()?;
+    if parallelism.is_set() {
+        all.insert(host.to_string(), parallelism);
+    } else {
+        all.remove(host);
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, toml::to_string(&all)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn load_all() -> Result<BTreeMap<String, BuildParallelism>> {
+    let path = parallelism_settings_path();
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Ok(BTreeMap::new());
+    };
+    toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RYZEN: CpuTopology = CpuTopology {
+        cores: 16,
+        threads: 32,
+    };
+
+    #[test]
+    fn test_suggest() {
+        assert_eq!(
+            suggest(RYZEN, 64),
+            BuildParallelism {
+                max_jobs: Some(4),
+                cores: Some(8)
+            }
+        );
+        // Memory caps the jobs before the cores do
+        assert_eq!(suggest(RYZEN, 16).max_jobs, Some(2));
+        let laptop = CpuTopology {
+            cores: 4,
+            threads: 8,
+        };
+        assert_eq!(suggest(laptop, 0).cores, Some(8));
+    }
+
+    #[test]
+    fn test_nix_args_and_fallback() {
+        let run = BuildParallelism {
+            max_jobs: Some(2),
+            cores: None,
+        };
+        let saved = BuildParallelism {
+            max_jobs: Some(4),
+            cores: Some(8),
+        };
+        assert_eq!(
+            run.or(saved).nix_args(),
+            vec!["--option", "max-jobs", "2", "--option", "cores", "8"]
+        );
+        assert!(BuildParallelism::default().nix_args().is_empty());
+    }
+
+    #[test]
+    fn test_effective() {
+        let nix_conf: BTreeMap<String, String> = [
+            ("max-jobs".to_string(), "auto".to_string()),
+            ("cores".to_string(), "0".to_string()),
+        ]
+        .into();
+        let unset = BuildParallelism::default();
+        assert_eq!(unset.effective(&nix_conf, 32), (32, 32));
+        assert!(BuildParallelism::oversubscribed(32, 32, RYZEN));
+
+        let tuned = BuildParallelism {
+            max_jobs: Some(4),
+            cores: None,
+        };
+        assert_eq!(tuned.effective(&BTreeMap::new(), 32), (4, 32));
+        assert_eq!(
+            BuildParallelism::describe(4, 8, RYZEN),
+            "4 jobs × 8 threads: up to 32 compilers on 32 hardware threads"
+        );
+    }
+
+    #[test]
+    fn test_parse_settings() {
+        let all: BTreeMap<String, BuildParallelism> =
+            toml::from_str("[kraken]\nmax_jobs = 4\n\n[G1a]\ncores = 2\n").unwrap();
+        assert_eq!(all["kraken"].max_jobs, Some(4));
+        assert_eq!(all["G1a"].max_jobs, None);
+        let unknown = toml::from_str::<BTreeMap<String, BuildParallelism>>("[kraken]\njobs = 4");
+        assert!(unknown.is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! Disk encryption passphrase strength
+//!
+//! A rough entropy estimate: the size of the character classes used, raised
+//! to the passphrase length. Repeated characters count for a quarter, so
+//! "aaaaaaaaaaaa" doesn't pass as long. It can't spot dictionary words, so
+//! the meter is a guard against short and lazy passphrases, not a guarantee.
+
+/// Bits below which a passphrase is rejected
+pub const MIN_PASSPHRASE_BITS: f64 = 40.0;
+
+/// Strength bucket shown next to the passphrase field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PassphraseStrength {
+    Weak,
+    Fair,
+    Good,
+    Strong,
+}
+
+impl PassphraseStrength {
+    pub fn from_bits(bits: f64) -> Self {
+        if bits < MIN_PASSPHRASE_BITS {
+            PassphraseStrength::Weak
+        } else if bits < 60.0 {
+            PassphraseStrength::Fair
+        } else if bits < 80.0 {
+            PassphraseStrength::Good
+        } else {
+            PassphraseStrength::Strong
+        }
+    }
+
+    /// Filled segments of a four-segment meter
+    pub fn level(&self) -> usize {
+        *self as usize + 1
+    }
+}
+
+impl std::fmt::Display for PassphraseStrength {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PassphraseStrength::Weak => write!(f, "Weak"),
+            PassphraseStrength::Fair => write!(f, "Fair"),
+            PassphraseStrength::Good => write!(f, "Good"),
+            PassphraseStrength::Strong => write!(f, "Strong"),
+        }
+    }
+}
+
+/// Estimated entropy of `passphrase` in bits
+pub fn estimate_bits(passphrase: &str) -> f64 {
+    let chars: Vec<char> = passphrase.chars().collect();
+    let mut pool = 0u32;
+    if chars.iter().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if chars.iter().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if chars.iter().any(|c| c.is_ascii_punctuation() || *c == ' ') {
+        pool += 33;
+    }
+    if chars.iter().any(|c| !c.is_ascii()) {
+        pool += 100;
+    }
+    if pool == 0 {
+        return 0.0;
+    }
+
+    let mut distinct = chars.clone();
+    distinct.sort_unstable();
+    distinct.dedup();
+    let repeats = chars.len() - distinct.len();
+    let effective_len = distinct.len() as f64 + repeats as f64 * 0.25;
+    effective_len * f64::from(pool).log2()
+}
+
+pub fn strength(passphrase: &str) -> PassphraseStrength {
+    PassphraseStrength::from_bits(estimate_bits(passphrase))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strength() {
+        assert_eq!(strength(""), PassphraseStrength::Weak);
+        assert_eq!(strength("hunter22"), PassphraseStrength::Weak);
+        assert_eq!(strength("aaaaaaaaaaaaaaaa"), PassphraseStrength::Weak);
+        assert_eq!(strength("Password1"), PassphraseStrength::Fair);
+        assert_eq!(strength("correct horse battery staple"), PassphraseStrength::Strong);
+        assert!(estimate_bits("abcdefgh1") > estimate_bits("abcdefgh"));
+    }
+
+    #[test]
+    fn test_level() {
+        assert_eq!(PassphraseStrength::Weak.level(), 1);
+        assert_eq!(PassphraseStrength::Strong.level(), 4);
+        assert_eq!(PassphraseStrength::from_bits(70.0).to_string(), "Good");
+    }
+}
@@ -0,0 +1,203 @@
+//! Ownership and permission audit for `forge fix-perms`
+//!
+//! Installs and `sudo` runs leave root-owned files in the user's trees (the
+//! config checkout, forge's data, the app-backup repo), after which git and
+//! forge fail with "permission denied". Key files end up readable by others.
+//! This module finds both and turns them into chown/chmod commands.
+
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Permission bits for group and others, which private files must not have
+const GROUP_OTHER_BITS: u32 = 0o077;
+
+/// The user everything should belong to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Owner {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl Owner {
+    /// Owner of `path` (the user's home directory)
+    pub fn of(path: &Path) -> std::io::Result<Self> {
+        let meta = std::fs::metadata(path)?;
+        Ok(Self {
+            uid: meta.uid(),
+            gid: meta.gid(),
+        })
+    }
+}
+
+/// A repair, as it is previewed and then run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fix {
+    /// Hand a tree back to the user; `foreign` entries belonged to someone else
+    Chown { path: PathBuf, owner: Owner, foreign: usize },
+    /// Take group and other access away from a private file or directory
+    Chmod { path: PathBuf, mode: u32, current: u32 },
+}
+
+impl Fix {
+    /// Program and arguments
+    pub fn command(&self) -> Vec<String> {
+        match self {
+            Fix::Chown { path, owner, .. } => vec![
+                "chown".to_string(),
+                "-R".to_string(),
+                format!("{}:{}", owner.uid, owner.gid),
+                path.display().to_string(),
+            ],
+            Fix::Chmod { path, mode, .. } => vec![
+                "chmod".to_string(),
+                format!("{:o}", mode),
+                path.display().to_string(),
+            ],
+        }
+    }
+
+    /// Why it is needed, for the preview
+    pub fn reason(&self) -> String {
+        match self {
+            Fix::Chown { foreign, .. } => {
+                format!("{} entr{} owned by another user", foreign, if *foreign == 1 { "y" } else { "ies" })
+            }
+            Fix::Chmod { current, .. } => format!("mode {:o} lets others read it", current),
+        }
+    }
+
+    /// Changing owners needs root; permissions of the user's own files don't
+    pub fn needs_root(&self) -> bool {
+        matches!(self, Fix::Chown { .. })
+    }
+}
+
+/// What to check: trees the user should own and files only they may read
+#[derive(Debug, Clone, Default)]
+pub struct AuditTargets {
+    pub trees: Vec<PathBuf>,
+    pub private: Vec<PathBuf>,
+}
+
+/// Fixes for `targets`, chowns first so the chmods can run as the user
+pub fn plan(targets: &AuditTargets, owner: Owner) -> Vec<Fix> {
+    let chowns = targets.trees.iter().filter_map(|tree| audit_owner(tree, owner));
+    let chmods = targets.private.iter().filter_map(|path| audit_mode(path));
+    chowns.chain(chmods).collect()
+}
+
+/// A chown of `root` if anything in it (symlinks not followed) isn't the user's
+fn audit_owner(root: &Path, owner: Owner) -> Option<Fix> {
+    let root_meta = std::fs::metadata(root).ok()?;
+    let mut foreign = usize::from(root_meta.uid() != owner.uid);
+    if root_meta.is_dir() {
+        foreign += count_foreign(root, owner.uid);
+    }
+    (foreign > 0).then(|| Fix::Chown {
+        path: root.to_path_buf(),
+        owner,
+        foreign,
+    })
+}
+
+fn count_foreign(dir: &Path, uid: u32) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| {
+            let Ok(meta) = entry.path().symlink_metadata() else {
+                return 0;
+            };
+            let own = usize::from(meta.uid() != uid);
+            if meta.is_dir() {
+                own + count_foreign(&entry.path(), uid)
+            } else {
+                own
+            }
+        })
+        .sum()
+}
+
+/// A chmod to 600 (700 for directories) if group or others have any access
+fn audit_mode(path: &Path) -> Option<Fix> {
+    let meta = path.symlink_metadata().ok()?;
+    if meta.file_type().is_symlink() {
+        return None;
+    }
+    let current = meta.mode() & 0o7777;
+    if current & GROUP_OTHER_BITS == 0 {
+        return None;
+    }
+    let mode = if meta.is_dir() { 0o700 } else { 0o600 };
+    Some(Fix::Chmod {
+        path: path.to_path_buf(),
+        mode,
+        current,
+    })
+}
+
+/// Private files in `~/.ssh`: the directory and everything but public keys
+/// and known_hosts
+pub fn ssh_private_files(ssh_dir: &Path) -> Vec<PathBuf> {
+    if !ssh_dir.is_dir() {
+        return Vec::new();
+    }
+    let mut files = vec![ssh_dir.to_path_buf()];
+    if let Ok(entries) = std::fs::read_dir(ssh_dir) {
+        let mut keys: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| {
+                let name = p.file_name().unwrap_or_default().to_string_lossy();
+                p.is_file() && !name.ends_with(".pub") && !name.starts_with("known_hosts")
+            })
+            .collect();
+        keys.sort();
+        files.extend(keys);
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_plan_fixes() {
+        let dir = std::env::temp_dir().join(format!("forge-perms-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let ssh = dir.join(".ssh");
+        std::fs::create_dir_all(ssh.join("sub")).unwrap();
+        std::fs::write(ssh.join("id_ed25519"), "key").unwrap();
+        std::fs::write(ssh.join("id_ed25519.pub"), "pub").unwrap();
+        std::fs::write(ssh.join("known_hosts"), "hosts").unwrap();
+        std::fs::set_permissions(&ssh, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::set_permissions(ssh.join("id_ed25519"), std::fs::Permissions::from_mode(0o644))
+            .unwrap();
+
+        let private = ssh_private_files(&ssh);
+        assert_eq!(private, vec![ssh.clone(), ssh.join("id_ed25519")]);
+
+        let mine = Owner::of(&dir).unwrap();
+        let targets = AuditTargets {
+            trees: vec![dir.clone(), dir.join("missing")],
+            private,
+        };
+        let fixes = plan(&targets, mine);
+        assert_eq!(fixes.len(), 2);
+        assert_eq!(fixes[0].command(), ["chmod", "700", &ssh.display().to_string()]);
+        assert_eq!(fixes[1].reason(), "mode 644 lets others read it");
+        assert!(!fixes[1].needs_root());
+
+        // Someone else's view: every entry in the tree is foreign
+        let other = Owner { uid: mine.uid + 1, gid: mine.gid };
+        let fixes = plan(&AuditTargets { trees: vec![ssh.clone()], private: Vec::new() }, other);
+        assert!(matches!(&fixes[..], [Fix::Chown { foreign: 5, .. }]));
+        assert!(fixes[0].command().starts_with(&["chown".to_string(), "-R".to_string()]));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,172 @@
+//! Repo-defined pipelines for `forge run`
+//!
+//! `pipelines.nix` at the root of the nixos-config repo evaluates to an
+//! attribute set of pipelines, so site-specific workflows live next to the
+//! config instead of in forge:
+//!
+//! ```nix
+//! {
+//!   firewall = {
+//!     description = "Update firewall rules";
+//!     steps = [
+//!       { name = "Fetch blocklist"; run = "./scripts/fetch-blocklist.sh"; }
+//!       {
+//!         name = "Rebuild";
+//!         run = "sudo nixos-rebuild switch --flake .#$(hostname)";
+//!         hosts = [ "kraken" ];        # only on these machines
+//!         timeout = 1800;              # seconds (default 300)
+//!       }
+//!       {
+//!         name = "Reload nftables";
+//!         run = "sudo systemctl reload nftables";
+//!         onlyIf = "systemctl is-active --quiet nftables";  # skipped unless exit 0
+//!         continueOnError = true;
+//!       }
+//!     ];
+//!   };
+//! }
+//! ```
+//!
+//! Commands run with `sh -c` from the repo root.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Pipeline definition filename (repo root)
+pub const PIPELINES_FILE: &str = "pipelines.nix";
+
+/// A single step of a pipeline
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineStep {
+    /// Shown in the progress list; unique within the pipeline
+    pub name: String,
+    /// Shell command
+    pub run: String,
+    /// Only run on these hostnames (empty = everywhere)
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    /// Shell command that must exit 0 for the step to run
+    pub only_if: Option<String>,
+    /// Keep going if this step fails (the pipeline still reports failure)
+    #[serde(default)]
+    pub continue_on_error: bool,
+    /// Timeout in seconds (None = forge's default command timeout)
+    pub timeout: Option<u64>,
+}
+
+impl PipelineStep {
+    /// Whether the step applies to this host
+    pub fn runs_on(&self, hostname: &str) -> bool {
+        self.hosts.is_empty() || self.hosts.iter().any(|h| h == hostname)
+    }
+}
+
+/// A named list of steps
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Pipeline {
+    /// Attribute name in pipelines.nix
+    #[serde(skip)]
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub steps: Vec<PipelineStep>,
+}
+
+/// Evaluate pipelines.nix from the config repo, sorted by name
+pub fn load_pipelines(config_dir: &Path) -> Result<Vec<Pipeline>> {
+    let path = config_dir.join(PIPELINES_FILE);
+    if !path.exists() {
+        bail!("No {} in {}", PIPELINES_FILE, config_dir.display());
+    }
+
+    let output = std::process::Command::new("nix")
+        .args(["eval", "--json", "--file"])
+        .arg(&path)
+        .output()
+        .context("Failed to run nix eval")?;
+    if !output.status.success() {
+        bail!(
+            "Failed to evaluate {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    parse_pipelines(&String::from_utf8_lossy(&output.stdout))
+        .with_context(|| format!("Invalid {}", path.display()))
+}
+
+/// Parse and validate the JSON form of pipelines.nix
+pub fn parse_pipelines(json: &str) -> Result<Vec<Pipeline>> {
+    let defs: BTreeMap<String, Pipeline> = serde_json::from_str(json)?;
+
+    let mut pipelines = Vec::with_capacity(defs.len());
+    for (name, mut pipeline) in defs {
+        if pipeline.steps.is_empty() {
+            bail!("Pipeline '{}' has no steps", name);
+        }
+        // Steps are tracked by name in the progress list
+        let mut seen = HashSet::new();
+        for step in &pipeline.steps {
+            if !seen.insert(step.name.as_str()) {
+                bail!("Pipeline '{}' has two steps named '{}'", name, step.name);
+            }
+        }
+        pipeline.name = name;
+        pipelines.push(pipeline);
+    }
+    Ok(pipelines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIPELINES: &str = r#"{
+        "firewall": {
+            "description": "Update firewall rules",
+            "steps": [
+                { "name": "Fetch", "run": "./fetch.sh" },
+                { "name": "Rebuild", "run": "nixos-rebuild switch", "hosts": ["kraken"], "timeout": 1800 },
+                { "name": "Reload", "run": "systemctl reload nftables", "onlyIf": "true", "continueOnError": true }
+            ]
+        },
+        "backup": { "steps": [ { "name": "Push", "run": "app-backup --push" } ] }
+    }"#;
+
+    #[test]
+    fn test_parse_pipelines() {
+        let pipelines = parse_pipelines(PIPELINES).unwrap();
+        let names: Vec<&str> = pipelines.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["backup", "firewall"]);
+
+        let firewall = &pipelines[1];
+        assert_eq!(firewall.description.as_deref(), Some("Update firewall rules"));
+        assert_eq!(firewall.steps[1].timeout, Some(1800));
+        assert_eq!(firewall.steps[2].only_if.as_deref(), Some("true"));
+        assert!(firewall.steps[2].continue_on_error);
+        assert!(!firewall.steps[0].continue_on_error);
+    }
+
+    #[test]
+    fn test_runs_on() {
+        let pipelines = parse_pipelines(PIPELINES).unwrap();
+        let firewall = &pipelines[1];
+        assert!(firewall.steps[0].runs_on("G1a"));
+        assert!(firewall.steps[1].runs_on("kraken"));
+        assert!(!firewall.steps[1].runs_on("G1a"));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_pipelines() {
+        assert!(parse_pipelines(r#"{ "empty": { "steps": [] } }"#).is_err());
+        assert!(parse_pipelines(
+            r#"{ "dup": { "steps": [ { "name": "a", "run": "x" }, { "name": "a", "run": "y" } ] } }"#
+        )
+        .is_err());
+        assert!(parse_pipelines(r#"{ "norun": { "steps": [ { "name": "a" } ] } }"#).is_err());
+    }
+}
@@ -0,0 +1,302 @@
+//! Install media preflight checks
+//!
+//! Run from the Live ISO before host selection so problems that would only
+//! surface halfway through an install (too little RAM, wrong boot mode, no
+//! network, a bad clock breaking TLS) are reported up front.
+
+use chrono::{DateTime, Utc};
+use std::process::Command;
+
+use super::hardware::{detect_boot_mode, BootMode};
+use crate::constants::{MIN_INSTALL_RAM_MB, RECOMMENDED_INSTALL_RAM_MB};
+
+/// EFI variable holding the Secure Boot state
+const SECURE_BOOT_EFIVAR: &str =
+    "/sys/firmware/efi/efivars/SecureBoot-8be4df61-93ca-11d2-aa0d-e398c7f81f9b";
+
+/// Clock skew (seconds) above which we warn
+const CLOCK_WARN_SKEW_SECS: i64 = 5 * 60;
+
+/// Clock skew (seconds) above which TLS certificate validation is likely to fail
+const CLOCK_FAIL_SKEW_SECS: i64 = 24 * 60 * 60;
+
+/// Result of a single preflight check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    /// Install can continue, but the user should know
+    Warn,
+    /// Install is blocked until this is fixed
+    Fail,
+}
+
+/// A single preflight check result
+#[derive(Debug, Clone)]
+pub struct PreflightCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl PreflightCheck {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Name of the connectivity check, which the WiFi setup can fix
+const NETWORK_CHECK: &str = "Network";
+
+/// Whether any check blocks the install
+pub fn has_blocking_failures(checks: &[PreflightCheck]) -> bool {
+    checks.iter().any(|c| c.status == CheckStatus::Fail)
+}
+
+/// Whether the network check failed, so joining a wireless network may help
+pub fn network_failed(checks: &[PreflightCheck]) -> bool {
+    checks
+        .iter()
+        .any(|c| c.name == NETWORK_CHECK && c.status == CheckStatus::Fail)
+}
+
+/// Run all preflight checks (blocking; call from a blocking task)
+pub fn run_preflight() -> Vec<PreflightCheck> {
+    vec![
+        check_ram(),
+        check_boot_mode(),
+        check_secure_boot(),
+        check_network(),
+        check_clock(),
+    ]
+}
+
+fn check_ram() -> PreflightCheck {
+    let total_mb = std::fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|c| parse_mem_total_mb(&c));
+
+    match total_mb {
+        Some(mb) => evaluate_ram(mb),
+        None => PreflightCheck::new("Memory", CheckStatus::Warn, "Could not read /proc/meminfo"),
+    }
+}
+
+/// Parse MemTotal from /proc/meminfo, in MiB
+fn parse_mem_total_mb(meminfo: &str) -> Option<u64> {
+    meminfo
+        .lines()
+        .find(|l| l.starts_with("MemTotal:"))?
+        .split_whitespace()
+        .nth(1)?
+        .parse::<u64>()
+        .ok()
+        .map(|kb| kb / 1024)
+}
+
+fn evaluate_ram(total_mb: u64) -> PreflightCheck {
+    let size = format!("{:.1} GB", total_mb as f64 / 1024.0);
+    if total_mb < MIN_INSTALL_RAM_MB {
+        PreflightCheck::new(
+            "Memory",
+            CheckStatus::Fail,
+            format!("{} - nixos-install needs at least {} GB", size, MIN_INSTALL_RAM_MB / 1024),
+        )
+    } else if total_mb < RECOMMENDED_INSTALL_RAM_MB {
+        PreflightCheck::new(
+            "Memory",
+            CheckStatus::Warn,
+            format!(
+                "{} - builds may run out of memory (recommended {} GB)",
+                size,
+                RECOMMENDED_INSTALL_RAM_MB / 1024
+            ),
+        )
+    } else {
+        PreflightCheck::new("Memory", CheckStatus::Pass, size)
+    }
+}
+
+fn check_boot_mode() -> PreflightCheck {
+    evaluate_boot_mode(detect_boot_mode())
+}
+
+fn evaluate_boot_mode(mode: BootMode) -> PreflightCheck {
+    match mode {
+        BootMode::Uefi => PreflightCheck::new("Boot mode", CheckStatus::Pass, "UEFI"),
+        BootMode::Bios => PreflightCheck::new(
+            "Boot mode",
+            CheckStatus::Warn,
+            "Legacy BIOS - new hosts will use GRUB with an MBR partition table",
+        ),
+    }
+}
+
+fn check_secure_boot() -> PreflightCheck {
+    match std::fs::read(SECURE_BOOT_EFIVAR) {
+        Ok(data) => evaluate_secure_boot(&data),
+        Err(_) => PreflightCheck::new("Secure Boot", CheckStatus::Pass, "Not available"),
+    }
+}
+
+/// efivarfs data is 4 attribute bytes followed by the value
+fn evaluate_secure_boot(data: &[u8]) -> PreflightCheck {
+    match data.get(4) {
+        Some(1) => PreflightCheck::new(
+            "Secure Boot",
+            CheckStatus::Warn,
+            "Enabled - disable it, or reset it to Setup Mode to enroll keys at install",
+        ),
+        Some(_) => PreflightCheck::new("Secure Boot", CheckStatus::Pass, "Disabled"),
+        None => PreflightCheck::new("Secure Boot", CheckStatus::Warn, "Unknown state"),
+    }
+}
+
+fn check_network() -> PreflightCheck {
+    if let Some(source) = super::offline::source() {
+        return PreflightCheck::new(
+            NETWORK_CHECK,
+            CheckStatus::Pass,
+            format!("Not needed (offline from {})", source.repo.display()),
+        );
+    }
+    match super::network::check_connectivity() {
+        Ok(true) => PreflightCheck::new(NETWORK_CHECK, CheckStatus::Pass, "github.com reachable"),
+        _ => PreflightCheck::new(
+            NETWORK_CHECK,
+            CheckStatus::Fail,
+            "github.com unreachable - join WiFi with w, or connect with: nmtui",
+        ),
+    }
+}
+
+fn check_clock() -> PreflightCheck {
+    // Plain HTTP on purpose: a wrong clock is exactly what breaks HTTPS
+    let output = Command::new("curl")
+        .args(["-sI", "--max-time", "5", "http://cache.nixos.org/nix-cache-info"])
+        .output();
+
+    let remote = output
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| parse_http_date(&String::from_utf8_lossy(&o.stdout)));
+
+    match remote {
+        Some(remote) => evaluate_clock_skew((Utc::now() - remote).num_seconds()),
+        None => PreflightCheck::new(
+            "Date/time",
+            CheckStatus::Warn,
+            format!("Could not verify (local: {})", Utc::now().format("%Y-%m-%d %H:%M UTC")),
+        ),
+    }
+}
+
+/// Extract the Date header from HTTP response headers
+fn parse_http_date(headers: &str) -> Option<DateTime<Utc>> {
+    let value = headers
+        .lines()
+        .find_map(|l| l.split_once(':').filter(|(k, _)| k.eq_ignore_ascii_case("date")))?
+        .1
+        .trim();
+    DateTime::parse_from_rfc2822(value).ok().map(|d| d.with_timezone(&Utc))
+}
+
+fn evaluate_clock_skew(skew_secs: i64) -> PreflightCheck {
+    let abs = skew_secs.abs();
+    let direction = if skew_secs > 0 { "ahead" } else { "behind" };
+    if abs >= CLOCK_FAIL_SKEW_SECS {
+        PreflightCheck::new(
+            "Date/time",
+            CheckStatus::Fail,
+            format!(
+                "Clock is {} days {} - TLS will fail, fix with: sudo timedatectl set-ntp true",
+                abs / 86400,
+                direction
+            ),
+        )
+    } else if abs >= CLOCK_WARN_SKEW_SECS {
+        PreflightCheck::new(
+            "Date/time",
+            CheckStatus::Warn,
+            format!("Clock is {} minutes {}", abs / 60, direction),
+        )
+    } else {
+        PreflightCheck::new("Date/time", CheckStatus::Pass, "In sync")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mem_total() {
+        let meminfo = "MemTotal:       16384000 kB\nMemFree:         1000 kB\n";
+        assert_eq!(parse_mem_total_mb(meminfo), Some(16000));
+        assert_eq!(parse_mem_total_mb("MemFree: 1 kB"), None);
+    }
+
+    #[test]
+    fn test_evaluate_ram() {
+        assert_eq!(evaluate_ram(1024).status, CheckStatus::Fail);
+        assert_eq!(evaluate_ram(MIN_INSTALL_RAM_MB).status, CheckStatus::Warn);
+        assert_eq!(evaluate_ram(RECOMMENDED_INSTALL_RAM_MB).status, CheckStatus::Pass);
+    }
+
+    #[test]
+    fn test_evaluate_boot_mode() {
+        assert_eq!(evaluate_boot_mode(BootMode::Uefi).status, CheckStatus::Pass);
+        // Legacy BIOS is supported, but worth pointing out
+        assert_eq!(evaluate_boot_mode(BootMode::Bios).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_evaluate_secure_boot() {
+        assert_eq!(evaluate_secure_boot(&[6, 0, 0, 0, 1]).status, CheckStatus::Warn);
+        assert_eq!(evaluate_secure_boot(&[6, 0, 0, 0, 0]).status, CheckStatus::Pass);
+        assert_eq!(evaluate_secure_boot(&[6, 0]).status, CheckStatus::Warn);
+    }
+
+    #[test]
+    fn test_parse_http_date() {
+        let headers = "HTTP/1.1 200 OK\r\ndate: Tue, 15 Nov 1994 08:12:31 GMT\r\n";
+        let date = parse_http_date(headers).unwrap();
+        assert_eq!(date.timestamp(), 784887151);
+        assert!(parse_http_date("HTTP/1.1 200 OK\r\n").is_none());
+    }
+
+    #[test]
+    fn test_evaluate_clock_skew() {
+        assert_eq!(evaluate_clock_skew(30).status, CheckStatus::Pass);
+        assert_eq!(evaluate_clock_skew(-600).status, CheckStatus::Warn);
+        assert_eq!(evaluate_clock_skew(3 * 86400).status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn test_has_blocking_failures() {
+        let checks = vec![
+            PreflightCheck::new("A", CheckStatus::Pass, ""),
+            PreflightCheck::new("B", CheckStatus::Warn, ""),
+        ];
+        assert!(!has_blocking_failures(&checks));
+
+        let mut failing = checks.clone();
+        failing.push(PreflightCheck::new("C", CheckStatus::Fail, ""));
+        assert!(has_blocking_failures(&failing));
+    }
+
+    #[test]
+    fn test_network_failed() {
+        let mut checks = vec![
+            PreflightCheck::new("RAM", CheckStatus::Fail, ""),
+            PreflightCheck::new(NETWORK_CHECK, CheckStatus::Pass, ""),
+        ];
+        // Only a failed network check offers the WiFi setup
+        assert!(!network_failed(&checks));
+        checks[1].status = CheckStatus::Fail;
+        assert!(network_failed(&checks));
+    }
+}
@@ -0,0 +1,180 @@
+//! Provenance of built systems: store path, narHash and signatures
+//!
+//! Every system forge builds is recorded in the run manifest. With a secret
+//! key at `/etc/nix/forge-signing.sec` the closure is signed first, so hosts
+//! that trust its public key (`nix.settings.trusted-public-keys`) can check a
+//! deploy before switching to it:
+//!
+//! ```sh
+//! sudo sh -c 'nix key generate-secret --key-name forge-kraken-1 > /etc/nix/forge-signing.sec'
+//! sudo chmod 600 /etc/nix/forge-signing.sec
+//! sudo nix key convert-secret-to-public < /etc/nix/forge-signing.sec
+//! ```
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::path::Path;
+use tokio::process::Command;
+
+/// Secret key closures are signed with, when it exists (readable by root only)
+pub const SIGNING_KEY_FILE: &str = "/etc/nix/forge-signing.sec";
+
+/// What a built system is and who vouches for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub path: String,
+    pub nar_hash: String,
+    /// Names of the keys that signed it (`cache.nixos.org-1`, ...)
+    pub signers: Vec<String>,
+}
+
+impl Provenance {
+    /// "signed by forge-kraken-1" or "unsigned"
+    pub fn signing_status(&self) -> String {
+        if self.signers.is_empty() {
+            "unsigned".to_string()
+        } else {
+            format!("signed by {}", self.signers.join(", "))
+        }
+    }
+}
+
+/// Parse `nix path-info --json` for `path`; newer nix prints an object keyed
+/// by path, older an array of objects with a `path` field
+pub fn parse_path_info(json: &str, path: &str) -> Result<Provenance> {
+    let value: Value = serde_json::from_str(json).context("Invalid nix path-info output")?;
+    let info = match &value {
+        Value::Object(map) => map.get(path),
+        Value::Array(items) => items.iter().find(|i| i["path"] == path),
+        _ => None,
+    }
+    .with_context(|| format!("nix path-info has no entry for {}", path))?;
+
+    let nar_hash = info["narHash"]
+        .as_str()
+        .context("nix path-info gave no narHash")?
+        .to_string();
+    let signers = info["signatures"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|sig| sig.as_str()?.split_once(':').map(|(name, _)| name.to_string()))
+        .collect();
+    Ok(Provenance {
+        path: path.to_string(),
+        nar_hash,
+        signers,
+    })
+}
+
+/// Look up a store path, in `store` (e.g. `ssh-ng://host`) or the local store
+pub async fn inspect(path: &str, store: Option<&str>) -> Result<Provenance> {
+    let mut args = vec!["path-info", "--json", path];
+    if let Some(store) = store {
+        args.extend(["--store", store]);
+    }
+    let stdout = nix(&args, false).await?;
+    parse_path_info(&stdout, path)
+}
+
+/// Sign the closure of a locally built path; false without a signing key
+pub async fn sign(path: &str) -> Result<bool> {
+    if !Path::new(SIGNING_KEY_FILE).exists() {
+        return Ok(false);
+    }
+    nix(
+        &["store", "sign", "--key-file", SIGNING_KEY_FILE, "--recursive", path],
+        true,
+    )
+    .await?;
+    Ok(true)
+}
+
+/// Check on `target` that the path and its whole closure carry a signature
+/// the host trusts (or were built there), before anything switches to it
+pub async fn verify_on(target: &str, path: &str) -> Result<()> {
+    let output = Command::new("ssh")
+        .args(["-o", "BatchMode=yes", target])
+        .args(verify_args(path))
+        .output()
+        .await
+        .context("Failed to run ssh")?;
+    if !output.status.success() {
+        bail!(
+            "{} does not trust {}: {}",
+            target,
+            path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+/// `nix store verify` for the closure `sign()` signed, not just its top path
+fn verify_args(path: &str) -> [&str; 8] {
+    [
+        "nix",
+        "store",
+        "verify",
+        "--recursive",
+        "--no-contents",
+        "--sigs-needed",
+        "1",
+        path,
+    ]
+}
+
+async fn nix(args: &[&str], sudo: bool) -> Result<String> {
+    let mut cmd = if sudo {
+        let mut cmd = Command::new("sudo");
+        cmd.arg("nix");
+        cmd
+    } else {
+        Command::new("nix")
+    };
+    let output = cmd
+        .args(args)
+        .output()
+        .await
+        .context("Failed to run nix")?;
+    if !output.status.success() {
+        bail!(
+            "nix {} failed: {}",
+            args.iter().take(2).copied().collect::<Vec<_>>().join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATH: &str = "/nix/store/abc-nixos-system-kraken-26.05";
+
+    #[test]
+    fn test_parse_path_info() {
+        let object = format!(
+            r#"{{"{}": {{"narHash": "sha256-AAAA", "signatures": ["forge-kraken-1:c2ln"]}}}}"#,
+            PATH
+        );
+        let provenance = parse_path_info(&object, PATH).unwrap();
+        assert_eq!(provenance.nar_hash, "sha256-AAAA");
+        assert_eq!(provenance.signing_status(), "signed by forge-kraken-1");
+
+        let array = format!(r#"[{{"path": "{}", "narHash": "sha256:0abc"}}]"#, PATH);
+        let provenance = parse_path_info(&array, PATH).unwrap();
+        assert_eq!(provenance.signing_status(), "unsigned");
+
+        assert!(parse_path_info("{}", PATH).is_err());
+    }
+
+    #[test]
+    fn test_verify_args() {
+        assert_eq!(
+            verify_args(PATH).join(" "),
+            format!("nix store verify --recursive --no-contents --sigs-needed 1 {}", PATH)
+        );
+    }
+}
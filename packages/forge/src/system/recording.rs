@@ -0,0 +1,170 @@
+//! Asciinema recordings of TUI sessions (`forge --record`)
+//!
+//! Everything forge draws is also written to an asciicast v2 file in
+//! `~/.local/share/forge/recordings`, so a failed install can be replayed
+//! later with `asciinema play`. Programs run with the TUI suspended (the
+//! install's rescue shell) are not part of the recording.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::constants::recordings_dir;
+
+/// Path for a recording started at `stamp` (`%Y%m%d-%H%M%S`)
+pub fn recording_path(stamp: &str) -> PathBuf {
+    recordings_dir().join(format!("forge-{}.cast", stamp))
+}
+
+/// Writes asciicast v2: a JSON header line, then one `[time, code, data]` line per event
+pub struct CastRecorder<W: Write> {
+    out: W,
+    started: Instant,
+    /// Output since the last flush; a frame is recorded as one event
+    pending: Vec<u8>,
+}
+
+impl CastRecorder<BufWriter<File>> {
+    /// Create the cast file at `path` for a terminal of `width` x `height`
+    pub fn create(path: &Path, width: u16, height: u16) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = BufWriter::new(File::create(path)?);
+        Self::new(file, width, height, chrono::Utc::now().timestamp())
+    }
+}
+
+impl<W: Write> CastRecorder<W> {
+    pub fn new(mut out: W, width: u16, height: u16, timestamp: i64) -> io::Result<Self> {
+        let header = serde_json::json!({
+            "version": 2,
+            "width": width,
+            "height": height,
+            "timestamp": timestamp,
+            "title": "forge",
+            "env": { "TERM": std::env::var("TERM").unwrap_or_default() },
+        });
+        writeln!(out, "{}", header)?;
+        Ok(Self {
+            out,
+            started: Instant::now(),
+            pending: Vec::new(),
+        })
+    }
+
+    /// Queue terminal output; it is recorded on the next flush
+    pub fn output(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+    }
+
+    /// Record the queued output, keeping a multi-byte character cut off at the end
+    pub fn flush(&mut self) -> io::Result<()> {
+        let complete = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+        if complete > 0 {
+            let rest = self.pending.split_off(complete);
+            let data = String::from_utf8_lossy(&self.pending).into_owned();
+            self.pending = rest;
+            self.event("o", &data)?;
+        }
+        self.out.flush()
+    }
+
+    /// Record a terminal resize
+    pub fn resize(&mut self, width: u16, height: u16) -> io::Result<()> {
+        self.event("r", &format!("{}x{}", width, height))?;
+        self.out.flush()
+    }
+
+    fn event(&mut self, code: &str, data: &str) -> io::Result<()> {
+        let time = (self.started.elapsed().as_secs_f64() * 1000.0).round() / 1000.0;
+        let line = serde_json::to_string(&(time, code, data)).map_err(io::Error::other)?;
+        writeln!(self.out, "{}", line)
+    }
+}
+
+/// The TUI's terminal output: stdout, copied into a recording when one is running
+pub struct TuiOutput {
+    stdout: io::Stdout,
+    recorder: Option<CastRecorder<BufWriter<File>>>,
+    /// Terminal size last recorded, to notice resizes
+    size: (u16, u16),
+}
+
+impl TuiOutput {
+    pub fn new(recorder: Option<CastRecorder<BufWriter<File>>>) -> Self {
+        Self {
+            stdout: io::stdout(),
+            recorder,
+            size: crossterm::terminal::size().unwrap_or_default(),
+        }
+    }
+
+    fn record(&mut self) -> io::Result<()> {
+        let Some(recorder) = &mut self.recorder else {
+            return Ok(());
+        };
+        // The frame after a resize is drawn at the new size
+        let size = crossterm::terminal::size().unwrap_or(self.size);
+        if size != self.size {
+            self.size = size;
+            recorder.resize(size.0, size.1)?;
+        }
+        recorder.flush()
+    }
+}
+
+impl Write for TuiOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.stdout.write(buf)?;
+        if let Some(recorder) = &mut self.recorder {
+            recorder.output(&buf[..written]);
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdout.flush()?;
+        // A full disk shouldn't take the TUI down with it
+        if let Err(e) = self.record() {
+            tracing::warn!("Stopped recording: {}", e);
+            self.recorder = None;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cast_recorder() {
+        let mut cast = Vec::new();
+        {
+            let mut recorder = CastRecorder::new(&mut cast, 80, 24, 1_700_000_000).unwrap();
+            recorder.output(b"\x1b[1;1Hhello \xe2\x94");
+            recorder.flush().unwrap();
+            recorder.output(b"\x80\"");
+            recorder.flush().unwrap();
+            recorder.flush().unwrap();
+            recorder.resize(100, 30).unwrap();
+        }
+        let cast = String::from_utf8(cast).unwrap();
+        let lines: Vec<serde_json::Value> =
+            cast.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 80);
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[1][2], "\x1b[1;1Hhello ");
+        assert_eq!(lines[2][2], "─\"");
+        assert_eq!(lines[3][1], "r");
+        assert_eq!(lines[3][2], "100x30");
+    }
+}
@@ -0,0 +1,198 @@
+//! Flake registry pins shared through the config repo
+//!
+//! `registry.json` at the repo root maps registry names to flake references.
+//! modules/registry.nix turns it into `nix.registry`, so after a rebuild every
+//! host resolves `nix run <name>#...` to the same flake:
+//!
+//! ```json
+//! {
+//!   "stable": {
+//!     "url": "github:NixOS/nixpkgs/nixos-24.11",
+//!     "to": { "type": "github", "owner": "NixOS", "repo": "nixpkgs", "rev": "...", ... },
+//!     "pinned": true
+//!   }
+//! }
+//! ```
+//!
+//! `to` is what `nix flake metadata` reports for `url`: the `original` attrs,
+//! or the `locked` attrs (with `rev`) for a pinned entry.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Registry pins filename (repo root)
+pub const REGISTRY_FILE: &str = "registry.json";
+
+/// One registry entry as stored in registry.json
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    /// Flake reference as entered; pins are refreshed from it
+    pub url: String,
+    /// Attribute form for `nix.registry.<name>.to`
+    pub to: Map<String, Value>,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl RegistryEntry {
+    /// Short locked revision, for pinned entries
+    pub fn pinned_rev(&self) -> Option<String> {
+        if !self.pinned {
+            return None;
+        }
+        self.to
+            .get("rev")
+            .and_then(Value::as_str)
+            .map(|rev| rev[..7.min(rev.len())].to_string())
+    }
+}
+
+/// registry.json contents, keyed by registry name
+pub type Registry = BTreeMap<String, RegistryEntry>;
+
+/// One line of the registry screen
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistryRow {
+    pub name: String,
+    pub url: String,
+    pub pinned_rev: Option<String>,
+    /// Present in the system registry, i.e. the current generation has it
+    pub applied: bool,
+}
+
+/// Read registry.json (an empty registry if the file doesn't exist yet)
+pub fn load_registry(config_dir: &Path) -> Result<Registry> {
+    let path = config_dir.join(REGISTRY_FILE);
+    if !path.exists() {
+        return Ok(Registry::new());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+}
+
+/// Write registry.json, sorted and pretty-printed for readable diffs
+pub fn save_registry(config_dir: &Path, registry: &Registry) -> Result<()> {
+    let path = config_dir.join(REGISTRY_FILE);
+    let json = serde_json::to_string_pretty(registry)? + "\n";
+    std::fs::write(&path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Registry names must be valid flake ids: a letter, then letters, digits, - or _
+pub fn validate_registry_name(name: &str) -> Option<String> {
+    if name.is_empty() {
+        return Some("Name is required".to_string());
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Some("Name must start with a letter".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Some("Name may only contain letters, digits, - and _".to_string());
+    }
+    None
+}
+
+/// Registry target from `nix flake metadata --json` output
+pub fn flake_ref_from_metadata(json: &str, pinned: bool) -> Result<Map<String, Value>> {
+    let metadata: Value = serde_json::from_str(json).context("Unexpected nix flake metadata output")?;
+    let key = if pinned { "locked" } else { "original" };
+    match metadata.get(key) {
+        Some(Value::Object(attrs)) if attrs.contains_key("type") => Ok(attrs.clone()),
+        _ => bail!("nix flake metadata returned no {} reference", key),
+    }
+}
+
+/// Names in the system scope of `nix registry list` output
+pub fn system_registry_names(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("system"), Some(from)) => from.strip_prefix("flake:").map(str::to_string),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METADATA: &str = r#"{
+        "description": "A collection of packages for the Nix package manager",
+        "original": { "owner": "NixOS", "ref": "nixos-24.11", "repo": "nixpkgs", "type": "github" },
+        "locked": {
+            "lastModified": 1735000000,
+            "narHash": "sha256-AAAA",
+            "owner": "NixOS",
+            "repo": "nixpkgs",
+            "rev": "0123456789abcdef0123456789abcdef01234567",
+            "type": "github"
+        }
+    }"#;
+
+    #[test]
+    fn test_flake_ref_from_metadata() {
+        let original = flake_ref_from_metadata(METADATA, false).unwrap();
+        assert_eq!(original.get("ref").and_then(Value::as_str), Some("nixos-24.11"));
+        assert!(!original.contains_key("rev"));
+
+        let locked = flake_ref_from_metadata(METADATA, true).unwrap();
+        let entry = RegistryEntry {
+            url: "github:NixOS/nixpkgs/nixos-24.11".to_string(),
+            to: locked,
+            pinned: true,
+        };
+        assert_eq!(entry.pinned_rev().as_deref(), Some("0123456"));
+
+        assert!(flake_ref_from_metadata("{}", false).is_err());
+    }
+
+    #[test]
+    fn test_validate_registry_name() {
+        assert!(validate_registry_name("stable").is_none());
+        assert!(validate_registry_name("my-proj_2").is_none());
+        assert!(validate_registry_name("").is_some());
+        assert!(validate_registry_name("2fast").is_some());
+        assert!(validate_registry_name("bad name").is_some());
+    }
+
+    #[test]
+    fn test_system_registry_names() {
+        let output = "\
+user   flake:scratch path:/home/john/scratch
+system flake:stable github:NixOS/nixpkgs/0123456789abcdef0123456789abcdef01234567
+system flake:nixpkgs path:/nix/store/abc-source
+global flake:agda github:agda/agda
+";
+        let names = system_registry_names(output);
+        assert_eq!(names.len(), 2);
+        assert!(names.contains("stable") && names.contains("nixpkgs"));
+        assert!(!names.contains("scratch"));
+    }
+
+    #[test]
+    fn test_registry_round_trip() {
+        let mut registry = Registry::new();
+        registry.insert(
+            "stable".to_string(),
+            RegistryEntry {
+                url: "github:NixOS/nixpkgs/nixos-24.11".to_string(),
+                to: flake_ref_from_metadata(METADATA, false).unwrap(),
+                pinned: false,
+            },
+        );
+        let json = serde_json::to_string(&registry).unwrap();
+        let parsed: Registry = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, registry);
+        assert_eq!(parsed["stable"].pinned_rev(), None);
+    }
+}
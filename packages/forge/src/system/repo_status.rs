@@ -0,0 +1,104 @@
+//! Git state of the config repo, for the main menu's status bar
+//!
+//! Read with `git status --porcelain=v2 --branch`, which works offline: ahead
+//! and behind are relative to the last fetch (the update check fetches).
+
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoStatus {
+    /// Branch name, or None on a detached HEAD
+    pub branch: Option<String>,
+    /// Commits not pushed to the upstream branch
+    pub ahead: u32,
+    /// Commits on the upstream branch not merged yet
+    pub behind: u32,
+    /// Modified, staged and untracked files
+    pub changed: usize,
+}
+
+impl RepoStatus {
+    /// Git status of `repo`; None if it isn't a git checkout
+    pub fn read(repo: &Path) -> Option<Self> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["status", "--porcelain=v2", "--branch"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        Some(parse_porcelain(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// e.g. "main ↑1 ↓2 · 3 changed", or "main ✓" when clean and in sync
+    pub fn summary(&self) -> String {
+        let mut parts = vec![self.branch.clone().unwrap_or_else(|| "(detached)".to_string())];
+        if self.ahead > 0 {
+            parts.push(format!("↑{}", self.ahead));
+        }
+        if self.behind > 0 {
+            parts.push(format!("↓{}", self.behind));
+        }
+        if self.changed > 0 {
+            parts.push(format!("· {} changed", self.changed));
+        }
+        if parts.len() == 1 {
+            parts.push("✓".to_string());
+        }
+        parts.join(" ")
+    }
+}
+
+/// Parse `git status --porcelain=v2 --branch` output
+fn parse_porcelain(output: &str) -> RepoStatus {
+    let mut status = RepoStatus::default();
+    for line in output.lines() {
+        if let Some(head) = line.strip_prefix("# branch.head ") {
+            status.branch = (head != "(detached)").then(|| head.to_string());
+        } else if let Some(ab) = line.strip_prefix("# branch.ab ") {
+            for count in ab.split_whitespace() {
+                if let Some(ahead) = count.strip_prefix('+') {
+                    status.ahead = ahead.parse().unwrap_or(0);
+                } else if let Some(behind) = count.strip_prefix('-') {
+                    status.behind = behind.parse().unwrap_or(0);
+                }
+            }
+        } else if !line.starts_with('#') && !line.is_empty() {
+            status.changed += 1;
+        }
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_porcelain() {
+        let output = "\
+# branch.oid 3f2a1c0
+# branch.head main
+# branch.upstream origin/main
+# branch.ab +1 -2
+1 .M N... 100644 100644 100644 abc abc hosts/G1a/default.nix
+? notes.txt
+";
+        let status = parse_porcelain(output);
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!((status.ahead, status.behind, status.changed), (1, 2, 2));
+        assert_eq!(status.summary(), "main ↑1 ↓2 · 2 changed");
+    }
+
+    #[test]
+    fn test_parse_porcelain_clean_and_detached() {
+        let clean = parse_porcelain("# branch.oid abc\n# branch.head main\n# branch.ab +0 -0\n");
+        assert_eq!(clean.summary(), "main ✓");
+        let detached = parse_porcelain("# branch.oid abc\n# branch.head (detached)\n");
+        assert_eq!(detached.branch, None);
+        assert_eq!(detached.summary(), "(detached) ✓");
+    }
+}
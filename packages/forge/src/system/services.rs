@@ -0,0 +1,201 @@
+//! Forge's background services, switched per host through the config repo
+//!
+//! `services.json` at the repo root records which hosts differ from the
+//! defaults; modules/forge-services.nix reads it into `forge.services.*` and
+//! the units come from the NixOS and Home Manager modules:
+//!
+//! ```json
+//! { "hosts": { "kraken": { "auto_update": true, "notify": false } } }
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Services settings filename (repo root)
+pub const SERVICES_FILE: &str = "services.json";
+
+/// A forge component that can run in the background
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Service {
+    /// Hourly update check with desktop notifications (forge-notify)
+    Notify,
+    /// Nightly `nixos-rebuild boot` from the main branch (system.autoUpgrade)
+    AutoUpdate,
+    /// Daily `app-backup --push` of browser and Termius profiles
+    AppsBackup,
+}
+
+impl Service {
+    /// All services, in screen order
+    pub const ALL: [Service; 3] = [Service::Notify, Service::AutoUpdate, Service::AppsBackup];
+
+    /// Name on the command line and in services.json
+    pub fn name(self) -> &'static str {
+        match self {
+            Service::Notify => "notify",
+            Service::AutoUpdate => "auto-update",
+            Service::AppsBackup => "apps-backup",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Service::Notify => "Hourly check for config, app profile and flake updates",
+            Service::AutoUpdate => "Nightly nixos-rebuild boot from main",
+            Service::AppsBackup => "Daily app profile backup, pushed to GitHub",
+        }
+    }
+
+    /// Whether hosts without a services.json entry run it
+    // Keep in sync with the defaults in modules/forge-services.nix
+    pub fn default_enabled(self) -> bool {
+        matches!(self, Service::Notify)
+    }
+
+    /// The timer that runs it
+    pub fn unit(self) -> &'static str {
+        match self {
+            Service::Notify => "forge-notify.timer",
+            Service::AutoUpdate => "nixos-upgrade.timer",
+            Service::AppsBackup => "forge-apps-backup.timer",
+        }
+    }
+
+    /// User unit (Home Manager) rather than a system one
+    pub fn is_user_unit(self) -> bool {
+        !matches!(self, Service::AutoUpdate)
+    }
+}
+
+/// Services one host sets itself; unset ones use the defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HostServices {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_update: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub apps_backup: Option<bool>,
+}
+
+impl HostServices {
+    fn get(&self, service: Service) -> Option<bool> {
+        match service {
+            Service::Notify => self.notify,
+            Service::AutoUpdate => self.auto_update,
+            Service::AppsBackup => self.apps_backup,
+        }
+    }
+
+    fn slot(&mut self, service: Service) -> &mut Option<bool> {
+        match service {
+            Service::Notify => &mut self.notify,
+            Service::AutoUpdate => &mut self.auto_update,
+            Service::AppsBackup => &mut self.apps_backup,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        Service::ALL.iter().all(|s| self.get(*s).is_none())
+    }
+}
+
+/// services.json contents
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServicesConfig {
+    /// Per-host settings, keyed by hostname
+    #[serde(default)]
+    pub hosts: BTreeMap<String, HostServices>,
+}
+
+impl ServicesConfig {
+    /// Whether `service` runs on `host`
+    pub fn enabled(&self, host: &str, service: Service) -> bool {
+        self.hosts
+            .get(host)
+            .and_then(|h| h.get(service))
+            .unwrap_or(service.default_enabled())
+    }
+
+    /// Turn `service` on or off for `host`; matching the default leaves it unset
+    pub fn set(&mut self, host: &str, service: Service, enabled: bool) {
+        let value = (enabled != service.default_enabled()).then_some(enabled);
+        *self.hosts.entry(host.to_string()).or_default().slot(service) = value;
+        // Don't leave empty host entries behind in services.json
+        if self.hosts.get(host).is_some_and(HostServices::is_empty) {
+            self.hosts.remove(host);
+        }
+    }
+
+    /// Pretty-printed for readable diffs
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)? + "\n")
+    }
+}
+
+/// One row of the services screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceRow {
+    pub service: Service,
+    /// Turned on for this host in services.json
+    pub configured: bool,
+    /// `systemctl is-enabled` of the unit, e.g. "enabled" or "not-found"
+    pub enabled: String,
+    /// `systemctl is-active` of the unit, e.g. "active" or "inactive"
+    pub active: String,
+}
+
+impl ServiceRow {
+    /// The unit doesn't match services.json yet (needs a rebuild)
+    pub fn is_pending(&self) -> bool {
+        self.configured != (self.enabled == "enabled")
+    }
+}
+
+/// Read services.json (all defaults if the file doesn't exist yet)
+pub fn load_services_config(config_dir: &Path) -> Result<ServicesConfig> {
+    let path = config_dir.join(SERVICES_FILE);
+    if !path.exists() {
+        return Ok(ServicesConfig::default());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enable_and_disable() {
+        let mut config = ServicesConfig::default();
+        assert!(config.enabled("kraken", Service::Notify));
+        assert!(!config.enabled("kraken", Service::AutoUpdate));
+
+        config.set("kraken", Service::AutoUpdate, true);
+        config.set("kraken", Service::Notify, false);
+        assert!(config.enabled("kraken", Service::AutoUpdate));
+        assert!(!config.enabled("kraken", Service::Notify));
+        assert!(!config.enabled("G1a", Service::AutoUpdate));
+        assert_eq!(
+            serde_json::to_string(&config).unwrap(),
+            r#"{"hosts":{"kraken":{"notify":false,"auto_update":true}}}"#
+        );
+
+        // Back to the defaults, the host entry goes away
+        config.set("kraken", Service::AutoUpdate, false);
+        config.set("kraken", Service::Notify, true);
+        assert!(config.hosts.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_keys_rejected() {
+        let json = r#"{"hosts":{"kraken":{"auto-update":true}}}"#;
+        assert!(serde_json::from_str::<ServicesConfig>(json).is_err());
+    }
+}
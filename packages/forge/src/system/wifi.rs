@@ -0,0 +1,184 @@
+//! Wireless networks for the installer's network setup screen
+//!
+//! When the install's network check fails, forge lists the networks the
+//! wireless interfaces can see and connects to one: through NetworkManager
+//! (`nmcli`, as on the forge ISO) when it is running, otherwise through iwd
+//! (`iwctl`). Both print tables meant for people; this module parses them.
+
+use regex::Regex;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// Regex to match ANSI escape codes (iwctl colours its tables)
+static ANSI_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1b\[[0-9;]*[a-zA-Z]").unwrap());
+
+/// Colour iwctl draws the missing signal bars in
+const IWCTL_DIM_BARS: &str = "\x1b[1;90m";
+
+/// Tool that manages the wireless interfaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WifiBackend {
+    NetworkManager,
+    Iwd,
+}
+
+impl std::fmt::Display for WifiBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WifiBackend::NetworkManager => write!(f, "NetworkManager"),
+            WifiBackend::Iwd => write!(f, "iwd"),
+        }
+    }
+}
+
+/// A network seen by a wireless interface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WifiNetwork {
+    pub interface: String,
+    pub ssid: String,
+    /// Signal strength in percent
+    pub signal: u8,
+    /// Whether it asks for a passphrase
+    pub secured: bool,
+}
+
+/// Wireless interfaces of this machine (those with a `wireless` directory in sysfs)
+pub fn wireless_interfaces() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir("/sys/class/net") else {
+        return Vec::new();
+    };
+    let mut interfaces: Vec<String> = entries
+        .flatten()
+        .filter(|e| Path::new("/sys/class/net").join(e.file_name()).join("wireless").exists())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .collect();
+    interfaces.sort();
+    interfaces
+}
+
+/// Parse `nmcli -t -f DEVICE,SSID,SIGNAL,SECURITY device wifi list`
+///
+/// Terse mode separates fields with `:` and escapes the ones inside values
+/// as `\:`.
+pub fn parse_nmcli_networks(output: &str) -> Vec<WifiNetwork> {
+    let networks = output.lines().filter_map(|line| {
+        let fields = split_terse(line);
+        let [interface, ssid, signal, security] = fields.as_slice() else {
+            return None;
+        };
+        Some(WifiNetwork {
+            interface: interface.clone(),
+            ssid: ssid.clone(),
+            signal: signal.parse().unwrap_or(0),
+            secured: !security.is_empty() && security != "--",
+        })
+    });
+    dedup_networks(networks)
+}
+
+fn split_terse(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    fields.last_mut().expect("fields is never empty").push(next);
+                }
+            }
+            ':' => fields.push(String::new()),
+            _ => fields.last_mut().expect("fields is never empty").push(c),
+        }
+    }
+    fields
+}
+
+/// Parse `iwctl station <interface> get-networks`
+///
+/// Rows follow the second dashed rule: an optional `>` for the connected
+/// network, then name, security and signal bars (`****`, the missing ones
+/// drawn dim), separated by runs of spaces.
+pub fn parse_iwctl_networks(interface: &str, output: &str) -> Vec<WifiNetwork> {
+    let networks = output
+        .lines()
+        .skip_while(|line| !is_rule(line))
+        .skip(1)
+        .skip_while(|line| !is_rule(line))
+        .skip(1)
+        .filter_map(|raw| {
+            let line = ANSI_RE.replace_all(raw, "");
+            let line = line.trim().trim_start_matches('>').trim();
+            let mut columns = line.split("  ").map(str::trim).filter(|c| !c.is_empty());
+            let ssid = columns.next()?;
+            let security = columns.next()?;
+            let bars = raw
+                .rsplit("  ")
+                .next()
+                .and_then(|signal| signal.split(IWCTL_DIM_BARS).next())
+                .map(|lit| ANSI_RE.replace_all(lit, "").matches('*').count())
+                .unwrap_or(0);
+            Some(WifiNetwork {
+                interface: interface.to_string(),
+                ssid: ssid.to_string(),
+                signal: (bars.min(4) * 25) as u8,
+                secured: security != "open",
+            })
+        });
+    dedup_networks(networks)
+}
+
+fn is_rule(line: &str) -> bool {
+    let line = ANSI_RE.replace_all(line, "");
+    let line = line.trim();
+    !line.is_empty() && line.chars().all(|c| c == '-')
+}
+
+/// Named networks, strongest first, each SSID once (from its strongest access point)
+fn dedup_networks(networks: impl Iterator<Item = WifiNetwork>) -> Vec<WifiNetwork> {
+    let mut networks: Vec<WifiNetwork> = networks.filter(|n| !n.ssid.is_empty()).collect();
+    networks.sort_by(|a, b| b.signal.cmp(&a.signal).then_with(|| a.ssid.cmp(&b.ssid)));
+    let mut seen = std::collections::HashSet::new();
+    networks.retain(|n| seen.insert(n.ssid.clone()));
+    networks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nmcli_networks() {
+        let output = "wlan0:HomeNet:72:WPA2\n\
+                      wlan0:Cafe\\: Guest:40:\n\
+                      wlan0::65:WPA2\n\
+                      wlan0:HomeNet:30:WPA2\n\
+                      wlan0:Lab:55:--\n";
+        let networks = parse_nmcli_networks(output);
+        let ssids: Vec<&str> = networks.iter().map(|n| n.ssid.as_str()).collect();
+        assert_eq!(ssids, vec!["HomeNet", "Lab", "Cafe: Guest"]);
+        assert_eq!(networks[0].signal, 72);
+        assert!(networks[0].secured);
+        assert!(!networks[1].secured);
+        assert!(!networks[2].secured);
+    }
+
+    #[test]
+    fn test_parse_iwctl_networks() {
+        let output = "                               Available networks\n\
+            --------------------------------------------------------------------------------\n\
+            \x1b[1;90m      Network name                      Security            Signal\x1b[0m\n\
+            --------------------------------------------------------------------------------\n\
+            \x20 \x1b[1;90m> \x1b[0m  Home Net                          psk                 ****\n\
+            \x20       Cafe                              open                **\x1b[1;90m**\x1b[0m\n";
+        let networks = parse_iwctl_networks("wlan0", output);
+        assert_eq!(networks.len(), 2);
+        assert_eq!(networks[0].ssid, "Home Net");
+        assert_eq!(networks[0].signal, 100);
+        assert!(networks[0].secured);
+        assert_eq!(networks[1].ssid, "Cafe");
+        assert_eq!(networks[1].signal, 50);
+        assert!(!networks[1].secured);
+        assert_eq!(networks[1].interface, "wlan0");
+    }
+}
@@ -0,0 +1,199 @@
+//! First-boot checklist written to the new user's home directory
+//!
+//! Summarises what the installer set up and what is still left to do. The
+//! greeting and date follow the locale configured in modules/common.nix.
+
+use chrono::{DateTime, Local};
+
+use crate::system::hardware::BootMode;
+
+/// File name of the checklist in the user's home directory
+pub const FIRST_BOOT_FILE: &str = "FIRST-BOOT.md";
+
+/// Facts about the install shown on the checklist
+#[derive(Debug, Clone)]
+pub struct FirstBootInfo {
+    pub hostname: String,
+    pub username: String,
+    pub disk: String,
+    /// Whether the disk was LUKS2 encrypted
+    pub encrypted: bool,
+    pub boot_mode: BootMode,
+    /// Value of `i18n.defaultLocale`
+    pub locale: Option<String>,
+    /// Value of `i18n.extraLocaleSettings.LC_TIME` (falls back to `locale`)
+    pub time_locale: Option<String>,
+    pub installed_at: DateTime<Local>,
+}
+
+/// Read `i18n.defaultLocale` and `LC_TIME` from a NixOS module
+pub fn parse_config_locale(nix: &str) -> (Option<String>, Option<String>) {
+    let find = |key: &str| {
+        nix.lines()
+            .map(str::trim)
+            .find(|l| l.starts_with(key))
+            .and_then(|l| l.split('"').nth(1))
+            .map(str::to_string)
+    };
+    (find("i18n.defaultLocale"), find("LC_TIME"))
+}
+
+/// Localised strings, keyed on the language part of a locale ("nl_NL.UTF-8" -> "nl")
+struct Strings {
+    welcome: &'static str,
+    installed_on: &'static str,
+    set_up: &'static str,
+    next_steps: &'static str,
+    delete_hint: &'static str,
+}
+
+fn strings_for(locale: Option<&str>) -> Strings {
+    match locale.and_then(|l| l.split(['_', '.']).next()) {
+        Some("nl") => Strings {
+            welcome: "Welkom op",
+            installed_on: "Geïnstalleerd op",
+            set_up: "Wat forge heeft ingesteld",
+            next_steps: "Nog te doen",
+            delete_hint: "Verwijder dit bestand als je klaar bent, dan verdwijnt ook de melding bij het inloggen.",
+        },
+        Some("de") => Strings {
+            welcome: "Willkommen auf",
+            installed_on: "Installiert am",
+            set_up: "Was forge eingerichtet hat",
+            next_steps: "Noch zu erledigen",
+            delete_hint: "Lösche diese Datei, wenn alles erledigt ist; dann verschwindet auch der Hinweis beim Login.",
+        },
+        _ => Strings {
+            welcome: "Welcome to",
+            installed_on: "Installed on",
+            set_up: "What forge set up",
+            next_steps: "Still to do",
+            delete_hint: "Delete this file when you're done; the login reminder goes away with it.",
+        },
+    }
+}
+
+/// Date format for the region part of a locale
+fn date_format(locale: Option<&str>) -> &'static str {
+    let region = locale
+        .and_then(|l| l.split('.').next())
+        .and_then(|l| l.split('_').nth(1));
+    match region {
+        Some("US") => "%m/%d/%Y %I:%M %p",
+        Some("NL") | Some("BE") => "%d-%m-%Y %H:%M",
+        Some("DE") | Some("AT") | Some("CH") => "%d.%m.%Y %H:%M",
+        Some("GB") | Some("IE") | Some("FR") | Some("ES") | Some("IT") => "%d/%m/%Y %H:%M",
+        _ => "%Y-%m-%d %H:%M",
+    }
+}
+
+/// Generate the checklist markdown
+pub fn generate_first_boot_checklist(info: &FirstBootInfo) -> String {
+    let s = strings_for(info.locale.as_deref());
+    let time_locale = info.time_locale.as_deref().or(info.locale.as_deref());
+    let date = info.installed_at.format(date_format(time_locale));
+
+    let bootloader = match info.boot_mode {
+        BootMode::Uefi => "Limine (UEFI)",
+        BootMode::Bios => "GRUB (legacy BIOS, MBR)",
+    };
+
+    format!(
+        r#"# {welcome} {hostname}
+
+{installed_on} {date}
+
+## {set_up}
+
+- Hostname: `{hostname}`
+- User: `{username}` (member of wheel, password set during install)
+- Disk: `{disk}`, {encryption}, Btrfs subvolumes (@, @home, @nix, @var-log)
+- Bootloader: {bootloader}
+- Configuration: `~/nixos-config` (symlinked from /etc/nixos)
+
+## {next_steps}
+
+- [ ] Connect to Wi-Fi if needed: `nmtui`
+- [ ] Restore or set up keys: `forge keys restore` (or `forge keys setup` for 1Password)
+- [ ] Restore app profiles: `forge apps restore`
+- [ ] Check for updates: `forge update`
+- [ ] Commit and push `hosts/{hostname}` from `~/nixos-config` if this is a new host
+
+{delete_hint}
+"#,
+        welcome = s.welcome,
+        installed_on = s.installed_on,
+        set_up = s.set_up,
+        next_steps = s.next_steps,
+        delete_hint = s.delete_hint,
+        hostname = info.hostname,
+        username = info.username,
+        disk = info.disk,
+        encryption = if info.encrypted { "LUKS2 encrypted" } else { "not encrypted" },
+        bootloader = bootloader,
+        date = date,
+    )
+}
+
+/// Fish snippet that reminds the user about the checklist at login while it exists
+pub fn generate_first_boot_motd() -> String {
+    format!(
+        r#"# Installed by forge - remove ~/{file} to silence
+if status is-interactive; and test -f ~/{file}
+    set_color yellow
+    echo "First boot checklist: ~/{file}"
+    set_color normal
+end
+"#,
+        file = FIRST_BOOT_FILE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn info(locale: &str, time_locale: Option<&str>) -> FirstBootInfo {
+        FirstBootInfo {
+            hostname: "kraken".to_string(),
+            username: "john".to_string(),
+            disk: "/dev/nvme0n1".to_string(),
+            encrypted: true,
+            boot_mode: BootMode::Uefi,
+            locale: Some(locale.to_string()),
+            time_locale: time_locale.map(str::to_string),
+            installed_at: Local.with_ymd_and_hms(2026, 3, 14, 9, 5, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_parse_config_locale() {
+        let nix = r#"
+  i18n.defaultLocale = "en_US.UTF-8";
+  i18n.extraLocaleSettings = {
+    LC_TIME = "nl_NL.UTF-8";
+  };
+"#;
+        let (locale, time) = parse_config_locale(nix);
+        assert_eq!(locale.as_deref(), Some("en_US.UTF-8"));
+        assert_eq!(time.as_deref(), Some("nl_NL.UTF-8"));
+        assert_eq!(parse_config_locale(""), (None, None));
+    }
+
+    #[test]
+    fn test_checklist_uses_locale() {
+        let en = generate_first_boot_checklist(&info("en_US.UTF-8", Some("nl_NL.UTF-8")));
+        assert!(en.starts_with("# Welcome to kraken"));
+        // LC_TIME decides the date format
+        assert!(en.contains("14-03-2026 09:05"));
+        assert!(en.contains("forge keys restore"));
+        assert!(en.contains("`/dev/nvme0n1`, LUKS2 encrypted"));
+
+        let nl = generate_first_boot_checklist(&info("nl_NL.UTF-8", None));
+        assert!(nl.starts_with("# Welkom op kraken"));
+
+        let us = generate_first_boot_checklist(&info("en_US.UTF-8", None));
+        assert!(us.contains("03/14/2026 09:05 AM"));
+    }
+}
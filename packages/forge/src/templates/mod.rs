@@ -1,7 +1,19 @@
 //! NixOS configuration template generators
 
-use crate::app::NewHostConfig;
-use crate::system::hardware::{CpuInfo, CpuVendor, FormFactor, GpuVendor};
+mod first_boot;
+
+pub use first_boot::{
+    generate_first_boot_checklist, generate_first_boot_motd, parse_config_locale, FirstBootInfo,
+    FIRST_BOOT_FILE,
+};
+
+use crate::app::{
+    DiskOptions, HostModule, KernelFlavor, NewHostConfig, RootFilesystem, SwapMode, ThemePreset,
+};
+use crate::system::disk::identity::DiskIdentity;
+use crate::system::disk::StorageKind;
+use crate::system::display::MonitorInfo;
+use crate::system::hardware::{BootMode, CpuInfo, CpuVendor, FormFactor, GpuVendor};
 
 /// Generate the host's default.nix configuration
 pub fn generate_host_default_nix(config: &NewHostConfig) -> String {
@@ -9,23 +21,46 @@ pub fn generate_host_default_nix(config: &NewHostConfig) -> String {
     let form_factor_config = generate_form_factor_config(&config.form_factor);
     let cpu_config = generate_cpu_config(&config.cpu.vendor);
     let initrd_modules = generate_initrd_modules(&config.gpu.vendor);
+    let detected_modules = generate_detected_initrd_modules(&config.initrd_modules);
+    let kernel_params: Vec<String> = gpu_kernel_params(&config.gpu.vendor)
+        .iter()
+        .map(|p| p.to_string())
+        .chain(config.kernel_params.iter().cloned())
+        .collect();
+    let kernel_config = generate_kernel_config(config.kernel, &kernel_params);
+    let filesystem_config = generate_filesystem_config(config.filesystem, &config.host_id);
+    let storage_config = generate_storage_config(config.storage, config.filesystem);
+    let swap_config = generate_swap_config(config.swap);
+    let modules_config = generate_modules_config(&config.modules, &config.gpu.vendor);
+    let theme_config = generate_theme_config(config.theme);
+    let ephemeral = config.filesystem == RootFilesystem::Btrfs
+        && config.subvolume_layout.ephemeral_root().is_some();
+    let (impermanence_import, persistence_config) = if ephemeral {
+        ("    ../../modules/impermanence.nix\n", generate_persistence_config())
+    } else {
+        ("", String::new())
+    };
+    let boot_module = match config.boot_mode {
+        BootMode::Uefi => "limine-plymouth.nix",
+        BootMode::Bios => "grub-plymouth.nix",
+    };
 
     format!(
         r#"# {hostname} - {description}
-{{ config, pkgs, lib, ... }}:
+{{ config, pkgs, lib, username, ... }}:
 
 {{
   imports = [
     ./hardware-configuration.nix
-    ../../modules/boot/limine-plymouth.nix
-  ];
+    ../../modules/boot/{boot_module}
+{impermanence_import}  ];
 
   networking.hostName = "{hostname}";
-{gpu_config}{cpu_config}{form_factor_config}
+{filesystem_config}{persistence_config}{storage_config}{swap_config}{kernel_config}{gpu_config}{cpu_config}{form_factor_config}{modules_config}{theme_config}
   # Early KMS for Plymouth boot splash
   boot.initrd.kernelModules = lib.mkForce [
 {initrd_modules}  ];
-}}
+{detected_modules}}}
 "#,
         hostname = config.hostname,
         description = generate_description(config),
@@ -33,6 +68,16 @@ pub fn generate_host_default_nix(config: &NewHostConfig) -> String {
         cpu_config = cpu_config,
         form_factor_config = form_factor_config,
         initrd_modules = initrd_modules,
+        boot_module = boot_module,
+        detected_modules = detected_modules,
+        filesystem_config = filesystem_config,
+        impermanence_import = impermanence_import,
+        persistence_config = persistence_config,
+        storage_config = storage_config,
+        swap_config = swap_config,
+        kernel_config = kernel_config,
+        modules_config = modules_config,
+        theme_config = theme_config,
     )
 }
 
@@ -53,6 +98,312 @@ fn generate_description(config: &NewHostConfig) -> String {
     format!("{} with {}", form, gpu)
 }
 
+/// Kernel params required by the GPU driver
+fn gpu_kernel_params(vendor: &GpuVendor) -> &'static [&'static str] {
+    match vendor {
+        GpuVendor::AMD => &["amdgpu.ppfeaturemask=0xffffffff"],
+        _ => &[],
+    }
+}
+
+/// Generate kernel package and extra kernel params
+/// (a single `boot.kernelParams` definition, Nix rejects duplicates in one attrset)
+fn generate_kernel_config(kernel: KernelFlavor, params: &[String]) -> String {
+    let mut out = format!(
+        r#"
+  # Kernel ({kernel})
+  boot.kernelPackages = pkgs.{package};
+"#,
+        kernel = kernel,
+        package = kernel.package_attr(),
+    );
+
+    if !params.is_empty() {
+        let list: Vec<String> = params.iter().map(|p| format!("\"{}\"", p)).collect();
+        out.push_str(&format!("  boot.kernelParams = [ {} ];\n", list.join(" ")));
+    }
+
+    out
+}
+
+/// Generate root filesystem support (nothing extra for btrfs)
+fn generate_filesystem_config(filesystem: RootFilesystem, host_id: &str) -> String {
+    match filesystem {
+        RootFilesystem::Btrfs => String::new(),
+        RootFilesystem::Zfs => format!(
+            r#"
+  # ZFS root (modules/disko/zfs.nix); hostId must be unique per machine
+  boot.supportedFilesystems = [ "zfs" ];
+  networking.hostId = "{host_id}";
+  services.zfs.autoScrub.enable = true;
+  services.zfs.trim.enable = true;
+"#,
+            host_id = host_id,
+        ),
+    }
+}
+
+/// Generate the state kept across reboots on an ephemeral root (modules/impermanence.nix)
+fn generate_persistence_config() -> String {
+    r#"
+  # Impermanence: / is wiped at every boot, this is kept in /persist
+  # (/home, /nix and /var/log are subvolumes of their own)
+  environment.persistence."/persist" = {
+    hideMounts = true;
+    directories = [
+      "/etc/NetworkManager/system-connections"
+      "/var/lib/bluetooth"
+      "/var/lib/docker"
+      "/var/lib/nixos"
+      "/var/lib/systemd/coredump"
+      "/var/lib/systemd/timers"
+    ];
+    files = [
+      "/etc/machine-id"
+    ];
+  };
+"#
+    .to_string()
+}
+
+/// Generate TRIM settings for the detected disk (ZFS trims through services.zfs.trim)
+fn generate_storage_config(storage: StorageKind, filesystem: RootFilesystem) -> String {
+    match (storage, filesystem) {
+        (StorageKind::Ssd, RootFilesystem::Btrfs) => r#"
+  # SSD/NVMe: weekly TRIM of unused blocks
+  services.fstrim.enable = true;
+"#
+        .to_string(),
+        (StorageKind::Hdd, RootFilesystem::Btrfs) => r#"
+  # Spinning disk: nothing to TRIM
+  services.fstrim.enable = false;
+"#
+        .to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Generate zram settings (the zram-only default comes from common.nix)
+///
+/// The swapfile or partition itself is created by the host's disko config.
+fn generate_swap_config(swap: SwapMode) -> String {
+    match swap {
+        SwapMode::Zram => String::new(),
+        SwapMode::None => r#"
+  # No swap: zram from common.nix switched off
+  zramSwap.enable = lib.mkForce false;
+"#
+        .to_string(),
+        SwapMode::Swapfile => r#"
+  # Swap: zram (common.nix) first, then the swapfile in @swap (disko config)
+  zramSwap.priority = 100;
+"#
+        .to_string(),
+        SwapMode::Partition => r#"
+  # Swap: zram (common.nix) first, then the encrypted swap partition (disko
+  # config), which is also the hibernation resume device
+  zramSwap.priority = 100;
+"#
+        .to_string(),
+    }
+}
+
+/// Nix contributed by an optional module. Packages and groups are merged
+/// across modules so each attribute is only defined once in default.nix.
+struct ModuleSnippet {
+    config: &'static str,
+    packages: &'static [&'static str],
+    groups: &'static [&'static str],
+}
+
+fn module_snippet(module: HostModule, gpu: &GpuVendor) -> ModuleSnippet {
+    match module {
+        HostModule::Printing => ModuleSnippet {
+            config: r#"
+  # Printing (CUPS)
+  services.printing = {
+    enable = true;
+    drivers = with pkgs; [ gutenprint hplip brlaser ];
+  };
+"#,
+            packages: &[],
+            groups: &["lp"],
+        },
+        HostModule::Scanning => ModuleSnippet {
+            config: r#"
+  # Scanning (SANE, driverless eSCL/WSD via airscan)
+  hardware.sane = {
+    enable = true;
+    extraBackends = [ pkgs.sane-airscan ];
+  };
+  services.ipp-usb.enable = true;
+"#,
+            packages: &["simple-scan"],
+            groups: &["scanner", "lp"],
+        },
+        HostModule::Android => ModuleSnippet {
+            // systemd's uaccess rules grant the logged-in user access to adb/fastboot devices
+            config: "",
+            packages: &["android-tools"],
+            groups: &[],
+        },
+        HostModule::Libvirt => ModuleSnippet {
+            config: r#"
+  # Virtualization (libvirt/QEMU)
+  virtualisation.libvirtd = {
+    enable = true;
+    qemu.swtpm.enable = true;
+  };
+  programs.virt-manager.enable = true;
+"#,
+            packages: &[],
+            groups: &["libvirtd", "kvm"],
+        },
+        HostModule::Docker => ModuleSnippet {
+            config: r#"
+  # Docker (also enabled in common.nix, kept explicit for this host)
+  virtualisation.docker.enable = true;
+"#,
+            packages: &[],
+            groups: &["docker"],
+        },
+        HostModule::Podman => ModuleSnippet {
+            config: r#"
+  # Podman with docker CLI compatibility (replaces Docker from common.nix)
+  virtualisation.docker.enable = lib.mkForce false;
+  virtualisation.podman = {
+    enable = true;
+    dockerCompat = true;
+    defaultNetwork.settings.dns_enabled = true;
+  };
+"#,
+            packages: &["podman-compose"],
+            groups: &[],
+        },
+        HostModule::VirtualBox => ModuleSnippet {
+            config: r#"
+  # VirtualBox
+  virtualisation.virtualbox.host.enable = true;
+"#,
+            packages: &[],
+            groups: &["vboxusers"],
+        },
+        HostModule::Gaming => ModuleSnippet {
+            config: generate_gaming_config(gpu),
+            packages: &[],
+            groups: &["gamemode"],
+        },
+        HostModule::Bootstrap => ModuleSnippet {
+            config: r#"
+  # Run `forge bootstrap` (keys restore, then apps restore) on first login
+  bootstrap.enable = true;
+"#,
+            packages: &[],
+            groups: &[],
+        },
+    }
+}
+
+/// Gaming module (modules/gaming.nix) plus GPU vendor specific tuning
+fn generate_gaming_config(gpu: &GpuVendor) -> &'static str {
+    match gpu {
+        GpuVendor::NVIDIA => {
+            // 32-bit NVIDIA userspace follows hardware.graphics.enable32Bit
+            r#"
+  # Gaming (Steam, GameMode, gamescope, 32-bit graphics)
+  gaming.enable = true;
+"#
+        }
+        GpuVendor::AMD => {
+            r#"
+  # Gaming (Steam, GameMode, gamescope, 32-bit graphics)
+  gaming.enable = true;
+  programs.gamemode.settings.gpu = {
+    apply_gpu_optimisations = "accept-responsibility";
+    gpu_device = 0;
+    amd_performance_level = "high";
+  };
+"#
+        }
+        GpuVendor::Intel => {
+            r#"
+  # Gaming (Steam, GameMode, gamescope, 32-bit graphics)
+  gaming.enable = true;
+  hardware.graphics.extraPackages32 = with pkgs.driversi686Linux; [ intel-media-driver ];
+"#
+        }
+        GpuVendor::None => {
+            r#"
+  # Gaming (Steam, GameMode, gamescope, 32-bit graphics)
+  gaming.enable = true;
+"#
+        }
+    }
+}
+
+/// Generate configuration for the optional modules picked in the wizard
+fn generate_modules_config(modules: &[HostModule], gpu: &GpuVendor) -> String {
+    if modules.is_empty() {
+        return String::new();
+    }
+
+    let snippets: Vec<ModuleSnippet> = modules.iter().map(|m| module_snippet(*m, gpu)).collect();
+    let mut out: String = snippets.iter().map(|s| s.config).collect();
+
+    // Printer and scanner discovery on the local network
+    if modules.contains(&HostModule::Printing) || modules.contains(&HostModule::Scanning) {
+        out.push_str(
+            r#"
+  services.avahi = {
+    enable = true;
+    nssmdns4 = true;
+    openFirewall = true;
+  };
+"#,
+        );
+    }
+
+    let mut packages: Vec<&str> = Vec::new();
+    for package in snippets.iter().flat_map(|s| s.packages) {
+        if !packages.contains(package) {
+            packages.push(package);
+        }
+    }
+    if !packages.is_empty() {
+        out.push_str(&format!(
+            "\n  environment.systemPackages = with pkgs; [ {} ];\n",
+            packages.join(" ")
+        ));
+    }
+
+    let mut groups: Vec<&str> = snippets.iter().flat_map(|s| s.groups).copied().collect();
+    groups.sort();
+    groups.dedup();
+    if !groups.is_empty() {
+        let list: Vec<String> = groups.iter().map(|g| format!("\"{}\"", g)).collect();
+        out.push_str(&format!(
+            "  users.users.${{username}}.extraGroups = [ {} ];\n",
+            list.join(" ")
+        ));
+    }
+
+    out
+}
+
+/// Generate the theming preset (modules/theming.nix), nothing for the default look
+fn generate_theme_config(theme: ThemePreset) -> String {
+    if theme == ThemePreset::Default {
+        return String::new();
+    }
+    format!(
+        r#"
+  # Look and feel (fonts, cursor, GTK/Qt theme)
+  theming.preset = "{}";
+"#,
+        theme.nix_name()
+    )
+}
+
 /// Generate GPU-specific configuration
 fn generate_gpu_config(vendor: &GpuVendor) -> String {
     match vendor {
@@ -61,13 +412,10 @@ fn generate_gpu_config(vendor: &GpuVendor) -> String {
             String::new()
         }
         GpuVendor::AMD => {
+            // Kernel params are emitted together with the user's in generate_kernel_config
             r#"
   # AMD GPU configuration
   hardware.amdgpu.initrd.enable = true;
-
-  boot.kernelParams = [
-    "amdgpu.ppfeaturemask=0xffffffff"
-  ];
 "#
             .to_string()
         }
@@ -172,15 +520,100 @@ fn generate_initrd_modules(vendor: &GpuVendor) -> String {
     }
 }
 
-/// Generate disko configuration for the host
-pub fn generate_disko_config(hostname: &str, disk_path: &str) -> String {
+/// Generate initrd modules detected on the machine the host was created on
+/// (storage controllers, USB and keyboard drivers needed before root is mounted)
+fn generate_detected_initrd_modules(modules: &[String]) -> String {
+    if modules.is_empty() {
+        return String::new();
+    }
+
+    let list: String = modules
+        .iter()
+        .map(|module| format!("    \"{}\"\n", module))
+        .collect();
+
     format!(
-        r#"# Disko configuration for {hostname}
+        r#"
+  # Storage and input drivers detected at install time
+  boot.initrd.availableKernelModules = [
+{list}  ];
+"#,
+        list = list,
+    )
+}
+
+/// Generate disko configuration for the host
+///
+/// `subvolumes` overrides the shared btrfs layout; None keeps its default.
+/// `swap` is the on-disk swap and its size in GiB, if any.
+/// `ephemeral_root` is `diskLayout.ephemeralRoot` for impermanence hosts.
+pub fn generate_disko_config(
+    hostname: &str,
+    disk_path: &str,
+    boot_mode: BootMode,
+    subvolumes: Option<&[(String, String)]>,
+    swap: Option<(SwapMode, u64)>,
+    options: &DiskOptions,
+    ephemeral_root: Option<&str>,
+) -> String {
+    let layout = generate_subvolumes_config(subvolumes)
+        + &generate_ephemeral_root_config(ephemeral_root)
+        + &generate_swap_disko_config(swap)
+        + &generate_disk_options_config(options, boot_mode);
+    match boot_mode {
+        BootMode::Uefi => format!(
+            r#"# Disko configuration for {hostname}
 {{ ... }}:
 
 {{
   imports = [ ./default.nix ];
 
+  disko.devices.disk.main.device = "{disk_path}";
+{layout}}}
+"#,
+            hostname = hostname,
+            disk_path = disk_path,
+            layout = layout,
+        ),
+        // GRUB is installed to the MBR of the same disk
+        BootMode::Bios => format!(
+            r#"# Disko configuration for {hostname} (legacy BIOS)
+{{ ... }}:
+
+{{
+  imports = [ ./bios.nix ];
+
+  disko.devices.disk.main.device = "{disk_path}";
+  boot.loader.grub.device = "{disk_path}";
+{layout}}}
+"#,
+            hostname = hostname,
+            disk_path = disk_path,
+            layout = layout,
+        ),
+    }
+}
+
+/// Note the target disk's serial/WWN under the header comment of a disko config
+pub fn annotate_disk_identity(disko: &str, disk: &DiskIdentity) -> String {
+    let (header, rest) = disko.split_once('\n').unwrap_or((disko, ""));
+    format!(
+        "{}\n# Target disk: {} (checked by forge install and update)\n{}",
+        header,
+        disk.describe(),
+        rest
+    )
+}
+
+/// Generate disko configuration for a ZFS root host (UEFI only)
+pub fn generate_zfs_disko_config(hostname: &str, disk_path: &str) -> String {
+    format!(
+        r#"# Disko configuration for {hostname} (ZFS root)
+{{ ... }}:
+
+{{
+  imports = [ ./zfs.nix ];
+
   disko.devices.disk.main.device = "{disk_path}";
 }}
 "#,
@@ -189,6 +622,123 @@ pub fn generate_disko_config(hostname: &str, disk_path: &str) -> String {
     )
 }
 
+/// `diskLayout.subvolumes` override for the disko config (modules/disko/subvolumes.nix)
+fn generate_subvolumes_config(subvolumes: Option<&[(String, String)]>) -> String {
+    match subvolumes {
+        None => String::new(),
+        Some([]) => concat!(
+            "\n  # Flat btrfs: no subvolumes, the filesystem is mounted at /\n",
+            "  diskLayout.subvolumes = { };\n"
+        )
+        .to_string(),
+        Some(subvolumes) => {
+            let entries: String = subvolumes
+                .iter()
+                .map(|(name, mountpoint)| format!("    \"{}\" = \"{}\";\n", name, mountpoint))
+                .collect();
+            format!(
+                "\n  # Btrfs subvolumes (name = mountpoint)\n  diskLayout.subvolumes = {{\n{}  }};\n",
+                entries
+            )
+        }
+    }
+}
+
+/// `diskLayout.ephemeralRoot` for the disko config (modules/disko/impermanence.nix)
+fn generate_ephemeral_root_config(ephemeral_root: Option<&str>) -> String {
+    match ephemeral_root {
+        Some(mode) => format!(
+            concat!(
+                "\n  # Impermanence: / is wiped at every boot\n",
+                "  diskLayout.ephemeralRoot = \"{}\";\n"
+            ),
+            mode
+        ),
+        None => String::new(),
+    }
+}
+
+/// `diskLayout` swap options for the disko config (modules/disko/subvolumes.nix)
+fn generate_swap_disko_config(swap: Option<(SwapMode, u64)>) -> String {
+    match swap {
+        Some((SwapMode::Swapfile, size)) => format!(
+            concat!(
+                "\n  # Swapfile in an @swap subvolume\n",
+                "  diskLayout.swapfileSize = \"{}G\";\n"
+            ),
+            size
+        ),
+        Some((SwapMode::Partition, size)) => format!(
+            concat!(
+                "\n  # Encrypted swap partition, RAM plus margin for hibernation\n",
+                "  diskLayout.swapPartitionSize = \"{}G\";\n"
+            ),
+            size
+        ),
+        _ => String::new(),
+    }
+}
+
+/// `diskLayout` compression, mount and label options that differ from the defaults
+fn generate_disk_options_config(options: &DiskOptions, boot_mode: BootMode) -> String {
+    let defaults = DiskOptions::default();
+    let mut lines = Vec::new();
+    if options.compression != defaults.compression {
+        lines.push(match options.compression {
+            Some(level) => format!("  diskLayout.compression = \"zstd:{}\";", level),
+            None => "  diskLayout.compression = null;".to_string(),
+        });
+    }
+    if options.noatime != defaults.noatime {
+        lines.push(format!("  diskLayout.noatime = {};", options.noatime));
+    }
+    if options.discard != defaults.discard {
+        lines.push(format!("  diskLayout.discard = {};", options.discard));
+    }
+    if options.autodefrag != defaults.autodefrag {
+        lines.push(format!("  diskLayout.autodefrag = {};", options.autodefrag));
+    }
+    // The BIOS layout uses an MBR, which has no partition labels
+    if boot_mode == BootMode::Uefi {
+        if options.boot_label != defaults.boot_label {
+            lines.push(format!("  diskLayout.bootLabel = \"{}\";", options.boot_label));
+        }
+        if !options.root_label.is_empty() {
+            lines.push(format!("  diskLayout.rootLabel = \"{}\";", options.root_label));
+        }
+    }
+    if lines.is_empty() {
+        return String::new();
+    }
+    format!("\n  # Btrfs mount options and partition labels\n{}\n", lines.join("\n"))
+}
+
+/// Generate hosts/<name>/monitors.conf (Hyprland syntax) from a captured layout
+/// Picked up by home/hyprland/monitors.nix instead of the generic fallback.
+pub fn generate_monitors_conf(hostname: &str, monitors: &[MonitorInfo]) -> String {
+    let mut out = format!(
+        "# Monitor layout captured by forge while creating {}\n\
+         # Format: monitor = [port], resolution@refresh, position, scale\n",
+        hostname
+    );
+
+    for m in monitors {
+        out.push_str(&format!(
+            "monitor = {},{}x{}@{:.2},{}x{},{}\n",
+            m.name, m.width, m.height, m.refresh, m.x, m.y, m.scale
+        ));
+    }
+
+    // Anything plugged in later gets sensible defaults
+    out.push_str("monitor = ,preferred,auto,auto\n");
+
+    if let Some(scale) = monitors.first().map(|m| m.scale).filter(|s| *s != 1.0) {
+        out.push_str(&format!("env = GDK_SCALE,{}\n", scale));
+    }
+
+    out
+}
+
 /// Generate hardware-configuration.nix template
 pub fn generate_hardware_config(cpu: &CpuInfo, hostname: &str) -> String {
     let kvm_module = match cpu.vendor {
@@ -232,14 +782,209 @@ pub fn generate_hardware_config(cpu: &CpuInfo, hostname: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app::SubvolumeLayout;
     use crate::system::disk::DiskInfo;
     use crate::system::hardware::GpuInfo;
 
     #[test]
     fn test_generate_disko_config() {
-        let config = generate_disko_config("testhost", "/dev/nvme0n1");
+        let config = generate_disko_config(
+            "testhost",
+            "/dev/nvme0n1",
+            BootMode::Uefi,
+            None,
+            None,
+            &DiskOptions::default(),
+            None,
+        );
         assert!(config.contains("testhost"));
         assert!(config.contains("/dev/nvme0n1"));
+        assert!(config.contains("./default.nix"));
+        assert!(!config.contains("grub"));
+        assert!(!config.contains("diskLayout"));
+    }
+
+    #[test]
+    fn test_generate_disko_config_subvolumes() {
+        let subvolumes = vec![
+            ("@".to_string(), "/".to_string()),
+            ("@snapshots".to_string(), "/.snapshots".to_string()),
+        ];
+        let config = generate_disko_config(
+            "testhost",
+            "/dev/nvme0n1",
+            BootMode::Uefi,
+            Some(&subvolumes),
+            None,
+            &DiskOptions::default(),
+            None,
+        );
+        assert!(config.contains("  diskLayout.subvolumes = {\n    \"@\" = \"/\";\n"));
+        assert!(config.contains("    \"@snapshots\" = \"/.snapshots\";\n  };\n}\n"));
+
+        let defaults = DiskOptions::default();
+        let flat = generate_disko_config(
+            "oldbox",
+            "/dev/sda",
+            BootMode::Bios,
+            Some(&[]),
+            None,
+            &defaults,
+            None,
+        );
+        assert!(flat.contains("diskLayout.subvolumes = { };"));
+        assert!(flat.contains("boot.loader.grub.device"));
+    }
+
+    #[test]
+    fn test_generate_disko_config_ephemeral_root() {
+        let layout = SubvolumeLayout::EphemeralTmpfs;
+        let subvolumes: Vec<(String, String)> = layout
+            .subvolumes()
+            .iter()
+            .map(|(n, m)| (n.to_string(), m.to_string()))
+            .collect();
+        let config = generate_disko_config(
+            "testhost",
+            "/dev/nvme0n1",
+            BootMode::Uefi,
+            Some(&subvolumes),
+            None,
+            &DiskOptions::default(),
+            layout.ephemeral_root(),
+        );
+        assert!(config.contains("    \"@persist\" = \"/persist\";\n"));
+        assert!(config.contains("  diskLayout.ephemeralRoot = \"tmpfs\";\n"));
+        assert!(!config.contains("\"@\" = \"/\""));
+    }
+
+    #[test]
+    fn test_generate_disko_config_swap() {
+        let swapfile = generate_disko_config(
+            "testhost",
+            "/dev/nvme0n1",
+            BootMode::Uefi,
+            None,
+            Some((SwapMode::Swapfile, 8)),
+            &DiskOptions::default(),
+            None,
+        );
+        assert!(swapfile.contains("  diskLayout.swapfileSize = \"8G\";\n}\n"));
+
+        let partition = generate_disko_config(
+            "testhost",
+            "/dev/nvme0n1",
+            BootMode::Uefi,
+            None,
+            Some((SwapMode::Partition, 38)),
+            &DiskOptions::default(),
+            None,
+        );
+        assert!(partition.contains("diskLayout.swapPartitionSize = \"38G\";"));
+        assert!(!partition.contains("swapfileSize"));
+    }
+
+    #[test]
+    fn test_generate_disko_config_bios() {
+        let defaults = DiskOptions::default();
+        let config = generate_disko_config(
+            "oldbox",
+            "/dev/sda",
+            BootMode::Bios,
+            None,
+            None,
+            &defaults,
+            None,
+        );
+        assert!(config.contains("./bios.nix"));
+        assert!(config.contains(r#"boot.loader.grub.device = "/dev/sda";"#));
+    }
+
+    #[test]
+    fn test_generate_disko_config_options() {
+        let options = DiskOptions {
+            compression: Some(6),
+            discard: true,
+            root_label: "nixos".to_string(),
+            ..DiskOptions::default()
+        };
+        let config = generate_disko_config(
+            "testhost",
+            "/dev/nvme0n1",
+            BootMode::Uefi,
+            None,
+            None,
+            &options,
+            None,
+        );
+        assert!(config.contains("  diskLayout.compression = \"zstd:6\";\n"));
+        assert!(config.contains("  diskLayout.discard = true;\n"));
+        assert!(config.contains("  diskLayout.rootLabel = \"nixos\";\n}\n"));
+        assert!(!config.contains("noatime"));
+        assert!(!config.contains("bootLabel"));
+
+        assert!(!config.contains("autodefrag"));
+
+        let off = DiskOptions { compression: None, autodefrag: true, ..options };
+        let bios =
+            generate_disko_config("oldbox", "/dev/sda", BootMode::Bios, None, None, &off, None);
+        assert!(bios.contains("diskLayout.compression = null;"));
+        assert!(bios.contains("diskLayout.autodefrag = true;"));
+        assert!(!bios.contains("rootLabel"));
+    }
+
+    #[test]
+    fn test_annotate_disk_identity() {
+        let disk = DiskIdentity {
+            serial: Some("S6B0NL0T123456".to_string()),
+            wwn: None,
+        };
+        let config = generate_zfs_disko_config("tank", "/dev/nvme0n1");
+        let annotated = annotate_disk_identity(&config, &disk);
+        let mut lines = annotated.lines();
+        assert_eq!(lines.next(), config.lines().next());
+        assert_eq!(
+            lines.next(),
+            Some("# Target disk: serial S6B0NL0T123456 (checked by forge install and update)")
+        );
+        assert!(annotated.ends_with(config.split_once('\n').unwrap().1));
+    }
+
+    #[test]
+    fn test_generate_zfs_disko_config() {
+        let config = generate_zfs_disko_config("tank", "/dev/nvme0n1");
+        assert!(config.contains("./zfs.nix"));
+        assert!(config.contains(r#"disko.devices.disk.main.device = "/dev/nvme0n1";"#));
+        assert!(!config.contains("diskLayout"));
+    }
+
+    #[test]
+    fn test_generate_monitors_conf() {
+        let monitors = vec![
+            MonitorInfo {
+                name: "DP-1".to_string(),
+                width: 3840,
+                height: 2160,
+                refresh: 165.0,
+                x: 0,
+                y: 0,
+                scale: 1.5,
+            },
+            MonitorInfo {
+                name: "HDMI-A-1".to_string(),
+                width: 1920,
+                height: 1080,
+                refresh: 60.0,
+                x: 2560,
+                y: 0,
+                scale: 1.0,
+            },
+        ];
+        let conf = generate_monitors_conf("kraken", &monitors);
+        assert!(conf.contains("monitor = DP-1,3840x2160@165.00,0x0,1.5\n"));
+        assert!(conf.contains("monitor = HDMI-A-1,1920x1080@60.00,2560x0,1\n"));
+        assert!(conf.contains("monitor = ,preferred,auto,auto"));
+        assert!(conf.contains("env = GDK_SCALE,1.5"));
     }
 
     #[test]
@@ -261,13 +1006,35 @@ mod tests {
                 size_bytes: 0,
                 model: None,
                 partitions: vec![],
+                free_bytes: 0,
+                by_id: None,
             },
+            storage: StorageKind::Unknown,
+            boot_mode: BootMode::Uefi,
+            initrd_modules: vec![],
+            kernel: KernelFlavor::Latest,
+            kernel_params: vec![],
+            modules: vec![],
+            theme: ThemePreset::Default,
+            filesystem: RootFilesystem::default(),
+            host_id: String::new(),
+            subvolume_layout: SubvolumeLayout::default(),
+            custom_subvolumes: Vec::new(),
+            swap: SwapMode::default(),
+            memory_gib: 16,
+            disk_options: DiskOptions::default(),
         };
 
         let result = generate_host_default_nix(&config);
         assert!(result.contains("testhost"));
         assert!(result.contains("nvidia"));
         assert!(result.contains("nvidia_modeset"));
+        assert!(result.contains("limine-plymouth.nix"));
+        assert!(result.contains("boot.kernelPackages = pkgs.linuxPackages_latest;"));
+        // Nothing detected, nothing emitted (hardware-configuration.nix covers it)
+        assert!(!result.contains("availableKernelModules"));
+        assert!(!result.contains("theming.preset"));
+        assert!(!result.contains("fstrim"));
     }
 
     #[test]
@@ -289,13 +1056,166 @@ mod tests {
                 size_bytes: 0,
                 model: None,
                 partitions: vec![],
+                free_bytes: 0,
+                by_id: None,
             },
+            storage: StorageKind::Ssd,
+            boot_mode: BootMode::Uefi,
+            initrd_modules: vec![],
+            kernel: KernelFlavor::Latest,
+            kernel_params: vec!["amd_pstate=active".to_string()],
+            modules: vec![],
+            theme: ThemePreset::Default,
+            filesystem: RootFilesystem::default(),
+            host_id: String::new(),
+            subvolume_layout: SubvolumeLayout::default(),
+            custom_subvolumes: Vec::new(),
+            swap: SwapMode::default(),
+            memory_gib: 16,
+            disk_options: DiskOptions::default(),
         };
 
         let result = generate_host_default_nix(&config);
         assert!(result.contains("laptop"));
         assert!(result.contains("amdgpu"));
+        assert!(result.contains(r#"boot.kernelParams = [ "amdgpu.ppfeaturemask=0xffffffff" "amd_pstate=active" ];"#));
+        assert_eq!(result.matches("boot.kernelParams").count(), 1);
         assert!(result.contains("tlp"));
         assert!(result.contains("power-profiles-daemon.enable = false"));
+        assert!(result.contains("services.fstrim.enable = true;"));
+    }
+
+    #[test]
+    fn test_generate_modules_config() {
+        assert!(generate_modules_config(&[], &GpuVendor::AMD).is_empty());
+
+        let config = generate_modules_config(
+            &[HostModule::Printing, HostModule::Scanning, HostModule::Android],
+            &GpuVendor::AMD,
+        );
+        assert!(config.contains("services.printing"));
+        assert!(config.contains("hardware.sane"));
+        // Shared attributes are only defined once
+        assert_eq!(config.matches("services.avahi").count(), 1);
+        assert_eq!(config.matches("environment.systemPackages").count(), 1);
+        assert!(config.contains("[ simple-scan android-tools ]"));
+        assert!(config.contains(r#"users.users.${username}.extraGroups = [ "lp" "scanner" ];"#));
+    }
+
+    #[test]
+    fn test_generate_virtualization_config() {
+        let config =
+            generate_modules_config(&[HostModule::Libvirt, HostModule::Podman], &GpuVendor::None);
+        assert!(config.contains("virtualisation.libvirtd"));
+        assert!(config.contains("virtualisation.docker.enable = lib.mkForce false;"));
+        assert!(config.contains("dockerCompat = true;"));
+        assert!(config.contains(r#"extraGroups = [ "kvm" "libvirtd" ];"#));
+
+        let config = generate_modules_config(&[HostModule::VirtualBox], &GpuVendor::None);
+        assert!(config.contains("virtualisation.virtualbox.host.enable = true;"));
+        assert!(config.contains(r#""vboxusers""#));
+    }
+
+    #[test]
+    fn test_generate_gaming_config() {
+        let amd = generate_modules_config(&[HostModule::Gaming], &GpuVendor::AMD);
+        assert!(amd.contains("gaming.enable = true;"));
+        assert!(amd.contains("amd_performance_level"));
+
+        let intel = generate_modules_config(&[HostModule::Gaming], &GpuVendor::Intel);
+        assert!(intel.contains("hardware.graphics.extraPackages32"));
+
+        let nvidia = generate_modules_config(&[HostModule::Gaming], &GpuVendor::NVIDIA);
+        assert!(nvidia.contains("gaming.enable = true;"));
+        assert!(!nvidia.contains("amd_performance_level"));
+        assert!(nvidia.contains(r#"extraGroups = [ "gamemode" ];"#));
+    }
+
+    #[test]
+    fn test_generate_bootstrap_config() {
+        let config = generate_modules_config(&[HostModule::Bootstrap], &GpuVendor::AMD);
+        assert!(config.contains("bootstrap.enable = true;"));
+        assert!(!config.contains("extraGroups"));
+    }
+
+    #[test]
+    fn test_generate_host_default_nix_bios() {
+        let config = NewHostConfig {
+            hostname: "oldbox".to_string(),
+            cpu: CpuInfo {
+                vendor: CpuVendor::Intel,
+                model_name: "Intel Core".to_string(),
+            },
+            gpu: GpuInfo {
+                vendor: GpuVendor::Intel,
+                model: None,
+            },
+            form_factor: FormFactor::Desktop,
+            disk: DiskInfo {
+                path: "/dev/sda".to_string(),
+                size: "256GB".to_string(),
+                size_bytes: 0,
+                model: None,
+                partitions: vec![],
+                free_bytes: 0,
+                by_id: None,
+            },
+            storage: StorageKind::Hdd,
+            boot_mode: BootMode::Bios,
+            initrd_modules: vec!["ahci".to_string(), "megaraid_sas".to_string()],
+            kernel: KernelFlavor::Lts,
+            kernel_params: vec!["mitigations=off".to_string()],
+            modules: vec![],
+            theme: ThemePreset::Simple,
+            filesystem: RootFilesystem::default(),
+            host_id: String::new(),
+            subvolume_layout: SubvolumeLayout::default(),
+            custom_subvolumes: Vec::new(),
+            swap: SwapMode::default(),
+            memory_gib: 16,
+            disk_options: DiskOptions::default(),
+        };
+
+        let result = generate_host_default_nix(&config);
+        assert!(result.contains("../../modules/boot/grub-plymouth.nix"));
+        assert!(!result.contains("limine"));
+        assert!(result.contains("boot.initrd.availableKernelModules"));
+        assert!(result.contains(r#""megaraid_sas""#));
+        assert!(result.contains("boot.kernelPackages = pkgs.linuxPackages;"));
+        assert!(result.contains(r#"boot.kernelParams = [ "mitigations=off" ];"#));
+        assert!(result.contains(r#"theming.preset = "simple";"#));
+        assert!(!result.contains("zfs"));
+        assert!(result.contains("services.fstrim.enable = false;"));
+
+        let zfs = NewHostConfig {
+            boot_mode: BootMode::Uefi,
+            filesystem: RootFilesystem::Zfs,
+            host_id: "8425e349".to_string(),
+            ..config
+        };
+        let result = generate_host_default_nix(&zfs);
+        assert!(result.contains(r#"boot.supportedFilesystems = [ "zfs" ];"#));
+        assert!(result.contains(r#"networking.hostId = "8425e349";"#));
+        assert!(!result.contains("zramSwap"));
+        assert!(!result.contains("fstrim"));
+
+        let no_swap = NewHostConfig {
+            swap: SwapMode::None,
+            ..zfs
+        };
+        let result = generate_host_default_nix(&no_swap);
+        assert!(result.contains("zramSwap.enable = lib.mkForce false;"));
+        assert_eq!(generate_swap_config(SwapMode::Partition).matches("zramSwap").count(), 1);
+
+        // Impermanence only applies to the btrfs layouts
+        assert!(!result.contains("impermanence"));
+        let ephemeral = NewHostConfig {
+            filesystem: RootFilesystem::Btrfs,
+            subvolume_layout: SubvolumeLayout::EphemeralSnapshot,
+            ..no_swap
+        };
+        let result = generate_host_default_nix(&ephemeral);
+        assert!(result.contains("    ../../modules/impermanence.nix\n  ];"));
+        assert!(result.contains(r#"environment.persistence."/persist" = {"#));
     }
 }
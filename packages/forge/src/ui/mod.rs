@@ -6,13 +6,14 @@ pub mod theme;
 pub mod widgets;
 
 use ratatui::{
-    layout::Rect,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     text::{Line, Span},
     widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
 
-use crate::app::{App, AppMode, AppProfileState, CreateHostState, InstallState, KeysState, PendingUpdates, UpdateState};
+use crate::app::{App, AppMode, AppProfileState, BootstrapState, BuildLogs, Changelog, CreateHostState, FleetState, InstallState, KeysState, OptimiseState, PendingUpdates, PipelineState, UpdateState};
+use crate::commands::{EditReview, SwitchPreview};
 
 /// Main draw function - dispatches to appropriate screen
 pub fn draw(frame: &mut Frame, app: &App) {
@@ -21,6 +22,9 @@ pub fn draw(frame: &mut Frame, app: &App) {
             screens::main_menu::draw(frame, *selected, app);
         }
         AppMode::Install(state) => match state {
+            InstallState::Preflight { checks } => {
+                screens::install::draw_preflight(frame, checks.as_deref(), app);
+            }
             InstallState::SelectHost { selected } => {
                 screens::install::draw_host_selection(frame, *selected, &app.hosts, app);
             }
@@ -28,8 +32,31 @@ pub fn draw(frame: &mut Frame, app: &App) {
                 host,
                 disks,
                 selected,
+                disk_mode,
+                marked,
+                error,
+            } => {
+                screens::install::draw_disk_selection(
+                    frame,
+                    host,
+                    disks,
+                    *selected,
+                    *disk_mode,
+                    marked,
+                    error.as_deref(),
+                    app,
+                );
+            }
+            InstallState::AssignRoles {
+                host,
+                disks,
+                roles,
+                selected,
+                error,
             } => {
-                screens::install::draw_disk_selection(frame, host, disks, *selected, app);
+                screens::install::draw_assign_roles(
+                    frame, host, disks, roles, *selected, error.as_deref(),
+                );
             }
             InstallState::EnterCredentials {
                 host,
@@ -37,13 +64,100 @@ pub fn draw(frame: &mut Frame, app: &App) {
                 credentials,
                 active_field,
                 error,
+                ..
             } => {
                 screens::install::draw_enter_credentials(
                     frame, host, disk, credentials, active_field, error.as_deref(), app,
                 );
             }
-            InstallState::Overview { host, disk, input, hardware_config, .. } => {
-                screens::install::draw_overview(frame, host, disk, input, hardware_config.as_ref(), app);
+            InstallState::ConfirmExistingData {
+                host,
+                disk,
+                typed,
+                error,
+            } => {
+                screens::install::draw_confirm_existing_data(
+                    frame, host, disk, typed, error.as_deref(),
+                );
+            }
+            InstallState::EditPartitions {
+                host,
+                disk,
+                inputs,
+                active_field,
+                error,
+                ..
+            } => {
+                screens::install::draw_edit_partitions(
+                    frame, host, disk, inputs, *active_field, error.as_deref(),
+                );
+            }
+            InstallState::SelectLocale {
+                host,
+                locale,
+                active_field,
+                error,
+                ..
+            } => {
+                screens::install::draw_select_locale(
+                    frame, host, locale, *active_field, error.as_deref(),
+                );
+            }
+            InstallState::Overview {
+                host,
+                disk,
+                disk_mode,
+                extra_disks,
+                input,
+                credentials,
+                remote,
+                hardware_config,
+                disk_health,
+                disk_drift,
+                secure_boot_support,
+                secure_boot,
+                erase_plan,
+                secure_erase,
+                erase_confirm,
+                ..
+            } => {
+                screens::install::draw_overview(
+                    frame,
+                    host,
+                    disk,
+                    *disk_mode,
+                    extra_disks,
+                    input,
+                    hardware_config.as_deref(),
+                    disk_health,
+                    disk_drift.as_deref(),
+                    credentials,
+                    remote.as_deref(),
+                    *secure_boot_support,
+                    *secure_boot,
+                    erase_plan,
+                    *secure_erase,
+                    erase_confirm.as_deref(),
+                    app,
+                );
+            }
+            InstallState::NetworkSetup {
+                networks,
+                selected,
+                passphrase,
+                connecting,
+                error,
+                ..
+            } => {
+                screens::install::draw_network_setup(
+                    frame,
+                    networks.as_deref(),
+                    *selected,
+                    passphrase.as_deref(),
+                    *connecting,
+                    error.as_deref(),
+                    app,
+                );
             }
             InstallState::Running {
                 host,
@@ -60,26 +174,53 @@ pub fn draw(frame: &mut Frame, app: &App) {
                 success,
                 output,
                 scroll_offset,
+                retry,
+                ..
             } => {
                 let output_vec: Vec<String> = output.iter().cloned().collect();
-                screens::install::draw_complete(frame, *success, &output_vec, *scroll_offset, app);
+                screens::install::draw_complete(
+                    frame,
+                    *success,
+                    &output_vec,
+                    *scroll_offset,
+                    retry.is_some(),
+                    app,
+                );
             }
         },
         AppMode::Update(state) => match state {
+            UpdateState::PickOverride(picker) => {
+                screens::update::draw_override_picker(frame, picker);
+            }
+            UpdateState::PickActivation {
+                selected,
+                last_scheduled,
+            } => {
+                let last = last_scheduled.as_deref();
+                screens::update::draw_activation_picker(frame, *selected, last);
+            }
             UpdateState::Running {
-                steps, output, ..
+                steps,
+                output,
+                overrides,
+                ..
             } => {
                 let output_vec: Vec<String> = output.iter().cloned().collect();
-                screens::update::draw_running(frame, steps, &output_vec, false, None, app);
+                screens::update::draw_running(
+                    frame, steps, &output_vec, false, None, overrides, app,
+                );
             }
             UpdateState::Complete {
                 steps,
                 output,
                 scroll_offset,
+                overrides,
                 success: _,
             } => {
                 let output_vec: Vec<String> = output.iter().cloned().collect();
-                screens::update::draw_running(frame, steps, &output_vec, true, *scroll_offset, app);
+                screens::update::draw_running(
+                    frame, steps, &output_vec, true, *scroll_offset, overrides, app,
+                );
             }
         },
         AppMode::Apps(state) => match state {
@@ -106,6 +247,11 @@ pub fn draw(frame: &mut Frame, app: &App) {
             }
         },
         AppMode::Keys(state) => match state {
+            KeysState::EnterPassphrase {
+                operation, prompt, ..
+            } => {
+                screens::keys::draw_passphrase(frame, operation, prompt, app);
+            }
             KeysState::Running {
                 operation, output, ..
             } => {
@@ -116,9 +262,170 @@ pub fn draw(frame: &mut Frame, app: &App) {
                 success,
                 output,
                 scroll_offset,
+                offer_github,
+            } => {
+                let output_vec: Vec<String> = output.iter().cloned().collect();
+                screens::keys::draw_complete(
+                    frame,
+                    *success,
+                    &output_vec,
+                    *scroll_offset,
+                    *offer_github,
+                    app,
+                );
+            }
+        },
+        AppMode::Registry(state) => {
+            screens::registry::draw(frame, state, app);
+        }
+        AppMode::Bisect(state) => {
+            screens::bisect::draw(frame, state, app);
+        }
+        AppMode::Optimise(state) => match state {
+            OptimiseState::Running { progress, output, gc } => {
+                let output_vec: Vec<String> = output.iter().cloned().collect();
+                screens::optimise::draw(frame, progress, gc.as_ref(), &output_vec, None, None, app);
+            }
+            OptimiseState::Complete {
+                success,
+                progress,
+                output,
+                scroll_offset,
+                gc,
             } => {
                 let output_vec: Vec<String> = output.iter().cloned().collect();
-                screens::keys::draw_complete(frame, *success, &output_vec, *scroll_offset, app);
+                screens::optimise::draw(
+                    frame,
+                    progress,
+                    gc.as_ref(),
+                    &output_vec,
+                    Some(*success),
+                    *scroll_offset,
+                    app,
+                );
+            }
+        },
+        AppMode::Gc(state) => {
+            screens::gc::draw(frame, state, app);
+        }
+        AppMode::NixConf(state) => {
+            screens::nix_conf::draw(frame, state, app);
+        }
+        AppMode::Parallelism(state) => {
+            screens::parallelism::draw(frame, state);
+        }
+        AppMode::Rollback(state) => {
+            screens::rollback::draw(frame, state, app);
+        }
+        AppMode::Generations(state) => {
+            screens::generations::draw(frame, state, app);
+        }
+        AppMode::Channels(state) => {
+            screens::channels::draw(frame, state, app);
+        }
+        AppMode::Services(state) => {
+            screens::services::draw(frame, state, app);
+        }
+        AppMode::DevShells(state) => {
+            screens::devshells::draw(frame, state, app);
+        }
+        AppMode::Pipeline(state) => match state {
+            PipelineState::Menu {
+                pipelines,
+                selected,
+            } => {
+                screens::pipeline::draw_menu(frame, pipelines, *selected, app);
+            }
+            PipelineState::Running {
+                pipeline,
+                steps,
+                output,
+            } => {
+                let output_vec: Vec<String> = output.iter().cloned().collect();
+                screens::pipeline::draw_running(
+                    frame,
+                    &pipeline.name,
+                    steps,
+                    &output_vec,
+                    None,
+                    None,
+                    app,
+                );
+            }
+            PipelineState::Complete {
+                success,
+                title,
+                steps,
+                output,
+                scroll_offset,
+            } => {
+                let output_vec: Vec<String> = output.iter().cloned().collect();
+                screens::pipeline::draw_running(
+                    frame,
+                    title,
+                    steps,
+                    &output_vec,
+                    Some(*success),
+                    *scroll_offset,
+                    app,
+                );
+            }
+        },
+        AppMode::Fleet(state) => match state {
+            FleetState::Status { report } => {
+                screens::fleet::draw_status(frame, report.as_ref(), app);
+            }
+            FleetState::Running {
+                steps,
+                stages,
+                output,
+                ..
+            } => {
+                let output_vec: Vec<String> = output.iter().cloned().collect();
+                screens::fleet::draw_running(frame, steps, stages, &output_vec, None, None, app);
+            }
+            FleetState::Complete {
+                success,
+                steps,
+                stages,
+                output,
+                scroll_offset,
+            } => {
+                let output_vec: Vec<String> = output.iter().cloned().collect();
+                screens::fleet::draw_running(
+                    frame,
+                    steps,
+                    stages,
+                    &output_vec,
+                    Some(*success),
+                    *scroll_offset,
+                    app,
+                );
+            }
+        },
+        AppMode::Bootstrap(state) => match state {
+            BootstrapState::Prompt { selected } => {
+                screens::bootstrap::draw_prompt(frame, *selected, app);
+            }
+            BootstrapState::Running { steps, output, .. } => {
+                let output_vec: Vec<String> = output.iter().cloned().collect();
+                screens::bootstrap::draw_running(frame, steps, &output_vec, None, None, app);
+            }
+            BootstrapState::Complete {
+                success,
+                steps,
+                output,
+                scroll_offset,
+            } => {
+                let output_vec: Vec<String> = output.iter().cloned().collect();
+                screens::bootstrap::draw_running(
+                    frame,
+                    steps,
+                    &output_vec,
+                    Some(*success),
+                    *scroll_offset,
+                    app,
+                );
             }
         },
         AppMode::CreateHost(state) => match state {
@@ -176,8 +483,62 @@ pub fn draw(frame: &mut Frame, app: &App) {
                     frame, cpu, gpu, form_factor, disk, input, error.as_deref(), app,
                 );
             }
-            CreateHostState::Review { config } => {
-                screens::create_host::draw_review(frame, config, app);
+            CreateHostState::SelectFilesystem {
+                config,
+                selected,
+                error,
+            } => {
+                screens::create_host::draw_select_filesystem(
+                    frame, config, *selected, error.as_deref(), app,
+                );
+            }
+            CreateHostState::SelectSwap {
+                config,
+                selected,
+                error,
+            } => {
+                screens::create_host::draw_select_swap(
+                    frame, config, *selected, error.as_deref(), app,
+                );
+            }
+            CreateHostState::SelectLayout {
+                config,
+                selected,
+                custom,
+                error,
+            } => {
+                screens::create_host::draw_select_layout(
+                    frame, config, *selected, custom, error.as_deref(), app,
+                );
+            }
+            CreateHostState::SelectKernel {
+                config,
+                selected,
+                params,
+                error,
+            } => {
+                screens::create_host::draw_select_kernel(
+                    frame, config, *selected, params, error.as_deref(), app,
+                );
+            }
+            CreateHostState::SelectModules { config, selected } => {
+                screens::create_host::draw_select_modules(frame, config, *selected, app);
+            }
+            CreateHostState::SelectTheme { config, selected } => {
+                screens::create_host::draw_select_theme(frame, config, *selected, app);
+            }
+            CreateHostState::Review { config, lint } => {
+                screens::create_host::draw_review(frame, config, *lint, app);
+            }
+            CreateHostState::EditDiskOptions {
+                config,
+                selected,
+                error,
+                ..
+            } => {
+                screens::create_host::draw_disk_options(
+                    frame, config, *selected, error.as_deref(), app,
+                );
             }
             CreateHostState::Generating {
                 config,
@@ -188,8 +549,8 @@ pub fn draw(frame: &mut Frame, app: &App) {
                 let output_vec: Vec<String> = output.iter().cloned().collect();
                 screens::create_host::draw_generating(frame, config, steps, &output_vec, app);
             }
-            CreateHostState::Complete { success, config } => {
-                screens::create_host::draw_complete(frame, *success, config, app);
+            CreateHostState::Complete { success, config, error } => {
+                screens::create_host::draw_complete(frame, *success, config, error.as_deref(), app);
             }
         },
         AppMode::Quit => {}
@@ -197,11 +558,26 @@ pub fn draw(frame: &mut Frame, app: &App) {
 
     // Render update dialog or commit list on top of any screen (but below exit confirm)
     if !app.show_exit_confirm {
-        if app.pending_updates.viewing_commits {
+        if app.build_logs.viewing.is_some() {
+            draw_build_log(frame, &app.build_logs);
+        } else if app.changelog.viewing.is_some() {
+            draw_changelog(frame, &app.changelog);
+        } else if app.pending_updates.viewing_commits {
             draw_commit_list(frame, &app.pending_updates);
         } else if app.pending_updates.has_updates() {
             draw_update_dialog(frame, &app.pending_updates);
         }
+        // Answered before the update dialog, so drawn over it
+        if let Some(config) = app.host_draft.as_ref().and_then(|d| d.draft_config()) {
+            draw_resume_draft(frame, &config.hostname);
+        }
+        // A command is waiting on it, so it goes over everything else
+        if let Some(review) = &app.edit_review {
+            draw_edit_review(frame, review, app.edit_review_scroll);
+        }
+        if let Some(preview) = &app.switch_preview {
+            draw_switch_preview(frame, preview, app.switch_preview_scroll);
+        }
     }
 
     // Render exit confirmation popup on top of any screen
@@ -245,6 +621,278 @@ fn draw_exit_confirm(frame: &mut Frame) {
     frame.render_widget(content, popup_area);
 }
 
+/// Draw the prompt to resume a create-host wizard left unfinished
+fn draw_resume_draft(frame: &mut Frame, hostname: &str) {
+    let area = frame.area();
+    let question = format!("Resume host creation for '{}'?", hostname);
+    let popup_width = (question.chars().count() as u16 + 6).max(48).min(area.width);
+    let popup_height = 7;
+    let x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(x, y, popup_width, popup_height);
+
+    frame.render_widget(Clear, popup_area);
+
+    let content = Paragraph::new(vec![
+        Line::from(""),
+        Line::from(Span::styled(question, theme::text())),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("Enter", theme::key_hint()),
+            Span::styled("] Resume  [", theme::dim()),
+            Span::styled("d", theme::key_hint()),
+            Span::styled("] Discard  [", theme::dim()),
+            Span::styled("Esc", theme::key_hint()),
+            Span::styled("] Later", theme::dim()),
+        ]),
+    ])
+    .alignment(ratatui::layout::Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active())
+            .title(Span::styled(" Resume ", theme::title())),
+    );
+    frame.render_widget(content, popup_area);
+}
+
+/// Draw the saved `nix log` of a failed derivation
+fn draw_build_log(frame: &mut Frame, logs: &BuildLogs) {
+    let Some(log) = logs.viewed() else {
+        return;
+    };
+    let popup_area = layout::centered_rect(90, 85, frame.area());
+    frame.render_widget(Clear, popup_area);
+
+    let visible = (popup_area.height as usize).saturating_sub(6);
+    let mut lines = vec![
+        Line::from(Span::styled(log.path.display().to_string(), theme::dim())),
+        Line::from(""),
+    ];
+    if log.lines.is_empty() {
+        lines.push(Line::from(Span::styled("(empty log)", theme::dim())));
+    }
+    lines.extend(
+        log.lines
+            .iter()
+            .skip(logs.scroll)
+            .take(visible)
+            .map(|l| Line::from(Span::styled(l.as_str(), theme::text()))),
+    );
+    while lines.len() < visible + 3 {
+        lines.push(Line::from(""));
+    }
+
+    let mut footer = vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓/PgUp/PgDn", theme::key_hint()),
+        Span::styled("] Scroll  [", theme::dim()),
+    ];
+    if logs.saved.len() > 1 {
+        footer.push(Span::styled("Tab", theme::key_hint()));
+        footer.push(Span::styled("] Next log  [", theme::dim()));
+    }
+    footer.push(Span::styled("Esc", theme::key_hint()));
+    footer.push(Span::styled("] Close", theme::dim()));
+    lines.push(Line::from(footer));
+
+    let title = format!(
+        " Failed build: {} ({}/{}) ",
+        log.name(),
+        logs.viewing.unwrap_or(0) + 1,
+        logs.saved.len()
+    );
+    let content = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active())
+            .title(Span::styled(title, theme::title())),
+    );
+    frame.render_widget(content, popup_area);
+}
+
+/// Draw the changelog viewer: inputs on the left, the selected one's changelog on the right
+fn draw_changelog(frame: &mut Frame, changelog: &Changelog) {
+    let Some(viewing) = changelog.viewing else {
+        return;
+    };
+    let popup_area = layout::centered_rect(90, 85, frame.area());
+    frame.render_widget(Clear, popup_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(5), Constraint::Length(1)])
+        .split(popup_area);
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(28), Constraint::Min(20)])
+        .split(chunks[0]);
+
+    let inputs: Vec<Line> = changelog
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(i, input)| {
+            let (prefix, style) = if i == viewing {
+                ("> ", theme::selected())
+            } else {
+                ("  ", theme::text())
+            };
+            let mut spans = vec![Span::styled(format!("{}{}", prefix, input.name), style)];
+            if !input.releases.is_empty() {
+                spans.push(Span::styled(format!(" ({})", input.releases.len()), theme::info()));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    let list = Paragraph::new(inputs).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(" Inputs "),
+    );
+    frame.render_widget(list, columns[0]);
+
+    let visible = (columns[1].height as usize).saturating_sub(2);
+    let lines: Vec<Line> = changelog
+        .viewed_lines()
+        .into_iter()
+        .skip(changelog.scroll)
+        .take(visible)
+        .map(|l| {
+            // Section headings and the input's own lines are unindented
+            let style = if l.starts_with(' ') { theme::text() } else { theme::info() };
+            Line::from(Span::styled(l, style))
+        })
+        .collect();
+    let title = format!(" Changelog: {} ", changelog.inputs[viewing].name);
+    let content = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active())
+            .title(Span::styled(title, theme::title())),
+    );
+    frame.render_widget(content, columns[1]);
+
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓/PgUp/PgDn", theme::key_hint()),
+        Span::styled("] Scroll  [", theme::dim()),
+        Span::styled("Tab/←→", theme::key_hint()),
+        Span::styled("] Input  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Close", theme::dim()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[1]);
+}
+
+/// Draw the diff of a flake.nix edit waiting for approval
+fn draw_edit_review(frame: &mut Frame, review: &EditReview, scroll: usize) {
+    let popup_area = layout::centered_rect(90, 85, frame.area());
+    frame.render_widget(Clear, popup_area);
+
+    let visible = (popup_area.height as usize).saturating_sub(6);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("forge wants to {} in {}:", review.reason, review.file),
+            theme::text(),
+        )),
+        Line::from(""),
+    ];
+    lines.extend(review.diff.lines().skip(scroll).take(visible).map(|l| {
+        let style = if l.starts_with("+++") || l.starts_with("---") {
+            theme::dim()
+        } else if l.starts_with('+') {
+            theme::success()
+        } else if l.starts_with('-') {
+            theme::error()
+        } else if l.starts_with("@@") {
+            theme::key_hint()
+        } else {
+            theme::text()
+        };
+        Line::from(Span::styled(l, style))
+    }));
+    while lines.len() < visible + 3 {
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓/PgUp/PgDn", theme::key_hint()),
+        Span::styled("] Scroll  [", theme::dim()),
+        Span::styled("Enter/Y", theme::key_hint()),
+        Span::styled("] Apply  [", theme::dim()),
+        Span::styled("Esc/N", theme::key_hint()),
+        Span::styled("] Reject", theme::dim()),
+    ]));
+
+    let content = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active())
+            .title(Span::styled(format!(" Review {} ", review.file), theme::title())),
+    );
+    frame.render_widget(content, popup_area);
+}
+
+/// Draw the nvd diff of a built system waiting for the go-ahead to switch
+fn draw_switch_preview(frame: &mut Frame, preview: &SwitchPreview, scroll: usize) {
+    let popup_area = layout::centered_rect(90, 85, frame.area());
+    frame.render_widget(Clear, popup_area);
+
+    let visible = (popup_area.height as usize).saturating_sub(7);
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("{} package(s) change version against the running system", preview.updated),
+            theme::text(),
+        )),
+        Line::from(Span::styled(
+            format!("Closure: {}", preview.closure_summary.as_deref().unwrap_or("unknown")),
+            theme::dim(),
+        )),
+        Line::from(""),
+    ];
+    if preview.diff.is_empty() {
+        lines.push(Line::from(Span::styled("nvd could not diff the systems", theme::warning())));
+    }
+    lines.extend(preview.diff.lines().skip(scroll).take(visible).map(|l| {
+        let style = if l.starts_with("[U") {
+            theme::key_hint()
+        } else if l.starts_with("[A") {
+            theme::success()
+        } else if l.starts_with("[R") {
+            theme::error()
+        } else if l.starts_with("<<<") || l.starts_with(">>>") {
+            theme::dim()
+        } else {
+            theme::text()
+        };
+        Line::from(Span::styled(l, style))
+    }));
+    while lines.len() < visible + 4 {
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓/PgUp/PgDn", theme::key_hint()),
+        Span::styled("] Scroll  [", theme::dim()),
+        Span::styled("Enter/Y", theme::key_hint()),
+        Span::styled(format!("] nixos-rebuild {}  [", preview.activation.verb()), theme::dim()),
+        Span::styled("Esc/N", theme::key_hint()),
+        Span::styled("] Keep the running system", theme::dim()),
+    ]));
+
+    let content = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active())
+            .title(Span::styled(" Use the new system? ", theme::title())),
+    );
+    frame.render_widget(content, popup_area);
+}
+
 /// Draw the combined update available dialog centered on screen
 fn draw_update_dialog(frame: &mut Frame, updates: &PendingUpdates) {
     let area = frame.area();
@@ -252,7 +900,8 @@ fn draw_update_dialog(frame: &mut Frame, updates: &PendingUpdates) {
 
     // Calculate dialog size based on content
     let popup_width = 55;
-    let popup_height = if both { 13 } else { 10 };
+    let flake_line = !updates.flake_inputs.is_empty();
+    let popup_height = if both { 13 } else { 10 } + u16::from(flake_line);
     let x = area.x + (area.width.saturating_sub(popup_width)) / 2;
     let y = area.y + (area.height.saturating_sub(popup_height)) / 2;
     let popup_area = Rect::new(x, y, popup_width, popup_height);
@@ -281,6 +930,15 @@ fn draw_update_dialog(frame: &mut Frame, updates: &PendingUpdates) {
         )));
     }
 
+    if flake_line {
+        let max_len = (popup_width as usize).saturating_sub(4);
+        let mut inputs = format!("Flake inputs: {}", updates.flake_inputs.join(", "));
+        if inputs.chars().count() > max_len {
+            inputs = format!("{}...", inputs.chars().take(max_len.saturating_sub(3)).collect::<String>());
+        }
+        lines.push(Line::from(Span::styled(inputs, theme::dim())));
+    }
+
     lines.push(Line::from(""));
 
     // Build menu options in order:
@@ -378,6 +1036,17 @@ fn draw_commit_list(frame: &mut Frame, updates: &PendingUpdates) {
     // Build content lines
     let mut lines = vec![Line::from("")];
 
+    // Show outdated flake inputs above the commits
+    for input in &updates.flake_inputs {
+        lines.push(Line::from(vec![
+            Span::styled("  Flake input ", theme::dim()),
+            Span::styled(input.as_str(), theme::info()),
+        ]));
+    }
+    if !updates.flake_inputs.is_empty() {
+        lines.push(Line::from(""));
+    }
+
     // Show commit count
     let commit_count = updates.commits.len();
     lines.push(Line::from(Span::styled(
@@ -386,8 +1055,10 @@ fn draw_commit_list(frame: &mut Frame, updates: &PendingUpdates) {
     )));
     lines.push(Line::from(""));
 
-    // Calculate visible area for commits (popup height - header - footer)
-    let visible_commits = (popup_height as usize).saturating_sub(8);
+    // Calculate visible area for commits (popup height - header - flake inputs - footer)
+    let visible_commits = (popup_height as usize)
+        .saturating_sub(8)
+        .saturating_sub(lines.len().saturating_sub(3));
 
     // Show commits with scroll
     let start = updates.commit_scroll;
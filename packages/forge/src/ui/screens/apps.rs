@@ -71,6 +71,7 @@ pub fn draw_running(frame: &mut Frame, operation: &AppOp, output: &[String], app
     let title = match operation {
         AppOp::Backup => " Backing Up App Profiles ",
         AppOp::Restore => " Restoring App Profiles ",
+        AppOp::Init { .. } => " Setting Up Backup Repo ",
     };
     let header = Paragraph::new(Line::from(Span::styled(title, theme::title())))
         .alignment(Alignment::Center)
@@ -83,7 +84,7 @@ pub fn draw_running(frame: &mut Frame, operation: &AppOp, output: &[String], app
 
     // Output with spinner
     let spinner_char = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏']
-        [app.spinner_state % 10];
+        [app.spinner_frame() % 10];
     let title = format!(" {} Running... ", spinner_char);
     let log = LogView::new(output).title(&title);
     frame.render_widget(log, chunks[1]);
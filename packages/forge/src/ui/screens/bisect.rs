@@ -0,0 +1,165 @@
+//! Bisect screen (narrowing a flake input regression down to one commit)
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::app::{App, BisectState};
+use crate::system::bisect::BisectMode;
+use crate::ui::screens::build_log_hint;
+use crate::ui::theme;
+use crate::ui::widgets::{LogView, Spinner};
+
+pub fn draw(frame: &mut Frame, state: &BisectState, app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(6),
+            Constraint::Min(8),
+            Constraint::Length(2),
+        ])
+        .split(area);
+
+    // Header
+    let culprit = match &state.bisection {
+        Some(Ok(bisection)) if state.finished() => bisection.culprit(),
+        _ => None,
+    };
+    let (title, style) = match &state.bisection {
+        Some(Err(_)) => (" Bisect Failed ".to_string(), theme::error()),
+        Some(Ok(_)) if culprit.is_some() => (" First Bad Commit Found ".to_string(), theme::success()),
+        Some(Ok(_)) if state.finished() => (" Bisect Inconclusive ".to_string(), theme::warning()),
+        _ => (format!(" Bisecting {} ", state.input), theme::title()),
+    };
+    let header = Paragraph::new(Line::from(Span::styled(title, style)))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    // Progress and the commit under test
+    let spinner_char = Spinner::new(app.spinner_frame()).char();
+    let lines = match &state.bisection {
+        None => vec![Line::from(Span::styled(
+            format!(
+                "{} Loading commits {}...{} from GitHub",
+                spinner_char, state.good, state.bad
+            ),
+            theme::dim(),
+        ))],
+        Some(Err(e)) => vec![Line::from(Span::styled(e.clone(), theme::error()))],
+        Some(Ok(bisection)) => {
+            let (good, bad) = bisection.bounds();
+            let mut lines = vec![Line::from(vec![
+                Span::styled("good ", theme::dim()),
+                Span::styled(good.short_sha().to_string(), theme::success()),
+                Span::styled(" .. bad ", theme::dim()),
+                Span::styled(bad.short_sha().to_string(), theme::error()),
+                Span::styled(
+                    format!(
+                        "    {} candidates, ~{} steps left",
+                        bisection.candidates().len(),
+                        bisection.steps_left()
+                    ),
+                    theme::dim(),
+                ),
+            ])];
+            match (culprit, state.testing) {
+                (Some(commit), _) => {
+                    lines.push(Line::from(vec![
+                        Span::styled(commit.short_sha().to_string(), theme::error()),
+                        Span::styled(format!(" {}", commit.message), theme::text()),
+                    ]));
+                    lines.push(Line::from(Span::styled(
+                        bisection.commit_url(commit),
+                        theme::info(),
+                    )));
+                }
+                (None, Some(index)) => {
+                    let commit = &bisection.commits[index];
+                    lines.push(Line::from(vec![
+                        Span::styled("Testing ", theme::dim()),
+                        Span::styled(commit.short_sha().to_string(), theme::info()),
+                        Span::styled(format!(" {}", commit.message), theme::text()),
+                    ]));
+                    lines.push(match state.built {
+                        None => Line::from(Span::styled(
+                            format!("{} nixos-rebuild {}...", spinner_char, state.mode.action()),
+                            theme::dim(),
+                        )),
+                        Some(true) => Line::from(Span::styled(
+                            "Rebuild succeeded - does the regression show up?",
+                            theme::warning(),
+                        )),
+                        Some(false) => Line::from(Span::styled(
+                            "Rebuild failed - bad if this is the regression, otherwise skip",
+                            theme::warning(),
+                        )),
+                    });
+                }
+                (None, None) => lines.push(Line::from(Span::styled(
+                    "Skipped commits left more than one candidate - see the range above",
+                    theme::warning(),
+                ))),
+            }
+            if state.finished() && state.mode == BisectMode::Test {
+                lines.push(Line::from(Span::styled(
+                    "Reboot or rebuild to return to your configuration",
+                    theme::dim(),
+                )));
+            }
+            lines
+        }
+    };
+    let status = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border()),
+    );
+    frame.render_widget(status, chunks[1]);
+
+    let output: Vec<String> = state.output.iter().cloned().collect();
+    frame.render_widget(LogView::new(&output).title(" Output "), chunks[2]);
+
+    // Footer
+    let footer = if state.finished() {
+        let mut hints = vec![Span::styled("[", theme::dim())];
+        hints.extend(build_log_hint(app));
+        hints.extend([
+            Span::styled("Enter", theme::key_hint()),
+            Span::styled("] Done  [", theme::dim()),
+            Span::styled("q", theme::key_hint()),
+            Span::styled("] Quit", theme::dim()),
+        ]);
+        Line::from(hints)
+    } else if state.awaiting_verdict() {
+        let mut hints = vec![Span::styled("[", theme::dim())];
+        hints.extend(build_log_hint(app));
+        hints.extend([
+            Span::styled("g", theme::key_hint()),
+            Span::styled("] Good  [", theme::dim()),
+            Span::styled("b", theme::key_hint()),
+            Span::styled("] Bad  [", theme::dim()),
+            Span::styled("s", theme::key_hint()),
+            Span::styled("] Skip  [", theme::dim()),
+            Span::styled("Esc", theme::key_hint()),
+            Span::styled("] Abort", theme::dim()),
+        ]);
+        Line::from(hints)
+    } else {
+        Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("Ctrl+C", theme::key_hint()),
+            Span::styled("] Cancel", theme::dim()),
+        ])
+    };
+    frame.render_widget(Paragraph::new(footer).alignment(Alignment::Center), chunks[3]);
+}
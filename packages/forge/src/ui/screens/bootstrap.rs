@@ -0,0 +1,161 @@
+//! First-boot bootstrap screens (keys restore, then app restore)
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::app::{App, StepStatus, BOOTSTRAP_MENU_ITEMS};
+use crate::ui::layout::{centered_rect, progress_layout};
+use crate::ui::theme;
+use crate::ui::widgets::{LogView, MenuList, ProgressSteps};
+
+/// Draw the first-login prompt
+pub fn draw_prompt(frame: &mut Frame, selected: usize, _app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(9),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
+        .split(centered_rect(60, 80, area));
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(
+        " Welcome - Restore Keys & Apps ",
+        theme::title(),
+    )))
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active()),
+    );
+    frame.render_widget(header, chunks[0]);
+
+    // What will happen
+    let text = vec![
+        Line::from(Span::styled(
+            "This machine was installed without your keys and app profiles.",
+            theme::text(),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  1. ", theme::dim()),
+            Span::styled("forge keys restore", theme::info()),
+            Span::styled("  SSH and age keys from the encrypted backup", theme::dim()),
+        ]),
+        Line::from(vec![
+            Span::styled("  2. ", theme::dim()),
+            Span::styled("forge apps restore", theme::info()),
+            Span::styled("  browser, Termius and other app profiles", theme::dim()),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Sign in to 1Password first - it unlocks the backup passphrase.",
+            theme::warning(),
+        )),
+    ];
+    let info = Paragraph::new(text)
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border())
+                .title(" First Login "),
+        );
+    frame.render_widget(info, chunks[1]);
+
+    // Menu
+    let menu = MenuList::new(BOOTSTRAP_MENU_ITEMS.to_vec(), selected);
+    frame.render_widget(menu, chunks[2]);
+
+    // Footer
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓", theme::key_hint()),
+        Span::styled("] Navigate  [", theme::dim()),
+        Span::styled("Enter", theme::key_hint()),
+        Span::styled("] Select  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Later", theme::dim()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[3]);
+}
+
+/// Draw running/complete bootstrap screen
+pub fn draw_running(
+    frame: &mut Frame,
+    steps: &[StepStatus],
+    output: &[String],
+    complete: Option<bool>,
+    scroll_offset: Option<usize>,
+    app: &App,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(2),
+        ])
+        .split(area);
+
+    // Header
+    let (title, style) = match complete {
+        None => (" Restoring Keys & Apps ", theme::title()),
+        Some(true) => (" Bootstrap Complete ", theme::success()),
+        Some(false) => (" Bootstrap Failed ", theme::error()),
+    };
+    let header = Paragraph::new(Line::from(Span::styled(title, style)))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    // Progress and output
+    let (steps_area, output_area) = progress_layout(chunks[1]);
+
+    let progress = ProgressSteps::new(steps, app.spinner_frame()).title(" Progress ");
+    frame.render_widget(progress, steps_area);
+
+    let mut log = LogView::new(output).title(" Output ");
+    if let Some(offset) = scroll_offset {
+        log = log.scroll_offset(offset);
+    }
+    frame.render_widget(log, output_area);
+
+    // Footer
+    let footer = match complete {
+        Some(success) => {
+            let mut spans = vec![
+                Span::styled("[", theme::dim()),
+                Span::styled("↑↓", theme::key_hint()),
+                Span::styled("] Scroll  [", theme::dim()),
+                Span::styled("Enter", theme::key_hint()),
+                Span::styled("] Close", theme::dim()),
+            ];
+            if !success {
+                spans.push(Span::styled("  - asks again at next login", theme::dim()));
+            }
+            Paragraph::new(Line::from(spans))
+        }
+        None => Paragraph::new(Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("Ctrl+C", theme::key_hint()),
+            Span::styled("] Cancel", theme::dim()),
+        ])),
+    }
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[2]);
+}
@@ -0,0 +1,127 @@
+//! Update channel screen: which nixpkgs each host follows
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::app::{App, ChannelsState};
+use crate::system::channels::{Channel, HostChannel};
+use crate::ui::layout::centered_rect;
+use crate::ui::theme;
+use crate::ui::widgets::Spinner;
+
+pub fn draw(frame: &mut Frame, state: &ChannelsState, app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(6),
+            Constraint::Length(2),
+            Constraint::Length(2),
+        ])
+        .split(centered_rect(70, 70, area));
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(" Update Channels ", theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    // Host table
+    let stable = match &state.stable {
+        Some(release) => format!(" flake.nix - stable is nixos-{} ", release),
+        None => " flake.nix - no host on stable ".to_string(),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme::border())
+        .title(Span::styled(stable, theme::title()));
+    match &state.hosts {
+        None => {
+            let spinner_char = Spinner::new(app.spinner_frame()).char();
+            let loading = Paragraph::new(Line::from(Span::styled(
+                format!("  {} Working...", spinner_char),
+                theme::dim(),
+            )))
+            .block(block);
+            frame.render_widget(loading, chunks[1]);
+        }
+        Some(Err(e)) => {
+            let error = Paragraph::new(Line::from(Span::styled(format!("  ✗ {}", e), theme::error())))
+                .block(block);
+            frame.render_widget(error, chunks[1]);
+        }
+        Some(Ok(hosts)) => {
+            let header = Row::new(vec!["", "Host", "Channel", "nixpkgs input"])
+                .style(theme::title())
+                .bottom_margin(1);
+            let rows: Vec<Row> = hosts
+                .iter()
+                .enumerate()
+                .map(|(i, host)| host_row(host, i == state.selected))
+                .collect();
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(2),
+                    Constraint::Length(16),
+                    Constraint::Length(10),
+                    Constraint::Min(14),
+                ],
+            )
+            .header(header)
+            .block(block);
+            let mut table_state = TableState::default().with_selected(Some(state.selected));
+            frame.render_stateful_widget(table, chunks[1], &mut table_state);
+        }
+    }
+
+    // Outcome of the last move
+    if let Some(status) = &state.status {
+        let line = match status {
+            Ok(message) => Line::from(Span::styled(format!("  ✓ {}", message), theme::success())),
+            Err(e) => Line::from(Span::styled(format!("  ✗ {}", e), theme::error())),
+        };
+        frame.render_widget(Paragraph::new(line), chunks[2]);
+    }
+
+    // Footer
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓", theme::key_hint()),
+        Span::styled("] Navigate  [", theme::dim()),
+        Span::styled("Space", theme::key_hint()),
+        Span::styled("] Switch channel  [", theme::dim()),
+        Span::styled("r", theme::key_hint()),
+        Span::styled("] Refresh  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Back", theme::dim()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[3]);
+}
+
+fn host_row<'a>(host: &HostChannel, selected: bool) -> Row<'a> {
+    let style = if selected {
+        theme::selected()
+    } else if host.channel == Channel::Stable {
+        theme::info()
+    } else {
+        theme::text()
+    };
+    Row::new(vec![
+        if selected { ">" } else { " " }.to_string(),
+        host.host.clone(),
+        host.channel.to_string(),
+        host.channel.input().to_string(),
+    ])
+    .style(style)
+}
@@ -13,8 +13,10 @@ use crate::system::disk::DiskInfo;
 use crate::system::hardware::{CpuInfo, FormFactor, GpuInfo};
 use crate::ui::layout::centered_rect;
 use crate::ui::theme;
+use crate::ui::widgets::LoadingPanel;
 
 /// Draw hostname entry screen (comes after disk selection)
+#[allow(clippy::too_many_arguments)]
 pub fn draw_enter_hostname(
     frame: &mut Frame,
     cpu: &CpuInfo,
@@ -115,7 +117,7 @@ pub fn draw_select_disk(
     form_factor: &FormFactor,
     disks: &[DiskInfo],
     selected: usize,
-    _app: &App,
+    app: &App,
 ) {
     let area = frame.area();
     let chunks = Layout::default()
@@ -147,6 +149,15 @@ pub fn draw_select_disk(
     );
     frame.render_widget(summary, chunks[1]);
 
+    // Disks are probed in the background
+    if disks.is_empty() && app.disks_loading {
+        let loading = LoadingPanel::new("Detecting disks...", app.spinner_frame())
+            .title(" Available Disks ");
+        frame.render_widget(loading, chunks[2]);
+        draw_footer(frame, chunks[3], &["Esc Back"]);
+        return;
+    }
+
     if disks.is_empty() {
         let message = Paragraph::new(vec![
             Line::from(""),
@@ -3,18 +3,20 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Paragraph, Wrap},
     Frame,
 };
 
 use super::helpers::{draw_footer, draw_header};
-use crate::app::{App, NewHostConfig, StepStatus};
+use crate::app::{App, NewHostConfig, RootFilesystem, StepStatus};
+use crate::system::disk::StorageKind;
+use crate::system::hardware::BootMode;
 use crate::ui::layout::{centered_rect, progress_layout};
 use crate::ui::theme;
 use crate::ui::widgets::{LogView, ProgressSteps};
 
 /// Draw review screen
-pub fn draw_review(frame: &mut Frame, config: &NewHostConfig, _app: &App) {
+pub fn draw_review(frame: &mut Frame, config: &NewHostConfig, lint: bool, _app: &App) {
     let area = frame.area();
     let center = centered_rect(70, 70, area);
 
@@ -23,7 +25,7 @@ pub fn draw_review(frame: &mut Frame, config: &NewHostConfig, _app: &App) {
         .constraints([
             Constraint::Length(5),
             Constraint::Min(12),
-            Constraint::Length(5),
+            Constraint::Length(7),
             Constraint::Length(3),
         ])
         .split(center);
@@ -34,6 +36,20 @@ pub fn draw_review(frame: &mut Frame, config: &NewHostConfig, _app: &App) {
     let gpu_model = config.gpu.model.as_deref().unwrap_or("N/A");
     let disk_model = config.disk.model.as_deref().unwrap_or("Unknown");
 
+    let options = &config.disk_options;
+    let mount_options = match options.mount_options() {
+        list if list.is_empty() => "defaults".to_string(),
+        list => list.join(","),
+    };
+    let labels = match (config.filesystem, config.boot_mode) {
+        (RootFilesystem::Btrfs, BootMode::Uefi) => format!(
+            " (labels {} / {})",
+            options.boot_label,
+            if options.root_label.is_empty() { "default" } else { &options.root_label }
+        ),
+        _ => String::new(),
+    };
+
     let summary = Paragraph::new(vec![
         Line::from(""),
         Line::from(vec![
@@ -61,6 +77,102 @@ pub fn draw_review(frame: &mut Frame, config: &NewHostConfig, _app: &App) {
             Span::styled(&config.disk.path, theme::text()),
             Span::styled(format!(" ({}, {})", config.disk.size, disk_model), theme::dim()),
         ]),
+        Line::from(vec![
+            Span::styled("  Disk path:   ", theme::dim()),
+            Span::styled(config.disk.config_path(), theme::text()),
+        ]),
+        Line::from(vec![
+            Span::styled("  Storage:     ", theme::dim()),
+            Span::styled(format!("{}", config.storage), theme::text()),
+            Span::styled(storage_note(config), theme::dim()),
+        ]),
+        Line::from(vec![
+            Span::styled("  Boot:        ", theme::dim()),
+            Span::styled(format!("{}", config.boot_mode), theme::text()),
+            Span::styled(
+                match config.boot_mode {
+                    BootMode::Uefi => " (Limine, GPT)",
+                    BootMode::Bios => " (GRUB, MBR)",
+                },
+                theme::dim(),
+            ),
+        ]),
+        match config.filesystem {
+            RootFilesystem::Zfs => Line::from(vec![
+                Span::styled("  Filesystem:  ", theme::dim()),
+                Span::styled("ZFS", theme::text()),
+                Span::styled(format!(" (rpool, hostId {})", config.host_id), theme::dim()),
+            ]),
+            RootFilesystem::Btrfs => Line::from(vec![
+                Span::styled("  Subvolumes:  ", theme::dim()),
+                Span::styled(format!("{}", config.subvolume_layout), theme::text()),
+                Span::styled(
+                    match config.subvolumes() {
+                        Some(subvolumes) if !subvolumes.is_empty() => format!(
+                            " ({})",
+                            subvolumes
+                                .iter()
+                                .map(|(name, _)| name.as_str())
+                                .collect::<Vec<_>>()
+                                .join(" ")
+                        ),
+                        _ => String::new(),
+                    },
+                    theme::dim(),
+                ),
+            ]),
+        },
+        Line::from(vec![
+            Span::styled("  Swap:        ", theme::dim()),
+            Span::styled(format!("{}", config.swap), theme::text()),
+            Span::styled(
+                match config.disk_swap() {
+                    Some((_, size)) => format!(" ({} GiB)", size),
+                    None => String::new(),
+                },
+                theme::dim(),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  Mounts:      ", theme::dim()),
+            match config.filesystem {
+                RootFilesystem::Zfs => Span::styled("ZFS dataset properties", theme::text()),
+                RootFilesystem::Btrfs => Span::styled(mount_options, theme::text()),
+            },
+            Span::styled(labels, theme::dim()),
+        ]),
+        Line::from(vec![
+            Span::styled("  Kernel:      ", theme::dim()),
+            Span::styled(format!("{}", config.kernel), theme::text()),
+            Span::styled(
+                if config.kernel_params.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", config.kernel_params.join(" "))
+                },
+                theme::dim(),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  Modules:     ", theme::dim()),
+            Span::styled(
+                if config.modules.is_empty() {
+                    "None".to_string()
+                } else {
+                    config
+                        .modules
+                        .iter()
+                        .map(|m| m.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                },
+                theme::text(),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  Look:        ", theme::dim()),
+            Span::styled(format!("{}", config.theme), theme::text()),
+        ]),
         Line::from(""),
     ])
     .block(
@@ -95,6 +207,14 @@ pub fn draw_review(frame: &mut Frame, config: &NewHostConfig, _app: &App) {
                 theme::text(),
             ),
         ]),
+        Line::from(vec![
+            Span::styled("Lint: ", theme::dim()),
+            if lint {
+                Span::styled("statix + deadnix, fixes applied", theme::success())
+            } else {
+                Span::styled("off", theme::text())
+            },
+        ]),
     ])
     .block(
         Block::default()
@@ -103,7 +223,21 @@ pub fn draw_review(frame: &mut Frame, config: &NewHostConfig, _app: &App) {
     );
     frame.render_widget(files, chunks[2]);
 
-    draw_footer(frame, chunks[3], &["Enter Create", "Esc Back"]);
+    let hints: &[&str] = match config.filesystem {
+        RootFilesystem::Btrfs => &["Enter Create", "Tab Lint", "d Disk options", "Esc Back"],
+        RootFilesystem::Zfs => &["Enter Create", "Tab Lint", "Esc Back"],
+    };
+    draw_footer(frame, chunks[3], hints);
+}
+
+/// What the generated config does for the detected disk type
+fn storage_note(config: &NewHostConfig) -> &'static str {
+    match (config.storage, config.filesystem) {
+        (_, RootFilesystem::Zfs) => " (TRIM through services.zfs.trim)",
+        (StorageKind::Ssd, RootFilesystem::Btrfs) => " (weekly fstrim enabled)",
+        (StorageKind::Hdd, RootFilesystem::Btrfs) => " (fstrim off, autodefrag by default)",
+        (StorageKind::Unknown, RootFilesystem::Btrfs) => " (not detected, NixOS defaults)",
+    }
 }
 
 /// Draw generating screen
@@ -140,7 +274,7 @@ pub fn draw_generating(
     // Progress and output
     let (steps_area, output_area) = progress_layout(chunks[1]);
 
-    let progress = ProgressSteps::new(steps, app.spinner_state).title(" Progress ");
+    let progress = ProgressSteps::new(steps, app.spinner_frame()).title(" Progress ");
     frame.render_widget(progress, steps_area);
 
     let log = LogView::new(output).title(" Output ");
@@ -160,10 +294,16 @@ pub fn draw_complete(
     frame: &mut Frame,
     success: bool,
     config: &crate::app::state::NewHostConfig,
+    error: Option<&str>,
     _app: &App,
 ) {
     let area = frame.area();
-    let center = centered_rect(60, 50, area);
+    // Room for an evaluation error, which names the option and file at fault
+    let center = if error.is_some() {
+        centered_rect(80, 70, area)
+    } else {
+        centered_rect(60, 50, area)
+    };
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -219,25 +359,39 @@ pub fn draw_complete(
         draw_footer(frame, chunks[2], &["Any key Continue", "q Quit"]);
     } else {
         // Failure message
-        let message = Paragraph::new(vec![
+        let mut lines = vec![
             Line::from(""),
             Line::from(Span::styled(
                 "Failed to create host configuration.",
                 theme::error(),
             )),
-            Line::from(""),
             Line::from(Span::styled(
-                "Check the output for error details.",
-                theme::dim(),
+                "The config repo was left unchanged.",
+                theme::text(),
             )),
             Line::from(""),
-        ])
-        .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(theme::error()),
-        );
+        ];
+        match error {
+            Some(error) => {
+                lines.extend(error.lines().map(|l| {
+                    Line::from(Span::styled(l, theme::text())).alignment(Alignment::Left)
+                }));
+            }
+            None => {
+                lines.push(Line::from(Span::styled(
+                    "Check the output for error details.",
+                    theme::dim(),
+                )));
+            }
+        }
+        let message = Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme::error()),
+            );
         frame.render_widget(message, chunks[1]);
         draw_footer(frame, chunks[2], &["Enter Menu", "q Quit"]);
     }
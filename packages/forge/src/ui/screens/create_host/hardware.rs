@@ -27,7 +27,7 @@ pub fn draw_detecting_hardware(frame: &mut Frame, app: &App) {
     draw_header(frame, chunks[0], "New Host Configuration");
 
     let spinner = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
-    let spin_char = spinner[app.spinner_state % spinner.len()];
+    let spin_char = spinner[app.spinner_frame() % spinner.len()];
 
     let detecting = Paragraph::new(vec![
         Line::from(""),
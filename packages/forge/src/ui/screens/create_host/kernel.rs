@@ -0,0 +1,87 @@
+//! Kernel selection screen
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use super::helpers::{draw_footer, draw_header};
+use crate::app::{App, KernelFlavor, NewHostConfig};
+use crate::ui::layout::centered_rect;
+use crate::ui::theme;
+use crate::ui::widgets::MenuList;
+
+/// Draw kernel flavor selection with extra kernel params input
+pub fn draw_select_kernel(
+    frame: &mut Frame,
+    config: &NewHostConfig,
+    selected: usize,
+    params: &str,
+    error: Option<&str>,
+    _app: &App,
+) {
+    let area = frame.area();
+    let center = centered_rect(70, 70, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Length(8),
+            Constraint::Length(3),
+            Constraint::Length(6),
+            Constraint::Min(3),
+        ])
+        .split(center);
+
+    draw_header(frame, chunks[0], "Select Kernel");
+
+    // Flavor menu
+    let labels: Vec<String> = KernelFlavor::ALL
+        .iter()
+        .map(|k| format!("{:<15} {}", k.to_string(), k.description()))
+        .collect();
+    let items: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let menu = MenuList::new(items, selected).title(" Kernel ");
+    frame.render_widget(menu, chunks[1]);
+
+    // Summary of what will be emitted
+    let flavor = KernelFlavor::ALL.get(selected).copied().unwrap_or_default();
+    let hint = Paragraph::new(Line::from(vec![
+        Span::styled(format!("{}: ", config.hostname), theme::dim()),
+        Span::styled("boot.kernelPackages = ", theme::dim()),
+        Span::styled(format!("pkgs.{}", flavor.package_attr()), theme::info()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(hint, chunks[2]);
+
+    // Extra params input
+    let mut lines = vec![
+        Line::from(Span::styled(
+            "Extra kernel parameters (optional, space separated):",
+            theme::text(),
+        )),
+        Line::from(vec![
+            Span::styled("> ", theme::info()),
+            Span::styled(params, theme::text()),
+            Span::styled("_", theme::info()),
+        ]),
+    ];
+
+    if let Some(err) = error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(err, theme::error())));
+    }
+
+    let input_block = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(" Kernel Parameters ", theme::title())),
+    );
+    frame.render_widget(input_block, chunks[3]);
+
+    draw_footer(frame, chunks[4], &["↑↓ Kernel", "Enter Continue", "Esc Back"]);
+}
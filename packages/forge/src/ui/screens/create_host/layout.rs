@@ -0,0 +1,299 @@
+//! Root filesystem, btrfs subvolume layout, swap and disk option screens
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use super::helpers::{draw_footer, draw_header};
+use crate::app::state::DiskOptionField;
+use crate::app::{App, NewHostConfig, RootFilesystem, SubvolumeLayout, SwapMode};
+use crate::system::hardware::BootMode;
+use crate::ui::layout::centered_rect;
+use crate::ui::theme;
+use crate::ui::widgets::MenuList;
+
+/// Draw root filesystem selection (btrfs or ZFS)
+pub fn draw_select_filesystem(
+    frame: &mut Frame,
+    config: &NewHostConfig,
+    selected: usize,
+    error: Option<&str>,
+    _app: &App,
+) {
+    let area = frame.area();
+    let center = centered_rect(70, 60, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Length(4),
+            Constraint::Length(4),
+            Constraint::Min(3),
+        ])
+        .split(center);
+
+    draw_header(frame, chunks[0], "Select Root Filesystem");
+
+    let labels: Vec<String> = RootFilesystem::ALL
+        .iter()
+        .map(|f| format!("{:<8} {}", f.to_string(), f.description()))
+        .collect();
+    let items: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let menu = MenuList::new(items, selected).title(" Filesystem ");
+    frame.render_widget(menu, chunks[1]);
+
+    let filesystem = RootFilesystem::ALL.get(selected).copied().unwrap_or_default();
+    let mut lines = vec![Line::from(vec![
+        Span::styled(format!("{}: ", config.disk.path), theme::dim()),
+        Span::styled(
+            match filesystem {
+                RootFilesystem::Btrfs => "LUKS2 → btrfs, subvolumes chosen next".to_string(),
+                RootFilesystem::Zfs => format!("rpool (hostId {})", config.host_id),
+            },
+            theme::info(),
+        ),
+    ])];
+    if let Some(err) = error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(err, theme::error())));
+    }
+    frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), chunks[2]);
+
+    draw_footer(frame, chunks[3], &["↑↓ Filesystem", "Enter Continue", "Esc Back"]);
+}
+
+/// Draw subvolume scheme selection, with a name=/mountpoint input for Custom
+pub fn draw_select_layout(
+    frame: &mut Frame,
+    config: &NewHostConfig,
+    selected: usize,
+    custom: &str,
+    error: Option<&str>,
+    _app: &App,
+) {
+    let area = frame.area();
+    let center = centered_rect(70, 70, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Length(8),
+            Constraint::Length(3),
+            Constraint::Length(6),
+            Constraint::Min(3),
+        ])
+        .split(center);
+
+    draw_header(frame, chunks[0], "Select Disk Layout");
+
+    // Layout menu
+    let labels: Vec<String> = SubvolumeLayout::ALL
+        .iter()
+        .map(|l| format!("{:<12} {}", l.to_string(), l.description()))
+        .collect();
+    let items: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let menu = MenuList::new(items, selected).title(" Btrfs Subvolumes ");
+    frame.render_widget(menu, chunks[1]);
+
+    // Preview of the subvolumes on the target disk
+    let layout = SubvolumeLayout::ALL.get(selected).copied().unwrap_or_default();
+    let subvolumes = layout
+        .subvolumes()
+        .iter()
+        .map(|(name, mountpoint)| format!("{} → {}", name, mountpoint))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let preview = match layout {
+        SubvolumeLayout::Flat => "/ (no subvolumes)".to_string(),
+        SubvolumeLayout::Custom => "entered below".to_string(),
+        SubvolumeLayout::EphemeralTmpfs => format!("tmpfs → /  {}", subvolumes),
+        _ => subvolumes,
+    };
+    let hint = Paragraph::new(Line::from(vec![
+        Span::styled(format!("{}: ", config.disk.path), theme::dim()),
+        Span::styled(preview, theme::info()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(hint, chunks[2]);
+
+    // Custom scheme input
+    if layout == SubvolumeLayout::Custom {
+        let mut lines = vec![
+            Line::from(Span::styled(
+                "Subvolumes as name=/mountpoint, space separated (one at /):",
+                theme::text(),
+            )),
+            Line::from(vec![
+                Span::styled("> ", theme::info()),
+                Span::styled(custom, theme::text()),
+                Span::styled("_", theme::info()),
+            ]),
+        ];
+
+        if let Some(err) = error {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(err, theme::error())));
+        }
+
+        let input_block = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border())
+                .title(Span::styled(" Custom Layout ", theme::title())),
+        );
+        frame.render_widget(input_block, chunks[3]);
+    }
+
+    draw_footer(frame, chunks[4], &["↑↓ Layout", "Enter Continue", "Esc Back"]);
+}
+
+/// Draw swap selection, with the swapfile/partition size for this machine's RAM
+pub fn draw_select_swap(
+    frame: &mut Frame,
+    config: &NewHostConfig,
+    selected: usize,
+    error: Option<&str>,
+    _app: &App,
+) {
+    let area = frame.area();
+    let center = centered_rect(70, 60, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Length(6),
+            Constraint::Length(4),
+            Constraint::Min(3),
+        ])
+        .split(center);
+
+    draw_header(frame, chunks[0], "Select Swap");
+
+    let labels: Vec<String> = SwapMode::ALL
+        .iter()
+        .map(|s| format!("{:<10} {}", s.to_string(), s.description()))
+        .collect();
+    let items: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let menu = MenuList::new(items, selected).title(" Swap ");
+    frame.render_widget(menu, chunks[1]);
+
+    let swap = SwapMode::ALL.get(selected).copied().unwrap_or_default();
+    let memory = if config.memory_gib == 0 {
+        "RAM unknown".to_string()
+    } else {
+        format!("{} GiB RAM", config.memory_gib)
+    };
+    let detail = match swap.disk_size_gib(config.memory_gib) {
+        Some(size) => format!("{} → {} GiB {}", memory, size, swap.to_string().to_lowercase()),
+        None if swap == SwapMode::Zram => format!("{} → zram up to 25% of RAM", memory),
+        None => format!("{} → no swap", memory),
+    };
+    let mut lines = vec![Line::from(vec![
+        Span::styled(format!("{}: ", config.disk.path), theme::dim()),
+        Span::styled(detail, theme::info()),
+    ])];
+    if let Some(err) = error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(err, theme::error())));
+    }
+    frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), chunks[2]);
+
+    draw_footer(frame, chunks[3], &["↑↓ Swap", "Enter Continue", "Esc Back"]);
+}
+
+/// Draw the advanced disk options: compression, mount options, partition labels
+pub fn draw_disk_options(
+    frame: &mut Frame,
+    config: &NewHostConfig,
+    selected: usize,
+    error: Option<&str>,
+    _app: &App,
+) {
+    let area = frame.area();
+    let center = centered_rect(70, 60, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Length(10),
+            Constraint::Length(4),
+            Constraint::Min(3),
+        ])
+        .split(center);
+
+    draw_header(frame, chunks[0], "Disk Options");
+
+    let options = &config.disk_options;
+    let check = |on: bool| if on { "[x]" } else { "[ ]" };
+    let gpt = config.boot_mode == BootMode::Uefi;
+    let value = |field: DiskOptionField| match field {
+        DiskOptionField::Compression => match options.compression {
+            Some(level) => format!("◀ zstd:{} ▶", level),
+            None => "◀ off ▶".to_string(),
+        },
+        DiskOptionField::Noatime => format!("{} noatime", check(options.noatime)),
+        DiskOptionField::Discard => format!("{} discard=async", check(options.discard)),
+        DiskOptionField::Autodefrag => format!("{} autodefrag", check(options.autodefrag)),
+        _ if !gpt => "n/a (MBR has no partition labels)".to_string(),
+        DiskOptionField::BootLabel => options.boot_label.clone(),
+        DiskOptionField::RootLabel if options.root_label.is_empty() => {
+            "(default: cryptroot / root)".to_string()
+        }
+        DiskOptionField::RootLabel => options.root_label.clone(),
+    };
+
+    let mut lines = vec![Line::from("")];
+    for (i, field) in DiskOptionField::ALL.iter().enumerate() {
+        let name = match field {
+            DiskOptionField::Compression => "Compression:",
+            DiskOptionField::Noatime => "Access times:",
+            DiskOptionField::Discard => "TRIM:",
+            DiskOptionField::Autodefrag => "Defrag:",
+            DiskOptionField::BootLabel => "Boot label:",
+            DiskOptionField::RootLabel => "Root label:",
+        };
+        let active = i == selected;
+        let mut text = value(*field);
+        if active && field.is_text() && gpt {
+            let label = match field {
+                DiskOptionField::BootLabel => &options.boot_label,
+                _ => &options.root_label,
+            };
+            text = format!("{}_", label);
+        }
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {:<15}", name), theme::dim()),
+            Span::styled(text, if active { theme::selected() } else { theme::text() }),
+        ]));
+    }
+    let fields = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(" Btrfs and Partitions ", theme::title())),
+    );
+    frame.render_widget(fields, chunks[1]);
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled("Mount options: ", theme::dim()),
+        Span::styled(options.mount_options().join(","), theme::info()),
+    ])];
+    if let Some(err) = error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(err, theme::error())));
+    }
+    frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), chunks[2]);
+
+    draw_footer(
+        frame,
+        chunks[3],
+        &["↑↓ Option", "←→/Space Change", "Enter Apply", "Esc Cancel"],
+    );
+}
@@ -3,14 +3,27 @@
 //! This module contains all UI screens for the host creation wizard:
 //! - Hardware detection and confirmation (CPU, GPU, form factor)
 //! - Disk selection and hostname entry
+//! - Root filesystem (btrfs or ZFS), subvolume layout and swap
+//! - Advanced disk options (compression, mount options, labels) from the review
+//! - Kernel selection, optional modules and theming preset
 //! - Configuration review and generation progress
 
 mod disk;
 mod generation;
 mod hardware;
 mod helpers;
+mod kernel;
+mod layout;
+mod modules;
+mod theming;
 
 // Re-export all public draw functions for external use
 pub use disk::{draw_enter_hostname, draw_select_disk};
 pub use generation::{draw_complete, draw_generating, draw_review};
 pub use hardware::{draw_confirm_cpu, draw_confirm_form_factor, draw_confirm_gpu, draw_detecting_hardware};
+pub use kernel::draw_select_kernel;
+pub use layout::{
+    draw_disk_options, draw_select_filesystem, draw_select_layout, draw_select_swap,
+};
+pub use modules::draw_select_modules;
+pub use theming::draw_select_theme;
@@ -0,0 +1,60 @@
+//! Optional module picker screen
+
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use super::helpers::{draw_footer, draw_header};
+use crate::app::{App, HostModule, NewHostConfig};
+use crate::ui::layout::centered_rect;
+use crate::ui::theme;
+
+/// Draw the optional module checklist
+pub fn draw_select_modules(frame: &mut Frame, config: &NewHostConfig, selected: usize, _app: &App) {
+    let area = frame.area();
+    let center = centered_rect(70, 70, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Min(8),
+            Constraint::Length(3),
+        ])
+        .split(center);
+
+    draw_header(frame, chunks[0], "Optional Modules");
+
+    let mut lines = vec![Line::from("")];
+    for (i, module) in HostModule::ALL.iter().enumerate() {
+        let is_selected = i == selected;
+        let checkbox = if config.has_module(*module) { "[x]" } else { "[ ]" };
+        let style = if is_selected {
+            theme::selected()
+        } else {
+            theme::text()
+        };
+
+        lines.push(Line::from(vec![
+            Span::styled(if is_selected { " > " } else { "   " }, style),
+            Span::styled(format!("{} {:<15}", checkbox, module.to_string()), style),
+            Span::styled(module.description(), theme::dim()),
+        ]));
+    }
+
+    let list = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(
+                format!(" Modules for {} ", config.hostname),
+                theme::title(),
+            )),
+    );
+    frame.render_widget(list, chunks[1]);
+
+    draw_footer(frame, chunks[2], &["↑↓ Navigate", "Space Toggle", "Enter Continue", "Esc Back"]);
+}
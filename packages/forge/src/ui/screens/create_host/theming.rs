@@ -0,0 +1,51 @@
+//! Theming preset selection screen
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+use super::helpers::{draw_footer, draw_header};
+use crate::app::{App, NewHostConfig, ThemePreset};
+use crate::ui::layout::centered_rect;
+use crate::ui::theme;
+use crate::ui::widgets::MenuList;
+
+/// Draw theming preset selection
+pub fn draw_select_theme(frame: &mut Frame, config: &NewHostConfig, selected: usize, _app: &App) {
+    let area = frame.area();
+    let center = centered_rect(70, 60, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Length(7),
+            Constraint::Length(2),
+            Constraint::Min(3),
+        ])
+        .split(center);
+
+    draw_header(frame, chunks[0], "Select Look");
+
+    let labels: Vec<String> = ThemePreset::ALL
+        .iter()
+        .map(|t| format!("{:<10} {}", t.to_string(), t.description()))
+        .collect();
+    let items: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let menu = MenuList::new(items, selected).title(" Theming Preset ");
+    frame.render_widget(menu, chunks[1]);
+
+    let preset = ThemePreset::ALL.get(selected).copied().unwrap_or_default();
+    let hint = Paragraph::new(Line::from(vec![
+        Span::styled(format!("{}: ", config.hostname), theme::dim()),
+        Span::styled("theming.preset = ", theme::dim()),
+        Span::styled(format!("\"{}\"", preset.nix_name()), theme::info()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(hint, chunks[2]);
+
+    draw_footer(frame, chunks[3], &["↑↓ Navigate", "Enter Select", "Esc Back"]);
+}
@@ -0,0 +1,91 @@
+//! Dev shells screen (flake devShells launcher)
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::app::{App, DevShellState};
+use crate::ui::layout::centered_rect;
+use crate::ui::theme;
+use crate::ui::widgets::{MenuList, Spinner};
+
+pub fn draw(frame: &mut Frame, state: &DevShellState, app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Min(10),
+            Constraint::Length(2),
+            Constraint::Length(3),
+        ])
+        .split(centered_rect(70, 80, area));
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(" Dev Shells ", theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    // Shell list
+    match &state.shells {
+        None => {
+            let spinner_char = Spinner::new(app.spinner_frame()).char();
+            let loading = Paragraph::new(Line::from(Span::styled(
+                format!("  {} Evaluating flakes...", spinner_char),
+                theme::dim(),
+            )));
+            frame.render_widget(loading, chunks[1]);
+        }
+        Some(shells) if shells.is_empty() => {
+            let empty = Paragraph::new(vec![
+                Line::from(Span::styled("  No devShells found", theme::text())),
+                Line::from(Span::styled(
+                    "  Looked in the nixos-config flake and flakes in ~/code",
+                    theme::dim(),
+                )),
+            ]);
+            frame.render_widget(empty, chunks[1]);
+        }
+        Some(shells) => {
+            let width = shells.iter().map(|s| s.project.len()).max().unwrap_or(0);
+            let items: Vec<String> = shells
+                .iter()
+                .map(|s| format!("{:<width$}  {}", s.project, s.name, width = width))
+                .collect();
+            let menu = MenuList::new(items.iter().map(String::as_str).collect(), state.selected);
+            frame.render_widget(menu, chunks[1]);
+        }
+    }
+
+    // Last launch result
+    if let Some(status) = &state.status {
+        let line = match status {
+            Ok(message) => Line::from(Span::styled(format!("  ✓ {}", message), theme::success())),
+            Err(e) => Line::from(Span::styled(format!("  ✗ {}", e), theme::error())),
+        };
+        frame.render_widget(Paragraph::new(line), chunks[2]);
+    }
+
+    // Footer
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓", theme::key_hint()),
+        Span::styled("] Navigate  [", theme::dim()),
+        Span::styled("Enter", theme::key_hint()),
+        Span::styled("] Open terminal  [", theme::dim()),
+        Span::styled("r", theme::key_hint()),
+        Span::styled("] Refresh  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Back", theme::dim()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[3]);
+}
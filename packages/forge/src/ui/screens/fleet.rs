@@ -0,0 +1,289 @@
+//! Fleet screens (rolling update, drift status)
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+use std::collections::HashMap;
+
+use crate::app::{App, StepState, StepStatus};
+use crate::system::fleet::DeployStage;
+use crate::system::host_state::{format_age, Drift, HostDrift};
+use crate::ui::screens::build_log_hint;
+use crate::ui::theme;
+use crate::ui::widgets::{LogView, ProgressSteps, Spinner};
+
+/// Narrowest host column; wider screens fit more hosts side by side
+const HOST_COLUMN_WIDTH: u16 = 26;
+
+/// Borders, one line per stage and the host's latest output line
+const HOST_COLUMN_HEIGHT: u16 = DeployStage::ALL.len() as u16 + 3;
+
+/// Draw running/complete fleet update screen
+pub fn draw_running(
+    frame: &mut Frame,
+    steps: &[StepStatus],
+    stages: &HashMap<String, DeployStage>,
+    output: &[String],
+    complete: Option<bool>,
+    scroll_offset: Option<usize>,
+    app: &App,
+) {
+    let area = frame.area();
+    // The first step pulls the config, the rest are hosts
+    let (pull, hosts) = steps.split_at(steps.len().min(1));
+    let per_row = (area.width / HOST_COLUMN_WIDTH).max(1) as usize;
+    let rows = hosts.len().div_ceil(per_row) as u16;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(rows * HOST_COLUMN_HEIGHT),
+            Constraint::Min(6),
+            Constraint::Length(2),
+        ])
+        .split(area);
+
+    // Header
+    let (title, style) = match complete {
+        None => (" Fleet Update ", theme::title()),
+        Some(true) => (" Fleet Update Complete ", theme::success()),
+        Some(false) => (" Fleet Update Stopped ", theme::error()),
+    };
+    let header = Paragraph::new(Line::from(Span::styled(title, style)))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    let progress = ProgressSteps::new(pull, app.spinner_frame()).title(" Configuration ");
+    frame.render_widget(progress, chunks[1]);
+
+    // One column per host, as many side by side as fit
+    let row_areas = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(HOST_COLUMN_HEIGHT); rows as usize])
+        .split(chunks[2]);
+    for (row, row_hosts) in row_areas.iter().zip(hosts.chunks(per_row)) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, per_row as u32); per_row])
+            .split(*row);
+        for (column, host) in columns.iter().zip(row_hosts) {
+            let stage = stages.get(&host.name).copied();
+            draw_host_column(frame, *column, host, stage, output, app.spinner_frame());
+        }
+    }
+
+    let mut log = LogView::new(output).title(" Output ");
+    if let Some(offset) = scroll_offset {
+        log = log.scroll_offset(offset);
+    }
+    frame.render_widget(log, chunks[3]);
+
+    // Footer
+    let footer = if complete.is_some() {
+        let mut hints = vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("↑↓", theme::key_hint()),
+            Span::styled("] Scroll  [", theme::dim()),
+        ];
+        hints.extend(build_log_hint(app));
+        hints.extend([
+            Span::styled("Enter", theme::key_hint()),
+            Span::styled("] Done  [", theme::dim()),
+            Span::styled("q", theme::key_hint()),
+            Span::styled("] Quit", theme::dim()),
+        ]);
+        Paragraph::new(Line::from(hints))
+    } else {
+        Paragraph::new(Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("Ctrl+C", theme::key_hint()),
+            Span::styled("] Cancel", theme::dim()),
+        ]))
+    }
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[4]);
+}
+
+/// A host's deploy stages and the last line it printed
+fn draw_host_column(
+    frame: &mut Frame,
+    area: Rect,
+    host: &StepStatus,
+    stage: Option<DeployStage>,
+    output: &[String],
+    spinner_state: usize,
+) {
+    let spinner = Spinner::new(spinner_state).char().to_string();
+    let mut lines: Vec<Line> = DeployStage::ALL
+        .iter()
+        .map(|s| {
+            let (icon, style) = match (&host.status, stage) {
+                (StepState::Complete, _) => ("✓".to_string(), theme::success()),
+                (StepState::Skipped, _) => ("-".to_string(), theme::dim()),
+                (_, Some(current)) if *s < current => ("✓".to_string(), theme::success()),
+                (StepState::Running, Some(current)) if *s == current => {
+                    (spinner.clone(), theme::info())
+                }
+                (StepState::Failed, Some(current)) if *s == current => {
+                    ("✗".to_string(), theme::error())
+                }
+                _ => ("·".to_string(), theme::dim()),
+            };
+            Line::from(vec![
+                Span::styled(format!(" {} ", icon), style),
+                Span::styled(s.label(), theme::text()),
+            ])
+        })
+        .collect();
+
+    // Output lines of a host are prefixed with its name
+    let prefix = format!("[{}] ", host.name);
+    let latest = output
+        .iter()
+        .rev()
+        .find_map(|line| line.strip_prefix(&prefix))
+        .unwrap_or("");
+    lines.push(Line::from(Span::styled(format!(" {}", latest.trim()), theme::dim())));
+
+    let (title_style, border_style) = match &host.status {
+        StepState::Running => (theme::info(), theme::border_active()),
+        StepState::Complete => (theme::success(), theme::border()),
+        StepState::Failed => (theme::error(), theme::error()),
+        StepState::Pending | StepState::Skipped => (theme::dim(), theme::border()),
+    };
+    let column = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(border_style)
+            .title(Span::styled(format!(" {} ", host.name), title_style)),
+    );
+    frame.render_widget(column, area);
+}
+
+/// Draw the host drift report
+pub fn draw_status(frame: &mut Frame, report: Option<&Result<Vec<HostDrift>, String>>, app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(2),
+        ])
+        .split(area);
+
+    let header = Paragraph::new(Line::from(Span::styled(" Fleet Status ", theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    let now = chrono::Utc::now();
+    let (title, lines) = match report {
+        None => {
+            let spinner_char = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏']
+                [app.spinner_frame() % 10];
+            (
+                " Hosts ".to_string(),
+                vec![Line::from(Span::styled(
+                    format!("  {} Fetching host reports...", spinner_char),
+                    theme::dim(),
+                ))],
+            )
+        }
+        Some(Err(e)) => (
+            " Hosts ".to_string(),
+            vec![Line::from(Span::styled(format!("  {}", e), theme::error()))],
+        ),
+        Some(Ok(hosts)) => {
+            let attention = hosts.iter().filter(|h| h.needs_attention(now)).count();
+            let width = hosts.iter().map(|h| h.state.hostname.len()).max().unwrap_or(0);
+            let mut lines = vec![Line::from("")];
+            lines.extend(hosts.iter().map(|h| host_line(h, width, now)));
+            (
+                format!(" Hosts ({} of {} need attention) ", attention, hosts.len()),
+                lines,
+            )
+        }
+    };
+    let list = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(title),
+    );
+    frame.render_widget(list, chunks[1]);
+
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("r", theme::key_hint()),
+        Span::styled("] Refresh  [", theme::dim()),
+        Span::styled("Enter", theme::key_hint()),
+        Span::styled("] Done  [", theme::dim()),
+        Span::styled("q", theme::key_hint()),
+        Span::styled("] Quit", theme::dim()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[2]);
+}
+
+/// One host row: name, generation, revision, drift, report age and the next
+/// maintenance window
+fn host_line(host: &HostDrift, width: usize, now: chrono::DateTime<chrono::Utc>) -> Line<'static> {
+    let state = &host.state;
+    let (icon, drift, style) = match host.drift {
+        Drift::UpToDate => ("✓", "up to date".to_string(), theme::success()),
+        Drift::Behind(n) => ("↓", format!("{} commit(s) behind", n), theme::warning()),
+        Drift::Diverged => ("✗", "not on main".to_string(), theme::error()),
+        Drift::Unknown => ("?", "no revision".to_string(), theme::dim()),
+    };
+    let revision = state
+        .commit()
+        .map(|c| c[..7.min(c.len())].to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let generation = state
+        .generation
+        .map_or("-".to_string(), |g| g.to_string());
+    let channel = host.channel.map_or("-".to_string(), |c| c.to_string());
+
+    let mut spans = vec![
+        Span::styled(format!("  {} ", icon), style),
+        Span::styled(format!("{:<width$}", state.hostname, width = width), theme::text()),
+        Span::styled(format!("  {:<8}", channel), theme::dim()),
+        Span::styled(format!("  gen {:>4}  ", generation), theme::dim()),
+        Span::styled(format!("{:<7}  ", revision), theme::info()),
+        Span::styled(format!("{:<20}", drift), style),
+    ];
+    if state.is_dirty() {
+        spans.push(Span::styled("dirty  ", theme::warning()));
+    }
+    let age_style = if host.is_stale(now) {
+        theme::error()
+    } else {
+        theme::dim()
+    };
+    spans.push(Span::styled(
+        format!("reported {}", format_age(state.reported_at, now)),
+        age_style,
+    ));
+    if let Some(window) = host.window {
+        let local = now.with_timezone(&chrono::Local).naive_local();
+        spans.push(Span::styled(
+            format!("  window {} ({})", window, window.status(local)),
+            theme::dim(),
+        ));
+    }
+    Line::from(spans)
+}
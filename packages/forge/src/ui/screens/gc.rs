@@ -0,0 +1,160 @@
+//! Garbage collection settings screen (gc.json)
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::app::{App, GcState};
+use crate::system::gc::GcField;
+use crate::ui::layout::centered_rect;
+use crate::ui::theme;
+use crate::ui::widgets::Spinner;
+
+pub fn draw(frame: &mut Frame, state: &GcState, app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(9),
+            Constraint::Length(2),
+            Constraint::Length(2),
+        ])
+        .split(centered_rect(75, 80, area));
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(
+        " Garbage Collection ",
+        theme::title(),
+    )))
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active()),
+    );
+    frame.render_widget(header, chunks[0]);
+
+    // Scope tabs: all hosts, then each host
+    let mut tabs = Vec::new();
+    let scopes = std::iter::once("All hosts").chain(state.hosts.iter().map(String::as_str));
+    for (i, scope) in scopes.enumerate() {
+        let style = if i == state.scope {
+            theme::selected()
+        } else {
+            theme::dim()
+        };
+        tabs.push(Span::styled(format!(" {} ", scope), style));
+    }
+    frame.render_widget(Paragraph::new(Line::from(tabs)).alignment(Alignment::Center), chunks[1]);
+
+    // Settings for the selected scope
+    let host = state.host();
+    let lines = match &state.config {
+        None => {
+            let spinner_char = Spinner::new(app.spinner_frame()).char();
+            vec![Line::from(Span::styled(
+                format!("  {} Working...", spinner_char),
+                theme::dim(),
+            ))]
+        }
+        Some(Err(e)) => vec![Line::from(Span::styled(format!("  {}", e), theme::error()))],
+        Some(Ok(config)) => {
+            let settings = config.resolve(host);
+            let mut lines = vec![Line::from("")];
+            for (i, field) in GcField::ALL.iter().enumerate() {
+                let selected = i == state.selected;
+                let (prefix, label_style) = if selected {
+                    ("> ", theme::selected())
+                } else {
+                    ("  ", theme::text())
+                };
+                let value = match &state.input {
+                    Some(input) if selected => {
+                        Span::styled(format!("{}_", input), theme::selected())
+                    }
+                    _ => Span::styled(settings.value(*field), theme::info()),
+                };
+                // Where the value comes from, so overrides stand out
+                let source = if config.is_set(host, *field) {
+                    if host.is_some() { "  (this host)" } else { "" }
+                } else if host.is_some() {
+                    "  (all hosts)"
+                } else {
+                    "  (default)"
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{}{:<20}", prefix, field.label()), label_style),
+                    value,
+                    Span::styled(source, theme::dim()),
+                ]));
+            }
+            lines.push(Line::from(""));
+            match &state.input_error {
+                Some(err) => lines.push(Line::from(Span::styled(
+                    format!("  ⚠ {}", err),
+                    theme::error(),
+                ))),
+                None => lines.push(Line::from(Span::styled(
+                    "  Schedule: systemd calendar (weekly, Sun 03:00)  Age: days (14d)",
+                    theme::dim(),
+                ))),
+            }
+            lines
+        }
+    };
+    let title = match host {
+        Some(h) => format!(" {} (overrides) ", h),
+        None => " gc.json ".to_string(),
+    };
+    let settings = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(title),
+    );
+    frame.render_widget(settings, chunks[2]);
+
+    // Last save
+    if let Some(status) = &state.status {
+        let line = match status {
+            Ok(message) => Line::from(Span::styled(format!("  ✓ {}", message), theme::success())),
+            Err(e) => Line::from(Span::styled(format!("  ✗ {}", e), theme::error())),
+        };
+        frame.render_widget(Paragraph::new(line), chunks[3]);
+    }
+
+    // Footer
+    let footer = if state.input.is_some() {
+        Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("Enter", theme::key_hint()),
+            Span::styled("] Save  [", theme::dim()),
+            Span::styled("Esc", theme::key_hint()),
+            Span::styled("] Cancel", theme::dim()),
+        ])
+    } else {
+        Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("←→", theme::key_hint()),
+            Span::styled("] Host  [", theme::dim()),
+            Span::styled("Enter", theme::key_hint()),
+            Span::styled("] Change  [", theme::dim()),
+            Span::styled("x", theme::key_hint()),
+            Span::styled("] Inherit  [", theme::dim()),
+            Span::styled("c", theme::key_hint()),
+            Span::styled("] Collect now  [", theme::dim()),
+            Span::styled("o", theme::key_hint()),
+            Span::styled("] Optimise now  [", theme::dim()),
+            Span::styled("r", theme::key_hint()),
+            Span::styled("] Reload  [", theme::dim()),
+            Span::styled("Esc", theme::key_hint()),
+            Span::styled("] Back", theme::dim()),
+        ])
+    };
+    frame.render_widget(Paragraph::new(footer).alignment(Alignment::Center), chunks[4]);
+}
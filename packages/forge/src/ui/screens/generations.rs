@@ -0,0 +1,183 @@
+//! System generations screen: closure sizes, deleting, the boot default
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::app::{App, GenerationsState};
+use crate::commands::generations::GenerationsOp;
+use crate::system::generations::Generation;
+use crate::system::optimise::format_bytes;
+use crate::ui::layout::centered_rect;
+use crate::ui::theme;
+use crate::ui::widgets::Spinner;
+
+pub fn draw(frame: &mut Frame, state: &GenerationsState, app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(2),
+            Constraint::Length(2),
+        ])
+        .split(centered_rect(90, 85, area));
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(" System Generations ", theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    // Generation table
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme::border())
+        .title(Span::styled(" /nix/var/nix/profiles/system ", theme::title()));
+    match &state.generations {
+        None => {
+            let spinner_char = Spinner::new(app.spinner_frame()).char();
+            let loading = Paragraph::new(Line::from(Span::styled(
+                format!("  {} Working...", spinner_char),
+                theme::dim(),
+            )))
+            .block(block);
+            frame.render_widget(loading, chunks[1]);
+        }
+        Some(Err(e)) => {
+            let error = Paragraph::new(Line::from(Span::styled(format!("  ✗ {}", e), theme::error())))
+                .block(block);
+            frame.render_widget(error, chunks[1]);
+        }
+        Some(Ok(list)) => {
+            let header = Row::new(vec!["", "", "Gen", "Created", "NixOS", "Kernel", "Closure", ""])
+                .style(theme::title())
+                .bottom_margin(1);
+            let rows: Vec<Row> = list
+                .iter()
+                .enumerate()
+                .map(|(i, generation)| generation_row(generation, i == state.selected, state))
+                .collect();
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(2),
+                    Constraint::Length(4),
+                    Constraint::Length(5),
+                    Constraint::Length(17),
+                    Constraint::Length(24),
+                    Constraint::Length(10),
+                    Constraint::Length(11),
+                    Constraint::Min(10),
+                ],
+            )
+            .header(header)
+            .block(block);
+            // The state only scrolls the table to the selection; rows style themselves
+            let mut table_state = TableState::default().with_selected(Some(state.selected));
+            frame.render_stateful_widget(table, chunks[1], &mut table_state);
+        }
+    }
+
+    // Pending confirmation, or the last change's outcome
+    let line = if let Some(op) = &state.confirm {
+        Some(Line::from(vec![
+            Span::styled(format!("  {} ", confirm_question(op)), theme::warning()),
+            Span::styled("[", theme::dim()),
+            Span::styled("y", theme::key_hint()),
+            Span::styled("/", theme::dim()),
+            Span::styled("n", theme::key_hint()),
+            Span::styled("]", theme::dim()),
+        ]))
+    } else {
+        state.status.as_ref().map(|status| match status {
+            Ok(message) => Line::from(Span::styled(format!("  ✓ {}", message), theme::success())),
+            Err(e) => Line::from(Span::styled(format!("  ✗ {}", e), theme::error())),
+        })
+    };
+    if let Some(line) = line {
+        frame.render_widget(Paragraph::new(line), chunks[2]);
+    }
+
+    // Footer
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓", theme::key_hint()),
+        Span::styled("] Navigate  [", theme::dim()),
+        Span::styled("Space", theme::key_hint()),
+        Span::styled("] Mark  [", theme::dim()),
+        Span::styled("d", theme::key_hint()),
+        Span::styled("] Delete  [", theme::dim()),
+        Span::styled("b", theme::key_hint()),
+        Span::styled("] Boot default  [", theme::dim()),
+        Span::styled("r", theme::key_hint()),
+        Span::styled("] Refresh  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Back", theme::dim()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[3]);
+}
+
+/// One table row: mark, number, date, NixOS version, kernel, closure size and markers
+fn generation_row<'a>(generation: &Generation, selected: bool, state: &GenerationsState) -> Row<'a> {
+    let style = if selected {
+        theme::selected()
+    } else if generation.current {
+        theme::success()
+    } else {
+        theme::text()
+    };
+    let mark = if state.marked.contains(&generation.number) {
+        "[x]"
+    } else {
+        "[ ]"
+    };
+    let mut markers = Vec::new();
+    if generation.current {
+        markers.push("boot default");
+    }
+    if generation.booted {
+        markers.push("running");
+    }
+    Row::new(vec![
+        if selected { ">" } else { " " }.to_string(),
+        mark.to_string(),
+        generation.number.to_string(),
+        generation
+            .created
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "?".to_string()),
+        generation.nixos_version.clone().unwrap_or_else(|| "?".to_string()),
+        generation.kernel.clone().unwrap_or_else(|| "?".to_string()),
+        generation
+            .closure_size
+            .map(format_bytes)
+            .unwrap_or_else(|| "-".to_string()),
+        markers.join(", "),
+    ])
+    .style(style)
+}
+
+fn confirm_question(op: &GenerationsOp) -> String {
+    match op {
+        GenerationsOp::Delete(numbers) if numbers.len() == 1 => {
+            format!("Delete generation {}?", numbers[0])
+        }
+        GenerationsOp::Delete(numbers) => format!(
+            "Delete generations {}?",
+            numbers.iter().map(u32::to_string).collect::<Vec<_>>().join(", ")
+        ),
+        GenerationsOp::SetBootDefault(number) => {
+            format!("Boot generation {} by default (without switching to it now)?", number)
+        }
+    }
+}
@@ -3,19 +3,99 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Row, Table},
+    widgets::{Block, Borders, Clear, Paragraph, Row, Table, Wrap},
     Frame,
 };
 
-use crate::app::{App, CredentialField, InstallCredentials, StepStatus};
+use crate::app::state::secure_boot_blocker;
+use crate::app::{
+    App, CredentialField, InstallCredentials, LocaleField, PartitionField, PartitionInputs,
+    StepStatus,
+};
 use crate::system::config::HostConfig;
-use crate::system::disk::DiskInfo;
+use crate::system::disk::{DiskAssignment, DiskInfo, DiskMode, DiskRole};
+use crate::system::disk::erase::EraseMethod;
+use crate::system::disk::health::DiskHealth;
+use crate::system::disk::layout::{DiskSwap, MAX_ESP_GIB, MIN_ESP_GIB, MIN_ROOT_GIB};
+use crate::system::hardware::SecureBootSupport;
+use crate::system::locale::LocaleSettings;
+use crate::system::optimise::format_bytes;
+use crate::system::preflight::{
+    has_blocking_failures, network_failed, CheckStatus, PreflightCheck,
+};
+use crate::system::wifi::WifiNetwork;
 use crate::ui::layout::{centered_rect, host_selection_layout, progress_layout};
+use crate::ui::screens::build_log_hint;
 use crate::ui::theme;
-use crate::ui::widgets::{LogView, MenuList, ProgressSteps};
+use crate::ui::widgets::{
+    LoadingPanel, LogView, MenuList, PassphraseField, PassphraseInput, ProgressSteps, Spinner,
+};
+
+/// Characters of an optional credentials field shown (the end of longer input)
+const OPTIONAL_FIELD_WIDTH: usize = 40;
+
+/// Draw Live ISO preflight report
+pub fn draw_preflight(frame: &mut Frame, checks: Option<&[PreflightCheck]>, app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(centered_rect(75, 70, area));
+
+    draw_header(frame, chunks[0], "Preflight Checks");
+
+    let Some(checks) = checks else {
+        let running = LoadingPanel::new("Checking install environment...", app.spinner_frame());
+        frame.render_widget(running, chunks[1]);
+        draw_footer(frame, chunks[2], &["Esc Back"]);
+        return;
+    };
+
+    let mut lines = vec![Line::from("")];
+    for check in checks {
+        let (icon, style) = match check.status {
+            CheckStatus::Pass => ("[✓]", theme::success()),
+            CheckStatus::Warn => ("[!]", theme::warning()),
+            CheckStatus::Fail => ("[✗]", theme::error()),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {} ", icon), style),
+            Span::styled(format!("{:<12}", check.name), theme::text()),
+            Span::styled(&check.detail, theme::dim()),
+        ]));
+    }
+
+    let blocked = has_blocking_failures(checks);
+    lines.push(Line::from(""));
+    lines.push(Line::from(if blocked {
+        Span::styled(" Fix the failed checks above, then re-run.", theme::error())
+    } else {
+        Span::styled(" Ready to install.", theme::success())
+    }));
+
+    let report = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(if blocked { theme::error() } else { theme::border() })
+            .title(Span::styled(" Install Environment ", theme::title())),
+    );
+    frame.render_widget(report, chunks[1]);
+
+    if network_failed(checks) {
+        draw_footer(frame, chunks[2], &["w Join WiFi", "r Re-run", "Esc Back"]);
+    } else if blocked {
+        draw_footer(frame, chunks[2], &["r Re-run", "Esc Back"]);
+    } else {
+        draw_footer(frame, chunks[2], &["Enter Continue", "r Re-run", "Esc Back"]);
+    }
+}
 
 /// Draw hostname selection screen
-pub fn draw_host_selection(frame: &mut Frame, selected: usize, hosts: &[HostConfig], _app: &App) {
+pub fn draw_host_selection(frame: &mut Frame, selected: usize, hosts: &[HostConfig], app: &App) {
     let area = frame.area();
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -37,7 +117,12 @@ pub fn draw_host_selection(frame: &mut Frame, selected: usize, hosts: &[HostConf
     items.extend(hosts.iter().map(|h| h.name.clone()));
     let items_ref: Vec<&str> = items.iter().map(|s| s.as_str()).collect();
 
-    let menu = MenuList::new(items_ref, selected).title(" Hosts ");
+    let title = if app.hosts_loading {
+        format!(" Hosts {} ", Spinner::new(app.spinner_frame()).char())
+    } else {
+        " Hosts ".to_string()
+    };
+    let menu = MenuList::new(items_ref, selected).title(&title);
     frame.render_widget(menu, list_area);
 
     // Preview panel
@@ -145,12 +230,16 @@ fn draw_host_preview(frame: &mut Frame, area: Rect, selected: usize, hosts: &[Ho
 }
 
 /// Draw disk selection screen
+#[allow(clippy::too_many_arguments)]
 pub fn draw_disk_selection(
     frame: &mut Frame,
     host: &str,
     disks: &[DiskInfo],
     selected: usize,
-    _app: &App,
+    disk_mode: DiskMode,
+    marked: &[String],
+    error: Option<&str>,
+    app: &App,
 ) {
     let area = frame.area();
     let chunks = Layout::default()
@@ -159,12 +248,22 @@ pub fn draw_disk_selection(
             Constraint::Length(5),
             Constraint::Min(10),
             Constraint::Length(3),
+            Constraint::Length(3),
         ])
         .split(centered_rect(70, 80, area));
 
     // Header
     draw_header(frame, chunks[0], &format!("Select Disk for {}", host));
 
+    // Disks are probed in the background
+    if disks.is_empty() && app.disks_loading {
+        let loading = LoadingPanel::new("Detecting disks...", app.spinner_frame())
+            .title(" Available Disks ");
+        frame.render_widget(loading, chunks[1]);
+        draw_footer(frame, chunks[3], &["Esc Back"]);
+        return;
+    }
+
     // Handle empty disk list
     if disks.is_empty() {
         let message = Paragraph::new(vec![
@@ -185,12 +284,12 @@ pub fn draw_disk_selection(
                 .title(Span::styled(" Available Disks ", theme::title())),
         );
         frame.render_widget(message, chunks[1]);
-        draw_footer(frame, chunks[2], &["Esc Back"]);
+        draw_footer(frame, chunks[3], &["Esc Back"]);
         return;
     }
 
     // Disk table
-    let header = Row::new(vec!["", "Device", "Size", "Model"])
+    let header = Row::new(vec!["", "", "Device", "Size", "Free", "Model"])
         .style(theme::title())
         .bottom_margin(1);
 
@@ -204,10 +303,17 @@ pub fn draw_disk_selection(
             } else {
                 theme::text()
             };
+            let mark = if marked.contains(&disk.path) { "[x]" } else { "[ ]" };
             Row::new(vec![
                 prefix.to_string(),
+                mark.to_string(),
                 disk.path.clone(),
                 disk.size.clone(),
+                if disk.free_bytes > 0 {
+                    format_bytes(disk.free_bytes)
+                } else {
+                    "-".to_string()
+                },
                 disk.model.clone().unwrap_or_default(),
             ])
             .style(style)
@@ -218,8 +324,10 @@ pub fn draw_disk_selection(
         rows,
         [
             Constraint::Length(2),
+            Constraint::Length(4),
             Constraint::Length(15),
             Constraint::Length(10),
+            Constraint::Length(12),
             Constraint::Min(20),
         ],
     )
@@ -233,8 +341,127 @@ pub fn draw_disk_selection(
 
     frame.render_widget(table, chunks[1]);
 
+    // Erase the disk, or keep its partitions and use the free space
+    let mode_line = match error {
+        Some(err) => Line::from(Span::styled(err, theme::error())),
+        None => Line::from(vec![
+            Span::styled("Mode: ", theme::dim()),
+            Span::styled(disk_mode.to_string(), theme::selected()),
+            Span::styled(
+                match disk_mode {
+                    DiskMode::Wipe => "  (all partitions are deleted)",
+                    DiskMode::Alongside => "  (existing partitions are kept, uses free space)",
+                },
+                theme::dim(),
+            ),
+        ]),
+    };
+    // The stable path the disko config will name this disk by
+    let by_id_line = match disks.get(selected).and_then(|d| d.by_id.as_deref()) {
+        Some(by_id) => Line::from(vec![
+            Span::styled("Config path: ", theme::dim()),
+            Span::styled(by_id, theme::text()),
+        ]),
+        None => Line::from(Span::styled("No /dev/disk/by-id name for this disk", theme::dim())),
+    };
+    frame.render_widget(
+        Paragraph::new(vec![mode_line, by_id_line])
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+        chunks[2],
+    );
+
     // Footer
-    draw_footer(frame, chunks[2], &["↑↓ Navigate", "Enter Select", "Esc Back"]);
+    draw_footer(
+        frame,
+        chunks[3],
+        &["↑↓ Navigate", "Space Mark", "Tab Mode", "Enter Select", "Esc Back"],
+    );
+}
+
+/// Draw the role assignment screen for a multi-disk install
+pub fn draw_assign_roles(
+    frame: &mut Frame,
+    host: &str,
+    disks: &[DiskInfo],
+    roles: &[DiskRole],
+    selected: usize,
+    error: Option<&str>,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Min(8),
+            Constraint::Length(3),
+            Constraint::Length(3),
+        ])
+        .split(centered_rect(70, 80, area));
+
+    draw_header(frame, chunks[0], &format!("Disk Roles for {}", host));
+
+    let header = Row::new(vec!["", "Device", "Size", "Role", "Model"])
+        .style(theme::title())
+        .bottom_margin(1);
+    let rows: Vec<Row> = disks
+        .iter()
+        .zip(roles)
+        .enumerate()
+        .map(|(i, (disk, role))| {
+            let (prefix, style) = if i == selected {
+                (">", theme::selected())
+            } else {
+                (" ", theme::text())
+            };
+            Row::new(vec![
+                prefix.to_string(),
+                disk.path.clone(),
+                disk.size.clone(),
+                format!("◀ {} ▶", role),
+                disk.model.clone().unwrap_or_default(),
+            ])
+            .style(style)
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(2),
+            Constraint::Length(15),
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(" Selected Disks ", theme::title())),
+    );
+    frame.render_widget(table, chunks[1]);
+
+    let line = match error {
+        Some(err) => Line::from(Span::styled(err, theme::error())),
+        None => {
+            let role = roles.get(selected).copied().unwrap_or_default();
+            Line::from(vec![
+                Span::styled(format!("{}: ", role), theme::dim()),
+                Span::styled(role.description(), theme::text()),
+                Span::styled("  (all selected disks are erased)", theme::dim()),
+            ])
+        }
+    };
+    frame.render_widget(
+        Paragraph::new(line)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true }),
+        chunks[2],
+    );
+
+    draw_footer(frame, chunks[3], &["↑↓ Navigate", "←→ Role", "Enter Continue", "Esc Back"]);
 }
 
 /// Draw credentials entry screen
@@ -250,13 +477,15 @@ pub fn draw_enter_credentials(
     let area = frame.area();
     let center = centered_rect(65, 70, area);
 
+    // Passphrase, strength meter and confirmation only while encrypting
+    let (fields_height, hints_height) = if credentials.encrypt { (23, 6) } else { (16, 5) };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
             Constraint::Length(4),
-            Constraint::Length(12),
-            Constraint::Length(3),
+            Constraint::Length(fields_height),
+            Constraint::Length(hints_height),
             Constraint::Min(3),
         ])
         .split(center);
@@ -297,7 +526,13 @@ pub fn draw_enter_credentials(
     } else {
         theme::text()
     };
-
+    let field_style = |field: CredentialField| {
+        if *active_field == field {
+            theme::selected()
+        } else {
+            theme::text()
+        }
+    };
     // Mask passwords with asterisks
     let password_masked = "*".repeat(credentials.password.len());
     let confirm_masked = "*".repeat(credentials.confirm_password.len());
@@ -318,6 +553,25 @@ pub fn draw_enter_credentials(
     } else {
         confirm_masked
     };
+    // Optional fields; pasted keys are long, so only their end is shown
+    let optional_display = |value: &str, field: CredentialField| {
+        let focused = *active_field == field;
+        let chars: Vec<char> = value.chars().collect();
+        let mut shown = if chars.len() > OPTIONAL_FIELD_WIDTH {
+            let tail: String = chars[chars.len() - OPTIONAL_FIELD_WIDTH..].iter().collect();
+            format!("…{}", tail)
+        } else {
+            value.to_string()
+        };
+        if focused {
+            shown.push('_');
+        }
+        if value.is_empty() && !focused {
+            Span::styled("(optional)", theme::dim())
+        } else {
+            Span::styled(shown, field_style(field))
+        }
+    };
 
     let mut lines = vec![
         Line::from(""),
@@ -336,16 +590,69 @@ pub fn draw_enter_credentials(
             Span::styled(confirm_display, confirm_style),
         ]),
         Line::from(""),
+        Line::from(vec![
+            Span::styled("  Extra users:      ", theme::dim()),
+            optional_display(&credentials.extra_users, CredentialField::ExtraUsers),
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  SSH keys:         ", theme::dim()),
+            optional_display(&credentials.ssh_keys, CredentialField::SshKeys),
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Disk encryption:  ", theme::dim()),
+            Span::styled(
+                if credentials.encrypt { "[x] LUKS2" } else { "[ ] None" },
+                field_style(CredentialField::Encryption),
+            ),
+        ]),
+        Line::from(""),
     ];
 
+    if credentials.encrypt {
+        let focus = match active_field {
+            CredentialField::Passphrase => Some(PassphraseField::Passphrase),
+            CredentialField::ConfirmPassphrase => Some(PassphraseField::Confirm),
+            _ => None,
+        };
+        lines.extend(
+            PassphraseInput::new(&credentials.passphrase)
+                .confirm(&credentials.confirm_passphrase)
+                .focus(focus)
+                .lines(),
+        );
+        lines.extend([
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("  TPM2 unlock:      ", theme::dim()),
+                Span::styled(
+                    if credentials.tpm2_unlock { "[x] Unlock at boot" } else { "[ ] Off" },
+                    field_style(CredentialField::Tpm2Unlock),
+                ),
+            ]),
+            Line::from(""),
+        ]);
+    }
+
     // Show error if present
     if let Some(err) = error {
         lines.push(Line::from(Span::styled(format!("  ⚠ {}", err), theme::error())));
-    } else {
+    } else if credentials.encrypt && credentials.tpm2_unlock {
         lines.push(Line::from(Span::styled(
-            "  Password will be used for login and LUKS encryption",
+            "  The TPM unlocks the disks at boot; the passphrase stays as a fallback",
             theme::dim(),
         )));
+    } else if credentials.encrypt {
+        lines.push(Line::from(Span::styled(
+            "  The passphrase unlocks the disks at boot; keep it somewhere safe",
+            theme::dim(),
+        )));
+    } else {
+        lines.push(Line::from(Span::styled(
+            "  Without encryption, anyone with the disk can read your data",
+            theme::warning(),
+        )));
     }
 
     let fields = Paragraph::new(lines).block(
@@ -357,13 +664,24 @@ pub fn draw_enter_credentials(
     frame.render_widget(fields, chunks[2]);
 
     // Requirements hint
-    let hints = Paragraph::new(vec![
+    let mut hint_lines = vec![
         Line::from(Span::styled(
             "  Username: lowercase letters, numbers, underscore, hyphen",
             theme::dim(),
         )),
         Line::from(Span::styled("  Password: minimum 8 characters", theme::dim())),
-    ])
+        Line::from(Span::styled(
+            "  SSH keys: gh:<user>, a .pub file or a pasted key, comma separated",
+            theme::dim(),
+        )),
+    ];
+    if credentials.encrypt {
+        hint_lines.push(Line::from(Span::styled(
+            "  Passphrase: minimum 8 characters, stronger than Weak",
+            theme::dim(),
+        )));
+    }
+    let hints = Paragraph::new(hint_lines)
     .block(Block::default().borders(Borders::ALL).border_style(theme::border()));
     frame.render_widget(hints, chunks[3]);
 
@@ -371,24 +689,310 @@ pub fn draw_enter_credentials(
     draw_footer(
         frame,
         chunks[4],
+        &["Tab/↑↓ Switch field", "Space Toggle", "Enter Continue", "Esc Back"],
+    );
+}
+
+/// Draw the timezone, locale and keymap step
+pub fn draw_select_locale(
+    frame: &mut Frame,
+    host: &str,
+    locale: &LocaleSettings,
+    active_field: LocaleField,
+    error: Option<&str>,
+) {
+    let area = frame.area();
+    let center = centered_rect(65, 60, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(10),
+            Constraint::Length(5),
+            Constraint::Min(3),
+        ])
+        .split(center);
+
+    draw_header(frame, chunks[0], "Timezone, Locale and Keyboard");
+
+    let field_line = |label: &str, value: &str, field: LocaleField| {
+        let (shown, style) = if active_field == field {
+            (format!("{}_", value), theme::selected())
+        } else {
+            (value.to_string(), theme::text())
+        };
+        Line::from(vec![
+            Span::styled(format!("  {:<18}", label), theme::dim()),
+            Span::styled(shown, style),
+        ])
+    };
+    let mut lines = vec![
+        Line::from(""),
+        field_line("Timezone:", &locale.timezone, LocaleField::Timezone),
+        Line::from(""),
+        field_line("Locale:", &locale.locale, LocaleField::Locale),
+        Line::from(""),
+        field_line("Console keymap:", &locale.keymap, LocaleField::Keymap),
+        Line::from(""),
+    ];
+    if let Some(err) = error {
+        lines.push(Line::from(Span::styled(format!("  ⚠ {}", err), theme::error())));
+    } else {
+        lines.push(Line::from(Span::styled(
+            "  Detected from the live ISO; written to the host's default.nix",
+            theme::dim(),
+        )));
+    }
+    let fields = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(format!(" {} ", host), theme::title())),
+    );
+    frame.render_widget(fields, chunks[1]);
+
+    let hints = Paragraph::new(vec![
+        Line::from(Span::styled("  Timezone: Region/City, e.g. Europe/Amsterdam or UTC", theme::dim())),
+        Line::from(Span::styled("  Locale: e.g. en_US.UTF-8, de_DE.UTF-8", theme::dim())),
+        Line::from(Span::styled("  Console keymap: e.g. us, uk, de-latin1, fr", theme::dim())),
+    ])
+    .block(Block::default().borders(Borders::ALL).border_style(theme::border()));
+    frame.render_widget(hints, chunks[2]);
+
+    draw_footer(
+        frame,
+        chunks[3],
         &["Tab/↑↓ Switch field", "Enter Continue", "Esc Back"],
     );
 }
 
+/// Draw the warning for a disk that still holds filesystems
+pub fn draw_confirm_existing_data(
+    frame: &mut Frame,
+    host: &str,
+    disk: &DiskInfo,
+    typed: &str,
+    error: Option<&str>,
+) {
+    let partitions = disk.existing_data();
+    let area = frame.area();
+    let center = centered_rect(75, 70, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(partitions.len() as u16 + 4),
+            Constraint::Length(5),
+            Constraint::Length(3),
+        ])
+        .split(center);
+
+    draw_header(frame, chunks[0], "Existing Data");
+
+    let summary = Paragraph::new(Line::from(vec![
+        Span::styled(format!("{} ", disk.path), theme::selected()),
+        Span::styled(
+            format!("({}) has data on it. Installing {} erases all of it.", disk.size, host),
+            theme::warning(),
+        ),
+    ]))
+    .alignment(Alignment::Center)
+    .wrap(Wrap { trim: true });
+    frame.render_widget(summary, chunks[1]);
+
+    let header = Row::new(vec!["Partition", "Label", "Filesystem", "OS", "Size"])
+        .style(theme::title())
+        .bottom_margin(1);
+    let rows: Vec<Row> = partitions
+        .iter()
+        .map(|p| {
+            Row::new(vec![
+                p.path.clone(),
+                p.label.clone().unwrap_or_else(|| "-".to_string()),
+                p.fstype.clone(),
+                p.os_type.as_ref().map_or_else(|| "-".to_string(), |os| os.to_string()),
+                p.size.clone(),
+            ])
+            .style(theme::text())
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(18),
+            Constraint::Min(12),
+            Constraint::Length(11),
+            Constraint::Length(10),
+            Constraint::Length(8),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::warning())
+            .title(Span::styled(" Partitions ", theme::title())),
+    );
+    frame.render_widget(table, chunks[2]);
+
+    let status = match error {
+        Some(err) => Line::from(Span::styled(format!("⚠ {}", err), theme::error())),
+        None => Line::from(Span::styled(
+            "Back up anything you need first; this can't be undone",
+            theme::dim(),
+        )),
+    };
+    let confirm = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled("Type ", theme::text()),
+            Span::styled(disk.name(), theme::selected()),
+            Span::styled(" to erase it: ", theme::text()),
+            Span::styled(format!("{}_", typed), theme::selected()),
+        ]),
+        Line::from(""),
+        status,
+    ])
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border()),
+    );
+    frame.render_widget(confirm, chunks[3]);
+
+    draw_footer(frame, chunks[4], &["Enter Continue", "Esc Back"]);
+}
+
+/// Draw the partition size editor (single-disk erase installs)
+pub fn draw_edit_partitions(
+    frame: &mut Frame,
+    host: &str,
+    disk: &DiskInfo,
+    inputs: &PartitionInputs,
+    active_field: PartitionField,
+    error: Option<&str>,
+) {
+    let area = frame.area();
+    let center = centered_rect(65, 60, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(12),
+            Constraint::Length(5),
+            Constraint::Min(3),
+        ])
+        .split(center);
+
+    draw_header(frame, chunks[0], "Partition Sizes");
+
+    let field_line = |label: &str, value: String, field: PartitionField| {
+        let (shown, style) = if active_field == field {
+            (format!("{}_", value), theme::selected())
+        } else {
+            (value, theme::text())
+        };
+        Line::from(vec![
+            Span::styled(format!("  {:<18}", label), theme::dim()),
+            Span::styled(shown, style),
+        ])
+    };
+    let gib = |value: &str| format!("{} GiB", value);
+    let root = if inputs.root.is_empty() && active_field != PartitionField::Root {
+        "rest of the disk (/home as a subvolume)".to_string()
+    } else {
+        gib(&inputs.root)
+    };
+    let swap_size = if inputs.swap == DiskSwap::None {
+        "-".to_string()
+    } else {
+        gib(&inputs.swap_size)
+    };
+    let mut lines = vec![
+        Line::from(""),
+        field_line("EFI partition:", gib(&inputs.esp), PartitionField::Esp),
+        Line::from(""),
+        field_line("Root partition:", root, PartitionField::Root),
+        Line::from(""),
+        field_line("Swap:", format!("◂ {} ▸", inputs.swap), PartitionField::Swap),
+        Line::from(""),
+        field_line("Swap size:", swap_size, PartitionField::SwapSize),
+        Line::from(""),
+    ];
+    if let Some(err) = error {
+        lines.push(Line::from(Span::styled(format!("  ⚠ {}", err), theme::error())));
+    } else {
+        lines.push(Line::from(Span::styled(
+            format!("  {} ({}) is erased; sizes go into the host's disko file", disk.path, disk.size),
+            theme::dim(),
+        )));
+    }
+    let fields = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(format!(" {} ", host), theme::title())),
+    );
+    frame.render_widget(fields, chunks[1]);
+
+    let hints = Paragraph::new(vec![
+        Line::from(Span::styled(
+            format!("  EFI: {}-{} GiB. Root: leave empty to use the whole disk,", MIN_ESP_GIB, MAX_ESP_GIB),
+            theme::dim(),
+        )),
+        Line::from(Span::styled(
+            format!("  or at least {} GiB; the rest becomes an encrypted /home", MIN_ROOT_GIB),
+            theme::dim(),
+        )),
+        Line::from(Span::styled("  Swap partition: RAM plus a little for hibernation", theme::dim())),
+    ])
+    .block(Block::default().borders(Borders::ALL).border_style(theme::border()));
+    frame.render_widget(hints, chunks[2]);
+
+    draw_footer(
+        frame,
+        chunks[3],
+        &["Tab/↑↓ Switch field", "←→ Swap type", "Enter Continue", "Esc Back"],
+    );
+}
+
 /// Draw overview/confirmation screen
+#[allow(clippy::too_many_arguments)]
 pub fn draw_overview(
     frame: &mut Frame,
     host: &str,
     disk: &DiskInfo,
+    disk_mode: DiskMode,
+    extra_disks: &[DiskAssignment],
     input: &str,
     hardware_config: Option<&crate::app::state::NewHostConfig>,
+    disk_health: &[(String, Option<DiskHealth>)],
+    disk_drift: Option<&str>,
+    credentials: &InstallCredentials,
+    remote: Option<&str>,
+    secure_boot_support: SecureBootSupport,
+    secure_boot: bool,
+    erase_plan: &[(String, EraseMethod)],
+    secure_erase: bool,
+    erase_confirm: Option<&str>,
     _app: &App,
 ) {
     let area = frame.area();
     let center = centered_rect(70, 70, area);
 
+    let accounts = credentials.accounts();
+
     // Calculate details height based on whether we have hardware info
-    let details_height = if hardware_config.is_some() { 10 } else { 6 };
+    let details_height = if hardware_config.is_some() { 12 } else { 8 }
+        + if secure_erase { erase_plan.len() } else { !erase_plan.is_empty() as usize } as u16
+        + !accounts.is_empty() as u16
+        + credentials.locale.is_some() as u16
+        + credentials.partitions.is_some() as u16
+        + extra_disks.len() as u16
+        + disk_health.len() as u16
+        + disk_drift.is_some() as u16
+        + remote.is_some() as u16;
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -401,9 +1005,20 @@ pub fn draw_overview(
         .split(center);
 
     // Warning header
+    let warning_text = match disk_mode {
+        DiskMode::Wipe if !extra_disks.is_empty() => format!(
+            "WARNING: This will ERASE ALL DATA on {} disks!",
+            extra_disks.len() + 1
+        ),
+        DiskMode::Wipe => "WARNING: This will ERASE ALL DATA!".to_string(),
+        DiskMode::Alongside => format!(
+            "Existing partitions are kept; NixOS uses {} of free space",
+            format_bytes(disk.free_bytes)
+        ),
+    };
     let warning = Paragraph::new(Line::from(vec![
         Span::styled("⚠ ", theme::warning()),
-        Span::styled("WARNING: This will ERASE ALL DATA!", theme::warning()),
+        Span::styled(warning_text, theme::warning()),
     ]))
     .alignment(Alignment::Center)
     .block(
@@ -421,11 +1036,46 @@ pub fn draw_overview(
         Span::styled(host, theme::text()),
     ]));
 
+    if let Some(target) = remote {
+        detail_lines.push(Line::from(vec![
+            Span::styled("  Target:   ", theme::dim()),
+            Span::styled(target, theme::text()),
+            Span::styled(" (nixos-anywhere over SSH)", theme::dim()),
+        ]));
+    }
+
     detail_lines.push(Line::from(vec![
         Span::styled("  Disk:     ", theme::dim()),
         Span::styled(&disk.path, theme::text()),
         Span::styled(format!(" ({})", disk.size), theme::dim()),
     ]));
+    for extra in extra_disks {
+        detail_lines.push(Line::from(vec![
+            Span::styled(format!("  {:<10}", format!("{}:", extra.role)), theme::dim()),
+            Span::styled(&extra.disk.path, theme::text()),
+            Span::styled(format!(" ({})", extra.disk.size), theme::dim()),
+        ]));
+    }
+
+    // Drive health, so a dying disk is noticed before it is wiped
+    for (path, health) in disk_health {
+        let (text, style) = match health {
+            Some(health) if !health.warnings().is_empty() => {
+                (format!("⚠ {}", health.summary()), theme::error())
+            }
+            Some(health) => (health.summary(), theme::success()),
+            None => ("not reported (no SMART data)".to_string(), theme::dim()),
+        };
+        detail_lines.push(Line::from(vec![
+            Span::styled("  Health:   ", theme::dim()),
+            Span::styled(format!("{} ", path), theme::dim()),
+            Span::styled(text, style),
+        ]));
+    }
+
+    if let Some(drift) = disk_drift {
+        detail_lines.push(Line::from(Span::styled(format!("  ⚠ {}", drift), theme::error())));
+    }
 
     // Add hardware info if available (new host)
     if let Some(hw) = hardware_config {
@@ -443,6 +1093,76 @@ pub fn draw_overview(
         ]));
     }
 
+    detail_lines.push(Line::from(vec![
+        Span::styled("  Encryption: ", theme::dim()),
+        if credentials.encrypt && credentials.tpm2_unlock {
+            Span::styled("LUKS2 (own passphrase, TPM2 auto-unlock)", theme::text())
+        } else if credentials.encrypt {
+            Span::styled("LUKS2 (own passphrase)", theme::text())
+        } else {
+            Span::styled("none", theme::warning())
+        },
+    ]));
+
+    if let Some(partitions) = &credentials.partitions {
+        detail_lines.push(Line::from(vec![
+            Span::styled("  Layout:   ", theme::dim()),
+            Span::styled(partitions.describe(), theme::text()),
+        ]));
+    }
+
+    if let Some(locale) = &credentials.locale {
+        detail_lines.push(Line::from(vec![
+            Span::styled("  Locale:   ", theme::dim()),
+            Span::styled(
+                format!("{}, {}, keymap {}", locale.timezone, locale.locale, locale.keymap),
+                theme::text(),
+            ),
+        ]));
+    }
+
+    if !accounts.is_empty() {
+        let mut summary: Vec<String> = accounts.users.iter().map(|u| format!("+{}", u)).collect();
+        if !accounts.key_sources.is_empty() {
+            summary.push(format!(
+                "SSH keys for {} from {} source(s)",
+                credentials.username,
+                accounts.key_sources.len()
+            ));
+        }
+        detail_lines.push(Line::from(vec![
+            Span::styled("  Accounts: ", theme::dim()),
+            Span::styled(summary.join(", "), theme::text()),
+        ]));
+    }
+
+    let blocker = match remote {
+        Some(_) => Some("local installs only".to_string()),
+        None => secure_boot_blocker(secure_boot_support, disk_mode),
+    };
+    detail_lines.push(Line::from(vec![
+        Span::styled("  Secure Boot: ", theme::dim()),
+        match (&blocker, secure_boot) {
+            (Some(reason), _) => Span::styled(format!("off ({})", reason), theme::dim()),
+            (None, true) => Span::styled("enroll keys, boot with lanzaboote", theme::success()),
+            (None, false) => Span::styled("off (Tab to enroll keys)", theme::text()),
+        },
+    ]));
+
+    if secure_erase {
+        for (path, method) in erase_plan {
+            detail_lines.push(Line::from(vec![
+                Span::styled("  Secure erase: ", theme::dim()),
+                Span::styled(format!("{} - {}", path, method.describe()), theme::warning()),
+            ]));
+        }
+    } else if !erase_plan.is_empty() {
+        detail_lines.push(Line::from(vec![
+            Span::styled("  Secure erase: ", theme::dim()),
+            Span::styled("off (Shift+Tab to erase before partitioning)", theme::text()),
+        ]));
+    }
+
     detail_lines.push(Line::from(""));
 
     let details = Paragraph::new(detail_lines).block(
@@ -471,7 +1191,163 @@ pub fn draw_overview(
     frame.render_widget(prompt, chunks[2]);
 
     // Footer
-    draw_footer(frame, chunks[3], &["Type 'yes' + Enter", "Esc Cancel"]);
+    let mut hints = vec!["Type 'yes' + Enter"];
+    if blocker.is_none() {
+        hints.push("Tab Secure Boot");
+    }
+    if !erase_plan.is_empty() {
+        hints.push("Shift+Tab Secure erase");
+    }
+    hints.push("Esc Cancel");
+    draw_footer(frame, chunks[3], &hints);
+
+    if let Some(typed) = erase_confirm {
+        draw_erase_confirm(frame, area, erase_plan, typed);
+    }
+}
+
+/// Second confirmation before a secure erase: typing 'erase' is required
+fn draw_erase_confirm(
+    frame: &mut Frame,
+    area: Rect,
+    erase_plan: &[(String, EraseMethod)],
+    typed: &str,
+) {
+    let popup_width = 70.min(area.width);
+    let popup_height = 9 + erase_plan.len() as u16;
+    let x = area.x + (area.width.saturating_sub(popup_width)) / 2;
+    let y = area.y + (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(x, y, popup_width, popup_height);
+
+    // Clear the area behind the popup
+    frame.render_widget(Clear, popup_area);
+
+    let (question, warning) = match erase_plan.len() {
+        1 => ("Securely erase this disk?", "Nothing on it can be recovered afterwards."),
+        _ => ("Securely erase all of these disks?", "Nothing on them can be recovered afterwards."),
+    };
+    let mut lines = vec![Line::from(""), Line::from(Span::styled(question, theme::text()))];
+    lines.extend(erase_plan.iter().map(|(path, method)| {
+        Line::from(vec![
+            Span::styled(path.as_str(), theme::text()),
+            Span::styled(format!(" - {}", method.describe()), theme::dim()),
+        ])
+    }));
+    lines.extend([
+        Line::from(Span::styled(warning, theme::warning())),
+        Line::from(""),
+        Line::from(Span::styled("Type 'erase' + Enter to continue:", theme::text())),
+        Line::from(vec![
+            Span::styled("> ", theme::info()),
+            Span::styled(typed, theme::text()),
+            Span::styled("_", theme::info()),
+        ]),
+    ]);
+    let content = Paragraph::new(lines)
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::warning())
+            .title(Span::styled(" Secure Erase ", theme::warning())),
+    );
+    frame.render_widget(content, popup_area);
+}
+
+/// Draw the WiFi setup shown when the install's network check failed
+#[allow(clippy::too_many_arguments)]
+pub fn draw_network_setup(
+    frame: &mut Frame,
+    networks: Option<&[WifiNetwork]>,
+    selected: usize,
+    passphrase: Option<&str>,
+    connecting: bool,
+    error: Option<&str>,
+    app: &App,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(centered_rect(75, 70, area));
+
+    draw_header(frame, chunks[0], "Network Setup");
+
+    let spinner = Spinner::new(app.spinner_frame()).char().to_string();
+    let mut lines = vec![
+        Line::from(Span::styled(
+            " No network connection. Join a wireless network to continue the install.",
+            theme::text(),
+        )),
+        Line::from(""),
+    ];
+    let networks = match networks {
+        Some([]) if error.is_none() => {
+            lines.push(Line::from(Span::styled(" No networks found.", theme::dim())));
+            &[]
+        }
+        Some(networks) => networks,
+        None => {
+            lines.push(Line::from(vec![
+                Span::styled(format!(" {}", spinner), theme::info()),
+                Span::styled(" Scanning for wireless networks...", theme::text()),
+            ]));
+            &[]
+        }
+    };
+    for (i, network) in networks.iter().enumerate() {
+        let style = if i == selected { theme::selected() } else { theme::text() };
+        lines.push(Line::from(vec![
+            Span::styled(if i == selected { " > " } else { "   " }, style),
+            Span::styled(format!("{:<32}", network.ssid), style),
+            Span::styled(format!("{:>4}%  ", network.signal), theme::dim()),
+            Span::styled(if network.secured { "secured" } else { "open" }, theme::dim()),
+        ]));
+    }
+    let chosen = networks.get(selected).map(|n| n.ssid.as_str()).unwrap_or_default();
+    if connecting {
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {}", spinner), theme::info()),
+            Span::styled(format!(" Connecting to {}...", chosen), theme::text()),
+        ]));
+    } else if let Some(typed) = passphrase {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(format!(" Passphrase for {}:", chosen), theme::dim())));
+        lines.extend(
+            PassphraseInput::new(typed)
+                .focus(Some(PassphraseField::Passphrase))
+                .lines(),
+        );
+    }
+    if let Some(error) = error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(format!(" {}", error), theme::error())));
+    }
+
+    let body = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(Span::styled(" Wireless Networks ", theme::title())),
+    );
+    frame.render_widget(body, chunks[1]);
+
+    if connecting {
+        draw_footer(frame, chunks[2], &[]);
+    } else if passphrase.is_some() {
+        draw_footer(frame, chunks[2], &["Enter Connect", "Esc Cancel"]);
+    } else {
+        draw_footer(
+            frame,
+            chunks[2],
+            &["↑↓ Navigate", "Enter Connect", "r Rescan", "s Retry check", "Esc Back"],
+        );
+    }
 }
 
 /// Draw running installation screen
@@ -512,7 +1388,7 @@ pub fn draw_running(
     // Progress and output
     let (steps_area, output_area) = progress_layout(chunks[1]);
 
-    let progress = ProgressSteps::new(steps, app.spinner_state).title(" Progress ");
+    let progress = ProgressSteps::new(steps, app.spinner_frame()).title(" Progress ");
     frame.render_widget(progress, steps_area);
 
     let log = LogView::new(output).title(" Output ");
@@ -534,7 +1410,8 @@ pub fn draw_complete(
     success: bool,
     output: &[String],
     scroll_offset: Option<usize>,
-    _app: &App,
+    rescue: bool,
+    app: &App,
 ) {
     let area = frame.area();
     let chunks = Layout::default()
@@ -569,16 +1446,29 @@ pub fn draw_complete(
     frame.render_widget(log, chunks[1]);
 
     // Footer
-    let footer = Paragraph::new(Line::from(vec![
+    let mut hints = vec![
         Span::styled("[", theme::dim()),
         Span::styled("↑↓", theme::key_hint()),
         Span::styled("] Scroll  [", theme::dim()),
+    ];
+    hints.extend(build_log_hint(app));
+    if rescue {
+        hints.extend([
+            Span::styled("s", theme::key_hint()),
+            Span::styled("] Shell  [", theme::dim()),
+            Span::styled("r", theme::key_hint()),
+            Span::styled("] Retry  [", theme::dim()),
+        ]);
+    }
+    hints.extend([
+        Span::styled("b", theme::key_hint()),
+        Span::styled("] Save logs  [", theme::dim()),
         Span::styled("Enter", theme::key_hint()),
         Span::styled("] Done  [", theme::dim()),
         Span::styled("q", theme::key_hint()),
         Span::styled("] Quit", theme::dim()),
-    ]))
-    .alignment(Alignment::Center);
+    ]);
+    let footer = Paragraph::new(Line::from(hints)).alignment(Alignment::Center);
     frame.render_widget(footer, chunks[2]);
 }
 
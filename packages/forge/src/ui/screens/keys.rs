@@ -7,9 +7,92 @@ use ratatui::{
     Frame,
 };
 
-use crate::app::{App, KeysOp};
+use crate::app::{App, KeysOp, PassphrasePrompt};
+use crate::ui::layout::centered_rect;
 use crate::ui::theme;
-use crate::ui::widgets::LogView;
+use crate::ui::widgets::{LogView, PassphraseField, PassphraseInput};
+
+/// Draw the archive passphrase prompt
+pub fn draw_passphrase(
+    frame: &mut Frame,
+    operation: &KeysOp,
+    prompt: &PassphrasePrompt,
+    _app: &App,
+) {
+    let mut input = PassphraseInput::new(&prompt.passphrase)
+        .labels("Keys Passphrase:", "Confirm:")
+        .focus(Some(if prompt.on_confirm {
+            PassphraseField::Confirm
+        } else {
+            PassphraseField::Passphrase
+        }));
+    if prompt.needs_confirm {
+        input = input.confirm(&prompt.confirm);
+    }
+
+    let area = centered_rect(60, 50, frame.area());
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(input.height() + 2),
+            Constraint::Length(2),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .split(area);
+
+    let title = match operation {
+        KeysOp::Restore => " Restore Keys ",
+        _ => " Back Up Keys ",
+    };
+    let header = Paragraph::new(Line::from(Span::styled(title, theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme::border());
+    let inner = block.inner(chunks[1]);
+    frame.render_widget(block, chunks[1]);
+    frame.render_widget(input, inner);
+
+    let message = match &prompt.error {
+        Some(error) => Line::from(Span::styled(format!("  {}", error), theme::error())),
+        None if prompt.needs_confirm => Line::from(Span::styled(
+            "  Choose the passphrase that encrypts the keys archive",
+            theme::dim(),
+        )),
+        None => Line::from(Span::styled(
+            "  Passphrase the keys archive was backed up with",
+            theme::dim(),
+        )),
+    };
+    frame.render_widget(Paragraph::new(message), chunks[2]);
+
+    let mut hints = vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("Enter", theme::key_hint()),
+        Span::styled("] Continue  [", theme::dim()),
+    ];
+    if prompt.needs_confirm {
+        hints.extend([
+            Span::styled("Tab", theme::key_hint()),
+            Span::styled("] Switch field  [", theme::dim()),
+        ]);
+    }
+    hints.extend([
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Cancel", theme::dim()),
+    ]);
+    let footer = Paragraph::new(Line::from(hints)).alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[4]);
+}
 
 /// Draw running operation screen
 pub fn draw_running(frame: &mut Frame, operation: &KeysOp, output: &[String], app: &App) {
@@ -29,6 +112,7 @@ pub fn draw_running(frame: &mut Frame, operation: &KeysOp, output: &[String], ap
         KeysOp::Backup => " Backing Up Keys ",
         KeysOp::Restore => " Restoring Keys ",
         KeysOp::Status => " Key Status ",
+        KeysOp::GitHub { .. } => " Registering Key with GitHub ",
     };
     let header = Paragraph::new(Line::from(Span::styled(title, theme::title())))
         .alignment(Alignment::Center)
@@ -41,7 +125,7 @@ pub fn draw_running(frame: &mut Frame, operation: &KeysOp, output: &[String], ap
 
     // Output with spinner
     let spinner_char = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏']
-        [app.spinner_state % 10];
+        [app.spinner_frame() % 10];
     let title = format!(" {} Running... ", spinner_char);
     let log = LogView::new(output).title(&title);
     frame.render_widget(log, chunks[1]);
@@ -62,6 +146,7 @@ pub fn draw_complete(
     success: bool,
     output: &[String],
     scroll_offset: Option<usize>,
+    offer_github: bool,
     _app: &App,
 ) {
     let area = frame.area();
@@ -97,15 +182,23 @@ pub fn draw_complete(
     frame.render_widget(log, chunks[1]);
 
     // Footer
-    let footer = Paragraph::new(Line::from(vec![
+    let mut hints = vec![
         Span::styled("[", theme::dim()),
         Span::styled("↑↓", theme::key_hint()),
         Span::styled("] Scroll  [", theme::dim()),
+    ];
+    if offer_github {
+        hints.extend([
+            Span::styled("g", theme::key_hint()),
+            Span::styled("] Register SSH key with GitHub  [", theme::dim()),
+        ]);
+    }
+    hints.extend([
         Span::styled("Enter", theme::key_hint()),
         Span::styled("] Done  [", theme::dim()),
         Span::styled("q", theme::key_hint()),
         Span::styled("] Quit", theme::dim()),
-    ]))
-    .alignment(Alignment::Center);
+    ]);
+    let footer = Paragraph::new(Line::from(hints)).alignment(Alignment::Center);
     frame.render_widget(footer, chunks[2]);
 }
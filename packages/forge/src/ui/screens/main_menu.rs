@@ -7,7 +7,7 @@ use ratatui::{
     Frame,
 };
 
-use crate::app::{App, MAIN_MENU_ITEMS};
+use crate::app::{App, AppMode, MAIN_MENU_ITEMS};
 use crate::ui::layout::centered_rect;
 use crate::ui::theme;
 use crate::ui::widgets::{MenuList, Spinner};
@@ -121,14 +121,32 @@ fn draw_footer(frame: &mut Frame, area: Rect, app: &App) {
         Span::styled("] Quit", theme::dim()),
     ];
 
+    // Update system can also run with an input overridden by a local checkout
+    if matches!(app.mode, AppMode::MainMenu { selected: 1 }) {
+        spans.push(Span::styled("  [", theme::dim()));
+        spans.push(Span::styled("o", theme::key_hint()));
+        spans.push(Span::styled("] Override input", theme::dim()));
+    }
+
     // Show checking indicator when startup check is running
     if app.startup_check_running {
-        let spinner_char = Spinner::new(app.spinner_state).char();
+        let spinner_char = Spinner::new(app.spinner_frame()).char();
         spans.push(Span::styled("     ", theme::dim()));
         spans.push(Span::styled(format!("{} ", spinner_char), theme::dim()));
         spans.push(Span::styled("Checking for updates...", theme::dim()));
     }
 
+    // Config repo branch and sync state, refreshed in the background
+    if let Some(status) = &app.repo_status {
+        let style = if status.changed > 0 || status.ahead > 0 || status.behind > 0 {
+            theme::warning()
+        } else {
+            theme::dim()
+        };
+        spans.push(Span::styled("     ", theme::dim()));
+        spans.push(Span::styled(format!("git: {}", status.summary()), style));
+    }
+
     let hints = Line::from(spans);
     let footer = Paragraph::new(hints).alignment(Alignment::Center);
     frame.render_widget(footer, area);
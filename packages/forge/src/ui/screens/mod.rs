@@ -1,8 +1,62 @@
 //! Screen modules
 
+use ratatui::text::Span;
+
+use crate::app::App;
+use crate::ui::theme;
+
 pub mod apps;
+pub mod bisect;
+pub mod bootstrap;
+pub mod channels;
 pub mod create_host;
+pub mod devshells;
+pub mod fleet;
+pub mod gc;
+pub mod generations;
 pub mod install;
 pub mod keys;
 pub mod main_menu;
+pub mod nix_conf;
+pub mod optimise;
+pub mod parallelism;
+pub mod pipeline;
+pub mod registry;
+pub mod rollback;
+pub mod services;
 pub mod update;
+
+/// `[c] Changelog` while the flake input changelog is available
+pub fn changelog_hint(app: &App) -> Vec<Span<'static>> {
+    if !app.changelog_available() {
+        return Vec::new();
+    }
+    vec![
+        Span::styled("c", theme::key_hint()),
+        Span::styled("] Changelog  [", theme::dim()),
+    ]
+}
+
+/// `[e] Export` while the update report can be exported
+pub fn report_hint(app: &App) -> Vec<Span<'static>> {
+    if !app.report_available() {
+        return Vec::new();
+    }
+    vec![
+        Span::styled("e", theme::key_hint()),
+        Span::styled("] Export  [", theme::dim()),
+    ]
+}
+
+/// Footer hint for the failed build log viewer, when this run saved any logs
+///
+/// Goes right after an opening `[`; ends with one for the next hint.
+pub fn build_log_hint(app: &App) -> Vec<Span<'static>> {
+    if !app.build_log_available() {
+        return Vec::new();
+    }
+    vec![
+        Span::styled("l", theme::key_hint()),
+        Span::styled("] Failed build log  [", theme::dim()),
+    ]
+}
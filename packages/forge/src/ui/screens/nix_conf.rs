@@ -0,0 +1,171 @@
+//! nix.conf settings screen (nix-conf.json)
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::app::{App, NixConfState};
+use crate::system::nix_conf::{NixConfField, SYSTEM_NIX_CONF};
+use crate::ui::layout::centered_rect;
+use crate::ui::theme;
+use crate::ui::widgets::Spinner;
+
+pub fn draw(frame: &mut Frame, state: &NixConfState, app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(9),
+            Constraint::Length(2),
+            Constraint::Length(2),
+        ])
+        .split(centered_rect(75, 80, area));
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(" Nix Settings ", theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    // Scope tabs: all hosts, then each host
+    let mut tabs = Vec::new();
+    let scopes = std::iter::once("All hosts").chain(state.hosts.iter().map(String::as_str));
+    for (i, scope) in scopes.enumerate() {
+        let style = if i == state.scope {
+            theme::selected()
+        } else {
+            theme::dim()
+        };
+        tabs.push(Span::styled(format!(" {} ", scope), style));
+    }
+    frame.render_widget(Paragraph::new(Line::from(tabs)).alignment(Alignment::Center), chunks[1]);
+
+    // Settings for the selected scope
+    let host = state.host();
+    let lines = match &state.config {
+        None => {
+            let spinner_char = Spinner::new(app.spinner_frame()).char();
+            vec![Line::from(Span::styled(
+                format!("  {} Working...", spinner_char),
+                theme::dim(),
+            ))]
+        }
+        Some(Err(e)) => vec![Line::from(Span::styled(format!("  {}", e), theme::error()))],
+        Some(Ok(config)) => {
+            let settings = config.resolve(host);
+            let mut lines = vec![Line::from("")];
+            for (i, field) in NixConfField::ALL.iter().enumerate() {
+                let selected = i == state.selected;
+                let (prefix, label_style) = if selected {
+                    ("> ", theme::selected())
+                } else {
+                    ("  ", theme::text())
+                };
+                let value = match &state.input {
+                    Some(input) if selected => {
+                        Span::styled(format!("{}_", input), theme::selected())
+                    }
+                    _ => Span::styled(settings.value(*field), theme::info()),
+                };
+                // Where the value comes from, so overrides stand out
+                let source = if config.is_set(host, *field) {
+                    if host.is_some() { "  (this host)" } else { "" }
+                } else if host.is_some() {
+                    "  (all hosts)"
+                } else {
+                    "  (default)"
+                };
+                let mut spans = vec![
+                    Span::styled(format!("{}{:<24}", prefix, field.label()), label_style),
+                    value,
+                    Span::styled(source, theme::dim()),
+                ];
+                // This machine still running with something else until it rebuilds
+                if let Some(live) = state.live_value(*field) {
+                    if !settings.matches(*field, live) {
+                        spans.push(Span::styled(
+                            format!("  ⟳ now {}", live),
+                            theme::warning(),
+                        ));
+                    }
+                }
+                lines.push(Line::from(spans));
+            }
+            lines.push(Line::from(""));
+            match (&state.input_error, &state.live) {
+                (Some(err), _) => lines.push(Line::from(Span::styled(
+                    format!("  ⚠ {}", err),
+                    theme::error(),
+                ))),
+                (None, Some(Err(e))) if host.is_some() && host == state.this_host.as_deref() => {
+                    lines.push(Line::from(Span::styled(format!("  {}", e), theme::dim())))
+                }
+                _ => lines.push(Line::from(Span::styled(
+                    "  Features: space-separated  Max jobs: number or auto  Cores: 0 = all",
+                    theme::dim(),
+                ))),
+            }
+            lines
+        }
+    };
+    let title = match host {
+        Some(h) if Some(h) == state.this_host.as_deref() => {
+            format!(" {} (overrides, compared with {}) ", h, SYSTEM_NIX_CONF)
+        }
+        Some(h) => format!(" {} (overrides) ", h),
+        None => " nix-conf.json ".to_string(),
+    };
+    let settings = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(title),
+    );
+    frame.render_widget(settings, chunks[2]);
+
+    // Last save
+    if let Some(status) = &state.status {
+        let line = match status {
+            Ok(message) => Line::from(Span::styled(format!("  ✓ {}", message), theme::success())),
+            Err(e) => Line::from(Span::styled(format!("  ✗ {}", e), theme::error())),
+        };
+        frame.render_widget(Paragraph::new(line), chunks[3]);
+    }
+
+    // Footer
+    let footer = if state.input.is_some() {
+        Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("Enter", theme::key_hint()),
+            Span::styled("] Save  [", theme::dim()),
+            Span::styled("Esc", theme::key_hint()),
+            Span::styled("] Cancel", theme::dim()),
+        ])
+    } else {
+        Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("←→", theme::key_hint()),
+            Span::styled("] Host  [", theme::dim()),
+            Span::styled("Enter", theme::key_hint()),
+            Span::styled("] Change  [", theme::dim()),
+            Span::styled("x", theme::key_hint()),
+            Span::styled("] Inherit  [", theme::dim()),
+            Span::styled("p", theme::key_hint()),
+            Span::styled("] Per-run jobs  [", theme::dim()),
+            Span::styled("r", theme::key_hint()),
+            Span::styled("] Reload  [", theme::dim()),
+            Span::styled("Esc", theme::key_hint()),
+            Span::styled("] Back", theme::dim()),
+        ])
+    };
+    frame.render_widget(Paragraph::new(footer).alignment(Alignment::Center), chunks[4]);
+}
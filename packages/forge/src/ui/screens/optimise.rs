@@ -0,0 +1,125 @@
+//! Store optimise screen (nix store optimise progress), also shown for
+//! `forge gc run` with the garbage collection before it
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Gauge, Paragraph},
+    Frame,
+};
+
+use crate::app::{App, GcRun};
+use crate::system::optimise::{format_bytes, OptimiseProgress};
+use crate::ui::theme;
+use crate::ui::widgets::{LogView, Spinner};
+
+pub fn draw(
+    frame: &mut Frame,
+    progress: &OptimiseProgress,
+    gc: Option<&GcRun>,
+    output: &[String],
+    complete: Option<bool>,
+    scroll_offset: Option<usize>,
+    app: &App,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(2),
+        ])
+        .split(area);
+
+    // Header
+    let (title, style) = match (complete, gc) {
+        (None, None) => (" Optimising Store ".to_string(), theme::title()),
+        (Some(true), None) => (" Store Optimised ".to_string(), theme::success()),
+        (Some(false), None) => (" Store Optimise Failed ".to_string(), theme::error()),
+        (None, Some(_)) => (" Collecting Garbage ".to_string(), theme::title()),
+        (Some(true), Some(_)) => (" Garbage Collected ".to_string(), theme::success()),
+        (Some(false), Some(_)) => (" Garbage Collection Failed ".to_string(), theme::error()),
+    };
+    let header = Paragraph::new(Line::from(Span::styled(title, style)))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    // Paths scanned
+    let collecting = gc.is_some_and(|gc| gc.freed.is_none());
+    let label = if collecting && complete.is_none() {
+        let spinner_char = Spinner::new(app.spinner_frame()).char();
+        format!("{} Deleting old generations and unreferenced store paths...", spinner_char)
+    } else if progress.paths_total == 0 {
+        let spinner_char = Spinner::new(app.spinner_frame()).char();
+        format!("{} Listing store paths...", spinner_char)
+    } else {
+        format!("{} / {} store paths", progress.paths_done, progress.paths_total)
+    };
+    let gauge = Gauge::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border()),
+        )
+        .gauge_style(theme::info())
+        .percent(progress.percent())
+        .label(label);
+    frame.render_widget(gauge, chunks[1]);
+
+    // Savings so far
+    let mut spans = Vec::new();
+    if let Some(freed) = gc.and_then(|gc| gc.freed) {
+        spans.push(Span::styled("Collected: ", theme::dim()));
+        spans.push(Span::styled(format_bytes(freed), theme::success()));
+        spans.push(Span::styled("    ", theme::dim()));
+    }
+    spans.extend([
+        Span::styled("Hard-linked files: ", theme::dim()),
+        Span::styled(progress.files_linked.to_string(), theme::text()),
+        Span::styled("    Saved: ", theme::dim()),
+        Span::styled(format_bytes(progress.bytes_saved), theme::success()),
+    ]);
+    let stats = Paragraph::new(Line::from(spans))
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border()),
+    );
+    frame.render_widget(stats, chunks[2]);
+
+    let mut log = LogView::new(output).title(" Output ");
+    if let Some(offset) = scroll_offset {
+        log = log.scroll_offset(offset);
+    }
+    frame.render_widget(log, chunks[3]);
+
+    // Footer
+    let footer = if complete.is_some() {
+        Paragraph::new(Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("↑↓", theme::key_hint()),
+            Span::styled("] Scroll  [", theme::dim()),
+            Span::styled("Enter", theme::key_hint()),
+            Span::styled("] Done  [", theme::dim()),
+            Span::styled("q", theme::key_hint()),
+            Span::styled("] Quit", theme::dim()),
+        ]))
+    } else {
+        Paragraph::new(Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("Ctrl+C", theme::key_hint()),
+            Span::styled("] Cancel", theme::dim()),
+        ]))
+    }
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[4]);
+}
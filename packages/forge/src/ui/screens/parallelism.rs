@@ -0,0 +1,136 @@
+//! Per-run build parallelism screen (`forge parallelism`)
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::app::ParallelismState;
+use crate::system::parallelism::{suggest, BuildParallelism};
+use crate::ui::layout::centered_rect;
+use crate::ui::theme;
+
+pub fn draw(frame: &mut Frame, state: &ParallelismState) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(12),
+            Constraint::Length(2),
+            Constraint::Length(2),
+        ])
+        .split(centered_rect(75, 80, area));
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(" Build Parallelism ", theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    let topology = state.topology;
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  CPU                     ", theme::text()),
+            Span::styled(
+                format!(
+                    "{} cores, {} threads, {} GiB memory",
+                    topology.cores, topology.threads, state.memory_gib
+                ),
+                theme::info(),
+            ),
+        ]),
+        Line::from(""),
+    ];
+
+    // The two settings; unset ones show what nix.conf has
+    let fields = [
+        ("Max jobs", state.value.max_jobs, "max-jobs"),
+        ("Cores per build", state.value.cores, "cores"),
+    ];
+    for (i, (label, value, key)) in fields.into_iter().enumerate() {
+        let (prefix, label_style) = if i == state.selected {
+            ("> ", theme::selected())
+        } else {
+            ("  ", theme::text())
+        };
+        let value = match value {
+            Some(value) => Span::styled(format!("◂ {} ▸", value), theme::info()),
+            None => Span::styled(
+                format!(
+                    "◂ nix.conf ({}) ▸",
+                    state.nix_conf.get(key).map_or("unknown", String::as_str)
+                ),
+                theme::dim(),
+            ),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!("{}{:<24}", prefix, label), label_style),
+            value,
+        ]));
+    }
+
+    // Live preview of what a rebuild would run with
+    let (jobs, cores) = state.value.effective(&state.nix_conf, topology.threads);
+    lines.push(Line::from(""));
+    lines.push(Line::from(vec![
+        Span::styled("  Preview                 ", theme::text()),
+        Span::styled(BuildParallelism::describe(jobs, cores, topology), theme::info()),
+    ]));
+    if BuildParallelism::oversubscribed(jobs, cores, topology) {
+        lines.push(Line::from(Span::styled(
+            "  ⚠ More compilers than the CPU can keep up with; builds will thrash",
+            theme::warning(),
+        )));
+    }
+    let suggested = suggest(topology, state.memory_gib);
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!(
+            "  Suggested: max-jobs {}, cores {} (a job per 4 cores, 8 GiB each)",
+            suggested.max_jobs.unwrap_or(1),
+            suggested.cores.unwrap_or(0)
+        ),
+        theme::dim(),
+    )));
+
+    let settings = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(format!(" {} (forge update and preview builds) ", state.host)),
+    );
+    frame.render_widget(settings, chunks[1]);
+
+    // Last save
+    if let Some(status) = &state.status {
+        let line = match status {
+            Ok(message) => Line::from(Span::styled(format!("  ✓ {}", message), theme::success())),
+            Err(e) => Line::from(Span::styled(format!("  ✗ {}", e), theme::error())),
+        };
+        frame.render_widget(Paragraph::new(line), chunks[2]);
+    }
+
+    // Footer
+    let footer = Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("←→", theme::key_hint()),
+        Span::styled("] Adjust  [", theme::dim()),
+        Span::styled("s", theme::key_hint()),
+        Span::styled("] Suggested  [", theme::dim()),
+        Span::styled("x", theme::key_hint()),
+        Span::styled("] Use nix.conf  [", theme::dim()),
+        Span::styled("Enter", theme::key_hint()),
+        Span::styled("] Save  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Back", theme::dim()),
+    ]);
+    frame.render_widget(Paragraph::new(footer).alignment(Alignment::Center), chunks[3]);
+}
@@ -0,0 +1,135 @@
+//! Repo-defined pipeline screens (pipelines.nix)
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::app::{App, StepStatus};
+use crate::system::pipeline::Pipeline;
+use crate::ui::layout::{centered_rect, progress_layout};
+use crate::ui::screens::build_log_hint;
+use crate::ui::theme;
+use crate::ui::widgets::{LogView, MenuList, ProgressSteps};
+
+/// Draw the pipeline picker
+pub fn draw_menu(frame: &mut Frame, pipelines: &[Pipeline], selected: usize, _app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(centered_rect(60, 80, area));
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(" Pipelines ", theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    // Menu
+    let items: Vec<String> = pipelines
+        .iter()
+        .map(|p| match &p.description {
+            Some(description) => format!("{} - {}", p.name, description),
+            None => p.name.clone(),
+        })
+        .collect();
+    let menu = MenuList::new(items.iter().map(String::as_str).collect(), selected);
+    frame.render_widget(menu, chunks[1]);
+
+    // Footer
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓", theme::key_hint()),
+        Span::styled("] Navigate  [", theme::dim()),
+        Span::styled("Enter", theme::key_hint()),
+        Span::styled("] Run  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Back", theme::dim()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[2]);
+}
+
+/// Draw running/complete pipeline screen
+pub fn draw_running(
+    frame: &mut Frame,
+    name: &str,
+    steps: &[StepStatus],
+    output: &[String],
+    complete: Option<bool>,
+    scroll_offset: Option<usize>,
+    app: &App,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(2),
+        ])
+        .split(area);
+
+    // Header
+    let (title, style) = match complete {
+        None => (format!(" Pipeline: {} ", name), theme::title()),
+        Some(true) => (format!(" Pipeline Complete: {} ", name), theme::success()),
+        Some(false) => (format!(" Pipeline Failed: {} ", name), theme::error()),
+    };
+    let header = Paragraph::new(Line::from(Span::styled(title, style)))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    // Progress and output
+    let (steps_area, output_area) = progress_layout(chunks[1]);
+
+    let progress = ProgressSteps::new(steps, app.spinner_frame()).title(" Steps ");
+    frame.render_widget(progress, steps_area);
+
+    let mut log = LogView::new(output).title(" Output ");
+    if let Some(offset) = scroll_offset {
+        log = log.scroll_offset(offset);
+    }
+    frame.render_widget(log, output_area);
+
+    // Footer
+    let footer = if complete.is_some() {
+        let mut hints = vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("↑↓", theme::key_hint()),
+            Span::styled("] Scroll  [", theme::dim()),
+        ];
+        hints.extend(build_log_hint(app));
+        hints.extend([
+            Span::styled("Enter", theme::key_hint()),
+            Span::styled("] Done  [", theme::dim()),
+            Span::styled("q", theme::key_hint()),
+            Span::styled("] Quit", theme::dim()),
+        ]);
+        Paragraph::new(Line::from(hints))
+    } else {
+        Paragraph::new(Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("Ctrl+C", theme::key_hint()),
+            Span::styled("] Cancel", theme::dim()),
+        ]))
+    }
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[2]);
+}
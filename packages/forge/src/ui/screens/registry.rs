@@ -0,0 +1,187 @@
+//! Flake registry screen (registry.json pins)
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::app::state::RegistryForm;
+use crate::app::{App, RegistryField, RegistryState};
+use crate::system::registry::RegistryRow;
+use crate::ui::layout::centered_rect;
+use crate::ui::theme;
+use crate::ui::widgets::Spinner;
+
+pub fn draw(frame: &mut Frame, state: &RegistryState, app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(2),
+            Constraint::Length(2),
+        ])
+        .split(centered_rect(80, 85, area));
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(
+        " Flake Registry ",
+        theme::title(),
+    )))
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active()),
+    );
+    frame.render_widget(header, chunks[0]);
+
+    // Entries
+    let lines = match &state.rows {
+        None => {
+            let spinner_char = Spinner::new(app.spinner_frame()).char();
+            vec![Line::from(Span::styled(
+                format!("  {} Working...", spinner_char),
+                theme::dim(),
+            ))]
+        }
+        Some(Err(e)) => vec![Line::from(Span::styled(format!("  {}", e), theme::error()))],
+        Some(Ok(rows)) if rows.is_empty() => vec![
+            Line::from(Span::styled("  No registry entries yet", theme::text())),
+            Line::from(Span::styled(
+                "  Press a to add one - it is saved to registry.json in the config repo",
+                theme::dim(),
+            )),
+        ],
+        Some(Ok(rows)) => {
+            let width = rows.iter().map(|r| r.name.len()).max().unwrap_or(0);
+            rows.iter()
+                .enumerate()
+                .map(|(i, row)| row_line(row, width, i == state.selected))
+                .collect()
+        }
+    };
+    let list = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(" registry.json "),
+    );
+    frame.render_widget(list, chunks[1]);
+
+    // Last change
+    if let Some(status) = &state.status {
+        let line = match status {
+            Ok(message) => Line::from(Span::styled(format!("  ✓ {}", message), theme::success())),
+            Err(e) => Line::from(Span::styled(format!("  ✗ {}", e), theme::error())),
+        };
+        frame.render_widget(Paragraph::new(line), chunks[2]);
+    }
+
+    // Footer
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("a", theme::key_hint()),
+        Span::styled("] Add  [", theme::dim()),
+        Span::styled("p", theme::key_hint()),
+        Span::styled("] Pin  [", theme::dim()),
+        Span::styled("u", theme::key_hint()),
+        Span::styled("] Unpin  [", theme::dim()),
+        Span::styled("d", theme::key_hint()),
+        Span::styled("] Remove  [", theme::dim()),
+        Span::styled("r", theme::key_hint()),
+        Span::styled("] Refresh  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Back", theme::dim()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[3]);
+
+    if let Some(form) = &state.form {
+        draw_form(frame, form, area);
+    }
+}
+
+/// One entry: name, flake reference, pin and whether this host has it yet
+fn row_line(row: &RegistryRow, width: usize, selected: bool) -> Line<'static> {
+    let (prefix, name_style) = if selected {
+        ("> ", theme::selected())
+    } else {
+        ("  ", theme::text())
+    };
+    let pin = match &row.pinned_rev {
+        Some(rev) => format!("pinned {}", rev),
+        None => "follows".to_string(),
+    };
+    let (applied, applied_style) = if row.applied {
+        ("applied", theme::success())
+    } else {
+        ("pending rebuild", theme::warning())
+    };
+
+    Line::from(vec![
+        Span::styled(format!("{}{:<width$}  ", prefix, row.name, width = width), name_style),
+        Span::styled(format!("{:<16}", pin), theme::info()),
+        Span::styled(format!("{:<17}", applied), applied_style),
+        Span::styled(row.url.clone(), theme::dim()),
+    ])
+}
+
+/// Add-entry popup
+fn draw_form(frame: &mut Frame, form: &RegistryForm, area: Rect) {
+    let popup_area = centered_rect(60, 40, area);
+    frame.render_widget(Clear, popup_area);
+
+    let field_line = |label: &str, value: &str, field: RegistryField| {
+        let active = form.field == field;
+        let (display, style) = if active {
+            (format!("{}_", value), theme::selected())
+        } else {
+            (value.to_string(), theme::text())
+        };
+        Line::from(vec![
+            Span::styled(format!("  {:<7}", label), theme::dim()),
+            Span::styled(display, style),
+        ])
+    };
+
+    let mut lines = vec![
+        Line::from(""),
+        field_line("Name:", &form.name, RegistryField::Name),
+        Line::from(""),
+        field_line("Flake:", &form.url, RegistryField::Url),
+        Line::from(""),
+    ];
+    if let Some(err) = &form.error {
+        lines.push(Line::from(Span::styled(format!("  ⚠ {}", err), theme::error())));
+    } else {
+        lines.push(Line::from(Span::styled(
+            "  e.g. stable  github:NixOS/nixpkgs/nixos-24.11",
+            theme::dim(),
+        )));
+    }
+    lines.push(Line::from(""));
+    lines.push(
+        Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("Tab", theme::key_hint()),
+            Span::styled("] Switch field  [", theme::dim()),
+            Span::styled("Enter", theme::key_hint()),
+            Span::styled("] Add  [", theme::dim()),
+            Span::styled("Esc", theme::key_hint()),
+            Span::styled("] Cancel", theme::dim()),
+        ])
+        .alignment(Alignment::Center),
+    );
+
+    let popup = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active())
+            .title(Span::styled(" Add Registry Entry ", theme::title())),
+    );
+    frame.render_widget(popup, popup_area);
+}
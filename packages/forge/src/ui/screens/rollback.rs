@@ -0,0 +1,224 @@
+//! Rollback screens: generation picker, then the switch's progress
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::app::state::rollback_label;
+use crate::app::{App, RollbackState, StepStatus};
+use crate::system::generations::Generation;
+use crate::ui::layout::{centered_rect, progress_layout};
+use crate::ui::theme;
+use crate::ui::widgets::{LogView, MenuList, ProgressSteps, Spinner};
+
+pub fn draw(frame: &mut Frame, state: &RollbackState, app: &App) {
+    match state {
+        RollbackState::Select {
+            generations,
+            selected,
+            confirm,
+        } => draw_select(frame, generations.as_ref(), *selected, *confirm, app),
+        RollbackState::Running {
+            target,
+            steps,
+            output,
+        } => {
+            let output_vec: Vec<String> = output.iter().cloned().collect();
+            draw_running(frame, *target, steps, &output_vec, None, None, app);
+        }
+        RollbackState::Complete {
+            success,
+            target,
+            steps,
+            output,
+            scroll_offset,
+        } => {
+            let output_vec: Vec<String> = output.iter().cloned().collect();
+            draw_running(frame, *target, steps, &output_vec, Some(*success), *scroll_offset, app);
+        }
+    }
+}
+
+fn draw_select(
+    frame: &mut Frame,
+    generations: Option<&Result<Vec<Generation>, String>>,
+    selected: usize,
+    confirm: Option<Option<u32>>,
+    app: &App,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Min(10),
+            Constraint::Length(2),
+            Constraint::Length(3),
+        ])
+        .split(centered_rect(80, 80, area));
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(" Roll Back System ", theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    // Generation list
+    match generations {
+        None => {
+            let spinner_char = Spinner::new(app.spinner_frame()).char();
+            let loading = Paragraph::new(Line::from(Span::styled(
+                format!("  {} Reading system generations...", spinner_char),
+                theme::dim(),
+            )));
+            frame.render_widget(loading, chunks[1]);
+        }
+        Some(Err(e)) => {
+            let error = Paragraph::new(Line::from(Span::styled(format!("  ✗ {}", e), theme::error())));
+            frame.render_widget(error, chunks[1]);
+        }
+        Some(Ok(list)) => {
+            let items: Vec<String> = list.iter().map(generation_row).collect();
+            let menu = MenuList::new(items.iter().map(String::as_str).collect(), selected)
+                .title(" Generation  Created           NixOS                 Kernel ");
+            frame.render_widget(menu, chunks[1]);
+        }
+    }
+
+    // Confirmation prompt
+    if let Some(target) = confirm {
+        let prompt = Paragraph::new(Line::from(vec![
+            Span::styled(format!("  Switch to {}? ", rollback_label(target)), theme::warning()),
+            Span::styled("[", theme::dim()),
+            Span::styled("y", theme::key_hint()),
+            Span::styled("/", theme::dim()),
+            Span::styled("n", theme::key_hint()),
+            Span::styled("]", theme::dim()),
+        ]));
+        frame.render_widget(prompt, chunks[2]);
+    }
+
+    draw_select_footer(frame, chunks[3]);
+}
+
+/// One list row: number, date, NixOS version, kernel and markers
+fn generation_row(generation: &Generation) -> String {
+    let created = generation
+        .created
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "?".to_string());
+    let mut markers = Vec::new();
+    if generation.current {
+        markers.push("current");
+    }
+    if generation.booted {
+        markers.push("booted");
+    }
+    let markers = if markers.is_empty() {
+        String::new()
+    } else {
+        format!("  ({})", markers.join(", "))
+    };
+    format!(
+        "{:>10}  {:<16}  {:<20}  {}{}",
+        generation.number,
+        created,
+        generation.nixos_version.as_deref().unwrap_or("?"),
+        generation.kernel.as_deref().unwrap_or("?"),
+        markers
+    )
+}
+
+fn draw_select_footer(frame: &mut Frame, area: Rect) {
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓", theme::key_hint()),
+        Span::styled("] Navigate  [", theme::dim()),
+        Span::styled("Enter", theme::key_hint()),
+        Span::styled("] Switch to  [", theme::dim()),
+        Span::styled("p", theme::key_hint()),
+        Span::styled("] Previous  [", theme::dim()),
+        Span::styled("r", theme::key_hint()),
+        Span::styled("] Refresh  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Back", theme::dim()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, area);
+}
+
+fn draw_running(
+    frame: &mut Frame,
+    target: Option<u32>,
+    steps: &[StepStatus],
+    output: &[String],
+    complete: Option<bool>,
+    scroll_offset: Option<usize>,
+    app: &App,
+) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(2),
+        ])
+        .split(area);
+
+    // Header
+    let label = rollback_label(target);
+    let (title, style) = match complete {
+        None => (format!(" Switching to {} ", label), theme::title()),
+        Some(true) => (format!(" Switched to {} ", label), theme::success()),
+        Some(false) => (format!(" Switch to {} Failed ", label), theme::error()),
+    };
+    let header = Paragraph::new(Line::from(Span::styled(title, style)))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    // Progress and output
+    let (steps_area, output_area) = progress_layout(chunks[1]);
+
+    let progress = ProgressSteps::new(steps, app.spinner_frame()).title(" Steps ");
+    frame.render_widget(progress, steps_area);
+
+    let mut log = LogView::new(output).title(" Output ");
+    if let Some(offset) = scroll_offset {
+        log = log.scroll_offset(offset);
+    }
+    frame.render_widget(log, output_area);
+
+    // Footer
+    let footer = if complete.is_some() {
+        Paragraph::new(Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("↑↓", theme::key_hint()),
+            Span::styled("] Scroll  [", theme::dim()),
+            Span::styled("Enter", theme::key_hint()),
+            Span::styled("] Done  [", theme::dim()),
+            Span::styled("q", theme::key_hint()),
+            Span::styled("] Quit", theme::dim()),
+        ]))
+    } else {
+        Paragraph::new(Line::from(vec![
+            Span::styled("[", theme::dim()),
+            Span::styled("Ctrl+C", theme::key_hint()),
+            Span::styled("] Cancel", theme::dim()),
+        ]))
+    }
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[2]);
+}
@@ -0,0 +1,149 @@
+//! Background services screen: forge's timers on this host
+
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+    Frame,
+};
+
+use crate::app::{App, ServicesState};
+use crate::system::services::{ServiceRow, SERVICES_FILE};
+use crate::ui::layout::centered_rect;
+use crate::ui::theme;
+use crate::ui::widgets::Spinner;
+
+pub fn draw(frame: &mut Frame, state: &ServicesState, app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(6),
+            Constraint::Length(2),
+            Constraint::Length(2),
+        ])
+        .split(centered_rect(80, 70, area));
+
+    // Header
+    let header = Paragraph::new(Line::from(Span::styled(
+        " Background Services ",
+        theme::title(),
+    )))
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border_active()),
+    );
+    frame.render_widget(header, chunks[0]);
+
+    // Service table
+    let title = if state.host.is_empty() {
+        format!(" {} ", SERVICES_FILE)
+    } else {
+        format!(" {} - {} ", SERVICES_FILE, state.host)
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(theme::border())
+        .title(Span::styled(title, theme::title()));
+    match &state.rows {
+        None => {
+            let spinner_char = Spinner::new(app.spinner_frame()).char();
+            let loading = Paragraph::new(Line::from(Span::styled(
+                format!("  {} Working...", spinner_char),
+                theme::dim(),
+            )))
+            .block(block);
+            frame.render_widget(loading, chunks[1]);
+        }
+        Some(Err(e)) => {
+            let error = Paragraph::new(Line::from(Span::styled(format!("  ✗ {}", e), theme::error())))
+                .block(block);
+            frame.render_widget(error, chunks[1]);
+        }
+        Some(Ok(rows)) => {
+            let header = Row::new(vec!["", "Service", "Config", "Unit", "Enabled", "Active", ""])
+                .style(theme::title())
+                .bottom_margin(1);
+            let rows: Vec<Row> = rows
+                .iter()
+                .enumerate()
+                .map(|(i, row)| service_row(row, i == state.selected))
+                .collect();
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(2),
+                    Constraint::Length(13),
+                    Constraint::Length(7),
+                    Constraint::Length(25),
+                    Constraint::Length(10),
+                    Constraint::Length(10),
+                    Constraint::Min(16),
+                ],
+            )
+            .header(header)
+            .block(block);
+            let mut table_state = TableState::default().with_selected(Some(state.selected));
+            frame.render_stateful_widget(table, chunks[1], &mut table_state);
+        }
+    }
+
+    // Outcome of the last change, or what the selected service does
+    let line = match &state.status {
+        Some(Ok(message)) => {
+            Line::from(Span::styled(format!("  ✓ {}", message), theme::success()))
+        }
+        Some(Err(e)) => Line::from(Span::styled(format!("  ✗ {}", e), theme::error())),
+        None => match &state.rows {
+            Some(Ok(rows)) => rows
+                .get(state.selected)
+                .map(|row| {
+                    Line::from(Span::styled(
+                        format!("  {}", row.service.description()),
+                        theme::dim(),
+                    ))
+                })
+                .unwrap_or_default(),
+            _ => Line::default(),
+        },
+    };
+    frame.render_widget(Paragraph::new(line), chunks[2]);
+
+    // Footer
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓", theme::key_hint()),
+        Span::styled("] Navigate  [", theme::dim()),
+        Span::styled("Space", theme::key_hint()),
+        Span::styled("] Turn on/off  [", theme::dim()),
+        Span::styled("r", theme::key_hint()),
+        Span::styled("] Refresh  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Back", theme::dim()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[3]);
+}
+
+fn service_row<'a>(row: &ServiceRow, selected: bool) -> Row<'a> {
+    let style = if selected {
+        theme::selected()
+    } else if row.is_pending() {
+        theme::warning()
+    } else {
+        theme::text()
+    };
+    Row::new(vec![
+        if selected { ">" } else { " " }.to_string(),
+        row.service.name().to_string(),
+        if row.configured { "on" } else { "off" }.to_string(),
+        row.service.unit().to_string(),
+        row.enabled.clone(),
+        row.active.clone(),
+        if row.is_pending() { "rebuild to apply" } else { "" }.to_string(),
+    ])
+    .style(style)
+}
@@ -3,12 +3,16 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Gauge, Paragraph},
     Frame,
 };
 
-use crate::app::{App, StepStatus};
-use crate::ui::layout::progress_layout;
+use crate::app::{App, OverridePicker, StepState, StepStatus};
+use crate::commands::update::flake::InputOverride;
+use crate::commands::update::Activation;
+use crate::system::build_progress::BuildProgress;
+use crate::ui::layout::{centered_rect, progress_layout};
+use crate::ui::screens::{build_log_hint, changelog_hint, report_hint};
 use crate::ui::theme;
 use crate::ui::widgets::{LogView, ProgressSteps};
 
@@ -19,6 +23,7 @@ pub fn draw_running(
     output: &[String],
     complete: bool,
     scroll_offset: Option<usize>,
+    overrides: &[InputOverride],
     app: &App,
 ) {
     let area = frame.area();
@@ -37,7 +42,12 @@ pub fn draw_running(
     } else {
         " NixOS System Update "
     };
-    let header = Paragraph::new(Line::from(Span::styled(title, theme::title())))
+    let mut header_spans = vec![Span::styled(title, theme::title())];
+    // Badge so a build from a local checkout is never mistaken for a normal update
+    for o in overrides {
+        header_spans.push(Span::styled(format!(" {} overridden ", o.input), theme::warning()));
+    }
+    let header = Paragraph::new(Line::from(header_spans))
         .alignment(Alignment::Center)
         .block(
             Block::default()
@@ -49,9 +59,30 @@ pub fn draw_running(
     // Progress and output
     let (steps_area, output_area) = progress_layout(chunks[1]);
 
-    let progress = ProgressSteps::new(steps, app.spinner_state).title(" Progress ");
+    let progress = ProgressSteps::new(steps, app.spinner_frame()).title(" Progress ");
     frame.render_widget(progress, steps_area);
 
+    let output_area = match build_progress(steps, app).filter(|_| !complete) {
+        Some(build) => {
+            let areas = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(5)])
+                .split(output_area);
+            let gauge = Gauge::default()
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(theme::border()),
+                )
+                .gauge_style(theme::info())
+                .percent(build.percent())
+                .label(build.label());
+            frame.render_widget(gauge, areas[0]);
+            areas[1]
+        }
+        None => output_area,
+    };
+
     let mut log = LogView::new(output).title(" Output ");
     if let Some(offset) = scroll_offset {
         log = log.scroll_offset(offset);
@@ -60,15 +91,21 @@ pub fn draw_running(
 
     // Footer
     let footer = if complete {
-        Paragraph::new(Line::from(vec![
+        let mut hints = vec![
             Span::styled("[", theme::dim()),
             Span::styled("↑↓", theme::key_hint()),
             Span::styled("] Scroll  [", theme::dim()),
+        ];
+        hints.extend(changelog_hint(app));
+        hints.extend(report_hint(app));
+        hints.extend(build_log_hint(app));
+        hints.extend([
             Span::styled("Enter", theme::key_hint()),
             Span::styled("] Done  [", theme::dim()),
             Span::styled("q", theme::key_hint()),
             Span::styled("] Quit", theme::dim()),
-        ]))
+        ]);
+        Paragraph::new(Line::from(hints))
     } else {
         Paragraph::new(Line::from(vec![
             Span::styled("[", theme::dim()),
@@ -79,3 +116,187 @@ pub fn draw_running(
     .alignment(Alignment::Center);
     frame.render_widget(footer, chunks[2]);
 }
+
+/// Progress of the build behind the running preview or rebuild step, once
+/// nix has said how much there is to do
+fn build_progress(steps: &[StepStatus], app: &App) -> Option<BuildProgress> {
+    let building = steps.iter().any(|s| {
+        s.status == StepState::Running
+            && matches!(s.name.as_str(), "Previewing new system" | "Rebuilding system")
+    });
+    app.build_progress.filter(|p| building && p.is_known())
+}
+
+/// Draw the input and checkout picker for `--override-input`
+pub fn draw_override_picker(frame: &mut Frame, picker: &OverridePicker) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(2),
+            Constraint::Length(2),
+        ])
+        .split(centered_rect(70, 80, frame.area()));
+
+    let title = match &picker.input {
+        None => " Override Flake Input ".to_string(),
+        Some(input) => format!(" Local checkout for {} ", input),
+    };
+    let header = Paragraph::new(Line::from(Span::styled(title, theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    let row = |selected: bool, text: String, badge: Option<&'static str>| {
+        let (prefix, style) = if selected {
+            ("> ", theme::selected())
+        } else {
+            ("  ", theme::text())
+        };
+        let mut spans = vec![Span::styled(format!("{}{}", prefix, text), style)];
+        if let Some(badge) = badge {
+            spans.push(Span::styled(badge, theme::info()));
+        }
+        Line::from(spans)
+    };
+
+    let (lines, list_title, hint, keys): (Vec<Line>, String, &str, &[&str]) = match &picker.input {
+        None => {
+            let lines = match &picker.inputs {
+                Ok(inputs) => inputs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, input)| row(i == picker.selected, input.clone(), None))
+                    .collect(),
+                Err(e) => vec![Line::from(Span::styled(format!("  {}", e), theme::error()))],
+            };
+            (
+                lines,
+                " Inputs (flake.lock) ".to_string(),
+                "The rebuild uses the checkout; flake.lock is left as it was",
+                &["↑↓ Navigate", "Enter Choose", "Esc Back"],
+            )
+        }
+        Some(_) => {
+            let lines = if picker.entries.is_empty() {
+                vec![Line::from(Span::styled("  (no subdirectories)", theme::dim()))]
+            } else {
+                picker
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| {
+                        let badge = entry.is_flake.then_some("  flake");
+                        row(i == picker.dir_selected, format!("{}/", entry.name), badge)
+                    })
+                    .collect()
+            };
+            (
+                lines,
+                format!(" {} ", picker.dir.display()),
+                "Space uses the highlighted flake checkout",
+                &["↑↓ Navigate", "Enter Open", "Backspace Up", "Space Use", "Esc Inputs"],
+            )
+        }
+    };
+
+    // Keep the selection visible in long directories
+    let selected = if picker.input.is_some() {
+        picker.dir_selected
+    } else {
+        picker.selected
+    };
+    let visible = chunks[1].height.saturating_sub(2) as usize;
+    let scroll = selected.saturating_sub(visible.saturating_sub(1));
+    let list = Paragraph::new(lines).scroll((scroll as u16, 0)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(list_title),
+    );
+    frame.render_widget(list, chunks[1]);
+
+    let status = match &picker.error {
+        Some(err) => Line::from(Span::styled(format!("⚠ {}", err), theme::error())),
+        None => Line::from(Span::styled(hint, theme::dim())),
+    };
+    frame.render_widget(Paragraph::new(status).alignment(Alignment::Center), chunks[2]);
+
+    let mut spans = Vec::new();
+    for (i, key) in keys.iter().enumerate() {
+        let (key, action) = key.split_once(' ').unwrap_or((key, ""));
+        let open = if i == 0 { "[" } else { "  [" };
+        spans.push(Span::styled(open, theme::dim()));
+        spans.push(Span::styled(key, theme::key_hint()));
+        spans.push(Span::styled(format!("] {}", action), theme::dim()));
+    }
+    frame.render_widget(
+        Paragraph::new(Line::from(spans)).alignment(Alignment::Center),
+        chunks[3],
+    );
+}
+
+/// Draw the choice between nixos-rebuild switch, boot and test
+pub fn draw_activation_picker(frame: &mut Frame, selected: usize, last_scheduled: Option<&str>) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(2),
+        ])
+        .split(centered_rect(60, 50, frame.area()));
+
+    let header = Paragraph::new(Line::from(Span::styled(" Update System ", theme::title())))
+        .alignment(Alignment::Center)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::border_active()),
+        );
+    frame.render_widget(header, chunks[0]);
+
+    let mut lines = vec![Line::from("")];
+    lines.extend(Activation::ALL.iter().enumerate().map(|(i, activation)| {
+        let (prefix, style) = if i == selected {
+            ("> ", theme::selected())
+        } else {
+            ("  ", theme::text())
+        };
+        Line::from(vec![
+            Span::styled(format!("{}{:<8}", prefix, activation.verb()), style),
+            Span::styled(activation.description(), theme::dim()),
+        ])
+    }));
+    if let Some(last) = last_scheduled {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!("  Last scheduled update: {}", last),
+            theme::dim(),
+        )));
+    }
+    let list = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border())
+            .title(" Activate the rebuilt system with "),
+    );
+    frame.render_widget(list, chunks[1]);
+
+    let footer = Paragraph::new(Line::from(vec![
+        Span::styled("[", theme::dim()),
+        Span::styled("↑↓", theme::key_hint()),
+        Span::styled("] Navigate  [", theme::dim()),
+        Span::styled("Enter", theme::key_hint()),
+        Span::styled("] Update  [", theme::dim()),
+        Span::styled("Esc", theme::key_hint()),
+        Span::styled("] Back", theme::dim()),
+    ]))
+    .alignment(Alignment::Center);
+    frame.render_widget(footer, chunks[2]);
+}
@@ -0,0 +1,56 @@
+//! Bordered panel with a spinner, shown while a list is still loading
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use super::Spinner;
+use crate::ui::theme;
+
+pub struct LoadingPanel<'a> {
+    message: &'a str,
+    spinner_state: usize,
+    title: Option<&'a str>,
+}
+
+impl<'a> LoadingPanel<'a> {
+    pub fn new(message: &'a str, spinner_state: usize) -> Self {
+        Self {
+            message,
+            spinner_state,
+            title: None,
+        }
+    }
+
+    pub fn title(mut self, title: &'a str) -> Self {
+        self.title = Some(title);
+        self
+    }
+}
+
+impl Widget for LoadingPanel<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme::border());
+
+        if let Some(title) = self.title {
+            block = block.title(Span::styled(title, theme::title()));
+        }
+
+        Paragraph::new(vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::styled(Spinner::new(self.spinner_state).char().to_string(), theme::info()),
+                Span::styled(format!(" {}", self.message), theme::text()),
+            ]),
+            Line::from(""),
+        ])
+        .alignment(Alignment::Center)
+        .block(block)
+        .render(area, buf);
+    }
+}
@@ -1,11 +1,17 @@
 //! Reusable UI widgets
 
+mod loading;
 mod log_view;
 mod menu_list;
+mod passphrase_input;
 mod progress;
 mod spinner;
+mod strength_meter;
 
+pub use loading::LoadingPanel;
 pub use log_view::LogView;
 pub use menu_list::MenuList;
+pub use passphrase_input::{PassphraseField, PassphraseInput};
 pub use progress::ProgressSteps;
 pub use spinner::Spinner;
+pub use strength_meter::StrengthMeter;
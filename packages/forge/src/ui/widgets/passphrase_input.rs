@@ -0,0 +1,102 @@
+//! Masked passphrase input, with a confirmation field and strength meter
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    text::{Line, Span},
+    widgets::{Paragraph, Widget},
+};
+
+use crate::ui::theme;
+use crate::ui::widgets::StrengthMeter;
+
+/// Width of the label column, matching the install credentials form
+const LABEL_WIDTH: usize = 20;
+
+/// Field of a passphrase input that has the cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassphraseField {
+    Passphrase,
+    Confirm,
+}
+
+/// Passphrase line, then (for a new passphrase) strength and confirmation
+pub struct PassphraseInput<'a> {
+    passphrase: &'a str,
+    confirm: Option<&'a str>,
+    focus: Option<PassphraseField>,
+    labels: (&'a str, &'a str),
+}
+
+impl<'a> PassphraseInput<'a> {
+    pub fn new(passphrase: &'a str) -> Self {
+        Self {
+            passphrase,
+            confirm: None,
+            focus: None,
+            labels: ("Passphrase:", "Confirm Phrase:"),
+        }
+    }
+
+    /// Ask for the passphrase twice and rate it (choosing a new one)
+    pub fn confirm(mut self, confirm: &'a str) -> Self {
+        self.confirm = Some(confirm);
+        self
+    }
+
+    pub fn focus(mut self, focus: Option<PassphraseField>) -> Self {
+        self.focus = focus;
+        self
+    }
+
+    pub fn labels(mut self, passphrase: &'a str, confirm: &'a str) -> Self {
+        self.labels = (passphrase, confirm);
+        self
+    }
+
+    /// Masked, with a cursor on the focused field
+    fn field(&self, value: &str, label: &str, field: PassphraseField) -> Line<'static> {
+        let focused = self.focus == Some(field);
+        let mut masked = "*".repeat(value.chars().count());
+        if focused {
+            masked.push('_');
+        }
+        let style = if focused { theme::selected() } else { theme::text() };
+        Line::from(vec![
+            Span::styled(format!("  {:<width$}", label, width = LABEL_WIDTH - 2), theme::dim()),
+            Span::styled(masked, style),
+        ])
+    }
+
+    pub fn lines(&self) -> Vec<Line<'static>> {
+        let mut lines = vec![self.field(self.passphrase, self.labels.0, PassphraseField::Passphrase)];
+        if let Some(confirm) = self.confirm {
+            let mut meter = vec![Span::styled(
+                format!("  {:<width$}", "Strength:", width = LABEL_WIDTH - 2),
+                theme::dim(),
+            )];
+            meter.extend(StrengthMeter::new(self.passphrase).line().spans);
+            lines.extend([
+                Line::from(meter),
+                Line::from(""),
+                self.field(confirm, self.labels.1, PassphraseField::Confirm),
+            ]);
+        }
+        lines
+    }
+
+    /// Rows the lines take
+    pub fn height(&self) -> u16 {
+        if self.confirm.is_some() {
+            4
+        } else {
+            1
+        }
+    }
+}
+
+impl Widget for PassphraseInput<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Paragraph::new(self.lines()).render(area, buf);
+    }
+}
@@ -37,18 +37,22 @@ impl Widget for ProgressSteps<'_> {
         let lines: Vec<Line> = self
             .steps
             .iter()
-            .enumerate()
-            .map(|(_i, step)| {
+            .map(|step| {
                 let (icon, style) = match step.status {
                     StepState::Pending => ("[ ]", theme::dim()),
                     StepState::Running => {
                         let spinner = Spinner::new(self.spinner_state);
                         // We'll handle this specially
-                        return Line::from(vec![
+                        let mut spans = vec![
                             Span::styled(format!(" [{}] ", spinner.char()), theme::info()),
                             Span::styled(&step.name, theme::text()),
                             Span::styled("...", theme::dim()),
-                        ]);
+                        ];
+                        if let Some(progress) = &step.progress {
+                            let label = format!(" {}", progress.label());
+                            spans.push(Span::styled(label, theme::info()));
+                        }
+                        return Line::from(spans);
                     }
                     StepState::Complete => ("[✓]", theme::success()),
                     StepState::Failed => ("[✗]", theme::error()),
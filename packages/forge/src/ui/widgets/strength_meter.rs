@@ -0,0 +1,64 @@
+//! Passphrase strength meter widget
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use crate::system::passphrase::{estimate_bits, PassphraseStrength};
+use crate::ui::theme;
+
+/// Cells per meter segment
+const SEGMENT_WIDTH: usize = 3;
+
+/// One line: a four-segment bar, the strength and the estimated bits
+pub struct StrengthMeter {
+    strength: PassphraseStrength,
+    bits: f64,
+    empty: bool,
+}
+
+impl StrengthMeter {
+    pub fn new(passphrase: &str) -> Self {
+        let bits = estimate_bits(passphrase);
+        Self {
+            strength: PassphraseStrength::from_bits(bits),
+            bits,
+            empty: passphrase.is_empty(),
+        }
+    }
+
+    fn style(&self) -> Style {
+        match self.strength {
+            PassphraseStrength::Weak => theme::error(),
+            PassphraseStrength::Fair => theme::warning(),
+            PassphraseStrength::Good => theme::info(),
+            PassphraseStrength::Strong => theme::success(),
+        }
+    }
+
+    pub fn line(&self) -> Line<'static> {
+        if self.empty {
+            return Line::from(Span::styled("░".repeat(4 * SEGMENT_WIDTH), theme::dim()));
+        }
+        let filled = self.strength.level() * SEGMENT_WIDTH;
+        Line::from(vec![
+            Span::styled("█".repeat(filled), self.style()),
+            Span::styled("░".repeat(4 * SEGMENT_WIDTH - filled), theme::dim()),
+            Span::styled(format!(" {}", self.strength), self.style()),
+            Span::styled(format!(" (~{:.0} bits)", self.bits), theme::dim()),
+        ])
+    }
+}
+
+impl Widget for StrengthMeter {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width < 1 || area.height < 1 {
+            return;
+        }
+        buf.set_line(area.x, area.y, &self.line(), area.width);
+    }
+}